@@ -16,3 +16,5 @@
 
 mod stats;
 pub(crate) use stats::{HttpHistogram, HttpHistogramRecorder, HttpRuntimeStats};
+#[cfg(feature = "quic")]
+pub(crate) use stats::{QuicHandshakeError, QuicHandshakeStats};
@@ -19,6 +19,7 @@ use std::time::Duration;
 use g3_histogram::{HistogramRecorder, KeepingHistogram};
 use g3_statsd_client::StatsdClient;
 use g3_types::ext::DurationExt;
+use serde_json::{json, Value};
 
 use crate::target::BenchHistogram;
 
@@ -75,6 +76,15 @@ impl BenchHistogram for HttpHistogram {
         Self::summary_newline();
         Self::summary_total_percentage(self.total_time.inner());
     }
+
+    fn json(&self) -> Option<Value> {
+        Some(json!({
+            "conn_reuse_count": Self::json_histogram(self.conn_reuse_count.inner()),
+            "send_hdr_time_ns": Self::json_histogram(self.send_hdr_time.inner()),
+            "recv_hdr_time_ns": Self::json_histogram(self.recv_hdr_time.inner()),
+            "total_time_ns": Self::json_histogram(self.total_time.inner()),
+        }))
+    }
 }
 
 #[derive(Clone)]
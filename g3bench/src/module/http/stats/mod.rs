@@ -17,5 +17,11 @@
 mod histogram;
 mod runtime;
 
+#[cfg(feature = "quic")]
+mod quic;
+
 pub(crate) use histogram::{HttpHistogram, HttpHistogramRecorder};
 pub(crate) use runtime::HttpRuntimeStats;
+
+#[cfg(feature = "quic")]
+pub(crate) use quic::{H3GoawayStats, QuicCloseStats, QuicHandshakeError, QuicHandshakeStats};
@@ -0,0 +1,165 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use ahash::AHashMap;
+
+use serde_json::{json, Value};
+
+#[derive(Clone, Copy)]
+pub(crate) enum QuicHandshakeError {
+    CertVerify,
+    AlpnMismatch,
+    VersionMismatch,
+    Timeout,
+    Other,
+}
+
+#[derive(Default)]
+pub(crate) struct QuicCloseStats {
+    reasons: Mutex<AHashMap<String, u64>>,
+}
+
+impl QuicCloseStats {
+    pub(crate) fn add_reason(&self, reason: String) {
+        let mut reasons = self.reasons.lock().unwrap();
+        *reasons.entry(reason).or_default() += 1;
+    }
+
+    fn sorted_reasons(&self) -> Vec<(String, u64)> {
+        let reasons = self.reasons.lock().unwrap();
+        let mut reasons: Vec<(String, u64)> =
+            reasons.iter().map(|(k, v)| (k.clone(), *v)).collect();
+        reasons.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        reasons
+    }
+
+    pub(crate) fn summary(&self) {
+        let reasons = self.sorted_reasons();
+        if reasons.is_empty() {
+            return;
+        }
+
+        println!("# QUIC Connection Close");
+        for (reason, count) in reasons {
+            println!("{count:>8} {reason}");
+        }
+    }
+
+    pub(crate) fn json(&self) -> Value {
+        let map = self
+            .sorted_reasons()
+            .into_iter()
+            .map(|(reason, count)| (reason, Value::from(count)))
+            .collect::<serde_json::Map<String, Value>>();
+        Value::Object(map)
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct H3GoawayStats {
+    refused_in_flight: AtomicU64,
+    refused_not_started: AtomicU64,
+    reconnect: AtomicU64,
+}
+
+impl H3GoawayStats {
+    pub(crate) fn add_refused_in_flight(&self) {
+        self.refused_in_flight.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_refused_not_started(&self) {
+        self.refused_not_started.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_reconnect(&self) {
+        self.reconnect.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn summary(&self) {
+        let refused_in_flight = self.refused_in_flight.load(Ordering::Relaxed);
+        let refused_not_started = self.refused_not_started.load(Ordering::Relaxed);
+        let reconnect = self.reconnect.load(Ordering::Relaxed);
+        if refused_in_flight + refused_not_started + reconnect == 0 {
+            return;
+        }
+
+        println!("# H3 GOAWAY");
+        println!("Refused in-flight: {refused_in_flight}");
+        println!("Refused new:       {refused_not_started}");
+        println!("Reconnects:        {reconnect}");
+    }
+
+    pub(crate) fn json(&self) -> Value {
+        json!({
+            "refused_in_flight": self.refused_in_flight.load(Ordering::Relaxed),
+            "refused_not_started": self.refused_not_started.load(Ordering::Relaxed),
+            "goaway_reconnects": self.reconnect.load(Ordering::Relaxed),
+        })
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct QuicHandshakeStats {
+    cert_verify: AtomicU64,
+    alpn_mismatch: AtomicU64,
+    version_mismatch: AtomicU64,
+    timeout: AtomicU64,
+    other: AtomicU64,
+}
+
+impl QuicHandshakeStats {
+    pub(crate) fn add_error(&self, e: QuicHandshakeError) {
+        let counter = match e {
+            QuicHandshakeError::CertVerify => &self.cert_verify,
+            QuicHandshakeError::AlpnMismatch => &self.alpn_mismatch,
+            QuicHandshakeError::VersionMismatch => &self.version_mismatch,
+            QuicHandshakeError::Timeout => &self.timeout,
+            QuicHandshakeError::Other => &self.other,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn summary(&self) {
+        let cert_verify = self.cert_verify.load(Ordering::Relaxed);
+        let alpn_mismatch = self.alpn_mismatch.load(Ordering::Relaxed);
+        let version_mismatch = self.version_mismatch.load(Ordering::Relaxed);
+        let timeout = self.timeout.load(Ordering::Relaxed);
+        let other = self.other.load(Ordering::Relaxed);
+        if cert_verify + alpn_mismatch + version_mismatch + timeout + other == 0 {
+            return;
+        }
+
+        println!("# QUIC Handshake Errors");
+        println!("Cert verify:   {cert_verify}");
+        println!("ALPN mismatch: {alpn_mismatch}");
+        println!("Version:       {version_mismatch}");
+        println!("Timeout:       {timeout}");
+        println!("Other:         {other}");
+    }
+
+    pub(crate) fn json(&self) -> Value {
+        json!({
+            "cert_verify": self.cert_verify.load(Ordering::Relaxed),
+            "alpn_mismatch": self.alpn_mismatch.load(Ordering::Relaxed),
+            "version_mismatch": self.version_mismatch.load(Ordering::Relaxed),
+            "timeout": self.timeout.load(Ordering::Relaxed),
+            "other": self.other.load(Ordering::Relaxed),
+        })
+    }
+}
@@ -19,7 +19,10 @@ use std::time::Duration;
 
 use g3_io_ext::{LimitedReaderStats, LimitedRecvStats, LimitedSendStats, LimitedWriterStats};
 use g3_statsd_client::StatsdClient;
+use serde_json::{json, Value};
 
+#[cfg(feature = "quic")]
+use super::{H3GoawayStats, QuicCloseStats, QuicHandshakeStats};
 use crate::module::ssl::SslSessionStats;
 use crate::target::BenchRuntimeStats;
 
@@ -49,6 +52,68 @@ enum HttpIoStats {
     Udp(HttpUdpIoStats),
 }
 
+struct HttpConnRequestStats {
+    conn_count: AtomicU64,
+    req_total: AtomicU64,
+    req_min: AtomicU64,
+    req_max: AtomicU64,
+}
+
+impl Default for HttpConnRequestStats {
+    fn default() -> Self {
+        HttpConnRequestStats {
+            conn_count: AtomicU64::new(0),
+            req_total: AtomicU64::new(0),
+            req_min: AtomicU64::new(u64::MAX),
+            req_max: AtomicU64::new(0),
+        }
+    }
+}
+
+impl HttpConnRequestStats {
+    #[cfg(feature = "quic")]
+    fn add(&self, count: u64) {
+        self.conn_count.fetch_add(1, Ordering::Relaxed);
+        self.req_total.fetch_add(count, Ordering::Relaxed);
+        self.req_min.fetch_min(count, Ordering::Relaxed);
+        self.req_max.fetch_max(count, Ordering::Relaxed);
+    }
+
+    fn summary(&self) {
+        let conn_count = self.conn_count.load(Ordering::Relaxed);
+        if conn_count == 0 {
+            return;
+        }
+        let req_total = self.req_total.load(Ordering::Relaxed);
+        println!(
+            "Req/Conn:      min {} mean {:.2} max {}",
+            self.req_min.load(Ordering::Relaxed),
+            req_total as f64 / conn_count as f64,
+            self.req_max.load(Ordering::Relaxed)
+        );
+        println!(
+            "Reuse ratio:   {:.2}%",
+            (req_total.saturating_sub(conn_count) as f64 / req_total as f64) * 100.0
+        );
+    }
+
+    fn json(&self) -> Value {
+        let conn_count = self.conn_count.load(Ordering::Relaxed);
+        let req_total = self.req_total.load(Ordering::Relaxed);
+        if conn_count == 0 {
+            return Value::Null;
+        }
+        json!({
+            "connections": conn_count,
+            "requests": req_total,
+            "min": self.req_min.load(Ordering::Relaxed),
+            "max": self.req_max.load(Ordering::Relaxed),
+            "mean": req_total as f64 / conn_count as f64,
+            "reuse_ratio": req_total.saturating_sub(conn_count) as f64 / req_total as f64,
+        })
+    }
+}
+
 pub(crate) struct HttpRuntimeStats {
     target: &'static str,
     task_total: AtomicU64,
@@ -62,10 +127,21 @@ pub(crate) struct HttpRuntimeStats {
 
     pub(crate) target_ssl_session: SslSessionStats,
     pub(crate) proxy_ssl_session: SslSessionStats,
+    #[cfg(feature = "quic")]
+    pub(crate) quic_handshake: QuicHandshakeStats,
+    #[cfg(feature = "quic")]
+    pub(crate) h3_goaway: H3GoawayStats,
+    #[cfg(feature = "quic")]
+    pub(crate) quic_close: QuicCloseStats,
 
     conn_close_error: AtomicU64,
     conn_close_timeout: AtomicU64,
 
+    body_mismatch: AtomicU64,
+    redirect: AtomicU64,
+
+    conn_requests: HttpConnRequestStats,
+
     io: HttpIoStats,
 }
 
@@ -92,8 +168,17 @@ impl HttpRuntimeStats {
             conn_success_total: AtomicU64::new(0),
             target_ssl_session: Default::default(),
             proxy_ssl_session: Default::default(),
+            #[cfg(feature = "quic")]
+            quic_handshake: Default::default(),
+            #[cfg(feature = "quic")]
+            h3_goaway: Default::default(),
+            #[cfg(feature = "quic")]
+            quic_close: Default::default(),
             conn_close_error: AtomicU64::new(0),
             conn_close_timeout: AtomicU64::new(0),
+            body_mismatch: AtomicU64::new(0),
+            redirect: AtomicU64::new(0),
+            conn_requests: HttpConnRequestStats::default(),
             io,
         }
     }
@@ -133,6 +218,22 @@ impl HttpRuntimeStats {
     pub(crate) fn add_conn_close_timeout(&self) {
         self.conn_close_timeout.fetch_add(1, Ordering::Relaxed);
     }
+
+    /// Record the number of requests that have been sent on a closed connection
+    #[cfg(feature = "quic")]
+    pub(crate) fn add_conn_requests(&self, count: u64) {
+        self.conn_requests.add(count);
+    }
+
+    #[cfg(feature = "quic")]
+    pub(crate) fn add_redirect(&self) {
+        self.redirect.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "quic")]
+    pub(crate) fn add_body_mismatch(&self) {
+        self.body_mismatch.fetch_add(1, Ordering::Relaxed);
+    }
 }
 
 impl LimitedReaderStats for HttpRuntimeStats {
@@ -266,9 +367,27 @@ impl BenchRuntimeStats for HttpRuntimeStats {
         if close_timeout > 0 {
             println!("Close timeout: {close_timeout}");
         }
+        self.conn_requests.summary();
+
+        let body_mismatch = self.body_mismatch.load(Ordering::Relaxed);
+        if body_mismatch > 0 {
+            println!("# Validation");
+            println!("Body mismatch: {body_mismatch}");
+        }
+        let redirect = self.redirect.load(Ordering::Relaxed);
+        if redirect > 0 {
+            println!("# Redirects");
+            println!("Redirect hops: {redirect}");
+        }
 
         self.proxy_ssl_session.summary("PROXY TLS");
         self.target_ssl_session.summary("TARGET TLS");
+        #[cfg(feature = "quic")]
+        self.quic_handshake.summary();
+        #[cfg(feature = "quic")]
+        self.h3_goaway.summary();
+        #[cfg(feature = "quic")]
+        self.quic_close.summary();
 
         println!("# Traffic");
         match &self.io {
@@ -300,4 +419,71 @@ impl BenchRuntimeStats for HttpRuntimeStats {
             }
         }
     }
+
+    fn json(&self, total_time: Duration) -> Option<Value> {
+        let total_secs = total_time.as_secs_f64();
+
+        let conn_attempt = self.conn_attempt_total.load(Ordering::Relaxed)
+            + self.conn_attempt.load(Ordering::Relaxed);
+        let conn_success = self.conn_success_total.load(Ordering::Relaxed)
+            + self.conn_success.load(Ordering::Relaxed);
+        let traffic = match &self.io {
+            HttpIoStats::Tcp(tcp) => {
+                let send_bytes =
+                    tcp.write_total.load(Ordering::Relaxed) + tcp.write.load(Ordering::Relaxed);
+                let recv_bytes =
+                    tcp.read_total.load(Ordering::Relaxed) + tcp.read.load(Ordering::Relaxed);
+                json!({
+                    "send_bytes": send_bytes,
+                    "send_rate": send_bytes as f64 / total_secs,
+                    "recv_bytes": recv_bytes,
+                    "recv_rate": recv_bytes as f64 / total_secs,
+                })
+            }
+            HttpIoStats::Udp(udp) => {
+                let send_bytes = udp.send_bytes_total.load(Ordering::Relaxed)
+                    + udp.send_bytes.load(Ordering::Relaxed);
+                let send_packets = udp.send_packets_total.load(Ordering::Relaxed)
+                    + udp.send_packets.load(Ordering::Relaxed);
+                let recv_bytes = udp.recv_bytes_total.load(Ordering::Relaxed)
+                    + udp.recv_bytes.load(Ordering::Relaxed);
+                let recv_packets = udp.recv_packets_total.load(Ordering::Relaxed)
+                    + udp.recv_packets.load(Ordering::Relaxed);
+                json!({
+                    "send_bytes": send_bytes,
+                    "send_packets": send_packets,
+                    "send_rate": send_bytes as f64 / total_secs,
+                    "recv_bytes": recv_bytes,
+                    "recv_packets": recv_packets,
+                    "recv_rate": recv_bytes as f64 / total_secs,
+                })
+            }
+        };
+
+        #[allow(unused_mut)]
+        let mut v = json!({
+            "connection": {
+                "attempt": conn_attempt,
+                "success": conn_success,
+                "close_error": self.conn_close_error.load(Ordering::Relaxed),
+                "close_timeout": self.conn_close_timeout.load(Ordering::Relaxed),
+            },
+            "connection_requests": self.conn_requests.json(),
+            "body_mismatch": self.body_mismatch.load(Ordering::Relaxed),
+            "redirect": self.redirect.load(Ordering::Relaxed),
+            "proxy_tls_session": self.proxy_ssl_session.json(),
+            "target_tls_session": self.target_ssl_session.json(),
+            "traffic": traffic,
+        });
+        #[cfg(feature = "quic")]
+        if let Value::Object(map) = &mut v {
+            map.insert(
+                "quic_handshake_error".to_string(),
+                self.quic_handshake.json(),
+            );
+            map.insert("h3_goaway".to_string(), self.h3_goaway.json());
+            map.insert("quic_close".to_string(), self.quic_close.json());
+        }
+        Some(v)
+    }
 }
@@ -14,6 +14,8 @@
  * limitations under the License.
  */
 
+#[cfg(feature = "quic")]
+use std::io;
 #[cfg(feature = "quic")]
 use std::net::UdpSocket;
 use std::net::{IpAddr, SocketAddr};
@@ -61,6 +63,25 @@ impl SocketArgs {
             .map_err(|e| anyhow!("failed to setup local udp socket: {e}"))
     }
 
+    #[cfg(feature = "quic")]
+    pub(crate) fn udp_std_socket_with_port_to(
+        &self,
+        peer: SocketAddr,
+        port: u16,
+    ) -> anyhow::Result<UdpSocket> {
+        g3_socket::udp::new_std_socket_with_port_to(
+            peer,
+            &self.bind,
+            port,
+            Default::default(),
+            Default::default(),
+        )
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::AddrInUse => anyhow!("local udp port {port} is already in use"),
+            _ => anyhow!("failed to setup local udp socket with port {port}: {e}"),
+        })
+    }
+
     pub(crate) fn hickory_udp_connect_info(&self, server: SocketAddr) -> UdpConnectInfo {
         UdpConnectInfo {
             server,
@@ -19,6 +19,7 @@ use std::time::Duration;
 use g3_histogram::{HistogramRecorder, KeepingHistogram};
 use g3_statsd_client::StatsdClient;
 use g3_types::ext::DurationExt;
+use serde_json::{json, Value};
 
 use crate::target::BenchHistogram;
 
@@ -52,6 +53,12 @@ impl BenchHistogram for SslHistogram {
         Self::summary_newline();
         Self::summary_total_percentage(total_time);
     }
+
+    fn json(&self) -> Option<Value> {
+        Some(json!({
+            "total_time_ns": Self::json_histogram(self.total_time.inner()),
+        }))
+    }
 }
 
 #[derive(Clone)]
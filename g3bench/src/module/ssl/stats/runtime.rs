@@ -19,6 +19,7 @@ use std::time::Duration;
 
 use g3_io_ext::{LimitedReaderStats, LimitedWriterStats};
 use g3_statsd_client::StatsdClient;
+use serde_json::{json, Value};
 
 use super::SslSessionStats;
 use crate::target::BenchRuntimeStats;
@@ -159,4 +160,32 @@ impl BenchRuntimeStats for SslRuntimeStats {
         println!("Recv bytes:    {total_recv}");
         println!("Recv rate:     {:.3}B/s", total_recv as f64 / total_secs);
     }
+
+    fn json(&self, total_time: Duration) -> Option<Value> {
+        let total_secs = total_time.as_secs_f64();
+
+        let conn_attempt = self.conn_attempt_total.load(Ordering::Relaxed)
+            + self.conn_attempt.load(Ordering::Relaxed);
+        let conn_success = self.conn_success_total.load(Ordering::Relaxed)
+            + self.conn_success.load(Ordering::Relaxed);
+        let send_bytes =
+            self.tcp_write_total.load(Ordering::Relaxed) + self.tcp_write.load(Ordering::Relaxed);
+        let recv_bytes =
+            self.tcp_read_total.load(Ordering::Relaxed) + self.tcp_read.load(Ordering::Relaxed);
+        Some(json!({
+            "connection": {
+                "attempt": conn_attempt,
+                "success": conn_success,
+                "close_error": self.conn_close_error.load(Ordering::Relaxed),
+                "close_timeout": self.conn_close_timeout.load(Ordering::Relaxed),
+            },
+            "tls_session": self.session.json(),
+            "traffic": {
+                "send_bytes": send_bytes,
+                "send_rate": send_bytes as f64 / total_secs,
+                "recv_bytes": recv_bytes,
+                "recv_rate": recv_bytes as f64 / total_secs,
+            },
+        }))
+    }
 }
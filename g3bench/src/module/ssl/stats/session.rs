@@ -16,6 +16,8 @@
 
 use std::sync::atomic::{AtomicU64, Ordering};
 
+use serde_json::{json, Value};
+
 #[derive(Default)]
 pub(crate) struct SslSessionStats {
     total: AtomicU64,
@@ -47,4 +49,11 @@ impl SslSessionStats {
             (session_reused as f64 / total as f64) * 100.0
         );
     }
+
+    pub(crate) fn json(&self) -> Value {
+        json!({
+            "total": self.total.load(Ordering::Relaxed),
+            "reused": self.reused.load(Ordering::Relaxed),
+        })
+    }
 }
@@ -25,6 +25,7 @@ use std::time::Duration;
 use ahash::AHashMap;
 use anyhow::{anyhow, Context};
 use clap::{value_parser, Arg, ArgAction, ArgMatches, Command, ValueHint};
+use serde_json::{json, Value};
 
 use g3_runtime::blended::BlendedRuntimeConfig;
 use g3_runtime::unaided::UnaidedRuntimeConfig;
@@ -54,6 +55,7 @@ const GLOBAL_ARG_EMIT_METRICS: &str = "emit-metrics";
 const GLOBAL_ARG_STATSD_TARGET_UDP: &str = "statsd-target-udp";
 const GLOBAL_ARG_STATSD_TARGET_UNIX: &str = "statsd-target-unix";
 const GLOBAL_ARG_NO_PROGRESS_BAR: &str = "no-progress-bar";
+const GLOBAL_ARG_OUTPUT_JSON: &str = "output-json";
 
 const GLOBAL_ARG_PEER_PICK_POLICY: &str = "peer-pick-policy";
 const GLOBAL_ARG_TCP_LIMIT_SHIFT: &str = "tcp-limit-shift";
@@ -78,6 +80,8 @@ pub struct ProcArgs {
 
     statsd_client_config: Option<StatsdClientConfig>,
     no_progress_bar: bool,
+    command: String,
+    pub(super) output_json: Option<PathBuf>,
 
     peer_pick_policy: SelectivePickPolicy,
     pub(super) tcp_sock_speed_limit: TcpSockSpeedLimitConfig,
@@ -101,6 +105,8 @@ impl Default for ProcArgs {
             main_runtime: BlendedRuntimeConfig::default(),
             statsd_client_config: None,
             no_progress_bar: false,
+            command: String::new(),
+            output_json: None,
             peer_pick_policy: SelectivePickPolicy::RoundRobin,
             tcp_sock_speed_limit: TcpSockSpeedLimitConfig::default(),
             udp_sock_speed_limit: UdpSockSpeedLimitConfig::default(),
@@ -114,6 +120,16 @@ impl ProcArgs {
         println!();
     }
 
+    pub(super) fn json_params(&self) -> Value {
+        json!({
+            "command": self.command,
+            "concurrency": self.concurrency.get(),
+            "requests": self.requests,
+            "time_limit_ns": self.time_limit.map(|d| d.as_nanos() as u64),
+            "latency_ns": self.latency.map(|d| d.as_nanos() as u64),
+        })
+    }
+
     pub(super) fn new_progress_bar(&self) -> Option<BenchProgress> {
         if self.no_progress_bar {
             None
@@ -367,6 +383,16 @@ pub fn add_global_args(app: Command) -> Command {
             .long(GLOBAL_ARG_NO_PROGRESS_BAR)
             .global(true),
     )
+    .arg(
+        Arg::new(GLOBAL_ARG_OUTPUT_JSON)
+            .help("Write the final results to this file in json format")
+            .value_name("JSON FILE PATH")
+            .long(GLOBAL_ARG_OUTPUT_JSON)
+            .global(true)
+            .num_args(1)
+            .value_hint(ValueHint::FilePath)
+            .value_parser(value_parser!(PathBuf)),
+    )
     .arg(
         Arg::new(GLOBAL_ARG_PEER_PICK_POLICY)
             .help("Set the pick policy for selecting peers")
@@ -514,6 +540,13 @@ pub fn parse_global_args(args: &ArgMatches) -> anyhow::Result<ProcArgs> {
         proc_args.no_progress_bar = true;
     }
 
+    if let Some(p) = args.get_one::<PathBuf>(GLOBAL_ARG_OUTPUT_JSON) {
+        proc_args.output_json = Some(p.clone());
+    }
+    if let Some(name) = args.subcommand_name() {
+        proc_args.command = name.to_string();
+    }
+
     if let Some(s) = args.get_one::<String>(GLOBAL_ARG_PEER_PICK_POLICY) {
         proc_args.peer_pick_policy = SelectivePickPolicy::from_str(s).unwrap();
     }
@@ -0,0 +1,59 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::anyhow;
+use rustls::KeyLog;
+
+/// Write TLS secrets in the NSS key log format, which can be loaded by Wireshark
+#[derive(Debug)]
+pub(super) struct H3KeyLogFile {
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl H3KeyLogFile {
+    pub(super) fn new(path: &Path) -> anyhow::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| anyhow!("failed to open key log file {}: {e}", path.display()))?;
+        Ok(H3KeyLogFile {
+            writer: Mutex::new(BufWriter::new(file)),
+        })
+    }
+}
+
+impl KeyLog for H3KeyLogFile {
+    fn log(&self, label: &str, client_random: &[u8], secret: &[u8]) {
+        let line = format!(
+            "{label} {} {}\n",
+            hex::encode(client_random),
+            hex::encode(secret)
+        );
+        let mut writer = self.writer.lock().unwrap();
+        if let Err(e) = writer
+            .write_all(line.as_bytes())
+            .and_then(|_| writer.flush())
+        {
+            eprintln!("failed to write key log: {e}");
+        }
+    }
+}
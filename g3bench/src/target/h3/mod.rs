@@ -0,0 +1,251 @@
+/*
+ * Copyright 2023 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use clap::{ArgMatches, Command};
+use http::{HeaderName, HeaderValue, Method, Request, Uri, Version};
+use tokio::time::Instant;
+
+use g3_types::collection::{SelectiveVec, WeightedValue};
+use g3_types::net::UpstreamAddr;
+
+use super::{BenchError, BenchTarget, BenchTaskContext, ProcArgs};
+use crate::module::http::{HttpHistogram, HttpHistogramRecorder, HttpRuntimeStats};
+
+mod keylog;
+
+mod opts;
+mod pacer;
+use pacer::{H3BacklogPolicy, H3RatePacer};
+
+use opts::BenchH3Args;
+
+mod pool;
+use pool::H3ConnectionPool;
+
+mod task;
+use task::H3TaskContext;
+
+pub const COMMAND: &str = "h3";
+
+struct H3Target {
+    args: Arc<BenchH3Args>,
+    proc_args: Arc<ProcArgs>,
+    stats: Arc<HttpRuntimeStats>,
+    histogram: Option<HttpHistogram>,
+    histogram_recorder: HttpHistogramRecorder,
+    pool: Option<Arc<H3ConnectionPool>>,
+    pre_requests: Arc<SelectiveVec<WeightedValue<H3PreRequest>>>,
+    pacer: Option<Arc<H3RatePacer>>,
+}
+
+impl BenchTarget<HttpRuntimeStats, HttpHistogram, H3TaskContext> for H3Target {
+    fn new_context(&self) -> anyhow::Result<H3TaskContext> {
+        H3TaskContext::new(
+            &self.args,
+            &self.proc_args,
+            &self.stats,
+            self.histogram_recorder.clone(),
+            self.pool.clone(),
+            self.pre_requests.clone(),
+            self.pacer.clone(),
+        )
+    }
+
+    fn fetch_runtime_stats(&self) -> Arc<HttpRuntimeStats> {
+        self.stats.clone()
+    }
+
+    fn take_histogram(&mut self) -> Option<HttpHistogram> {
+        self.histogram.take()
+    }
+
+    fn target_url(&self) -> Option<String> {
+        Some(self.args.target_url.to_string())
+    }
+
+    fn notify_finish(&mut self) {
+        if let Some(pool) = self.pool.take() {
+            // the connection stats should be recorded before the summary
+            pool.close();
+        }
+    }
+}
+
+pub fn command() -> Command {
+    opts::add_h3_args(Command::new(COMMAND))
+}
+
+pub async fn run(proc_args: &Arc<ProcArgs>, cmd_args: &ArgMatches) -> anyhow::Result<()> {
+    let mut h3_args = opts::parse_h3_args(cmd_args)?;
+    if h3_args.local_port.is_some() {
+        let conn_count = h3_args.pool_size.unwrap_or(proc_args.concurrency.get());
+        if conn_count > 1 {
+            return Err(anyhow!(
+                "only one connection is allowed if local port is set, but {conn_count} is needed"
+            ));
+        }
+    }
+    h3_args.resolve_target_address(proc_args).await?;
+    let pre_requests = h3_args
+        .build_pre_requests()
+        .context("failed to build request header")?;
+    let h3_args = Arc::new(h3_args);
+
+    let runtime_stats = Arc::new(HttpRuntimeStats::new_udp(COMMAND));
+    let (histogram, histogram_recorder) = HttpHistogram::new();
+
+    let pool = h3_args.pool_size.map(|s| {
+        Arc::new(H3ConnectionPool::new(
+            &h3_args,
+            proc_args,
+            s,
+            &runtime_stats,
+            &histogram_recorder,
+        ))
+    });
+
+    let pre_requests = Arc::new(pre_requests);
+
+    if let Some(warmup) = h3_args.warmup {
+        let (passed, failed) =
+            run_warmup(&h3_args, proc_args, &pre_requests, pool.as_ref(), warmup).await?;
+        println!(
+            "Warmup requests: {} (passed {passed}, failed {failed})\n",
+            passed + failed
+        );
+        if let Some(pool) = &pool {
+            pool.reset_usage().await;
+            pool.pre_connect()
+                .await
+                .context("failed to pre-establish pooled connections")?;
+        }
+    }
+
+    let pacer = h3_args
+        .rate
+        .map(|rate| Arc::new(H3RatePacer::new(rate, h3_args.backlog_policy)));
+
+    let target = H3Target {
+        args: h3_args,
+        proc_args: Arc::clone(proc_args),
+        stats: runtime_stats,
+        histogram: Some(histogram),
+        histogram_recorder,
+        pool,
+        pre_requests,
+        pacer: pacer.clone(),
+    };
+
+    super::run(target, proc_args).await?;
+
+    if let Some(pacer) = pacer {
+        if pacer.backlog_policy() == H3BacklogPolicy::Drop {
+            println!("\n# Pacing");
+            println!("Dropped requests: {}", pacer.dropped());
+        }
+    }
+    Ok(())
+}
+
+async fn run_warmup(
+    args: &Arc<BenchH3Args>,
+    proc_args: &Arc<ProcArgs>,
+    pre_requests: &Arc<SelectiveVec<WeightedValue<H3PreRequest>>>,
+    pool: Option<&Arc<H3ConnectionPool>>,
+    duration: Duration,
+) -> anyhow::Result<(u64, u64)> {
+    // stats and histogram of the warmup requests are dropped. The pooled connections are kept
+    // for the measured run, so the handshakes won't be done again, and their connection level
+    // stats will still be recorded in the main stats
+    let runtime_stats = Arc::new(HttpRuntimeStats::new_udp(COMMAND));
+    let (_histogram, histogram_recorder) = HttpHistogram::new();
+
+    let time_end = Instant::now() + duration;
+    let mut handles = Vec::with_capacity(proc_args.concurrency.get());
+    for i in 0..proc_args.concurrency.get() {
+        let mut context = H3TaskContext::new(
+            args,
+            proc_args,
+            &runtime_stats,
+            histogram_recorder.clone(),
+            pool.cloned(),
+            pre_requests.clone(),
+            None,
+        )
+        .context(format!("failed to create warmup context #{i}"))?;
+        let ignore_fatal_error = proc_args.ignore_fatal_error;
+        handles.push(tokio::spawn(async move {
+            let mut passed = 0u64;
+            let mut failed = 0u64;
+            while Instant::now() < time_end {
+                match context.run(i, Instant::now()).await {
+                    Ok(_) => passed += 1,
+                    Err(BenchError::Fatal(e)) => {
+                        failed += 1;
+                        if !ignore_fatal_error {
+                            return Err(e.context(format!("fatal error with warmup context {i}")));
+                        }
+                    }
+                    Err(BenchError::Task(_)) => failed += 1,
+                }
+            }
+            Ok((passed, failed))
+        }));
+    }
+
+    let mut total_passed = 0;
+    let mut total_failed = 0;
+    for h in handles {
+        let (passed, failed) = h
+            .await
+            .map_err(|e| anyhow!("failed to join warmup task: {e}"))??;
+        total_passed += passed;
+        total_failed += failed;
+    }
+    Ok((total_passed, total_failed))
+}
+
+#[derive(Hash)]
+struct H3PreRequest {
+    target: UpstreamAddr,
+    method: Method,
+    uri: Uri,
+    auth: Option<HeaderValue>,
+    headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+impl H3PreRequest {
+    fn build_request(&self) -> anyhow::Result<Request<()>> {
+        let mut req = Request::builder()
+            .version(Version::HTTP_3)
+            .method(self.method.clone())
+            .uri(self.uri.clone())
+            .body(())
+            .map_err(|e| anyhow!("failed to build request: {e:?}"))?;
+        for (name, value) in &self.headers {
+            req.headers_mut().append(name.clone(), value.clone());
+        }
+        if let Some(v) = &self.auth {
+            req.headers_mut()
+                .insert(http::header::AUTHORIZATION, v.clone());
+        }
+        Ok(req)
+    }
+}
@@ -0,0 +1,902 @@
+/*
+ * Copyright 2023 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ahash::AHashMap;
+use anyhow::{anyhow, Context};
+use bytes::Bytes;
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command, ValueHint};
+use h3::client::SendRequest;
+use h3_quinn::OpenStreams;
+use http::{header, HeaderName, HeaderValue, Method, StatusCode};
+use quinn::crypto::rustls::QuicClientConfig;
+use quinn::{
+    ClientConfig, ConnectionError, Endpoint, TokioRuntime, TransportConfig, TransportErrorCode,
+    VarInt,
+};
+use rustls::{KeyLog, KeyLogFile};
+use rustls_pki_types::ServerName;
+use url::Url;
+
+use g3_io_ext::LimitedTokioRuntime;
+use g3_socks::v5::Socks5UdpTokioRuntime;
+use g3_types::collection::{SelectiveVec, SelectiveVecBuilder, WeightedValue};
+use g3_types::net::{
+    AlpnProtocol, HttpAuth, Proxy, RustlsClientConfigBuilder, Socks5Proxy, UpstreamAddr,
+};
+
+use super::keylog::H3KeyLogFile;
+use super::pacer::H3BacklogPolicy;
+use super::{H3PreRequest, HttpRuntimeStats, ProcArgs};
+use crate::module::http::QuicHandshakeError;
+use crate::module::rustls::{AppendRustlsArgs, RustlsTlsClientArgs};
+use crate::module::socket::{AppendSocketArgs, SocketArgs};
+
+const HTTP_ARG_CONNECTION_POOL: &str = "connection-pool";
+const HTTP_ARG_URI: &str = "uri";
+const HTTP_ARG_URI_FILE: &str = "uri-file";
+const HTTP_ARG_ALLOW_MULTI_HOST: &str = "allow-multi-host";
+const HTTP_ARG_METHOD: &str = "method";
+const HTTP_ARG_HEADER: &str = "header";
+const HTTP_ARG_PROXY: &str = "proxy";
+const HTTP_ARG_PEER: &str = "peer";
+const HTTP_ARG_NO_MULTIPLEX: &str = "no-multiplex";
+const HTTP_ARG_RECONNECT: &str = "reconnect";
+const HTTP_ARG_OK_STATUS: &str = "ok-status";
+const HTTP_ARG_FOLLOW_REDIRECTS: &str = "follow-redirects";
+const HTTP_ARG_EXPECT_SIZE: &str = "expect-size";
+const HTTP_ARG_GRPC: &str = "grpc";
+const HTTP_ARG_GRPC_MESSAGE: &str = "grpc-message";
+const HTTP_ARG_EXPECT_SHA256: &str = "expect-sha256";
+const HTTP_ARG_TIMEOUT: &str = "timeout";
+const HTTP_ARG_CONNECT_TIMEOUT: &str = "connect-timeout";
+const HTTP_ARG_WARMUP: &str = "warmup";
+const HTTP_ARG_KEYLOG: &str = "keylog";
+const HTTP_ARG_LOCAL_PORT: &str = "local-port";
+const HTTP_ARG_RATE: &str = "rate";
+const HTTP_ARG_ON_BACKLOG: &str = "on-backlog";
+
+pub(super) struct BenchH3Args {
+    pub(super) pool_size: Option<usize>,
+    pub(super) method: Method,
+    headers: Vec<(HeaderName, HeaderValue)>,
+    pub(super) target_url: Url,
+    extra_urls: Vec<WeightedValue<Url>>,
+    target_url_weight: f64,
+    pub(super) allow_multi_host: bool,
+    socks_proxy: Option<Socks5Proxy>,
+    pub(super) no_multiplex: bool,
+    pub(super) goaway_reconnect: bool,
+    pub(super) ok_status: Option<StatusCode>,
+    pub(super) max_redirects: usize,
+    pub(super) expect_size: Option<u64>,
+    pub(super) expect_sha256: Option<[u8; 32]>,
+    pub(super) grpc_frame: Option<Bytes>,
+    pub(super) timeout: Duration,
+    pub(super) connect_timeout: Duration,
+    pub(super) warmup: Option<Duration>,
+    pub(super) rate: Option<u32>,
+    pub(super) backlog_policy: H3BacklogPolicy,
+
+    socket: SocketArgs,
+    pub(super) local_port: Option<u16>,
+    target_tls: RustlsTlsClientArgs,
+    key_log: Option<Arc<dyn KeyLog>>,
+
+    pub(super) target: UpstreamAddr,
+    proxy_peer_addrs: Option<SelectiveVec<WeightedValue<SocketAddr>>>,
+    quic_peer_addrs: AHashMap<UpstreamAddr, SelectiveVec<WeightedValue<SocketAddr>>>,
+    static_peer_addrs: Option<SelectiveVec<WeightedValue<SocketAddr>>>,
+}
+
+impl BenchH3Args {
+    fn new(url: Url) -> anyhow::Result<Self> {
+        let upstream = UpstreamAddr::try_from(&url)?;
+
+        let tls = RustlsTlsClientArgs {
+            config: Some(RustlsClientConfigBuilder::default()),
+            alpn_protocol: Some(AlpnProtocol::Http3),
+            ..Default::default()
+        };
+
+        Ok(BenchH3Args {
+            pool_size: None,
+            method: Method::GET,
+            headers: Vec::new(),
+            target_url: url,
+            extra_urls: Vec::new(),
+            target_url_weight: 1.0,
+            allow_multi_host: false,
+            socks_proxy: None,
+            no_multiplex: false,
+            goaway_reconnect: false,
+            ok_status: None,
+            max_redirects: 0,
+            expect_size: None,
+            expect_sha256: None,
+            grpc_frame: None,
+            timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(15),
+            warmup: None,
+            rate: None,
+            backlog_policy: H3BacklogPolicy::Queue,
+            socket: SocketArgs::default(),
+            local_port: None,
+            target_tls: tls,
+            key_log: None,
+            target: upstream,
+            proxy_peer_addrs: None,
+            quic_peer_addrs: AHashMap::new(),
+            static_peer_addrs: None,
+        })
+    }
+
+    fn check_urls(&self) -> anyhow::Result<()> {
+        for (url, _) in self.all_urls() {
+            if url.scheme() != "https" {
+                return Err(anyhow!("unsupported target url {url}"));
+            }
+            if !self.allow_multi_host {
+                let upstream = UpstreamAddr::try_from(url)?;
+                if upstream != self.target {
+                    return Err(anyhow!(
+                        "the host of uri {url} is different from {}, use --{HTTP_ARG_ALLOW_MULTI_HOST} if needed",
+                        self.target
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn all_urls(&self) -> impl Iterator<Item = (&Url, f64)> {
+        [(&self.target_url, self.target_url_weight)]
+            .into_iter()
+            .chain(self.extra_urls.iter().map(|v| (v.inner(), v.weight())))
+    }
+
+    pub(super) async fn resolve_target_address(
+        &mut self,
+        proc_args: &ProcArgs,
+    ) -> anyhow::Result<()> {
+        if let Some(proxy) = &self.socks_proxy {
+            let addrs = proc_args.resolve(proxy.peer()).await?;
+            self.proxy_peer_addrs = Some(addrs);
+        };
+        let addrs = match self.static_peer_addrs.take() {
+            Some(addrs) => addrs,
+            None => proc_args.resolve(&self.target).await?,
+        };
+        self.quic_peer_addrs.insert(self.target.clone(), addrs);
+        if self.allow_multi_host {
+            for v in &self.extra_urls {
+                let upstream = UpstreamAddr::try_from(v.inner())?;
+                if self.quic_peer_addrs.contains_key(&upstream) {
+                    continue;
+                }
+                let addrs = proc_args.resolve(&upstream).await?;
+                self.quic_peer_addrs.insert(upstream, addrs);
+            }
+        }
+        Ok(())
+    }
+
+    fn new_udp_socket(&self, peer: SocketAddr) -> anyhow::Result<UdpSocket> {
+        if let Some(port) = self.local_port {
+            self.socket.udp_std_socket_with_port_to(peer, port)
+        } else {
+            self.socket.udp_std_socket_to(peer)
+        }
+    }
+
+    async fn new_quic_endpoint(
+        &self,
+        stats: &Arc<HttpRuntimeStats>,
+        proc_args: &ProcArgs,
+        quic_peer: SocketAddr,
+    ) -> anyhow::Result<Endpoint> {
+        if let Some(socks5_proxy) = &self.socks_proxy {
+            let proxy_addrs = self
+                .proxy_peer_addrs
+                .as_ref()
+                .ok_or_else(|| anyhow!("no proxy addr set"))?;
+            let peer = *proc_args.select_peer(proxy_addrs);
+
+            let mut stream = self.socket.tcp_connect_to(peer).await.context(format!(
+                "failed to connect to socks5 proxy {}",
+                socks5_proxy.peer()
+            ))?;
+
+            let socket = self.new_udp_socket(peer)?;
+
+            let local_udp_addr = socket
+                .local_addr()
+                .map_err(|e| anyhow!("failed to get local addr of udp socket: {e}"))?;
+            let peer_udp_addr = g3_socks::v5::client::socks5_udp_associate(
+                &mut stream,
+                &socks5_proxy.auth,
+                local_udp_addr,
+            )
+            .await
+            .map_err(|e| {
+                anyhow!(
+                    "socks5 udp associate to {} failed: {e}",
+                    socks5_proxy.peer()
+                )
+            })?;
+
+            socket.connect(peer_udp_addr).map_err(|e| {
+                anyhow!("failed to connect local udp socket to {peer_udp_addr}: {e}")
+            })?;
+
+            let limit = &proc_args.udp_sock_speed_limit;
+            let runtime = LimitedTokioRuntime::local_limited(
+                Socks5UdpTokioRuntime::new(stream, quic_peer),
+                limit.shift_millis,
+                limit.max_north_packets,
+                limit.max_north_bytes,
+                limit.max_south_packets,
+                limit.max_south_bytes,
+                stats.clone(),
+            );
+            Endpoint::new(Default::default(), None, socket, Arc::new(runtime))
+                .map_err(|e| anyhow!("failed to create quic endpoint: {e}"))
+        } else {
+            let socket = self.new_udp_socket(quic_peer)?;
+            socket
+                .connect(quic_peer)
+                .map_err(|e| anyhow!("failed to connect local udp socket to {quic_peer}: {e}"))?;
+
+            let limit = &proc_args.udp_sock_speed_limit;
+            let runtime = LimitedTokioRuntime::local_limited(
+                TokioRuntime,
+                limit.shift_millis,
+                limit.max_north_packets,
+                limit.max_north_bytes,
+                limit.max_south_packets,
+                limit.max_south_bytes,
+                stats.clone(),
+            );
+            Endpoint::new(Default::default(), None, socket, Arc::new(runtime))
+                .map_err(|e| anyhow!("failed to create quic endpoint: {e}"))
+        }
+    }
+
+    async fn new_quic_connection(
+        &self,
+        stats: &Arc<HttpRuntimeStats>,
+        proc_args: &ProcArgs,
+        target: &UpstreamAddr,
+    ) -> anyhow::Result<quinn::Connection> {
+        let quic_peer = match self.quic_peer_addrs.get(target) {
+            Some(addrs) => *proc_args.select_peer(addrs),
+            None => {
+                // the redirect target is not known before
+                let addrs = proc_args.resolve(target).await?;
+                *proc_args.select_peer(&addrs)
+            }
+        };
+        let endpoint = self.new_quic_endpoint(stats, proc_args, quic_peer).await?;
+
+        let Some(tls_client) = &self.target_tls.client else {
+            unreachable!()
+        };
+        let mut transport = TransportConfig::default();
+        // no remotely-initiated bidi streams is needed
+        transport.max_concurrent_bidi_streams(VarInt::from_u32(0));
+        // remotely-initiated uni streams is needed by QPACK headers as say in
+        //   https://http3-explained.haxx.se/en/h3/h3-streams
+        // transport.max_concurrent_uni_streams(VarInt::from_u32(0));
+        // TODO add more transport settings
+        let mut tls_config = tls_client.driver.as_ref().clone();
+        if let Some(key_log) = &self.key_log {
+            tls_config.key_log = key_log.clone();
+        }
+        let quic_config = QuicClientConfig::try_from(tls_config)
+            .map_err(|e| anyhow!("invalid quic tls config: {e}"))?;
+        let mut client_config = ClientConfig::new(Arc::new(quic_config));
+        client_config.transport_config(Arc::new(transport));
+
+        let tls_name = match &self.target_tls.tls_name {
+            Some(ServerName::DnsName(domain)) => domain.as_ref().to_string(),
+            Some(ServerName::IpAddress(ip)) => IpAddr::from(*ip).to_string(),
+            Some(_) => return Err(anyhow!("unsupported tls server name type")),
+            None => target.host().to_string(),
+        };
+        let conn = endpoint
+            .connect_with(client_config, quic_peer, &tls_name)
+            .map_err(|e| anyhow!("failed to create quic client: {e}"))?
+            .await
+            .map_err(|e| {
+                let handshake_error = quic_handshake_error(&e);
+                stats.quic_handshake.add_error(handshake_error);
+                anyhow!("failed to connect: {e}")
+            })?;
+        Ok(conn)
+    }
+
+    pub(super) async fn new_h3_connection(
+        &self,
+        stats: &Arc<HttpRuntimeStats>,
+        proc_args: &ProcArgs,
+        target: &UpstreamAddr,
+    ) -> anyhow::Result<SendRequest<OpenStreams, Bytes>> {
+        let quic_conn = self.new_quic_connection(stats, proc_args, target).await?;
+
+        let mut client_builder = h3::client::builder();
+        // TODO add more client config
+        let (mut driver, send_request) = client_builder
+            .build(h3_quinn::Connection::new(quic_conn.clone()))
+            .await
+            .map_err(|e| anyhow!("failed to create h3 connection: {e}"))?;
+        let stats = stats.clone();
+        tokio::spawn(async move {
+            let _ = driver.wait_idle().await;
+            let e = quic_conn.closed().await;
+            stats.quic_close.add_reason(quic_close_reason(&e));
+        });
+
+        Ok(send_request)
+    }
+
+    pub(super) fn build_pre_request_header(&self, url: &Url) -> anyhow::Result<H3PreRequest> {
+        let target = UpstreamAddr::try_from(url)?;
+        let path_and_query = if let Some(q) = url.query() {
+            format!("{}?{q}", url.path())
+        } else {
+            url.path().to_string()
+        };
+        let uri = http::Uri::builder()
+            .scheme(url.scheme())
+            .authority(target.to_string())
+            .path_and_query(path_and_query)
+            .build()
+            .map_err(|e| anyhow!("failed to build request: {e:?}"))?;
+
+        let auth = HttpAuth::try_from(url)
+            .map_err(|e| anyhow!("failed to detect upstream auth method: {e}"))?;
+        let auth = match &auth {
+            HttpAuth::None => None,
+            HttpAuth::Basic(basic) => {
+                let value = format!("Basic {}", basic.encoded_value());
+                let value = HeaderValue::from_str(&value)
+                    .map_err(|e| anyhow!("invalid auth value: {e:?}"))?;
+                Some(value)
+            }
+        };
+
+        Ok(H3PreRequest {
+            target,
+            method: self.method.clone(),
+            uri,
+            auth,
+            headers: self.headers.clone(),
+        })
+    }
+
+    pub(super) fn build_pre_requests(
+        &self,
+    ) -> anyhow::Result<SelectiveVec<WeightedValue<H3PreRequest>>> {
+        let mut builder = SelectiveVecBuilder::with_capacity(self.extra_urls.len() + 1);
+        for (url, weight) in self.all_urls() {
+            let pre_request = self
+                .build_pre_request_header(url)
+                .context(format!("failed to build request header for {url}"))?;
+            builder.insert(WeightedValue::with_weight(pre_request, weight));
+        }
+        builder.build().ok_or_else(|| anyhow!("no request uri set"))
+    }
+}
+
+fn load_uri_file(file: &PathBuf) -> anyhow::Result<Vec<WeightedValue<Url>>> {
+    let f =
+        File::open(file).map_err(|e| anyhow!("failed to open uri file {}: {e}", file.display()))?;
+    let reader = BufReader::new(f);
+    let mut urls = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| anyhow!("failed to read line {}: {e}", i + 1))?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        let Some(uri) = parts.next() else {
+            continue;
+        };
+        let url =
+            Url::parse(uri).map_err(|e| anyhow!("invalid uri {uri} at line {}: {e}", i + 1))?;
+        let weight = match parts.next() {
+            Some(w) => {
+                let w = f64::from_str(w)
+                    .map_err(|e| anyhow!("invalid weight {w} at line {}: {e}", i + 1))?;
+                if !w.is_normal() || w < 0.0 {
+                    return Err(anyhow!("invalid weight {w} at line {}", i + 1));
+                }
+                w
+            }
+            None => 1.0,
+        };
+        if parts.next().is_some() {
+            return Err(anyhow!("too many fields at line {}", i + 1));
+        }
+        urls.push(WeightedValue::with_weight(url, weight));
+    }
+    Ok(urls)
+}
+
+fn parse_peer(s: &str) -> anyhow::Result<WeightedValue<SocketAddr>> {
+    if let Ok(addr) = SocketAddr::from_str(s) {
+        return Ok(WeightedValue::new(addr));
+    }
+    let Some((addr, weight)) = s.rsplit_once(':') else {
+        return Err(anyhow!("invalid socket address"));
+    };
+    let addr = SocketAddr::from_str(addr).map_err(|e| anyhow!("invalid socket address: {e}"))?;
+    let weight = f64::from_str(weight).map_err(|e| anyhow!("invalid weight {weight}: {e}"))?;
+    if !weight.is_normal() || weight < 0.0 {
+        return Err(anyhow!("weight should be positive"));
+    }
+    Ok(WeightedValue::with_weight(addr, weight))
+}
+
+fn parse_header(s: &str) -> anyhow::Result<(HeaderName, HeaderValue)> {
+    if s.starts_with(':') {
+        return Err(anyhow!("pseudo header is reserved and can not be set"));
+    }
+    let Some((name, value)) = s.split_once(':') else {
+        return Err(anyhow!("no ':' delimiter found"));
+    };
+    let name =
+        HeaderName::from_str(name.trim()).map_err(|e| anyhow!("invalid header name: {e}"))?;
+    // connection specific headers are not allowed in HTTP/3, see RFC 9114 Section 4.2
+    match name.as_str() {
+        "connection" | "keep-alive" | "proxy-connection" | "transfer-encoding" | "upgrade" => {
+            return Err(anyhow!("connection specific header {name} is not allowed"));
+        }
+        _ => {}
+    }
+    let value =
+        HeaderValue::from_str(value.trim()).map_err(|e| anyhow!("invalid header value: {e}"))?;
+    Ok((name, value))
+}
+
+fn build_grpc_frame(message: &[u8]) -> anyhow::Result<Bytes> {
+    let len = u32::try_from(message.len()).map_err(|_| anyhow!("too large grpc message"))?;
+    let mut frame = Vec::with_capacity(5 + message.len());
+    frame.push(0); // not compressed
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend_from_slice(message);
+    Ok(Bytes::from(frame))
+}
+
+fn quic_close_reason(e: &ConnectionError) -> String {
+    match e {
+        ConnectionError::VersionMismatch => "version mismatch".to_string(),
+        ConnectionError::TransportError(e) => format!("local transport error: {e}"),
+        ConnectionError::ConnectionClosed(c) => format!(
+            "peer transport close: {} {}",
+            c.error_code,
+            String::from_utf8_lossy(&c.reason)
+        ),
+        ConnectionError::ApplicationClosed(c) => format!(
+            "peer application close: {:#x} {}",
+            c.error_code.into_inner(),
+            String::from_utf8_lossy(&c.reason)
+        ),
+        ConnectionError::Reset => "reset".to_string(),
+        ConnectionError::TimedOut => "idle timeout".to_string(),
+        ConnectionError::LocallyClosed => "locally closed".to_string(),
+        _ => e.to_string(),
+    }
+}
+
+fn quic_handshake_error(e: &ConnectionError) -> QuicHandshakeError {
+    // TLS alerts are carried in the CRYPTO_ERROR range, see RFC 9001 Section 4.8
+    fn tls_alert_error(code: TransportErrorCode) -> QuicHandshakeError {
+        match u64::from(code) {
+            0x12a..=0x12e | 0x130 => QuicHandshakeError::CertVerify,
+            0x146 => QuicHandshakeError::VersionMismatch,
+            0x178 => QuicHandshakeError::AlpnMismatch,
+            _ => QuicHandshakeError::Other,
+        }
+    }
+
+    match e {
+        ConnectionError::VersionMismatch => QuicHandshakeError::VersionMismatch,
+        ConnectionError::TimedOut => QuicHandshakeError::Timeout,
+        ConnectionError::TransportError(e) => tls_alert_error(e.code),
+        ConnectionError::ConnectionClosed(c) => tls_alert_error(c.error_code),
+        _ => QuicHandshakeError::Other,
+    }
+}
+
+pub(super) fn add_h3_args(app: Command) -> Command {
+    app.arg(
+        Arg::new(HTTP_ARG_URI)
+            .required_unless_present(HTTP_ARG_URI_FILE)
+            .num_args(1)
+            .conflicts_with(HTTP_ARG_URI_FILE),
+    )
+    .arg(
+        Arg::new(HTTP_ARG_URI_FILE)
+            .help(
+                "Load request uris from file, one per line.\n\
+                    in the form <URI> or <URI> <WEIGHT>",
+            )
+            .value_name("FILE PATH")
+            .long(HTTP_ARG_URI_FILE)
+            .num_args(1)
+            .value_parser(value_parser!(PathBuf))
+            .value_hint(ValueHint::FilePath),
+    )
+    .arg(
+        Arg::new(HTTP_ARG_ALLOW_MULTI_HOST)
+            .help("Allow uris with different hosts, a new connection will be used for each host")
+            .action(ArgAction::SetTrue)
+            .long(HTTP_ARG_ALLOW_MULTI_HOST)
+            .requires(HTTP_ARG_URI_FILE)
+            .conflicts_with(HTTP_ARG_CONNECTION_POOL),
+    )
+    .arg(
+        Arg::new(HTTP_ARG_CONNECTION_POOL)
+            .help(
+                "Set the number of pooled underlying h3 connections.\n\
+                        If not set, each concurrency will use it's own h3 connection",
+            )
+            .value_name("POOL SIZE")
+            .long(HTTP_ARG_CONNECTION_POOL)
+            .short('C')
+            .num_args(1)
+            .value_parser(value_parser!(usize))
+            .conflicts_with(HTTP_ARG_NO_MULTIPLEX),
+    )
+    .arg(
+        Arg::new(HTTP_ARG_METHOD)
+            .value_name("METHOD")
+            .short('m')
+            .long(HTTP_ARG_METHOD)
+            .num_args(1)
+            .value_parser(["GET", "HEAD"])
+            .default_value("GET"),
+    )
+    .arg(
+        Arg::new(HTTP_ARG_HEADER)
+            .help("Add extra request header, in the form 'Name: Value'")
+            .value_name("HEADER")
+            .short('H')
+            .long(HTTP_ARG_HEADER)
+            .action(ArgAction::Append),
+    )
+    .arg(
+        Arg::new(HTTP_ARG_PROXY)
+            .value_name("PROXY URL")
+            .short('x')
+            .help("Use a proxy")
+            .long(HTTP_ARG_PROXY)
+            .num_args(1)
+            .value_name("PROXY URL"),
+    )
+    .arg(
+        Arg::new(HTTP_ARG_PEER)
+            .help(
+                "Connect to this peer address instead of the resolved ones of the target host.\n\
+                        In the form 'IP:PORT[:WEIGHT]', and can be set multiple times",
+            )
+            .value_name("PEER ADDRESS")
+            .long(HTTP_ARG_PEER)
+            .action(ArgAction::Append),
+    )
+    .arg(
+        Arg::new(HTTP_ARG_NO_MULTIPLEX)
+            .help("Disable h3 connection multiplexing")
+            .action(ArgAction::SetTrue)
+            .long(HTTP_ARG_NO_MULTIPLEX)
+            .conflicts_with(HTTP_ARG_CONNECTION_POOL),
+    )
+    .arg(
+        Arg::new(HTTP_ARG_RECONNECT)
+            .help("Retry the request on a new connection if it is refused by GOAWAY")
+            .action(ArgAction::SetTrue)
+            .long(HTTP_ARG_RECONNECT)
+            .conflicts_with(HTTP_ARG_CONNECTION_POOL),
+    )
+    .arg(
+        Arg::new(HTTP_ARG_OK_STATUS)
+            .help("Only treat this status code as success")
+            .value_name("STATUS CODE")
+            .long(HTTP_ARG_OK_STATUS)
+            .num_args(1)
+            .value_parser(value_parser!(StatusCode)),
+    )
+    .arg(
+        Arg::new(HTTP_ARG_FOLLOW_REDIRECTS)
+            .help("Follow redirects up to this number of hops")
+            .value_name("MAX HOPS")
+            .long(HTTP_ARG_FOLLOW_REDIRECTS)
+            .num_args(1)
+            .value_parser(value_parser!(usize)),
+    )
+    .arg(
+        Arg::new(HTTP_ARG_GRPC)
+            .help(
+                "Send gRPC unary requests, the grpc-status trailer will be checked \
+                        instead of the http status code",
+            )
+            .action(ArgAction::SetTrue)
+            .long(HTTP_ARG_GRPC)
+            .conflicts_with_all([HTTP_ARG_METHOD, HTTP_ARG_OK_STATUS]),
+    )
+    .arg(
+        Arg::new(HTTP_ARG_GRPC_MESSAGE)
+            .help("Read the serialized gRPC request message from this file")
+            .value_name("MESSAGE FILE")
+            .long(HTTP_ARG_GRPC_MESSAGE)
+            .num_args(1)
+            .value_parser(value_parser!(PathBuf))
+            .value_hint(ValueHint::FilePath)
+            .requires(HTTP_ARG_GRPC),
+    )
+    .arg(
+        Arg::new(HTTP_ARG_EXPECT_SIZE)
+            .help("Only treat the response as success if the body has this size")
+            .value_name("BODY SIZE")
+            .long(HTTP_ARG_EXPECT_SIZE)
+            .num_args(1)
+            .value_parser(value_parser!(u64)),
+    )
+    .arg(
+        Arg::new(HTTP_ARG_EXPECT_SHA256)
+            .help("Only treat the response as success if the body has this SHA-256 checksum")
+            .value_name("HEX CHECKSUM")
+            .long(HTTP_ARG_EXPECT_SHA256)
+            .num_args(1),
+    )
+    .arg(
+        Arg::new(HTTP_ARG_TIMEOUT)
+            .help("Http response timeout")
+            .value_name("TIMEOUT DURATION")
+            .default_value("30s")
+            .long(HTTP_ARG_TIMEOUT)
+            .num_args(1),
+    )
+    .arg(
+        Arg::new(HTTP_ARG_CONNECT_TIMEOUT)
+            .help("Timeout for connection to next peer")
+            .value_name("TIMEOUT DURATION")
+            .default_value("15s")
+            .long(HTTP_ARG_CONNECT_TIMEOUT)
+            .num_args(1),
+    )
+    .arg(
+        Arg::new(HTTP_ARG_WARMUP)
+            .help(
+                "Send requests for this duration before the measurement starts.\n\
+                        The warmup requests and connections are not counted in the results,\n\
+                        and the pooled connections will be established before the measurement",
+            )
+            .value_name("WARMUP DURATION")
+            .long(HTTP_ARG_WARMUP)
+            .num_args(1),
+    )
+    .arg(
+        Arg::new(HTTP_ARG_RATE)
+            .help(
+                "Issue requests at this fixed rate (per second) regardless of the response latency.\n\
+                        The concurrency will be the max number of in-flight requests",
+            )
+            .value_name("REQUESTS PER SECOND")
+            .long(HTTP_ARG_RATE)
+            .num_args(1)
+            .value_parser(value_parser!(u32).range(1..)),
+    )
+    .arg(
+        Arg::new(HTTP_ARG_ON_BACKLOG)
+            .help("What to do if the requests can not be issued in time")
+            .value_name("POLICY")
+            .long(HTTP_ARG_ON_BACKLOG)
+            .num_args(1)
+            .value_parser(["queue", "drop"])
+            .default_value("queue")
+            .requires(HTTP_ARG_RATE),
+    )
+    .arg(
+        Arg::new(HTTP_ARG_KEYLOG)
+            .help(
+                "Write TLS secrets to this file for packet analysis.\n\
+                        If not set, the SSLKEYLOGFILE env var will be used if present",
+            )
+            .value_name("KEYLOG FILE")
+            .long(HTTP_ARG_KEYLOG)
+            .num_args(1)
+            .value_parser(value_parser!(PathBuf))
+            .value_hint(ValueHint::FilePath),
+    )
+    .arg(
+        Arg::new(HTTP_ARG_LOCAL_PORT)
+            .help(
+                "Bind the local udp socket to this port.\n\
+                        Only one quic connection can be established at the same time",
+            )
+            .value_name("LOCAL PORT")
+            .long(HTTP_ARG_LOCAL_PORT)
+            .num_args(1)
+            .value_parser(value_parser!(u16).range(1..)),
+    )
+    .append_socket_args()
+    .append_rustls_args()
+}
+
+pub(super) fn parse_h3_args(args: &ArgMatches) -> anyhow::Result<BenchH3Args> {
+    let mut h3_args = if let Some(v) = args.get_one::<String>(HTTP_ARG_URI) {
+        let url = Url::parse(v).context(format!("invalid {HTTP_ARG_URI} value"))?;
+        BenchH3Args::new(url)?
+    } else if let Some(p) = args.get_one::<PathBuf>(HTTP_ARG_URI_FILE) {
+        let mut urls = load_uri_file(p)?;
+        if urls.is_empty() {
+            return Err(anyhow!("no uri found in file {}", p.display()));
+        }
+        let first = urls.remove(0);
+        let weight = first.weight();
+        let mut h3_args = BenchH3Args::new(first.into_inner())?;
+        h3_args.target_url_weight = weight;
+        h3_args.extra_urls = urls;
+        h3_args
+    } else {
+        return Err(anyhow!("no target url set"));
+    };
+
+    if args.get_flag(HTTP_ARG_ALLOW_MULTI_HOST) {
+        h3_args.allow_multi_host = true;
+    }
+
+    if let Some(c) = args.get_one::<usize>(HTTP_ARG_CONNECTION_POOL) {
+        if *c > 0 {
+            h3_args.pool_size = Some(*c);
+        }
+    }
+
+    if let Some(v) = args.get_one::<String>(HTTP_ARG_METHOD) {
+        let method = Method::from_str(v).context(format!("invalid {HTTP_ARG_METHOD} value"))?;
+        h3_args.method = method;
+    }
+
+    if let Some(headers) = args.get_many::<String>(HTTP_ARG_HEADER) {
+        for h in headers {
+            let (name, value) = parse_header(h).context(format!("invalid header {h}"))?;
+            h3_args.headers.push((name, value));
+        }
+    }
+
+    if let Some(v) = args.get_one::<String>(HTTP_ARG_PROXY) {
+        let url = Url::parse(v).context(format!("invalid {HTTP_ARG_PROXY} value"))?;
+        let proxy = Proxy::try_from(&url).map_err(|e| anyhow!("invalid proxy: {e}"))?;
+        let Proxy::Socks5(proxy) = proxy else {
+            return Err(anyhow!("unsupported proxy {v}"));
+        };
+        h3_args.socks_proxy = Some(proxy);
+    }
+
+    if let Some(peers) = args.get_many::<String>(HTTP_ARG_PEER) {
+        let mut builder = SelectiveVecBuilder::new();
+        for p in peers {
+            let peer = parse_peer(p).context(format!("invalid {HTTP_ARG_PEER} value {p}"))?;
+            builder.insert(peer);
+        }
+        let addrs = builder
+            .build()
+            .ok_or_else(|| anyhow!("no valid {HTTP_ARG_PEER} value set"))?;
+        h3_args.static_peer_addrs = Some(addrs);
+    }
+
+    if args.get_flag(HTTP_ARG_NO_MULTIPLEX) {
+        h3_args.no_multiplex = true;
+    }
+    if args.get_flag(HTTP_ARG_RECONNECT) {
+        h3_args.goaway_reconnect = true;
+    }
+
+    if let Some(code) = args.get_one::<StatusCode>(HTTP_ARG_OK_STATUS) {
+        h3_args.ok_status = Some(*code);
+    }
+
+    if let Some(n) = args.get_one::<usize>(HTTP_ARG_FOLLOW_REDIRECTS) {
+        h3_args.max_redirects = *n;
+    }
+
+    if args.get_flag(HTTP_ARG_GRPC) {
+        let message = match args.get_one::<PathBuf>(HTTP_ARG_GRPC_MESSAGE) {
+            Some(p) => std::fs::read(p)
+                .map_err(|e| anyhow!("failed to read grpc message file {}: {e}", p.display()))?,
+            None => Vec::new(),
+        };
+        h3_args.grpc_frame = Some(build_grpc_frame(&message)?);
+        h3_args.method = Method::POST;
+        h3_args.headers.push((
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/grpc"),
+        ));
+        h3_args
+            .headers
+            .push((header::TE, HeaderValue::from_static("trailers")));
+    }
+
+    if let Some(size) = args.get_one::<u64>(HTTP_ARG_EXPECT_SIZE) {
+        h3_args.expect_size = Some(*size);
+    }
+
+    if let Some(v) = args.get_one::<String>(HTTP_ARG_EXPECT_SHA256) {
+        let mut checksum = [0u8; 32];
+        hex::decode_to_slice(v, &mut checksum)
+            .map_err(|e| anyhow!("invalid {HTTP_ARG_EXPECT_SHA256} value: {e}"))?;
+        h3_args.expect_sha256 = Some(checksum);
+    }
+
+    if let Some(timeout) = g3_clap::humanize::get_duration(args, HTTP_ARG_TIMEOUT)? {
+        h3_args.timeout = timeout;
+    }
+
+    if let Some(timeout) = g3_clap::humanize::get_duration(args, HTTP_ARG_CONNECT_TIMEOUT)? {
+        h3_args.connect_timeout = timeout;
+    }
+
+    if let Some(warmup) = g3_clap::humanize::get_duration(args, HTTP_ARG_WARMUP)? {
+        if !warmup.is_zero() {
+            h3_args.warmup = Some(warmup);
+        }
+    }
+
+    h3_args
+        .socket
+        .parse_args(args)
+        .context("invalid socket config")?;
+    if let Some(port) = args.get_one::<u16>(HTTP_ARG_LOCAL_PORT) {
+        h3_args.local_port = Some(*port);
+    }
+    h3_args
+        .target_tls
+        .parse_tls_args(args)
+        .context("invalid target tls config")?;
+
+    if let Some(rate) = args.get_one::<u32>(HTTP_ARG_RATE) {
+        h3_args.rate = Some(*rate);
+    }
+    if let Some(v) = args.get_one::<String>(HTTP_ARG_ON_BACKLOG) {
+        h3_args.backlog_policy = match v.as_str() {
+            "queue" => H3BacklogPolicy::Queue,
+            "drop" => H3BacklogPolicy::Drop,
+            _ => return Err(anyhow!("invalid {HTTP_ARG_ON_BACKLOG} value {v}")),
+        };
+    }
+
+    if let Some(p) = args.get_one::<PathBuf>(HTTP_ARG_KEYLOG) {
+        let key_log = H3KeyLogFile::new(p)?;
+        h3_args.key_log = Some(Arc::new(key_log));
+    } else if std::env::var_os("SSLKEYLOGFILE").is_some() {
+        h3_args.key_log = Some(Arc::new(KeyLogFile::new()));
+    }
+
+    h3_args.check_urls()?;
+
+    Ok(h3_args)
+}
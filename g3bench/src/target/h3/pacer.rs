@@ -0,0 +1,84 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum H3BacklogPolicy {
+    /// Wait for the scheduled slot, and measure the latency from that slot
+    Queue,
+    /// Skip the slots that have already been missed
+    Drop,
+}
+
+/// Open-loop request scheduler, which will issue requests at a fixed rate
+/// regardless of the response latency
+pub(super) struct H3RatePacer {
+    interval: Duration,
+    interval_nanos: u64,
+    backlog_policy: H3BacklogPolicy,
+    time_start: OnceLock<Instant>,
+    next_slot: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl H3RatePacer {
+    pub(super) fn new(rate: u32, backlog_policy: H3BacklogPolicy) -> Self {
+        let interval = Duration::from_secs(1) / rate;
+        H3RatePacer {
+            interval,
+            interval_nanos: interval.as_nanos() as u64,
+            backlog_policy,
+            time_start: OnceLock::new(),
+            next_slot: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    fn slot_time(&self, slot: u64) -> Instant {
+        let time_start = self.time_start.get_or_init(Instant::now);
+        *time_start + Duration::from_nanos(self.interval_nanos.saturating_mul(slot))
+    }
+
+    /// Wait for the next request slot, and return the time that the request should be started
+    pub(super) async fn wait(&self) -> Instant {
+        let mut slot_time = self.slot_time(self.next_slot.fetch_add(1, Ordering::Relaxed));
+        if self.backlog_policy == H3BacklogPolicy::Drop {
+            let now = Instant::now();
+            while slot_time + self.interval < now {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                slot_time = self.slot_time(self.next_slot.fetch_add(1, Ordering::Relaxed));
+            }
+        }
+        tokio::time::sleep_until(slot_time).await;
+        match self.backlog_policy {
+            H3BacklogPolicy::Queue => slot_time,
+            H3BacklogPolicy::Drop => Instant::now(),
+        }
+    }
+
+    pub(super) fn backlog_policy(&self) -> H3BacklogPolicy {
+        self.backlog_policy
+    }
+
+    pub(super) fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,284 @@
+/*
+ * Copyright 2023 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use bytes::Bytes;
+use h3::client::SendRequest;
+use h3_quinn::OpenStreams;
+use tokio::sync::Mutex;
+
+use super::{BenchH3Args, HttpHistogramRecorder, HttpRuntimeStats, ProcArgs};
+use crate::module::http::QuicHandshakeError;
+
+#[derive(Default)]
+struct H3ConnectionUsage {
+    req_count: u64,
+}
+
+impl H3ConnectionUsage {
+    fn add_request(&mut self) {
+        self.req_count += 1;
+    }
+
+    /// Take the (request count, reuse count) pair of the closed connection.
+    /// Connections that have never been used for a request are not counted.
+    fn take(&mut self) -> Option<(u64, u64)> {
+        let req_count = std::mem::take(&mut self.req_count);
+        if req_count == 0 {
+            None
+        } else {
+            Some((req_count, req_count - 1))
+        }
+    }
+}
+
+struct H3ConnectionUnlocked {
+    args: Arc<BenchH3Args>,
+    proc_args: Arc<ProcArgs>,
+    index: usize,
+    h3s: Option<SendRequest<OpenStreams, Bytes>>,
+    runtime_stats: Arc<HttpRuntimeStats>,
+    histogram_recorder: HttpHistogramRecorder,
+    usage: H3ConnectionUsage,
+}
+
+impl Drop for H3ConnectionUnlocked {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+impl H3ConnectionUnlocked {
+    fn new(
+        args: Arc<BenchH3Args>,
+        proc_args: Arc<ProcArgs>,
+        index: usize,
+        runtime_stats: Arc<HttpRuntimeStats>,
+        histogram_recorder: HttpHistogramRecorder,
+    ) -> Self {
+        H3ConnectionUnlocked {
+            args,
+            proc_args,
+            index,
+            h3s: None,
+            runtime_stats,
+            histogram_recorder,
+            usage: H3ConnectionUsage::default(),
+        }
+    }
+
+    async fn fetch_stream(&mut self) -> anyhow::Result<SendRequest<OpenStreams, Bytes>> {
+        let h3s = match self.h3s.clone() {
+            // TODO check close
+            Some(h3s) => h3s,
+            None => self.connect().await?,
+        };
+        self.usage.add_request();
+        Ok(h3s)
+    }
+
+    async fn connect(&mut self) -> anyhow::Result<SendRequest<OpenStreams, Bytes>> {
+        self.runtime_stats.add_conn_attempt();
+        let new_h3s = match tokio::time::timeout(
+            self.args.connect_timeout,
+            self.args
+                .new_h3_connection(&self.runtime_stats, &self.proc_args, &self.args.target),
+        )
+        .await
+        {
+            Ok(Ok(h3s)) => h3s,
+            Ok(Err(e)) => return Err(e.context(format!("P#{} new connection failed", self.index))),
+            Err(_) => {
+                self.runtime_stats
+                    .quic_handshake
+                    .add_error(QuicHandshakeError::Timeout);
+                return Err(anyhow!("timeout to get new connection"));
+            }
+        };
+        self.runtime_stats.add_conn_success();
+        let s = new_h3s.clone();
+        self.h3s = Some(new_h3s);
+        Ok(s)
+    }
+
+    async fn pre_connect(&mut self) -> anyhow::Result<()> {
+        if self.h3s.is_none() {
+            self.connect().await?;
+        }
+        Ok(())
+    }
+
+    /// Close the connection and record the connection level stats
+    fn close(&mut self) {
+        if self.h3s.take().is_none() {
+            return;
+        }
+        if let Some((req_count, reuse_count)) = self.usage.take() {
+            self.runtime_stats.add_conn_requests(req_count);
+            self.histogram_recorder.record_conn_reuse_count(reuse_count);
+        }
+    }
+}
+
+struct H3Connection {
+    inner: Mutex<H3ConnectionUnlocked>,
+}
+
+impl H3Connection {
+    fn new(
+        args: Arc<BenchH3Args>,
+        proc_args: Arc<ProcArgs>,
+        index: usize,
+        runtime_stats: Arc<HttpRuntimeStats>,
+        histogram_recorder: HttpHistogramRecorder,
+    ) -> Self {
+        H3Connection {
+            inner: Mutex::new(H3ConnectionUnlocked::new(
+                args,
+                proc_args,
+                index,
+                runtime_stats,
+                histogram_recorder,
+            )),
+        }
+    }
+
+    async fn fetch_stream(&self) -> anyhow::Result<SendRequest<OpenStreams, Bytes>> {
+        let mut inner = self.inner.lock().await;
+        inner.fetch_stream().await
+    }
+
+    async fn pre_connect(&self) -> anyhow::Result<()> {
+        let mut inner = self.inner.lock().await;
+        inner.pre_connect().await
+    }
+
+    async fn reset_usage(&self) {
+        let mut inner = self.inner.lock().await;
+        inner.usage = H3ConnectionUsage::default();
+    }
+
+    fn close(&self) {
+        // no task should be using the connection when closing
+        if let Ok(mut inner) = self.inner.try_lock() {
+            inner.close();
+        }
+    }
+}
+
+pub(super) struct H3ConnectionPool {
+    pool: Vec<H3Connection>,
+    pool_size: usize,
+    cur_index: AtomicUsize,
+}
+
+impl H3ConnectionPool {
+    pub(super) fn new(
+        args: &Arc<BenchH3Args>,
+        proc_args: &Arc<ProcArgs>,
+        pool_size: usize,
+        runtime_stats: &Arc<HttpRuntimeStats>,
+        histogram_recorder: &HttpHistogramRecorder,
+    ) -> Self {
+        let mut pool = Vec::with_capacity(pool_size);
+        for i in 0..pool_size {
+            pool.push(H3Connection::new(
+                args.clone(),
+                proc_args.clone(),
+                i,
+                runtime_stats.clone(),
+                histogram_recorder.clone(),
+            ));
+        }
+
+        H3ConnectionPool {
+            pool,
+            pool_size,
+            cur_index: AtomicUsize::new(0),
+        }
+    }
+
+    pub(super) async fn pre_connect(&self) -> anyhow::Result<()> {
+        for c in &self.pool {
+            c.pre_connect().await?;
+        }
+        Ok(())
+    }
+
+    /// Clear the request counts of the connections, so the warmup requests won't be counted
+    pub(super) async fn reset_usage(&self) {
+        for c in &self.pool {
+            c.reset_usage().await;
+        }
+    }
+
+    /// Close all connections and record their stats, should be called before the summary
+    pub(super) fn close(&self) {
+        for c in &self.pool {
+            c.close();
+        }
+    }
+
+    pub(super) async fn fetch_stream(&self) -> anyhow::Result<SendRequest<OpenStreams, Bytes>> {
+        match self.pool_size {
+            0 => Err(anyhow!("no connections configured for this pool")),
+            1 => self.pool[0].fetch_stream().await,
+            _ => {
+                let mut indent = self.cur_index.load(Ordering::Acquire);
+                loop {
+                    let mut next = indent + 1;
+                    if next >= self.pool_size {
+                        next = 0;
+                    }
+
+                    match self.cur_index.compare_exchange(
+                        indent,
+                        next,
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                    ) {
+                        Ok(_) => return self.pool.get(indent).unwrap().fetch_stream().await,
+                        Err(v) => indent = v,
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connection_usage() {
+        let mut usage = H3ConnectionUsage::default();
+        // pre-connected but never used
+        assert_eq!(usage.take(), None);
+
+        usage.add_request();
+        assert_eq!(usage.take(), Some((1, 0)));
+        assert_eq!(usage.take(), None);
+
+        usage.add_request();
+        usage.add_request();
+        usage.add_request();
+        assert_eq!(usage.take(), Some((3, 2)));
+    }
+}
@@ -0,0 +1,398 @@
+/*
+ * Copyright 2023 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+
+use ahash::AHashMap;
+use anyhow::{anyhow, Context};
+use bytes::{Buf, Bytes};
+use h3::client::SendRequest;
+use h3::error::{Code, Kind};
+use h3_quinn::OpenStreams;
+use http::{HeaderMap, StatusCode};
+use openssl::sha::Sha256;
+use thiserror::Error;
+use tokio::time::Instant;
+use url::Url;
+
+use g3_types::collection::{SelectiveVec, WeightedValue};
+use g3_types::net::UpstreamAddr;
+
+use super::{
+    BenchH3Args, BenchTaskContext, H3ConnectionPool, H3PreRequest, H3RatePacer,
+    HttpHistogramRecorder, HttpRuntimeStats, ProcArgs,
+};
+use crate::module::http::QuicHandshakeError;
+use crate::target::BenchError;
+
+#[derive(Clone, Copy, Debug, Error)]
+enum H3GoawayError {
+    #[error("request refused by goaway before started")]
+    NotStarted,
+    #[error("request refused by goaway while in flight")]
+    InFlight,
+}
+
+fn map_h3_error(e: h3::Error, msg: &str) -> anyhow::Error {
+    if matches!(e.kind(), Kind::Closing) {
+        anyhow::Error::new(H3GoawayError::NotStarted)
+    } else if e.try_get_code() == Some(Code::H3_REQUEST_REJECTED) {
+        anyhow::Error::new(H3GoawayError::InFlight)
+    } else {
+        anyhow!("{msg}: {e}")
+    }
+}
+
+pub(super) struct H3TaskContext {
+    args: Arc<BenchH3Args>,
+    proc_args: Arc<ProcArgs>,
+
+    pool: Option<Arc<H3ConnectionPool>>,
+    h3s: AHashMap<UpstreamAddr, (SendRequest<OpenStreams, Bytes>, u64)>,
+
+    reuse_conn_count: u64,
+    pre_requests: Arc<SelectiveVec<WeightedValue<H3PreRequest>>>,
+    pacer: Option<Arc<H3RatePacer>>,
+
+    runtime_stats: Arc<HttpRuntimeStats>,
+    histogram_recorder: HttpHistogramRecorder,
+}
+
+impl Drop for H3TaskContext {
+    fn drop(&mut self) {
+        self.histogram_recorder
+            .record_conn_reuse_count(self.reuse_conn_count);
+        for (_, req_count) in self.h3s.values() {
+            self.runtime_stats.add_conn_requests(*req_count);
+        }
+    }
+}
+
+impl H3TaskContext {
+    pub(super) fn new(
+        args: &Arc<BenchH3Args>,
+        proc_args: &Arc<ProcArgs>,
+        runtime_stats: &Arc<HttpRuntimeStats>,
+        histogram_recorder: HttpHistogramRecorder,
+        pool: Option<Arc<H3ConnectionPool>>,
+        pre_requests: Arc<SelectiveVec<WeightedValue<H3PreRequest>>>,
+        pacer: Option<Arc<H3RatePacer>>,
+    ) -> anyhow::Result<Self> {
+        Ok(H3TaskContext {
+            args: Arc::clone(args),
+            proc_args: Arc::clone(proc_args),
+            pool,
+            h3s: AHashMap::new(),
+            reuse_conn_count: 0,
+            pre_requests,
+            pacer,
+            runtime_stats: Arc::clone(runtime_stats),
+            histogram_recorder,
+        })
+    }
+
+    fn drop_connection(&mut self, target: &UpstreamAddr) {
+        if let Some((_, req_count)) = self.h3s.remove(target) {
+            self.runtime_stats.add_conn_requests(req_count);
+        }
+    }
+
+    async fn fetch_stream(
+        &mut self,
+        target: &UpstreamAddr,
+    ) -> anyhow::Result<SendRequest<OpenStreams, Bytes>> {
+        if let Some(pool) = &self.pool {
+            if self.args.target.eq(target) {
+                return pool.fetch_stream().await;
+            }
+        }
+
+        if let Some((h3s, req_count)) = self.h3s.get_mut(target) {
+            // TODO check close
+            self.reuse_conn_count += 1;
+            *req_count += 1;
+            return Ok(h3s.clone());
+        }
+
+        if self.reuse_conn_count > 0 {
+            self.histogram_recorder
+                .record_conn_reuse_count(self.reuse_conn_count);
+            self.reuse_conn_count = 0;
+        }
+
+        self.runtime_stats.add_conn_attempt();
+        let h3s = match tokio::time::timeout(
+            self.args.connect_timeout,
+            self.args
+                .new_h3_connection(&self.runtime_stats, &self.proc_args, target),
+        )
+        .await
+        {
+            Ok(Ok(h3s)) => h3s,
+            Ok(Err(e)) => return Err(e),
+            Err(_) => {
+                self.runtime_stats
+                    .quic_handshake
+                    .add_error(QuicHandshakeError::Timeout);
+                return Err(anyhow!("timeout to get new connection"));
+            }
+        };
+        self.runtime_stats.add_conn_success();
+
+        let s = h3s.clone();
+        self.h3s.insert(target.clone(), (h3s, 1));
+        Ok(s)
+    }
+
+    async fn run_with_stream(
+        &mut self,
+        time_started: Instant,
+        pre_request: &H3PreRequest,
+        mut send_req: SendRequest<OpenStreams, Bytes>,
+    ) -> anyhow::Result<Option<Url>> {
+        let req = pre_request
+            .build_request()
+            .context("failed to build request header")?;
+
+        // send hdr
+        let mut send_stream = send_req
+            .send_request(req)
+            .await
+            .map_err(|e| map_h3_error(e, "failed to send request header"))?;
+        if let Some(frame) = &self.args.grpc_frame {
+            send_stream
+                .send_data(frame.clone())
+                .await
+                .map_err(|e| map_h3_error(e, "failed to send grpc message"))?;
+        }
+        send_stream.finish().await?;
+        let send_hdr_time = time_started.elapsed();
+
+        // recv hdr
+        let rsp = match tokio::time::timeout(self.args.timeout, send_stream.recv_response()).await {
+            Ok(Ok(rsp)) => rsp,
+            Ok(Err(e)) => return Err(map_h3_error(e, "failed to read response")),
+            Err(_) => return Err(anyhow!("timeout to read response")),
+        };
+        let recv_hdr_time = time_started.elapsed();
+        if self.args.max_redirects > 0 {
+            if let Some(location) = redirect_location(pre_request, rsp.status(), rsp.headers())? {
+                return Ok(Some(location));
+            }
+        }
+        self.histogram_recorder.record_send_hdr_time(send_hdr_time);
+        self.histogram_recorder.record_recv_hdr_time(recv_hdr_time);
+        if let Some(ok_status) = self.args.ok_status {
+            let status = rsp.status();
+            if status != ok_status {
+                return Err(anyhow!(
+                    "Got rsp code {} while {} is expected",
+                    status.as_u16(),
+                    ok_status.as_u16()
+                ));
+            }
+        }
+
+        // recv body
+        let mut body_size = 0u64;
+        let mut body_hasher = self.args.expect_sha256.map(|_| Sha256::new());
+        while let Some(mut data) = send_stream
+            .recv_data()
+            .await
+            .map_err(|e| map_h3_error(e, "failed to recv data"))?
+        {
+            while data.has_remaining() {
+                let chunk = data.chunk();
+                body_size += chunk.len() as u64;
+                if let Some(hasher) = &mut body_hasher {
+                    hasher.update(chunk);
+                }
+                let len = chunk.len();
+                data.advance(len);
+            }
+        }
+        let trailers = send_stream
+            .recv_trailers()
+            .await
+            .map_err(|e| map_h3_error(e, "failed to recv trailer"))?;
+
+        if self.args.grpc_frame.is_some() {
+            // trailers-only response is allowed for errors
+            check_grpc_status(trailers.as_ref().unwrap_or(rsp.headers()))?;
+        }
+
+        if let Some(expect_size) = self.args.expect_size {
+            if body_size != expect_size {
+                self.runtime_stats.add_body_mismatch();
+                return Err(anyhow!(
+                    "Got body size {body_size} while {expect_size} is expected"
+                ));
+            }
+        }
+        if let (Some(hasher), Some(expect_sha256)) = (body_hasher, &self.args.expect_sha256) {
+            let checksum = hasher.finish();
+            if checksum != *expect_sha256 {
+                self.runtime_stats.add_body_mismatch();
+                return Err(anyhow!(
+                    "Got body sha256 {} while {} is expected",
+                    hex::encode(checksum),
+                    hex::encode(expect_sha256)
+                ));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+fn redirect_location(
+    pre_request: &H3PreRequest,
+    status: StatusCode,
+    headers: &HeaderMap,
+) -> anyhow::Result<Option<Url>> {
+    match status {
+        StatusCode::MOVED_PERMANENTLY
+        | StatusCode::FOUND
+        | StatusCode::SEE_OTHER
+        | StatusCode::TEMPORARY_REDIRECT
+        | StatusCode::PERMANENT_REDIRECT => {}
+        _ => return Ok(None),
+    }
+    let Some(location) = headers.get(http::header::LOCATION) else {
+        return Ok(None);
+    };
+    let location = location
+        .to_str()
+        .map_err(|e| anyhow!("invalid location header: {e}"))?;
+    let base = Url::parse(&pre_request.uri.to_string())
+        .map_err(|e| anyhow!("invalid request uri {}: {e}", pre_request.uri))?;
+    let url = base
+        .join(location)
+        .map_err(|e| anyhow!("invalid redirect location {location}: {e}"))?;
+    Ok(Some(url))
+}
+
+fn check_grpc_status(headers: &HeaderMap) -> anyhow::Result<()> {
+    let Some(status) = headers.get("grpc-status") else {
+        return Err(anyhow!("no grpc-status found in response"));
+    };
+    if status.as_bytes() == b"0" {
+        return Ok(());
+    }
+    let message = headers
+        .get("grpc-message")
+        .map(|v| String::from_utf8_lossy(v.as_bytes()))
+        .unwrap_or_default();
+    Err(anyhow!(
+        "Got grpc-status {} with message '{message}'",
+        String::from_utf8_lossy(status.as_bytes())
+    ))
+}
+
+impl BenchTaskContext for H3TaskContext {
+    fn mark_task_start(&self) {
+        self.runtime_stats.add_task_total();
+        self.runtime_stats.inc_task_alive();
+    }
+
+    fn mark_task_passed(&self) {
+        self.runtime_stats.add_task_passed();
+        self.runtime_stats.dec_task_alive();
+    }
+
+    fn mark_task_failed(&self) {
+        self.runtime_stats.add_task_failed();
+        self.runtime_stats.dec_task_alive();
+    }
+
+    async fn run(&mut self, _task_id: usize, time_started: Instant) -> Result<(), BenchError> {
+        let time_started = match &self.pacer {
+            Some(pacer) => pacer.wait().await,
+            None => time_started,
+        };
+
+        let pre_requests = self.pre_requests.clone();
+        let pre_request = pre_requests.pick_random().inner();
+
+        let mut redirect_request: Option<H3PreRequest> = None;
+        let mut visited_uris = Vec::new();
+        let mut goaway_reconnected = false;
+        loop {
+            let pre_request = redirect_request.as_ref().unwrap_or(pre_request);
+
+            let send_req = self
+                .fetch_stream(&pre_request.target)
+                .await
+                .context("fetch new stream failed")
+                .map_err(BenchError::Fatal)?;
+
+            match self
+                .run_with_stream(time_started, pre_request, send_req)
+                .await
+            {
+                Ok(None) => {
+                    let total_time = time_started.elapsed();
+                    self.histogram_recorder.record_total_time(total_time);
+                    if self.args.no_multiplex {
+                        self.drop_connection(&pre_request.target);
+                    }
+                    return Ok(());
+                }
+                Ok(Some(location)) => {
+                    if self.args.no_multiplex {
+                        self.drop_connection(&pre_request.target);
+                    }
+                    if visited_uris.len() >= self.args.max_redirects {
+                        return Err(BenchError::Task(anyhow!(
+                            "too many redirects, the last location is {location}"
+                        )));
+                    }
+                    visited_uris.push(pre_request.uri.clone());
+                    let next_request = self
+                        .args
+                        .build_pre_request_header(&location)
+                        .map_err(BenchError::Task)?;
+                    if visited_uris.contains(&next_request.uri) {
+                        return Err(BenchError::Task(anyhow!(
+                            "redirect loop detected at {location}"
+                        )));
+                    }
+                    self.runtime_stats.add_redirect();
+                    redirect_request = Some(next_request);
+                }
+                Err(e) => {
+                    self.drop_connection(&pre_request.target);
+                    if let Some(goaway) = e.downcast_ref::<H3GoawayError>() {
+                        if self.args.goaway_reconnect && !goaway_reconnected {
+                            self.runtime_stats.h3_goaway.add_reconnect();
+                            goaway_reconnected = true;
+                            continue;
+                        }
+                        match goaway {
+                            H3GoawayError::NotStarted => {
+                                self.runtime_stats.h3_goaway.add_refused_not_started()
+                            }
+                            H3GoawayError::InFlight => {
+                                self.runtime_stats.h3_goaway.add_refused_in_flight()
+                            }
+                        }
+                    }
+                    return Err(BenchError::Task(e));
+                }
+            }
+        }
+    }
+}
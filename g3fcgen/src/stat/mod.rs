@@ -37,14 +37,18 @@ pub(crate) fn spawn_working_thread(
         .build()
         .map_err(|e| anyhow!("failed to build statsd client: {e}"))?;
 
+    let mut last_emit = None;
     let handle = std::thread::Builder::new()
         .name("stat-main".to_string())
         .spawn(move || loop {
             let instant_start = Instant::now();
 
+            let rate_secs =
+                g3_daemon::stat::emit::rate_elapsed_secs(config.emit_rate, &mut last_emit);
+
             metrics::backend::emit_stats(&mut client, &backend_stats);
             metrics::backend::emit_duration_stats(&mut client, &backend_duration_stats);
-            metrics::frontend::emit_stats(&mut client, &frontend_stats);
+            metrics::frontend::emit_stats(&mut client, &frontend_stats, rate_secs);
             g3_daemon::runtime::metrics::emit_stats(&mut client);
 
             client.flush_sink();
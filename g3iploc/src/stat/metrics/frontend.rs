@@ -18,15 +18,24 @@ use g3_statsd_client::StatsdClient;
 
 use crate::FrontendStats;
 
-pub(crate) fn emit_stats(client: &mut StatsdClient, s: &FrontendStats) {
+pub(crate) fn emit_stats(client: &mut StatsdClient, s: &FrontendStats, rate_secs: Option<f64>) {
     macro_rules! emit_count {
         ($take:ident, $name:literal) => {
             let v = s.$take();
             client.count(concat!("frontend.", $name), v).send();
         };
+        ($take:ident, $name:literal, $rate_name:literal) => {
+            let v = s.$take();
+            client.count(concat!("frontend.", $name), v).send();
+            if let Some(secs) = rate_secs {
+                client
+                    .gauge_float(concat!("frontend.", $rate_name), v as f64 / secs)
+                    .send();
+            }
+        };
     }
 
-    emit_count!(take_request_total, "request_total");
+    emit_count!(take_request_total, "request_total", "request_rate");
     emit_count!(take_request_invalid, "request_invalid");
     emit_count!(take_response_total, "response_total");
     emit_count!(take_response_fail, "response_fail");
@@ -34,12 +34,15 @@ pub(crate) fn spawn_working_thread(
         .build()
         .map_err(|e| anyhow!("failed to build statsd client: {e}"))?;
 
+    let mut last_emit = None;
     let handle = std::thread::Builder::new()
         .name("stat-main".to_string())
         .spawn(move || loop {
             let instant_start = Instant::now();
 
-            metrics::frontend::emit_stats(&mut client, &frontend_stats);
+            let rate_secs =
+                g3_daemon::stat::emit::rate_elapsed_secs(config.emit_rate, &mut last_emit);
+            metrics::frontend::emit_stats(&mut client, &frontend_stats, rate_secs);
 
             client.flush_sink();
 
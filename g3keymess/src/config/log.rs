@@ -57,6 +57,12 @@ pub(crate) fn load(v: &Yaml, conf_dir: &Path) -> anyhow::Result<()> {
                     TASK_DEFAULT_LOG_CONFIG_CONTAINER.with_mut(|l| l.set(config));
                     Ok(())
                 }
+                "flush_timeout" => {
+                    let timeout = g3_yaml::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    g3_daemon::log::set_flush_timeout(timeout);
+                    Ok(())
+                }
                 _ => Err(anyhow!("invalid key {k}")),
             })?;
             Ok(())
@@ -131,6 +131,7 @@ impl KeyServerRuntime {
         server_reload_sender: &broadcast::Sender<ServerReloadCommand>,
     ) -> anyhow::Result<()> {
         let listener = g3_socket::tcp::new_listen_to(listen_config)?;
+        self.listen_stats.set_expected_runtime_count(1);
         let server_reload_receiver = server_reload_sender.subscribe();
         tokio::spawn(async move {
             self.pre_start();
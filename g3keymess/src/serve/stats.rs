@@ -16,6 +16,7 @@
 
 use std::sync::atomic::{AtomicI32, AtomicIsize, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 
 use arc_swap::ArcSwapOption;
 
@@ -144,6 +145,8 @@ pub(crate) struct KeyServerSnapshot {
     pub(crate) ecdsa_sign: KeyServerRequestSnapshot,
     pub(crate) ed25519_sign: KeyServerRequestSnapshot,
     pub(crate) noop: KeyServerRequestSnapshot,
+
+    pub(crate) last_emit: Option<Instant>,
 }
 
 impl KeyServerStats {
@@ -31,9 +31,11 @@ use crate::serve::{
 const TAG_KEY_REASON: &str = "reason";
 
 const METRIC_NAME_SERVER_TASK_TOTAL: &str = "server.task.total";
+const METRIC_NAME_SERVER_TASK_RATE: &str = "server.task.rate";
 const METRIC_NAME_SERVER_TASK_ALIVE: &str = "server.task.alive";
 
 const METRIC_NAME_SERVER_REQUEST_TOTAL: &str = "server.request.total";
+const METRIC_NAME_SERVER_REQUEST_RATE: &str = "server.request.rate";
 const METRIC_NAME_SERVER_REQUEST_ALIVE: &str = "server.request.alive";
 const METRIC_NAME_SERVER_REQUEST_PASSED: &str = "server.request.passed";
 const METRIC_NAME_SERVER_REQUEST_FAILED: &str = "server.request.failed";
@@ -93,10 +95,10 @@ pub(in crate::stat) fn sync_stats() {
     drop(duration_stats_map);
 }
 
-pub(in crate::stat) fn emit_stats(client: &mut StatsdClient) {
+pub(in crate::stat) fn emit_stats(client: &mut StatsdClient, emit_rate: bool) {
     let mut server_stats_map = SERVER_STATS_MAP.lock().unwrap();
     server_stats_map.retain(|_, (stats, snap)| {
-        emit_server_stats(client, stats, snap);
+        emit_server_stats(client, stats, snap, emit_rate);
         // use Arc instead of Weak here, as we should emit the final metrics before drop it
         Arc::strong_count(stats) > 1
     });
@@ -123,6 +125,7 @@ fn emit_server_stats(
     client: &mut StatsdClient,
     stats: &Arc<KeyServerStats>,
     snap: &mut KeyServerSnapshot,
+    emit_rate: bool,
 ) {
     let mut common_tags = StatsdTagGroup::default();
     common_tags.add_server_tags(stats.name(), stats.is_online(), stats.stat_id());
@@ -130,11 +133,22 @@ fn emit_server_stats(
         common_tags.add_static_tags(&tags);
     }
 
+    let rate_secs = g3_daemon::stat::emit::rate_elapsed_secs(emit_rate, &mut snap.last_emit);
+
     let new_value = stats.get_task_total();
     let diff_value = new_value.wrapping_sub(snap.task_total);
     client
         .count_with_tags(METRIC_NAME_SERVER_TASK_TOTAL, diff_value, &common_tags)
         .send();
+    if let Some(secs) = rate_secs {
+        client
+            .gauge_float_with_tags(
+                METRIC_NAME_SERVER_TASK_RATE,
+                diff_value as f64 / secs,
+                &common_tags,
+            )
+            .send();
+    }
     snap.task_total = new_value;
 
     client
@@ -153,6 +167,7 @@ fn emit_server_stats(
                 stats.$id.snapshot(),
                 &mut snap.$id,
                 &common_tags,
+                rate_secs,
             );
         };
     }
@@ -171,6 +186,7 @@ fn emit_server_request_stats(
     stats: KeyServerRequestSnapshot,
     snap: &mut KeyServerRequestSnapshot,
     common_tags: &StatsdTagGroup,
+    rate_secs: Option<f64>,
 ) {
     let new_value = stats.total;
     if new_value == 0 && snap.total == 0 {
@@ -181,6 +197,16 @@ fn emit_server_request_stats(
         .count_with_tags(METRIC_NAME_SERVER_REQUEST_TOTAL, diff_value, common_tags)
         .with_tag(TAG_KEY_REQUEST, request)
         .send();
+    if let Some(secs) = rate_secs {
+        client
+            .gauge_float_with_tags(
+                METRIC_NAME_SERVER_REQUEST_RATE,
+                diff_value as f64 / secs,
+                common_tags,
+            )
+            .with_tag(TAG_KEY_REQUEST, request)
+            .send();
+    }
     snap.total = new_value;
 
     client
@@ -41,6 +41,7 @@ fn spawn_main_thread(config: &StatsdClientConfig) -> anyhow::Result<JoinHandle<(
     let mut client = build_statsd_client(config)?;
 
     let emit_duration = config.emit_duration;
+    let emit_rate = config.emit_rate;
     let handle = std::thread::Builder::new()
         .name("stat".to_string())
         .spawn(move || loop {
@@ -49,7 +50,7 @@ fn spawn_main_thread(config: &StatsdClientConfig) -> anyhow::Result<JoinHandle<(
             metrics::server::sync_stats();
             g3_daemon::log::metrics::sync_stats();
 
-            metrics::server::emit_stats(&mut client);
+            metrics::server::emit_stats(&mut client, emit_rate);
             g3_daemon::runtime::metrics::emit_stats(&mut client);
             g3_daemon::log::metrics::emit_stats(&mut client);
 
@@ -51,6 +51,7 @@ pub(crate) struct User {
     tcp_conn_rate_limit: Option<Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>>,
     tcp_all_upload_speed_limit: Option<Arc<GlobalStreamLimiter>>,
     tcp_all_download_speed_limit: Option<Arc<GlobalStreamLimiter>>,
+    tcp_all_egress_speed_limit: Option<Arc<GlobalStreamLimiter>>,
     udp_all_upload_speed_limit: Option<Arc<GlobalDatagramLimiter>>,
     udp_all_download_speed_limit: Option<Arc<GlobalDatagramLimiter>>,
     ingress_net_filter: Option<Arc<AclNetworkRule>>,
@@ -128,6 +129,13 @@ impl User {
         } else {
             None
         };
+        let tcp_all_egress_speed_limit = if let Some(config) = config.tcp_all_egress_speed_limit {
+            let limiter = Arc::new(GlobalStreamLimiter::new(GlobalLimitGroup::User, config));
+            limiter.clone().tokio_spawn_replenish();
+            Some(limiter)
+        } else {
+            None
+        };
         let udp_all_upload_speed_limit = if let Some(config) = config.udp_all_upload_speed_limit {
             let limiter = Arc::new(GlobalDatagramLimiter::new(config));
             limiter.clone().tokio_spawn_replenish();
@@ -160,6 +168,7 @@ impl User {
             tcp_conn_rate_limit,
             tcp_all_upload_speed_limit,
             tcp_all_download_speed_limit,
+            tcp_all_egress_speed_limit,
             udp_all_upload_speed_limit,
             udp_all_download_speed_limit,
             ingress_net_filter: None,
@@ -266,6 +275,18 @@ impl User {
         } else {
             None
         };
+        let tcp_all_egress_speed_limit = if let Some(config) = config.tcp_all_egress_speed_limit {
+            if let Some(old) = self.tcp_all_egress_speed_limit.clone() {
+                old.update(config);
+                Some(old)
+            } else {
+                let limiter = Arc::new(GlobalStreamLimiter::new(GlobalLimitGroup::User, config));
+                limiter.clone().tokio_spawn_replenish();
+                Some(limiter)
+            }
+        } else {
+            None
+        };
         let udp_all_upload_speed_limit = if let Some(config) = config.udp_all_upload_speed_limit {
             if let Some(old) = self.udp_all_upload_speed_limit.clone() {
                 old.update(config);
@@ -318,6 +339,7 @@ impl User {
             tcp_conn_rate_limit,
             tcp_all_upload_speed_limit,
             tcp_all_download_speed_limit,
+            tcp_all_egress_speed_limit,
             udp_all_upload_speed_limit,
             udp_all_download_speed_limit,
             ingress_net_filter: None,
@@ -673,6 +695,11 @@ impl User {
         self.tcp_all_download_speed_limit.as_ref()
     }
 
+    #[inline]
+    pub(crate) fn tcp_all_egress_speed_limit(&self) -> Option<&Arc<GlobalStreamLimiter>> {
+        self.tcp_all_egress_speed_limit.as_ref()
+    }
+
     #[inline]
     pub(crate) fn udp_all_upload_speed_limit(&self) -> Option<&Arc<GlobalDatagramLimiter>> {
         self.udp_all_upload_speed_limit.as_ref()
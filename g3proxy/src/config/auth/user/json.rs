@@ -90,6 +90,13 @@ impl UserConfig {
                 self.tcp_all_download_speed_limit = Some(limit);
                 Ok(())
             }
+            "tcp_all_egress_speed_limit" => {
+                let limit = g3_json::value::as_global_stream_speed_limit(v).context(format!(
+                    "invalid global stream speed limit config value for key {k}"
+                ))?;
+                self.tcp_all_egress_speed_limit = Some(limit);
+                Ok(())
+            }
             "udp_all_upload_speed_limit" => {
                 let limit = g3_json::value::as_global_datagram_speed_limit(v).context(format!(
                     "invalid global datagram speed limit config value for key {k}"
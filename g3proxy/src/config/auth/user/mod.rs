@@ -63,6 +63,7 @@ pub(crate) struct UserConfig {
     pub(crate) udp_sock_speed_limit: UdpSockSpeedLimitConfig,
     pub(crate) tcp_all_upload_speed_limit: Option<GlobalStreamSpeedLimitConfig>,
     pub(crate) tcp_all_download_speed_limit: Option<GlobalStreamSpeedLimitConfig>,
+    pub(crate) tcp_all_egress_speed_limit: Option<GlobalStreamSpeedLimitConfig>,
     pub(crate) udp_all_upload_speed_limit: Option<GlobalDatagramSpeedLimitConfig>,
     pub(crate) udp_all_download_speed_limit: Option<GlobalDatagramSpeedLimitConfig>,
     pub(crate) log_rate_limit: Option<RateLimitQuotaConfig>,
@@ -103,6 +104,7 @@ impl Default for UserConfig {
             udp_sock_speed_limit: Default::default(),
             tcp_all_upload_speed_limit: None,
             tcp_all_download_speed_limit: None,
+            tcp_all_egress_speed_limit: None,
             udp_all_upload_speed_limit: None,
             udp_all_download_speed_limit: None,
             log_rate_limit: None,
@@ -0,0 +1,229 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+use g3_ip_locate::IpLocateServiceConfig;
+use g3_types::acl::AclAction;
+
+/// The result of the egress asn filter check on a single remote ip address
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum EgressAsnCheck {
+    Pass,
+    Deny,
+    LookupFailed(AclAction),
+}
+
+/// Deny egress connections to peers in the listed AS numbers or ISP domains
+#[derive(Clone, Eq, PartialEq)]
+pub(crate) struct EgressAsnFilterConfig {
+    pub(crate) ip_locate_service: IpLocateServiceConfig,
+    pub(crate) deny: BTreeSet<u32>,
+    deny_isp_domains: BTreeSet<String>,
+    pub(crate) on_lookup_failure: AclAction,
+}
+
+impl EgressAsnFilterConfig {
+    pub(crate) fn parse_yaml(value: &Yaml) -> anyhow::Result<Self> {
+        let Yaml::Hash(map) = value else {
+            return Err(anyhow!(
+                "yaml value type for 'egress asn filter config' should be 'map'"
+            ));
+        };
+
+        let mut config = EgressAsnFilterConfig {
+            ip_locate_service: IpLocateServiceConfig::default(),
+            deny: BTreeSet::new(),
+            deny_isp_domains: BTreeSet::new(),
+            // fail closed by default as this is a deny list
+            on_lookup_failure: AclAction::Forbid,
+        };
+        g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+            "ip_locate_service" => {
+                config.ip_locate_service = IpLocateServiceConfig::parse_yaml(v).context(
+                    format!("invalid ip locate service config value for key {k}"),
+                )?;
+                Ok(())
+            }
+            "deny" | "asn" | "as_numbers" => {
+                let all_as = g3_yaml::value::as_list(v, g3_yaml::value::as_u32)
+                    .context(format!("invalid as number list value for key {k}"))?;
+                config.deny.extend(all_as);
+                Ok(())
+            }
+            "isp_domain" | "isp_domains" => {
+                let domains = g3_yaml::value::as_list(v, g3_yaml::value::as_string)
+                    .context(format!("invalid string list value for key {k}"))?;
+                for domain in domains {
+                    let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+                    if domain.is_empty() {
+                        return Err(anyhow!("empty isp domain found in key {k}"));
+                    }
+                    config.deny_isp_domains.insert(domain);
+                }
+                Ok(())
+            }
+            "on_lookup_failure" => {
+                let action = g3_yaml::value::as_string(v)
+                    .context(format!("invalid string value for key {k}"))?;
+                config.on_lookup_failure = AclAction::from_str(&action)
+                    .map_err(|_| anyhow!("invalid acl action {action} for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+        if config.deny.is_empty() && config.deny_isp_domains.is_empty() {
+            return Err(anyhow!("no as number or isp domain set"));
+        }
+        Ok(config)
+    }
+
+    /// Check the AS number and the ISP domain located for a remote ip address.
+    ///
+    /// The *on_lookup_failure* action will be used if any of the values needed by the
+    /// configured rules is missing, and the other one doesn't deny the address.
+    pub(crate) fn check(&self, asn: Option<u32>, isp_domain: Option<&str>) -> EgressAsnCheck {
+        let deny_asn = asn.map(|asn| self.deny.contains(&asn)).unwrap_or(false);
+        let deny_isp_domain = isp_domain
+            .map(|domain| self.deny_isp_domain(domain))
+            .unwrap_or(false);
+        if deny_asn || deny_isp_domain {
+            return EgressAsnCheck::Deny;
+        }
+
+        let asn_missing = !self.deny.is_empty() && asn.is_none();
+        let isp_domain_missing = !self.deny_isp_domains.is_empty() && isp_domain.is_none();
+        if asn_missing || isp_domain_missing {
+            EgressAsnCheck::LookupFailed(self.on_lookup_failure)
+        } else {
+            EgressAsnCheck::Pass
+        }
+    }
+
+    /// Check if the isp domain is one of the denied domains or their sub domains
+    pub(crate) fn deny_isp_domain(&self, domain: &str) -> bool {
+        if self.deny_isp_domains.is_empty() {
+            return false;
+        }
+        let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+        let mut s = domain.as_str();
+        loop {
+            if self.deny_isp_domains.contains(s) {
+                return true;
+            }
+            match s.split_once('.') {
+                Some((_, parent)) => s = parent,
+                None => return false,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    fn parse(s: &str) -> anyhow::Result<EgressAsnFilterConfig> {
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        EgressAsnFilterConfig::parse_yaml(&docs[0])
+    }
+
+    #[test]
+    fn check_asn() {
+        let config = parse("deny: [64496, 64497]").unwrap();
+        assert_eq!(config.check(Some(64496), None), EgressAsnCheck::Deny);
+        assert_eq!(
+            config.check(Some(64497), Some("a.net")),
+            EgressAsnCheck::Deny
+        );
+        assert_eq!(config.check(Some(64498), None), EgressAsnCheck::Pass);
+        assert_eq!(
+            config.check(None, None),
+            EgressAsnCheck::LookupFailed(AclAction::Forbid)
+        );
+        // the isp domain is not needed
+        assert_eq!(config.check(Some(64498), None), EgressAsnCheck::Pass);
+    }
+
+    #[test]
+    fn check_isp_domain() {
+        let config = parse(
+            r#"
+            isp_domain: [Example.NET., example.org]
+            on_lookup_failure: permit
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.check(None, Some("example.net")),
+            EgressAsnCheck::Deny
+        );
+        assert_eq!(
+            config.check(Some(64496), Some("cdn.example.org.")),
+            EgressAsnCheck::Deny
+        );
+        assert_eq!(
+            config.check(None, Some("example.com")),
+            EgressAsnCheck::Pass
+        );
+        assert_eq!(config.check(None, Some("net")), EgressAsnCheck::Pass);
+        assert_eq!(
+            config.check(Some(64496), None),
+            EgressAsnCheck::LookupFailed(AclAction::Permit)
+        );
+    }
+
+    #[test]
+    fn check_mixed() {
+        let config = parse(
+            r#"
+            asn: 64496
+            isp_domains: example.net
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.check(Some(64496), None), EgressAsnCheck::Deny);
+        assert_eq!(
+            config.check(None, Some("a.example.net")),
+            EgressAsnCheck::Deny
+        );
+        assert_eq!(
+            config.check(Some(64497), None),
+            EgressAsnCheck::LookupFailed(AclAction::Forbid)
+        );
+        assert_eq!(
+            config.check(None, Some("example.com")),
+            EgressAsnCheck::LookupFailed(AclAction::Forbid)
+        );
+        assert_eq!(
+            config.check(Some(64497), Some("example.com")),
+            EgressAsnCheck::Pass
+        );
+    }
+
+    #[test]
+    fn parse_err() {
+        assert!(parse("on_lookup_failure: permit").is_err());
+        assert!(parse("isp_domain: \".\"").is_err());
+        assert!(parse("deny: 1\non_lookup_failure: xx").is_err());
+        assert!(parse("[]").is_err());
+    }
+}
@@ -0,0 +1,94 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+/// Circuit breaker config for the connections to the next proxy peers
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct CircuitBreakerConfig {
+    pub(crate) failure_ratio: f64,
+    pub(crate) window: Duration,
+    pub(crate) min_requests: usize,
+    pub(crate) cool_down: Duration,
+    pub(crate) trial_requests: usize,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        CircuitBreakerConfig {
+            failure_ratio: 0.5,
+            window: Duration::from_secs(10),
+            min_requests: 20,
+            cool_down: Duration::from_secs(30),
+            trial_requests: 3,
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+    pub(crate) fn parse_yaml(value: &Yaml) -> anyhow::Result<Self> {
+        let Yaml::Hash(map) = value else {
+            return Err(anyhow!(
+                "yaml value type for 'circuit breaker config' should be 'map'"
+            ));
+        };
+
+        let mut config = CircuitBreakerConfig::default();
+        g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+            "failure_ratio" => {
+                let ratio =
+                    g3_yaml::value::as_f64(v).context(format!("invalid f64 value for key {k}"))?;
+                if ratio <= 0.0 || ratio > 1.0 {
+                    return Err(anyhow!("the failure ratio should be in range (0, 1]"));
+                }
+                config.failure_ratio = ratio;
+                Ok(())
+            }
+            "window" => {
+                config.window = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "min_requests" => {
+                config.min_requests = g3_yaml::value::as_usize(v)
+                    .context(format!("invalid usize value for key {k}"))?;
+                Ok(())
+            }
+            "cool_down" => {
+                config.cool_down = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "trial_requests" => {
+                config.trial_requests = g3_yaml::value::as_usize(v)
+                    .context(format!("invalid usize value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+
+        if config.window.is_zero() {
+            return Err(anyhow!("the window should not be zero"));
+        }
+        if config.trial_requests == 0 {
+            return Err(anyhow!("the trial requests should not be zero"));
+        }
+        Ok(config)
+    }
+}
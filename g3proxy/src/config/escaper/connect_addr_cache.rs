@@ -0,0 +1,70 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+/// Config for the cache of the tcp connect latency to each resolved address of the next proxy peers
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ConnectAddrCacheConfig {
+    pub(crate) expire: Duration,
+    pub(crate) reprobe_ratio: f64,
+}
+
+impl Default for ConnectAddrCacheConfig {
+    fn default() -> Self {
+        ConnectAddrCacheConfig {
+            expire: Duration::from_secs(600),
+            reprobe_ratio: 0.05,
+        }
+    }
+}
+
+impl ConnectAddrCacheConfig {
+    pub(crate) fn parse_yaml(value: &Yaml) -> anyhow::Result<Self> {
+        let Yaml::Hash(map) = value else {
+            return Err(anyhow!(
+                "yaml value type for 'connect addr cache config' should be 'map'"
+            ));
+        };
+
+        let mut config = ConnectAddrCacheConfig::default();
+        g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+            "expire" | "expire_time" | "ttl" => {
+                config.expire = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "reprobe_ratio" => {
+                let ratio =
+                    g3_yaml::value::as_f64(v).context(format!("invalid f64 value for key {k}"))?;
+                if !(0.0..=1.0).contains(&ratio) {
+                    return Err(anyhow!("the reprobe ratio should be in range [0, 1]"));
+                }
+                config.reprobe_ratio = ratio;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+
+        if config.expire.is_zero() {
+            return Err(anyhow!("the expire time should not be zero"));
+        }
+        Ok(config)
+    }
+}
@@ -0,0 +1,184 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+fn as_retry_status(v: &Yaml) -> anyhow::Result<u16> {
+    let code = g3_yaml::value::as_u16(v)?;
+    // only the error responses can be retried
+    if (400..600).contains(&code) {
+        Ok(code)
+    } else {
+        Err(anyhow!("status code {code} is not in range 400-599"))
+    }
+}
+
+/// Retry config for the CONNECT negotiation with the next proxy peers
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct HttpConnectRetryConfig {
+    pub(crate) max_retries: usize,
+    pub(crate) retry_status: BTreeSet<u16>,
+    pub(crate) initial_backoff: Duration,
+    pub(crate) max_backoff: Duration,
+}
+
+impl Default for HttpConnectRetryConfig {
+    fn default() -> Self {
+        HttpConnectRetryConfig {
+            max_retries: 2,
+            retry_status: BTreeSet::from([502, 503]),
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+impl HttpConnectRetryConfig {
+    pub(crate) fn parse_yaml(value: &Yaml) -> anyhow::Result<Self> {
+        let Yaml::Hash(map) = value else {
+            return Err(anyhow!(
+                "yaml value type for 'http connect retry config' should be 'map'"
+            ));
+        };
+
+        let mut config = HttpConnectRetryConfig::default();
+        g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+            "max_retries" => {
+                config.max_retries = g3_yaml::value::as_usize(v)
+                    .context(format!("invalid usize value for key {k}"))?;
+                Ok(())
+            }
+            "retry_status" => {
+                config.retry_status.clear();
+                if let Yaml::Array(seq) = v {
+                    for (i, v) in seq.iter().enumerate() {
+                        let code = as_retry_status(v)
+                            .context(format!("invalid retry status value for {k}#{i}"))?;
+                        config.retry_status.insert(code);
+                    }
+                } else {
+                    let code = as_retry_status(v)
+                        .context(format!("invalid retry status value for key {k}"))?;
+                    config.retry_status.insert(code);
+                }
+                Ok(())
+            }
+            "initial_backoff" => {
+                config.initial_backoff = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "max_backoff" => {
+                config.max_backoff = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+
+        if config.retry_status.is_empty() {
+            return Err(anyhow!("no retry status set"));
+        }
+        if config.max_backoff < config.initial_backoff {
+            config.max_backoff = config.initial_backoff;
+        }
+        Ok(config)
+    }
+
+    #[inline]
+    pub(crate) fn is_retryable(&self, code: u16) -> bool {
+        self.retry_status.contains(&code)
+    }
+
+    /// Get the jittered backoff time before the next retry, `retry` is the count of retries already done.
+    pub(crate) fn backoff(&self, retry: usize) -> Duration {
+        let shift = retry.min(16) as u32;
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(1 << shift)
+            .min(self.max_backoff);
+        // use equal jitter: half fixed, half random
+        let half = backoff / 2;
+        let jitter_nanos = fastrand::u64(0..=(half.as_nanos() as u64));
+        half + Duration::from_nanos(jitter_nanos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    fn parse(s: &str) -> anyhow::Result<HttpConnectRetryConfig> {
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        HttpConnectRetryConfig::parse_yaml(&docs[0])
+    }
+
+    #[test]
+    fn parse_ok() {
+        let config = parse(
+            "{max_retries: 3, retry_status: [502, 504], initial_backoff: 50ms, max_backoff: 2s}",
+        )
+        .unwrap();
+        assert_eq!(config.max_retries, 3);
+        assert!(config.is_retryable(502));
+        assert!(config.is_retryable(504));
+        assert!(!config.is_retryable(503));
+        assert_eq!(config.initial_backoff, Duration::from_millis(50));
+        assert_eq!(config.max_backoff, Duration::from_secs(2));
+
+        let config = parse("{retry_status: 429}").unwrap();
+        assert!(config.is_retryable(429));
+        assert!(!config.is_retryable(502));
+
+        let config = parse("{initial_backoff: 2s, max_backoff: 1s}").unwrap();
+        assert_eq!(config.max_backoff, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn parse_err() {
+        assert!(parse("{retry_status: []}").is_err());
+        assert!(parse("{retry_status: 200}").is_err());
+        assert!(parse("{retry_status: [502, 399]}").is_err());
+        assert!(parse("{retry_status: 600}").is_err());
+        assert!(parse("{retry_status: 70000}").is_err());
+        assert!(parse("{unknown: 1}").is_err());
+        assert!(parse("[]").is_err());
+    }
+
+    #[test]
+    fn backoff() {
+        let config = HttpConnectRetryConfig {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(300),
+            ..Default::default()
+        };
+        for _ in 0..100 {
+            let b0 = config.backoff(0);
+            assert!(b0 >= Duration::from_millis(50) && b0 <= Duration::from_millis(100));
+            let b1 = config.backoff(1);
+            assert!(b1 >= Duration::from_millis(100) && b1 <= Duration::from_millis(200));
+            let b5 = config.backoff(5);
+            assert!(b5 >= Duration::from_millis(150) && b5 <= Duration::from_millis(300));
+            let b_max = config.backoff(usize::MAX);
+            assert!(b_max <= Duration::from_millis(300));
+        }
+    }
+}
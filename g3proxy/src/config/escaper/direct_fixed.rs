@@ -29,7 +29,12 @@ use g3_types::net::{HappyEyeballsConfig, TcpKeepAliveConfig, TcpMiscSockOpts, Ud
 use g3_types::resolve::{QueryStrategy, ResolveRedirectionBuilder, ResolveStrategy};
 use g3_yaml::YamlDocPosition;
 
-use super::{AnyEscaperConfig, EscaperConfig, EscaperConfigDiffAction, GeneralEscaperConfig};
+#[cfg(target_os = "linux")]
+use super::Ipv6FlowLabelConfig;
+use super::{
+    AnyEscaperConfig, EgressAsnFilterConfig, EscaperConfig, EscaperConfigDiffAction,
+    GeneralEscaperConfig,
+};
 
 const ESCAPER_CONFIG_TYPE: &str = "DirectFixed";
 
@@ -48,10 +53,13 @@ pub(crate) struct DirectFixedEscaperConfig {
     pub(crate) resolve_strategy: ResolveStrategy,
     pub(crate) resolve_redirection: Option<ResolveRedirectionBuilder>,
     pub(crate) egress_net_filter: AclNetworkRuleBuilder,
+    pub(crate) egress_asn_filter: Option<EgressAsnFilterConfig>,
     pub(crate) general: GeneralEscaperConfig,
     pub(crate) happy_eyeballs: HappyEyeballsConfig,
     pub(crate) tcp_keepalive: TcpKeepAliveConfig,
     pub(crate) tcp_misc_opts: TcpMiscSockOpts,
+    #[cfg(target_os = "linux")]
+    pub(crate) ipv6_flow_label: Option<Ipv6FlowLabelConfig>,
     pub(crate) udp_misc_opts: UdpMiscSockOpts,
     pub(crate) enable_path_selection: bool,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
@@ -73,10 +81,13 @@ impl DirectFixedEscaperConfig {
             resolve_strategy: Default::default(),
             resolve_redirection: None,
             egress_net_filter: AclNetworkRuleBuilder::new_egress(AclAction::Permit),
+            egress_asn_filter: None,
             general: Default::default(),
             happy_eyeballs: Default::default(),
             tcp_keepalive: Default::default(),
             tcp_misc_opts: Default::default(),
+            #[cfg(target_os = "linux")]
+            ipv6_flow_label: None,
             udp_misc_opts: Default::default(),
             enable_path_selection: false,
             extra_metrics_tags: None,
@@ -152,6 +163,12 @@ impl DirectFixedEscaperConfig {
                     .context(format!("invalid network acl rule value for key {k}"))?;
                 Ok(())
             }
+            "egress_asn_filter" => {
+                let filter = EgressAsnFilterConfig::parse_yaml(v)
+                    .context(format!("invalid egress asn filter value for key {k}"))?;
+                self.egress_asn_filter = Some(filter);
+                Ok(())
+            }
             "tcp_sock_speed_limit" | "tcp_conn_speed_limit" | "tcp_conn_limit" => {
                 self.general.tcp_sock_speed_limit = g3_yaml::value::as_tcp_sock_speed_limit(v)
                     .context(format!("invalid tcp conn socket limit value for key {k}"))?;
@@ -172,6 +189,13 @@ impl DirectFixedEscaperConfig {
                     .context(format!("invalid tcp misc sock opts value for key {k}"))?;
                 Ok(())
             }
+            #[cfg(target_os = "linux")]
+            "ipv6_flow_label" => {
+                let flow_label = Ipv6FlowLabelConfig::parse_yaml(v)
+                    .context(format!("invalid ipv6 flow label value for key {k}"))?;
+                self.ipv6_flow_label = Some(flow_label);
+                Ok(())
+            }
             "udp_misc_opts" => {
                 self.udp_misc_opts = g3_yaml::value::as_udp_misc_sock_opts(v)
                     .context(format!("invalid udp misc sock opts value for key {k}"))?;
@@ -190,6 +214,9 @@ impl DirectFixedEscaperConfig {
                     .context(format!("invalid tcp connect value for key {k}"))?;
                 Ok(())
             }
+            "tls_handshake_success_log_ratio" => {
+                self.general.tls_handshake_log.set_success_ratio(k, v)
+            }
             "happy_eyeballs" => {
                 self.happy_eyeballs = g3_yaml::value::as_happy_eyeballs_config(v)
                     .context(format!("invalid happy eyeballs config value for key {k}"))?;
@@ -203,6 +230,7 @@ impl DirectFixedEscaperConfig {
         if self.name.is_empty() {
             return Err(anyhow!("name is not set"));
         }
+        super::check_tcp_sock_mark(&self.name, &self.tcp_misc_opts);
         if self.resolver.is_empty() {
             return Err(anyhow!("resolver is not set"));
         }
@@ -221,6 +249,14 @@ impl DirectFixedEscaperConfig {
             }
         }
 
+        #[cfg(target_os = "linux")]
+        if self.ipv6_flow_label.is_some() && self.no_ipv6 {
+            log::warn!(
+                "escaper {}: ipv6 flow label is set but ipv6 is disabled, it will be ignored",
+                self.name
+            );
+        }
+
         Ok(())
     }
 
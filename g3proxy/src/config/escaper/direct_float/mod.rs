@@ -183,6 +183,9 @@ impl DirectFloatEscaperConfig {
                     .context(format!("invalid tcp connect value for key {k}"))?;
                 Ok(())
             }
+            "tls_handshake_success_log_ratio" => {
+                self.general.tls_handshake_log.set_success_ratio(k, v)
+            }
             "happy_eyeballs" => {
                 self.happy_eyeballs = g3_yaml::value::as_happy_eyeballs_config(v)
                     .context(format!("invalid happy eyeballs config value for key {k}"))?;
@@ -211,6 +214,7 @@ impl DirectFloatEscaperConfig {
         if self.name.is_empty() {
             return Err(anyhow!("name is not set"));
         }
+        super::check_tcp_sock_mark(&self.name, &self.tcp_misc_opts);
         if self.resolver.is_empty() {
             return Err(anyhow!("resolver is not set"));
         }
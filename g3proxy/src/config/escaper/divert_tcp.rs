@@ -177,6 +177,9 @@ impl DivertTcpEscaperConfig {
                     .context(format!("invalid tcp connect value for key {k}"))?;
                 Ok(())
             }
+            "tls_handshake_success_log_ratio" => {
+                self.general.tls_handshake_log.set_success_ratio(k, v)
+            }
             "happy_eyeballs" => {
                 self.happy_eyeballs = g3_yaml::value::as_happy_eyeballs_config(v)
                     .context(format!("invalid happy eyeballs config value for key {k}"))?;
@@ -190,6 +193,7 @@ impl DivertTcpEscaperConfig {
         if self.name.is_empty() {
             return Err(anyhow!("name is not set"));
         }
+        super::check_tcp_sock_mark(&self.name, &self.tcp_misc_opts);
         if self.proxy_nodes.is_empty() {
             return Err(anyhow!("proxy addr is not set"));
         }
@@ -0,0 +1,202 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context};
+use rand::distributions::{Bernoulli, Distribution};
+use yaml_rust::Yaml;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum FaultKind {
+    ConnectRefused,
+    ConnectTimeout,
+    NegotiationTimeout,
+    BadStatus(u16),
+}
+
+impl FromStr for FaultKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "connect_refused" => Ok(FaultKind::ConnectRefused),
+            "connect_timeout" => Ok(FaultKind::ConnectTimeout),
+            "negotiation_timeout" => Ok(FaultKind::NegotiationTimeout),
+            "bad_status" => Ok(FaultKind::BadStatus(503)),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct FaultInjectionRule {
+    fault: FaultKind,
+    ratio: Bernoulli,
+}
+
+impl FaultInjectionRule {
+    fn parse_yaml(value: &Yaml) -> anyhow::Result<Self> {
+        let Yaml::Hash(map) = value else {
+            return Err(anyhow!(
+                "yaml value type for 'fault injection rule' should be 'map'"
+            ));
+        };
+
+        let mut fault = None;
+        let mut status = None;
+        let mut ratio = Bernoulli::new(1.0).unwrap();
+        g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+            "fault" => {
+                let s = g3_yaml::value::as_string(v)
+                    .context(format!("invalid string value for key {k}"))?;
+                let kind =
+                    FaultKind::from_str(&s).map_err(|_| anyhow!("unsupported fault kind {s}"))?;
+                fault = Some(kind);
+                Ok(())
+            }
+            "status" | "status_code" => {
+                let code =
+                    g3_yaml::value::as_u16(v).context(format!("invalid u16 value for key {k}"))?;
+                if !(300..600).contains(&code) {
+                    return Err(anyhow!("the status code should be in range [300, 600)"));
+                }
+                status = Some(code);
+                Ok(())
+            }
+            "ratio" | "probability" => {
+                ratio = g3_yaml::value::as_random_ratio(v)
+                    .context(format!("invalid random ratio value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+
+        let Some(mut fault) = fault else {
+            return Err(anyhow!("no fault kind set"));
+        };
+        if let Some(code) = status {
+            let FaultKind::BadStatus(c) = &mut fault else {
+                return Err(anyhow!("status code is only allowed for bad_status fault"));
+            };
+            *c = code;
+        }
+        Ok(FaultInjectionRule { fault, ratio })
+    }
+}
+
+/// Inject faults into the connection setup, only for testing of the error handling paths
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct FaultInjectionConfig {
+    rules: Vec<FaultInjectionRule>,
+}
+
+impl FaultInjectionConfig {
+    pub(crate) fn parse_yaml(value: &Yaml) -> anyhow::Result<Self> {
+        let mut config = FaultInjectionConfig::default();
+        match value {
+            Yaml::Array(seq) => {
+                for (i, v) in seq.iter().enumerate() {
+                    let rule = FaultInjectionRule::parse_yaml(v)
+                        .context(format!("invalid fault injection rule #{i}"))?;
+                    config.rules.push(rule);
+                }
+            }
+            _ => {
+                let rule = FaultInjectionRule::parse_yaml(value)?;
+                config.rules.push(rule);
+            }
+        }
+        Ok(config)
+    }
+
+    /// Select the fault to inject, the rules will be checked in order
+    pub(crate) fn sample(&self) -> Option<FaultKind> {
+        let mut rng = rand::thread_rng();
+        self.rules
+            .iter()
+            .find(|rule| rule.ratio.sample(&mut rng))
+            .map(|rule| rule.fault)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    fn parse(s: &str) -> anyhow::Result<FaultInjectionConfig> {
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        FaultInjectionConfig::parse_yaml(&docs[0])
+    }
+
+    #[test]
+    fn parse_ok() {
+        let config = parse("fault: connect_refused").unwrap();
+        assert_eq!(config.rules.len(), 1);
+        assert_eq!(config.rules[0].fault, FaultKind::ConnectRefused);
+
+        let config = parse(
+            r#"
+            - fault: connect_timeout
+              ratio: 0.1
+            - fault: negotiation_timeout
+            - fault: bad_status
+            - fault: bad_status
+              status: 429
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.rules.len(), 4);
+        assert_eq!(config.rules[0].fault, FaultKind::ConnectTimeout);
+        assert_eq!(config.rules[0].ratio, Bernoulli::new(0.1).unwrap());
+        assert_eq!(config.rules[1].fault, FaultKind::NegotiationTimeout);
+        assert_eq!(config.rules[2].fault, FaultKind::BadStatus(503));
+        assert_eq!(config.rules[3].fault, FaultKind::BadStatus(429));
+    }
+
+    #[test]
+    fn parse_err() {
+        assert!(parse("fault: unknown").is_err());
+        assert!(parse("ratio: 0.5").is_err());
+        assert!(parse("{fault: bad_status, status: 200}").is_err());
+        assert!(parse("{fault: connect_refused, status: 503}").is_err());
+        assert!(parse("{fault: connect_refused, foo: bar}").is_err());
+        assert!(parse("- fault: connect_refused\n- fault: unknown").is_err());
+    }
+
+    #[test]
+    fn sample() {
+        let config = parse(
+            r#"
+            - fault: connect_refused
+              ratio: 0
+            - fault: bad_status
+              ratio: 1
+            "#,
+        )
+        .unwrap();
+        for _ in 0..16 {
+            assert_eq!(config.sample(), Some(FaultKind::BadStatus(503)));
+        }
+
+        let config = parse("{fault: connect_refused, ratio: 0}").unwrap();
+        assert_eq!(config.sample(), None);
+
+        let config = FaultInjectionConfig::default();
+        assert_eq!(config.sample(), None);
+    }
+}
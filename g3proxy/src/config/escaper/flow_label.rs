@@ -0,0 +1,117 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::anyhow;
+use uuid::Uuid;
+use yaml_rust::Yaml;
+
+const IPV6_FLOW_LABEL_MASK: u32 = 0x000f_ffff;
+
+/// IPv6 flow label to set on the outbound sockets
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Ipv6FlowLabelConfig {
+    Fixed(u32),
+    TaskHash,
+}
+
+impl Ipv6FlowLabelConfig {
+    pub(crate) fn parse_yaml(value: &Yaml) -> anyhow::Result<Self> {
+        match value {
+            Yaml::Integer(_) => {
+                let label = g3_yaml::value::as_u32(value)?;
+                if label == 0 || label > IPV6_FLOW_LABEL_MASK {
+                    return Err(anyhow!(
+                        "flow label should be in range 1-{IPV6_FLOW_LABEL_MASK}"
+                    ));
+                }
+                Ok(Ipv6FlowLabelConfig::Fixed(label))
+            }
+            Yaml::String(s) => match g3_yaml::key::normalize(s).as_str() {
+                "task_hash" | "hash" => Ok(Ipv6FlowLabelConfig::TaskHash),
+                _ => Err(anyhow!("invalid flow label mode {s}")),
+            },
+            _ => Err(anyhow!(
+                "yaml value type for 'ipv6 flow label' should be 'int' or 'string'"
+            )),
+        }
+    }
+
+    pub(crate) fn label_for_task(&self, task_id: &Uuid) -> u32 {
+        match self {
+            Ipv6FlowLabelConfig::Fixed(label) => *label,
+            Ipv6FlowLabelConfig::TaskHash => {
+                let v = task_id.as_u128();
+                let v = (v as u64) ^ ((v >> 64) as u64);
+                let v = (v as u32) ^ ((v >> 32) as u32);
+                let label = (v ^ (v >> 20)) & IPV6_FLOW_LABEL_MASK;
+                // 0 means no flow label
+                if label == 0 {
+                    1
+                } else {
+                    label
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    fn parse(s: &str) -> anyhow::Result<Ipv6FlowLabelConfig> {
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        Ipv6FlowLabelConfig::parse_yaml(&docs[0])
+    }
+
+    #[test]
+    fn parse_ok() {
+        assert_eq!(parse("1").unwrap(), Ipv6FlowLabelConfig::Fixed(1));
+        assert_eq!(
+            parse("1048575").unwrap(),
+            Ipv6FlowLabelConfig::Fixed(IPV6_FLOW_LABEL_MASK)
+        );
+        assert_eq!(parse("task_hash").unwrap(), Ipv6FlowLabelConfig::TaskHash);
+        assert_eq!(parse("Task-Hash").unwrap(), Ipv6FlowLabelConfig::TaskHash);
+    }
+
+    #[test]
+    fn parse_err() {
+        assert!(parse("0").is_err());
+        assert!(parse("1048576").is_err());
+        assert!(parse("-1").is_err());
+        assert!(parse("random").is_err());
+        assert!(parse("[]").is_err());
+    }
+
+    #[test]
+    fn label_for_task() {
+        let fixed = Ipv6FlowLabelConfig::Fixed(0x12345);
+        assert_eq!(fixed.label_for_task(&Uuid::new_v4()), 0x12345);
+
+        let hash = Ipv6FlowLabelConfig::TaskHash;
+        let id = Uuid::new_v4();
+        let label = hash.label_for_task(&id);
+        assert_eq!(label, hash.label_for_task(&id));
+        assert!(label > 0 && label <= IPV6_FLOW_LABEL_MASK);
+        assert_eq!(hash.label_for_task(&Uuid::nil()), 1);
+        for _ in 0..100 {
+            let label = hash.label_for_task(&Uuid::new_v4());
+            assert!(label > 0 && label <= IPV6_FLOW_LABEL_MASK);
+        }
+    }
+}
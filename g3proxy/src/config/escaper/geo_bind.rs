@@ -0,0 +1,265 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::{yaml, Yaml};
+
+use g3_geoip_types::{ContinentCode, IpLocation, IsoCountryCode};
+use g3_ip_locate::IpLocateServiceConfig;
+
+/// Select the local bind ip by the ip location of the upstream address
+#[derive(Clone, Default, Eq, PartialEq)]
+pub(crate) struct GeoBindConfig {
+    pub(crate) ip_locate_service: IpLocateServiceConfig,
+    country_rules: BTreeMap<IsoCountryCode, Vec<IpAddr>>,
+    continent_rules: BTreeMap<ContinentCode, Vec<IpAddr>>,
+}
+
+impl GeoBindConfig {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.country_rules.is_empty() && self.continent_rules.is_empty()
+    }
+
+    pub(crate) fn parse_yaml(value: &Yaml) -> anyhow::Result<Self> {
+        let Yaml::Hash(map) = value else {
+            return Err(anyhow!(
+                "yaml value type for 'geo bind config' should be 'map'"
+            ));
+        };
+
+        let mut config = GeoBindConfig::default();
+        g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+            "ip_locate_service" => {
+                config.ip_locate_service = IpLocateServiceConfig::parse_yaml(v).context(
+                    format!("invalid ip locate service config value for key {k}"),
+                )?;
+                Ok(())
+            }
+            "rules" => {
+                if let Yaml::Array(seq) = v {
+                    for (i, rule) in seq.iter().enumerate() {
+                        if let Yaml::Hash(map) = rule {
+                            config
+                                .add_rule(map)
+                                .context(format!("invalid value for {k}#{i}"))?;
+                        } else {
+                            return Err(anyhow!("invalid value type for {k}#{i}"));
+                        }
+                    }
+                    Ok(())
+                } else {
+                    Err(anyhow!("invalid array value for key {k}"))
+                }
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+        if config.is_empty() {
+            return Err(anyhow!("no rules set"));
+        }
+        Ok(config)
+    }
+
+    fn add_rule(&mut self, map: &yaml::Hash) -> anyhow::Result<()> {
+        let mut countries = Vec::new();
+        let mut continents = Vec::new();
+        let mut bind_ips = Vec::new();
+        g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+            "country" | "countries" => {
+                countries = g3_yaml::value::as_list(v, g3_yaml::value::as_iso_country_code)
+                    .context(format!("invalid iso country code list value for key {k}"))?;
+                Ok(())
+            }
+            "continent" | "continents" => {
+                continents = g3_yaml::value::as_list(v, g3_yaml::value::as_continent_code)
+                    .context(format!("invalid continent code list value for key {k}"))?;
+                Ok(())
+            }
+            "bind_ip" | "bind_ips" => {
+                bind_ips = g3_yaml::value::as_list(v, g3_yaml::value::as_ipaddr)
+                    .context(format!("invalid ip address list value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+        if bind_ips.is_empty() {
+            return Err(anyhow!("no bind ip set"));
+        }
+        for ip in &bind_ips {
+            if ip.is_unspecified() || ip.is_multicast() {
+                return Err(anyhow!("{ip} is not a valid bind ip"));
+            }
+        }
+        if countries.is_empty() && continents.is_empty() {
+            return Err(anyhow!("no country or continent set"));
+        }
+        for country in countries {
+            if self
+                .country_rules
+                .insert(country, bind_ips.clone())
+                .is_some()
+            {
+                return Err(anyhow!("found duplicated country {country}"));
+            }
+        }
+        for continent in continents {
+            if self
+                .continent_rules
+                .insert(continent, bind_ips.clone())
+                .is_some()
+            {
+                return Err(anyhow!("found duplicated continent {continent}"));
+            }
+        }
+        Ok(())
+    }
+
+    fn select_from(ips: &[IpAddr], peer_ip: IpAddr) -> Option<IpAddr> {
+        let mut matched = ips
+            .iter()
+            .filter(|ip| ip.is_ipv4() == peer_ip.is_ipv4())
+            .copied();
+        let count = matched.clone().count();
+        match count {
+            0 => None,
+            1 => matched.next(),
+            _ => matched.nth(fastrand::usize(0..count)),
+        }
+    }
+
+    /// Select a bind ip by the ip location of the upstream address.
+    ///
+    /// Country rules take precedence over continent rules, and only ips of the
+    /// same address family as `peer_ip` will be selected.
+    pub(crate) fn select_bind_ip(&self, location: &IpLocation, peer_ip: IpAddr) -> Option<IpAddr> {
+        if let Some(ips) = location
+            .country()
+            .and_then(|country| self.country_rules.get(&country))
+        {
+            if let Some(ip) = GeoBindConfig::select_from(ips, peer_ip) {
+                return Some(ip);
+            }
+        }
+
+        location
+            .continent()
+            .and_then(|continent| self.continent_rules.get(&continent))
+            .and_then(|ips| GeoBindConfig::select_from(ips, peer_ip))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use yaml_rust::YamlLoader;
+
+    fn parse(s: &str) -> anyhow::Result<GeoBindConfig> {
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        GeoBindConfig::parse_yaml(&docs[0])
+    }
+
+    fn location(s: &str) -> IpLocation {
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        g3_yaml::value::as_ip_location(&docs[0]).unwrap()
+    }
+
+    #[test]
+    fn parse_and_select() {
+        let config = parse(
+            r#"
+            rules:
+              - country: CN
+                bind_ip:
+                  - 192.168.1.1
+                  - 2001:db8::1
+              - continent: [EU, NA]
+                bind_ip: 192.168.2.1
+            "#,
+        )
+        .unwrap();
+        assert!(!config.is_empty());
+
+        let v4_peer = IpAddr::from_str("10.0.0.1").unwrap();
+        let v6_peer = IpAddr::from_str("2001:db8::100").unwrap();
+
+        let cn = location("{net: 1.0.1.0/24, country: CN}");
+        assert_eq!(
+            config.select_bind_ip(&cn, v4_peer),
+            Some(IpAddr::from_str("192.168.1.1").unwrap())
+        );
+        assert_eq!(
+            config.select_bind_ip(&cn, v6_peer),
+            Some(IpAddr::from_str("2001:db8::1").unwrap())
+        );
+
+        // continent rules are used if no country rule matched
+        let de = location("{net: 2.0.0.0/24, country: DE}");
+        assert_eq!(
+            config.select_bind_ip(&de, v4_peer),
+            Some(IpAddr::from_str("192.168.2.1").unwrap())
+        );
+        assert_eq!(config.select_bind_ip(&de, v6_peer), None);
+
+        let jp = location("{net: 1.0.16.0/24, country: JP}");
+        assert_eq!(config.select_bind_ip(&jp, v4_peer), None);
+        let unknown = location("{net: 3.0.0.0/24}");
+        assert_eq!(config.select_bind_ip(&unknown, v4_peer), None);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(parse("rules: []").is_err());
+
+        assert!(parse(
+            r#"
+            rules:
+              - country: CN
+            "#
+        )
+        .is_err());
+
+        assert!(parse(
+            r#"
+            rules:
+              - bind_ip: 192.168.1.1
+            "#
+        )
+        .is_err());
+
+        assert!(parse(
+            r#"
+            rules:
+              - country: CN
+                bind_ip: 0.0.0.0
+            "#
+        )
+        .is_err());
+
+        assert!(parse(
+            r#"
+            rules:
+              - country: CN
+                bind_ip: 192.168.1.1
+              - country: [US, CN]
+                bind_ip: 192.168.1.2
+            "#
+        )
+        .is_err());
+    }
+}
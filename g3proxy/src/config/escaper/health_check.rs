@@ -0,0 +1,93 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+use g3_types::net::UpstreamAddr;
+
+/// Health check config for the next proxy peers
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct ProxyHealthCheckConfig {
+    pub(crate) target: UpstreamAddr,
+    pub(crate) interval: Duration,
+    pub(crate) timeout: Duration,
+    pub(crate) failure_threshold: usize,
+    pub(crate) remove_unhealthy: bool,
+}
+
+impl Default for ProxyHealthCheckConfig {
+    fn default() -> Self {
+        ProxyHealthCheckConfig {
+            target: UpstreamAddr::empty(),
+            interval: Duration::from_secs(30),
+            timeout: Duration::from_secs(10),
+            failure_threshold: 3,
+            remove_unhealthy: false,
+        }
+    }
+}
+
+impl ProxyHealthCheckConfig {
+    pub(crate) fn parse_yaml(value: &Yaml) -> anyhow::Result<Self> {
+        let Yaml::Hash(map) = value else {
+            return Err(anyhow!(
+                "yaml value type for 'proxy health check config' should be 'map'"
+            ));
+        };
+
+        let mut config = ProxyHealthCheckConfig::default();
+        g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+            "target" => {
+                config.target = g3_yaml::value::as_upstream_addr(v, 443)
+                    .context(format!("invalid upstream addr value for key {k}"))?;
+                Ok(())
+            }
+            "interval" => {
+                config.interval = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "timeout" => {
+                config.timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "failure_threshold" => {
+                config.failure_threshold = g3_yaml::value::as_nonzero_usize(v)
+                    .context(format!("invalid nonzero usize value for key {k}"))?
+                    .get();
+                Ok(())
+            }
+            "remove_unhealthy" => {
+                config.remove_unhealthy = g3_yaml::value::as_bool(v)
+                    .context(format!("invalid bool value for key {k}"))?;
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+
+        if config.target.is_empty() {
+            return Err(anyhow!("no health check target set"));
+        }
+        if config.interval.is_zero() {
+            return Err(anyhow!("health check interval should not be zero"));
+        }
+        Ok(config)
+    }
+}
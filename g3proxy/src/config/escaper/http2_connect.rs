@@ -0,0 +1,63 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+use g3_types::net::{Host, OpensslClientConfigBuilder};
+
+/// Config for tunneling by HTTP/2 CONNECT streams over a shared connection to the next proxy
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Http2ConnectConfig {
+    pub(crate) tls_client: Option<OpensslClientConfigBuilder>,
+    pub(crate) tls_name: Option<Host>,
+}
+
+impl Http2ConnectConfig {
+    pub(crate) fn parse_yaml(value: &Yaml, lookup_dir: Option<&Path>) -> anyhow::Result<Self> {
+        match value {
+            Yaml::Boolean(true) => Ok(Http2ConnectConfig::default()),
+            Yaml::Hash(map) => {
+                let mut config = Http2ConnectConfig::default();
+                g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                    "tls" | "tls_client" => {
+                        let builder = g3_yaml::value::as_to_one_openssl_tls_client_config_builder(
+                            v, lookup_dir,
+                        )
+                        .context(format!(
+                            "invalid openssl tls client config value for key {k}"
+                        ))?;
+                        config.tls_client = Some(builder);
+                        Ok(())
+                    }
+                    "tls_name" => {
+                        let name = g3_yaml::value::as_host(v)
+                            .context(format!("invalid tls server name value for key {k}"))?;
+                        config.tls_name = Some(name);
+                        Ok(())
+                    }
+                    _ => Err(anyhow!("invalid key {k}")),
+                })?;
+                Ok(config)
+            }
+            _ => Err(anyhow!(
+                "yaml value type for 'http2 connect config' should be 'true' or 'map'"
+            )),
+        }
+    }
+}
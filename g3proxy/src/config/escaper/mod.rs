@@ -19,12 +19,18 @@ use std::path::Path;
 use std::sync::Arc;
 
 use anyhow::anyhow;
+#[cfg(target_os = "linux")]
+use log::debug;
+#[cfg(not(target_os = "linux"))]
+use log::warn;
 use slog::Logger;
 use yaml_rust::{yaml, Yaml};
 
 use g3_daemon::config::TopoMap;
 use g3_types::metrics::NodeName;
-use g3_types::net::{TcpConnectConfig, TcpSockSpeedLimitConfig, UdpSockSpeedLimitConfig};
+use g3_types::net::{
+    TcpConnectConfig, TcpMiscSockOpts, TcpSockSpeedLimitConfig, UdpSockSpeedLimitConfig,
+};
 use g3_yaml::{HybridParser, YamlDocPosition};
 
 pub(crate) mod comply_audit;
@@ -53,9 +59,60 @@ pub(crate) use registry::clear;
 mod verify;
 use verify::EscaperConfigVerifier;
 
+mod geo_bind;
+pub(crate) use geo_bind::GeoBindConfig;
+
+mod asn_filter;
+pub(crate) use asn_filter::{EgressAsnCheck, EgressAsnFilterConfig};
+
+mod health_check;
+pub(crate) use health_check::ProxyHealthCheckConfig;
+
+mod connect_retry;
+pub(crate) use connect_retry::HttpConnectRetryConfig;
+
+mod circuit_breaker;
+pub(crate) use circuit_breaker::CircuitBreakerConfig;
+
+mod connect_addr_cache;
+pub(crate) use connect_addr_cache::ConnectAddrCacheConfig;
+
+#[cfg(feature = "fault-injection")]
+mod fault_injection;
+#[cfg(feature = "fault-injection")]
+pub(crate) use fault_injection::{FaultInjectionConfig, FaultKind};
+
+mod http2_connect;
+pub(crate) use http2_connect::Http2ConnectConfig;
+
+mod peer_feed;
+pub(crate) use peer_feed::{PeerFeedConfig, PeerFeedSource};
+
+mod socket_buffer;
+pub(crate) use socket_buffer::as_tcp_socket_buffer_config;
+
+mod tls_handshake_log;
+pub(crate) use tls_handshake_log::TlsHandshakeLogConfig;
+
+#[cfg(target_os = "linux")]
+mod flow_label;
+#[cfg(target_os = "linux")]
+pub(crate) use flow_label::Ipv6FlowLabelConfig;
+
 const CONFIG_KEY_ESCAPER_TYPE: &str = "type";
 const CONFIG_KEY_ESCAPER_NAME: &str = "name";
 
+fn check_tcp_sock_mark(name: &NodeName, misc_opts: &TcpMiscSockOpts) {
+    if let Some(mark) = misc_opts.netfilter_mark {
+        #[cfg(target_os = "linux")]
+        debug!("escaper {name}: SO_MARK {mark} will be set on outgoing tcp sockets");
+        #[cfg(not(target_os = "linux"))]
+        warn!(
+            "escaper {name}: SO_MARK {mark} is not supported on this platform and will be ignored"
+        );
+    }
+}
+
 pub(crate) enum EscaperConfigDiffAction {
     NoAction,
     SpawnNew,
@@ -92,6 +149,7 @@ pub(crate) struct GeneralEscaperConfig {
     pub(crate) tcp_sock_speed_limit: TcpSockSpeedLimitConfig,
     pub(crate) udp_sock_speed_limit: UdpSockSpeedLimitConfig,
     pub(crate) tcp_connect: TcpConnectConfig,
+    pub(crate) tls_handshake_log: TlsHandshakeLogConfig,
 }
 
 #[derive(Clone)]
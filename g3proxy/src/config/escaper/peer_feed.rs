@@ -0,0 +1,170 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::SocketAddr;
+use std::path::Path;
+use std::str::FromStr;
+#[cfg(feature = "hickory")]
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use url::Url;
+use yaml_rust::Yaml;
+
+use g3_types::net::{Host, UpstreamAddr};
+
+use crate::escape::TlsClientIdentities;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum PeerFeedSource {
+    /// fetch by HTTP GET, the response body should be the msgpack encoded peer list
+    Http(Url),
+    /// query by a single UDP datagram, the reply should be the msgpack encoded peer list
+    Udp(SocketAddr),
+    /// query the SRV records of the domain proxy addrs from the dns server,
+    /// with the optional service labels prepended to the domain
+    #[cfg(feature = "hickory")]
+    Srv(SocketAddr, Option<Arc<str>>),
+}
+
+impl PeerFeedSource {
+    fn parse_url(url: &Url) -> anyhow::Result<Self> {
+        match url.scheme() {
+            "http" => {
+                if url.host().is_none() {
+                    return Err(anyhow!("no host set in http url"));
+                }
+                Ok(PeerFeedSource::Http(url.clone()))
+            }
+            "udp" => {
+                let addr =
+                    UpstreamAddr::try_from(url).map_err(|e| anyhow!("invalid udp address: {e}"))?;
+                let Host::Ip(ip) = addr.host() else {
+                    return Err(anyhow!("the host of udp address should be an ip address"));
+                };
+                if addr.port() == 0 {
+                    return Err(anyhow!("port is required for udp address"));
+                }
+                Ok(PeerFeedSource::Udp(SocketAddr::new(*ip, addr.port())))
+            }
+            #[cfg(feature = "hickory")]
+            "srv" => {
+                let addr =
+                    UpstreamAddr::try_from(url).map_err(|e| anyhow!("invalid dns server: {e}"))?;
+                let Host::Ip(ip) = addr.host() else {
+                    return Err(anyhow!("the host of dns server should be an ip address"));
+                };
+                let port = if addr.port() == 0 { 53 } else { addr.port() };
+                let service = url.path().trim_matches(|c| c == '/' || c == '.');
+                let service = if service.is_empty() {
+                    None
+                } else {
+                    Some(Arc::from(service))
+                };
+                Ok(PeerFeedSource::Srv(SocketAddr::new(*ip, port), service))
+            }
+            s => Err(anyhow!("unsupported url scheme {s}")),
+        }
+    }
+}
+
+/// Config for fetching the next proxy peers from an external msgpack feed
+#[derive(Clone, PartialEq)]
+pub(crate) struct PeerFeedConfig {
+    pub(crate) source: PeerFeedSource,
+    pub(crate) refresh_interval: Duration,
+    pub(crate) fetch_timeout: Duration,
+    pub(crate) max_body_size: usize,
+    pub(crate) tls_client_identities: TlsClientIdentities,
+}
+
+impl PeerFeedConfig {
+    fn new(source: PeerFeedSource) -> Self {
+        PeerFeedConfig {
+            source,
+            refresh_interval: Duration::from_secs(60),
+            fetch_timeout: Duration::from_secs(10),
+            max_body_size: 1 << 20,
+            tls_client_identities: TlsClientIdentities::default(),
+        }
+    }
+
+    pub(crate) fn parse_yaml(value: &Yaml, lookup_dir: Option<&Path>) -> anyhow::Result<Self> {
+        match value {
+            Yaml::String(s) => {
+                let url = Url::from_str(s).map_err(|e| anyhow!("invalid url string: {e}"))?;
+                let source = PeerFeedSource::parse_url(&url)?;
+                Ok(PeerFeedConfig::new(source))
+            }
+            Yaml::Hash(map) => {
+                let v = map
+                    .iter()
+                    .find_map(|(k, v)| match k {
+                        Yaml::String(k) if g3_yaml::key::normalize(k) == "url" => Some(v),
+                        _ => None,
+                    })
+                    .ok_or_else(|| anyhow!("no url set"))?;
+                let url = g3_yaml::value::as_url(v).context("invalid url value for key url")?;
+                let source = PeerFeedSource::parse_url(&url)?;
+
+                let mut config = PeerFeedConfig::new(source);
+                g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                    "url" => Ok(()),
+                    "refresh_interval" | "interval" => {
+                        config.refresh_interval = g3_yaml::humanize::as_duration(v)
+                            .context(format!("invalid humanize duration value for key {k}"))?;
+                        Ok(())
+                    }
+                    "fetch_timeout" | "timeout" => {
+                        config.fetch_timeout = g3_yaml::humanize::as_duration(v)
+                            .context(format!("invalid humanize duration value for key {k}"))?;
+                        Ok(())
+                    }
+                    "max_body_size" => {
+                        config.max_body_size = g3_yaml::humanize::as_usize(v)
+                            .context(format!("invalid humanize usize value for key {k}"))?;
+                        Ok(())
+                    }
+                    "tls_client_identities" => config
+                        .tls_client_identities
+                        .set_identities_by_yaml(v, lookup_dir)
+                        .context(format!("invalid tls client identities value for key {k}")),
+                    _ => Err(anyhow!("invalid key {k}")),
+                })?;
+
+                config.check()?;
+                Ok(config)
+            }
+            _ => Err(anyhow!(
+                "yaml value type for 'peer feed config' should be 'string' or 'map'"
+            )),
+        }
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        if self.refresh_interval.is_zero() {
+            return Err(anyhow!("refresh interval should not be zero"));
+        }
+        if self.fetch_timeout.is_zero() {
+            return Err(anyhow!("fetch timeout should not be zero"));
+        }
+        if self.max_body_size == 0 {
+            return Err(anyhow!("max body size should not be zero"));
+        }
+        Ok(())
+    }
+}
@@ -17,6 +17,7 @@
 use std::collections::BTreeSet;
 use std::net::IpAddr;
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -33,13 +34,39 @@ use g3_types::net::{
 };
 use g3_yaml::YamlDocPosition;
 
-use super::{AnyEscaperConfig, EscaperConfig, EscaperConfigDiffAction};
+use super::{AnyEscaperConfig, EscaperConfig, EscaperConfigDiffAction, TlsHandshakeLogConfig};
 
 pub(crate) mod source;
 pub(crate) use source::ProxyFloatSource;
 
+mod prewarm;
+pub(crate) use prewarm::ProxyFloatPrewarmConfig;
+
 const ESCAPER_CONFIG_TYPE: &str = "ProxyFloat";
 
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) enum ProxyFloatPeerPickPolicy {
+    #[default]
+    Random,
+    RoundRobin,
+    LeastConnection,
+}
+
+impl FromStr for ProxyFloatPeerPickPolicy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "random" => Ok(ProxyFloatPeerPickPolicy::Random),
+            "round_robin" | "roundrobin" | "rr" => Ok(ProxyFloatPeerPickPolicy::RoundRobin),
+            "least_connection" | "least_conn" | "leastconn" => {
+                Ok(ProxyFloatPeerPickPolicy::LeastConnection)
+            }
+            _ => Err(()),
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq)]
 pub(crate) struct ProxyFloatEscaperConfig {
     pub(crate) name: NodeName,
@@ -59,6 +86,9 @@ pub(crate) struct ProxyFloatEscaperConfig {
     pub(crate) udp_misc_opts: UdpMiscSockOpts,
     pub(crate) expire_guard_duration: chrono::Duration,
     pub(crate) peer_negotiation_timeout: Duration,
+    pub(crate) peer_pick_policy: ProxyFloatPeerPickPolicy,
+    pub(crate) prewarm: Option<ProxyFloatPrewarmConfig>,
+    pub(crate) tls_handshake_log: TlsHandshakeLogConfig,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
 }
 
@@ -82,6 +112,9 @@ impl ProxyFloatEscaperConfig {
             udp_misc_opts: Default::default(),
             expire_guard_duration: chrono::Duration::seconds(5),
             peer_negotiation_timeout: Duration::from_secs(10),
+            peer_pick_policy: ProxyFloatPeerPickPolicy::default(),
+            prewarm: None,
+            tls_handshake_log: TlsHandshakeLogConfig::default(),
             extra_metrics_tags: None,
         }
     }
@@ -145,6 +178,7 @@ impl ProxyFloatEscaperConfig {
                 self.tls_config = builder;
                 Ok(())
             }
+            "tls_handshake_success_log_ratio" => self.tls_handshake_log.set_success_ratio(k, v),
             "source" => {
                 self.source = ProxyFloatSource::parse(v, self.position.as_ref())
                     .context(format!("invalid value for key {k}"))?;
@@ -195,6 +229,22 @@ impl ProxyFloatEscaperConfig {
                     .context(format!("invalid humanize duration value for key {k}"))?;
                 Ok(())
             }
+            "peer_pick_policy" => {
+                let s = g3_yaml::value::as_string(v)?;
+                self.peer_pick_policy = ProxyFloatPeerPickPolicy::from_str(&s)
+                    .map_err(|_| anyhow!("invalid peer pick policy {s}"))?;
+                Ok(())
+            }
+            "prewarm" => {
+                if v.is_null() {
+                    self.prewarm = None;
+                } else {
+                    let config = ProxyFloatPrewarmConfig::parse_yaml(v)
+                        .context(format!("invalid prewarm config value for key {k}"))?;
+                    self.prewarm = Some(config);
+                }
+                Ok(())
+            }
             _ => Err(anyhow!("invalid key {k}")),
         }
     }
@@ -203,6 +253,7 @@ impl ProxyFloatEscaperConfig {
         if self.name.is_empty() {
             return Err(anyhow!("name is not set"));
         }
+        super::check_tcp_sock_mark(&self.name, &self.tcp_misc_opts);
         if self.source.need_local_cache() && self.cache_file.is_none() {
             warn!(
                 "It is very recommended to set local cache for escaper {}",
@@ -0,0 +1,120 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+/// Config for the idle connections opened in advance to newly added peers
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct ProxyFloatPrewarmConfig {
+    pub(crate) count: usize,
+    pub(crate) idle_timeout: Duration,
+}
+
+impl Default for ProxyFloatPrewarmConfig {
+    fn default() -> Self {
+        ProxyFloatPrewarmConfig {
+            count: 2,
+            idle_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ProxyFloatPrewarmConfig {
+    pub(super) fn parse_yaml(value: &Yaml) -> anyhow::Result<Self> {
+        let mut config = ProxyFloatPrewarmConfig::default();
+        match value {
+            Yaml::Hash(map) => {
+                g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                    "count" => {
+                        config.count = g3_yaml::value::as_usize(v)
+                            .context(format!("invalid usize value for key {k}"))?;
+                        Ok(())
+                    }
+                    "idle_timeout" => {
+                        config.idle_timeout = g3_yaml::humanize::as_duration(v)
+                            .context(format!("invalid humanize duration value for key {k}"))?;
+                        Ok(())
+                    }
+                    _ => Err(anyhow!("invalid key {k}")),
+                })?;
+            }
+            Yaml::Integer(_) => {
+                config.count = g3_yaml::value::as_usize(value)
+                    .context("invalid usize value for prewarm count")?;
+            }
+            _ => {
+                return Err(anyhow!(
+                    "yaml value type for 'proxy float prewarm config' should be 'map' or 'usize'"
+                ))
+            }
+        }
+
+        if config.count == 0 {
+            return Err(anyhow!("prewarm count should not be zero"));
+        }
+        if config.idle_timeout.is_zero() {
+            return Err(anyhow!("prewarm idle timeout should not be zero"));
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn parse_ok() {
+        let yaml = YamlLoader::load_from_str("count: 4\nidle_timeout: 1m").unwrap();
+        let config = ProxyFloatPrewarmConfig::parse_yaml(&yaml[0]).unwrap();
+        assert_eq!(config.count, 4);
+        assert_eq!(config.idle_timeout, Duration::from_secs(60));
+
+        let yaml = YamlLoader::load_from_str("idle-timeout: 10s").unwrap();
+        let config = ProxyFloatPrewarmConfig::parse_yaml(&yaml[0]).unwrap();
+        assert_eq!(config.count, 2);
+        assert_eq!(config.idle_timeout, Duration::from_secs(10));
+
+        let yaml = Yaml::Integer(8);
+        let config = ProxyFloatPrewarmConfig::parse_yaml(&yaml).unwrap();
+        assert_eq!(config.count, 8);
+        assert_eq!(config.idle_timeout, Duration::from_secs(30));
+    }
+
+    #[test]
+    fn parse_err() {
+        assert!(ProxyFloatPrewarmConfig::parse_yaml(&Yaml::Integer(0)).is_err());
+        assert!(ProxyFloatPrewarmConfig::parse_yaml(&Yaml::Integer(-1)).is_err());
+        assert!(ProxyFloatPrewarmConfig::parse_yaml(&Yaml::Boolean(true)).is_err());
+
+        for s in [
+            "count: 0",
+            "idle_timeout: 0s",
+            "idle_timeout: abc",
+            "unknown: 1",
+        ] {
+            let yaml = YamlLoader::load_from_str(s).unwrap();
+            assert!(
+                ProxyFloatPrewarmConfig::parse_yaml(&yaml[0]).is_err(),
+                "{s}"
+            );
+        }
+    }
+}
@@ -15,6 +15,8 @@
  */
 
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+#[cfg(unix)]
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -28,13 +30,21 @@ use g3_types::metrics::{NodeName, StaticMetricsTags};
 #[cfg(any(target_os = "linux", target_os = "android"))]
 use g3_types::net::InterfaceName;
 use g3_types::net::{
-    HappyEyeballsConfig, Host, HttpForwardCapability, ProxyProtocolVersion, TcpKeepAliveConfig,
-    TcpMiscSockOpts, WeightedUpstreamAddr,
+    HappyEyeballsConfig, Host, HttpForwardCapability, PortRange, ProxyProtocolVersion,
+    SocketBufferConfig, TcpKeepAliveConfig, TcpMiscSockOpts, WeightedUpstreamAddr,
 };
 use g3_types::resolve::{QueryStrategy, ResolveStrategy};
 use g3_yaml::YamlDocPosition;
 
-use super::{AnyEscaperConfig, EscaperConfig, EscaperConfigDiffAction, GeneralEscaperConfig};
+#[cfg(feature = "fault-injection")]
+use super::FaultInjectionConfig;
+#[cfg(target_os = "linux")]
+use super::Ipv6FlowLabelConfig;
+use super::{
+    AnyEscaperConfig, CircuitBreakerConfig, ConnectAddrCacheConfig, EscaperConfig,
+    EscaperConfigDiffAction, GeneralEscaperConfig, GeoBindConfig, Http2ConnectConfig,
+    HttpConnectRetryConfig, PeerFeedConfig, ProxyHealthCheckConfig,
+};
 
 const ESCAPER_CONFIG_TYPE: &str = "ProxyHttp";
 
@@ -44,13 +54,18 @@ pub(crate) struct ProxyHttpEscaperConfig {
     position: Option<YamlDocPosition>,
     pub(crate) shared_logger: Option<AsciiString>,
     pub(crate) proxy_nodes: Vec<WeightedUpstreamAddr>,
+    #[cfg(unix)]
+    pub(crate) proxy_unix_path: Option<PathBuf>,
     pub(crate) proxy_pick_policy: SelectivePickPolicy,
+    pub(crate) proxy_pick_least_rtt: bool,
     proxy_username: Username,
     proxy_password: Password,
     #[cfg(any(target_os = "linux", target_os = "android"))]
     pub(crate) bind_interface: Option<InterfaceName>,
     pub(crate) bind_v4: Option<Ipv4Addr>,
     pub(crate) bind_v6: Option<Ipv6Addr>,
+    pub(crate) local_port_range: Option<PortRange>,
+    pub(crate) geo_bind: GeoBindConfig,
     pub(crate) no_ipv4: bool,
     pub(crate) no_ipv6: bool,
     pub(crate) resolver: NodeName,
@@ -60,11 +75,29 @@ pub(crate) struct ProxyHttpEscaperConfig {
     pub(crate) http_forward_capability: HttpForwardCapability,
     pub(crate) tcp_keepalive: TcpKeepAliveConfig,
     pub(crate) tcp_misc_opts: TcpMiscSockOpts,
+    pub(crate) tcp_socket_buffer: SocketBufferConfig,
+    #[cfg(target_os = "linux")]
+    pub(crate) enable_mptcp: bool,
+    #[cfg(target_os = "linux")]
+    pub(crate) ipv6_flow_label: Option<Ipv6FlowLabelConfig>,
     pub(crate) http_connect_rsp_hdr_max_size: usize,
+    pub(crate) http_connect_rsp_reject_body: bool,
     pub(crate) append_http_headers: Vec<String>,
     pub(crate) pass_proxy_userid: bool,
     pub(crate) use_proxy_protocol: Option<ProxyProtocolVersion>,
     pub(crate) peer_negotiation_timeout: Duration,
+    pub(crate) upstream_tls_handshake_timeout: Option<Duration>,
+    pub(crate) max_concurrent_negotiations: usize,
+    pub(crate) max_tunnels_per_peer: usize,
+    pub(crate) peer_tunnel_wait_timeout: Duration,
+    pub(crate) connect_retry: Option<HttpConnectRetryConfig>,
+    pub(crate) health_check: Option<ProxyHealthCheckConfig>,
+    pub(crate) circuit_breaker: Option<CircuitBreakerConfig>,
+    pub(crate) connect_addr_cache: Option<ConnectAddrCacheConfig>,
+    pub(crate) http2_connect: Option<Http2ConnectConfig>,
+    pub(crate) peer_feed: Option<PeerFeedConfig>,
+    #[cfg(feature = "fault-injection")]
+    pub(crate) fault_injection: Option<FaultInjectionConfig>,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
 }
 
@@ -75,13 +108,18 @@ impl ProxyHttpEscaperConfig {
             position,
             shared_logger: None,
             proxy_nodes: Vec::with_capacity(1),
+            #[cfg(unix)]
+            proxy_unix_path: None,
             proxy_pick_policy: SelectivePickPolicy::Random,
+            proxy_pick_least_rtt: false,
             proxy_username: Username::empty(),
             proxy_password: Password::empty(),
             #[cfg(any(target_os = "linux", target_os = "android"))]
             bind_interface: None,
             bind_v4: None,
             bind_v6: None,
+            local_port_range: None,
+            geo_bind: GeoBindConfig::default(),
             no_ipv4: false,
             no_ipv6: false,
             resolver: NodeName::default(),
@@ -91,11 +129,29 @@ impl ProxyHttpEscaperConfig {
             http_forward_capability: Default::default(),
             tcp_keepalive: Default::default(),
             tcp_misc_opts: Default::default(),
+            tcp_socket_buffer: SocketBufferConfig::default(),
+            #[cfg(target_os = "linux")]
+            enable_mptcp: false,
+            #[cfg(target_os = "linux")]
+            ipv6_flow_label: None,
             http_connect_rsp_hdr_max_size: 4096,
+            http_connect_rsp_reject_body: false,
             append_http_headers: Vec::new(),
             pass_proxy_userid: false,
             use_proxy_protocol: None,
             peer_negotiation_timeout: Duration::from_secs(10),
+            upstream_tls_handshake_timeout: None,
+            max_concurrent_negotiations: 0,
+            max_tunnels_per_peer: 0,
+            peer_tunnel_wait_timeout: Duration::ZERO,
+            connect_retry: None,
+            health_check: None,
+            circuit_breaker: None,
+            connect_addr_cache: None,
+            http2_connect: None,
+            peer_feed: None,
+            #[cfg(feature = "fault-injection")]
+            fault_injection: None,
             extra_metrics_tags: None,
         }
     }
@@ -131,6 +187,21 @@ impl ProxyHttpEscaperConfig {
                 Ok(())
             }
             "proxy_addr" => {
+                #[cfg(unix)]
+                if let Yaml::String(s) = v {
+                    if let Some(path) = s.strip_prefix("unix:") {
+                        let path =
+                            g3_yaml::value::as_absolute_path(&Yaml::String(path.to_string()))
+                                .context(format!("invalid unix socket path value for key {k}"))?;
+                        self.proxy_unix_path = Some(path);
+                        self.proxy_nodes.clear();
+                        return Ok(());
+                    }
+                }
+                #[cfg(unix)]
+                {
+                    self.proxy_unix_path = None;
+                }
                 self.proxy_nodes = g3_yaml::value::as_list(v, |v| {
                     g3_yaml::value::as_weighted_upstream_addr(v, 3128)
                 })
@@ -140,6 +211,16 @@ impl ProxyHttpEscaperConfig {
                 Ok(())
             }
             "proxy_addr_pick_policy" => {
+                if let Yaml::String(s) = v {
+                    if matches!(
+                        g3_yaml::key::normalize(s).as_str(),
+                        "least_rtt" | "least_latency"
+                    ) {
+                        self.proxy_pick_least_rtt = true;
+                        return Ok(());
+                    }
+                }
+                self.proxy_pick_least_rtt = false;
                 self.proxy_pick_policy = g3_yaml::value::as_selective_pick_policy(v)?;
                 Ok(())
             }
@@ -170,6 +251,17 @@ impl ProxyHttpEscaperConfig {
                 self.bind_v6 = Some(ip6);
                 Ok(())
             }
+            "local_port_range" => {
+                let range = g3_yaml::value::as_port_range(v)
+                    .context(format!("invalid port range value for key {k}"))?;
+                self.local_port_range = Some(range);
+                Ok(())
+            }
+            "geo_bind" => {
+                self.geo_bind = GeoBindConfig::parse_yaml(v)
+                    .context(format!("invalid geo bind config value for key {k}"))?;
+                Ok(())
+            }
             "resolver" => {
                 self.resolver = g3_yaml::value::as_metrics_name(v)?;
                 Ok(())
@@ -193,11 +285,29 @@ impl ProxyHttpEscaperConfig {
                     .context(format!("invalid tcp keepalive config value for key {k}"))?;
                 Ok(())
             }
+            "tcp_socket_buffer" => {
+                self.tcp_socket_buffer = super::as_tcp_socket_buffer_config(v).context(format!(
+                    "invalid tcp socket buffer config value for key {k}"
+                ))?;
+                Ok(())
+            }
             "tcp_misc_opts" => {
                 self.tcp_misc_opts = g3_yaml::value::as_tcp_misc_sock_opts(v)
                     .context(format!("invalid tcp misc sock opts value for key {k}"))?;
                 Ok(())
             }
+            #[cfg(target_os = "linux")]
+            "enable_mptcp" => {
+                self.enable_mptcp = g3_yaml::value::as_bool(v)?;
+                Ok(())
+            }
+            #[cfg(target_os = "linux")]
+            "ipv6_flow_label" => {
+                let flow_label = Ipv6FlowLabelConfig::parse_yaml(v)
+                    .context(format!("invalid ipv6 flow label value for key {k}"))?;
+                self.ipv6_flow_label = Some(flow_label);
+                Ok(())
+            }
             "no_ipv4" => {
                 self.no_ipv4 = g3_yaml::value::as_bool(v)?;
                 Ok(())
@@ -211,6 +321,9 @@ impl ProxyHttpEscaperConfig {
                     .context(format!("invalid tcp connect value for key {k}"))?;
                 Ok(())
             }
+            "tls_handshake_success_log_ratio" => {
+                self.general.tls_handshake_log.set_success_ratio(k, v)
+            }
             "happy_eyeballs" => {
                 self.happy_eyeballs = g3_yaml::value::as_happy_eyeballs_config(v)
                     .context(format!("invalid happy eyeballs config value for key {k}"))?;
@@ -221,6 +334,11 @@ impl ProxyHttpEscaperConfig {
                     .context(format!("invalid humanize usize value for key {k}"))?;
                 Ok(())
             }
+            "http_connect_rsp_reject_body" => {
+                self.http_connect_rsp_reject_body = g3_yaml::value::as_bool(v)
+                    .context(format!("invalid bool value for key {k}"))?;
+                Ok(())
+            }
             "pass_proxy_userid" => {
                 self.pass_proxy_userid = g3_yaml::value::as_bool(v)
                     .context(format!("invalid bool value for key {k}"))?;
@@ -237,6 +355,83 @@ impl ProxyHttpEscaperConfig {
                     .context(format!("invalid humanize duration value for key {k}"))?;
                 Ok(())
             }
+            "upstream_tls_handshake_timeout" => {
+                let timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                self.upstream_tls_handshake_timeout = Some(timeout);
+                Ok(())
+            }
+            "max_concurrent_negotiations" => {
+                self.max_concurrent_negotiations = g3_yaml::value::as_usize(v)
+                    .context(format!("invalid usize value for key {k}"))?;
+                Ok(())
+            }
+            "max_tunnels_per_peer" => {
+                self.max_tunnels_per_peer = g3_yaml::value::as_usize(v)
+                    .context(format!("invalid usize value for key {k}"))?;
+                Ok(())
+            }
+            "peer_tunnel_wait_timeout" => {
+                self.peer_tunnel_wait_timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                Ok(())
+            }
+            "connect_retry" => {
+                let config = HttpConnectRetryConfig::parse_yaml(v).context(format!(
+                    "invalid http connect retry config value for key {k}"
+                ))?;
+                self.connect_retry = Some(config);
+                Ok(())
+            }
+            "health_check" => {
+                let config = ProxyHealthCheckConfig::parse_yaml(v).context(format!(
+                    "invalid proxy health check config value for key {k}"
+                ))?;
+                self.health_check = Some(config);
+                Ok(())
+            }
+            "circuit_breaker" => {
+                let config = CircuitBreakerConfig::parse_yaml(v)
+                    .context(format!("invalid circuit breaker config value for key {k}"))?;
+                self.circuit_breaker = Some(config);
+                Ok(())
+            }
+            "connect_addr_cache" => {
+                if let Yaml::Boolean(enable) = v {
+                    self.connect_addr_cache = enable.then(ConnectAddrCacheConfig::default);
+                    return Ok(());
+                }
+                let config = ConnectAddrCacheConfig::parse_yaml(v).context(format!(
+                    "invalid connect addr cache config value for key {k}"
+                ))?;
+                self.connect_addr_cache = Some(config);
+                Ok(())
+            }
+            "http2_connect" => {
+                if let Yaml::Boolean(false) = v {
+                    self.http2_connect = None;
+                    return Ok(());
+                }
+                let lookup_dir = g3_daemon::config::get_lookup_dir(self.position.as_ref())?;
+                let config = Http2ConnectConfig::parse_yaml(v, Some(lookup_dir))
+                    .context(format!("invalid http2 connect config value for key {k}"))?;
+                self.http2_connect = Some(config);
+                Ok(())
+            }
+            "peer_feed" => {
+                let lookup_dir = g3_daemon::config::get_lookup_dir(self.position.as_ref())?;
+                let config = PeerFeedConfig::parse_yaml(v, Some(lookup_dir))
+                    .context(format!("invalid peer feed config value for key {k}"))?;
+                self.peer_feed = Some(config);
+                Ok(())
+            }
+            #[cfg(feature = "fault-injection")]
+            "fault_injection" => {
+                let config = FaultInjectionConfig::parse_yaml(v)
+                    .context(format!("invalid fault injection config value for key {k}"))?;
+                self.fault_injection = Some(config);
+                Ok(())
+            }
             _ => Err(anyhow!("invalid key {k}")),
         }
     }
@@ -245,7 +440,92 @@ impl ProxyHttpEscaperConfig {
         if self.name.is_empty() {
             return Err(anyhow!("name is not set"));
         }
-        if self.proxy_nodes.is_empty() {
+        super::check_tcp_sock_mark(&self.name, &self.tcp_misc_opts);
+        #[cfg(feature = "fault-injection")]
+        if self.fault_injection.is_some() {
+            log::warn!(
+                "escaper {}: fault injection is enabled, it should only be used for testing",
+                self.name
+            );
+        }
+        #[cfg(unix)]
+        if self.proxy_unix_path.is_some() {
+            self.check_proxy_unix_path()?;
+        } else {
+            self.check_proxy_nodes()?;
+        }
+        #[cfg(not(unix))]
+        self.check_proxy_nodes()?;
+        self.check_http2_connect()?;
+        self.check_proxy_auth()
+    }
+
+    fn check_http2_connect(&self) -> anyhow::Result<()> {
+        if self.http2_connect.is_none() {
+            return Ok(());
+        }
+        // the tunnels share the h2 connection, so per task headers are not possible
+        if self.pass_proxy_userid {
+            return Err(anyhow!(
+                "pass proxy userid is not supported with http2 connect"
+            ));
+        }
+        if self.max_tunnels_per_peer > 0 {
+            return Err(anyhow!(
+                "max tunnels per peer is not supported with http2 connect"
+            ));
+        }
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn check_proxy_unix_path(&self) -> anyhow::Result<()> {
+        if self.use_proxy_protocol.is_some() {
+            return Err(anyhow!(
+                "proxy protocol is not supported for unix socket proxy addr"
+            ));
+        }
+        if self.health_check.is_some() {
+            return Err(anyhow!(
+                "health check is not supported for unix socket proxy addr"
+            ));
+        }
+        if self.max_tunnels_per_peer > 0 {
+            return Err(anyhow!(
+                "max tunnels per peer is not supported for unix socket proxy addr"
+            ));
+        }
+        if self.peer_feed.is_some() {
+            return Err(anyhow!(
+                "peer feed is not supported for unix socket proxy addr"
+            ));
+        }
+        if !self.geo_bind.is_empty() {
+            return Err(anyhow!(
+                "geo bind is not supported for unix socket proxy addr"
+            ));
+        }
+        if let Some(c) = &self.http2_connect {
+            if c.tls_client.is_some() && c.tls_name.is_none() {
+                return Err(anyhow!(
+                    "tls name should be set for http2 connect to unix socket proxy addr"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_proxy_nodes(&mut self) -> anyhow::Result<()> {
+        if self.peer_feed.is_some() {
+            if self.health_check.is_some() {
+                return Err(anyhow!("health check is not supported with peer feed"));
+            }
+            if self.max_tunnels_per_peer > 0 {
+                return Err(anyhow!(
+                    "max tunnels per peer is not supported with peer feed"
+                ));
+            }
+        } else if self.proxy_nodes.is_empty() {
             return Err(anyhow!("proxy addr is not set"));
         }
         self.proxy_nodes.reverse(); // reverse as we push to the back
@@ -253,9 +533,22 @@ impl ProxyHttpEscaperConfig {
             return Err(anyhow!("both ipv4 and ipv6 are disabled"));
         }
 
-        let mut disable_ipv4 = true;
-        let mut disable_ipv6 = true;
+        // the address family of the feed peers is not known in advance
+        let mut disable_ipv4 = self.peer_feed.is_none();
+        let mut disable_ipv6 = self.peer_feed.is_none();
         let mut check_resolver = false;
+        #[cfg(feature = "hickory")]
+        if let Some(feed) = &self.peer_feed {
+            // the srv records are queried for the domain proxy addrs
+            if matches!(feed.source, super::PeerFeedSource::Srv(..))
+                && !self
+                    .proxy_nodes
+                    .iter()
+                    .any(|node| matches!(node.inner().host(), Host::Domain(_)))
+            {
+                return Err(anyhow!("no domain proxy addr set for srv peer feed"));
+            }
+        }
         for node in &self.proxy_nodes {
             match node.inner().host() {
                 Host::Domain(_) => {
@@ -298,7 +591,10 @@ impl ProxyHttpEscaperConfig {
                 }
             }
         }
+        Ok(())
+    }
 
+    fn check_proxy_auth(&mut self) -> anyhow::Result<()> {
         if !self.proxy_username.is_empty() {
             if self.pass_proxy_userid {
                 return Err(anyhow!(
@@ -350,3 +646,63 @@ impl EscaperConfig for ProxyHttpEscaperConfig {
         self.shared_logger.as_ref().map(|s| s.as_str())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    fn parse(s: &str) -> anyhow::Result<ProxyHttpEscaperConfig> {
+        let doc = YamlLoader::load_from_str(s).unwrap();
+        let Yaml::Hash(map) = &doc[0] else {
+            unreachable!()
+        };
+        ProxyHttpEscaperConfig::parse(map, None)
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn unix_proxy_addr() {
+        let config = parse("name: test\nproxy_addr: unix:/run/proxy.sock\n").unwrap();
+        assert!(config.proxy_unix_path.is_some());
+        assert!(config.proxy_nodes.is_empty());
+
+        let config = parse(
+            "name: test\nproxy_addr: unix:/run/proxy.sock\nproxy_username: u\nproxy_password: p\n",
+        )
+        .unwrap();
+        assert_eq!(config.append_http_headers.len(), 1);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn unix_proxy_addr_err() {
+        assert!(
+            parse("name: test\nproxy_addr: unix:/run/proxy.sock\nuse_proxy_protocol: 1\n").is_err()
+        );
+        assert!(
+            parse("name: test\nproxy_addr: unix:/run/proxy.sock\nmax_tunnels_per_peer: 8\n")
+                .is_err()
+        );
+        assert!(parse(
+            "name: test\nproxy_addr: unix:/run/proxy.sock\nproxy_username: u\npass_proxy_userid: true\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn http2_connect() {
+        let config =
+            parse("name: test\nproxy_addr: 127.0.0.1:3128\nhttp2_connect: true\n").unwrap();
+        assert!(config.http2_connect.is_some());
+
+        assert!(parse(
+            "name: test\nproxy_addr: 127.0.0.1:3128\nhttp2_connect: true\npass_proxy_userid: true\n"
+        )
+        .is_err());
+        assert!(parse(
+            "name: test\nproxy_addr: 127.0.0.1:3128\nhttp2_connect: true\nmax_tunnels_per_peer: 8\n"
+        )
+        .is_err());
+    }
+}
@@ -35,6 +35,7 @@ use g3_types::resolve::{QueryStrategy, ResolveStrategy};
 use g3_yaml::YamlDocPosition;
 
 use super::{AnyEscaperConfig, EscaperConfig, EscaperConfigDiffAction, GeneralEscaperConfig};
+use crate::escape::TlsClientIdentities;
 
 const ESCAPER_CONFIG_TYPE: &str = "ProxyHttps";
 
@@ -55,6 +56,7 @@ pub(crate) struct ProxyHttpsEscaperConfig {
     pub(crate) no_ipv6: bool,
     pub(crate) tls_config: OpensslClientConfigBuilder,
     pub(crate) tls_name: Option<Host>,
+    pub(crate) tls_client_identities: TlsClientIdentities,
     pub(crate) resolver: NodeName,
     pub(crate) resolve_strategy: ResolveStrategy,
     pub(crate) general: GeneralEscaperConfig,
@@ -63,10 +65,13 @@ pub(crate) struct ProxyHttpsEscaperConfig {
     pub(crate) tcp_keepalive: TcpKeepAliveConfig,
     pub(crate) tcp_misc_opts: TcpMiscSockOpts,
     pub(crate) http_connect_rsp_hdr_max_size: usize,
+    pub(crate) http_connect_rsp_reject_body: bool,
     pub(crate) append_http_headers: Vec<String>,
     pub(crate) pass_proxy_userid: bool,
     pub(crate) use_proxy_protocol: Option<ProxyProtocolVersion>,
     pub(crate) peer_negotiation_timeout: Duration,
+    pub(crate) upstream_tls_handshake_timeout: Option<Duration>,
+    pub(crate) tls_early_data: bool,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
 }
 
@@ -88,6 +93,7 @@ impl ProxyHttpsEscaperConfig {
             no_ipv6: false,
             tls_config: OpensslClientConfigBuilder::with_cache_for_many_sites(),
             tls_name: None,
+            tls_client_identities: TlsClientIdentities::default(),
             resolver: NodeName::default(),
             resolve_strategy: Default::default(),
             general: Default::default(),
@@ -96,10 +102,13 @@ impl ProxyHttpsEscaperConfig {
             tcp_keepalive: Default::default(),
             tcp_misc_opts: Default::default(),
             http_connect_rsp_hdr_max_size: 4096,
+            http_connect_rsp_reject_body: false,
             append_http_headers: Vec::new(),
             pass_proxy_userid: false,
             use_proxy_protocol: None,
             peer_negotiation_timeout: Duration::from_secs(10),
+            upstream_tls_handshake_timeout: None,
+            tls_early_data: false,
             extra_metrics_tags: None,
         }
     }
@@ -227,11 +236,39 @@ impl ProxyHttpsEscaperConfig {
                 self.tls_name = Some(name);
                 Ok(())
             }
+            "tls_client_identities" => {
+                let lookup_dir = g3_daemon::config::get_lookup_dir(self.position.as_ref())?;
+                self.tls_client_identities
+                    .set_identities_by_yaml(v, Some(lookup_dir))
+                    .context(format!("invalid tls client identities value for key {k}"))
+            }
+            "tls_client_identity_pick" => self
+                .tls_client_identities
+                .set_pick_policy_by_yaml(v)
+                .context(format!(
+                    "invalid tls client identity pick value for key {k}"
+                )),
+            "tls_client_identity_hosts" => {
+                self.tls_client_identities
+                    .set_hosts_by_yaml(v)
+                    .context(format!(
+                        "invalid tls client identity hosts value for key {k}"
+                    ))
+            }
+            "tls_client_identity_default" => self
+                .tls_client_identities
+                .set_default_by_yaml(v)
+                .context(format!(
+                    "invalid tls client identity name value for key {k}"
+                )),
             "tcp_connect" => {
                 self.general.tcp_connect = g3_yaml::value::as_tcp_connect_config(v)
                     .context(format!("invalid tcp connect value for key {k}"))?;
                 Ok(())
             }
+            "tls_handshake_success_log_ratio" => {
+                self.general.tls_handshake_log.set_success_ratio(k, v)
+            }
             "happy_eyeballs" => {
                 self.happy_eyeballs = g3_yaml::value::as_happy_eyeballs_config(v)
                     .context(format!("invalid happy eyeballs config value for key {k}"))?;
@@ -242,6 +279,11 @@ impl ProxyHttpsEscaperConfig {
                     .context(format!("invalid humanize usize value for key {k}"))?;
                 Ok(())
             }
+            "http_connect_rsp_reject_body" => {
+                self.http_connect_rsp_reject_body = g3_yaml::value::as_bool(v)
+                    .context(format!("invalid bool value for key {k}"))?;
+                Ok(())
+            }
             "pass_proxy_userid" => {
                 self.pass_proxy_userid = g3_yaml::value::as_bool(v)
                     .context(format!("invalid bool value for key {k}"))?;
@@ -258,6 +300,17 @@ impl ProxyHttpsEscaperConfig {
                     .context(format!("invalid humanize duration value for key {k}"))?;
                 Ok(())
             }
+            "upstream_tls_handshake_timeout" => {
+                let timeout = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                self.upstream_tls_handshake_timeout = Some(timeout);
+                Ok(())
+            }
+            "tls_early_data" | "tls_0rtt" => {
+                self.tls_early_data = g3_yaml::value::as_bool(v)
+                    .context(format!("invalid bool value for key {k}"))?;
+                Ok(())
+            }
             _ => Err(anyhow!("invalid key {k}")),
         }
     }
@@ -266,10 +319,12 @@ impl ProxyHttpsEscaperConfig {
         if self.name.is_empty() {
             return Err(anyhow!("name is not set"));
         }
+        super::check_tcp_sock_mark(&self.name, &self.tcp_misc_opts);
         if self.proxy_nodes.is_empty() {
             return Err(anyhow!("proxy addr is not set"));
         }
         self.proxy_nodes.reverse(); // reverse as we push to the back
+        self.tls_client_identities.check()?;
         if self.no_ipv4 && self.no_ipv6 {
             return Err(anyhow!("both ipv4 and ipv6 are disabled"));
         }
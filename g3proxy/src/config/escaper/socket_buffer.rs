@@ -0,0 +1,149 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+use g3_types::net::SocketBufferConfig;
+
+/// Parse the socket buffer config for the tcp sockets to the next proxy.
+///
+/// Besides the explicit sizes, an *auto* mode is also supported, which sizes both
+/// the send and recv buffers by the configured bandwidth-delay product.
+pub(crate) fn as_tcp_socket_buffer_config(value: &Yaml) -> anyhow::Result<SocketBufferConfig> {
+    if let Yaml::Hash(map) = value {
+        let auto = map.iter().find_map(|(k, v)| match k {
+            Yaml::String(k) if g3_yaml::key::normalize(k) == "auto" => Some(v),
+            _ => None,
+        });
+        if let Some(v) = auto {
+            if map.len() > 1 {
+                return Err(anyhow!(
+                    "no other keys should be set along with the auto key"
+                ));
+            }
+            let size = as_bdp_size(v).context("invalid auto socket buffer config")?;
+            return Ok(SocketBufferConfig::new(size));
+        }
+    }
+    g3_yaml::value::as_socket_buffer_config(value)
+}
+
+fn as_bdp_size(value: &Yaml) -> anyhow::Result<usize> {
+    let Yaml::Hash(map) = value else {
+        // the bdp value can be set directly
+        return g3_yaml::humanize::as_usize(value)
+            .context("invalid humanize usize value for bdp size");
+    };
+
+    let mut bdp: Option<usize> = None;
+    let mut bandwidth: u64 = 0;
+    let mut rtt = Duration::ZERO;
+    g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+        "bdp" => {
+            let size = g3_yaml::humanize::as_usize(v)
+                .context(format!("invalid humanize usize value for key {k}"))?;
+            bdp = Some(size);
+            Ok(())
+        }
+        "bandwidth" => {
+            bandwidth = g3_yaml::humanize::as_u64(v)
+                .context(format!("invalid humanize u64 value for key {k}"))?;
+            Ok(())
+        }
+        "rtt" => {
+            rtt = g3_yaml::humanize::as_duration(v)
+                .context(format!("invalid humanize duration value for key {k}"))?;
+            Ok(())
+        }
+        _ => Err(anyhow!("invalid key {k}")),
+    })?;
+
+    let size = match bdp {
+        Some(size) => {
+            if bandwidth != 0 || !rtt.is_zero() {
+                return Err(anyhow!("bandwidth and rtt should not be set if bdp is set"));
+            }
+            size
+        }
+        None => {
+            if bandwidth == 0 || rtt.is_zero() {
+                return Err(anyhow!(
+                    "both bandwidth and rtt should be set if no bdp set"
+                ));
+            }
+            // bandwidth is in bytes per second
+            let size = (bandwidth as u128) * rtt.as_micros() / 1_000_000;
+            usize::try_from(size).unwrap_or(usize::MAX)
+        }
+    };
+    if size == 0 {
+        return Err(anyhow!("the bdp size should not be zero"));
+    }
+    // the value will be passed to setsockopt as int
+    Ok(size.min(i32::MAX as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    fn parse(s: &str) -> anyhow::Result<SocketBufferConfig> {
+        let doc = YamlLoader::load_from_str(s).unwrap();
+        as_tcp_socket_buffer_config(&doc[0])
+    }
+
+    #[test]
+    fn parse_ok() {
+        let config = parse("auto: 65536").unwrap();
+        assert_eq!(config.send_size(), Some(65536));
+        assert_eq!(config.recv_size(), Some(65536));
+
+        let config = parse("auto:\n  bdp: 1024").unwrap();
+        assert_eq!(config.send_size(), Some(1024));
+        assert_eq!(config.recv_size(), Some(1024));
+
+        let config = parse("auto:\n  bandwidth: 1000000\n  rtt: 100ms").unwrap();
+        assert_eq!(config.send_size(), Some(100_000));
+        assert_eq!(config.recv_size(), Some(100_000));
+
+        let config = parse("recv: 4096\nsend: 8192").unwrap();
+        assert_eq!(config.recv_size(), Some(4096));
+        assert_eq!(config.send_size(), Some(8192));
+
+        let config = parse("4096").unwrap();
+        assert_eq!(config.recv_size(), Some(4096));
+        assert_eq!(config.send_size(), Some(4096));
+    }
+
+    #[test]
+    fn parse_err() {
+        for s in [
+            "auto: 65536\nsend: 4096",
+            "recv: 4096\nauto: 65536",
+            "auto: 0",
+            "auto:\n  bandwidth: 1000000",
+            "auto:\n  rtt: 100ms",
+            "auto:\n  bdp: 1024\n  rtt: 100ms",
+            "auto:\n  unknown: 1",
+        ] {
+            assert!(parse(s).is_err(), "{s}");
+        }
+    }
+}
@@ -0,0 +1,41 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::Context;
+use rand::distributions::{Bernoulli, Distribution};
+use yaml_rust::Yaml;
+
+/// Control the escape log for tls handshakes to the next peer
+#[derive(Clone, Default, Eq, PartialEq)]
+pub(crate) struct TlsHandshakeLogConfig {
+    success_ratio: Option<Bernoulli>,
+}
+
+impl TlsHandshakeLogConfig {
+    pub(crate) fn set_success_ratio(&mut self, k: &str, v: &Yaml) -> anyhow::Result<()> {
+        let ratio = g3_yaml::value::as_random_ratio(v)
+            .context(format!("invalid random ratio value for key {k}"))?;
+        self.success_ratio = Some(ratio);
+        Ok(())
+    }
+
+    /// Whether we should log this successful tls handshake
+    pub(crate) fn sample_success(&self) -> bool {
+        self.success_ratio
+            .map(|ratio| ratio.sample(&mut rand::thread_rng()))
+            .unwrap_or(false)
+    }
+}
@@ -0,0 +1,132 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Context};
+use log::info;
+use yaml_rust::Yaml;
+
+use g3_geoip_types::IpLocation;
+
+/// The ip location entries collected from all config docs, which will only be
+/// installed into the static table after the whole config is loaded
+#[derive(Default)]
+struct StagedTable {
+    locations: Vec<IpLocation>,
+    aggregate: bool,
+    loaded: bool,
+}
+
+static STAGED_TABLE: Mutex<Option<StagedTable>> = Mutex::new(None);
+
+fn parse(v: &Yaml) -> anyhow::Result<(Vec<IpLocation>, bool)> {
+    let mut aggregate = false;
+    let locations = match v {
+        Yaml::Hash(map) => {
+            let mut locations = Vec::new();
+            g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                "entries" | "locations" => {
+                    locations = g3_yaml::value::as_ip_location_list(v)
+                        .context(format!("invalid ip location list value for key {k}"))?;
+                    Ok(())
+                }
+                "aggregate" => {
+                    aggregate = g3_yaml::value::as_bool(v)
+                        .context(format!("invalid bool value for key {k}"))?;
+                    Ok(())
+                }
+                _ => Err(anyhow!("invalid key {k}")),
+            })?;
+            locations
+        }
+        Yaml::Null => Vec::new(),
+        _ => g3_yaml::value::as_ip_location_list(v)?,
+    };
+    Ok((locations, aggregate))
+}
+
+pub(super) fn load(v: &Yaml) -> anyhow::Result<()> {
+    let (locations, aggregate) = parse(v)?;
+
+    let mut staged = STAGED_TABLE.lock().unwrap();
+    let staged = staged.get_or_insert_with(StagedTable::default);
+    staged.locations.extend(locations);
+    staged.aggregate |= aggregate;
+    staged.loaded = true;
+    Ok(())
+}
+
+pub(super) fn begin_load() {
+    *STAGED_TABLE.lock().unwrap() = Some(StagedTable::default());
+}
+
+/// Install the staged table, or clear the static table if it has been removed from the config
+pub(super) fn commit_load() {
+    let Some(staged) = STAGED_TABLE.lock().unwrap().take() else {
+        return;
+    };
+
+    if staged.loaded {
+        let mut locations = staged.locations;
+        if staged.aggregate {
+            let old_count = locations.len();
+            locations = g3_ip_locate::aggregate_ip_locations(locations);
+            info!(
+                "aggregated {old_count} ip location entries into {}",
+                locations.len()
+            );
+        }
+        let count = g3_ip_locate::load_static_table(locations);
+        info!("loaded {count} entries into static ip location table");
+    } else if g3_ip_locate::static_table_entry_count() > 0 {
+        g3_ip_locate::load_static_table(Vec::new());
+        info!("cleared static ip location table");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn parse_ok() {
+        let yaml = YamlLoader::load_from_str(
+            r#"
+            aggregate: true
+            entries:
+              - network: 192.0.2.0/24
+                country: US
+            "#,
+        )
+        .unwrap();
+        let (locations, aggregate) = parse(&yaml[0]).unwrap();
+        assert_eq!(locations.len(), 1);
+        assert!(aggregate);
+
+        let yaml = YamlLoader::load_from_str("~").unwrap();
+        let (locations, aggregate) = parse(&yaml[0]).unwrap();
+        assert!(locations.is_empty());
+        assert!(!aggregate);
+    }
+
+    #[test]
+    fn parse_err() {
+        let yaml = YamlLoader::load_from_str("unknown: 1").unwrap();
+        assert!(parse(&yaml[0]).is_err());
+    }
+}
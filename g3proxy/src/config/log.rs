@@ -47,7 +47,7 @@ pub(crate) fn load(v: &Yaml, conf_dir: &Path) -> anyhow::Result<()> {
                     Ok(())
                 }
                 "syslog" => {
-                    let config = LogConfig::parse_syslog_yaml(v, crate::build::PKG_NAME)
+                    let config = LogConfig::parse_syslog_yaml(v, conf_dir, crate::build::PKG_NAME)
                         .context(format!("invalid syslog config value for key {k}"))?;
                     default_log_config = Some(config);
                     Ok(())
@@ -58,6 +58,12 @@ pub(crate) fn load(v: &Yaml, conf_dir: &Path) -> anyhow::Result<()> {
                     default_log_config = Some(config);
                     Ok(())
                 }
+                "otlp" => {
+                    let config = LogConfig::parse_otlp_yaml(v, crate::build::PKG_NAME)
+                        .context(format!("invalid otlp config value for key {k}"))?;
+                    default_log_config = Some(config);
+                    Ok(())
+                }
                 "resolve" => {
                     let config = LogConfig::parse_yaml(v, conf_dir, crate::build::PKG_NAME)
                         .context(format!("invalid value for key {k}"))?;
@@ -82,6 +88,12 @@ pub(crate) fn load(v: &Yaml, conf_dir: &Path) -> anyhow::Result<()> {
                     TASK_DEFAULT_LOG_CONFIG_CONTAINER.with_mut(|l| l.set(config));
                     Ok(())
                 }
+                "flush_timeout" => {
+                    let timeout = g3_yaml::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    g3_daemon::log::set_flush_timeout(timeout);
+                    Ok(())
+                }
                 _ => Err(anyhow!("invalid key {k}")),
             })?;
         }
@@ -31,6 +31,7 @@ pub use plantuml::plantuml_graph;
 pub(crate) mod audit;
 pub(crate) mod auth;
 pub(crate) mod escaper;
+mod ip_location;
 pub(crate) mod log;
 pub(crate) mod resolver;
 pub(crate) mod server;
@@ -39,11 +40,13 @@ pub fn load() -> anyhow::Result<&'static Path> {
     let config_file =
         g3_daemon::opts::config_file().ok_or_else(|| anyhow!("no config file set"))?;
 
+    ip_location::begin_load();
     // allow multiple docs, and treat them as the same
     g3_yaml::foreach_doc(config_file, |_, doc| match doc {
         Yaml::Hash(map) => load_doc(map),
         _ => Err(anyhow!("yaml doc root should be hash")),
     })?;
+    ip_location::commit_load();
 
     Ok(config_file)
 }
@@ -65,11 +68,13 @@ pub(crate) async fn reload() -> anyhow::Result<()> {
 fn reload_blocking() -> anyhow::Result<()> {
     clear_all();
     if let Some(conf_file) = g3_daemon::opts::config_file() {
+        ip_location::begin_load();
         // allow multiple docs, and treat them as the same
         g3_yaml::foreach_doc(conf_file, |_, doc| match doc {
             Yaml::Hash(map) => reload_doc(map),
             _ => Err(anyhow!("yaml doc root should be hash")),
         })?;
+        ip_location::commit_load();
     }
     Ok(())
 }
@@ -84,6 +89,7 @@ fn reload_doc(map: &yaml::Hash) -> anyhow::Result<()> {
         "resolver" => resolver::load_all(v, conf_dir),
         "user" | "user_group" => auth::load_all(v, conf_dir),
         "auditor" => audit::load_all(v, conf_dir),
+        "ip_location_table" => ip_location::load(v),
         _ => Ok(()),
     })?;
     Ok(())
@@ -103,6 +109,7 @@ fn load_doc(map: &yaml::Hash) -> anyhow::Result<()> {
         "resolver" => resolver::load_all(v, conf_dir),
         "user" | "user_group" => auth::load_all(v, conf_dir),
         "auditor" => audit::load_all(v, conf_dir),
+        "ip_location_table" => ip_location::load(v),
         _ => Err(anyhow!("invalid key {k} in main conf")),
     })?;
     Ok(())
@@ -38,7 +38,7 @@ use g3_yaml::YamlDocPosition;
 
 use super::{
     AnyServerConfig, ServerConfig, ServerConfigDiffAction, IDLE_CHECK_DEFAULT_DURATION,
-    IDLE_CHECK_MAXIMUM_DURATION,
+    IDLE_CHECK_MAXIMUM_DURATION, USER_METRICS_TOP_K_MAXIMUM,
 };
 
 const SERVER_CONFIG_TYPE: &str = "HttpProxy";
@@ -102,8 +102,12 @@ pub(crate) struct HttpProxyServerConfig {
     pub(crate) echo_chained_info: bool,
     pub(crate) untrusted_read_limit: Option<TcpSockSpeedLimitConfig>,
     pub(crate) egress_path_selection_header: Option<HeaderName>,
+    pub(crate) connect_deadline: Option<Duration>,
+    pub(crate) connect_deadline_header: Option<HeaderName>,
     pub(crate) steal_forwarded_for: bool,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
+    pub(crate) dead_mans_switch: bool,
+    pub(crate) user_metrics_top_k: usize,
 }
 
 impl HttpProxyServerConfig {
@@ -148,8 +152,12 @@ impl HttpProxyServerConfig {
             echo_chained_info: false,
             untrusted_read_limit: None,
             egress_path_selection_header: None,
+            connect_deadline: None,
+            connect_deadline_header: None,
             steal_forwarded_for: false,
             extra_metrics_tags: None,
+            dead_mans_switch: false,
+            user_metrics_top_k: 0,
         }
     }
 
@@ -195,6 +203,14 @@ impl HttpProxyServerConfig {
                 self.extra_metrics_tags = Some(Arc::new(tags));
                 Ok(())
             }
+            "dead_mans_switch" => {
+                self.dead_mans_switch = g3_yaml::value::as_bool(v)?;
+                Ok(())
+            }
+            "user_metrics_top_k" => {
+                self.user_metrics_top_k = g3_yaml::value::as_usize(v)?;
+                Ok(())
+            }
             "listen" => {
                 let config = g3_yaml::value::as_tcp_listen_config(v)
                     .context(format!("invalid tcp listen config value for key {k}"))?;
@@ -392,6 +408,22 @@ impl HttpProxyServerConfig {
                     Err(anyhow!("invalid value type"))
                 }
             }
+            "connect_deadline" => {
+                let deadline = g3_yaml::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                self.connect_deadline = Some(deadline);
+                Ok(())
+            }
+            "connect_deadline_header" => {
+                if let Yaml::String(s) = v {
+                    let header = HeaderName::from_str(s)
+                        .map_err(|e| anyhow!("invalid http header name: {e}"))?;
+                    self.connect_deadline_header = Some(header);
+                    Ok(())
+                } else {
+                    Err(anyhow!("invalid value type"))
+                }
+            }
             "steal_forwarded_for" => {
                 self.steal_forwarded_for = g3_yaml::value::as_bool(v)
                     .context(format!("invalid boolean value for key {k}"))?;
@@ -420,6 +452,9 @@ impl HttpProxyServerConfig {
         if self.task_idle_check_duration > IDLE_CHECK_MAXIMUM_DURATION {
             self.task_idle_check_duration = IDLE_CHECK_MAXIMUM_DURATION;
         }
+        if self.user_metrics_top_k > USER_METRICS_TOP_K_MAXIMUM {
+            self.user_metrics_top_k = USER_METRICS_TOP_K_MAXIMUM;
+        }
 
         Ok(())
     }
@@ -470,6 +505,10 @@ impl ServerConfig for HttpProxyServerConfig {
         self.shared_logger.as_ref().map(|s| s.as_str())
     }
 
+    fn rustls_server_configs(&self) -> Vec<&RustlsServerConfigBuilder> {
+        self.server_tls_config.iter().collect()
+    }
+
     #[inline]
     fn limited_copy_config(&self) -> LimitedCopyConfig {
         self.tcp_copy
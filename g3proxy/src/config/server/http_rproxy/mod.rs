@@ -35,7 +35,7 @@ use g3_yaml::YamlDocPosition;
 
 use super::{
     AnyServerConfig, ServerConfig, ServerConfigDiffAction, IDLE_CHECK_DEFAULT_DURATION,
-    IDLE_CHECK_MAXIMUM_DURATION,
+    IDLE_CHECK_MAXIMUM_DURATION, USER_METRICS_TOP_K_MAXIMUM,
 };
 
 mod host;
@@ -93,6 +93,8 @@ pub(crate) struct HttpRProxyServerConfig {
     pub(crate) untrusted_read_limit: Option<TcpSockSpeedLimitConfig>,
     pub(crate) append_forwarded_for: HttpForwardedHeaderType,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
+    pub(crate) dead_mans_switch: bool,
+    pub(crate) user_metrics_top_k: usize,
     pub(crate) hosts: HostMatch<Arc<HttpHostConfig>>,
     pub(crate) enable_tls_server: bool,
     pub(crate) global_tls_server: Option<RustlsServerConfigBuilder>,
@@ -133,6 +135,8 @@ impl HttpRProxyServerConfig {
             untrusted_read_limit: None,
             append_forwarded_for: HttpForwardedHeaderType::default(),
             extra_metrics_tags: None,
+            dead_mans_switch: false,
+            user_metrics_top_k: 0,
             hosts: Default::default(),
             enable_tls_server: false,
             global_tls_server: None,
@@ -179,6 +183,14 @@ impl HttpRProxyServerConfig {
                 self.extra_metrics_tags = Some(Arc::new(tags));
                 Ok(())
             }
+            "dead_mans_switch" => {
+                self.dead_mans_switch = g3_yaml::value::as_bool(v)?;
+                Ok(())
+            }
+            "user_metrics_top_k" => {
+                self.user_metrics_top_k = g3_yaml::value::as_usize(v)?;
+                Ok(())
+            }
             "listen" => {
                 let config = g3_yaml::value::as_tcp_listen_config(v)
                     .context(format!("invalid tcp listen config value for key {k}"))?;
@@ -367,6 +379,9 @@ impl HttpRProxyServerConfig {
         if self.task_idle_check_duration > IDLE_CHECK_MAXIMUM_DURATION {
             self.task_idle_check_duration = IDLE_CHECK_MAXIMUM_DURATION;
         }
+        if self.user_metrics_top_k > USER_METRICS_TOP_K_MAXIMUM {
+            self.user_metrics_top_k = USER_METRICS_TOP_K_MAXIMUM;
+        }
 
         Ok(())
     }
@@ -417,6 +432,16 @@ impl ServerConfig for HttpRProxyServerConfig {
         self.shared_logger.as_ref().map(|s| s.as_str())
     }
 
+    fn rustls_server_configs(&self) -> Vec<&RustlsServerConfigBuilder> {
+        let mut configs: Vec<&RustlsServerConfigBuilder> = self.global_tls_server.iter().collect();
+        self.hosts.foreach_value(|host| {
+            if let Some(builder) = &host.tls_server_builder {
+                configs.push(builder);
+            }
+        });
+        configs
+    }
+
     #[inline]
     fn limited_copy_config(&self) -> LimitedCopyConfig {
         self.tcp_copy
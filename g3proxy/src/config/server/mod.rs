@@ -26,6 +26,7 @@ use yaml_rust::{yaml, Yaml};
 use g3_daemon::config::TopoMap;
 use g3_io_ext::LimitedCopyConfig;
 use g3_types::metrics::NodeName;
+use g3_types::net::RustlsServerConfigBuilder;
 use g3_yaml::{HybridParser, YamlDocPosition};
 
 use crate::audit::AuditHandle;
@@ -61,6 +62,7 @@ const CONFIG_KEY_SERVER_NAME: &str = "name";
 
 const IDLE_CHECK_MAXIMUM_DURATION: Duration = Duration::from_secs(1800);
 const IDLE_CHECK_DEFAULT_DURATION: Duration = Duration::from_secs(300);
+const USER_METRICS_TOP_K_MAXIMUM: usize = 100;
 
 pub(crate) enum ServerConfigDiffAction {
     NoAction,
@@ -88,6 +90,9 @@ pub(crate) trait ServerConfig {
     fn shared_logger(&self) -> Option<&str> {
         None
     }
+    fn rustls_server_configs(&self) -> Vec<&RustlsServerConfigBuilder> {
+        Vec::new()
+    }
     fn get_task_logger(&self) -> Logger {
         if let Some(shared_logger) = self.shared_logger() {
             crate::log::task::get_shared_logger(shared_logger, self.server_type(), self.name())
@@ -217,6 +222,7 @@ impl AnyServerConfig {
     impl_transparent0!(escaper, &NodeName);
     impl_transparent0!(user_group, &NodeName);
     impl_transparent0!(auditor, &NodeName);
+    impl_transparent0!(rustls_server_configs, Vec<&RustlsServerConfigBuilder>);
 
     impl_transparent1!(diff_action, ServerConfigDiffAction, &Self);
 }
@@ -23,7 +23,7 @@ use yaml_rust::{yaml, Yaml};
 use g3_tls_ticket::TlsTicketConfig;
 use g3_types::acl::AclNetworkRuleBuilder;
 use g3_types::metrics::NodeName;
-use g3_types::net::{RustlsServerConfigBuilder, UdpListenConfig};
+use g3_types::net::{QuinnRetryPolicy, RustlsServerConfigBuilder, UdpListenConfig};
 use g3_yaml::YamlDocPosition;
 
 use super::ServerConfig;
@@ -50,6 +50,10 @@ pub(crate) struct PlainQuicPortConfig {
     pub(crate) ingress_net_filter: Option<AclNetworkRuleBuilder>,
     pub(crate) server: NodeName,
     pub(crate) offline_rebind_port: Option<u16>,
+    pub(crate) retry_policy: QuinnRetryPolicy,
+    pub(crate) enable_gso: bool,
+    pub(crate) gso_segment_size: Option<u16>,
+    pub(crate) enable_gro: Option<bool>,
 }
 
 impl PlainQuicPortConfig {
@@ -64,6 +68,10 @@ impl PlainQuicPortConfig {
             ingress_net_filter: None,
             server: NodeName::default(),
             offline_rebind_port: None,
+            retry_policy: QuinnRetryPolicy::default(),
+            enable_gso: true,
+            gso_segment_size: None,
+            enable_gro: None,
         }
     }
 
@@ -100,6 +108,28 @@ impl PlainQuicPortConfig {
                 self.offline_rebind_port = Some(port);
                 Ok(())
             }
+            "retry_policy" | "quic_retry_policy" => {
+                self.retry_policy = g3_yaml::value::as_quinn_retry_policy(v)
+                    .context(format!("invalid quinn retry policy value for key {k}"))?;
+                Ok(())
+            }
+            "enable_gso" => {
+                self.enable_gso = g3_yaml::value::as_bool(v)
+                    .context(format!("invalid bool value for key {k}"))?;
+                Ok(())
+            }
+            "gso_segment_size" => {
+                let size =
+                    g3_yaml::value::as_u16(v).context(format!("invalid u16 value for key {k}"))?;
+                self.gso_segment_size = Some(size);
+                Ok(())
+            }
+            "enable_gro" => {
+                let enable = g3_yaml::value::as_bool(v)
+                    .context(format!("invalid bool value for key {k}"))?;
+                self.enable_gro = Some(enable);
+                Ok(())
+            }
             "quic_server" => {
                 let lookup_dir = g3_daemon::config::get_lookup_dir(self.position.as_ref())?;
                 self.tls_server =
@@ -137,6 +167,16 @@ impl PlainQuicPortConfig {
         }
         // make sure listen is always set
         self.listen.check().context("invalid listen config")?;
+        if let Some(enable) = self.enable_gro {
+            // GRO is a socket level option, so it will be applied with the listen config
+            self.listen.set_udp_gro(enable);
+        }
+        if let Some(size) = self.gso_segment_size {
+            // the minimal max udp payload size for QUIC is 1200
+            if size < 1200 {
+                return Err(anyhow!("gso segment size should be at least 1200"));
+            }
+        }
         self.tls_server.check().context("invalid quic tls config")?;
 
         Ok(())
@@ -185,7 +225,10 @@ impl ServerConfig for PlainQuicPortConfig {
         if self.listen != new.listen {
             flags.set(PlainQuicPortUpdateFlags::LISTEN, true);
         }
-        if self.tls_server != new.tls_server {
+        if self.tls_server != new.tls_server
+            || self.enable_gso != new.enable_gso
+            || self.gso_segment_size != new.gso_segment_size
+        {
             flags.set(PlainQuicPortUpdateFlags::QUINN, true);
         }
         if self.server != new.server {
@@ -200,4 +243,8 @@ impl ServerConfig for PlainQuicPortConfig {
         set.insert(self.server.clone());
         Some(set)
     }
+
+    fn rustls_server_configs(&self) -> Vec<&RustlsServerConfigBuilder> {
+        vec![&self.tls_server]
+    }
 }
@@ -43,6 +43,8 @@ pub(crate) struct PlainTlsPortConfig {
     pub(crate) server: NodeName,
     pub(crate) proxy_protocol: Option<ProxyProtocolVersion>,
     pub(crate) proxy_protocol_read_timeout: Duration,
+    pub(crate) tls_client_ja3: bool,
+    pub(crate) tls_client_ja4: bool,
 }
 
 impl PlainTlsPortConfig {
@@ -58,6 +60,8 @@ impl PlainTlsPortConfig {
             server: NodeName::default(),
             proxy_protocol: None,
             proxy_protocol_read_timeout: Duration::from_secs(5),
+            tls_client_ja3: false,
+            tls_client_ja4: false,
         }
     }
 
@@ -126,6 +130,14 @@ impl PlainTlsPortConfig {
                 self.proxy_protocol_read_timeout = t;
                 Ok(())
             }
+            "tls_client_ja3" => {
+                self.tls_client_ja3 = g3_yaml::value::as_bool(v)?;
+                Ok(())
+            }
+            "tls_client_ja4" => {
+                self.tls_client_ja4 = g3_yaml::value::as_bool(v)?;
+                Ok(())
+            }
             _ => Err(anyhow!("invalid key {k}")),
         }
     }
@@ -193,4 +205,8 @@ impl ServerConfig for PlainTlsPortConfig {
         set.insert(self.server.clone());
         Some(set)
     }
+
+    fn rustls_server_configs(&self) -> Vec<&RustlsServerConfigBuilder> {
+        self.server_tls_config.iter().collect()
+    }
 }
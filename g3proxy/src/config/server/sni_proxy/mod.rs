@@ -61,6 +61,7 @@ pub(crate) struct SniProxyServerConfig {
     pub(crate) server_tcp_portmap: ProtocolPortMap,
     pub(crate) client_tcp_portmap: ProtocolPortMap,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
+    pub(crate) dead_mans_switch: bool,
     pub(crate) allowed_sites: Option<HostMatch<Arc<SniHostConfig>>>,
 }
 
@@ -90,6 +91,7 @@ impl SniProxyServerConfig {
             server_tcp_portmap: ProtocolPortMap::tcp_server(),
             client_tcp_portmap: ProtocolPortMap::tcp_client(),
             extra_metrics_tags: None,
+            dead_mans_switch: false,
             allowed_sites: None,
         }
     }
@@ -132,6 +134,10 @@ impl SniProxyServerConfig {
                 self.extra_metrics_tags = Some(Arc::new(tags));
                 Ok(())
             }
+            "dead_mans_switch" => {
+                self.dead_mans_switch = g3_yaml::value::as_bool(v)?;
+                Ok(())
+            }
             "listen" => {
                 let config = g3_yaml::value::as_tcp_listen_config(v)
                     .context(format!("invalid tcp listen config value for key {k}"))?;
@@ -35,7 +35,7 @@ use g3_yaml::YamlDocPosition;
 
 use super::{
     AnyServerConfig, ServerConfig, ServerConfigDiffAction, IDLE_CHECK_DEFAULT_DURATION,
-    IDLE_CHECK_MAXIMUM_DURATION,
+    IDLE_CHECK_MAXIMUM_DURATION, USER_METRICS_TOP_K_MAXIMUM,
 };
 
 const SERVER_CONFIG_TYPE: &str = "SocksProxy";
@@ -91,6 +91,8 @@ pub(crate) struct SocksProxyServerConfig {
     pub(crate) udp_misc_opts: UdpMiscSockOpts,
     pub(crate) transmute_udp_echo_ip: Option<AHashMap<IpAddr, IpAddr>>,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
+    pub(crate) dead_mans_switch: bool,
+    pub(crate) user_metrics_top_k: usize,
 }
 
 impl SocksProxyServerConfig {
@@ -126,6 +128,8 @@ impl SocksProxyServerConfig {
             udp_misc_opts: Default::default(),
             transmute_udp_echo_ip: None,
             extra_metrics_tags: None,
+            dead_mans_switch: false,
+            user_metrics_top_k: 0,
         }
     }
 
@@ -171,6 +175,14 @@ impl SocksProxyServerConfig {
                 self.extra_metrics_tags = Some(Arc::new(tags));
                 Ok(())
             }
+            "dead_mans_switch" => {
+                self.dead_mans_switch = g3_yaml::value::as_bool(v)?;
+                Ok(())
+            }
+            "user_metrics_top_k" => {
+                self.user_metrics_top_k = g3_yaml::value::as_usize(v)?;
+                Ok(())
+            }
             "listen" => {
                 let config = g3_yaml::value::as_tcp_listen_config(v)
                     .context(format!("invalid tcp listen config value for key {k}"))?;
@@ -345,6 +357,9 @@ impl SocksProxyServerConfig {
         if self.task_idle_check_duration > IDLE_CHECK_MAXIMUM_DURATION {
             self.task_idle_check_duration = IDLE_CHECK_MAXIMUM_DURATION;
         }
+        if self.user_metrics_top_k > USER_METRICS_TOP_K_MAXIMUM {
+            self.user_metrics_top_k = USER_METRICS_TOP_K_MAXIMUM;
+        }
 
         Ok(())
     }
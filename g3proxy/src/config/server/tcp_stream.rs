@@ -58,6 +58,7 @@ pub(crate) struct TcpStreamServerConfig {
     pub(crate) tcp_copy: LimitedCopyConfig,
     pub(crate) tcp_misc_opts: TcpMiscSockOpts,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
+    pub(crate) dead_mans_switch: bool,
 }
 
 impl TcpStreamServerConfig {
@@ -84,6 +85,7 @@ impl TcpStreamServerConfig {
             tcp_copy: Default::default(),
             tcp_misc_opts: Default::default(),
             extra_metrics_tags: None,
+            dead_mans_switch: false,
         }
     }
 
@@ -125,6 +127,10 @@ impl TcpStreamServerConfig {
                 self.extra_metrics_tags = Some(Arc::new(tags));
                 Ok(())
             }
+            "dead_mans_switch" => {
+                self.dead_mans_switch = g3_yaml::value::as_bool(v)?;
+                Ok(())
+            }
             "listen" => {
                 let config = g3_yaml::value::as_tcp_listen_config(v)
                     .context(format!("invalid tcp listen config value for key {k}"))?;
@@ -50,6 +50,7 @@ pub(crate) struct TcpTProxyServerConfig {
     pub(crate) tcp_copy: LimitedCopyConfig,
     pub(crate) tcp_misc_opts: TcpMiscSockOpts,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
+    pub(crate) dead_mans_switch: bool,
 }
 
 impl TcpTProxyServerConfig {
@@ -72,6 +73,7 @@ impl TcpTProxyServerConfig {
             tcp_copy: Default::default(),
             tcp_misc_opts: Default::default(),
             extra_metrics_tags: None,
+            dead_mans_switch: false,
         }
     }
 
@@ -113,6 +115,10 @@ impl TcpTProxyServerConfig {
                 self.extra_metrics_tags = Some(Arc::new(tags));
                 Ok(())
             }
+            "dead_mans_switch" => {
+                self.dead_mans_switch = g3_yaml::value::as_bool(v)?;
+                Ok(())
+            }
             "listen" => {
                 self.listen = g3_yaml::value::as_tcp_listen_config(v)
                     .context(format!("invalid tcp listen config value for key {k}"))?;
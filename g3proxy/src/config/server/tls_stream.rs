@@ -61,6 +61,7 @@ pub(crate) struct TlsStreamServerConfig {
     pub(crate) tcp_copy: LimitedCopyConfig,
     pub(crate) tcp_misc_opts: TcpMiscSockOpts,
     pub(crate) extra_metrics_tags: Option<Arc<StaticMetricsTags>>,
+    pub(crate) dead_mans_switch: bool,
 }
 
 impl TlsStreamServerConfig {
@@ -89,6 +90,7 @@ impl TlsStreamServerConfig {
             tcp_copy: Default::default(),
             tcp_misc_opts: Default::default(),
             extra_metrics_tags: None,
+            dead_mans_switch: false,
         }
     }
 
@@ -130,6 +132,10 @@ impl TlsStreamServerConfig {
                 self.extra_metrics_tags = Some(Arc::new(tags));
                 Ok(())
             }
+            "dead_mans_switch" => {
+                self.dead_mans_switch = g3_yaml::value::as_bool(v)?;
+                Ok(())
+            }
             "listen" => {
                 let config = g3_yaml::value::as_tcp_listen_config(v)
                     .context(format!("invalid tcp listen config value for key {k}"))?;
@@ -321,6 +327,10 @@ impl ServerConfig for TlsStreamServerConfig {
         self.shared_logger.as_ref().map(|s| s.as_str())
     }
 
+    fn rustls_server_configs(&self) -> Vec<&RustlsServerConfigBuilder> {
+        vec![&self.server_tls_config]
+    }
+
     #[inline]
     fn limited_copy_config(&self) -> LimitedCopyConfig {
         self.tcp_copy
@@ -26,11 +26,13 @@ macro_rules! impl_reload {
             position: Option<YamlDocPosition>,
         ) -> anyhow::Result<()> {
             let name = unsafe { NodeName::new_unchecked(name) };
-            g3_daemon::runtime::main_handle()
+            let r = g3_daemon::runtime::main_handle()
                 .ok_or(anyhow!("unable to get main runtime handle"))?
                 .spawn(async move { crate::$m::reload(&name, position).await })
                 .await
-                .map_err(|e| anyhow!("failed to spawn reload task: {e}"))?
+                .map_err(|e| anyhow!("failed to spawn reload task: {e}"))?;
+            g3_daemon::config::metrics::add_reload_result(&r);
+            r
         }
     };
 }
@@ -14,12 +14,14 @@
  * limitations under the License.
  */
 
+use std::str::FromStr;
 use std::sync::Arc;
 
 use capnp::capability::Promise;
 use capnp_rpc::pry;
 
 use g3_types::metrics::NodeName;
+use g3_types::net::UpstreamAddr;
 
 use g3proxy_proto::escaper_capnp::escaper_control;
 
@@ -51,4 +53,33 @@ impl escaper_control::Server for EscaperControlImpl {
             Ok(())
         })
     }
+
+    fn probe(
+        &mut self,
+        params: escaper_control::ProbeParams,
+        mut results: escaper_control::ProbeResults,
+    ) -> Promise<(), capnp::Error> {
+        let target = pry!(pry!(pry!(params.get()).get_target()).to_str());
+        let target = match UpstreamAddr::from_str(target) {
+            Ok(addr) => addr,
+            Err(e) => {
+                set_operation_result(
+                    results.get().init_result(),
+                    Err(e.context("invalid probe target")),
+                );
+                return Promise::ok(());
+            }
+        };
+        let escaper = Arc::clone(&self.escaper);
+        Promise::from_future(async move {
+            match escaper.probe(target).await {
+                Ok(elapsed) => results
+                    .get()
+                    .init_result()
+                    .set_ok(format!("success, time used {elapsed:?}").as_str()),
+                Err(e) => set_operation_result(results.get().init_result(), Err(e)),
+            }
+            Ok(())
+        })
+    }
 }
@@ -46,6 +46,7 @@ impl server_control::Server for ServerControlImpl {
             builder.set_alive_task_count(stats.get_alive_count());
             builder.set_total_conn_count(stats.get_conn_total());
             builder.set_total_task_count(stats.get_task_total());
+            builder.set_ready(self.server.get_listen_stats().is_ready());
             Promise::ok(())
         } else {
             Promise::err(capnp::Error::failed(
@@ -53,4 +54,15 @@ impl server_control::Server for ServerControlImpl {
             ))
         }
     }
+
+    fn ready(
+        &mut self,
+        _params: server_control::ReadyParams,
+        mut results: server_control::ReadyResults,
+    ) -> Promise<(), capnp::Error> {
+        results
+            .get()
+            .set_ready(self.server.get_listen_stats().is_ready());
+        Promise::ok(())
+    }
 }
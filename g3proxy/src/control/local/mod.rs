@@ -48,6 +48,9 @@ impl UniqueController {
             .await;
         }
 
+        // cancel all remaining upstream negotiations
+        crate::escape::force_quit_negotiation();
+
         debug!("aborting unique controller");
         LocalController::abort_unique().await;
     }
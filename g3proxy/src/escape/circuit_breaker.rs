@@ -0,0 +1,359 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Mutex;
+use std::time::Instant;
+
+use super::EscaperCircuitBreakerStats;
+use crate::config::escaper::CircuitBreakerConfig;
+
+enum BreakerState {
+    Closed {
+        window_start: Instant,
+        success: usize,
+        failure: usize,
+    },
+    Open {
+        until: Instant,
+    },
+    HalfOpen {
+        /// changed each time the breaker goes half-open, to match the trial permits
+        epoch: u64,
+        issued: usize,
+        succeeded: usize,
+    },
+}
+
+impl BreakerState {
+    fn new_closed() -> Self {
+        BreakerState::Closed {
+            window_start: Instant::now(),
+            success: 0,
+            failure: 0,
+        }
+    }
+
+    fn as_metric_value(&self) -> u8 {
+        match self {
+            BreakerState::Closed { .. } => 0,
+            BreakerState::Open { .. } => 1,
+            BreakerState::HalfOpen { .. } => 2,
+        }
+    }
+}
+
+struct BreakerInner {
+    state: BreakerState,
+    next_epoch: u64,
+}
+
+/// A circuit breaker which will fail fast new connections if the failure ratio is too high.
+///
+/// It will be open if the failure ratio exceeds the threshold in the window, and after the
+/// cool down time, a few trial requests will be allowed to decide whether to close it again.
+pub(crate) struct EscaperCircuitBreaker {
+    config: CircuitBreakerConfig,
+    inner: Mutex<BreakerInner>,
+}
+
+/// The permit for a new attempt.
+///
+/// The result should be recorded by calling `record`. If dropped without any result,
+/// the attempt will be ignored, and the trial slot will be released if it's a trial one.
+pub(crate) struct CircuitBreakerPermit<'a> {
+    breaker: &'a EscaperCircuitBreaker,
+    stats: &'a EscaperCircuitBreakerStats,
+    trial_epoch: Option<u64>,
+    recorded: bool,
+}
+
+impl CircuitBreakerPermit<'_> {
+    pub(crate) fn record(mut self, success: bool) {
+        self.recorded = true;
+        self.breaker.record(self.trial_epoch, success, self.stats);
+    }
+}
+
+impl Drop for CircuitBreakerPermit<'_> {
+    fn drop(&mut self) {
+        if self.recorded {
+            return;
+        }
+        if let Some(epoch) = self.trial_epoch {
+            self.breaker.release_trial(epoch);
+        }
+    }
+}
+
+impl EscaperCircuitBreaker {
+    pub(crate) fn new(config: CircuitBreakerConfig) -> Self {
+        EscaperCircuitBreaker {
+            config,
+            inner: Mutex::new(BreakerInner {
+                state: BreakerState::new_closed(),
+                next_epoch: 0,
+            }),
+        }
+    }
+
+    fn set_state(
+        &self,
+        state: &mut BreakerState,
+        new: BreakerState,
+        stats: &EscaperCircuitBreakerStats,
+    ) {
+        *state = new;
+        stats.set_state(state.as_metric_value());
+    }
+
+    fn trip(&self, state: &mut BreakerState, stats: &EscaperCircuitBreakerStats) {
+        stats.add_trip();
+        self.set_state(
+            state,
+            BreakerState::Open {
+                until: Instant::now() + self.config.cool_down,
+            },
+            stats,
+        );
+    }
+
+    /// Check if a new attempt is allowed
+    pub(crate) fn try_acquire<'a>(
+        &'a self,
+        stats: &'a EscaperCircuitBreakerStats,
+    ) -> Option<CircuitBreakerPermit<'a>> {
+        let mut inner = self.inner.lock().unwrap();
+        let BreakerInner { state, next_epoch } = &mut *inner;
+        let trial_epoch = match state {
+            BreakerState::Closed { .. } => None,
+            BreakerState::Open { until } => {
+                if Instant::now() < *until {
+                    return None;
+                }
+                let epoch = *next_epoch;
+                *next_epoch = next_epoch.wrapping_add(1);
+                self.set_state(
+                    state,
+                    BreakerState::HalfOpen {
+                        epoch,
+                        issued: 1,
+                        succeeded: 0,
+                    },
+                    stats,
+                );
+                Some(epoch)
+            }
+            BreakerState::HalfOpen { epoch, issued, .. } => {
+                if *issued < self.config.trial_requests {
+                    *issued += 1;
+                    Some(*epoch)
+                } else {
+                    return None;
+                }
+            }
+        };
+        Some(CircuitBreakerPermit {
+            breaker: self,
+            stats,
+            trial_epoch,
+            recorded: false,
+        })
+    }
+
+    fn release_trial(&self, trial_epoch: u64) {
+        let mut inner = self.inner.lock().unwrap();
+        if let BreakerState::HalfOpen { epoch, issued, .. } = &mut inner.state {
+            if *epoch == trial_epoch {
+                *issued = issued.saturating_sub(1);
+            }
+        }
+    }
+
+    fn record(&self, trial_epoch: Option<u64>, success: bool, stats: &EscaperCircuitBreakerStats) {
+        let mut inner = self.inner.lock().unwrap();
+        let state = &mut inner.state;
+        match state {
+            BreakerState::Closed {
+                window_start,
+                success: success_count,
+                failure: failure_count,
+            } => {
+                let now = Instant::now();
+                if now.duration_since(*window_start) >= self.config.window {
+                    *window_start = now;
+                    *success_count = 0;
+                    *failure_count = 0;
+                }
+                if success {
+                    *success_count += 1;
+                    return;
+                }
+                *failure_count += 1;
+
+                let total = *success_count + *failure_count;
+                if total < self.config.min_requests {
+                    return;
+                }
+                if (*failure_count as f64) >= (total as f64) * self.config.failure_ratio {
+                    self.trip(state, stats);
+                }
+            }
+            BreakerState::Open { .. } => {
+                // results of the attempts acquired before open are ignored
+            }
+            BreakerState::HalfOpen {
+                epoch, succeeded, ..
+            } => {
+                if trial_epoch != Some(*epoch) {
+                    // only the results of the trial attempts are counted
+                    return;
+                }
+                if success {
+                    *succeeded += 1;
+                    if *succeeded >= self.config.trial_requests {
+                        self.set_state(state, BreakerState::new_closed(), stats);
+                    }
+                } else {
+                    self.trip(state, stats);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn new_breaker(cool_down: Duration) -> (EscaperCircuitBreaker, EscaperCircuitBreakerStats) {
+        let config = CircuitBreakerConfig {
+            failure_ratio: 0.5,
+            window: Duration::from_secs(60),
+            min_requests: 4,
+            cool_down,
+            trial_requests: 2,
+        };
+        let stats = EscaperCircuitBreakerStats::default();
+        stats.set_enabled(true);
+        (EscaperCircuitBreaker::new(config), stats)
+    }
+
+    fn state(stats: &EscaperCircuitBreakerStats) -> u8 {
+        stats.snapshot().unwrap().state
+    }
+
+    fn trip(breaker: &EscaperCircuitBreaker, stats: &EscaperCircuitBreakerStats) {
+        for _ in 0..4 {
+            breaker.try_acquire(stats).unwrap().record(false);
+        }
+        assert_eq!(state(stats), 1);
+    }
+
+    #[test]
+    fn closed_to_open() {
+        let (breaker, stats) = new_breaker(Duration::from_secs(60));
+
+        for _ in 0..3 {
+            breaker.try_acquire(&stats).unwrap().record(false);
+        }
+        // not enough requests
+        assert_eq!(state(&stats), 0);
+        breaker.try_acquire(&stats).unwrap().record(true);
+        // 3 failures in 4 requests
+        assert_eq!(state(&stats), 0);
+
+        let (breaker, stats) = new_breaker(Duration::from_secs(60));
+        breaker.try_acquire(&stats).unwrap().record(true);
+        breaker.try_acquire(&stats).unwrap().record(true);
+        breaker.try_acquire(&stats).unwrap().record(false);
+        assert_eq!(state(&stats), 0);
+        breaker.try_acquire(&stats).unwrap().record(false);
+        assert_eq!(state(&stats), 1);
+        assert_eq!(stats.snapshot().unwrap().tripped, 1);
+        assert!(breaker.try_acquire(&stats).is_none());
+    }
+
+    #[test]
+    fn ignored_results() {
+        let (breaker, stats) = new_breaker(Duration::from_secs(60));
+        for _ in 0..8 {
+            let permit = breaker.try_acquire(&stats).unwrap();
+            drop(permit);
+        }
+        assert_eq!(state(&stats), 0);
+    }
+
+    #[test]
+    fn half_open_to_closed() {
+        let (breaker, stats) = new_breaker(Duration::ZERO);
+        let stale = breaker.try_acquire(&stats).unwrap();
+        trip(&breaker, &stats);
+
+        let p1 = breaker.try_acquire(&stats).unwrap();
+        assert_eq!(state(&stats), 2);
+        let p2 = breaker.try_acquire(&stats).unwrap();
+        assert!(breaker.try_acquire(&stats).is_none());
+
+        // the result of attempts acquired before open should not be counted
+        stale.record(true);
+        p1.record(true);
+        assert_eq!(state(&stats), 2);
+        p2.record(true);
+        assert_eq!(state(&stats), 0);
+        assert_eq!(stats.snapshot().unwrap().tripped, 1);
+    }
+
+    #[test]
+    fn half_open_to_open() {
+        let (breaker, stats) = new_breaker(Duration::ZERO);
+        trip(&breaker, &stats);
+
+        let p1 = breaker.try_acquire(&stats).unwrap();
+        let p2 = breaker.try_acquire(&stats).unwrap();
+        p1.record(false);
+        assert_eq!(state(&stats), 1);
+        assert_eq!(stats.snapshot().unwrap().tripped, 2);
+
+        // the trial permit of the previous half-open state should not affect the new one
+        let p3 = breaker.try_acquire(&stats).unwrap();
+        assert_eq!(state(&stats), 2);
+        drop(p2);
+        let p4 = breaker.try_acquire(&stats).unwrap();
+        assert!(breaker.try_acquire(&stats).is_none());
+        p3.record(true);
+        p4.record(true);
+        assert_eq!(state(&stats), 0);
+    }
+
+    #[test]
+    fn half_open_release() {
+        let (breaker, stats) = new_breaker(Duration::ZERO);
+        trip(&breaker, &stats);
+
+        let p1 = breaker.try_acquire(&stats).unwrap();
+        let p2 = breaker.try_acquire(&stats).unwrap();
+        assert!(breaker.try_acquire(&stats).is_none());
+
+        // dropped without result, such as canceled
+        drop(p1);
+        let p3 = breaker.try_acquire(&stats).unwrap();
+        assert!(breaker.try_acquire(&stats).is_none());
+        p2.record(true);
+        p3.record(true);
+        assert_eq!(state(&stats), 0);
+    }
+}
@@ -23,6 +23,7 @@ use async_trait::async_trait;
 use slog::Logger;
 
 use g3_daemon::stat::remote::ArcTcpConnectionTaskRemoteStats;
+use g3_ip_locate::IpLocationServiceHandle;
 use g3_resolver::ResolveError;
 use g3_socket::util::AddressFamily;
 use g3_socket::BindAddr;
@@ -73,6 +74,7 @@ pub(super) struct DirectFixedEscaper {
     stats: Arc<DirectFixedEscaperStats>,
     resolver_handle: ArcIntegratedResolverHandle,
     egress_net_filter: Arc<AclNetworkRule>,
+    ip_locate_handle: Option<IpLocationServiceHandle>,
     resolve_redirection: Option<ResolveRedirection>,
     escape_logger: Logger,
 }
@@ -84,6 +86,10 @@ impl DirectFixedEscaper {
     ) -> anyhow::Result<ArcEscaper> {
         let resolver_handle = crate::resolve::get_handle(config.resolver())?;
         let egress_net_filter = Arc::new(config.egress_net_filter.build());
+        let ip_locate_handle = match &config.egress_asn_filter {
+            Some(filter) => Some(filter.ip_locate_service.spawn_ip_locate_agent()?),
+            None => None,
+        };
 
         let resolve_redirection = config
             .resolve_redirection
@@ -99,6 +105,7 @@ impl DirectFixedEscaper {
             stats,
             resolver_handle,
             egress_net_filter,
+            ip_locate_handle,
             resolve_redirection,
             escape_logger,
         };
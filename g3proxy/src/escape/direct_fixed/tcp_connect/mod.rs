@@ -32,6 +32,7 @@ use g3_types::net::{
 };
 
 use super::DirectFixedEscaper;
+use crate::config::escaper::EgressAsnCheck;
 use crate::log::escape::tcp_connect::EscapeLogForTcpConnect;
 use crate::module::tcp_connect::{
     TcpConnectError, TcpConnectRemoteWrapperStats, TcpConnectResult, TcpConnectTaskConf,
@@ -75,6 +76,45 @@ impl DirectFixedEscaper {
         }
     }
 
+    async fn check_egress_asn(
+        &self,
+        peer_ip: IpAddr,
+        tcp_notes: &mut TcpConnectTaskNotes,
+        task_notes: &ServerTaskNotes,
+    ) -> Result<Option<Arc<str>>, TcpConnectError> {
+        let (Some(filter), Some(ip_locate_handle)) =
+            (&self.config.egress_asn_filter, &self.ip_locate_handle)
+        else {
+            return Ok(None);
+        };
+
+        let location = ip_locate_handle.fetch(peer_ip).await;
+        let asn = location.as_ref().and_then(|l| l.network_asn());
+        let isp_domain = location.as_ref().and_then(|l| l.isp_domain());
+        let r = match filter.check(asn, isp_domain) {
+            EgressAsnCheck::Pass => Ok(()),
+            EgressAsnCheck::Deny => {
+                self.stats.forbidden.add_asn_blocked();
+                self.handle_tcp_target_ip_acl_action(AclAction::Forbid, task_notes)
+            }
+            EgressAsnCheck::LookupFailed(action) => {
+                self.stats
+                    .forbidden
+                    .add_ip_locate_failed(action.forbid_early());
+                self.handle_tcp_target_ip_acl_action(action, task_notes)
+            }
+        };
+        let isp_domain = isp_domain.map(Arc::from);
+        match r {
+            Ok(_) => Ok(isp_domain),
+            Err(e) => {
+                // keep the isp domain of the denied address for logging
+                tcp_notes.next_isp_domain = isp_domain;
+                Err(e)
+            }
+        }
+    }
+
     fn prepare_connect_socket(
         &self,
         peer_ip: IpAddr,
@@ -113,6 +153,36 @@ impl DirectFixedEscaper {
         Ok((sock, bind))
     }
 
+    /// Set the ipv6 flow label on the socket, and return the peer address to connect to.
+    /// The flow label only applies to ipv6 peers.
+    #[cfg(target_os = "linux")]
+    fn set_ipv6_flow_label(
+        &self,
+        sock: &TcpSocket,
+        peer: SocketAddr,
+        task_notes: &ServerTaskNotes,
+    ) -> Result<SocketAddr, TcpConnectError> {
+        let Some(flow_label) = &self.config.ipv6_flow_label else {
+            return Ok(peer);
+        };
+        if peer.is_ipv4() {
+            return Ok(peer);
+        }
+        let label = flow_label.label_for_task(&task_notes.id);
+        g3_socket::tcp::set_ipv6_flow_label(sock, peer, label)
+            .map_err(TcpConnectError::SetupSocketFailed)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn set_ipv6_flow_label(
+        &self,
+        _sock: &TcpSocket,
+        peer: SocketAddr,
+        _task_notes: &ServerTaskNotes,
+    ) -> Result<SocketAddr, TcpConnectError> {
+        Ok(peer)
+    }
+
     async fn fixed_try_connect(
         &self,
         peer_ip: IpAddr,
@@ -121,17 +191,24 @@ impl DirectFixedEscaper {
         tcp_notes: &mut TcpConnectTaskNotes,
         task_notes: &ServerTaskNotes,
     ) -> Result<TcpStream, TcpConnectError> {
+        let isp_domain = self
+            .check_egress_asn(peer_ip, tcp_notes, task_notes)
+            .await?;
         let (sock, bind) =
             self.prepare_connect_socket(peer_ip, tcp_notes.bind, task_notes, &config)?;
         let peer = SocketAddr::new(peer_ip, task_conf.upstream.port());
+        let connect_peer = self.set_ipv6_flow_label(&sock, peer, task_notes)?;
         tcp_notes.next = Some(peer);
+        tcp_notes.next_isp_domain = isp_domain;
         tcp_notes.bind = bind;
+        tcp_notes.socket_cookie = g3_socket::tcp::socket_cookie(&sock);
 
         let instant_now = Instant::now();
 
         self.stats.tcp.connect.add_attempted();
         tcp_notes.tries = 1;
-        match tokio::time::timeout(config.connect.each_timeout(), sock.connect(peer)).await {
+        match tokio::time::timeout(config.connect.each_timeout(), sock.connect(connect_peer)).await
+        {
             Ok(Ok(ups_stream)) => {
                 self.stats.tcp.connect.add_success();
                 tcp_notes.duration = instant_now.elapsed();
@@ -215,19 +292,29 @@ impl DirectFixedEscaper {
         loop {
             if spawn_new_connection {
                 if let Some(ip) = ips.pop() {
+                    let isp_domain = match self.check_egress_asn(ip, tcp_notes, task_notes).await {
+                        Ok(isp_domain) => isp_domain,
+                        Err(e) => {
+                            // skip the denied address and try the next one
+                            returned_err = e;
+                            continue;
+                        }
+                    };
                     let (sock, bind) =
                         self.prepare_connect_socket(ip, tcp_notes.bind, task_notes, &config)?;
+                    let socket_cookie = g3_socket::tcp::socket_cookie(&sock);
                     let peer = SocketAddr::new(ip, port);
+                    let connect_peer = self.set_ipv6_flow_label(&sock, peer, task_notes)?;
                     running_connection += 1;
                     spawn_new_connection = false;
                     tcp_notes.tries += 1;
                     let stats = self.stats.clone();
                     c_set.spawn(async move {
                         stats.tcp.connect.add_attempted();
-                        match tokio::time::timeout(each_timeout, sock.connect(peer)).await {
+                        match tokio::time::timeout(each_timeout, sock.connect(connect_peer)).await {
                             Ok(Ok(stream)) => {
                                 stats.tcp.connect.add_success();
-                                (Ok(stream), peer, bind)
+                                (Ok(stream), peer, bind, socket_cookie, isp_domain)
                             }
                             Ok(Err(e)) => {
                                 stats.tcp.connect.add_error();
@@ -235,11 +322,19 @@ impl DirectFixedEscaper {
                                     Err(TcpConnectError::ConnectFailed(ConnectError::from(e))),
                                     peer,
                                     bind,
+                                    socket_cookie,
+                                    isp_domain,
                                 )
                             }
                             Err(_) => {
                                 stats.tcp.connect.add_timeout();
-                                (Err(TcpConnectError::TimeoutByRule), peer, bind)
+                                (
+                                    Err(TcpConnectError::TimeoutByRule),
+                                    peer,
+                                    bind,
+                                    socket_cookie,
+                                    isp_domain,
+                                )
                             }
                         }
                     });
@@ -259,12 +354,15 @@ impl DirectFixedEscaper {
                                 let peer_addr = r.1;
                                 tcp_notes.next = Some(peer_addr);
                                 tcp_notes.bind = r.2;
+                                tcp_notes.socket_cookie = r.3;
+                                tcp_notes.next_isp_domain = r.4;
                                 match r.0 {
                                     Ok(ups_stream) => {
                                         let local_addr = ups_stream
                                             .local_addr()
                                             .map_err(TcpConnectError::SetupSocketFailed)?;
                                         self.stats.tcp.connect.add_established();
+                                        self.stats.tcp.connect.add_happy_established(peer_addr);
                                         tcp_notes.local = Some(local_addr);
                                         tcp_notes.chained.target_addr = Some(peer_addr);
                                         tcp_notes.chained.outgoing_addr = Some(local_addr);
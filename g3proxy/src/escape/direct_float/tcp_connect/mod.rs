@@ -123,6 +123,7 @@ impl DirectFloatEscaper {
         let peer = SocketAddr::new(peer_ip, task_conf.upstream.port());
         tcp_notes.next = Some(peer);
         tcp_notes.bind = BindAddr::Ip(bind.ip);
+        tcp_notes.socket_cookie = g3_socket::tcp::socket_cookie(&sock);
         tcp_notes.expire = bind.expire_datetime;
         tcp_notes.egress = Some(bind.egress_info.clone());
 
@@ -215,6 +216,7 @@ impl DirectFloatEscaper {
                 if let Some(ip) = ips.pop() {
                     let (sock, bind) =
                         self.prepare_connect_socket(ip, tcp_notes.bind, task_notes, &config)?;
+                    let socket_cookie = g3_socket::tcp::socket_cookie(&sock);
                     let peer = SocketAddr::new(ip, task_conf.upstream.port());
                     running_connection += 1;
                     spawn_new_connection = false;
@@ -225,7 +227,7 @@ impl DirectFloatEscaper {
                         match tokio::time::timeout(each_timeout, sock.connect(peer)).await {
                             Ok(Ok(stream)) => {
                                 stats.tcp.connect.add_success();
-                                (Ok(stream), peer, bind)
+                                (Ok(stream), peer, bind, socket_cookie)
                             }
                             Ok(Err(e)) => {
                                 stats.tcp.connect.add_error();
@@ -233,11 +235,17 @@ impl DirectFloatEscaper {
                                     Err(TcpConnectError::ConnectFailed(ConnectError::from(e))),
                                     peer,
                                     bind,
+                                    socket_cookie,
                                 )
                             }
                             Err(_) => {
                                 stats.tcp.connect.add_timeout();
-                                (Err(TcpConnectError::TimeoutByRule), peer, bind)
+                                (
+                                    Err(TcpConnectError::TimeoutByRule),
+                                    peer,
+                                    bind,
+                                    socket_cookie,
+                                )
                             }
                         }
                     });
@@ -256,6 +264,7 @@ impl DirectFloatEscaper {
                                 running_connection -= 1;
                                 let peer_addr = r.1;
                                 let bind = r.2;
+                                tcp_notes.socket_cookie = r.3;
                                 tcp_notes.next = Some(peer_addr);
                                 tcp_notes.bind = BindAddr::Ip(bind.ip);
                                 tcp_notes.expire = bind.expire_datetime;
@@ -266,6 +275,7 @@ impl DirectFloatEscaper {
                                             .local_addr()
                                             .map_err(TcpConnectError::SetupSocketFailed)?;
                                         self.stats.tcp.connect.add_established();
+                                        self.stats.tcp.connect.add_happy_established(peer_addr);
                                         tcp_notes.local = Some(local_addr);
                                         tcp_notes.chained.target_addr = Some(peer_addr);
                                         tcp_notes.chained.outgoing_addr = Some(local_addr);
@@ -18,6 +18,7 @@ use std::sync::Arc;
 
 use anyhow::anyhow;
 use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::time::Instant;
 
 use g3_daemon::stat::remote::{
     ArcTcpConnectionTaskRemoteStats, TcpConnectionTaskRemoteStatsWrapper,
@@ -58,10 +59,25 @@ impl DirectFloatEscaper {
         let connector = SslConnector::new(ssl, stream)
             .map_err(|e| TcpConnectError::InternalTlsClientError(anyhow::Error::new(e)))?;
 
+        let instant_now = Instant::now();
         match tokio::time::timeout(task_conf.handshake_timeout(), connector.connect()).await {
-            Ok(Ok(stream)) => Ok((stream, bind)),
+            Ok(Ok(stream)) => {
+                if self.config.general.tls_handshake_log.sample_success() {
+                    EscapeLogForTlsHandshake {
+                        upstream: task_conf.tcp.upstream,
+                        tcp_notes,
+                        task_id: &task_notes.id,
+                        tls_name: task_conf.tls_name,
+                        tls_peer: task_conf.tcp.upstream,
+                        tls_application,
+                        tls_handshake_duration: instant_now.elapsed(),
+                    }
+                    .log_success(&self.escape_logger, stream.ssl());
+                }
+                Ok((stream, bind))
+            }
             Ok(Err(e)) => {
-                let e = anyhow::Error::new(e);
+                let e = task_conf.handshake_error(e);
                 EscapeLogForTlsHandshake {
                     upstream: task_conf.tcp.upstream,
                     tcp_notes,
@@ -69,6 +85,7 @@ impl DirectFloatEscaper {
                     tls_name: task_conf.tls_name,
                     tls_peer: task_conf.tcp.upstream,
                     tls_application,
+                    tls_handshake_duration: instant_now.elapsed(),
                 }
                 .log(&self.escape_logger, &e);
                 Err(TcpConnectError::UpstreamTlsHandshakeFailed(e))
@@ -82,6 +99,7 @@ impl DirectFloatEscaper {
                     tls_name: task_conf.tls_name,
                     tls_peer: task_conf.tcp.upstream,
                     tls_application,
+                    tls_handshake_duration: instant_now.elapsed(),
                 }
                 .log(&self.escape_logger, &e);
                 Err(TcpConnectError::UpstreamTlsHandshakeTimeout)
@@ -85,6 +85,7 @@ impl DivertTcpEscaper {
         let (sock, bind) = self.prepare_connect_socket(peer.ip())?;
         tcp_notes.next = Some(peer);
         tcp_notes.bind = bind;
+        tcp_notes.socket_cookie = g3_socket::tcp::socket_cookie(&sock);
 
         let instant_now = Instant::now();
 
@@ -178,6 +179,7 @@ impl DivertTcpEscaper {
             if spawn_new_connection {
                 if let Some(ip) = ips.pop() {
                     let (sock, bind) = self.prepare_connect_socket(ip)?;
+                    let socket_cookie = g3_socket::tcp::socket_cookie(&sock);
                     let peer = SocketAddr::new(ip, peer_port);
                     running_connection += 1;
                     spawn_new_connection = false;
@@ -188,7 +190,7 @@ impl DivertTcpEscaper {
                         match tokio::time::timeout(each_timeout, sock.connect(peer)).await {
                             Ok(Ok(stream)) => {
                                 stats.tcp.connect.add_success();
-                                (Ok(stream), peer, bind)
+                                (Ok(stream), peer, bind, socket_cookie)
                             }
                             Ok(Err(e)) => {
                                 stats.tcp.connect.add_error();
@@ -196,11 +198,17 @@ impl DivertTcpEscaper {
                                     Err(TcpConnectError::ConnectFailed(ConnectError::from(e))),
                                     peer,
                                     bind,
+                                    socket_cookie,
                                 )
                             }
                             Err(_) => {
                                 stats.tcp.connect.add_timeout();
-                                (Err(TcpConnectError::TimeoutByRule), peer, bind)
+                                (
+                                    Err(TcpConnectError::TimeoutByRule),
+                                    peer,
+                                    bind,
+                                    socket_cookie,
+                                )
                             }
                         }
                     });
@@ -220,12 +228,14 @@ impl DivertTcpEscaper {
                                 let peer_addr = r.1;
                                 tcp_notes.next = Some(peer_addr);
                                 tcp_notes.bind = r.2;
+                                tcp_notes.socket_cookie = r.3;
                                 match r.0 {
                                     Ok(ups_stream) => {
                                         let local_addr = ups_stream
                                             .local_addr()
                                             .map_err(TcpConnectError::SetupSocketFailed)?;
                                         self.stats.tcp.connect.add_established();
+                                        self.stats.tcp.connect.add_happy_established(peer_addr);
                                         tcp_notes.local = Some(local_addr);
                                         // the chained outgoing addr is not detected at here
                                         return Ok(ups_stream);
@@ -0,0 +1,395 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::hash::Hash;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use ahash::AHashMap;
+use anyhow::anyhow;
+use bytes::Bytes;
+use h2::client::SendRequest;
+use http::{HeaderMap, HeaderName, HeaderValue, Method, Request, StatusCode, Version};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+use tokio::time::Instant;
+
+use g3_h2::{H2StreamReader, H2StreamWriter};
+use g3_types::net::UpstreamAddr;
+
+use crate::module::tcp_connect::{TcpConnectError, TcpConnectTaskNotes};
+
+/// The connection specific headers that are not allowed in HTTP/2
+const HOP_BY_HOP_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "transfer-encoding",
+    "upgrade",
+    "te",
+];
+
+fn build_headers(append_http_headers: &[String]) -> anyhow::Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    for line in append_http_headers {
+        let Some((name, value)) = line.trim_end().split_once(':') else {
+            return Err(anyhow!("invalid http header line {line}"));
+        };
+        let name = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|e| anyhow!("invalid http header name in line {line}: {e}"))?;
+        if HOP_BY_HOP_HEADERS.contains(&name.as_str()) {
+            continue;
+        }
+        let value = HeaderValue::from_str(value.trim())
+            .map_err(|e| anyhow!("invalid http header value in line {line}: {e}"))?;
+        headers.append(name, value);
+    }
+    Ok(headers)
+}
+
+/// Copy the notes about the shared connection to the notes of a new stream on it.
+///
+/// The notes about the task itself, like the escaper, the selected next proxy and the connect
+/// tries and duration, will be kept.
+fn copy_connection_notes(dst: &mut TcpConnectTaskNotes, src: &TcpConnectTaskNotes) {
+    dst.bind = src.bind;
+    dst.next = src.next;
+    dst.local = src.local;
+    dst.expire = src.expire;
+    dst.egress.clone_from(&src.egress);
+    dst.chained.clone_from(&src.chained);
+    dst.socket_cookie = src.socket_cookie;
+    dst.tls_client_identity.clone_from(&src.tls_client_identity);
+    dst.next_isp_domain.clone_from(&src.next_isp_domain);
+    dst.negotiation_rtt = None;
+}
+
+#[derive(Clone)]
+struct H2PeerConnection {
+    id: u64,
+    send_request: SendRequest<Bytes>,
+    tcp_notes: TcpConnectTaskNotes,
+}
+
+/// The setup lock for one key, the tasks which want a new connection will wait on it,
+/// and then share the connection set up by the first one.
+pub(crate) struct H2SetupGuard<'a, K: Hash + Eq> {
+    setup_locks: &'a Mutex<AHashMap<K, Arc<AsyncMutex<()>>>>,
+    key: K,
+    lock: Arc<AsyncMutex<()>>,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl<K: Hash + Eq> Drop for H2SetupGuard<'_, K> {
+    fn drop(&mut self) {
+        drop(self.guard.take());
+        let mut setup_locks = self.setup_locks.lock().unwrap();
+        // remove the lock if there is no other waiter, the left refs are the map and us
+        if Arc::strong_count(&self.lock) == 2 {
+            setup_locks.remove(&self.key);
+        }
+    }
+}
+
+/// The shared HTTP/2 connections to the next proxies, all tunnels will be CONNECT streams on them.
+///
+/// There will be at most one connection for each key. The lock is only held to get or update the
+/// connection, so the connection setup and the stream negotiation won't block each other.
+/// The setup of new connections for the same key is serialized by the setup lock, so a burst of
+/// tasks will share one connection instead of doing their own handshakes.
+pub(crate) struct H2ConnectPool<K> {
+    headers: HeaderMap,
+    next_id: AtomicU64,
+    connections: Mutex<AHashMap<K, H2PeerConnection>>,
+    setup_locks: Mutex<AHashMap<K, Arc<AsyncMutex<()>>>>,
+}
+
+impl<K: Hash + Eq + Clone> H2ConnectPool<K> {
+    pub(crate) fn new(append_http_headers: &[String]) -> anyhow::Result<Self> {
+        Ok(H2ConnectPool {
+            headers: build_headers(append_http_headers)?,
+            next_id: AtomicU64::new(0),
+            connections: Mutex::new(AHashMap::new()),
+            setup_locks: Mutex::new(AHashMap::new()),
+        })
+    }
+
+    /// Wait for the in-flight connection setup for the same key to finish, and then lock the setup.
+    ///
+    /// The caller should call `get_send_request` again after the lock is acquired, as the
+    /// connection may have been set up by other tasks, and only set up a new one if still needed.
+    pub(crate) async fn lock_setup(&self, key: &K) -> H2SetupGuard<'_, K> {
+        let lock = self
+            .setup_locks
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_default()
+            .clone();
+        let guard = lock.clone().lock_owned().await;
+        H2SetupGuard {
+            setup_locks: &self.setup_locks,
+            key: key.clone(),
+            lock,
+            guard: Some(guard),
+        }
+    }
+
+    /// Get a ready sender on the shared connection, and copy the connection notes to `tcp_notes`.
+    ///
+    /// None will be returned if no usable connection found, and the caller should set up a new one.
+    pub(crate) async fn get_send_request(
+        &self,
+        key: &K,
+        tcp_notes: &mut TcpConnectTaskNotes,
+    ) -> Option<SendRequest<Bytes>> {
+        let c = self.connections.lock().unwrap().get(key).cloned()?;
+        match c.send_request.ready().await {
+            Ok(send_request) => {
+                copy_connection_notes(tcp_notes, &c.tcp_notes);
+                Some(send_request)
+            }
+            Err(_) => {
+                // the old connection is no longer usable
+                let mut connections = self.connections.lock().unwrap();
+                if connections.get(key).map(|v| v.id) == Some(c.id) {
+                    connections.remove(key);
+                }
+                None
+            }
+        }
+    }
+
+    /// Do the HTTP/2 handshake on the new connection, and add it to the pool.
+    ///
+    /// This should be called with the setup lock held. The old connection for the same key will be
+    /// replaced, and it will be closed after all its streams are done.
+    pub(crate) async fn add_connection<S>(
+        &self,
+        key: K,
+        stream: S,
+        tcp_notes: &TcpConnectTaskNotes,
+    ) -> Result<SendRequest<Bytes>, TcpConnectError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let (send_request, connection) = h2::client::handshake(stream)
+            .await
+            .map_err(|e| TcpConnectError::NegotiationWriteFailed(io::Error::other(e)))?;
+        tokio::spawn(async move {
+            let _ = connection.await;
+        });
+        let send_request = send_request
+            .ready()
+            .await
+            .map_err(|e| TcpConnectError::NegotiationWriteFailed(io::Error::other(e)))?;
+
+        let connection = H2PeerConnection {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            send_request: send_request.clone(),
+            tcp_notes: tcp_notes.clone(),
+        };
+        self.connections.lock().unwrap().insert(key, connection);
+        Ok(send_request)
+    }
+
+    /// Send the CONNECT request on the shared connection, the negotiation rtt will be set in `tcp_notes`.
+    ///
+    /// The response status will be returned, and the streams should only be used if it's success.
+    pub(crate) async fn send_connect_request(
+        &self,
+        mut send_request: SendRequest<Bytes>,
+        upstream: &UpstreamAddr,
+        tcp_notes: &mut TcpConnectTaskNotes,
+    ) -> Result<(StatusCode, H2StreamReader, H2StreamWriter), TcpConnectError> {
+        let mut req = Request::builder()
+            .method(Method::CONNECT)
+            .version(Version::HTTP_2)
+            .uri(upstream.to_string())
+            .body(())
+            .map_err(|_| TcpConnectError::InternalServerError("failed to build h2 request"))?;
+        req.headers_mut().clone_from(&self.headers);
+
+        let negotiation_start = Instant::now();
+        let (rsp_fut, send_stream) = send_request
+            .send_request(req, false)
+            .map_err(|e| TcpConnectError::NegotiationWriteFailed(io::Error::other(e)))?;
+        let rsp = rsp_fut
+            .await
+            .map_err(|e| TcpConnectError::NegotiationReadFailed(io::Error::other(e)))?;
+        tcp_notes.negotiation_rtt = Some(negotiation_start.elapsed());
+
+        let status = rsp.status();
+        Ok((
+            status,
+            H2StreamReader::new(rsp.into_body()),
+            H2StreamWriter::new(send_stream),
+        ))
+    }
+}
+
+/// Get the error for the non-success response status of the CONNECT request
+pub(crate) fn connect_rejected_error(status: StatusCode) -> TcpConnectError {
+    if matches!(status.as_u16(), 504 | 522 | 524) {
+        TcpConnectError::NegotiationPeerTimeout
+    } else {
+        TcpConnectError::NegotiationRejected(format!(
+            "rejected by remote proxy with response {status}"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    #[test]
+    fn filter_hop_by_hop_headers() {
+        let lines = [
+            "Proxy-Authorization: Basic dTpw\r\n".to_string(),
+            "Connection: keep-alive\r\n".to_string(),
+            "Proxy-Connection: keep-alive\r\n".to_string(),
+            "Keep-Alive: timeout=5\r\n".to_string(),
+            "X-Forwarded-For: 192.0.2.1\r\n".to_string(),
+        ];
+        let headers = build_headers(&lines).unwrap();
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers.get("proxy-authorization").unwrap(), "Basic dTpw");
+        assert_eq!(headers.get("x-forwarded-for").unwrap(), "192.0.2.1");
+        assert!(!headers.contains_key("connection"));
+
+        assert!(build_headers(&["invalid line".to_string()]).is_err());
+    }
+
+    #[test]
+    fn copy_notes() {
+        let shared = TcpConnectTaskNotes {
+            tries: 3,
+            duration: Duration::from_millis(100),
+            negotiation_rtt: Some(Duration::from_millis(10)),
+            next: Some(SocketAddr::from_str("192.0.2.1:3128").unwrap()),
+            local: Some(SocketAddr::from_str("192.0.2.2:40000").unwrap()),
+            socket_cookie: Some(1),
+            ..Default::default()
+        };
+
+        let mut notes = TcpConnectTaskNotes {
+            tries: 1,
+            ..Default::default()
+        };
+        copy_connection_notes(&mut notes, &shared);
+        assert_eq!(notes.next, shared.next);
+        assert_eq!(notes.local, shared.local);
+        assert_eq!(notes.socket_cookie, Some(1));
+        assert_eq!(notes.tries, 1);
+        assert_eq!(notes.duration, Duration::ZERO);
+        assert!(notes.negotiation_rtt.is_none());
+    }
+
+    async fn run_server(stream: tokio::io::DuplexStream) {
+        let mut connection = h2::server::handshake(stream).await.unwrap();
+        while let Some(Ok((req, mut respond))) = connection.accept().await {
+            let status = if req.uri().authority().map(|v| v.as_str()) == Some("example.net:443") {
+                StatusCode::OK
+            } else {
+                StatusCode::FORBIDDEN
+            };
+            let rsp = http::Response::builder().status(status).body(()).unwrap();
+            let _ = respond.send_response(rsp, false);
+        }
+    }
+
+    #[tokio::test]
+    async fn pool() {
+        let pool = H2ConnectPool::<u8>::new(&["X-Test: 1".to_string()]).unwrap();
+        let mut tcp_notes = TcpConnectTaskNotes::default();
+        assert!(pool.get_send_request(&0, &mut tcp_notes).await.is_none());
+
+        let (client_s, server_s) = tokio::io::duplex(4096);
+        let server = tokio::spawn(run_server(server_s));
+
+        tcp_notes.next = Some(SocketAddr::from_str("192.0.2.1:3128").unwrap());
+        let send_request = pool.add_connection(0, client_s, &tcp_notes).await.unwrap();
+
+        let upstream = UpstreamAddr::from_str("example.net:443").unwrap();
+        let (status, _r, _w) = pool
+            .send_connect_request(send_request, &upstream, &mut tcp_notes)
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert!(tcp_notes.negotiation_rtt.is_some());
+
+        let mut notes = TcpConnectTaskNotes::default();
+        let send_request = pool.get_send_request(&0, &mut notes).await.unwrap();
+        assert_eq!(notes.next, tcp_notes.next);
+        assert!(notes.negotiation_rtt.is_none());
+        let upstream = UpstreamAddr::from_str("example.org:443").unwrap();
+        let (status, _r, _w) = pool
+            .send_connect_request(send_request, &upstream, &mut notes)
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert!(matches!(
+            connect_rejected_error(status),
+            TcpConnectError::NegotiationRejected(_)
+        ));
+
+        // the closed connection should be removed,
+        // retry as the client side connection task may not notice the close immediately
+        server.abort();
+        let _ = server.await;
+        let mut removed = false;
+        for _ in 0..100 {
+            let mut notes = TcpConnectTaskNotes::default();
+            if pool.get_send_request(&0, &mut notes).await.is_none() {
+                removed = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(removed);
+        assert!(pool.connections.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn setup_lock() {
+        let pool = Arc::new(H2ConnectPool::<u8>::new(&[]).unwrap());
+
+        let guard = pool.lock_setup(&0).await;
+        // other keys should not be blocked
+        let other = pool.lock_setup(&1).await;
+        drop(other);
+
+        let (tx, mut rx) = tokio::sync::oneshot::channel();
+        let waiter = tokio::spawn({
+            let pool = pool.clone();
+            async move {
+                let _guard = pool.lock_setup(&0).await;
+                let _ = tx.send(());
+            }
+        });
+        tokio::task::yield_now().await;
+        assert!(rx.try_recv().is_err());
+
+        drop(guard);
+        waiter.await.unwrap();
+        assert!(pool.setup_locks.lock().unwrap().is_empty());
+    }
+}
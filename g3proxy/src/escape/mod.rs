@@ -17,7 +17,9 @@
 use std::collections::BTreeSet;
 use std::net::IpAddr;
 use std::sync::Arc;
+use std::time::Duration;
 
+use anyhow::anyhow;
 use async_trait::async_trait;
 
 use g3_daemon::stat::remote::ArcTcpConnectionTaskRemoteStats;
@@ -50,15 +52,31 @@ pub(crate) use registry::{foreach as foreach_escaper, get_names, get_or_insert_d
 
 mod stats;
 pub(crate) use stats::{
-    ArcEscaperInternalStats, ArcEscaperStats, EscaperForbiddenSnapshot, EscaperForbiddenStats,
-    EscaperInterfaceStats, EscaperInternalStats, EscaperStats, EscaperTcpConnectSnapshot,
-    EscaperTcpStats, EscaperTlsSnapshot, EscaperTlsStats, EscaperUdpStats, RouteEscaperSnapshot,
+    ArcEscaperInternalStats, ArcEscaperStats, EscaperCircuitBreakerSnapshot,
+    EscaperCircuitBreakerStats, EscaperConnectErrorSnapshot, EscaperConnectErrorStats,
+    EscaperForbiddenSnapshot, EscaperForbiddenStats, EscaperInterfaceStats, EscaperInternalStats,
+    EscaperNegotiationQueueStats, EscaperPeerExpireSnapshot, EscaperPeerFeedSnapshot,
+    EscaperPeerFeedStats, EscaperPeerHealthSnapshot, EscaperPeerHealthStats, EscaperStats,
+    EscaperTcpConnectSnapshot, EscaperTcpStats, EscaperTlsSnapshot, EscaperTlsStats,
+    EscaperUdpStats, EscaperUpstreamAgentStats, EscaperUpstreamRttStats,
+    EscaperUpstreamSelectStats, EscaperUpstreamTunnelStats, RouteEscaperSnapshot,
     RouteEscaperStats,
 };
 
+mod circuit_breaker;
+pub(crate) use circuit_breaker::EscaperCircuitBreaker;
+
 mod egress_path;
 pub(crate) use egress_path::EgressPathSelection;
 
+mod tls_identity;
+pub(crate) use tls_identity::TlsClientIdentities;
+
+mod quit;
+pub(crate) use quit::force_quit_negotiation;
+
+mod h2_connect;
+
 mod comply_audit;
 mod direct_fixed;
 mod direct_float;
@@ -170,6 +188,13 @@ pub(crate) trait Escaper: EscaperInternal {
 
     async fn publish(&self, data: String) -> anyhow::Result<()>;
 
+    /// Validate that a tunnel to the target can be set up through this escaper,
+    /// without creating any task stats or relaying any data.
+    /// The time spent on the negotiation will be returned on success.
+    async fn probe(&self, _target: UpstreamAddr) -> anyhow::Result<Duration> {
+        Err(anyhow!("probe is not supported by this escaper"))
+    }
+
     async fn tcp_setup_connection(
         &self,
         task_conf: &TcpConnectTaskConf<'_>,
@@ -58,7 +58,10 @@ mod stats;
 use stats::ProxyFloatEscaperStats;
 
 mod peer;
-use peer::{ArcNextProxyPeer, NextProxyPeer, PeerSet};
+use peer::{ArcNextProxyPeer, NextProxyPeer, PeerAliveTaskStats, PeerSet};
+
+mod prewarm;
+use prewarm::ProxyFloatPeerWarmer;
 
 mod source;
 
@@ -71,6 +74,7 @@ pub(super) struct ProxyFloatEscaper {
     stats: Arc<ProxyFloatEscaperStats>,
     quit_job_sender: Option<mpsc::Sender<()>>,
     peers: Arc<ArcSwap<PeerSet>>,
+    peer_warmer: Option<Arc<ProxyFloatPeerWarmer>>,
     tls_config: Arc<OpensslClientConfig>,
     escape_logger: Logger,
 }
@@ -89,6 +93,8 @@ impl ProxyFloatEscaper {
             .context("failed to setup tls client config")?;
 
         let config = Arc::new(config);
+        let tls_config = Arc::new(tls_config);
+        let peer_warmer = ProxyFloatPeerWarmer::new(&config, &stats, &tls_config);
 
         let peers = match peers {
             Some(peers) => peers,
@@ -105,17 +111,23 @@ impl ProxyFloatEscaper {
                 Arc::new(peers)
             }
         };
+        if let Some(warmer) = &peer_warmer {
+            warmer.update(&peers);
+        }
         let peers = Arc::new(ArcSwap::new(peers));
-        let quit_job_sender = source::new_job(Arc::clone(&config), Arc::clone(&peers))?;
+        let quit_job_sender =
+            source::new_job(Arc::clone(&config), Arc::clone(&peers), peer_warmer.clone())?;
 
         stats.set_extra_tags(config.extra_metrics_tags.clone());
+        stats.set_peers(Arc::clone(&peers));
 
         let escaper = ProxyFloatEscaper {
             config,
             stats,
             quit_job_sender,
             peers,
-            tls_config: Arc::new(tls_config),
+            peer_warmer,
+            tls_config,
             escape_logger,
         };
 
@@ -159,7 +171,7 @@ impl ProxyFloatEscaper {
 
     fn select_peer_from_escaper(&self) -> Option<ArcNextProxyPeer> {
         let peer_set = self.peers.load();
-        peer_set.select_random_peer()
+        peer_set.select_peer(self.config.peer_pick_policy)
     }
 
     fn select_peer(&self, task_notes: &ServerTaskNotes) -> anyhow::Result<ArcNextProxyPeer> {
@@ -201,7 +213,7 @@ impl Escaper for ProxyFloatEscaper {
     }
 
     async fn publish(&self, data: String) -> anyhow::Result<()> {
-        source::publish_peers(&self.config, &self.peers, data).await
+        source::publish_peers(&self.config, &self.peers, self.peer_warmer.as_ref(), data).await
     }
 
     async fn tcp_setup_connection(
@@ -217,6 +229,10 @@ impl Escaper for ProxyFloatEscaper {
         let peer = self
             .select_peer(task_notes)
             .map_err(TcpConnectError::EscaperNotUsable)?;
+        let task_stats = Arc::new(PeerAliveTaskStats::new(
+            task_stats,
+            peer.alive_count().guard(),
+        ));
         peer.tcp_setup_connection(self, task_conf, tcp_notes, task_notes, task_stats)
             .await
     }
@@ -234,6 +250,10 @@ impl Escaper for ProxyFloatEscaper {
         let peer = self
             .select_peer(task_notes)
             .map_err(TcpConnectError::EscaperNotUsable)?;
+        let task_stats = Arc::new(PeerAliveTaskStats::new(
+            task_stats,
+            peer.alive_count().guard(),
+        ));
         peer.tls_setup_connection(self, task_conf, tcp_notes, task_notes, task_stats)
             .await
     }
@@ -250,6 +270,10 @@ impl Escaper for ProxyFloatEscaper {
         let peer = self
             .select_peer(task_notes)
             .map_err(UdpConnectError::EscaperNotUsable)?;
+        let task_stats = Arc::new(PeerAliveTaskStats::new(
+            task_stats,
+            peer.alive_count().guard(),
+        ));
         peer.udp_setup_connection(self, task_conf, udp_notes, task_notes, task_stats)
             .await
     }
@@ -266,6 +290,10 @@ impl Escaper for ProxyFloatEscaper {
         let peer = self
             .select_peer(task_notes)
             .map_err(UdpRelaySetupError::EscaperNotUsable)?;
+        let task_stats = Arc::new(PeerAliveTaskStats::new(
+            task_stats,
+            peer.alive_count().guard(),
+        ));
         peer.udp_setup_relay(self, task_conf, udp_notes, task_notes, task_stats)
             .await
     }
@@ -334,6 +362,10 @@ impl EscaperInternal for ProxyFloatEscaper {
         let peer = self
             .select_peer(task_notes)
             .map_err(TcpConnectError::EscaperNotUsable)?;
+        let task_stats = Arc::new(PeerAliveTaskStats::new(
+            task_stats,
+            peer.alive_count().guard(),
+        ));
         peer.new_http_forward_connection(self, task_conf, tcp_notes, task_notes, task_stats)
             .await
     }
@@ -352,6 +384,10 @@ impl EscaperInternal for ProxyFloatEscaper {
         let peer = self
             .select_peer(task_notes)
             .map_err(TcpConnectError::EscaperNotUsable)?;
+        let task_stats = Arc::new(PeerAliveTaskStats::new(
+            task_stats,
+            peer.alive_count().guard(),
+        ));
         peer.new_https_forward_connection(self, task_conf, tcp_notes, task_notes, task_stats)
             .await
     }
@@ -0,0 +1,123 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use g3_daemon::stat::remote::TcpConnectionTaskRemoteStats;
+
+use crate::module::http_forward::HttpForwardTaskRemoteStats;
+use crate::module::udp_connect::UdpConnectTaskRemoteStats;
+use crate::module::udp_relay::UdpRelayTaskRemoteStats;
+
+/// The count of alive connections through a peer
+#[derive(Default)]
+pub(crate) struct PeerAliveCount(Arc<AtomicUsize>);
+
+impl PeerAliveCount {
+    #[inline]
+    pub(super) fn get(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn guard(&self) -> PeerAliveGuard {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        PeerAliveGuard(self.0.clone())
+    }
+}
+
+pub(crate) struct PeerAliveGuard(Arc<AtomicUsize>);
+
+impl Drop for PeerAliveGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Wrap the task remote stats, so the connection will be counted as alive
+/// until all the io stats references are dropped
+pub(crate) struct PeerAliveTaskStats<T: ?Sized> {
+    inner: Arc<T>,
+    _guard: PeerAliveGuard,
+}
+
+impl<T: ?Sized> PeerAliveTaskStats<T> {
+    pub(crate) fn new(inner: Arc<T>, guard: PeerAliveGuard) -> Self {
+        PeerAliveTaskStats {
+            inner,
+            _guard: guard,
+        }
+    }
+}
+
+impl TcpConnectionTaskRemoteStats
+    for PeerAliveTaskStats<dyn TcpConnectionTaskRemoteStats + Send + Sync>
+{
+    fn add_read_bytes(&self, size: u64) {
+        self.inner.add_read_bytes(size);
+    }
+
+    fn add_write_bytes(&self, size: u64) {
+        self.inner.add_write_bytes(size);
+    }
+}
+
+impl HttpForwardTaskRemoteStats
+    for PeerAliveTaskStats<dyn HttpForwardTaskRemoteStats + Send + Sync>
+{
+    fn add_read_bytes(&self, size: u64) {
+        self.inner.add_read_bytes(size);
+    }
+
+    fn add_write_bytes(&self, size: u64) {
+        self.inner.add_write_bytes(size);
+    }
+}
+
+impl UdpConnectTaskRemoteStats for PeerAliveTaskStats<dyn UdpConnectTaskRemoteStats + Send + Sync> {
+    fn add_recv_bytes(&self, size: u64) {
+        self.inner.add_recv_bytes(size);
+    }
+
+    fn add_recv_packets(&self, n: usize) {
+        self.inner.add_recv_packets(n);
+    }
+
+    fn add_send_bytes(&self, size: u64) {
+        self.inner.add_send_bytes(size);
+    }
+
+    fn add_send_packets(&self, n: usize) {
+        self.inner.add_send_packets(n);
+    }
+}
+
+impl UdpRelayTaskRemoteStats for PeerAliveTaskStats<dyn UdpRelayTaskRemoteStats + Send + Sync> {
+    fn add_recv_bytes(&self, size: u64) {
+        self.inner.add_recv_bytes(size);
+    }
+
+    fn add_recv_packets(&self, n: usize) {
+        self.inner.add_recv_packets(n);
+    }
+
+    fn add_send_bytes(&self, size: u64) {
+        self.inner.add_send_bytes(size);
+    }
+
+    fn add_send_packets(&self, n: usize) {
+        self.inner.add_send_packets(n);
+    }
+}
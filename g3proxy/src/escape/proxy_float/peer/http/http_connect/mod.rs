@@ -24,7 +24,10 @@ use g3_http::connect::{HttpConnectRequest, HttpConnectResponse};
 use g3_io_ext::{AsyncStream, FlexBufReader, LimitedStream, OnceBufReader};
 use g3_openssl::SslStream;
 
-use super::{ProxyFloatEscaper, ProxyFloatHttpPeer};
+use super::{
+    check_http_connect_rsp_body, set_http_connect_chained_notes, ProxyFloatEscaper,
+    ProxyFloatHttpPeer,
+};
 use crate::log::escape::tls_handshake::TlsApplication;
 use crate::module::tcp_connect::{
     TcpConnectError, TcpConnectRemoteWrapperStats, TcpConnectResult, TcpConnectTaskConf,
@@ -40,9 +43,14 @@ impl ProxyFloatHttpPeer {
         tcp_notes: &mut TcpConnectTaskNotes,
         task_notes: &ServerTaskNotes,
     ) -> Result<FlexBufReader<LimitedStream<TcpStream>>, TcpConnectError> {
-        let mut stream = escaper
-            .tcp_new_connection(self, task_conf, tcp_notes, task_notes)
-            .await?;
+        let mut stream = match escaper.claim_warm_tcp_connection(self, tcp_notes) {
+            Some(stream) => stream,
+            None => {
+                escaper
+                    .tcp_new_connection(self, task_conf, tcp_notes, task_notes)
+                    .await?
+            }
+        };
 
         let req =
             HttpConnectRequest::new(task_conf.upstream, &self.shared_config.append_http_headers);
@@ -51,11 +59,17 @@ impl ProxyFloatHttpPeer {
             .map_err(TcpConnectError::NegotiationWriteFailed)?;
 
         let mut buf_stream = FlexBufReader::new(stream);
-        let _ =
+        let rsp =
             HttpConnectResponse::recv(&mut buf_stream, self.http_connect_rsp_hdr_max_size).await?;
-
-        // TODO detect and set outgoing_addr and target_addr for supported remote proxies
-        // set with the registered public ip by default
+        check_http_connect_rsp_body(
+            escaper,
+            self.http_connect_rsp_reject_body,
+            &rsp,
+            task_conf,
+            tcp_notes,
+            task_notes,
+        )?;
+        set_http_connect_chained_notes(tcp_notes, &self.egress_info, &rsp);
 
         Ok(buf_stream)
     }
@@ -67,12 +81,25 @@ impl ProxyFloatHttpPeer {
         tcp_notes: &mut TcpConnectTaskNotes,
         task_notes: &ServerTaskNotes,
     ) -> Result<FlexBufReader<LimitedStream<TcpStream>>, TcpConnectError> {
-        tokio::time::timeout(
-            escaper.config.peer_negotiation_timeout,
+        let (deadline, by_task_deadline) =
+            task_notes.escaper_connect_deadline(escaper.config.peer_negotiation_timeout)?;
+        let negotiation = tokio::time::timeout_at(
+            deadline,
             self.http_connect_tcp_connect_to(escaper, task_conf, tcp_notes, task_notes),
-        )
-        .await
-        .map_err(|_| TcpConnectError::NegotiationPeerTimeout)?
+        );
+
+        tokio::select! {
+            r = negotiation => r.map_err(|_| {
+                if by_task_deadline {
+                    TcpConnectError::TaskDeadlineExceeded
+                } else {
+                    TcpConnectError::NegotiationPeerTimeout
+                }
+            })?,
+            _ = crate::escape::quit::wait_negotiation_quit() => {
+                Err(TcpConnectError::CanceledAsServerQuit)
+            }
+        }
     }
 
     pub(super) async fn http_connect_new_tcp_connection(
@@ -24,13 +24,15 @@ use serde_json::Value;
 use tokio::time::Instant;
 
 use g3_daemon::stat::remote::ArcTcpConnectionTaskRemoteStats;
+use g3_http::connect::HttpConnectResponse;
 use g3_types::auth::{Password, Username};
 use g3_types::net::{EgressInfo, TcpSockSpeedLimitConfig};
 
 use super::{
-    ArcNextProxyPeer, NextProxyPeer, NextProxyPeerInternal, ProxyFloatEscaper,
-    ProxyFloatEscaperStats,
+    ArcNextProxyPeer, NextProxyPeer, NextProxyPeerInternal, PeerAliveCount, ProxyFloatEscaper,
+    ProxyFloatEscaperStats, ProxyFloatPeerWarmer, WarmConnection,
 };
+use crate::log::escape::http_connect::EscapeLogForHttpConnect;
 use crate::module::http_forward::{ArcHttpForwardTaskRemoteStats, BoxHttpForwardConnection};
 use crate::module::tcp_connect::{
     TcpConnectError, TcpConnectResult, TcpConnectTaskConf, TcpConnectTaskNotes, TlsConnectTaskConf,
@@ -72,12 +74,63 @@ impl ProxyFloatHttpPeerSharedConfig {
     }
 }
 
+/// Set the chained notes with the registered public ip by default,
+/// and override with the addresses in the connect response if available
+pub(super) fn set_http_connect_chained_notes(
+    tcp_notes: &mut TcpConnectTaskNotes,
+    egress_info: &EgressInfo,
+    rsp: &HttpConnectResponse,
+) {
+    if let Some(ip) = egress_info.ip() {
+        tcp_notes.chained.outgoing_addr = Some(SocketAddr::new(ip, 0));
+    }
+
+    let (outgoing_addr, target_addr) =
+        crate::module::http_header::parse_remote_connection_info(&rsp.headers);
+    if outgoing_addr.is_some() {
+        tcp_notes.chained.outgoing_addr = outgoing_addr;
+    }
+    if target_addr.is_some() {
+        tcp_notes.chained.target_addr = target_addr;
+    }
+}
+
+/// Count and log the 2xx connect response with body, and fail the task if `reject_body` is set
+pub(super) fn check_http_connect_rsp_body(
+    escaper: &ProxyFloatEscaper,
+    reject_body: bool,
+    rsp: &HttpConnectResponse,
+    task_conf: &TcpConnectTaskConf<'_>,
+    tcp_notes: &TcpConnectTaskNotes,
+    task_notes: &ServerTaskNotes,
+) -> Result<(), TcpConnectError> {
+    if !rsp.has_unexpected_body() {
+        return Ok(());
+    }
+
+    escaper.stats.tcp.connect.add_unexpected_rsp_body();
+    let log = EscapeLogForHttpConnect {
+        upstream: task_conf.upstream,
+        tcp_notes,
+        task_id: &task_notes.id,
+    };
+    if reject_body {
+        let e = TcpConnectError::NegotiationUnexpectedBody;
+        log.log(&escaper.escape_logger, &e);
+        return Err(e);
+    }
+    log.log_discarded_body(&escaper.escape_logger, rsp.code);
+    Ok(())
+}
+
 pub(super) struct ProxyFloatHttpPeer {
     addr: SocketAddr,
     username: Username,
     password: Password,
     egress_info: EgressInfo,
+    alive_count: PeerAliveCount,
     http_connect_rsp_hdr_max_size: usize,
+    http_connect_rsp_reject_body: bool,
     shared_config: Arc<ProxyFloatHttpPeerSharedConfig>,
 }
 
@@ -88,7 +141,9 @@ impl ProxyFloatHttpPeer {
             username: Username::empty(),
             password: Password::empty(),
             egress_info: Default::default(),
+            alive_count: Default::default(),
             http_connect_rsp_hdr_max_size: 4096,
+            http_connect_rsp_reject_body: false,
             shared_config: Arc::new(Default::default()),
         })
     }
@@ -126,6 +181,11 @@ impl NextProxyPeerInternal for ProxyFloatHttpPeer {
                 self.http_connect_rsp_hdr_max_size = g3_json::humanize::as_usize(v)?;
                 Ok(())
             }
+            "http_connect_rsp_reject_body" => {
+                self.http_connect_rsp_reject_body = g3_json::value::as_bool(v)
+                    .context(format!("invalid bool value for key {k}"))?;
+                Ok(())
+            }
             "extra_append_headers" => {
                 if let Value::Object(map) = v {
                     let shared_config = Arc::make_mut(&mut self.shared_config);
@@ -180,6 +240,15 @@ impl NextProxyPeer for ProxyFloatHttpPeer {
         self.egress_info.clone()
     }
 
+    #[inline]
+    fn alive_count(&self) -> &PeerAliveCount {
+        &self.alive_count
+    }
+
+    async fn prewarm_connection(&self, warmer: &ProxyFloatPeerWarmer) -> Option<WarmConnection> {
+        warmer.new_tcp_connection(self).await
+    }
+
     async fn tcp_setup_connection(
         &self,
         escaper: &ProxyFloatEscaper,
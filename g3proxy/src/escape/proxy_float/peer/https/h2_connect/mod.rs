@@ -0,0 +1,149 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use bytes::Bytes;
+use h2::client::SendRequest;
+
+use g3_daemon::stat::remote::{
+    ArcTcpConnectionTaskRemoteStats, TcpConnectionTaskRemoteStatsWrapper,
+};
+use g3_h2::{H2StreamReader, H2StreamWriter};
+use g3_io_ext::{LimitedReader, LimitedWriter};
+use g3_types::net::AlpnProtocol;
+
+use super::{PeerAliveTaskStats, ProxyFloatEscaper, ProxyFloatHttpsPeer};
+use crate::escape::h2_connect::{connect_rejected_error, H2ConnectPool};
+use crate::module::tcp_connect::{
+    TcpConnectError, TcpConnectResult, TcpConnectTaskConf, TcpConnectTaskNotes,
+};
+use crate::serve::ServerTaskNotes;
+
+impl ProxyFloatHttpsPeer {
+    /// Get a ready sender on the shared connection, a new connection will be set up if needed,
+    /// and the tasks arrived during the setup will wait for and share it
+    async fn h2_get_send_request(
+        &self,
+        pool: &H2ConnectPool<()>,
+        escaper: &ProxyFloatEscaper,
+        task_conf: &TcpConnectTaskConf<'_>,
+        tcp_notes: &mut TcpConnectTaskNotes,
+        task_notes: &ServerTaskNotes,
+    ) -> Result<SendRequest<Bytes>, TcpConnectError> {
+        if let Some(send_request) = pool.get_send_request(&(), tcp_notes).await {
+            return Ok(send_request);
+        }
+        let _setup_guard = pool.lock_setup(&()).await;
+        // the connection may have been set up by other tasks while waiting
+        if let Some(send_request) = pool.get_send_request(&(), tcp_notes).await {
+            return Ok(send_request);
+        }
+
+        let stream = escaper
+            .tls_handshake_with_peer(
+                task_conf,
+                tcp_notes,
+                task_notes,
+                &self.tls_name,
+                self,
+                &self.tls_client_identities,
+                Some(AlpnProtocol::Http2),
+            )
+            .await?;
+        if stream.ssl().selected_alpn_protocol()
+            != Some(AlpnProtocol::Http2.identification_sequence())
+        {
+            return Err(TcpConnectError::PeerTlsHandshakeFailed(anyhow!(
+                "h2 is not negotiated by alpn"
+            )));
+        }
+        if let Some(ip) = self.egress_info.ip() {
+            tcp_notes.chained.outgoing_addr = Some(SocketAddr::new(ip, 0));
+        }
+        pool.add_connection((), stream, tcp_notes).await
+    }
+
+    async fn h2_connect_tcp_connect_to(
+        &self,
+        escaper: &ProxyFloatEscaper,
+        task_conf: &TcpConnectTaskConf<'_>,
+        tcp_notes: &mut TcpConnectTaskNotes,
+        task_notes: &ServerTaskNotes,
+    ) -> Result<(H2StreamReader, H2StreamWriter), TcpConnectError> {
+        let Some(pool) = &self.h2_pool else {
+            return Err(TcpConnectError::InternalServerError(
+                "no http2 connect pool set",
+            ));
+        };
+        let send_request = self
+            .h2_get_send_request(pool, escaper, task_conf, tcp_notes, task_notes)
+            .await?;
+
+        let (status, r, w) = pool
+            .send_connect_request(send_request, task_conf.upstream, tcp_notes)
+            .await?;
+        if status.is_success() {
+            Ok((r, w))
+        } else {
+            Err(connect_rejected_error(status))
+        }
+    }
+
+    pub(super) async fn h2_connect_new_tcp_connection(
+        &self,
+        escaper: &ProxyFloatEscaper,
+        task_conf: &TcpConnectTaskConf<'_>,
+        tcp_notes: &mut TcpConnectTaskNotes,
+        task_notes: &ServerTaskNotes,
+        task_stats: ArcTcpConnectionTaskRemoteStats,
+    ) -> TcpConnectResult {
+        let (deadline, by_task_deadline) =
+            task_notes.escaper_connect_deadline(escaper.config.peer_negotiation_timeout)?;
+        let negotiation = tokio::time::timeout_at(
+            deadline,
+            self.h2_connect_tcp_connect_to(escaper, task_conf, tcp_notes, task_notes),
+        );
+        let (r, w) = tokio::select! {
+            r = negotiation => r.map_err(|_| {
+                if by_task_deadline {
+                    TcpConnectError::TaskDeadlineExceeded
+                } else {
+                    TcpConnectError::NegotiationPeerTimeout
+                }
+            })??,
+            _ = crate::escape::quit::wait_negotiation_quit() => {
+                return Err(TcpConnectError::CanceledAsServerQuit);
+            }
+        };
+
+        // the stream will be counted as alive on the shared connection
+        // until all the io stats references are dropped
+        let task_stats: ArcTcpConnectionTaskRemoteStats = Arc::new(PeerAliveTaskStats::new(
+            task_stats,
+            self.h2_alive_streams.guard(),
+        ));
+        let mut wrapper_stats = TcpConnectionTaskRemoteStatsWrapper::new(task_stats);
+        wrapper_stats.push_other_stats(escaper.fetch_user_upstream_io_stats(task_notes));
+        let wrapper_stats = Arc::new(wrapper_stats);
+
+        let r = LimitedReader::new(r, wrapper_stats.clone());
+        let w = LimitedWriter::new(w, wrapper_stats);
+        Ok((Box::new(r), Box::new(w)))
+    }
+}
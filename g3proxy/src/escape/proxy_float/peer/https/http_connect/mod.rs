@@ -25,7 +25,10 @@ use g3_http::connect::{HttpConnectRequest, HttpConnectResponse};
 use g3_io_ext::{AsyncStream, FlexBufReader, LimitedReader, LimitedWriter, OnceBufReader};
 use g3_openssl::SslStream;
 
-use super::{ProxyFloatEscaper, ProxyFloatHttpsPeer};
+use super::{
+    check_http_connect_rsp_body, set_http_connect_chained_notes, ProxyFloatEscaper,
+    ProxyFloatHttpsPeer,
+};
 use crate::log::escape::tls_handshake::TlsApplication;
 use crate::module::tcp_connect::{
     TcpConnectError, TcpConnectResult, TcpConnectTaskConf, TcpConnectTaskNotes, TlsConnectTaskConf,
@@ -40,9 +43,22 @@ impl ProxyFloatHttpsPeer {
         tcp_notes: &mut TcpConnectTaskNotes,
         task_notes: &ServerTaskNotes,
     ) -> Result<FlexBufReader<SslStream<impl AsyncRead + AsyncWrite>>, TcpConnectError> {
-        let mut stream = escaper
-            .tls_handshake_with_peer(task_conf, tcp_notes, task_notes, &self.tls_name, self)
-            .await?;
+        let mut stream = match escaper.claim_warm_tls_connection(self, tcp_notes) {
+            Some(stream) => stream,
+            None => {
+                escaper
+                    .tls_handshake_with_peer(
+                        task_conf,
+                        tcp_notes,
+                        task_notes,
+                        &self.tls_name,
+                        self,
+                        &self.tls_client_identities,
+                        None,
+                    )
+                    .await?
+            }
+        };
 
         let req =
             HttpConnectRequest::new(task_conf.upstream, &self.shared_config.append_http_headers);
@@ -51,11 +67,17 @@ impl ProxyFloatHttpsPeer {
             .map_err(TcpConnectError::NegotiationWriteFailed)?;
 
         let mut buf_stream = FlexBufReader::new(stream);
-        let _ =
+        let rsp =
             HttpConnectResponse::recv(&mut buf_stream, self.http_connect_rsp_hdr_max_size).await?;
-
-        // TODO detect and set outgoing_addr and target_addr for supported remote proxies
-        // set with the registered public ip by default
+        check_http_connect_rsp_body(
+            escaper,
+            self.http_connect_rsp_reject_body,
+            &rsp,
+            task_conf,
+            tcp_notes,
+            task_notes,
+        )?;
+        set_http_connect_chained_notes(tcp_notes, &self.egress_info, &rsp);
 
         Ok(buf_stream)
     }
@@ -67,12 +89,25 @@ impl ProxyFloatHttpsPeer {
         tcp_notes: &mut TcpConnectTaskNotes,
         task_notes: &ServerTaskNotes,
     ) -> Result<FlexBufReader<SslStream<impl AsyncRead + AsyncWrite>>, TcpConnectError> {
-        tokio::time::timeout(
-            escaper.config.peer_negotiation_timeout,
+        let (deadline, by_task_deadline) =
+            task_notes.escaper_connect_deadline(escaper.config.peer_negotiation_timeout)?;
+        let negotiation = tokio::time::timeout_at(
+            deadline,
             self.http_connect_tcp_connect_to(escaper, task_conf, tcp_notes, task_notes),
-        )
-        .await
-        .map_err(|_| TcpConnectError::NegotiationPeerTimeout)?
+        );
+
+        tokio::select! {
+            r = negotiation => r.map_err(|_| {
+                if by_task_deadline {
+                    TcpConnectError::TaskDeadlineExceeded
+                } else {
+                    TcpConnectError::NegotiationPeerTimeout
+                }
+            })?,
+            _ = crate::escape::quit::wait_negotiation_quit() => {
+                Err(TcpConnectError::CanceledAsServerQuit)
+            }
+        }
     }
 
     pub(super) async fn http_connect_new_tcp_connection(
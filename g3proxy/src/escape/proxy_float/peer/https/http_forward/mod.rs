@@ -42,7 +42,15 @@ impl ProxyFloatHttpsPeer {
         task_stats: ArcHttpForwardTaskRemoteStats,
     ) -> Result<BoxHttpForwardConnection, TcpConnectError> {
         let tls_stream = escaper
-            .tls_handshake_with_peer(task_conf, tcp_notes, task_notes, &self.tls_name, self)
+            .tls_handshake_with_peer(
+                task_conf,
+                tcp_notes,
+                task_notes,
+                &self.tls_name,
+                self,
+                &self.tls_client_identities,
+                None,
+            )
             .await?;
         let (ups_r, ups_w) = tls_stream.into_split();
 
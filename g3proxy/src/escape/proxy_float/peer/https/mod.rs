@@ -27,8 +27,15 @@ use g3_daemon::stat::remote::ArcTcpConnectionTaskRemoteStats;
 use g3_types::auth::{Password, Username};
 use g3_types::net::{EgressInfo, Host, TcpSockSpeedLimitConfig};
 
-use super::http::ProxyFloatHttpPeerSharedConfig;
-use super::{ArcNextProxyPeer, NextProxyPeer, NextProxyPeerInternal, ProxyFloatEscaper};
+use super::http::{
+    check_http_connect_rsp_body, set_http_connect_chained_notes, ProxyFloatHttpPeerSharedConfig,
+};
+use super::{
+    ArcNextProxyPeer, NextProxyPeer, NextProxyPeerInternal, PeerAliveCount, PeerAliveTaskStats,
+    ProxyFloatEscaper, ProxyFloatPeerWarmer, WarmConnection,
+};
+use crate::escape::h2_connect::H2ConnectPool;
+use crate::escape::TlsClientIdentities;
 use crate::module::http_forward::{ArcHttpForwardTaskRemoteStats, BoxHttpForwardConnection};
 use crate::module::tcp_connect::{
     TcpConnectError, TcpConnectResult, TcpConnectTaskConf, TcpConnectTaskNotes, TlsConnectTaskConf,
@@ -43,6 +50,8 @@ use crate::module::udp_relay::{
 };
 use crate::serve::ServerTaskNotes;
 
+mod h2_connect;
+
 mod http_connect;
 mod http_forward;
 
@@ -52,7 +61,13 @@ pub(super) struct ProxyFloatHttpsPeer {
     username: Username,
     password: Password,
     egress_info: EgressInfo,
+    alive_count: PeerAliveCount,
     http_connect_rsp_hdr_max_size: usize,
+    http_connect_rsp_reject_body: bool,
+    http2_connect: bool,
+    h2_pool: Option<H2ConnectPool<()>>,
+    h2_alive_streams: PeerAliveCount,
+    tls_client_identities: TlsClientIdentities,
     shared_config: Arc<ProxyFloatHttpPeerSharedConfig>,
 }
 
@@ -64,7 +79,13 @@ impl ProxyFloatHttpsPeer {
             username: Username::empty(),
             password: Password::empty(),
             egress_info: Default::default(),
+            alive_count: Default::default(),
             http_connect_rsp_hdr_max_size: 4096,
+            http_connect_rsp_reject_body: false,
+            http2_connect: false,
+            h2_pool: None,
+            h2_alive_streams: Default::default(),
+            tls_client_identities: TlsClientIdentities::with_pick(),
             shared_config: Arc::new(Default::default()),
         })
     }
@@ -103,10 +124,30 @@ impl NextProxyPeerInternal for ProxyFloatHttpsPeer {
                     .context(format!("invalid tls server name value for key {k}"))?;
                 Ok(())
             }
+            "tls_client_identities" => self
+                .tls_client_identities
+                .set_identities_by_json(v)
+                .context(format!("invalid tls client identities value for key {k}")),
+            "tls_client_identity_pick" => self
+                .tls_client_identities
+                .set_pick_policy_by_json(v)
+                .context(format!(
+                    "invalid tls client identity pick value for key {k}"
+                )),
             "http_connect_rsp_header_max_size" => {
                 self.http_connect_rsp_hdr_max_size = g3_json::humanize::as_usize(v)?;
                 Ok(())
             }
+            "http_connect_rsp_reject_body" => {
+                self.http_connect_rsp_reject_body = g3_json::value::as_bool(v)
+                    .context(format!("invalid bool value for key {k}"))?;
+                Ok(())
+            }
+            "http2_connect" => {
+                self.http2_connect = g3_json::value::as_bool(v)
+                    .context(format!("invalid bool value for key {k}"))?;
+                Ok(())
+            }
             "extra_append_headers" => {
                 if let Value::Object(map) = v {
                     let shared_config = Arc::make_mut(&mut self.shared_config);
@@ -133,6 +174,16 @@ impl NextProxyPeerInternal for ProxyFloatHttpsPeer {
         if self.tls_name.is_empty() {
             self.tls_name = Host::Ip(self.addr.ip());
         }
+        if self.http2_connect {
+            if !self.tls_client_identities.is_empty() {
+                return Err(anyhow!(
+                    "tls client identities can not be used with http2 connect"
+                ));
+            }
+            let pool = H2ConnectPool::new(&shared_config.append_http_headers)
+                .context("failed to set http2 connect request headers")?;
+            self.h2_pool = Some(pool);
+        }
         Ok(())
     }
 
@@ -164,6 +215,29 @@ impl NextProxyPeer for ProxyFloatHttpsPeer {
         self.egress_info.clone()
     }
 
+    #[inline]
+    fn alive_count(&self) -> &PeerAliveCount {
+        &self.alive_count
+    }
+
+    #[inline]
+    fn tls_name(&self) -> Option<&Host> {
+        Some(&self.tls_name)
+    }
+
+    fn h2_alive_streams(&self) -> Option<usize> {
+        self.http2_connect.then(|| self.h2_alive_streams.get())
+    }
+
+    async fn prewarm_connection(&self, warmer: &ProxyFloatPeerWarmer) -> Option<WarmConnection> {
+        if self.http2_connect || !self.tls_client_identities.is_empty() {
+            // the client identity is selected per task,
+            // and the http2 connection is shared by all tasks
+            return None;
+        }
+        warmer.new_tls_connection(self, &self.tls_name).await
+    }
+
     async fn tcp_setup_connection(
         &self,
         escaper: &ProxyFloatEscaper,
@@ -172,8 +246,17 @@ impl NextProxyPeer for ProxyFloatHttpsPeer {
         task_notes: &ServerTaskNotes,
         task_stats: ArcTcpConnectionTaskRemoteStats,
     ) -> TcpConnectResult {
-        self.http_connect_new_tcp_connection(escaper, task_conf, tcp_notes, task_notes, task_stats)
+        if self.http2_connect {
+            self.h2_connect_new_tcp_connection(
+                escaper, task_conf, tcp_notes, task_notes, task_stats,
+            )
             .await
+        } else {
+            self.http_connect_new_tcp_connection(
+                escaper, task_conf, tcp_notes, task_notes, task_stats,
+            )
+            .await
+        }
     }
 
     async fn tls_setup_connection(
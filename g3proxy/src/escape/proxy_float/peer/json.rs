@@ -24,9 +24,9 @@ use serde_json::Value;
 use tokio::time::Instant;
 
 use super::{
-    ArcNextProxyPeer, CONFIG_KEY_PEER_ADDR, CONFIG_KEY_PEER_AREA, CONFIG_KEY_PEER_EIP,
-    CONFIG_KEY_PEER_EXPIRE, CONFIG_KEY_PEER_ID, CONFIG_KEY_PEER_ISP,
-    CONFIG_KEY_PEER_TCP_SOCK_SPEED_LIMIT, CONFIG_KEY_PEER_TYPE,
+    ArcNextProxyPeer, NextProxyPeer, CONFIG_KEY_PEER_ADDR, CONFIG_KEY_PEER_AREA,
+    CONFIG_KEY_PEER_EIP, CONFIG_KEY_PEER_EXPIRE, CONFIG_KEY_PEER_ID, CONFIG_KEY_PEER_ISP,
+    CONFIG_KEY_PEER_TCP_SOCK_SPEED_LIMIT, CONFIG_KEY_PEER_TTL, CONFIG_KEY_PEER_TYPE,
 };
 use crate::config::escaper::proxy_float::ProxyFloatEscaperConfig;
 
@@ -49,6 +49,8 @@ pub(super) fn do_parse_peer(
             _ => return Err(anyhow!("unsupported peer type {peer_type}")),
         };
         let mut peer_id = String::new();
+        let mut expire = None;
+        let mut ttl_expire = None;
         let peer_mut = Arc::get_mut(&mut peer).unwrap();
         for (k, v) in map {
             match g3_json::key::normalize(k).as_str() {
@@ -75,23 +77,18 @@ pub(super) fn do_parse_peer(
                     // not a required field, skip if value format is invalid
                 }
                 CONFIG_KEY_PEER_EXPIRE => {
-                    let datetime_expire_orig = g3_json::value::as_rfc3339_datetime(v)?;
-                    let Some(datetime_expire) = datetime_expire_orig
-                        .checked_sub_signed(escaper_config.expire_guard_duration)
-                    else {
-                        return Ok(None);
-                    };
-                    if datetime_expire <= datetime_now {
-                        return Ok(None);
-                    }
-                    let Ok(duration) = datetime_expire.signed_duration_since(datetime_now).to_std()
+                    expire = Some(g3_json::value::as_rfc3339_datetime(v)?);
+                }
+                CONFIG_KEY_PEER_TTL => {
+                    let ttl = g3_json::humanize::as_duration(v)
+                        .context(format!("invalid humanize duration value for key {k}"))?;
+                    let Some(datetime_expire) = chrono::Duration::from_std(ttl)
+                        .ok()
+                        .and_then(|ttl| datetime_now.checked_add_signed(ttl))
                     else {
-                        return Ok(None);
-                    };
-                    let Some(instant_expire) = instant_now.checked_add(duration) else {
-                        return Ok(None);
+                        return Err(anyhow!("out of range ttl value"));
                     };
-                    peer_mut.set_expire(datetime_expire_orig, instant_expire);
+                    ttl_expire = Some(datetime_expire);
                 }
                 CONFIG_KEY_PEER_TCP_SOCK_SPEED_LIMIT => {
                     let limit = g3_json::value::as_tcp_sock_speed_limit(v)?;
@@ -102,9 +99,47 @@ pub(super) fn do_parse_peer(
                     .context(format!("failed to parse key {k}"))?,
             }
         }
+        // ttl takes precedence over expire, regardless of the key order in the record
+        if let Some(datetime_expire) = ttl_expire.or(expire) {
+            if !set_peer_expire(
+                peer_mut,
+                datetime_expire,
+                escaper_config,
+                instant_now,
+                datetime_now,
+            ) {
+                return Ok(None);
+            }
+        }
         peer_mut.finalize()?;
         Ok(Some((peer_id, peer)))
     } else {
         Err(anyhow!("record root type should be json map"))
     }
 }
+
+/// Set the expire time for the peer, return false if it's already expired
+fn set_peer_expire(
+    peer_mut: &mut (dyn NextProxyPeer + Send + Sync),
+    datetime_expire_orig: DateTime<Utc>,
+    escaper_config: &ProxyFloatEscaperConfig,
+    instant_now: Instant,
+    datetime_now: DateTime<Utc>,
+) -> bool {
+    let Some(datetime_expire) =
+        datetime_expire_orig.checked_sub_signed(escaper_config.expire_guard_duration)
+    else {
+        return false;
+    };
+    if datetime_expire <= datetime_now {
+        return false;
+    }
+    let Ok(duration) = datetime_expire.signed_duration_since(datetime_now).to_std() else {
+        return false;
+    };
+    let Some(instant_expire) = instant_now.checked_add(duration) else {
+        return false;
+    };
+    peer_mut.set_expire(datetime_expire_orig, instant_expire);
+    true
+}
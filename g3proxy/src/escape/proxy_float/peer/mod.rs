@@ -15,6 +15,7 @@
  */
 
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use ahash::AHashMap;
@@ -26,9 +27,11 @@ use serde_json::Value;
 use tokio::time::Instant;
 
 use g3_daemon::stat::remote::ArcTcpConnectionTaskRemoteStats;
-use g3_types::net::{EgressInfo, TcpSockSpeedLimitConfig};
+use g3_types::net::{EgressInfo, Host, TcpSockSpeedLimitConfig};
 
+use super::prewarm::{ProxyFloatPeerWarmer, WarmConnection};
 use super::{ProxyFloatEscaper, ProxyFloatEscaperConfig, ProxyFloatEscaperStats};
+use crate::config::escaper::proxy_float::ProxyFloatPeerPickPolicy;
 use crate::module::http_forward::{ArcHttpForwardTaskRemoteStats, BoxHttpForwardConnection};
 use crate::module::tcp_connect::{
     TcpConnectError, TcpConnectResult, TcpConnectTaskConf, TcpConnectTaskNotes, TlsConnectTaskConf,
@@ -43,6 +46,10 @@ use crate::serve::ServerTaskNotes;
 
 mod json;
 
+mod alive;
+use alive::PeerAliveCount;
+pub(super) use alive::PeerAliveTaskStats;
+
 mod http;
 mod https;
 mod socks5;
@@ -52,6 +59,7 @@ const CONFIG_KEY_PEER_TYPE: &str = "type";
 const CONFIG_KEY_PEER_ID: &str = "id";
 const CONFIG_KEY_PEER_ADDR: &str = "addr";
 const CONFIG_KEY_PEER_EXPIRE: &str = "expire";
+const CONFIG_KEY_PEER_TTL: &str = "ttl";
 const CONFIG_KEY_PEER_ISP: &str = "isp";
 const CONFIG_KEY_PEER_EIP: &str = "eip";
 const CONFIG_KEY_PEER_AREA: &str = "area";
@@ -91,6 +99,22 @@ pub(super) trait NextProxyPeer: NextProxyPeerInternal {
     fn tcp_sock_speed_limit(&self) -> &TcpSockSpeedLimitConfig;
     fn expire_datetime(&self) -> Option<DateTime<Utc>>;
     fn egress_info(&self) -> EgressInfo;
+    fn alive_count(&self) -> &PeerAliveCount;
+
+    /// The count of alive streams on the shared http2 connection, if enabled
+    fn h2_alive_streams(&self) -> Option<usize> {
+        None
+    }
+
+    /// The tls server name used when connecting to this peer, if it's a tls peer
+    fn tls_name(&self) -> Option<&Host> {
+        None
+    }
+
+    /// Open a new idle connection to this peer, which can be claimed by later http connect tasks
+    async fn prewarm_connection(&self, _warmer: &ProxyFloatPeerWarmer) -> Option<WarmConnection> {
+        None
+    }
 
     async fn tcp_setup_connection(
         &self,
@@ -187,6 +211,7 @@ pub(super) fn parse_peers(
 pub(super) struct PeerSet {
     unnamed: Vec<ArcNextProxyPeer>,
     named: AHashMap<String, ArcNextProxyPeer>,
+    rr_index: AtomicUsize,
 }
 
 impl PeerSet {
@@ -198,12 +223,40 @@ impl PeerSet {
         self.named.insert(id, peer);
     }
 
-    pub(super) fn select_random_peer(&self) -> Option<ArcNextProxyPeer> {
+    pub(super) fn alive_peers(&self) -> impl Iterator<Item = &ArcNextProxyPeer> {
         self.unnamed
             .iter()
             .chain(self.named.values())
             .filter(|p| !p.is_expired())
-            .choose(&mut rand::thread_rng())
+    }
+
+    pub(super) fn select_peer(
+        &self,
+        pick_policy: ProxyFloatPeerPickPolicy,
+    ) -> Option<ArcNextProxyPeer> {
+        match pick_policy {
+            ProxyFloatPeerPickPolicy::Random => self.select_random_peer(),
+            ProxyFloatPeerPickPolicy::RoundRobin => self.select_round_robin_peer(),
+            ProxyFloatPeerPickPolicy::LeastConnection => self.select_least_connection_peer(),
+        }
+    }
+
+    fn select_random_peer(&self) -> Option<ArcNextProxyPeer> {
+        self.alive_peers().choose(&mut rand::thread_rng()).cloned()
+    }
+
+    fn select_round_robin_peer(&self) -> Option<ArcNextProxyPeer> {
+        let peers: Vec<&ArcNextProxyPeer> = self.alive_peers().collect();
+        if peers.is_empty() {
+            return None;
+        }
+        let i = self.rr_index.fetch_add(1, Ordering::Relaxed) % peers.len();
+        Some(peers[i].clone())
+    }
+
+    fn select_least_connection_peer(&self) -> Option<ArcNextProxyPeer> {
+        self.alive_peers()
+            .min_by_key(|p| p.alive_count().get())
             .cloned()
     }
 
@@ -217,8 +270,79 @@ impl PeerSet {
         None
     }
 
+    pub(super) fn count_alive_expired(&self) -> (u64, u64) {
+        let mut alive = 0;
+        let mut expired = 0;
+        for peer in self.unnamed.iter().chain(self.named.values()) {
+            if peer.is_expired() {
+                expired += 1;
+            } else {
+                alive += 1;
+            }
+        }
+        (alive, expired)
+    }
+
     #[inline]
     pub(super) fn select_named_peer(&self, id: &str) -> Option<ArcNextProxyPeer> {
         self.named.get(id).cloned()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn new_peer(addr: &str) -> ArcNextProxyPeer {
+        http::ProxyFloatHttpPeer::new_obj(SocketAddr::from_str(addr).unwrap())
+    }
+
+    #[test]
+    fn least_connection() {
+        let p1 = new_peer("127.0.0.1:1001");
+        let p2 = new_peer("127.0.0.1:1002");
+        let p3 = new_peer("127.0.0.1:1003");
+
+        let mut peer_set = PeerSet::default();
+        peer_set.insert_named("p1".to_string(), p1.clone());
+        peer_set.insert_named("p2".to_string(), p2.clone());
+        peer_set.push_unnamed(p3.clone());
+
+        let _g1: Vec<_> = (0..5).map(|_| p1.alive_count().guard()).collect();
+        let g2: Vec<_> = (0..3).map(|_| p2.alive_count().guard()).collect();
+        let _g3: Vec<_> = (0..8).map(|_| p3.alive_count().guard()).collect();
+
+        let peer = peer_set
+            .select_peer(ProxyFloatPeerPickPolicy::LeastConnection)
+            .unwrap();
+        assert_eq!(peer.peer_addr(), p2.peer_addr());
+
+        drop(g2);
+        assert_eq!(p2.alive_count().get(), 0);
+        let _g2: Vec<_> = (0..6).map(|_| p2.alive_count().guard()).collect();
+        let peer = peer_set
+            .select_peer(ProxyFloatPeerPickPolicy::LeastConnection)
+            .unwrap();
+        assert_eq!(peer.peer_addr(), p1.peer_addr());
+    }
+
+    #[test]
+    fn round_robin() {
+        let mut peer_set = PeerSet::default();
+        peer_set.push_unnamed(new_peer("127.0.0.1:1001"));
+        peer_set.push_unnamed(new_peer("127.0.0.1:1002"));
+
+        let a = peer_set
+            .select_peer(ProxyFloatPeerPickPolicy::RoundRobin)
+            .unwrap();
+        let b = peer_set
+            .select_peer(ProxyFloatPeerPickPolicy::RoundRobin)
+            .unwrap();
+        let c = peer_set
+            .select_peer(ProxyFloatPeerPickPolicy::RoundRobin)
+            .unwrap();
+        assert_ne!(a.peer_addr(), b.peer_addr());
+        assert_eq!(a.peer_addr(), c.peer_addr());
+    }
+}
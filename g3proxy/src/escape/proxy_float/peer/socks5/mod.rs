@@ -30,7 +30,7 @@ use g3_types::auth::{Password, Username};
 use g3_types::net::{EgressInfo, SocksAuth, TcpSockSpeedLimitConfig, UdpSockSpeedLimitConfig};
 
 use super::{
-    ArcNextProxyPeer, NextProxyPeer, NextProxyPeerInternal, ProxyFloatEscaper,
+    ArcNextProxyPeer, NextProxyPeer, NextProxyPeerInternal, PeerAliveCount, ProxyFloatEscaper,
     ProxyFloatEscaperStats,
 };
 use crate::module::http_forward::{ArcHttpForwardTaskRemoteStats, BoxHttpForwardConnection};
@@ -80,6 +80,7 @@ pub(super) struct ProxyFloatSocks5Peer {
     username: Username,
     password: Password,
     egress_info: EgressInfo,
+    alive_count: PeerAliveCount,
     shared_config: Arc<ProxyFloatSocks5PeerSharedConfig>,
     transmute_udp_peer_ip: Option<AHashMap<IpAddr, IpAddr>>,
     udp_sock_speed_limit: UdpSockSpeedLimitConfig,
@@ -93,6 +94,7 @@ impl ProxyFloatSocks5Peer {
             username: Username::empty(),
             password: Password::empty(),
             egress_info: Default::default(),
+            alive_count: Default::default(),
             shared_config: Arc::new(Default::default()),
             transmute_udp_peer_ip: None,
             udp_sock_speed_limit: Default::default(),
@@ -212,6 +214,11 @@ impl NextProxyPeer for ProxyFloatSocks5Peer {
         self.egress_info.clone()
     }
 
+    #[inline]
+    fn alive_count(&self) -> &PeerAliveCount {
+        &self.alive_count
+    }
+
     async fn tcp_setup_connection(
         &self,
         escaper: &ProxyFloatEscaper,
@@ -30,7 +30,10 @@ use g3_types::auth::{Password, Username};
 use g3_types::net::{EgressInfo, Host, TcpSockSpeedLimitConfig, UdpSockSpeedLimitConfig};
 
 use super::socks5::ProxyFloatSocks5PeerSharedConfig;
-use super::{ArcNextProxyPeer, NextProxyPeer, NextProxyPeerInternal, ProxyFloatEscaper};
+use super::{
+    ArcNextProxyPeer, NextProxyPeer, NextProxyPeerInternal, PeerAliveCount, ProxyFloatEscaper,
+};
+use crate::escape::TlsClientIdentities;
 use crate::module::http_forward::{ArcHttpForwardTaskRemoteStats, BoxHttpForwardConnection};
 use crate::module::tcp_connect::{
     TcpConnectError, TcpConnectResult, TcpConnectTaskConf, TcpConnectTaskNotes, TlsConnectTaskConf,
@@ -54,10 +57,12 @@ pub(super) struct ProxyFloatSocks5sPeer {
     username: Username,
     password: Password,
     egress_info: EgressInfo,
+    alive_count: PeerAliveCount,
     shared_config: Arc<ProxyFloatSocks5PeerSharedConfig>,
     transmute_udp_peer_ip: Option<AHashMap<IpAddr, IpAddr>>,
     udp_sock_speed_limit: UdpSockSpeedLimitConfig,
     end_on_control_closed: bool,
+    tls_client_identities: TlsClientIdentities,
 }
 
 impl ProxyFloatSocks5sPeer {
@@ -68,10 +73,12 @@ impl ProxyFloatSocks5sPeer {
             username: Username::empty(),
             password: Password::empty(),
             egress_info: Default::default(),
+            alive_count: Default::default(),
             shared_config: Arc::new(Default::default()),
             transmute_udp_peer_ip: None,
             udp_sock_speed_limit: Default::default(),
             end_on_control_closed: false,
+            tls_client_identities: TlsClientIdentities::with_pick(),
         })
     }
 
@@ -124,6 +131,16 @@ impl NextProxyPeerInternal for ProxyFloatSocks5sPeer {
                     .context(format!("invalid tls server name value for key {k}"))?;
                 Ok(())
             }
+            "tls_client_identities" => self
+                .tls_client_identities
+                .set_identities_by_json(v)
+                .context(format!("invalid tls client identities value for key {k}")),
+            "tls_client_identity_pick" => self
+                .tls_client_identities
+                .set_pick_policy_by_json(v)
+                .context(format!(
+                    "invalid tls client identity pick value for key {k}"
+                )),
             "transmute_udp_peer_ip" => {
                 if let Value::Object(_) = v {
                     let map = g3_json::value::as_hashmap(
@@ -192,6 +209,16 @@ impl NextProxyPeer for ProxyFloatSocks5sPeer {
         self.egress_info.clone()
     }
 
+    #[inline]
+    fn alive_count(&self) -> &PeerAliveCount {
+        &self.alive_count
+    }
+
+    #[inline]
+    fn tls_name(&self) -> Option<&Host> {
+        Some(&self.tls_name)
+    }
+
     async fn tcp_setup_connection(
         &self,
         escaper: &ProxyFloatEscaper,
@@ -46,7 +46,15 @@ impl ProxyFloatSocks5sPeer {
         task_notes: &ServerTaskNotes,
     ) -> Result<SslStream<impl AsyncRead + AsyncWrite>, TcpConnectError> {
         let mut stream = escaper
-            .tls_handshake_with_peer(task_conf, tcp_notes, task_notes, &self.tls_name, self)
+            .tls_handshake_with_peer(
+                task_conf,
+                tcp_notes,
+                task_notes,
+                &self.tls_name,
+                self,
+                &self.tls_client_identities,
+                None,
+            )
             .await?;
         let outgoing_addr = v5::client::socks5_connect_to(
             &mut stream,
@@ -98,7 +106,15 @@ impl ProxyFloatSocks5sPeer {
             upstream: &UpstreamAddr::empty(),
         };
         let mut ctl_stream = escaper
-            .tls_handshake_with_peer(&tcp_task_conf, tcp_notes, task_notes, &self.tls_name, self)
+            .tls_handshake_with_peer(
+                &tcp_task_conf,
+                tcp_notes,
+                task_notes,
+                &self.tls_name,
+                self,
+                &self.tls_client_identities,
+                None,
+            )
             .await
             .map_err(io::Error::other)?;
         let local_tcp_addr = tcp_notes
@@ -0,0 +1,558 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::hash_map::Entry;
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ahash::AHashMap;
+use log::debug;
+use tokio::net::TcpStream;
+use tokio::time::Instant;
+
+use g3_io_ext::LimitedStream;
+use g3_openssl::{SslConnector, SslStream};
+use g3_socket::BindAddr;
+use g3_types::net::{Host, OpensslClientConfig, UpstreamAddr, WeightedUpstreamAddr};
+
+use super::peer::{ArcNextProxyPeer, NextProxyPeer, NextProxyPeerInternal, PeerSet};
+use super::{ProxyFloatEscaper, ProxyFloatEscaperConfig, ProxyFloatEscaperStats};
+use crate::config::escaper::proxy_float::ProxyFloatPrewarmConfig;
+use crate::module::tcp_connect::TcpConnectTaskNotes;
+
+pub(super) enum WarmStream {
+    Tcp(LimitedStream<TcpStream>),
+    Tls(SslStream<LimitedStream<TcpStream>>),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum WarmStreamKind {
+    Tcp,
+    Tls,
+}
+
+impl WarmStream {
+    fn kind(&self) -> WarmStreamKind {
+        match self {
+            WarmStream::Tcp(_) => WarmStreamKind::Tcp,
+            WarmStream::Tls(_) => WarmStreamKind::Tls,
+        }
+    }
+
+    fn tcp_stream(&self) -> &TcpStream {
+        match self {
+            WarmStream::Tcp(s) => s.get_ref(),
+            WarmStream::Tls(s) => s.get_ref().get_ref(),
+        }
+    }
+}
+
+/// An idle connection opened in advance to a peer
+pub(super) struct WarmConnection {
+    stream: WarmStream,
+    bind: BindAddr,
+    local: SocketAddr,
+    idle_deadline: Instant,
+}
+
+impl WarmConnection {
+    fn is_usable(&self, now: Instant) -> bool {
+        self.idle_deadline > now && g3_socket::tcp::is_connection_alive(self.stream.tcp_stream())
+    }
+}
+
+#[derive(Default)]
+struct WarmIdleQueue {
+    idle: VecDeque<WarmConnection>,
+    /// the number of connections that are still being opened
+    pending: usize,
+}
+
+impl WarmIdleQueue {
+    /// Take the first usable connection of the given kind.
+    /// Unusable connections are dropped, and so are the ones of other kinds,
+    /// as the kind only changes if the peer at the same address has changed.
+    /// Return the connection found and the number of mismatched ones dropped.
+    fn take(&mut self, now: Instant, kind: WarmStreamKind) -> (Option<WarmConnection>, usize) {
+        let mut mismatched = 0;
+        while let Some(conn) = self.idle.pop_front() {
+            if !conn.is_usable(now) {
+                continue;
+            }
+            if conn.stream.kind() == kind {
+                return (Some(conn), mismatched);
+            }
+            mismatched += 1;
+        }
+        (None, mismatched)
+    }
+
+    /// Get the number of new connections needed to keep `count` connections
+    fn need(&self, count: usize) -> usize {
+        count.saturating_sub(self.idle.len() + self.pending)
+    }
+
+    fn finish_pending(&mut self, conn: Option<WarmConnection>) {
+        self.pending = self.pending.saturating_sub(1);
+        if let Some(conn) = conn {
+            self.idle.push_back(conn);
+        }
+    }
+
+    fn gc(&mut self, now: Instant) {
+        self.idle.retain(|conn| conn.is_usable(now));
+    }
+}
+
+/// The warm pools are keyed by both the peer address and the tls name, so the connections
+/// warmed up for the old tls name won't be used after the peer is updated
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+struct WarmPoolKey {
+    addr: SocketAddr,
+    tls_name: Option<Host>,
+}
+
+impl WarmPoolKey {
+    fn new<P: NextProxyPeer + ?Sized>(peer: &P) -> Self {
+        WarmPoolKey {
+            addr: peer.peer_addr(),
+            tls_name: peer.tls_name().cloned(),
+        }
+    }
+}
+
+struct PeerWarmPool {
+    peer: ArcNextProxyPeer,
+    expire: Option<Instant>,
+    queue: WarmIdleQueue,
+}
+
+impl PeerWarmPool {
+    fn new(peer: ArcNextProxyPeer) -> Self {
+        PeerWarmPool {
+            expire: peer.expire_instant(),
+            peer,
+            queue: WarmIdleQueue::default(),
+        }
+    }
+
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expire.map(|t| t <= now).unwrap_or(false)
+    }
+}
+
+/// Open and hold idle connections to newly added peers, so the first tasks
+/// to these peers can skip the connection setup
+pub(super) struct ProxyFloatPeerWarmer {
+    config: Arc<ProxyFloatEscaperConfig>,
+    prewarm: ProxyFloatPrewarmConfig,
+    stats: Arc<ProxyFloatEscaperStats>,
+    tls_config: Arc<OpensslClientConfig>,
+    pools: Mutex<AHashMap<WarmPoolKey, PeerWarmPool>>,
+}
+
+impl ProxyFloatPeerWarmer {
+    pub(super) fn new(
+        config: &Arc<ProxyFloatEscaperConfig>,
+        stats: &Arc<ProxyFloatEscaperStats>,
+        tls_config: &Arc<OpensslClientConfig>,
+    ) -> Option<Arc<Self>> {
+        let prewarm = config.prewarm.clone()?;
+        let gc_interval = (prewarm.idle_timeout / 2).max(Duration::from_secs(1));
+
+        let warmer = Arc::new(ProxyFloatPeerWarmer {
+            config: Arc::clone(config),
+            prewarm,
+            stats: Arc::clone(stats),
+            tls_config: Arc::clone(tls_config),
+            pools: Mutex::new(AHashMap::new()),
+        });
+
+        let weak_warmer = Arc::downgrade(&warmer);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(gc_interval);
+            interval.tick().await; // will tick immediately
+            loop {
+                interval.tick().await;
+                let Some(warmer) = weak_warmer.upgrade() else {
+                    break;
+                };
+                warmer.gc();
+            }
+        });
+
+        Some(warmer)
+    }
+
+    /// Drop the pools of the peers that are gone, and start warming up the new peers
+    pub(super) fn update(self: &Arc<Self>, peers: &PeerSet) {
+        let mut alive_peers = AHashMap::new();
+        for peer in peers.alive_peers() {
+            alive_peers.insert(WarmPoolKey::new(peer.as_ref()), peer);
+        }
+
+        let mut new_peers = Vec::new();
+        let mut pools = self.pools.lock().unwrap();
+        pools.retain(|key, _| alive_peers.contains_key(key));
+        for (key, peer) in alive_peers {
+            match pools.entry(key) {
+                Entry::Occupied(mut o) => {
+                    let pool = o.get_mut();
+                    pool.expire = peer.expire_instant();
+                    pool.peer = Arc::clone(peer);
+                }
+                Entry::Vacant(v) => {
+                    let pool = v.insert(PeerWarmPool::new(Arc::clone(peer)));
+                    pool.queue.pending = self.prewarm.count;
+                    new_peers.push(Arc::clone(peer));
+                }
+            }
+        }
+        drop(pools);
+
+        for peer in new_peers {
+            for _ in 0..self.prewarm.count {
+                self.spawn_warm(Arc::clone(&peer));
+            }
+        }
+    }
+
+    /// The caller should have increased the pending count of the pool
+    fn spawn_warm(self: &Arc<Self>, peer: ArcNextProxyPeer) {
+        let warmer = Arc::clone(self);
+        tokio::spawn(async move {
+            let conn = peer.prewarm_connection(&warmer).await;
+            warmer.put(&WarmPoolKey::new(peer.as_ref()), conn);
+        });
+    }
+
+    fn put(&self, key: &WarmPoolKey, conn: Option<WarmConnection>) {
+        let mut pools = self.pools.lock().unwrap();
+        // the connection will be dropped if the peer is gone
+        if let Some(pool) = pools.get_mut(key) {
+            pool.queue.finish_pending(conn);
+        }
+    }
+
+    /// Claim an idle connection of the given kind, and open new ones to keep the pool full
+    fn claim(self: &Arc<Self>, key: &WarmPoolKey, kind: WarmStreamKind) -> Option<WarmConnection> {
+        let now = Instant::now();
+        let mut pools = self.pools.lock().unwrap();
+        let pool = pools.get_mut(key)?;
+        if pool.is_expired(now) {
+            pool.queue.idle.clear();
+            return None;
+        }
+        let (conn, mismatched) = pool.queue.take(now, kind);
+        if mismatched > 0 {
+            debug!(
+                "escaper {}: dropped {mismatched} prewarmed connections to peer {} as they are not {kind:?} ones",
+                self.config.name, key.addr
+            );
+        }
+        let need = pool.queue.need(self.prewarm.count);
+        pool.queue.pending += need;
+        let peer = Arc::clone(&pool.peer);
+        drop(pools);
+
+        for _ in 0..need {
+            self.spawn_warm(Arc::clone(&peer));
+        }
+        conn
+    }
+
+    fn gc(&self) {
+        let now = Instant::now();
+        let mut pools = self.pools.lock().unwrap();
+        pools.retain(|_, pool| {
+            if pool.is_expired(now) {
+                return false;
+            }
+            pool.queue.gc(now);
+            true
+        });
+    }
+
+    async fn connect_tcp<P: NextProxyPeer>(
+        &self,
+        peer: &P,
+    ) -> Option<(LimitedStream<TcpStream>, BindAddr, SocketAddr)> {
+        let peer_addr = peer.peer_addr();
+        let bind = super::tcp_connect::select_bind(&self.config, peer_addr);
+        let sock = match super::tcp_connect::new_tcp_socket(&self.config, peer_addr, &bind) {
+            Ok(sock) => sock,
+            Err(e) => {
+                debug!(
+                    "escaper {}: failed to prewarm tcp connection to peer {peer_addr}: {e}",
+                    self.config.name
+                );
+                return None;
+            }
+        };
+        // the prewarm connections are not counted in the tcp connect stats of the escaper,
+        // as they are not made for any task
+        let ret =
+            tokio::time::timeout(self.config.tcp_connect_timeout, sock.connect(peer_addr)).await;
+        let stream = match ret {
+            Ok(Ok(stream)) => stream,
+            Ok(Err(e)) => {
+                debug!(
+                    "escaper {}: failed to prewarm tcp connection to peer {peer_addr}: {e}",
+                    self.config.name
+                );
+                return None;
+            }
+            Err(_) => {
+                debug!(
+                    "escaper {}: timed out to prewarm tcp connection to peer {peer_addr}",
+                    self.config.name
+                );
+                return None;
+            }
+        };
+        let local_addr = stream.local_addr().ok()?;
+
+        let limit_config = peer.tcp_sock_speed_limit();
+        let stream = LimitedStream::local_limited(
+            stream,
+            limit_config.shift_millis,
+            limit_config.max_south,
+            limit_config.max_north,
+            self.stats.clone(),
+        );
+        Some((stream, bind, local_addr))
+    }
+
+    pub(super) async fn new_tcp_connection<P: NextProxyPeer>(
+        &self,
+        peer: &P,
+    ) -> Option<WarmConnection> {
+        let (stream, bind, local) = self.connect_tcp(peer).await?;
+        Some(WarmConnection {
+            stream: WarmStream::Tcp(stream),
+            bind,
+            local,
+            idle_deadline: Instant::now() + self.prewarm.idle_timeout,
+        })
+    }
+
+    pub(super) async fn new_tls_connection<P: NextProxyPeer>(
+        &self,
+        peer: &P,
+        tls_name: &Host,
+    ) -> Option<WarmConnection> {
+        let (stream, bind, local) = self.connect_tcp(peer).await?;
+        let peer_addr = peer.peer_addr();
+
+        let ssl = self.tls_config.build_ssl(tls_name, peer_addr.port()).ok()?;
+        let connector = SslConnector::new(ssl, stream).ok()?;
+        let stream = match tokio::time::timeout(
+            self.tls_config.handshake_timeout,
+            connector.connect(),
+        )
+        .await
+        {
+            Ok(Ok(stream)) => stream,
+            Ok(Err(e)) => {
+                debug!(
+                    "escaper {}: failed to prewarm tls connection to peer {peer_addr}: {e}",
+                    self.config.name
+                );
+                return None;
+            }
+            Err(_) => {
+                debug!(
+                    "escaper {}: timed out to prewarm tls connection to peer {peer_addr}",
+                    self.config.name
+                );
+                return None;
+            }
+        };
+
+        Some(WarmConnection {
+            stream: WarmStream::Tls(stream),
+            bind,
+            local,
+            idle_deadline: Instant::now() + self.prewarm.idle_timeout,
+        })
+    }
+}
+
+impl ProxyFloatEscaper {
+    fn claim_warm_connection<P: NextProxyPeer>(
+        &self,
+        peer: &P,
+        kind: WarmStreamKind,
+        tcp_notes: &mut TcpConnectTaskNotes,
+    ) -> Option<WarmStream> {
+        let warmer = self.peer_warmer.as_ref()?;
+        let peer_addr = peer.peer_addr();
+        let conn = warmer.claim(&WarmPoolKey::new(peer), kind)?;
+
+        tcp_notes.bind = conn.bind;
+        tcp_notes.next_proxy = Some(WeightedUpstreamAddr::new(UpstreamAddr::from(peer_addr)));
+        tcp_notes.next = Some(peer_addr);
+        tcp_notes.expire = peer.expire_datetime();
+        tcp_notes.egress = Some(peer.egress_info());
+        // no connect attempt is made for this task
+        tcp_notes.tries = 0;
+        tcp_notes.duration = Duration::ZERO;
+        tcp_notes.local = Some(conn.local);
+        Some(conn.stream)
+    }
+
+    pub(super) fn claim_warm_tcp_connection<P: NextProxyPeer>(
+        &self,
+        peer: &P,
+        tcp_notes: &mut TcpConnectTaskNotes,
+    ) -> Option<LimitedStream<TcpStream>> {
+        match self.claim_warm_connection(peer, WarmStreamKind::Tcp, tcp_notes)? {
+            WarmStream::Tcp(mut stream) => {
+                let limit_config = peer.tcp_sock_speed_limit();
+                stream.reset_local_limit(
+                    limit_config.shift_millis,
+                    limit_config.max_south,
+                    limit_config.max_north,
+                );
+                Some(stream)
+            }
+            WarmStream::Tls(_) => None,
+        }
+    }
+
+    pub(super) fn claim_warm_tls_connection<P: NextProxyPeer>(
+        &self,
+        peer: &P,
+        tcp_notes: &mut TcpConnectTaskNotes,
+    ) -> Option<SslStream<LimitedStream<TcpStream>>> {
+        match self.claim_warm_connection(peer, WarmStreamKind::Tls, tcp_notes)? {
+            WarmStream::Tcp(_) => None,
+            WarmStream::Tls(mut stream) => {
+                let limit_config = peer.tcp_sock_speed_limit();
+                stream.get_mut().reset_local_limit(
+                    limit_config.shift_millis,
+                    limit_config.max_south,
+                    limit_config.max_north,
+                );
+                Some(stream)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use g3_io_ext::{LimitedReaderStats, LimitedWriterStats};
+    use tokio::net::TcpListener;
+
+    struct NilStats {}
+
+    impl LimitedReaderStats for NilStats {
+        fn add_read_bytes(&self, _size: usize) {}
+    }
+
+    impl LimitedWriterStats for NilStats {
+        fn add_write_bytes(&self, _size: usize) {}
+    }
+
+    async fn tcp_conn(
+        listener: &TcpListener,
+        idle_deadline: Instant,
+    ) -> (WarmConnection, TcpStream) {
+        let stream = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (accepted, _) = listener.accept().await.unwrap();
+        let local = stream.local_addr().unwrap();
+        let stream = LimitedStream::local_limited(stream, 0, 0, 0, Arc::new(NilStats {}));
+        let conn = WarmConnection {
+            stream: WarmStream::Tcp(stream),
+            bind: BindAddr::None,
+            local,
+            idle_deadline,
+        };
+        (conn, accepted)
+    }
+
+    #[test]
+    fn need() {
+        let mut queue = WarmIdleQueue {
+            idle: VecDeque::new(),
+            pending: 2,
+        };
+        assert_eq!(queue.need(2), 0);
+
+        queue.finish_pending(None);
+        assert_eq!(queue.pending, 1);
+        assert_eq!(queue.need(2), 1);
+
+        queue.finish_pending(None);
+        queue.finish_pending(None);
+        assert_eq!(queue.pending, 0);
+        assert_eq!(queue.need(3), 3);
+    }
+
+    #[tokio::test]
+    async fn take_kind() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let now = Instant::now();
+        let deadline = now + Duration::from_secs(60);
+
+        let mut queue = WarmIdleQueue {
+            idle: VecDeque::new(),
+            pending: 2,
+        };
+        let (conn1, _peer1) = tcp_conn(&listener, deadline).await;
+        queue.finish_pending(Some(conn1));
+        let (conn2, _peer2) = tcp_conn(&listener, deadline).await;
+        queue.finish_pending(Some(conn2));
+        assert_eq!(queue.idle.len(), 2);
+        assert_eq!(queue.need(2), 0);
+
+        let (conn, mismatched) = queue.take(now, WarmStreamKind::Tcp);
+        assert!(conn.is_some());
+        assert_eq!(mismatched, 0);
+        assert_eq!(queue.need(2), 1);
+
+        let (conn, mismatched) = queue.take(now, WarmStreamKind::Tls);
+        assert!(conn.is_none());
+        assert_eq!(mismatched, 1);
+        assert!(queue.idle.is_empty());
+        assert_eq!(queue.need(2), 2);
+    }
+
+    #[tokio::test]
+    async fn take_unusable() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let now = Instant::now();
+
+        let mut queue = WarmIdleQueue::default();
+        let (expired, _peer1) = tcp_conn(&listener, now).await;
+        queue.idle.push_back(expired);
+        let (closed, peer2) = tcp_conn(&listener, now + Duration::from_secs(60)).await;
+        drop(peer2);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        queue.idle.push_back(closed);
+
+        let (conn, mismatched) = queue.take(now, WarmStreamKind::Tcp);
+        assert!(conn.is_none());
+        assert_eq!(mismatched, 0);
+        assert!(queue.idle.is_empty());
+    }
+}
@@ -22,7 +22,7 @@ use arc_swap::ArcSwap;
 use log::warn;
 use tokio::sync::mpsc;
 
-use super::PeerSet;
+use super::{PeerSet, ProxyFloatPeerWarmer};
 use crate::config::escaper::proxy_float::{ProxyFloatEscaperConfig, ProxyFloatSource};
 
 mod file;
@@ -46,11 +46,15 @@ pub(super) async fn load_cached_peers(
 async fn parse_and_save_peers(
     config: &Arc<ProxyFloatEscaperConfig>,
     container: &Arc<ArcSwap<PeerSet>>,
+    warmer: Option<&Arc<ProxyFloatPeerWarmer>>,
     records: Vec<serde_json::Value>,
 ) -> anyhow::Result<()> {
     let peers = super::peer::parse_peers(config, &records)
         .map_err(|e| anyhow!("failed to parse peers: {e:?}"))?;
 
+    if let Some(warmer) = warmer {
+        warmer.update(&peers);
+    }
     container.store(Arc::new(peers));
     if let Some(cache_file) = &config.cache_file {
         file::save_peers_to_cache(cache_file, records)
@@ -63,6 +67,7 @@ async fn parse_and_save_peers(
 pub(super) async fn publish_peers(
     config: &Arc<ProxyFloatEscaperConfig>,
     peers_container: &Arc<ArcSwap<PeerSet>>,
+    peer_warmer: Option<&Arc<ProxyFloatPeerWarmer>>,
     data: String,
 ) -> anyhow::Result<()> {
     let obj = serde_json::from_str(&data)
@@ -73,12 +78,13 @@ pub(super) async fn publish_peers(
         _ => return Err(anyhow!("invalid input json data type")),
     };
 
-    parse_and_save_peers(config, peers_container, records).await
+    parse_and_save_peers(config, peers_container, peer_warmer, records).await
 }
 
 pub(super) fn new_job(
     config: Arc<ProxyFloatEscaperConfig>,
     peers_container: Arc<ArcSwap<PeerSet>>,
+    peer_warmer: Option<Arc<ProxyFloatPeerWarmer>>,
 ) -> anyhow::Result<Option<mpsc::Sender<()>>> {
     let (quit_sender, quit_receiver) = mpsc::channel(1);
 
@@ -86,7 +92,13 @@ pub(super) fn new_job(
         ProxyFloatSource::Passive => return Ok(None),
         ProxyFloatSource::Redis(redis) => {
             let redis_job = redis::RedisFetchJob::new(redis)?;
-            spawn_job(config, peers_container, redis_job, quit_receiver);
+            spawn_job(
+                config,
+                peers_container,
+                peer_warmer,
+                redis_job,
+                quit_receiver,
+            );
         }
     };
 
@@ -96,6 +108,7 @@ pub(super) fn new_job(
 fn spawn_job<T>(
     config: Arc<ProxyFloatEscaperConfig>,
     peers_container: Arc<ArcSwap<PeerSet>>,
+    peer_warmer: Option<Arc<ProxyFloatPeerWarmer>>,
     fetch_job: T,
     mut quit_receiver: mpsc::Receiver<()>,
 ) where
@@ -116,7 +129,14 @@ fn spawn_job<T>(
                         Err(TryRecvError::Disconnected) => break,
                     }
 
-                    if let Err(e) = parse_and_save_peers(&config, &peers_container, records).await {
+                    if let Err(e) = parse_and_save_peers(
+                        &config,
+                        &peers_container,
+                        peer_warmer.as_ref(),
+                        records,
+                    )
+                    .await
+                    {
                         warn!("failed to update peers for escaper {}: {e:?}", config.name);
                     }
                 }
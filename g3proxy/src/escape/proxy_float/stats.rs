@@ -16,16 +16,19 @@
 
 use std::sync::Arc;
 
-use arc_swap::ArcSwapOption;
+use arc_swap::{ArcSwap, ArcSwapOption};
 
 use g3_daemon::stat::remote::TcpConnectionTaskRemoteStats;
 use g3_io_ext::{LimitedReaderStats, LimitedWriterStats};
 use g3_types::metrics::{NodeName, StaticMetricsTags};
+use g3_types::net::UpstreamAddr;
 use g3_types::stats::{StatId, TcpIoSnapshot, UdpIoSnapshot};
 
+use super::{NextProxyPeer, PeerSet};
 use crate::escape::{
-    EscaperInterfaceStats, EscaperInternalStats, EscaperStats, EscaperTcpConnectSnapshot,
-    EscaperTcpStats, EscaperTlsSnapshot, EscaperTlsStats, EscaperUdpStats,
+    EscaperInterfaceStats, EscaperInternalStats, EscaperPeerExpireSnapshot, EscaperStats,
+    EscaperTcpConnectSnapshot, EscaperTcpStats, EscaperTlsSnapshot, EscaperTlsStats,
+    EscaperUdpStats,
 };
 use crate::module::http_forward::HttpForwardTaskRemoteStats;
 use crate::module::udp_connect::UdpConnectTaskRemoteStats;
@@ -39,6 +42,7 @@ pub(crate) struct ProxyFloatEscaperStats {
     pub(crate) tcp: EscaperTcpStats,
     pub(crate) udp: EscaperUdpStats,
     pub(crate) tls: EscaperTlsStats,
+    peers: ArcSwapOption<ArcSwap<PeerSet>>,
 }
 
 impl ProxyFloatEscaperStats {
@@ -51,12 +55,17 @@ impl ProxyFloatEscaperStats {
             tcp: EscaperTcpStats::default(),
             udp: EscaperUdpStats::default(),
             tls: EscaperTlsStats::default(),
+            peers: ArcSwapOption::new(None),
         }
     }
 
     pub(crate) fn set_extra_tags(&self, tags: Option<Arc<StaticMetricsTags>>) {
         self.extra_metrics_tags.store(tags);
     }
+
+    pub(super) fn set_peers(&self, peers: Arc<ArcSwap<PeerSet>>) {
+        self.peers.store(Some(peers));
+    }
 }
 
 impl EscaperInternalStats for ProxyFloatEscaperStats {
@@ -115,6 +124,30 @@ impl EscaperStats for ProxyFloatEscaperStats {
     fn udp_io_snapshot(&self) -> Option<UdpIoSnapshot> {
         Some(self.udp.io.snapshot())
     }
+
+    fn peer_expire_snapshot(&self) -> Option<EscaperPeerExpireSnapshot> {
+        let peers = self.peers.load();
+        let peer_set = peers.as_ref()?.load();
+        let (alive, expired) = peer_set.count_alive_expired();
+        Some(EscaperPeerExpireSnapshot { alive, expired })
+    }
+
+    fn upstream_tunnel_snapshot(&self) -> Option<Vec<(UpstreamAddr, u64)>> {
+        let peers = self.peers.load();
+        let peer_set = peers.as_ref()?.load();
+        let snapshot: Vec<(UpstreamAddr, u64)> = peer_set
+            .alive_peers()
+            .filter_map(|peer| {
+                let streams = peer.h2_alive_streams()?;
+                Some((UpstreamAddr::from(peer.peer_addr()), streams as u64))
+            })
+            .collect();
+        if snapshot.is_empty() {
+            None
+        } else {
+            Some(snapshot)
+        }
+    }
 }
 
 impl LimitedReaderStats for ProxyFloatEscaperStats {
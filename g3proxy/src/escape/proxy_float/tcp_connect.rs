@@ -16,40 +16,64 @@
 
 use std::net::SocketAddr;
 
-use tokio::net::TcpStream;
+use tokio::net::{TcpSocket, TcpStream};
 use tokio::time::Instant;
 
 use g3_io_ext::LimitedStream;
 use g3_socket::BindAddr;
-use g3_types::net::ConnectError;
+use g3_types::net::{ConnectError, UpstreamAddr, WeightedUpstreamAddr};
 
-use super::{NextProxyPeer, ProxyFloatEscaper};
+use super::{NextProxyPeer, ProxyFloatEscaper, ProxyFloatEscaperConfig, ProxyFloatEscaperStats};
 use crate::log::escape::tcp_connect::EscapeLogForTcpConnect;
 use crate::module::tcp_connect::{TcpConnectError, TcpConnectTaskConf, TcpConnectTaskNotes};
 use crate::serve::ServerTaskNotes;
 
-impl ProxyFloatEscaper {
-    async fn try_connect_tcp(
-        &self,
-        peer: SocketAddr,
-        bind: &BindAddr,
-    ) -> Result<TcpStream, TcpConnectError> {
-        // use new socket every time, as we set bind_no_port
-        let sock = g3_socket::tcp::new_socket_to(
-            peer.ip(),
-            bind,
-            &self.config.tcp_keepalive,
-            &self.config.tcp_misc_opts,
-            true,
-        )
-        .map_err(TcpConnectError::SetupSocketFailed)?;
-        self.stats.tcp.connect.add_attempted();
-        match sock.connect(peer).await {
-            Ok(ups_stream) => Ok(ups_stream),
-            Err(e) => Err(TcpConnectError::ConnectFailed(ConnectError::from(e))),
-        }
+pub(super) fn select_bind(config: &ProxyFloatEscaperConfig, peer_addr: SocketAddr) -> BindAddr {
+    let bind_ip = match peer_addr {
+        SocketAddr::V4(_) => config.bind_v4,
+        SocketAddr::V6(_) => config.bind_v6,
+    };
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    let bind = bind_ip.map(BindAddr::Ip).unwrap_or_else(|| {
+        config
+            .bind_interface
+            .map(BindAddr::Interface)
+            .unwrap_or_default()
+    });
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    let bind = bind_ip.map(BindAddr::Ip).unwrap_or_default();
+    bind
+}
+
+pub(super) fn new_tcp_socket(
+    config: &ProxyFloatEscaperConfig,
+    peer: SocketAddr,
+    bind: &BindAddr,
+) -> Result<TcpSocket, TcpConnectError> {
+    // use new socket every time, as we set bind_no_port
+    g3_socket::tcp::new_socket_to(
+        peer.ip(),
+        bind,
+        &config.tcp_keepalive,
+        &config.tcp_misc_opts,
+        true,
+    )
+    .map_err(TcpConnectError::SetupSocketFailed)
+}
+
+pub(super) async fn try_connect_tcp(
+    stats: &ProxyFloatEscaperStats,
+    sock: TcpSocket,
+    peer: SocketAddr,
+) -> Result<TcpStream, TcpConnectError> {
+    stats.tcp.connect.add_attempted();
+    match sock.connect(peer).await {
+        Ok(ups_stream) => Ok(ups_stream),
+        Err(e) => Err(TcpConnectError::ConnectFailed(ConnectError::from(e))),
     }
+}
 
+impl ProxyFloatEscaper {
     async fn tcp_connect_to<P: NextProxyPeer>(
         &self,
         peer: &P,
@@ -58,28 +82,18 @@ impl ProxyFloatEscaper {
         task_notes: &ServerTaskNotes,
     ) -> Result<TcpStream, TcpConnectError> {
         let peer_addr = peer.peer_addr();
-        let bind_ip = match peer_addr {
-            SocketAddr::V4(_) => self.config.bind_v4,
-            SocketAddr::V6(_) => self.config.bind_v6,
-        };
-        #[cfg(any(target_os = "linux", target_os = "android"))]
-        let bind = bind_ip.map(BindAddr::Ip).unwrap_or_else(|| {
-            self.config
-                .bind_interface
-                .map(BindAddr::Interface)
-                .unwrap_or_default()
-        });
-        #[cfg(not(any(target_os = "linux", target_os = "android")))]
-        let bind = bind_ip.map(BindAddr::Ip).unwrap_or_default();
-        tcp_notes.bind = bind;
+        tcp_notes.bind = select_bind(&self.config, peer_addr);
+        tcp_notes.next_proxy = Some(WeightedUpstreamAddr::new(UpstreamAddr::from(peer_addr)));
         tcp_notes.next = Some(peer_addr);
         tcp_notes.expire = peer.expire_datetime();
         tcp_notes.egress = Some(peer.egress_info());
+        let sock = new_tcp_socket(&self.config, peer_addr, &tcp_notes.bind)?;
+        tcp_notes.socket_cookie = g3_socket::tcp::socket_cookie(&sock);
         tcp_notes.tries = 1;
         let instant_now = Instant::now();
         let ret = tokio::time::timeout(
             self.config.tcp_connect_timeout,
-            self.try_connect_tcp(peer_addr, &tcp_notes.bind),
+            try_connect_tcp(&self.stats, sock, peer_addr),
         )
         .await;
         tcp_notes.duration = instant_now.elapsed();
@@ -18,6 +18,7 @@ use std::sync::Arc;
 
 use anyhow::anyhow;
 use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::time::Instant;
 
 use g3_daemon::stat::remote::{
     ArcTcpConnectionTaskRemoteStats, TcpConnectionTaskRemoteStatsWrapper,
@@ -48,10 +49,25 @@ impl ProxyFloatEscaper {
         let connector = SslConnector::new(ssl, stream)
             .map_err(|e| TcpConnectError::InternalTlsClientError(anyhow::Error::new(e)))?;
 
+        let instant_now = Instant::now();
         match tokio::time::timeout(task_conf.handshake_timeout(), connector.connect()).await {
-            Ok(Ok(stream)) => Ok(stream),
+            Ok(Ok(stream)) => {
+                if self.config.tls_handshake_log.sample_success() {
+                    EscapeLogForTlsHandshake {
+                        upstream: task_conf.tcp.upstream,
+                        tcp_notes,
+                        task_id: &task_notes.id,
+                        tls_name: task_conf.tls_name,
+                        tls_peer: task_conf.tcp.upstream,
+                        tls_application,
+                        tls_handshake_duration: instant_now.elapsed(),
+                    }
+                    .log_success(&self.escape_logger, stream.ssl());
+                }
+                Ok(stream)
+            }
             Ok(Err(e)) => {
-                let e = anyhow::Error::new(e);
+                let e = task_conf.handshake_error(e);
                 EscapeLogForTlsHandshake {
                     upstream: task_conf.tcp.upstream,
                     tcp_notes,
@@ -59,6 +75,7 @@ impl ProxyFloatEscaper {
                     tls_name: task_conf.tls_name,
                     tls_peer: task_conf.tcp.upstream,
                     tls_application,
+                    tls_handshake_duration: instant_now.elapsed(),
                 }
                 .log(&self.escape_logger, &e);
                 Err(TcpConnectError::UpstreamTlsHandshakeFailed(e))
@@ -72,6 +89,7 @@ impl ProxyFloatEscaper {
                     tls_name: task_conf.tls_name,
                     tls_peer: task_conf.tcp.upstream,
                     tls_application,
+                    tls_handshake_duration: instant_now.elapsed(),
                 }
                 .log(&self.escape_logger, &e);
                 Err(TcpConnectError::UpstreamTlsHandshakeTimeout)
@@ -15,14 +15,16 @@
  */
 
 use anyhow::anyhow;
-use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio::time::Instant;
 
 use g3_io_ext::LimitedStream;
 use g3_openssl::{SslConnector, SslStream};
-use g3_types::net::{Host, UpstreamAddr};
+use g3_types::net::{AlpnProtocol, Host, UpstreamAddr};
 
 use super::ProxyFloatEscaper;
 use crate::escape::proxy_float::peer::NextProxyPeer;
+use crate::escape::TlsClientIdentities;
 use crate::log::escape::tls_handshake::{EscapeLogForTlsHandshake, TlsApplication};
 use crate::module::tcp_connect::{TcpConnectError, TcpConnectTaskConf, TcpConnectTaskNotes};
 use crate::serve::ServerTaskNotes;
@@ -35,22 +37,48 @@ impl ProxyFloatEscaper {
         task_notes: &ServerTaskNotes,
         tls_name: &Host,
         peer: &P,
-    ) -> Result<SslStream<LimitedStream<impl AsyncRead + AsyncWrite>>, TcpConnectError> {
+        tls_client_identities: &TlsClientIdentities,
+        alpn_protocol: Option<AlpnProtocol>,
+    ) -> Result<SslStream<LimitedStream<TcpStream>>, TcpConnectError> {
         let stream = self
             .tcp_new_connection(peer, task_conf, tcp_notes, task_notes)
             .await?;
         let peer_addr = peer.peer_addr();
 
-        let ssl = self
+        let identity = tls_client_identities.select(tls_name, task_notes);
+        let mut ssl = self
             .tls_config
-            .build_ssl(tls_name, peer_addr.port())
+            .build_ssl_with_identity(
+                tls_name,
+                peer_addr.port(),
+                identity.map(|(name, pair)| (name.as_ref(), pair)),
+            )
             .map_err(TcpConnectError::InternalTlsClientError)?;
+        tcp_notes.tls_client_identity = identity.map(|(name, _)| name.clone());
+        if let Some(p) = alpn_protocol {
+            ssl.set_alpn_protos(p.wired_identification_sequence())
+                .map_err(|e| TcpConnectError::InternalTlsClientError(anyhow::Error::new(e)))?;
+        }
         let connector = SslConnector::new(ssl, stream)
             .map_err(|e| TcpConnectError::InternalTlsClientError(anyhow::Error::new(e)))?;
 
+        let instant_now = Instant::now();
         match tokio::time::timeout(self.tls_config.handshake_timeout, connector.connect()).await {
             Ok(Ok(stream)) => {
                 self.stats.tls.add_handshake_success();
+                if self.config.tls_handshake_log.sample_success() {
+                    let tls_peer = UpstreamAddr::from(peer_addr);
+                    EscapeLogForTlsHandshake {
+                        upstream: task_conf.upstream,
+                        tcp_notes,
+                        task_id: &task_notes.id,
+                        tls_name,
+                        tls_peer: &tls_peer,
+                        tls_application: TlsApplication::HttpProxy,
+                        tls_handshake_duration: instant_now.elapsed(),
+                    }
+                    .log_success(&self.escape_logger, stream.ssl());
+                }
                 Ok(stream)
             }
             Ok(Err(e)) => {
@@ -64,6 +92,7 @@ impl ProxyFloatEscaper {
                     tls_name,
                     tls_peer: &tls_peer,
                     tls_application: TlsApplication::HttpProxy,
+                    tls_handshake_duration: instant_now.elapsed(),
                 }
                 .log(&self.escape_logger, &e);
                 Err(TcpConnectError::PeerTlsHandshakeFailed(e))
@@ -79,6 +108,7 @@ impl ProxyFloatEscaper {
                     tls_name,
                     tls_peer: &tls_peer,
                     tls_application: TlsApplication::HttpProxy,
+                    tls_handshake_duration: instant_now.elapsed(),
                 }
                 .log(&self.escape_logger, &e);
                 Err(TcpConnectError::PeerTlsHandshakeTimeout)
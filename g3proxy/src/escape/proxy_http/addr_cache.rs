@@ -0,0 +1,262 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::hash_map::Entry;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use ahash::AHashMap;
+use tokio::time::Instant;
+
+use crate::config::escaper::ConnectAddrCacheConfig;
+
+struct AddrLatency {
+    /// EWMA of the tcp connect time, in microseconds
+    ewma: u64,
+    updated: Instant,
+}
+
+/// The recent tcp connect latency to each resolved address of the next proxy peers
+pub(super) struct ConnectAddrCache {
+    config: ConnectAddrCacheConfig,
+    inner: Mutex<AHashMap<SocketAddr, AddrLatency>>,
+}
+
+impl ConnectAddrCache {
+    pub(super) fn new(config: ConnectAddrCacheConfig) -> Self {
+        ConnectAddrCache {
+            config,
+            inner: Mutex::new(AHashMap::new()),
+        }
+    }
+
+    /// Add a connect time sample, the connect timeout should be used for failed connections
+    pub(super) fn add_sample(&self, addr: SocketAddr, time: Duration) {
+        let sample = u64::try_from(time.as_micros()).unwrap_or(u64::MAX).max(1);
+        let now = Instant::now();
+        let mut map = self.inner.lock().unwrap();
+        match map.entry(addr) {
+            Entry::Occupied(mut o) => {
+                let v = o.get_mut();
+                if now.duration_since(v.updated) >= self.config.expire {
+                    v.ewma = sample;
+                } else {
+                    // use the same smoothing factor 1/8 as tcp srtt
+                    v.ewma = v.ewma - v.ewma / 8 + sample / 8;
+                }
+                v.updated = now;
+            }
+            Entry::Vacant(v) => {
+                v.insert(AddrLatency {
+                    ewma: sample,
+                    updated: now,
+                });
+            }
+        }
+    }
+
+    /// Reorder the list so the address with the lowest latency will be tried first.
+    ///
+    /// The list is popped from the back, and the address families will still be interleaved after
+    /// the reorder. The preferred address will be returned, or None if no preference is made.
+    /// The list will be left untouched for a random part of the calls, so the slow addresses will
+    /// be probed again.
+    pub(super) fn prefer(&self, ips: &mut Vec<IpAddr>, port: u16) -> Option<IpAddr> {
+        if ips.len() < 2 {
+            return None;
+        }
+        if self.config.reprobe_ratio > 0.0 && fastrand::f64() < self.config.reprobe_ratio {
+            return None;
+        }
+
+        let now = Instant::now();
+        let mut map = self.inner.lock().unwrap();
+        map.retain(|_, v| now.duration_since(v.updated) < self.config.expire);
+        let (i, _) = ips
+            .iter()
+            .enumerate()
+            .filter_map(|(i, ip)| map.get(&SocketAddr::new(*ip, port)).map(|v| (i, v.ewma)))
+            .min_by_key(|(_, ewma)| *ewma)?;
+        drop(map);
+
+        Some(move_to_front_interleaved(ips, i))
+    }
+}
+
+/// Move the ip at index `i` to the back of the list, which is the front in pop order, and interleave
+/// the address families starting with the family of it
+fn move_to_front_interleaved(ips: &mut Vec<IpAddr>, i: usize) -> IpAddr {
+    let preferred = ips.remove(i);
+    // collect in pop order
+    let (same, other): (Vec<IpAddr>, Vec<IpAddr>) = ips
+        .drain(..)
+        .rev()
+        .partition(|ip| ip.is_ipv4() == preferred.is_ipv4());
+
+    let mut same = same.into_iter();
+    let mut other = other.into_iter();
+    ips.push(preferred);
+    loop {
+        let o = other.next();
+        if let Some(ip) = o {
+            ips.push(ip);
+        }
+        let s = same.next();
+        if let Some(ip) = s {
+            ips.push(ip);
+        }
+        if o.is_none() && s.is_none() {
+            break;
+        }
+    }
+    ips.reverse();
+    preferred
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn cache(expire: Duration) -> ConnectAddrCache {
+        ConnectAddrCache::new(ConnectAddrCacheConfig {
+            expire,
+            reprobe_ratio: 0.0,
+        })
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        IpAddr::from_str(s).unwrap()
+    }
+
+    fn ewma(cache: &ConnectAddrCache, addr: SocketAddr) -> Option<u64> {
+        cache.inner.lock().unwrap().get(&addr).map(|v| v.ewma)
+    }
+
+    #[test]
+    fn add_sample() {
+        let cache = cache(Duration::from_secs(60));
+        let addr = SocketAddr::new(ip("192.0.2.1"), 3128);
+
+        cache.add_sample(addr, Duration::from_millis(80));
+        assert_eq!(ewma(&cache, addr), Some(80_000));
+        cache.add_sample(addr, Duration::from_millis(160));
+        assert_eq!(ewma(&cache, addr), Some(90_000));
+        cache.add_sample(addr, Duration::ZERO);
+        assert_eq!(ewma(&cache, addr), Some(78_750));
+
+        // the port is part of the key
+        let other = SocketAddr::new(ip("192.0.2.1"), 8080);
+        assert_eq!(ewma(&cache, other), None);
+    }
+
+    #[test]
+    fn add_sample_expired() {
+        let cache = cache(Duration::from_millis(1));
+        let addr = SocketAddr::new(ip("192.0.2.1"), 3128);
+
+        cache.add_sample(addr, Duration::from_millis(80));
+        std::thread::sleep(Duration::from_millis(5));
+        cache.add_sample(addr, Duration::from_millis(160));
+        assert_eq!(ewma(&cache, addr), Some(160_000));
+    }
+
+    #[test]
+    fn prefer() {
+        let cache = cache(Duration::from_secs(60));
+        // pop order: v6b, v4b, v6a, v4a
+        let list = vec![
+            ip("192.0.2.1"),
+            ip("2001:db8::1"),
+            ip("192.0.2.2"),
+            ip("2001:db8::2"),
+        ];
+
+        let mut ips = list.clone();
+        assert_eq!(cache.prefer(&mut ips, 3128), None);
+        assert_eq!(ips, list);
+
+        cache.add_sample(
+            SocketAddr::new(ip("192.0.2.1"), 3128),
+            Duration::from_millis(50),
+        );
+        cache.add_sample(
+            SocketAddr::new(ip("192.0.2.2"), 3128),
+            Duration::from_millis(20),
+        );
+        cache.add_sample(
+            SocketAddr::new(ip("2001:db8::1"), 8080),
+            Duration::from_millis(1),
+        );
+
+        let mut ips = list.clone();
+        assert_eq!(cache.prefer(&mut ips, 3128), Some(ip("192.0.2.2")));
+        // pop order: v4b, v6b, v4a, v6a
+        assert_eq!(
+            ips,
+            vec![
+                ip("2001:db8::1"),
+                ip("192.0.2.1"),
+                ip("2001:db8::2"),
+                ip("192.0.2.2"),
+            ]
+        );
+
+        let mut ips = list.clone();
+        assert_eq!(cache.prefer(&mut ips, 8080), Some(ip("2001:db8::1")));
+        // pop order: v6a, v4b, v6b, v4a
+        assert_eq!(
+            ips,
+            vec![
+                ip("192.0.2.1"),
+                ip("2001:db8::2"),
+                ip("192.0.2.2"),
+                ip("2001:db8::1"),
+            ]
+        );
+
+        let mut ips = vec![ip("192.0.2.1")];
+        assert_eq!(cache.prefer(&mut ips, 3128), None);
+    }
+
+    #[test]
+    fn prefer_single_family() {
+        let cache = cache(Duration::from_secs(60));
+        cache.add_sample(
+            SocketAddr::new(ip("192.0.2.1"), 3128),
+            Duration::from_millis(1),
+        );
+
+        let mut ips = vec![ip("192.0.2.1"), ip("192.0.2.2"), ip("192.0.2.3")];
+        assert_eq!(cache.prefer(&mut ips, 3128), Some(ip("192.0.2.1")));
+        assert_eq!(ips, vec![ip("192.0.2.2"), ip("192.0.2.3"), ip("192.0.2.1")]);
+    }
+
+    #[test]
+    fn prefer_expired() {
+        let cache = cache(Duration::from_millis(1));
+        cache.add_sample(
+            SocketAddr::new(ip("192.0.2.1"), 3128),
+            Duration::from_millis(1),
+        );
+        std::thread::sleep(Duration::from_millis(5));
+
+        let mut ips = vec![ip("192.0.2.1"), ip("192.0.2.2")];
+        assert_eq!(cache.prefer(&mut ips, 3128), None);
+        assert!(cache.inner.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,44 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use g3_types::net::ConnectError;
+
+use super::ProxyHttpEscaper;
+use crate::config::escaper::FaultKind;
+use crate::module::tcp_connect::TcpConnectError;
+
+impl ProxyHttpEscaper {
+    /// Get the injected error for the new connection setup, if any
+    pub(super) fn inject_fault(&self, retryable: &mut bool) -> Option<TcpConnectError> {
+        let fault = self.config.fault_injection.as_ref()?.sample()?;
+        let e = match fault {
+            FaultKind::ConnectRefused => {
+                TcpConnectError::ConnectFailed(ConnectError::ConnectionRefused)
+            }
+            FaultKind::ConnectTimeout => TcpConnectError::ConnectFailed(ConnectError::TimedOut),
+            FaultKind::NegotiationTimeout => TcpConnectError::NegotiationPeerTimeout,
+            FaultKind::BadStatus(code) => {
+                if let Some(retry_config) = &self.config.connect_retry {
+                    *retryable = retry_config.is_retryable(code);
+                }
+                TcpConnectError::NegotiationRejected(format!(
+                    "rejected by remote proxy with injected response {code}"
+                ))
+            }
+        };
+        Some(e)
+    }
+}
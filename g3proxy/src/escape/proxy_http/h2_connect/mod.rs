@@ -0,0 +1,261 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context};
+use bytes::Bytes;
+use h2::client::SendRequest;
+use tokio::time::Instant;
+
+use g3_daemon::stat::remote::{
+    ArcTcpConnectionTaskRemoteStats, TcpConnectionTaskRemoteStatsWrapper,
+};
+use g3_h2::{H2StreamReader, H2StreamWriter};
+use g3_io_ext::{LimitedReader, LimitedWriter};
+use g3_openssl::SslConnector;
+use g3_types::net::{AlpnProtocol, Host, OpensslClientConfig, UpstreamAddr};
+
+use super::{ProxyHttpEscaper, SelectedPeer};
+use crate::config::escaper::Http2ConnectConfig;
+use crate::escape::h2_connect::{connect_rejected_error, H2ConnectPool};
+use crate::log::escape::http_connect::EscapeLogForHttpConnect;
+use crate::log::escape::tls_handshake::{EscapeLogForTlsHandshake, TlsApplication};
+use crate::module::tcp_connect::{
+    TcpConnectError, TcpConnectResult, TcpConnectTaskConf, TcpConnectTaskNotes,
+};
+use crate::serve::ServerTaskNotes;
+
+/// The http2 connect setup of the escaper.
+///
+/// There will be at most one connection for each next proxy, which is keyed by the peer address,
+/// or None for the unix socket peer.
+pub(super) struct H2Connect {
+    tls_config: Option<OpensslClientConfig>,
+    tls_name: Option<Host>,
+    pool: H2ConnectPool<Option<UpstreamAddr>>,
+}
+
+impl H2Connect {
+    pub(super) fn new(
+        config: &Http2ConnectConfig,
+        append_http_headers: &[String],
+    ) -> anyhow::Result<Self> {
+        let tls_config = match &config.tls_client {
+            Some(builder) => Some(
+                builder
+                    .build_with_alpn_protocols(Some(vec![AlpnProtocol::Http2]))
+                    .context("failed to build tls client config")?,
+            ),
+            None => None,
+        };
+
+        Ok(H2Connect {
+            tls_config,
+            tls_name: config.tls_name.clone(),
+            pool: H2ConnectPool::new(append_http_headers)?,
+        })
+    }
+}
+
+impl ProxyHttpEscaper {
+    async fn h2_new_connection(
+        &self,
+        h2: &H2Connect,
+        key: Option<UpstreamAddr>,
+        peer: SelectedPeer,
+        task_conf: &TcpConnectTaskConf<'_>,
+        tcp_notes: &mut TcpConnectTaskNotes,
+        task_notes: &ServerTaskNotes,
+    ) -> Result<SendRequest<Bytes>, TcpConnectError> {
+        let stream = self
+            .tcp_new_connection_to(peer, task_conf, tcp_notes, task_notes)
+            .await?;
+
+        let Some(tls_config) = &h2.tls_config else {
+            // use prior knowledge of h2c
+            return h2.pool.add_connection(key, stream, tcp_notes).await;
+        };
+
+        let tls_peer = match tcp_notes.next_proxy_addr() {
+            Some(peer) => peer.clone(),
+            None => {
+                // the tls name is required in config for unix socket peer
+                let Some(tls_name) = h2.tls_name.clone() else {
+                    return Err(TcpConnectError::InternalServerError(
+                        "no tls name set for unix socket peer",
+                    ));
+                };
+                UpstreamAddr::new(tls_name, 0)
+            }
+        };
+        let tls_name = h2.tls_name.as_ref().unwrap_or_else(|| tls_peer.host());
+        let identity = self.select_feed_tls_client_identity(&tls_peer);
+        let ssl = tls_config
+            .build_ssl_with_identity(
+                tls_name,
+                tls_peer.port(),
+                identity.as_ref().map(|(name, pair)| (name.as_ref(), pair)),
+            )
+            .map_err(TcpConnectError::InternalTlsClientError)?;
+        tcp_notes.tls_client_identity = identity.map(|(name, _)| name);
+        let connector = SslConnector::new(ssl, stream)
+            .map_err(|e| TcpConnectError::InternalTlsClientError(anyhow::Error::new(e)))?;
+
+        let instant_now = Instant::now();
+        let log_error = |tcp_notes: &TcpConnectTaskNotes, e: &anyhow::Error| {
+            EscapeLogForTlsHandshake {
+                upstream: task_conf.upstream,
+                tcp_notes,
+                task_id: &task_notes.id,
+                tls_name,
+                tls_peer: &tls_peer,
+                tls_application: TlsApplication::HttpProxy,
+                tls_handshake_duration: instant_now.elapsed(),
+            }
+            .log(&self.escape_logger, e);
+        };
+        match tokio::time::timeout(tls_config.handshake_timeout, connector.connect()).await {
+            Ok(Ok(stream)) => {
+                if stream.ssl().selected_alpn_protocol()
+                    == Some(AlpnProtocol::Http2.identification_sequence())
+                {
+                    return h2.pool.add_connection(key, stream, tcp_notes).await;
+                }
+                let e = anyhow!("h2 is not negotiated by alpn");
+                log_error(tcp_notes, &e);
+                Err(TcpConnectError::PeerTlsHandshakeFailed(e))
+            }
+            Ok(Err(e)) => {
+                let e = tls_config.handshake_error(e);
+                log_error(tcp_notes, &e);
+                Err(TcpConnectError::PeerTlsHandshakeFailed(e))
+            }
+            Err(_) => {
+                let e = anyhow!("peer tls handshake timed out");
+                log_error(tcp_notes, &e);
+                Err(TcpConnectError::PeerTlsHandshakeTimeout)
+            }
+        }
+    }
+
+    /// Get a ready sender on the shared connection to the selected peer, a new connection will be
+    /// set up if needed, and the tasks arrived during the setup will wait for and share it
+    async fn h2_get_send_request(
+        &self,
+        h2: &H2Connect,
+        peer: SelectedPeer,
+        task_conf: &TcpConnectTaskConf<'_>,
+        tcp_notes: &mut TcpConnectTaskNotes,
+        task_notes: &ServerTaskNotes,
+    ) -> Result<SendRequest<Bytes>, TcpConnectError> {
+        let key = match &peer {
+            #[cfg(unix)]
+            SelectedPeer::Unix => None,
+            SelectedPeer::Tcp(addr, _) => Some(addr.clone()),
+        };
+
+        if let Some(send_request) = h2.pool.get_send_request(&key, tcp_notes).await {
+            return Ok(send_request);
+        }
+        let _setup_guard = h2.pool.lock_setup(&key).await;
+        // the connection may have been set up by other tasks while waiting
+        if let Some(send_request) = h2.pool.get_send_request(&key, tcp_notes).await {
+            return Ok(send_request);
+        }
+        self.h2_new_connection(h2, key, peer, task_conf, tcp_notes, task_notes)
+            .await
+    }
+
+    pub(super) async fn h2_connect_tcp_connect_to(
+        &self,
+        h2: &H2Connect,
+        peer: SelectedPeer,
+        task_conf: &TcpConnectTaskConf<'_>,
+        tcp_notes: &mut TcpConnectTaskNotes,
+        task_notes: &ServerTaskNotes,
+        retryable: &mut bool,
+    ) -> Result<(H2StreamReader, H2StreamWriter), TcpConnectError> {
+        let r = self
+            .h2_connect_send_request(h2, peer, task_conf, tcp_notes, task_notes, retryable)
+            .await;
+        if let Err(e) = &r {
+            EscapeLogForHttpConnect {
+                upstream: task_conf.upstream,
+                tcp_notes,
+                task_id: &task_notes.id,
+            }
+            .log(&self.escape_logger, e);
+        }
+        r
+    }
+
+    async fn h2_connect_send_request(
+        &self,
+        h2: &H2Connect,
+        peer: SelectedPeer,
+        task_conf: &TcpConnectTaskConf<'_>,
+        tcp_notes: &mut TcpConnectTaskNotes,
+        task_notes: &ServerTaskNotes,
+        retryable: &mut bool,
+    ) -> Result<(H2StreamReader, H2StreamWriter), TcpConnectError> {
+        let send_request = self
+            .h2_get_send_request(h2, peer, task_conf, tcp_notes, task_notes)
+            .await?;
+
+        let (status, r, w) = h2
+            .pool
+            .send_connect_request(send_request, task_conf.upstream, tcp_notes)
+            .await?;
+        if let (Some(next_proxy), Some(rtt)) =
+            (tcp_notes.next_proxy_addr(), tcp_notes.negotiation_rtt)
+        {
+            self.stats.upstream_rtt.add_sample(next_proxy, rtt);
+        }
+
+        if status.is_success() {
+            return Ok((r, w));
+        }
+        if let Some(retry_config) = &self.config.connect_retry {
+            *retryable = retry_config.is_retryable(status.as_u16());
+        }
+        Err(connect_rejected_error(status))
+    }
+
+    pub(super) async fn h2_connect_new_tcp_connection(
+        &self,
+        task_conf: &TcpConnectTaskConf<'_>,
+        tcp_notes: &mut TcpConnectTaskNotes,
+        task_notes: &ServerTaskNotes,
+        task_stats: ArcTcpConnectionTaskRemoteStats,
+    ) -> TcpConnectResult {
+        let (r, w) = self
+            .timed_h2_connect_tcp_connect_to(task_conf, tcp_notes, task_notes)
+            .await?;
+
+        // the escaper level io stats are counted on the shared connection
+        let mut wrapper_stats = TcpConnectionTaskRemoteStatsWrapper::new(task_stats);
+        wrapper_stats.push_other_stats(self.fetch_user_upstream_io_stats(task_notes));
+        let wrapper_stats = Arc::new(wrapper_stats);
+
+        let r = LimitedReader::new(r, wrapper_stats.clone());
+        let mut w = LimitedWriter::new(w, wrapper_stats);
+        if let Some(limiter) = self.fetch_user_egress_speed_limit(task_notes) {
+            w.add_global_limiter(limiter);
+        }
+        Ok((Box::new(r), Box::new(w)))
+    }
+}
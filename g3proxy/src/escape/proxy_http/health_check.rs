@@ -0,0 +1,146 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Weak};
+
+use log::debug;
+use tokio::sync::mpsc;
+
+use g3_http::connect::{HttpConnectRequest, HttpConnectResponse};
+use g3_io_ext::FlexBufReader;
+use g3_types::collection::SelectiveVecBuilder;
+use g3_types::net::{ConnectError, Host, UpstreamAddr, WeightedUpstreamAddr};
+
+use super::ProxyHttpEscaper;
+use crate::config::escaper::ProxyHealthCheckConfig;
+use crate::module::tcp_connect::TcpConnectError;
+
+impl ProxyHttpEscaper {
+    async fn probe_peer(
+        &self,
+        peer: &UpstreamAddr,
+        target: &UpstreamAddr,
+    ) -> Result<(), TcpConnectError> {
+        let peer_ip = match peer.host() {
+            Host::Ip(ip) => *ip,
+            Host::Domain(domain) => {
+                let mut resolver_job = self.resolve_happy(domain.clone())?;
+                let mut ips = resolver_job
+                    .get_r1_or_first(self.config.happy_eyeballs.resolution_delay(), 1)
+                    .await?;
+                ips.pop().ok_or(TcpConnectError::NoAddressConnected)?
+            }
+        };
+
+        let (sock, _bind) = self.prepare_connect_socket(peer_ip, None)?;
+        let mut stream = sock
+            .connect(SocketAddr::new(peer_ip, peer.port()))
+            .await
+            .map_err(|e| TcpConnectError::ConnectFailed(ConnectError::from(e)))?;
+
+        let req = HttpConnectRequest::new(target, &self.config.append_http_headers);
+        req.send(&mut stream)
+            .await
+            .map_err(TcpConnectError::NegotiationWriteFailed)?;
+
+        let mut buf_stream = FlexBufReader::new(stream);
+        HttpConnectResponse::recv(&mut buf_stream, self.config.http_connect_rsp_hdr_max_size)
+            .await?;
+        Ok(())
+    }
+
+    async fn check_peers(&self, health_check: &ProxyHealthCheckConfig, failures: &mut [usize]) {
+        let probes = self.config.proxy_nodes.iter().map(|node| {
+            tokio::time::timeout(
+                health_check.timeout,
+                self.probe_peer(node.inner(), &health_check.target),
+            )
+        });
+        let results = futures_util::future::join_all(probes).await;
+
+        let mut builder = SelectiveVecBuilder::<WeightedUpstreamAddr>::new();
+        let mut unhealthy = 0u64;
+        for ((node, r), failed) in self
+            .config
+            .proxy_nodes
+            .iter()
+            .zip(results)
+            .zip(failures.iter_mut())
+        {
+            match r {
+                Ok(Ok(_)) => *failed = 0,
+                Ok(Err(e)) => {
+                    debug!(
+                        "escaper {}: health check via peer {} failed: {e}",
+                        self.config.name,
+                        node.inner()
+                    );
+                    *failed += 1;
+                }
+                Err(_) => {
+                    debug!(
+                        "escaper {}: health check via peer {} timed out",
+                        self.config.name,
+                        node.inner()
+                    );
+                    *failed += 1;
+                }
+            }
+            if *failed >= health_check.failure_threshold {
+                unhealthy += 1;
+            } else {
+                builder.insert(node.clone());
+            }
+        }
+        let healthy = self.config.proxy_nodes.len() as u64 - unhealthy;
+        self.stats.peer_health.set(healthy, unhealthy);
+
+        if health_check.remove_unhealthy {
+            // fallback to all peers if none of them is healthy
+            self.healthy_proxy_nodes
+                .store(builder.build().map(Arc::new));
+        }
+    }
+}
+
+pub(super) fn spawn_job(escaper: Weak<ProxyHttpEscaper>, mut quit_receiver: mpsc::Receiver<()>) {
+    tokio::spawn(async move {
+        let Some(health_check) = escaper
+            .upgrade()
+            .and_then(|escaper| escaper.config.health_check.clone())
+        else {
+            return;
+        };
+
+        let mut failures = Vec::new();
+        let mut interval = tokio::time::interval(health_check.interval);
+        loop {
+            tokio::select! {
+                biased;
+
+                _ = quit_receiver.recv() => break,
+                _ = interval.tick() => {
+                    let Some(escaper) = escaper.upgrade() else {
+                        break;
+                    };
+                    failures.resize(escaper.config.proxy_nodes.len(), 0);
+                    escaper.check_peers(&health_check, &mut failures).await;
+                }
+            }
+        }
+    });
+}
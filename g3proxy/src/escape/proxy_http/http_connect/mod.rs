@@ -14,22 +14,29 @@
  * limitations under the License.
  */
 
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::anyhow;
 use tokio::io::{AsyncRead, AsyncWrite};
-use tokio::net::TcpStream;
+use tokio::sync::SemaphorePermit;
+use tokio::time::Instant;
 
+use g3_daemon::server::ClientConnectionInfo;
 use g3_daemon::stat::remote::{
     ArcTcpConnectionTaskRemoteStats, TcpConnectionTaskRemoteStatsWrapper,
 };
-use g3_http::connect::{HttpConnectRequest, HttpConnectResponse};
+use g3_h2::{H2StreamReader, H2StreamWriter};
+use g3_http::connect::{HttpConnectError, HttpConnectRequest, HttpConnectResponse};
 use g3_io_ext::{
     AsyncStream, FlexBufReader, LimitedReader, LimitedStream, LimitedWriter, OnceBufReader,
 };
 use g3_openssl::{SslConnector, SslStream};
+use g3_types::net::UpstreamAddr;
 
-use super::ProxyHttpEscaper;
+use super::{H2Connect, PeerStream, ProxyHttpEscaper, SelectedPeer};
+use crate::log::escape::http_connect::EscapeLogForHttpConnect;
 use crate::log::escape::tls_handshake::{EscapeLogForTlsHandshake, TlsApplication};
 use crate::module::tcp_connect::{
     TcpConnectError, TcpConnectRemoteWrapperStats, TcpConnectResult, TcpConnectTaskConf,
@@ -37,15 +44,23 @@ use crate::module::tcp_connect::{
 };
 use crate::serve::ServerTaskNotes;
 
+/// The tunnel negotiated with the next proxy
+enum PeerTunnel {
+    Http1(FlexBufReader<LimitedStream<PeerStream>>),
+    Http2(H2StreamReader, H2StreamWriter),
+}
+
 impl ProxyHttpEscaper {
     async fn http_connect_tcp_connect_to(
         &self,
+        peer: SelectedPeer,
         task_conf: &TcpConnectTaskConf<'_>,
         tcp_notes: &mut TcpConnectTaskNotes,
         task_notes: &ServerTaskNotes,
-    ) -> Result<FlexBufReader<LimitedStream<TcpStream>>, TcpConnectError> {
+        retryable: &mut bool,
+    ) -> Result<FlexBufReader<LimitedStream<PeerStream>>, TcpConnectError> {
         let mut stream = self
-            .tcp_new_connection(task_conf, tcp_notes, task_notes)
+            .tcp_new_connection_to(peer, task_conf, tcp_notes, task_notes)
             .await?;
 
         let mut req = HttpConnectRequest::new(task_conf.upstream, &self.config.append_http_headers);
@@ -57,32 +72,326 @@ impl ProxyHttpEscaper {
             }
         }
 
-        req.send(&mut stream)
-            .await
-            .map_err(TcpConnectError::NegotiationWriteFailed)?;
+        let negotiation_start = Instant::now();
+        if let Err(e) = req.send(&mut stream).await {
+            let e = TcpConnectError::NegotiationWriteFailed(e);
+            EscapeLogForHttpConnect {
+                upstream: task_conf.upstream,
+                tcp_notes,
+                task_id: &task_notes.id,
+            }
+            .log(&self.escape_logger, &e);
+            return Err(e);
+        }
 
         let mut buf_stream = FlexBufReader::new(stream);
-        let _ =
+        let rsp =
             HttpConnectResponse::recv(&mut buf_stream, self.config.http_connect_rsp_hdr_max_size)
-                .await?;
-
-        // TODO detect and set outgoing_addr and target_addr for supported remote proxies
+                .await;
+        if matches!(
+            rsp,
+            Ok(_) | Err(HttpConnectError::UnexpectedStatusCode(_, _))
+        ) {
+            // a full round trip is done if we get the response
+            let rtt = negotiation_start.elapsed();
+            tcp_notes.negotiation_rtt = Some(rtt);
+            if let Some(next_proxy) = tcp_notes.next_proxy_addr() {
+                self.stats.upstream_rtt.add_sample(next_proxy, rtt);
+            }
+        }
+        match rsp {
+            Ok(rsp) => {
+                if rsp.has_unexpected_body() {
+                    self.stats.tcp.connect.add_unexpected_rsp_body();
+                    let log = EscapeLogForHttpConnect {
+                        upstream: task_conf.upstream,
+                        tcp_notes,
+                        task_id: &task_notes.id,
+                    };
+                    if self.config.http_connect_rsp_reject_body {
+                        let e = TcpConnectError::NegotiationUnexpectedBody;
+                        log.log(&self.escape_logger, &e);
+                        return Err(e);
+                    }
+                    log.log_discarded_body(&self.escape_logger, rsp.code);
+                }
+                let (outgoing_addr, target_addr) =
+                    crate::module::http_header::parse_remote_connection_info(&rsp.headers);
+                tcp_notes.chained.outgoing_addr = outgoing_addr;
+                tcp_notes.chained.target_addr = target_addr;
+                if let Some(next_proxy) = tcp_notes.next_proxy_addr() {
+                    let (agent, version) = crate::module::http_header::proxy_agent(&rsp.headers);
+                    self.stats.upstream_agent.set(next_proxy, agent, version);
+                }
+            }
+            Err(e) => {
+                if let HttpConnectError::UnexpectedStatusCode(code, _) = &e {
+                    if let Some(retry_config) = &self.config.connect_retry {
+                        *retryable = retry_config.is_retryable(*code);
+                    }
+                }
+                let e = TcpConnectError::from(e);
+                EscapeLogForHttpConnect {
+                    upstream: task_conf.upstream,
+                    tcp_notes,
+                    task_id: &task_notes.id,
+                }
+                .log(&self.escape_logger, &e);
+                return Err(e);
+            }
+        }
 
         Ok(buf_stream)
     }
 
+    /// the time waiting in queue is also limited by the negotiation deadline
+    async fn acquire_negotiation_permit(
+        &self,
+        deadline: Instant,
+        by_task_deadline: bool,
+    ) -> Result<Option<SemaphorePermit<'_>>, TcpConnectError> {
+        let Some(semaphore) = &self.negotiation_semaphore else {
+            return Ok(None);
+        };
+        if let Ok(permit) = semaphore.try_acquire() {
+            return Ok(Some(permit));
+        }
+
+        let _queue_guard = self.stats.negotiation_queue.enter();
+        match tokio::time::timeout_at(deadline, semaphore.acquire()).await {
+            Ok(Ok(permit)) => Ok(Some(permit)),
+            Ok(Err(_)) => Ok(None), // the semaphore won't be closed
+            Err(_) if by_task_deadline => Err(TcpConnectError::TaskDeadlineExceeded),
+            Err(_) => Err(TcpConnectError::NegotiationQueueTimeout),
+        }
+    }
+
     async fn timed_http_connect_tcp_connect_to(
         &self,
         task_conf: &TcpConnectTaskConf<'_>,
         tcp_notes: &mut TcpConnectTaskNotes,
         task_notes: &ServerTaskNotes,
-    ) -> Result<FlexBufReader<LimitedStream<TcpStream>>, TcpConnectError> {
-        tokio::time::timeout(
-            self.config.peer_negotiation_timeout,
-            self.http_connect_tcp_connect_to(task_conf, tcp_notes, task_notes),
-        )
-        .await
-        .map_err(|_| TcpConnectError::NegotiationPeerTimeout)?
+    ) -> Result<FlexBufReader<LimitedStream<PeerStream>>, TcpConnectError> {
+        match self
+            .timed_peer_negotiation(None, task_conf, tcp_notes, task_notes)
+            .await?
+        {
+            PeerTunnel::Http1(stream) => Ok(stream),
+            PeerTunnel::Http2(..) => Err(TcpConnectError::InternalServerError(
+                "unexpected http2 tunnel",
+            )),
+        }
+    }
+
+    pub(super) async fn timed_h2_connect_tcp_connect_to(
+        &self,
+        task_conf: &TcpConnectTaskConf<'_>,
+        tcp_notes: &mut TcpConnectTaskNotes,
+        task_notes: &ServerTaskNotes,
+    ) -> Result<(H2StreamReader, H2StreamWriter), TcpConnectError> {
+        let Some(pool) = &self.h2_connect else {
+            return Err(TcpConnectError::InternalServerError(
+                "no http2 connect pool set",
+            ));
+        };
+        match self
+            .timed_peer_negotiation(Some(pool), task_conf, tcp_notes, task_notes)
+            .await?
+        {
+            PeerTunnel::Http2(r, w) => Ok((r, w)),
+            PeerTunnel::Http1(_) => Err(TcpConnectError::InternalServerError(
+                "unexpected http1 tunnel",
+            )),
+        }
+    }
+
+    /// Run the negotiation with the next proxy, with the max concurrent negotiations,
+    /// connect retry and circuit breaker config applied
+    async fn timed_peer_negotiation(
+        &self,
+        h2_pool: Option<&H2Connect>,
+        task_conf: &TcpConnectTaskConf<'_>,
+        tcp_notes: &mut TcpConnectTaskNotes,
+        task_notes: &ServerTaskNotes,
+    ) -> Result<PeerTunnel, TcpConnectError> {
+        let negotiation = async {
+            let (deadline, by_task_deadline) =
+                task_notes.escaper_connect_deadline(self.config.peer_negotiation_timeout)?;
+            let Some(breaker) = &self.circuit_breaker else {
+                return self
+                    .run_peer_negotiation(
+                        h2_pool,
+                        task_conf,
+                        tcp_notes,
+                        task_notes,
+                        deadline,
+                        by_task_deadline,
+                    )
+                    .await;
+            };
+            // the trial slot will be released if dropped without result
+            let Some(breaker_permit) = breaker.try_acquire(&self.stats.circuit_breaker) else {
+                return Err(TcpConnectError::EscaperNotUsable(anyhow!(
+                    "circuit breaker is open"
+                )));
+            };
+            let r = self
+                .run_peer_negotiation(
+                    h2_pool,
+                    task_conf,
+                    tcp_notes,
+                    task_notes,
+                    deadline,
+                    by_task_deadline,
+                )
+                .await;
+            match &r {
+                Ok(_) => breaker_permit.record(true),
+                Err(
+                    TcpConnectError::CanceledAsServerQuit
+                    | TcpConnectError::PeerTunnelLimitReached
+                    | TcpConnectError::TaskDeadlineExceeded
+                    | TcpConnectError::NegotiationQueueTimeout,
+                ) => {}
+                Err(_) => breaker_permit.record(false),
+            }
+            r
+        };
+
+        tokio::select! {
+            r = negotiation => r,
+            _ = crate::escape::quit::wait_negotiation_quit() => {
+                Err(TcpConnectError::CanceledAsServerQuit)
+            }
+        }
+    }
+
+    async fn run_peer_negotiation(
+        &self,
+        h2_pool: Option<&H2Connect>,
+        task_conf: &TcpConnectTaskConf<'_>,
+        tcp_notes: &mut TcpConnectTaskNotes,
+        task_notes: &ServerTaskNotes,
+        deadline: Instant,
+        by_task_deadline: bool,
+    ) -> Result<PeerTunnel, TcpConnectError> {
+        let mut retry = 0;
+        loop {
+            let mut retryable = false;
+            let attempt = async {
+                #[cfg(feature = "fault-injection")]
+                if let Some(e) = self.inject_fault(&mut retryable) {
+                    EscapeLogForHttpConnect {
+                        upstream: task_conf.upstream,
+                        tcp_notes,
+                        task_id: &task_notes.id,
+                    }
+                    .log(&self.escape_logger, &e);
+                    return Err(e);
+                }
+
+                // wait for the per peer tunnel permit before taking the global negotiation
+                // permit, so a busy peer won't block the negotiations to other peers
+                let peer = self
+                    .select_peer(task_conf, tcp_notes, task_notes, Some(deadline))
+                    .await?;
+                // wait in queue if there are too many concurrent negotiations
+                let _permit = self
+                    .acquire_negotiation_permit(deadline, by_task_deadline)
+                    .await?;
+
+                match h2_pool {
+                    Some(pool) => self
+                        .h2_connect_tcp_connect_to(
+                            pool,
+                            peer,
+                            task_conf,
+                            tcp_notes,
+                            task_notes,
+                            &mut retryable,
+                        )
+                        .await
+                        .map(|(r, w)| PeerTunnel::Http2(r, w)),
+                    None => self
+                        .http_connect_tcp_connect_to(
+                            peer,
+                            task_conf,
+                            tcp_notes,
+                            task_notes,
+                            &mut retryable,
+                        )
+                        .await
+                        .map(PeerTunnel::Http1),
+                }
+            };
+            let e = match tokio::time::timeout_at(deadline, attempt).await {
+                Ok(Ok(stream)) => return Ok(stream),
+                Ok(Err(e)) => e,
+                Err(_) => {
+                    let e = if by_task_deadline {
+                        TcpConnectError::TaskDeadlineExceeded
+                    } else {
+                        TcpConnectError::NegotiationPeerTimeout
+                    };
+                    EscapeLogForHttpConnect {
+                        upstream: task_conf.upstream,
+                        tcp_notes,
+                        task_id: &task_notes.id,
+                    }
+                    .log(&self.escape_logger, &e);
+                    return Err(e);
+                }
+            };
+
+            let Some(retry_config) = &self.config.connect_retry else {
+                return Err(e);
+            };
+            if !retryable || retry >= retry_config.max_retries {
+                return Err(e);
+            }
+            let backoff = retry_config.backoff(retry);
+            if Instant::now() + backoff >= deadline {
+                return Err(e);
+            }
+            tokio::time::sleep(backoff).await;
+            retry += 1;
+            // the next proxy will be selected again by the pick policy
+            self.stats.tcp.connect.add_negotiation_retry();
+        }
+    }
+
+    /// Run the CONNECT negotiation to the canary target and drop the tunnel at once.
+    /// No task stats will be created, but the escaper level stats will still be updated.
+    pub(super) async fn http_connect_probe(
+        &self,
+        target: &UpstreamAddr,
+    ) -> anyhow::Result<Duration> {
+        let unspecified = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0);
+        let task_notes = ServerTaskNotes::new(
+            ClientConnectionInfo::new(unspecified, unspecified),
+            None,
+            Duration::ZERO,
+        );
+        let task_conf = TcpConnectTaskConf { upstream: target };
+        let mut tcp_notes = TcpConnectTaskNotes::default();
+        tcp_notes.escaper.clone_from(&self.config.name);
+
+        let start = Instant::now();
+        match self
+            .timed_peer_negotiation(
+                self.h2_connect.as_ref(),
+                &task_conf,
+                &mut tcp_notes,
+                &task_notes,
+            )
+            .await
+        {
+            Ok(_tunnel) => Ok(start.elapsed()),
+            Err(e) => Err(anyhow!(
+                "probe to {target} failed after {:?}: {e}",
+                start.elapsed()
+            )),
+        }
     }
 
     pub(super) async fn http_connect_new_tcp_connection(
@@ -110,7 +419,10 @@ impl ProxyHttpEscaper {
         // reset underlying io stats
         buf_stream.get_mut().reset_stats(wrapper_stats.clone());
 
-        let (r, w) = buf_stream.into_split();
+        let (r, mut w) = buf_stream.into_split();
+        if let Some(limiter) = self.fetch_user_egress_speed_limit(task_notes) {
+            w.add_global_limiter(limiter);
+        }
         let r = OnceBufReader::from(r);
         Ok((Box::new(r), Box::new(w)))
     }
@@ -130,10 +442,29 @@ impl ProxyHttpEscaper {
         let connector = SslConnector::new(ssl, buf_stream.into_inner())
             .map_err(|e| TcpConnectError::InternalTlsClientError(anyhow::Error::new(e)))?;
 
-        match tokio::time::timeout(task_conf.handshake_timeout(), connector.connect()).await {
-            Ok(Ok(stream)) => Ok(stream),
+        let handshake_timeout = self
+            .config
+            .upstream_tls_handshake_timeout
+            .unwrap_or_else(|| task_conf.handshake_timeout());
+        let instant_now = Instant::now();
+        match tokio::time::timeout(handshake_timeout, connector.connect()).await {
+            Ok(Ok(stream)) => {
+                if self.config.general.tls_handshake_log.sample_success() {
+                    EscapeLogForTlsHandshake {
+                        upstream: task_conf.tcp.upstream,
+                        tcp_notes,
+                        task_id: &task_notes.id,
+                        tls_name: task_conf.tls_name,
+                        tls_peer: task_conf.tcp.upstream,
+                        tls_application,
+                        tls_handshake_duration: instant_now.elapsed(),
+                    }
+                    .log_success(&self.escape_logger, stream.ssl());
+                }
+                Ok(stream)
+            }
             Ok(Err(e)) => {
-                let e = anyhow::Error::new(e);
+                let e = task_conf.handshake_error(e);
                 EscapeLogForTlsHandshake {
                     upstream: task_conf.tcp.upstream,
                     tcp_notes,
@@ -141,6 +472,7 @@ impl ProxyHttpEscaper {
                     tls_name: task_conf.tls_name,
                     tls_peer: task_conf.tcp.upstream,
                     tls_application,
+                    tls_handshake_duration: instant_now.elapsed(),
                 }
                 .log(&self.escape_logger, &e);
                 Err(TcpConnectError::UpstreamTlsHandshakeFailed(e))
@@ -154,6 +486,7 @@ impl ProxyHttpEscaper {
                     tls_name: task_conf.tls_name,
                     tls_peer: task_conf.tcp.upstream,
                     tls_application,
+                    tls_handshake_duration: instant_now.elapsed(),
                 }
                 .log(&self.escape_logger, &e);
                 Err(TcpConnectError::UpstreamTlsHandshakeTimeout)
@@ -185,7 +518,10 @@ impl ProxyHttpEscaper {
         let wrapper_stats = Arc::new(wrapper_stats);
 
         let ups_r = LimitedReader::new(ups_r, wrapper_stats.clone());
-        let ups_w = LimitedWriter::new(ups_w, wrapper_stats);
+        let mut ups_w = LimitedWriter::new(ups_w, wrapper_stats);
+        if let Some(limiter) = self.fetch_user_egress_speed_limit(task_notes) {
+            ups_w.add_global_limiter(limiter);
+        }
 
         Ok((Box::new(ups_r), Box::new(ups_w)))
     }
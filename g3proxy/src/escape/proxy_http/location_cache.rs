@@ -0,0 +1,112 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ahash::AHashMap;
+use tokio::time::Instant;
+
+use g3_geoip_types::IpLocation;
+use g3_types::net::Host;
+
+/// The located results will be reused for this long
+const LOCATION_CACHE_TTL: Duration = Duration::from_secs(60);
+/// The max number of upstream hosts to keep in the cache
+const LOCATION_CACHE_MAX_ENTRIES: usize = 4096;
+
+/// The recent ip location of each upstream host, so the resolve and the ip location lookup
+/// won't be done again for every new connection
+#[derive(Default)]
+pub(super) struct UpstreamLocationCache {
+    inner: Mutex<AHashMap<Host, (Arc<IpLocation>, Instant)>>,
+}
+
+impl UpstreamLocationCache {
+    pub(super) fn get(&self, host: &Host) -> Option<Arc<IpLocation>> {
+        self.get_at(host, Instant::now())
+    }
+
+    fn get_at(&self, host: &Host, now: Instant) -> Option<Arc<IpLocation>> {
+        let map = self.inner.lock().unwrap();
+        let (location, expire) = map.get(host)?;
+        if *expire > now {
+            Some(location.clone())
+        } else {
+            None
+        }
+    }
+
+    pub(super) fn insert(&self, host: Host, location: Arc<IpLocation>) {
+        self.insert_at(host, location, Instant::now());
+    }
+
+    fn insert_at(&self, host: Host, location: Arc<IpLocation>, now: Instant) {
+        let mut map = self.inner.lock().unwrap();
+        if map.len() >= LOCATION_CACHE_MAX_ENTRIES && !map.contains_key(&host) {
+            map.retain(|_, (_, expire)| *expire > now);
+            if map.len() >= LOCATION_CACHE_MAX_ENTRIES {
+                map.clear();
+            }
+        }
+        map.insert(host, (location, now + LOCATION_CACHE_TTL));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    use g3_geoip_types::IpLocationBuilder;
+    use ip_network::IpNetwork;
+
+    fn location() -> Arc<IpLocation> {
+        let mut builder = IpLocationBuilder::default();
+        builder.set_network(IpNetwork::from_str("192.0.2.0/24").unwrap());
+        Arc::new(builder.build().unwrap())
+    }
+
+    #[test]
+    fn expire() {
+        let cache = UpstreamLocationCache::default();
+        let host = Host::from_str("example.net").unwrap();
+        let now = Instant::now();
+        assert!(cache.get_at(&host, now).is_none());
+
+        cache.insert_at(host.clone(), location(), now);
+        assert!(cache.get_at(&host, now).is_some());
+        assert!(cache
+            .get_at(&host, now + LOCATION_CACHE_TTL - Duration::from_secs(1))
+            .is_some());
+        assert!(cache.get_at(&host, now + LOCATION_CACHE_TTL).is_none());
+    }
+
+    #[test]
+    fn max_entries() {
+        let cache = UpstreamLocationCache::default();
+        let now = Instant::now();
+        for i in 0..LOCATION_CACHE_MAX_ENTRIES {
+            let host = Host::from_str(&format!("h{i}.example.net")).unwrap();
+            cache.insert_at(host, location(), now);
+        }
+        let later = now + LOCATION_CACHE_TTL;
+        let host = Host::from_str("example.org").unwrap();
+        cache.insert_at(host.clone(), location(), later);
+        assert_eq!(cache.inner.lock().unwrap().len(), 1);
+        assert!(cache.get_at(&host, later).is_some());
+    }
+}
@@ -16,18 +16,27 @@
 
 use std::collections::BTreeSet;
 use std::sync::Arc;
+use std::time::Duration;
 
+use ahash::AHashMap;
 use anyhow::anyhow;
+use arc_swap::ArcSwapOption;
 use async_trait::async_trait;
 use slog::Logger;
+use tokio::sync::{mpsc, Semaphore};
 
 use g3_daemon::stat::remote::ArcTcpConnectionTaskRemoteStats;
+use g3_io_ext::GlobalStreamLimiter;
+use g3_ip_locate::IpLocationServiceHandle;
 use g3_resolver::{ResolveError, ResolveLocalError};
 use g3_types::collection::{SelectiveVec, SelectiveVecBuilder};
 use g3_types::metrics::NodeName;
 use g3_types::net::{Host, HttpForwardCapability, UpstreamAddr, WeightedUpstreamAddr};
 
-use super::{ArcEscaper, ArcEscaperStats, Escaper, EscaperExt, EscaperInternal, EscaperStats};
+use super::{
+    ArcEscaper, ArcEscaperStats, Escaper, EscaperCircuitBreaker, EscaperExt, EscaperInternal,
+    EscaperStats,
+};
 use crate::audit::AuditContext;
 use crate::auth::UserUpstreamTrafficStats;
 use crate::config::escaper::proxy_http::ProxyHttpEscaperConfig;
@@ -57,15 +66,48 @@ use crate::serve::ServerTaskNotes;
 mod stats;
 pub(crate) use stats::ProxyHttpEscaperStats;
 
+mod addr_cache;
+#[cfg(feature = "fault-injection")]
+mod fault_injection;
+mod h2_connect;
+mod health_check;
 mod http_connect;
 mod http_forward;
+mod location_cache;
+mod peer_feed;
 mod tcp_connect;
+use tcp_connect::SelectedPeer;
+
+mod stream;
+use stream::PeerStream;
+
+mod tunnel_limit;
+use tunnel_limit::{PeerTunnelLimiter, PeerTunnelPermit};
+
+use addr_cache::ConnectAddrCache;
+use h2_connect::H2Connect;
+use location_cache::UpstreamLocationCache;
+use peer_feed::FeedPeerSet;
+
+/// The rtt samples older than this will be ignored when selecting next proxy by least rtt
+const LEAST_RTT_SAMPLE_MAX_AGE: Duration = Duration::from_secs(30);
 
 pub(super) struct ProxyHttpEscaper {
     config: Arc<ProxyHttpEscaperConfig>,
     stats: Arc<ProxyHttpEscaperStats>,
-    proxy_nodes: SelectiveVec<WeightedUpstreamAddr>,
+    proxy_nodes: Option<SelectiveVec<WeightedUpstreamAddr>>,
+    healthy_proxy_nodes: ArcSwapOption<SelectiveVec<WeightedUpstreamAddr>>,
+    quit_health_check_sender: Option<mpsc::Sender<()>>,
+    feed_peers: ArcSwapOption<FeedPeerSet>,
+    quit_peer_feed_sender: Option<mpsc::Sender<()>>,
+    negotiation_semaphore: Option<Semaphore>,
+    peer_tunnel_limiters: AHashMap<UpstreamAddr, Arc<PeerTunnelLimiter>>,
+    circuit_breaker: Option<EscaperCircuitBreaker>,
+    connect_addr_cache: Option<Arc<ConnectAddrCache>>,
+    h2_connect: Option<H2Connect>,
     resolver_handle: Option<ArcIntegratedResolverHandle>,
+    ip_locate_handle: Option<IpLocationServiceHandle>,
+    upstream_locations: UpstreamLocationCache,
     escape_logger: Logger,
 }
 
@@ -73,14 +115,24 @@ impl ProxyHttpEscaper {
     fn new_obj(
         config: ProxyHttpEscaperConfig,
         stats: Arc<ProxyHttpEscaperStats>,
+        feed_peers: Option<Arc<FeedPeerSet>>,
+        old_tunnel_limiters: Option<&AHashMap<UpstreamAddr, Arc<PeerTunnelLimiter>>>,
     ) -> anyhow::Result<ArcEscaper> {
         let mut nodes_builder = SelectiveVecBuilder::new();
         for node in &config.proxy_nodes {
             nodes_builder.insert(node.clone());
         }
-        let proxy_nodes = nodes_builder
-            .build()
-            .ok_or_else(|| anyhow!("no next proxy node set"))?;
+        // may be empty if the next proxy is a unix socket path
+        let proxy_nodes = nodes_builder.build();
+        stats
+            .upstream_rtt
+            .retain(config.proxy_nodes.iter().map(|node| node.inner()));
+        stats
+            .upstream_agent
+            .retain(config.proxy_nodes.iter().map(|node| node.inner()));
+        stats
+            .upstream_select
+            .retain(config.proxy_nodes.iter().map(|node| node.inner()));
 
         let escape_logger = config.get_escape_logger();
 
@@ -91,43 +143,161 @@ impl ProxyHttpEscaper {
             Some(crate::resolve::get_handle(resolver)?)
         };
 
+        let ip_locate_handle = if config.geo_bind.is_empty() {
+            None
+        } else {
+            Some(config.geo_bind.ip_locate_service.spawn_ip_locate_agent()?)
+        };
+
         stats.set_extra_tags(config.extra_metrics_tags.clone());
 
-        let escaper = ProxyHttpEscaper {
+        let (quit_health_check_sender, quit_health_check_receiver) =
+            if config.health_check.is_some() {
+                let (sender, receiver) = mpsc::channel(1);
+                (Some(sender), Some(receiver))
+            } else {
+                stats.peer_health.set(0, 0);
+                (None, None)
+            };
+
+        let (quit_peer_feed_sender, quit_peer_feed_receiver) = if config.peer_feed.is_some() {
+            stats.peer_feed.set_enabled(true);
+            let (sender, receiver) = mpsc::channel(1);
+            (Some(sender), Some(receiver))
+        } else {
+            stats.peer_feed.set_enabled(false);
+            (None, None)
+        };
+
+        let negotiation_semaphore = if config.max_concurrent_negotiations > 0 {
+            stats.negotiation_queue.set_enabled(true);
+            Some(Semaphore::new(config.max_concurrent_negotiations))
+        } else {
+            stats.negotiation_queue.set_enabled(false);
+            None
+        };
+
+        let circuit_breaker = if let Some(c) = &config.circuit_breaker {
+            stats.circuit_breaker.set_enabled(true);
+            Some(EscaperCircuitBreaker::new(c.clone()))
+        } else {
+            stats.circuit_breaker.set_enabled(false);
+            None
+        };
+
+        let connect_addr_cache = config
+            .connect_addr_cache
+            .as_ref()
+            .map(|c| Arc::new(ConnectAddrCache::new(c.clone())));
+
+        let h2_connect = match &config.http2_connect {
+            Some(c) => Some(H2Connect::new(c, &config.append_http_headers)?),
+            None => None,
+        };
+
+        // the limiters of the existing peers are reused, so the alive tunnels are still counted
+        let mut peer_tunnel_limiters = AHashMap::new();
+        if config.max_tunnels_per_peer > 0 {
+            for node in &config.proxy_nodes {
+                let peer = node.inner();
+                let limiter = match old_tunnel_limiters.and_then(|m| m.get(peer)) {
+                    Some(limiter) => {
+                        limiter.set_limit(config.max_tunnels_per_peer);
+                        limiter.clone()
+                    }
+                    None => Arc::new(PeerTunnelLimiter::new(config.max_tunnels_per_peer)),
+                };
+                peer_tunnel_limiters.insert(peer.clone(), limiter);
+            }
+        }
+        stats.upstream_tunnel.retain(peer_tunnel_limiters.keys());
+
+        let escaper = Arc::new(ProxyHttpEscaper {
             config: Arc::new(config),
             stats,
             proxy_nodes,
+            healthy_proxy_nodes: ArcSwapOption::new(None),
+            quit_health_check_sender,
+            feed_peers: ArcSwapOption::new(feed_peers),
+            quit_peer_feed_sender,
+            negotiation_semaphore,
+            peer_tunnel_limiters,
+            circuit_breaker,
+            connect_addr_cache,
+            h2_connect,
             resolver_handle,
+            ip_locate_handle,
+            upstream_locations: UpstreamLocationCache::default(),
             escape_logger,
-        };
+        });
 
-        Ok(Arc::new(escaper))
+        if let Some(receiver) = quit_health_check_receiver {
+            health_check::spawn_job(Arc::downgrade(&escaper), receiver);
+        }
+        if let Some(receiver) = quit_peer_feed_receiver {
+            peer_feed::spawn_job(Arc::downgrade(&escaper), receiver);
+        }
+
+        Ok(escaper)
     }
 
     pub(super) fn prepare_initial(config: ProxyHttpEscaperConfig) -> anyhow::Result<ArcEscaper> {
         let stats = Arc::new(ProxyHttpEscaperStats::new(config.name()));
-        ProxyHttpEscaper::new_obj(config, stats)
+        ProxyHttpEscaper::new_obj(config, stats, None, None)
     }
 
     fn prepare_reload(
         config: AnyEscaperConfig,
         stats: Arc<ProxyHttpEscaperStats>,
+        feed_peers: Option<Arc<FeedPeerSet>>,
+        tunnel_limiters: &AHashMap<UpstreamAddr, Arc<PeerTunnelLimiter>>,
     ) -> anyhow::Result<ArcEscaper> {
         if let AnyEscaperConfig::ProxyHttp(config) = config {
-            ProxyHttpEscaper::new_obj(*config, stats)
+            ProxyHttpEscaper::new_obj(*config, stats, feed_peers, Some(tunnel_limiters))
         } else {
             Err(anyhow!("invalid escaper config type"))
         }
     }
 
-    fn get_next_proxy(&self, task_notes: &ServerTaskNotes, target_host: &Host) -> &UpstreamAddr {
+    fn get_next_proxy(
+        &self,
+        task_notes: &ServerTaskNotes,
+        target_host: &Host,
+    ) -> Option<WeightedUpstreamAddr> {
+        if let Some(healthy_nodes) = self.healthy_proxy_nodes.load_full() {
+            return Some(self.select_node(&healthy_nodes, task_notes, target_host));
+        }
+        if let Some(feed_peers) = self.feed_peers.load_full() {
+            return Some(self.select_node(feed_peers.nodes(), task_notes, target_host));
+        }
+        let proxy_nodes = self.proxy_nodes.as_ref()?;
+        Some(self.select_node(proxy_nodes, task_notes, target_host))
+    }
+
+    fn select_node(
+        &self,
+        nodes: &SelectiveVec<WeightedUpstreamAddr>,
+        task_notes: &ServerTaskNotes,
+        target_host: &Host,
+    ) -> WeightedUpstreamAddr {
+        if self.config.proxy_pick_least_rtt {
+            // pick two by weight and use the faster one, nodes without recent rtt samples
+            // will be selected first, so that the slow ones will be probed again
+            if let Some(node) = nodes.pick_random_n(2).into_iter().min_by_key(|node| {
+                self.stats
+                    .upstream_rtt
+                    .get_recent(node.inner(), LEAST_RTT_SAMPLE_MAX_AGE)
+            }) {
+                return node.clone();
+            }
+        }
         self.select_consistent(
-            &self.proxy_nodes,
+            nodes,
             self.config.proxy_pick_policy,
             task_notes,
             target_host,
         )
-        .inner()
+        .clone()
     }
 
     fn resolve_happy(&self, domain: Arc<str>) -> Result<HappyEyeballsResolveJob, ResolveError> {
@@ -138,6 +308,15 @@ impl ProxyHttpEscaper {
         }
     }
 
+    fn fetch_user_egress_speed_limit(
+        &self,
+        task_notes: &ServerTaskNotes,
+    ) -> Option<Arc<GlobalStreamLimiter>> {
+        task_notes
+            .user_ctx()
+            .and_then(|ctx| ctx.user().tcp_all_egress_speed_limit().cloned())
+    }
+
     fn fetch_user_upstream_io_stats(
         &self,
         task_notes: &ServerTaskNotes,
@@ -169,6 +348,10 @@ impl Escaper for ProxyHttpEscaper {
         Err(anyhow!("not implemented"))
     }
 
+    async fn probe(&self, target: UpstreamAddr) -> anyhow::Result<Duration> {
+        self.http_connect_probe(&target).await
+    }
+
     async fn tcp_setup_connection(
         &self,
         task_conf: &TcpConnectTaskConf<'_>,
@@ -179,6 +362,11 @@ impl Escaper for ProxyHttpEscaper {
     ) -> TcpConnectResult {
         self.stats.interface.add_tcp_connect_attempted();
         tcp_notes.escaper.clone_from(&self.config.name);
+        if self.h2_connect.is_some() {
+            return self
+                .h2_connect_new_tcp_connection(task_conf, tcp_notes, task_notes, task_stats)
+                .await;
+        }
         self.http_connect_new_tcp_connection(task_conf, tcp_notes, task_notes, task_stats)
             .await
     }
@@ -264,7 +452,23 @@ impl EscaperInternal for ProxyHttpEscaper {
 
     async fn _lock_safe_reload(&self, config: AnyEscaperConfig) -> anyhow::Result<ArcEscaper> {
         let stats = Arc::clone(&self.stats);
-        ProxyHttpEscaper::prepare_reload(config, stats)
+        // keep the last good feed peers if the feed is not changed
+        let feed_peers = match &config {
+            AnyEscaperConfig::ProxyHttp(new) if new.peer_feed == self.config.peer_feed => {
+                self.feed_peers.load_full()
+            }
+            _ => None,
+        };
+        ProxyHttpEscaper::prepare_reload(config, stats, feed_peers, &self.peer_tunnel_limiters)
+    }
+
+    fn _clean_to_offline(&self) {
+        if let Some(sender) = &self.quit_health_check_sender {
+            let _ = sender.try_send(());
+        }
+        if let Some(sender) = &self.quit_peer_feed_sender {
+            let _ = sender.try_send(());
+        }
     }
 
     #[inline]
@@ -0,0 +1,672 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::{Arc, Weak};
+#[cfg(feature = "hickory")]
+use std::time::Duration;
+
+use ahash::AHashMap;
+use anyhow::{anyhow, Context};
+#[cfg(feature = "hickory")]
+use hickory_client::client::{Client, ClientHandle};
+#[cfg(feature = "hickory")]
+use hickory_proto::op::{Message, ResponseCode};
+#[cfg(feature = "hickory")]
+use hickory_proto::rr::rdata::SRV;
+#[cfg(feature = "hickory")]
+use hickory_proto::rr::{DNSClass, Name, RData, RecordType};
+#[cfg(feature = "hickory")]
+use hickory_proto::BufDnsStreamHandle;
+use http::Method;
+use log::warn;
+use rmpv::ValueRef;
+use tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::mpsc;
+use url::Url;
+
+use g3_http::client::HttpForwardRemoteResponse;
+use g3_http::HttpBodyReader;
+#[cfg(feature = "hickory")]
+use g3_socket::{BindAddr, TcpConnectInfo, UdpConnectInfo};
+use g3_types::collection::{SelectiveVec, SelectiveVecBuilder};
+use g3_types::net::{Host, OpensslCertificatePair, UpstreamAddr, WeightedUpstreamAddr};
+
+use super::ProxyHttpEscaper;
+use crate::config::escaper::{PeerFeedConfig, PeerFeedSource};
+
+/// The peers published by the external peer feed
+pub(super) struct FeedPeerSet {
+    nodes: SelectiveVec<WeightedUpstreamAddr>,
+    tls_client_identities: AHashMap<UpstreamAddr, Arc<str>>,
+}
+
+impl FeedPeerSet {
+    pub(super) fn nodes(&self) -> &SelectiveVec<WeightedUpstreamAddr> {
+        &self.nodes
+    }
+
+    pub(super) fn tls_client_identity(&self, peer: &UpstreamAddr) -> Option<&Arc<str>> {
+        self.tls_client_identities.get(peer)
+    }
+}
+
+impl ProxyHttpEscaper {
+    fn check_feed_peer_host(&self, host: &Host) -> anyhow::Result<()> {
+        match host {
+            Host::Domain(_) => {
+                if self.resolver_handle.is_none() {
+                    return Err(anyhow!("resolver is not set for domain peer addr"));
+                }
+            }
+            Host::Ip(IpAddr::V4(_)) => {
+                if self.config.no_ipv4 {
+                    return Err(anyhow!("ipv4 is disabled but the peer addr is ipv4"));
+                }
+            }
+            Host::Ip(IpAddr::V6(_)) => {
+                if self.config.no_ipv6 {
+                    return Err(anyhow!("ipv6 is disabled but the peer addr is ipv6"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn fetch_feed_by_http(
+        &self,
+        feed: &PeerFeedConfig,
+        url: &Url,
+    ) -> anyhow::Result<Vec<u8>> {
+        let addr = UpstreamAddr::try_from(url).map_err(|e| anyhow!("invalid http url: {e}"))?;
+        let ip = match addr.host() {
+            Host::Ip(ip) => *ip,
+            Host::Domain(domain) => {
+                // use the resolver of this escaper, same as the next proxy peers
+                let mut resolver_job = self
+                    .resolve_happy(domain.clone())
+                    .map_err(|e| anyhow!("failed to resolve {domain}: {e}"))?;
+                let mut ips = resolver_job
+                    .get_r1_or_first(self.config.happy_eyeballs.resolution_delay(), 1)
+                    .await
+                    .map_err(|e| anyhow!("failed to resolve {domain}: {e}"))?;
+                ips.pop()
+                    .ok_or_else(|| anyhow!("no ip address resolved for {domain}"))?
+            }
+        };
+        let peer = SocketAddr::new(ip, addr.port());
+        let mut stream = TcpStream::connect(peer)
+            .await
+            .map_err(|e| anyhow!("failed to connect to {addr}({peer}): {e}"))?;
+
+        let req = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nAccept: application/msgpack\r\nConnection: close\r\n\r\n",
+            &url[url::Position::BeforePath..],
+            &url[url::Position::BeforeHost..url::Position::AfterPort],
+        );
+        stream
+            .write_all(req.as_bytes())
+            .await
+            .map_err(|e| anyhow!("failed to send request: {e}"))?;
+
+        let mut reader = BufReader::new(stream);
+        let rsp = HttpForwardRemoteResponse::parse(&mut reader, &Method::GET, false, 4096)
+            .await
+            .map_err(|e| anyhow!("failed to read response: {e}"))?;
+        if rsp.code != 200 {
+            return Err(anyhow!("unexpected response status code {}", rsp.code));
+        }
+        let Some(body_type) = rsp.body_type(&Method::GET) else {
+            return Err(anyhow!("no body found in response"));
+        };
+
+        let body_reader = HttpBodyReader::new(&mut reader, body_type, 1024);
+        let mut data = Vec::new();
+        body_reader
+            .take(feed.max_body_size as u64 + 1)
+            .read_to_end(&mut data)
+            .await
+            .map_err(|e| anyhow!("failed to read response body: {e}"))?;
+        if data.len() > feed.max_body_size {
+            return Err(anyhow!("response body is too large"));
+        }
+        Ok(data)
+    }
+
+    async fn fetch_feed_by_udp(
+        &self,
+        feed: &PeerFeedConfig,
+        peer: SocketAddr,
+    ) -> anyhow::Result<Vec<u8>> {
+        let socket = connect_udp(peer).await?;
+
+        let req = ValueRef::Map(vec![(
+            ValueRef::String("escaper".into()),
+            ValueRef::String(self.config.name.as_str().into()),
+        )]);
+        let mut buf = Vec::new();
+        rmpv::encode::write_value_ref(&mut buf, &req)
+            .map_err(|e| anyhow!("failed to encode request: {e}"))?;
+        socket
+            .send(&buf)
+            .await
+            .map_err(|e| anyhow!("failed to send request: {e}"))?;
+
+        let mut data = vec![0u8; feed.max_body_size.min(u16::MAX as usize)];
+        let len = socket
+            .recv(&mut data)
+            .await
+            .map_err(|e| anyhow!("failed to recv response: {e}"))?;
+        data.truncate(len);
+        Ok(data)
+    }
+
+    /// Build the peer list from the SRV records of the domain proxy addrs.
+    ///
+    /// For each domain proxy addr, the records of the lowest priority value with resolvable targets
+    /// will be used, the targets will be resolved by the resolver of this escaper. The proxy addr
+    /// itself will be used if no usable SRV record exists.
+    #[cfg(feature = "hickory")]
+    async fn query_feed_by_srv(
+        &self,
+        feed: &PeerFeedConfig,
+        server: SocketAddr,
+        service: Option<&str>,
+    ) -> anyhow::Result<FeedPeerSet> {
+        let mut builder = SelectiveVecBuilder::new();
+        for node in &self.config.proxy_nodes {
+            let Host::Domain(domain) = node.inner().host() else {
+                builder.insert(node.clone());
+                continue;
+            };
+            let name = match service {
+                Some(service) => format!("{service}.{domain}"),
+                None => domain.to_string(),
+            };
+            let records = query_srv_records(server, &name, feed.fetch_timeout)
+                .await
+                .context(format!("failed to query srv records for {name}"))?;
+            let mut found = false;
+            for group in srv_priority_groups(records) {
+                for srv in group {
+                    if let Some(peer) = self.srv_target_peer(&srv).await {
+                        builder.insert(peer);
+                        found = true;
+                    }
+                }
+                if found {
+                    break;
+                }
+            }
+            if !found {
+                builder.insert(node.clone());
+            }
+        }
+        let Some(nodes) = builder.build() else {
+            return Err(anyhow!("empty peer list"));
+        };
+
+        Ok(FeedPeerSet {
+            nodes,
+            tls_client_identities: AHashMap::new(),
+        })
+    }
+
+    #[cfg(feature = "hickory")]
+    async fn srv_target_peer(&self, srv: &SRV) -> Option<WeightedUpstreamAddr> {
+        let target = srv.target().to_ascii();
+        let addr =
+            match UpstreamAddr::from_host_str_and_port(target.trim_end_matches('.'), srv.port()) {
+                Ok(addr) => addr,
+                Err(e) => {
+                    warn!(
+                        "escaper {}: invalid srv target {target}: {e}",
+                        self.config.name
+                    );
+                    return None;
+                }
+            };
+        self.check_feed_peer_host(addr.host()).ok()?;
+        if let Host::Domain(domain) = addr.host() {
+            let mut resolver_job = self.resolve_happy(domain.clone()).ok()?;
+            let ips = resolver_job
+                .get_r1_or_first(self.config.happy_eyeballs.resolution_delay(), 1)
+                .await
+                .ok()?;
+            if ips.is_empty() {
+                return None;
+            }
+        }
+        // a zero weight record should only have a very small chance to be selected
+        let weight = f64::from(srv.weight()).max(0.01);
+        Some(WeightedUpstreamAddr::with_weight(addr, weight))
+    }
+
+    async fn refresh_feed_peers(&self, feed: &PeerFeedConfig) -> anyhow::Result<FeedPeerSet> {
+        let fetch = async {
+            match &feed.source {
+                PeerFeedSource::Http(url) => {
+                    let data = self.fetch_feed_by_http(feed, url).await?;
+                    parse_feed_peers(feed, &data, |host| self.check_feed_peer_host(host))
+                }
+                PeerFeedSource::Udp(addr) => {
+                    let data = self.fetch_feed_by_udp(feed, *addr).await?;
+                    parse_feed_peers(feed, &data, |host| self.check_feed_peer_host(host))
+                }
+                #[cfg(feature = "hickory")]
+                PeerFeedSource::Srv(server, service) => {
+                    self.query_feed_by_srv(feed, *server, service.as_deref())
+                        .await
+                }
+            }
+        };
+        tokio::time::timeout(feed.fetch_timeout, fetch)
+            .await
+            .map_err(|_| anyhow!("fetch timed out"))?
+    }
+
+    /// Select the tls client identity referenced by the feed peer
+    pub(super) fn select_feed_tls_client_identity(
+        &self,
+        peer: &UpstreamAddr,
+    ) -> Option<(Arc<str>, OpensslCertificatePair)> {
+        let feed = self.config.peer_feed.as_ref()?;
+        let feed_peers = self.feed_peers.load();
+        let name = feed_peers.as_ref()?.tls_client_identity(peer)?;
+        feed.tls_client_identities
+            .get(name)
+            .map(|(name, pair)| (name.clone(), pair.clone()))
+    }
+
+    fn publish_feed_peers(&self, peers: FeedPeerSet) {
+        self.stats
+            .peer_feed
+            .add_refresh_success(peers.nodes.iter().len());
+        let upstreams = || {
+            self.config
+                .proxy_nodes
+                .iter()
+                .chain(peers.nodes.iter())
+                .map(|node| node.inner())
+        };
+        self.stats.upstream_rtt.retain(upstreams());
+        self.stats.upstream_agent.retain(upstreams());
+        self.stats.upstream_select.retain(upstreams());
+        self.feed_peers.store(Some(Arc::new(peers)));
+    }
+}
+
+fn parse_feed_peer<F>(
+    feed: &PeerFeedConfig,
+    value: &ValueRef,
+    check_host: &F,
+) -> anyhow::Result<(WeightedUpstreamAddr, Option<Arc<str>>)>
+where
+    F: Fn(&Host) -> anyhow::Result<()>,
+{
+    let node = g3_msgpack::value::as_weighted_upstream_addr(value, 3128)?;
+    check_host(node.inner().host())?;
+    let weight = node.weight();
+    if !weight.is_finite() || weight < 0.0 {
+        return Err(anyhow!("invalid weight {weight}"));
+    }
+
+    let mut tls_client_identity = None;
+    if let ValueRef::Map(map) = value {
+        for (k, v) in map {
+            let key = g3_msgpack::value::as_string(k).context("all keys should be string")?;
+            match g3_msgpack::key::normalize(key.as_str()).as_str() {
+                "tls_client_identity" | "client_cert" => {
+                    let name = g3_msgpack::value::as_string(v)
+                        .context(format!("invalid string value for key {key}"))?;
+                    let Some((name, _)) = feed.tls_client_identities.get(&name) else {
+                        return Err(anyhow!("no tls client identity {name} found"));
+                    };
+                    tls_client_identity = Some(name.clone());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok((node, tls_client_identity))
+}
+
+fn parse_feed_peers<F>(
+    feed: &PeerFeedConfig,
+    data: &[u8],
+    check_host: F,
+) -> anyhow::Result<FeedPeerSet>
+where
+    F: Fn(&Host) -> anyhow::Result<()>,
+{
+    let mut data = data;
+    let value = rmpv::decode::read_value_ref(&mut data)
+        .map_err(|e| anyhow!("invalid msgpack data: {e}"))?;
+    let ValueRef::Array(seq) = value else {
+        return Err(anyhow!(
+            "msgpack value type for peer list should be 'array'"
+        ));
+    };
+
+    let mut builder = SelectiveVecBuilder::new();
+    let mut tls_client_identities = AHashMap::new();
+    for (i, v) in seq.iter().enumerate() {
+        let (node, identity) =
+            parse_feed_peer(feed, v, &check_host).context(format!("invalid peer #{i}"))?;
+        if let Some(name) = identity {
+            tls_client_identities.insert(node.inner().clone(), name);
+        }
+        builder.insert(node);
+    }
+    let Some(nodes) = builder.build() else {
+        return Err(anyhow!("empty peer list"));
+    };
+
+    Ok(FeedPeerSet {
+        nodes,
+        tls_client_identities,
+    })
+}
+
+#[cfg(feature = "hickory")]
+async fn query_srv_records(
+    server: SocketAddr,
+    name: &str,
+    timeout: Duration,
+) -> anyhow::Result<Vec<SRV>> {
+    let name = Name::from_ascii(name).map_err(|e| anyhow!("invalid srv name: {e}"))?;
+
+    let udp_connect = g3_hickory_client::io::udp::connect(
+        UdpConnectInfo {
+            server,
+            bind: BindAddr::None,
+            buf_conf: Default::default(),
+            misc_opts: Default::default(),
+        },
+        timeout,
+    );
+    let (mut client, bg) = Client::connect(Box::pin(udp_connect))
+        .await
+        .map_err(|e| anyhow!("failed to create udp dns client: {e}"))?;
+    tokio::spawn(bg);
+    let (rsp, _) = client
+        .query(name.clone(), DNSClass::IN, RecordType::SRV)
+        .await
+        .map_err(|e| anyhow!("udp dns query failed: {e}"))?
+        .into_parts();
+
+    if rsp.truncated() {
+        // retry by tcp to get the full record list
+        let (message_sender, outbound_messages) = BufDnsStreamHandle::new(server);
+        let tcp_connect = g3_hickory_client::io::tcp::connect(
+            TcpConnectInfo {
+                server,
+                bind: BindAddr::None,
+                keepalive: Default::default(),
+                misc_opts: Default::default(),
+            },
+            outbound_messages,
+            timeout,
+        );
+        let (mut client, bg) =
+            Client::with_timeout(Box::pin(tcp_connect), message_sender, timeout, None)
+                .await
+                .map_err(|e| anyhow!("failed to create tcp dns client: {e}"))?;
+        tokio::spawn(bg);
+        let (rsp, _) = client
+            .query(name, DNSClass::IN, RecordType::SRV)
+            .await
+            .map_err(|e| anyhow!("tcp dns query failed: {e}"))?
+            .into_parts();
+        return srv_records(rsp);
+    }
+
+    srv_records(rsp)
+}
+
+#[cfg(feature = "hickory")]
+fn srv_records(rsp: Message) -> anyhow::Result<Vec<SRV>> {
+    match rsp.response_code() {
+        ResponseCode::NoError | ResponseCode::NXDomain => {}
+        code => return Err(anyhow!("dns query failed with response code {code}")),
+    }
+    let records = rsp
+        .answers()
+        .iter()
+        .filter_map(|r| match r.data() {
+            // a root target means the service is not available at this domain
+            RData::SRV(srv) if !srv.target().is_root() => Some(srv.clone()),
+            _ => None,
+        })
+        .collect();
+    Ok(records)
+}
+
+/// Group the SRV records by priority, the group with the lowest priority value goes first
+#[cfg(feature = "hickory")]
+fn srv_priority_groups(mut records: Vec<SRV>) -> Vec<Vec<SRV>> {
+    records.sort_by_key(|srv| srv.priority());
+    let mut groups: Vec<Vec<SRV>> = Vec::new();
+    for srv in records {
+        match groups.last_mut() {
+            Some(group) if group[0].priority() == srv.priority() => group.push(srv),
+            _ => groups.push(vec![srv]),
+        }
+    }
+    groups
+}
+
+async fn connect_udp(peer: SocketAddr) -> anyhow::Result<UdpSocket> {
+    let bind_ip = match peer {
+        SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    };
+    let socket = UdpSocket::bind(SocketAddr::new(bind_ip, 0))
+        .await
+        .map_err(|e| anyhow!("failed to bind udp socket: {e}"))?;
+    socket
+        .connect(peer)
+        .await
+        .map_err(|e| anyhow!("failed to connect to {peer}: {e}"))?;
+    Ok(socket)
+}
+
+pub(super) fn spawn_job(escaper: Weak<ProxyHttpEscaper>, mut quit_receiver: mpsc::Receiver<()>) {
+    tokio::spawn(async move {
+        let Some(feed) = escaper
+            .upgrade()
+            .and_then(|escaper| escaper.config.peer_feed.clone())
+        else {
+            return;
+        };
+
+        let mut interval = tokio::time::interval(feed.refresh_interval);
+        loop {
+            tokio::select! {
+                biased;
+
+                _ = quit_receiver.recv() => break,
+                _ = interval.tick() => {
+                    let Some(escaper) = escaper.upgrade() else {
+                        break;
+                    };
+                    match escaper.refresh_feed_peers(&feed).await {
+                        Ok(peers) => escaper.publish_feed_peers(peers),
+                        Err(e) => {
+                            // keep using the last good peer list
+                            escaper.stats.peer_feed.add_refresh_failure();
+                            warn!(
+                                "escaper {}: failed to refresh peers from feed: {e:?}",
+                                escaper.config.name
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn encode(value: &ValueRef) -> Vec<u8> {
+        let mut buf = Vec::new();
+        rmpv::encode::write_value_ref(&mut buf, value).unwrap();
+        buf
+    }
+
+    fn feed_config() -> PeerFeedConfig {
+        let doc = yaml_rust::Yaml::String("udp://127.0.0.1:5353".to_string());
+        PeerFeedConfig::parse_yaml(&doc, None).unwrap()
+    }
+
+    fn weighted_peer(addr: &'static str, weight: f64) -> ValueRef<'static> {
+        ValueRef::Map(vec![
+            (
+                ValueRef::String("addr".into()),
+                ValueRef::String(addr.into()),
+            ),
+            (ValueRef::String("weight".into()), ValueRef::F64(weight)),
+        ])
+    }
+
+    #[test]
+    fn parse_ok() {
+        let feed = feed_config();
+        let data = encode(&ValueRef::Array(vec![
+            ValueRef::String("127.0.0.1".into()),
+            weighted_peer("proxy.example.net:8080", 2.0),
+            weighted_peer("[::1]:3129", 0.0),
+        ]));
+        let peers = parse_feed_peers(&feed, &data, |_| Ok(())).unwrap();
+        let nodes: Vec<_> = peers.nodes().iter().collect();
+        // sorted by weight
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(
+            nodes[0].inner(),
+            &UpstreamAddr::from_str("proxy.example.net:8080").unwrap()
+        );
+        assert_eq!(nodes[0].weight(), 2.0);
+        assert_eq!(
+            nodes[1].inner(),
+            &UpstreamAddr::from_str("127.0.0.1:3128").unwrap()
+        );
+        assert_eq!(
+            nodes[2].inner(),
+            &UpstreamAddr::from_str("[::1]:3129").unwrap()
+        );
+        assert_eq!(nodes[2].weight(), 0.0);
+        assert!(peers.tls_client_identity(nodes[1].inner()).is_none());
+    }
+
+    #[test]
+    fn parse_err() {
+        let feed = feed_config();
+
+        assert!(parse_feed_peers(&feed, b"\xc1", |_| Ok(())).is_err());
+
+        let data = encode(&ValueRef::String("127.0.0.1".into()));
+        assert!(parse_feed_peers(&feed, &data, |_| Ok(())).is_err());
+
+        let data = encode(&ValueRef::Array(vec![]));
+        assert!(parse_feed_peers(&feed, &data, |_| Ok(())).is_err());
+
+        for weight in [-1.0, f64::NAN, f64::INFINITY] {
+            let data = encode(&ValueRef::Array(vec![weighted_peer("127.0.0.1", weight)]));
+            assert!(parse_feed_peers(&feed, &data, |_| Ok(())).is_err());
+        }
+
+        let data = encode(&ValueRef::Array(vec![ValueRef::Map(vec![
+            (
+                ValueRef::String("addr".into()),
+                ValueRef::String("127.0.0.1".into()),
+            ),
+            (
+                ValueRef::String("tls_client_identity".into()),
+                ValueRef::String("not-found".into()),
+            ),
+        ])]));
+        assert!(parse_feed_peers(&feed, &data, |_| Ok(())).is_err());
+
+        let data = encode(&ValueRef::Array(vec![
+            ValueRef::String("127.0.0.1".into()),
+            ValueRef::String("proxy.example.net".into()),
+        ]));
+        let r = parse_feed_peers(&feed, &data, |host| match host {
+            Host::Domain(_) => Err(anyhow!("no resolver")),
+            Host::Ip(_) => Ok(()),
+        });
+        assert!(r.is_err());
+    }
+
+    #[cfg(feature = "hickory")]
+    fn srv(priority: u16, weight: u16, target: &str) -> SRV {
+        SRV::new(priority, weight, 8080, Name::from_ascii(target).unwrap())
+    }
+
+    #[cfg(feature = "hickory")]
+    #[test]
+    fn srv_groups() {
+        let groups = srv_priority_groups(vec![
+            srv(20, 1, "c.example.net."),
+            srv(10, 1, "a.example.net."),
+            srv(20, 2, "d.example.net."),
+            srv(10, 2, "b.example.net."),
+        ]);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].len(), 2);
+        assert!(groups[0].iter().all(|srv| srv.priority() == 10));
+        assert_eq!(groups[1].len(), 2);
+        assert!(groups[1].iter().all(|srv| srv.priority() == 20));
+
+        assert!(srv_priority_groups(Vec::new()).is_empty());
+    }
+
+    #[cfg(feature = "hickory")]
+    #[test]
+    fn srv_response() {
+        use hickory_proto::rr::Record;
+
+        let name = Name::from_ascii("_proxy._tcp.example.net.").unwrap();
+        let mut rsp = Message::new();
+        rsp.add_answer(Record::from_rdata(
+            name.clone(),
+            60,
+            RData::SRV(srv(10, 1, "a.example.net.")),
+        ));
+        rsp.add_answer(Record::from_rdata(
+            name.clone(),
+            60,
+            RData::SRV(srv(10, 1, ".")),
+        ));
+        let records = srv_records(rsp).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].target().to_ascii(), "a.example.net.");
+
+        let mut rsp = Message::new();
+        rsp.set_response_code(ResponseCode::NXDomain);
+        assert!(srv_records(rsp).unwrap().is_empty());
+
+        let mut rsp = Message::new();
+        rsp.set_response_code(ResponseCode::ServFail);
+        assert!(srv_records(rsp).is_err());
+    }
+}
@@ -21,11 +21,15 @@ use arc_swap::ArcSwapOption;
 use g3_daemon::stat::remote::TcpConnectionTaskRemoteStats;
 use g3_io_ext::{LimitedReaderStats, LimitedWriterStats};
 use g3_types::metrics::{NodeName, StaticMetricsTags};
+use g3_types::net::UpstreamAddr;
 use g3_types::stats::{StatId, TcpIoSnapshot};
 
 use crate::escape::{
-    EscaperInterfaceStats, EscaperInternalStats, EscaperStats, EscaperTcpConnectSnapshot,
-    EscaperTcpStats,
+    EscaperCircuitBreakerSnapshot, EscaperCircuitBreakerStats, EscaperInterfaceStats,
+    EscaperInternalStats, EscaperNegotiationQueueStats, EscaperPeerFeedSnapshot,
+    EscaperPeerFeedStats, EscaperPeerHealthSnapshot, EscaperPeerHealthStats, EscaperStats,
+    EscaperTcpConnectSnapshot, EscaperTcpStats, EscaperUpstreamAgentStats, EscaperUpstreamRttStats,
+    EscaperUpstreamSelectStats, EscaperUpstreamTunnelStats,
 };
 use crate::module::http_forward::HttpForwardTaskRemoteStats;
 
@@ -35,6 +39,14 @@ pub(crate) struct ProxyHttpEscaperStats {
     extra_metrics_tags: Arc<ArcSwapOption<StaticMetricsTags>>,
     pub(crate) interface: EscaperInterfaceStats,
     pub(crate) tcp: EscaperTcpStats,
+    pub(crate) peer_health: EscaperPeerHealthStats,
+    pub(crate) peer_feed: EscaperPeerFeedStats,
+    pub(crate) negotiation_queue: EscaperNegotiationQueueStats,
+    pub(crate) upstream_rtt: EscaperUpstreamRttStats,
+    pub(crate) upstream_agent: EscaperUpstreamAgentStats,
+    pub(crate) upstream_tunnel: EscaperUpstreamTunnelStats,
+    pub(crate) upstream_select: EscaperUpstreamSelectStats,
+    pub(crate) circuit_breaker: EscaperCircuitBreakerStats,
 }
 
 impl ProxyHttpEscaperStats {
@@ -45,6 +57,14 @@ impl ProxyHttpEscaperStats {
             extra_metrics_tags: Arc::new(ArcSwapOption::new(None)),
             interface: EscaperInterfaceStats::default(),
             tcp: EscaperTcpStats::default(),
+            peer_health: EscaperPeerHealthStats::default(),
+            peer_feed: EscaperPeerFeedStats::default(),
+            negotiation_queue: EscaperNegotiationQueueStats::default(),
+            upstream_rtt: EscaperUpstreamRttStats::default(),
+            upstream_agent: EscaperUpstreamAgentStats::default(),
+            upstream_tunnel: EscaperUpstreamTunnelStats::default(),
+            upstream_select: EscaperUpstreamSelectStats::default(),
+            circuit_breaker: EscaperCircuitBreakerStats::default(),
         }
     }
 
@@ -101,6 +121,38 @@ impl EscaperStats for ProxyHttpEscaperStats {
     fn tcp_io_snapshot(&self) -> Option<TcpIoSnapshot> {
         Some(self.tcp.io.snapshot())
     }
+
+    fn peer_health_snapshot(&self) -> Option<EscaperPeerHealthSnapshot> {
+        self.peer_health.snapshot()
+    }
+
+    fn peer_feed_snapshot(&self) -> Option<EscaperPeerFeedSnapshot> {
+        self.peer_feed.snapshot()
+    }
+
+    fn negotiation_queued(&self) -> Option<u64> {
+        self.negotiation_queue.queued()
+    }
+
+    fn circuit_breaker_snapshot(&self) -> Option<EscaperCircuitBreakerSnapshot> {
+        self.circuit_breaker.snapshot()
+    }
+
+    fn upstream_rtt_snapshot(&self) -> Option<Vec<(UpstreamAddr, u64)>> {
+        Some(self.upstream_rtt.snapshot())
+    }
+
+    fn upstream_tunnel_snapshot(&self) -> Option<Vec<(UpstreamAddr, u64)>> {
+        Some(self.upstream_tunnel.snapshot())
+    }
+
+    fn upstream_select_snapshot(&self) -> Option<Vec<(UpstreamAddr, f64, u64)>> {
+        Some(self.upstream_select.snapshot())
+    }
+
+    fn upstream_agent_snapshot(&self) -> Option<Vec<(UpstreamAddr, &'static str, Option<String>)>> {
+        Some(self.upstream_agent.snapshot())
+    }
 }
 
 impl LimitedReaderStats for ProxyHttpEscaperStats {
@@ -0,0 +1,245 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::{self, IoSlice};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{tcp, TcpStream};
+#[cfg(unix)]
+use tokio::net::{unix, UnixStream};
+
+use g3_io_ext::AsyncStream;
+
+use super::PeerTunnelPermit;
+
+enum PeerConnection {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+enum PeerConnectionReadHalf {
+    Tcp(tcp::OwnedReadHalf),
+    #[cfg(unix)]
+    Unix(unix::OwnedReadHalf),
+}
+
+enum PeerConnectionWriteHalf {
+    Tcp(tcp::OwnedWriteHalf),
+    #[cfg(unix)]
+    Unix(unix::OwnedWriteHalf),
+}
+
+/// The stream to the next proxy, which may be a tcp or a unix socket connection
+pub(super) struct PeerStream {
+    conn: PeerConnection,
+    tunnel_permit: Option<Arc<PeerTunnelPermit>>,
+}
+
+impl PeerStream {
+    pub(super) fn new_tcp(stream: TcpStream, tunnel_permit: Option<PeerTunnelPermit>) -> Self {
+        PeerStream {
+            conn: PeerConnection::Tcp(stream),
+            tunnel_permit: tunnel_permit.map(Arc::new),
+        }
+    }
+
+    #[cfg(unix)]
+    pub(super) fn new_unix(stream: UnixStream) -> Self {
+        PeerStream {
+            conn: PeerConnection::Unix(stream),
+            tunnel_permit: None,
+        }
+    }
+}
+
+pub(super) struct PeerStreamReadHalf {
+    conn: PeerConnectionReadHalf,
+    _tunnel_permit: Option<Arc<PeerTunnelPermit>>,
+}
+
+pub(super) struct PeerStreamWriteHalf {
+    conn: PeerConnectionWriteHalf,
+    _tunnel_permit: Option<Arc<PeerTunnelPermit>>,
+}
+
+impl AsyncStream for PeerStream {
+    type R = PeerStreamReadHalf;
+    type W = PeerStreamWriteHalf;
+
+    fn into_split(self) -> (Self::R, Self::W) {
+        let (r, w) = match self.conn {
+            PeerConnection::Tcp(s) => {
+                let (r, w) = s.into_split();
+                (
+                    PeerConnectionReadHalf::Tcp(r),
+                    PeerConnectionWriteHalf::Tcp(w),
+                )
+            }
+            #[cfg(unix)]
+            PeerConnection::Unix(s) => {
+                let (r, w) = s.into_split();
+                (
+                    PeerConnectionReadHalf::Unix(r),
+                    PeerConnectionWriteHalf::Unix(w),
+                )
+            }
+        };
+        (
+            PeerStreamReadHalf {
+                conn: r,
+                _tunnel_permit: self.tunnel_permit.clone(),
+            },
+            PeerStreamWriteHalf {
+                conn: w,
+                _tunnel_permit: self.tunnel_permit,
+            },
+        )
+    }
+}
+
+macro_rules! impl_async_read {
+    ($t:ident) => {
+        impl AsyncRead for $t {
+            fn poll_read(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &mut ReadBuf<'_>,
+            ) -> Poll<io::Result<()>> {
+                match self.get_mut() {
+                    $t::Tcp(s) => Pin::new(s).poll_read(cx, buf),
+                    #[cfg(unix)]
+                    $t::Unix(s) => Pin::new(s).poll_read(cx, buf),
+                }
+            }
+        }
+    };
+}
+
+macro_rules! impl_async_write {
+    ($t:ident) => {
+        impl AsyncWrite for $t {
+            fn poll_write(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &[u8],
+            ) -> Poll<io::Result<usize>> {
+                match self.get_mut() {
+                    $t::Tcp(s) => Pin::new(s).poll_write(cx, buf),
+                    #[cfg(unix)]
+                    $t::Unix(s) => Pin::new(s).poll_write(cx, buf),
+                }
+            }
+
+            fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+                match self.get_mut() {
+                    $t::Tcp(s) => Pin::new(s).poll_flush(cx),
+                    #[cfg(unix)]
+                    $t::Unix(s) => Pin::new(s).poll_flush(cx),
+                }
+            }
+
+            fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+                match self.get_mut() {
+                    $t::Tcp(s) => Pin::new(s).poll_shutdown(cx),
+                    #[cfg(unix)]
+                    $t::Unix(s) => Pin::new(s).poll_shutdown(cx),
+                }
+            }
+
+            fn poll_write_vectored(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                bufs: &[IoSlice<'_>],
+            ) -> Poll<io::Result<usize>> {
+                match self.get_mut() {
+                    $t::Tcp(s) => Pin::new(s).poll_write_vectored(cx, bufs),
+                    #[cfg(unix)]
+                    $t::Unix(s) => Pin::new(s).poll_write_vectored(cx, bufs),
+                }
+            }
+
+            fn is_write_vectored(&self) -> bool {
+                match self {
+                    $t::Tcp(s) => s.is_write_vectored(),
+                    #[cfg(unix)]
+                    $t::Unix(s) => s.is_write_vectored(),
+                }
+            }
+        }
+    };
+}
+
+macro_rules! impl_delegate_read {
+    ($t:ident) => {
+        impl AsyncRead for $t {
+            fn poll_read(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &mut ReadBuf<'_>,
+            ) -> Poll<io::Result<()>> {
+                Pin::new(&mut self.get_mut().conn).poll_read(cx, buf)
+            }
+        }
+    };
+}
+
+macro_rules! impl_delegate_write {
+    ($t:ident) => {
+        impl AsyncWrite for $t {
+            fn poll_write(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &[u8],
+            ) -> Poll<io::Result<usize>> {
+                Pin::new(&mut self.get_mut().conn).poll_write(cx, buf)
+            }
+
+            fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+                Pin::new(&mut self.get_mut().conn).poll_flush(cx)
+            }
+
+            fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+                Pin::new(&mut self.get_mut().conn).poll_shutdown(cx)
+            }
+
+            fn poll_write_vectored(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                bufs: &[IoSlice<'_>],
+            ) -> Poll<io::Result<usize>> {
+                Pin::new(&mut self.get_mut().conn).poll_write_vectored(cx, bufs)
+            }
+
+            fn is_write_vectored(&self) -> bool {
+                self.conn.is_write_vectored()
+            }
+        }
+    };
+}
+
+impl_async_read!(PeerConnection);
+impl_async_read!(PeerConnectionReadHalf);
+impl_async_write!(PeerConnection);
+impl_async_write!(PeerConnectionWriteHalf);
+
+impl_delegate_read!(PeerStream);
+impl_delegate_read!(PeerStreamReadHalf);
+impl_delegate_write!(PeerStream);
+impl_delegate_write!(PeerStreamWriteHalf);
@@ -14,27 +14,70 @@
  * limitations under the License.
  */
 
+use std::io;
 use std::net::{IpAddr, SocketAddr};
+#[cfg(unix)]
+use std::path::Path;
+use std::sync::Arc;
 
+use anyhow::anyhow;
 use tokio::io::AsyncWriteExt;
+#[cfg(unix)]
+use tokio::net::UnixStream;
 use tokio::net::{TcpSocket, TcpStream};
 use tokio::task::JoinSet;
 use tokio::time::Instant;
 
+use g3_geoip_types::IpLocation;
 use g3_io_ext::LimitedStream;
 use g3_socket::BindAddr;
-use g3_types::net::{ConnectError, Host, ProxyProtocolEncoder};
+use g3_types::net::{ConnectError, Host, PortRange, ProxyProtocolEncoder, UpstreamAddr};
 
-use super::ProxyHttpEscaper;
+use super::{PeerStream, PeerTunnelPermit, ProxyHttpEscaper};
 use crate::log::escape::tcp_connect::EscapeLogForTcpConnect;
 use crate::module::tcp_connect::{TcpConnectError, TcpConnectTaskConf, TcpConnectTaskNotes};
 use crate::resolve::HappyEyeballsResolveJob;
 use crate::serve::ServerTaskNotes;
 
+/// The next proxy selected for a new connection
+pub(super) enum SelectedPeer {
+    #[cfg(unix)]
+    Unix,
+    Tcp(UpstreamAddr, Option<PeerTunnelPermit>),
+}
+
 impl ProxyHttpEscaper {
-    fn prepare_connect_socket(
+    /// Get the ip location of the upstream address, which is used to select the geo bind ip.
+    ///
+    /// None will be returned if the upstream domain can not be resolved or the
+    /// lookup timed out, and the default bind ip will be used in that case.
+    /// The located results are cached for each upstream host.
+    async fn locate_upstream(&self, upstream: &UpstreamAddr) -> Option<Arc<IpLocation>> {
+        let ip_locate_handle = self.ip_locate_handle.as_ref()?;
+        if let Some(location) = self.upstream_locations.get(upstream.host()) {
+            return Some(location);
+        }
+        let ip = match upstream.host() {
+            Host::Ip(ip) => *ip,
+            Host::Domain(domain) => {
+                let mut resolver_job = self.resolve_happy(domain.clone()).ok()?;
+                let mut ips = resolver_job
+                    .get_r1_or_first(self.config.happy_eyeballs.resolution_delay(), 1)
+                    .await
+                    .ok()?;
+                ips.pop()?
+            }
+        };
+        let location = ip_locate_handle.fetch(ip).await?;
+        self.upstream_locations
+            .insert(upstream.host().clone(), location.clone());
+        Some(location)
+    }
+
+    pub(super) fn prepare_connect_socket(
         &self,
         peer_ip: IpAddr,
+        upstream_location: Option<&IpLocation>,
     ) -> Result<(TcpSocket, BindAddr), TcpConnectError> {
         let bind_ip = match peer_ip {
             IpAddr::V4(_) => {
@@ -50,6 +93,9 @@ impl ProxyHttpEscaper {
                 self.config.bind_v6.map(IpAddr::V6)
             }
         };
+        let bind_ip = upstream_location
+            .and_then(|location| self.config.geo_bind.select_bind_ip(location, peer_ip))
+            .or(bind_ip);
 
         #[cfg(any(target_os = "linux", target_os = "android"))]
         let bind = bind_ip.map(BindAddr::Ip).unwrap_or_else(|| {
@@ -60,27 +106,135 @@ impl ProxyHttpEscaper {
         });
         #[cfg(not(any(target_os = "linux", target_os = "android")))]
         let bind = bind_ip.map(BindAddr::Ip).unwrap_or_default();
-        let sock = g3_socket::tcp::new_socket_to(
+        let sock = self.new_connect_socket(peer_ip, &bind)?;
+        // set the buffer size before connect, so the window scale option can be set correctly
+        let buf_conf = &self.config.tcp_socket_buffer;
+        if let Some(size) = buf_conf.send_size() {
+            sock.set_send_buffer_size(size.min(u32::MAX as usize) as u32)
+                .map_err(TcpConnectError::SetupSocketFailed)?;
+        }
+        if let Some(size) = buf_conf.recv_size() {
+            sock.set_recv_buffer_size(size.min(u32::MAX as usize) as u32)
+                .map_err(TcpConnectError::SetupSocketFailed)?;
+        }
+        Ok((sock, bind))
+    }
+
+    fn new_connect_socket(
+        &self,
+        peer_ip: IpAddr,
+        bind: &BindAddr,
+    ) -> Result<TcpSocket, TcpConnectError> {
+        if let Some(port_range) = self.config.local_port_range {
+            return self.new_connect_socket_in_range(peer_ip, bind, port_range);
+        }
+        #[cfg(target_os = "linux")]
+        if self.config.enable_mptcp {
+            return g3_socket::tcp::new_mptcp_socket_to(
+                peer_ip,
+                bind,
+                &self.config.tcp_keepalive,
+                &self.config.tcp_misc_opts,
+                true,
+            )
+            .map_err(TcpConnectError::SetupSocketFailed);
+        }
+        g3_socket::tcp::new_socket_to(
             peer_ip,
-            &bind,
+            bind,
             &self.config.tcp_keepalive,
             &self.config.tcp_misc_opts,
             true,
         )
-        .map_err(TcpConnectError::SetupSocketFailed)?;
-        Ok((sock, bind))
+        .map_err(TcpConnectError::SetupSocketFailed)
+    }
+
+    fn new_connect_socket_in_range(
+        &self,
+        peer_ip: IpAddr,
+        bind: &BindAddr,
+        port_range: PortRange,
+    ) -> Result<TcpSocket, TcpConnectError> {
+        #[cfg(target_os = "linux")]
+        let r = if self.config.enable_mptcp {
+            g3_socket::tcp::new_mptcp_socket_in_range_to(
+                peer_ip,
+                bind,
+                port_range,
+                &self.config.tcp_keepalive,
+                &self.config.tcp_misc_opts,
+                true,
+            )
+        } else {
+            g3_socket::tcp::new_socket_in_range_to(
+                peer_ip,
+                bind,
+                port_range,
+                &self.config.tcp_keepalive,
+                &self.config.tcp_misc_opts,
+                true,
+            )
+        };
+        #[cfg(not(target_os = "linux"))]
+        let r = g3_socket::tcp::new_socket_in_range_to(
+            peer_ip,
+            bind,
+            port_range,
+            &self.config.tcp_keepalive,
+            &self.config.tcp_misc_opts,
+            true,
+        );
+        r.map_err(|e| {
+            if e.kind() == io::ErrorKind::AddrNotAvailable {
+                self.stats.tcp.connect.add_local_port_exhausted();
+            }
+            TcpConnectError::SetupSocketFailed(e)
+        })
+    }
+
+    /// Set the ipv6 flow label on the socket, and return the peer address to connect to.
+    /// The flow label only applies to ipv6 peers.
+    #[cfg(target_os = "linux")]
+    fn set_ipv6_flow_label(
+        &self,
+        sock: &TcpSocket,
+        peer: SocketAddr,
+        task_notes: &ServerTaskNotes,
+    ) -> Result<SocketAddr, TcpConnectError> {
+        let Some(flow_label) = &self.config.ipv6_flow_label else {
+            return Ok(peer);
+        };
+        if peer.is_ipv4() {
+            return Ok(peer);
+        }
+        let label = flow_label.label_for_task(&task_notes.id);
+        g3_socket::tcp::set_ipv6_flow_label(sock, peer, label)
+            .map_err(TcpConnectError::SetupSocketFailed)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn set_ipv6_flow_label(
+        &self,
+        _sock: &TcpSocket,
+        peer: SocketAddr,
+        _task_notes: &ServerTaskNotes,
+    ) -> Result<SocketAddr, TcpConnectError> {
+        Ok(peer)
     }
 
     async fn fixed_try_connect(
         &self,
         peer: SocketAddr,
+        upstream_location: Option<&IpLocation>,
         task_conf: &TcpConnectTaskConf<'_>,
         tcp_notes: &mut TcpConnectTaskNotes,
         task_notes: &ServerTaskNotes,
     ) -> Result<TcpStream, TcpConnectError> {
-        let (sock, bind) = self.prepare_connect_socket(peer.ip())?;
+        let (sock, bind) = self.prepare_connect_socket(peer.ip(), upstream_location)?;
+        let connect_peer = self.set_ipv6_flow_label(&sock, peer, task_notes)?;
         tcp_notes.next = Some(peer);
         tcp_notes.bind = bind;
+        tcp_notes.socket_cookie = g3_socket::tcp::socket_cookie(&sock);
 
         let instant_now = Instant::now();
 
@@ -88,7 +242,7 @@ impl ProxyHttpEscaper {
         tcp_notes.tries = 1;
         match tokio::time::timeout(
             self.config.general.tcp_connect.each_timeout(),
-            sock.connect(peer),
+            sock.connect(connect_peer),
         )
         .await
         {
@@ -141,6 +295,7 @@ impl ProxyHttpEscaper {
         &self,
         mut resolver_job: HappyEyeballsResolveJob,
         peer_port: u16,
+        upstream_location: Option<&IpLocation>,
         task_conf: &TcpConnectTaskConf<'_>,
         tcp_notes: &mut TcpConnectTaskNotes,
         task_notes: &ServerTaskNotes,
@@ -152,6 +307,10 @@ impl ProxyHttpEscaper {
                 max_tries_each_family,
             )
             .await?;
+        let preferred_ip = self
+            .connect_addr_cache
+            .as_ref()
+            .and_then(|cache| cache.prefer(&mut ips, peer_port));
 
         let mut c_set = JoinSet::new();
 
@@ -173,30 +332,49 @@ impl ProxyHttpEscaper {
         loop {
             if spawn_new_connection {
                 if let Some(ip) = ips.pop() {
-                    let (sock, bind) = self.prepare_connect_socket(ip)?;
+                    let (sock, bind) = self.prepare_connect_socket(ip, upstream_location)?;
+                    let socket_cookie = g3_socket::tcp::socket_cookie(&sock);
                     let peer = SocketAddr::new(ip, peer_port);
+                    let connect_peer = self.set_ipv6_flow_label(&sock, peer, task_notes)?;
                     running_connection += 1;
                     spawn_new_connection = false;
                     tcp_notes.tries += 1;
                     let stats = self.stats.clone();
+                    let addr_cache = self.connect_addr_cache.clone();
                     c_set.spawn(async move {
                         stats.tcp.connect.add_attempted();
-                        match tokio::time::timeout(each_timeout, sock.connect(peer)).await {
+                        let connect_start = Instant::now();
+                        match tokio::time::timeout(each_timeout, sock.connect(connect_peer)).await {
                             Ok(Ok(stream)) => {
                                 stats.tcp.connect.add_success();
-                                (Ok(stream), peer, bind)
+                                if let Some(cache) = addr_cache {
+                                    cache.add_sample(peer, connect_start.elapsed());
+                                }
+                                (Ok(stream), peer, bind, socket_cookie)
                             }
                             Ok(Err(e)) => {
                                 stats.tcp.connect.add_error();
+                                if let Some(cache) = addr_cache {
+                                    cache.add_sample(peer, each_timeout);
+                                }
                                 (
                                     Err(TcpConnectError::ConnectFailed(ConnectError::from(e))),
                                     peer,
                                     bind,
+                                    socket_cookie,
                                 )
                             }
                             Err(_) => {
                                 stats.tcp.connect.add_timeout();
-                                (Err(TcpConnectError::TimeoutByRule), peer, bind)
+                                if let Some(cache) = addr_cache {
+                                    cache.add_sample(peer, each_timeout);
+                                }
+                                (
+                                    Err(TcpConnectError::TimeoutByRule),
+                                    peer,
+                                    bind,
+                                    socket_cookie,
+                                )
                             }
                         }
                     });
@@ -216,12 +394,20 @@ impl ProxyHttpEscaper {
                                 let peer_addr = r.1;
                                 tcp_notes.next = Some(peer_addr);
                                 tcp_notes.bind = r.2;
+                                tcp_notes.socket_cookie = r.3;
                                 match r.0 {
                                     Ok(ups_stream) => {
                                         let local_addr = ups_stream
                                             .local_addr()
                                             .map_err(TcpConnectError::SetupSocketFailed)?;
                                         self.stats.tcp.connect.add_established();
+                                        self.stats.tcp.connect.add_happy_established(peer_addr);
+                                        if let Some(ip) = preferred_ip {
+                                            self.stats
+                                                .tcp
+                                                .connect
+                                                .add_preferred_addr_result(ip == peer_addr.ip());
+                                        }
                                         tcp_notes.local = Some(local_addr);
                                         // the chained outgoing addr is not detected at here
                                         return Ok(ups_stream);
@@ -291,18 +477,48 @@ impl ProxyHttpEscaper {
         }
     }
 
+    /// Select the next proxy and wait for the per peer tunnel permit.
+    ///
+    /// The wait will be stopped early at the negotiation deadline if set.
+    pub(super) async fn select_peer(
+        &self,
+        task_conf: &TcpConnectTaskConf<'_>,
+        tcp_notes: &mut TcpConnectTaskNotes,
+        task_notes: &ServerTaskNotes,
+        deadline: Option<Instant>,
+    ) -> Result<SelectedPeer, TcpConnectError> {
+        #[cfg(unix)]
+        if self.config.proxy_unix_path.is_some() {
+            return Ok(SelectedPeer::Unix);
+        }
+
+        let next_proxy = self
+            .get_next_proxy(task_notes, task_conf.upstream.host())
+            .ok_or_else(|| TcpConnectError::EscaperNotUsable(anyhow!("no next proxy node set")))?;
+        let peer_proxy = next_proxy.inner().clone();
+        self.stats.upstream_select.add_selected(&next_proxy);
+        tcp_notes.next_proxy = Some(next_proxy);
+
+        let tunnel_permit = self
+            .acquire_peer_tunnel_permit(&peer_proxy, deadline)
+            .await?;
+        Ok(SelectedPeer::Tcp(peer_proxy, tunnel_permit))
+    }
+
     async fn tcp_connect_to(
         &self,
+        peer_proxy: &UpstreamAddr,
         task_conf: &TcpConnectTaskConf<'_>,
         tcp_notes: &mut TcpConnectTaskNotes,
         task_notes: &ServerTaskNotes,
     ) -> Result<TcpStream, TcpConnectError> {
-        let peer_proxy = self.get_next_proxy(task_notes, task_conf.upstream.host());
+        let upstream_location = self.locate_upstream(task_conf.upstream).await;
 
         match peer_proxy.host() {
             Host::Ip(ip) => {
                 self.fixed_try_connect(
                     SocketAddr::new(*ip, peer_proxy.port()),
+                    upstream_location.as_deref(),
                     task_conf,
                     tcp_notes,
                     task_notes,
@@ -315,6 +531,7 @@ impl ProxyHttpEscaper {
                 self.happy_try_connect(
                     resolver_job,
                     peer_proxy.port(),
+                    upstream_location.as_deref(),
                     task_conf,
                     tcp_notes,
                     task_notes,
@@ -324,14 +541,148 @@ impl ProxyHttpEscaper {
         }
     }
 
+    async fn acquire_peer_tunnel_permit(
+        &self,
+        peer_proxy: &UpstreamAddr,
+        deadline: Option<Instant>,
+    ) -> Result<Option<PeerTunnelPermit>, TcpConnectError> {
+        let Some(limiter) = self.peer_tunnel_limiters.get(peer_proxy) else {
+            return Ok(None);
+        };
+        let permit = match limiter.try_acquire() {
+            Some(permit) => permit,
+            None => {
+                if self.config.peer_tunnel_wait_timeout.is_zero() {
+                    return Err(TcpConnectError::PeerTunnelLimitReached);
+                }
+                let mut wait_deadline = Instant::now() + self.config.peer_tunnel_wait_timeout;
+                if let Some(deadline) = deadline {
+                    wait_deadline = wait_deadline.min(deadline);
+                }
+                limiter
+                    .acquire_until(wait_deadline)
+                    .await
+                    .ok_or(TcpConnectError::PeerTunnelLimitReached)?
+            }
+        };
+        let alive = self.stats.upstream_tunnel.get_alive_counter(peer_proxy);
+        Ok(Some(PeerTunnelPermit::new(permit, limiter.clone(), alive)))
+    }
+
+    #[cfg(unix)]
+    async fn unix_connect_to(
+        &self,
+        path: &Path,
+        task_conf: &TcpConnectTaskConf<'_>,
+        tcp_notes: &mut TcpConnectTaskNotes,
+        task_notes: &ServerTaskNotes,
+    ) -> Result<UnixStream, TcpConnectError> {
+        let instant_now = Instant::now();
+
+        self.stats.tcp.connect.add_attempted();
+        tcp_notes.tries = 1;
+        match tokio::time::timeout(
+            self.config.general.tcp_connect.each_timeout(),
+            UnixStream::connect(path),
+        )
+        .await
+        {
+            Ok(Ok(ups_stream)) => {
+                self.stats.tcp.connect.add_success();
+                tcp_notes.duration = instant_now.elapsed();
+                self.stats.tcp.connect.add_established();
+                Ok(ups_stream)
+            }
+            Ok(Err(e)) => {
+                self.stats.tcp.connect.add_error();
+                tcp_notes.duration = instant_now.elapsed();
+
+                let e = TcpConnectError::ConnectFailed(ConnectError::from(e));
+                EscapeLogForTcpConnect {
+                    upstream: task_conf.upstream,
+                    tcp_notes,
+                    task_id: &task_notes.id,
+                }
+                .log(&self.escape_logger, &e);
+                Err(e)
+            }
+            Err(_) => {
+                self.stats.tcp.connect.add_timeout();
+                tcp_notes.duration = instant_now.elapsed();
+
+                let e = TcpConnectError::TimeoutByRule;
+                EscapeLogForTcpConnect {
+                    upstream: task_conf.upstream,
+                    tcp_notes,
+                    task_id: &task_notes.id,
+                }
+                .log(&self.escape_logger, &e);
+                Err(e)
+            }
+        }
+    }
+
+    async fn peer_connect_to(
+        &self,
+        peer: SelectedPeer,
+        task_conf: &TcpConnectTaskConf<'_>,
+        tcp_notes: &mut TcpConnectTaskNotes,
+        task_notes: &ServerTaskNotes,
+    ) -> Result<PeerStream, TcpConnectError> {
+        let (peer_proxy, tunnel_permit) = match peer {
+            #[cfg(unix)]
+            SelectedPeer::Unix => {
+                let Some(path) = &self.config.proxy_unix_path else {
+                    return Err(TcpConnectError::EscaperNotUsable(anyhow!(
+                        "no next proxy unix path set"
+                    )));
+                };
+                let stream = self
+                    .unix_connect_to(path, task_conf, tcp_notes, task_notes)
+                    .await?;
+                return Ok(PeerStream::new_unix(stream));
+            }
+            SelectedPeer::Tcp(peer_proxy, tunnel_permit) => (peer_proxy, tunnel_permit),
+        };
+
+        let stream = self
+            .tcp_connect_to(&peer_proxy, task_conf, tcp_notes, task_notes)
+            .await?;
+
+        #[cfg(target_os = "linux")]
+        if self.config.enable_mptcp {
+            match g3_socket::tcp::is_mptcp_in_use(&stream) {
+                Some(true) => self.stats.tcp.connect.add_mptcp_established(),
+                Some(false) => self.stats.tcp.connect.add_mptcp_fallback(),
+                None => {} // unable to check on old kernels
+            }
+        }
+
+        Ok(PeerStream::new_tcp(stream, tunnel_permit))
+    }
+
     pub(super) async fn tcp_new_connection(
         &self,
         task_conf: &TcpConnectTaskConf<'_>,
         tcp_notes: &mut TcpConnectTaskNotes,
         task_notes: &ServerTaskNotes,
-    ) -> Result<LimitedStream<TcpStream>, TcpConnectError> {
+    ) -> Result<LimitedStream<PeerStream>, TcpConnectError> {
+        let peer = self
+            .select_peer(task_conf, tcp_notes, task_notes, None)
+            .await?;
+        self.tcp_new_connection_to(peer, task_conf, tcp_notes, task_notes)
+            .await
+    }
+
+    pub(super) async fn tcp_new_connection_to(
+        &self,
+        peer: SelectedPeer,
+        task_conf: &TcpConnectTaskConf<'_>,
+        tcp_notes: &mut TcpConnectTaskNotes,
+        task_notes: &ServerTaskNotes,
+    ) -> Result<LimitedStream<PeerStream>, TcpConnectError> {
         let stream = self
-            .tcp_connect_to(task_conf, tcp_notes, task_notes)
+            .peer_connect_to(peer, task_conf, tcp_notes, task_notes)
             .await?;
 
         let limit_config = &self.config.general.tcp_sock_speed_limit;
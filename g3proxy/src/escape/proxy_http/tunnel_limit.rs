@@ -0,0 +1,170 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+/// The limit of alive tunnels to a next proxy.
+///
+/// It will be kept across reloads, so the tunnels created before the reload are still counted.
+pub(super) struct PeerTunnelLimiter {
+    semaphore: Arc<Semaphore>,
+    limit: AtomicUsize,
+    /// permits that should be forgotten instead of released, as the limit has been decreased
+    to_forget: AtomicUsize,
+}
+
+impl PeerTunnelLimiter {
+    pub(super) fn new(limit: usize) -> Self {
+        PeerTunnelLimiter {
+            semaphore: Arc::new(Semaphore::new(limit)),
+            limit: AtomicUsize::new(limit),
+            to_forget: AtomicUsize::new(0),
+        }
+    }
+
+    /// Update the limit in place, should only be called when reloading
+    pub(super) fn set_limit(&self, limit: usize) {
+        let old = self.limit.swap(limit, Ordering::AcqRel);
+        if limit > old {
+            let delta = limit - old;
+            // cancel the pending decrease first
+            let pending = self
+                .to_forget
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                    Some(n - n.min(delta))
+                })
+                .unwrap_or_default();
+            let add = delta - pending.min(delta);
+            if add > 0 {
+                self.semaphore.add_permits(add);
+            }
+        } else if limit < old {
+            let delta = old - limit;
+            let forgot = self.semaphore.forget_permits(delta);
+            if forgot < delta {
+                self.to_forget.fetch_add(delta - forgot, Ordering::AcqRel);
+            }
+        }
+    }
+
+    pub(super) fn try_acquire(&self) -> Option<OwnedSemaphorePermit> {
+        self.semaphore.clone().try_acquire_owned().ok()
+    }
+
+    pub(super) async fn acquire_until(&self, deadline: Instant) -> Option<OwnedSemaphorePermit> {
+        match tokio::time::timeout_at(deadline, self.semaphore.clone().acquire_owned()).await {
+            Ok(Ok(permit)) => Some(permit),
+            Ok(Err(_)) => None, // the semaphore won't be closed
+            Err(_) => None,
+        }
+    }
+
+    fn take_forget(&self) -> bool {
+        self.to_forget
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| n.checked_sub(1))
+            .is_ok()
+    }
+}
+
+/// The permit for an alive tunnel to the next proxy, which will be released when dropped
+pub(super) struct PeerTunnelPermit {
+    permit: Option<OwnedSemaphorePermit>,
+    limiter: Arc<PeerTunnelLimiter>,
+    alive: Arc<AtomicU64>,
+}
+
+impl PeerTunnelPermit {
+    pub(super) fn new(
+        permit: OwnedSemaphorePermit,
+        limiter: Arc<PeerTunnelLimiter>,
+        alive: Arc<AtomicU64>,
+    ) -> Self {
+        alive.fetch_add(1, Ordering::Relaxed);
+        PeerTunnelPermit {
+            permit: Some(permit),
+            limiter,
+            alive,
+        }
+    }
+}
+
+impl Drop for PeerTunnelPermit {
+    fn drop(&mut self) {
+        self.alive.fetch_sub(1, Ordering::Relaxed);
+        if let Some(permit) = self.permit.take() {
+            if self.limiter.take_forget() {
+                permit.forget();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn acquire(limiter: &Arc<PeerTunnelLimiter>, alive: &Arc<AtomicU64>) -> PeerTunnelPermit {
+        let permit = limiter.try_acquire().unwrap();
+        PeerTunnelPermit::new(permit, limiter.clone(), alive.clone())
+    }
+
+    #[test]
+    fn decrease_limit() {
+        let limiter = Arc::new(PeerTunnelLimiter::new(3));
+        let alive = Arc::new(AtomicU64::new(0));
+
+        let p1 = acquire(&limiter, &alive);
+        let p2 = acquire(&limiter, &alive);
+        assert_eq!(alive.load(Ordering::Relaxed), 2);
+
+        limiter.set_limit(1);
+        assert!(limiter.try_acquire().is_none());
+
+        drop(p1);
+        assert!(limiter.try_acquire().is_none());
+        drop(p2);
+        assert_eq!(alive.load(Ordering::Relaxed), 0);
+
+        let p3 = acquire(&limiter, &alive);
+        assert!(limiter.try_acquire().is_none());
+        drop(p3);
+        assert_eq!(limiter.semaphore.available_permits(), 1);
+    }
+
+    #[test]
+    fn increase_limit() {
+        let limiter = Arc::new(PeerTunnelLimiter::new(2));
+        let alive = Arc::new(AtomicU64::new(0));
+
+        let p1 = acquire(&limiter, &alive);
+        let p2 = acquire(&limiter, &alive);
+        assert!(limiter.try_acquire().is_none());
+
+        limiter.set_limit(1);
+        limiter.set_limit(3);
+        let p3 = acquire(&limiter, &alive);
+        assert!(limiter.try_acquire().is_none());
+
+        drop(p1);
+        drop(p2);
+        drop(p3);
+        assert_eq!(limiter.semaphore.available_permits(), 3);
+    }
+}
@@ -18,6 +18,7 @@ use std::sync::Arc;
 
 use anyhow::anyhow;
 use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::time::Instant;
 
 use g3_daemon::stat::remote::{
     ArcTcpConnectionTaskRemoteStats, TcpConnectionTaskRemoteStatsWrapper,
@@ -27,6 +28,7 @@ use g3_io_ext::{AsyncStream, FlexBufReader, LimitedReader, LimitedWriter, OnceBu
 use g3_openssl::{SslConnector, SslStream};
 
 use super::ProxyHttpsEscaper;
+use crate::log::escape::http_connect::EscapeLogForHttpConnect;
 use crate::log::escape::tls_handshake::{EscapeLogForTlsHandshake, TlsApplication};
 use crate::module::tcp_connect::{
     TcpConnectError, TcpConnectResult, TcpConnectTaskConf, TcpConnectTaskNotes, TlsConnectTaskConf,
@@ -40,10 +42,6 @@ impl ProxyHttpsEscaper {
         tcp_notes: &mut TcpConnectTaskNotes,
         task_notes: &ServerTaskNotes,
     ) -> Result<FlexBufReader<SslStream<impl AsyncRead + AsyncWrite>>, TcpConnectError> {
-        let mut stream = self
-            .tls_handshake_to_remote(task_conf, tcp_notes, task_notes)
-            .await?;
-
         let mut req = HttpConnectRequest::new(task_conf.upstream, &self.config.append_http_headers);
 
         if self.config.pass_proxy_userid {
@@ -53,16 +51,71 @@ impl ProxyHttpsEscaper {
             }
         }
 
-        req.send(&mut stream)
-            .await
-            .map_err(TcpConnectError::NegotiationWriteFailed)?;
+        // CONNECT is safe to be replayed, so it can be sent as TLS early data
+        let mut early_data = Vec::new();
+        if self.config.tls_early_data {
+            req.send(&mut early_data)
+                .await
+                .map_err(TcpConnectError::NegotiationWriteFailed)?;
+        }
 
-        let mut buf_stream = FlexBufReader::new(stream);
-        let _ =
-            HttpConnectResponse::recv(&mut buf_stream, self.config.http_connect_rsp_hdr_max_size)
-                .await?;
+        let mut stream = self
+            .tls_handshake_to_remote(
+                task_conf,
+                tcp_notes,
+                task_notes,
+                (!early_data.is_empty()).then_some(early_data.as_slice()),
+            )
+            .await?;
+        // resend the request if it's not sent as early data or rejected by the peer
+        if stream.early_data_accepted() != Some(true) {
+            if let Err(e) = req.send(&mut stream).await {
+                let e = TcpConnectError::NegotiationWriteFailed(e);
+                EscapeLogForHttpConnect {
+                    upstream: task_conf.upstream,
+                    tcp_notes,
+                    task_id: &task_notes.id,
+                }
+                .log(&self.escape_logger, &e);
+                return Err(e);
+            }
+        }
 
-        // TODO detect and set outgoing_addr and target_addr for supported remote proxies
+        let mut buf_stream = FlexBufReader::new(stream);
+        match HttpConnectResponse::recv(&mut buf_stream, self.config.http_connect_rsp_hdr_max_size)
+            .await
+        {
+            Ok(rsp) => {
+                if rsp.has_unexpected_body() {
+                    self.stats.tcp.connect.add_unexpected_rsp_body();
+                    let log = EscapeLogForHttpConnect {
+                        upstream: task_conf.upstream,
+                        tcp_notes,
+                        task_id: &task_notes.id,
+                    };
+                    if self.config.http_connect_rsp_reject_body {
+                        let e = TcpConnectError::NegotiationUnexpectedBody;
+                        log.log(&self.escape_logger, &e);
+                        return Err(e);
+                    }
+                    log.log_discarded_body(&self.escape_logger, rsp.code);
+                }
+                let (outgoing_addr, target_addr) =
+                    crate::module::http_header::parse_remote_connection_info(&rsp.headers);
+                tcp_notes.chained.outgoing_addr = outgoing_addr;
+                tcp_notes.chained.target_addr = target_addr;
+            }
+            Err(e) => {
+                let e = TcpConnectError::from(e);
+                EscapeLogForHttpConnect {
+                    upstream: task_conf.upstream,
+                    tcp_notes,
+                    task_id: &task_notes.id,
+                }
+                .log(&self.escape_logger, &e);
+                return Err(e);
+            }
+        }
 
         Ok(buf_stream)
     }
@@ -73,12 +126,39 @@ impl ProxyHttpsEscaper {
         tcp_notes: &mut TcpConnectTaskNotes,
         task_notes: &ServerTaskNotes,
     ) -> Result<FlexBufReader<SslStream<impl AsyncRead + AsyncWrite>>, TcpConnectError> {
-        tokio::time::timeout(
-            self.config.peer_negotiation_timeout,
+        let (deadline, by_task_deadline) =
+            task_notes.escaper_connect_deadline(self.config.peer_negotiation_timeout)?;
+        let negotiation = tokio::time::timeout_at(
+            deadline,
             self.http_connect_tcp_connect_to(task_conf, tcp_notes, task_notes),
-        )
-        .await
-        .map_err(|_| TcpConnectError::NegotiationPeerTimeout)?
+        );
+
+        let mut timed_out = false;
+        let r = tokio::select! {
+            r = negotiation => r.unwrap_or_else(|_| {
+                timed_out = true;
+                Err(if by_task_deadline {
+                    TcpConnectError::TaskDeadlineExceeded
+                } else {
+                    TcpConnectError::NegotiationPeerTimeout
+                })
+            }),
+            _ = crate::escape::quit::wait_negotiation_quit() => {
+                Err(TcpConnectError::CanceledAsServerQuit)
+            }
+        };
+        if let Err(e) = &r {
+            if timed_out {
+                EscapeLogForHttpConnect {
+                    upstream: task_conf.upstream,
+                    tcp_notes,
+                    task_id: &task_notes.id,
+                }
+                .log(&self.escape_logger, e);
+            }
+            self.stats.connect_error.add_error(e);
+        }
+        r
     }
 
     pub(super) async fn http_connect_new_tcp_connection(
@@ -127,10 +207,29 @@ impl ProxyHttpsEscaper {
         let connector = SslConnector::new(ssl, buf_stream.into_inner())
             .map_err(|e| TcpConnectError::InternalTlsClientError(anyhow::Error::new(e)))?;
 
-        match tokio::time::timeout(task_conf.handshake_timeout(), connector.connect()).await {
-            Ok(Ok(stream)) => Ok(stream),
+        let handshake_timeout = self
+            .config
+            .upstream_tls_handshake_timeout
+            .unwrap_or_else(|| task_conf.handshake_timeout());
+        let instant_now = Instant::now();
+        match tokio::time::timeout(handshake_timeout, connector.connect()).await {
+            Ok(Ok(stream)) => {
+                if self.config.general.tls_handshake_log.sample_success() {
+                    EscapeLogForTlsHandshake {
+                        upstream: task_conf.tcp.upstream,
+                        tcp_notes,
+                        task_id: &task_notes.id,
+                        tls_name: task_conf.tls_name,
+                        tls_peer: task_conf.tcp.upstream,
+                        tls_application,
+                        tls_handshake_duration: instant_now.elapsed(),
+                    }
+                    .log_success(&self.escape_logger, stream.ssl());
+                }
+                Ok(stream)
+            }
             Ok(Err(e)) => {
-                let e = anyhow::Error::new(e);
+                let e = task_conf.handshake_error(e);
                 EscapeLogForTlsHandshake {
                     upstream: task_conf.tcp.upstream,
                     tcp_notes,
@@ -138,9 +237,12 @@ impl ProxyHttpsEscaper {
                     tls_name: task_conf.tls_name,
                     tls_peer: task_conf.tcp.upstream,
                     tls_application,
+                    tls_handshake_duration: instant_now.elapsed(),
                 }
                 .log(&self.escape_logger, &e);
-                Err(TcpConnectError::UpstreamTlsHandshakeFailed(e))
+                let e = TcpConnectError::UpstreamTlsHandshakeFailed(e);
+                self.stats.connect_error.add_error(&e);
+                Err(e)
             }
             Err(_) => {
                 let e = anyhow!("upstream tls handshake timed out");
@@ -151,9 +253,12 @@ impl ProxyHttpsEscaper {
                     tls_name: task_conf.tls_name,
                     tls_peer: task_conf.tcp.upstream,
                     tls_application,
+                    tls_handshake_duration: instant_now.elapsed(),
                 }
                 .log(&self.escape_logger, &e);
-                Err(TcpConnectError::UpstreamTlsHandshakeTimeout)
+                let e = TcpConnectError::UpstreamTlsHandshakeTimeout;
+                self.stats.connect_error.add_error(&e);
+                Err(e)
             }
         }
     }
@@ -43,7 +43,7 @@ impl ProxyHttpsEscaper {
         task_stats: ArcHttpForwardTaskRemoteStats,
     ) -> Result<BoxHttpForwardConnection, TcpConnectError> {
         let tls_stream = self
-            .tls_handshake_to_remote(task_conf, tcp_notes, task_notes)
+            .tls_handshake_to_remote(task_conf, tcp_notes, task_notes, None)
             .await?;
         let (ups_r, ups_w) = tls_stream.into_split();
 
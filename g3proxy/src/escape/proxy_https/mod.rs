@@ -100,6 +100,9 @@ impl ProxyHttpsEscaper {
             Some(crate::resolve::get_handle(resolver)?)
         };
 
+        stats
+            .upstream_select
+            .retain(config.proxy_nodes.iter().map(|node| node.inner()));
         stats.set_extra_tags(config.extra_metrics_tags.clone());
 
         let escaper = ProxyHttpsEscaper {
@@ -129,14 +132,17 @@ impl ProxyHttpsEscaper {
         }
     }
 
-    fn get_next_proxy(&self, task_notes: &ServerTaskNotes, target_host: &Host) -> &UpstreamAddr {
+    fn get_next_proxy(
+        &self,
+        task_notes: &ServerTaskNotes,
+        target_host: &Host,
+    ) -> &WeightedUpstreamAddr {
         self.select_consistent(
             &self.proxy_nodes,
             self.config.proxy_pick_policy,
             task_notes,
             target_host,
         )
-        .inner()
     }
 
     fn resolve_happy(&self, domain: Arc<str>) -> Result<HappyEyeballsResolveJob, ResolveError> {
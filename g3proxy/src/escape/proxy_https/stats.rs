@@ -21,11 +21,13 @@ use arc_swap::ArcSwapOption;
 use g3_daemon::stat::remote::TcpConnectionTaskRemoteStats;
 use g3_io_ext::{LimitedReaderStats, LimitedWriterStats};
 use g3_types::metrics::{NodeName, StaticMetricsTags};
+use g3_types::net::UpstreamAddr;
 use g3_types::stats::{StatId, TcpIoSnapshot};
 
 use crate::escape::{
-    EscaperInterfaceStats, EscaperInternalStats, EscaperStats, EscaperTcpConnectSnapshot,
-    EscaperTcpStats, EscaperTlsSnapshot, EscaperTlsStats,
+    EscaperConnectErrorSnapshot, EscaperConnectErrorStats, EscaperInterfaceStats,
+    EscaperInternalStats, EscaperStats, EscaperTcpConnectSnapshot, EscaperTcpStats,
+    EscaperTlsSnapshot, EscaperTlsStats, EscaperUpstreamSelectStats,
 };
 use crate::module::http_forward::HttpForwardTaskRemoteStats;
 
@@ -36,6 +38,8 @@ pub(crate) struct ProxyHttpsEscaperStats {
     pub(crate) interface: EscaperInterfaceStats,
     pub(crate) tcp: EscaperTcpStats,
     pub(crate) tls: EscaperTlsStats,
+    pub(crate) connect_error: EscaperConnectErrorStats,
+    pub(crate) upstream_select: EscaperUpstreamSelectStats,
 }
 
 impl ProxyHttpsEscaperStats {
@@ -47,6 +51,8 @@ impl ProxyHttpsEscaperStats {
             interface: EscaperInterfaceStats::default(),
             tcp: EscaperTcpStats::default(),
             tls: EscaperTlsStats::default(),
+            connect_error: EscaperConnectErrorStats::default(),
+            upstream_select: EscaperUpstreamSelectStats::default(),
         }
     }
 
@@ -104,6 +110,14 @@ impl EscaperStats for ProxyHttpsEscaperStats {
         Some(self.tls.snapshot())
     }
 
+    fn connect_error_snapshot(&self) -> Option<EscaperConnectErrorSnapshot> {
+        Some(self.connect_error.snapshot())
+    }
+
+    fn upstream_select_snapshot(&self) -> Option<Vec<(UpstreamAddr, f64, u64)>> {
+        Some(self.upstream_select.snapshot())
+    }
+
     fn tcp_io_snapshot(&self) -> Option<TcpIoSnapshot> {
         Some(self.tcp.io.snapshot())
     }
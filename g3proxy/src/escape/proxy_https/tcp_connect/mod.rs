@@ -81,6 +81,7 @@ impl ProxyHttpsEscaper {
         let (sock, bind) = self.prepare_connect_socket(peer.ip())?;
         tcp_notes.next = Some(peer);
         tcp_notes.bind = bind;
+        tcp_notes.socket_cookie = g3_socket::tcp::socket_cookie(&sock);
 
         let instant_now = Instant::now();
 
@@ -174,6 +175,7 @@ impl ProxyHttpsEscaper {
             if spawn_new_connection {
                 if let Some(ip) = ips.pop() {
                     let (sock, bind) = self.prepare_connect_socket(ip)?;
+                    let socket_cookie = g3_socket::tcp::socket_cookie(&sock);
                     let peer = SocketAddr::new(ip, peer_port);
                     running_connection += 1;
                     spawn_new_connection = false;
@@ -184,7 +186,7 @@ impl ProxyHttpsEscaper {
                         match tokio::time::timeout(each_timeout, sock.connect(peer)).await {
                             Ok(Ok(stream)) => {
                                 stats.tcp.connect.add_success();
-                                (Ok(stream), peer, bind)
+                                (Ok(stream), peer, bind, socket_cookie)
                             }
                             Ok(Err(e)) => {
                                 stats.tcp.connect.add_error();
@@ -192,11 +194,17 @@ impl ProxyHttpsEscaper {
                                     Err(TcpConnectError::ConnectFailed(ConnectError::from(e))),
                                     peer,
                                     bind,
+                                    socket_cookie,
                                 )
                             }
                             Err(_) => {
                                 stats.tcp.connect.add_timeout();
-                                (Err(TcpConnectError::TimeoutByRule), peer, bind)
+                                (
+                                    Err(TcpConnectError::TimeoutByRule),
+                                    peer,
+                                    bind,
+                                    socket_cookie,
+                                )
                             }
                         }
                     });
@@ -216,12 +224,14 @@ impl ProxyHttpsEscaper {
                                 let peer_addr = r.1;
                                 tcp_notes.next = Some(peer_addr);
                                 tcp_notes.bind = r.2;
+                                tcp_notes.socket_cookie = r.3;
                                 match r.0 {
                                     Ok(ups_stream) => {
                                         let local_addr = ups_stream
                                             .local_addr()
                                             .map_err(TcpConnectError::SetupSocketFailed)?;
                                         self.stats.tcp.connect.add_established();
+                                        self.stats.tcp.connect.add_happy_established(peer_addr);
                                         tcp_notes.local = Some(local_addr);
                                         // the chained outgoing addr is not detected at here
                                         return Ok(ups_stream);
@@ -297,9 +307,10 @@ impl ProxyHttpsEscaper {
         tcp_notes: &mut TcpConnectTaskNotes,
         task_notes: &ServerTaskNotes,
     ) -> Result<(UpstreamAddr, TcpStream), TcpConnectError> {
-        let peer_proxy = self
-            .get_next_proxy(task_notes, task_conf.upstream.host())
-            .clone();
+        let next_proxy = self.get_next_proxy(task_notes, task_conf.upstream.host());
+        self.stats.upstream_select.add_selected(next_proxy);
+        tcp_notes.next_proxy = Some(next_proxy.clone());
+        let peer_proxy = next_proxy.inner().clone();
 
         let stream = match peer_proxy.host() {
             Host::Ip(ip) => {
@@ -16,6 +16,7 @@
 
 use anyhow::anyhow;
 use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::time::Instant;
 
 use g3_openssl::{SslConnector, SslStream};
 
@@ -30,22 +31,60 @@ impl ProxyHttpsEscaper {
         task_conf: &TcpConnectTaskConf<'_>,
         tcp_notes: &mut TcpConnectTaskNotes,
         task_notes: &ServerTaskNotes,
+        early_data: Option<&[u8]>,
     ) -> Result<SslStream<impl AsyncRead + AsyncWrite>, TcpConnectError> {
         let (peer, ups_s) = self
             .tcp_new_connection(task_conf, tcp_notes, task_notes)
             .await?;
 
         let tls_name = self.config.tls_name.as_ref().unwrap_or_else(|| peer.host());
+        let identity = self
+            .config
+            .tls_client_identities
+            .select(tls_name, task_notes);
         let ssl = self
             .tls_config
-            .build_ssl(tls_name, peer.port())
+            .build_ssl_with_identity(
+                tls_name,
+                peer.port(),
+                identity.map(|(name, pair)| (name.as_ref(), pair)),
+            )
             .map_err(TcpConnectError::InternalTlsClientError)?;
-        let connector = SslConnector::new(ssl, ups_s)
+        tcp_notes.tls_client_identity = identity.map(|(name, _)| name.clone());
+        let mut connector = SslConnector::new(ssl, ups_s)
             .map_err(|e| TcpConnectError::InternalTlsClientError(anyhow::Error::new(e)))?;
 
-        match tokio::time::timeout(self.tls_config.handshake_timeout, connector.connect()).await {
+        let instant_now = Instant::now();
+        let handshake = async {
+            if let Some(data) = early_data {
+                // only send early data if the resumed session allows it,
+                // the caller should resend the data if it's not accepted
+                if connector.early_data_allowed() {
+                    connector.write_all_early_data(data).await?;
+                }
+            }
+            connector.connect().await
+        };
+        match tokio::time::timeout(self.tls_config.handshake_timeout, handshake).await {
             Ok(Ok(stream)) => {
                 self.stats.tls.add_handshake_success();
+                match stream.early_data_accepted() {
+                    Some(true) => self.stats.tls.add_early_data_accepted(),
+                    Some(false) => self.stats.tls.add_early_data_rejected(),
+                    None => {}
+                }
+                if self.config.general.tls_handshake_log.sample_success() {
+                    EscapeLogForTlsHandshake {
+                        upstream: task_conf.upstream,
+                        tcp_notes,
+                        task_id: &task_notes.id,
+                        tls_name,
+                        tls_peer: &peer,
+                        tls_application: TlsApplication::HttpProxy,
+                        tls_handshake_duration: instant_now.elapsed(),
+                    }
+                    .log_success(&self.escape_logger, stream.ssl());
+                }
                 Ok(stream)
             }
             Ok(Err(e)) => {
@@ -58,6 +97,7 @@ impl ProxyHttpsEscaper {
                     tls_name,
                     tls_peer: &peer,
                     tls_application: TlsApplication::HttpProxy,
+                    tls_handshake_duration: instant_now.elapsed(),
                 }
                 .log(&self.escape_logger, &e);
                 Err(TcpConnectError::PeerTlsHandshakeFailed(e))
@@ -72,6 +112,7 @@ impl ProxyHttpsEscaper {
                     tls_name,
                     tls_peer: &peer,
                     tls_application: TlsApplication::HttpProxy,
+                    tls_handshake_duration: instant_now.elapsed(),
                 }
                 .log(&self.escape_logger, &e);
                 Err(TcpConnectError::PeerTlsHandshakeTimeout)
@@ -16,6 +16,7 @@
 
 use anyhow::anyhow;
 use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::time::Instant;
 
 use g3_openssl::{SslConnector, SslStream};
 
@@ -43,9 +44,22 @@ impl ProxySocks5sEscaper {
         let connector = SslConnector::new(ssl, ups_s)
             .map_err(|e| TcpConnectError::InternalTlsClientError(anyhow::Error::new(e)))?;
 
+        let instant_now = Instant::now();
         match tokio::time::timeout(self.tls_config.handshake_timeout, connector.connect()).await {
             Ok(Ok(stream)) => {
                 self.stats.tls.add_handshake_success();
+                if self.config.general.tls_handshake_log.sample_success() {
+                    EscapeLogForTlsHandshake {
+                        upstream: task_conf.upstream,
+                        tcp_notes,
+                        task_id: &task_notes.id,
+                        tls_name,
+                        tls_peer: &peer,
+                        tls_application: TlsApplication::HttpProxy,
+                        tls_handshake_duration: instant_now.elapsed(),
+                    }
+                    .log_success(&self.escape_logger, stream.ssl());
+                }
                 Ok(stream)
             }
             Ok(Err(e)) => {
@@ -58,6 +72,7 @@ impl ProxySocks5sEscaper {
                     tls_name,
                     tls_peer: &peer,
                     tls_application: TlsApplication::HttpProxy,
+                    tls_handshake_duration: instant_now.elapsed(),
                 }
                 .log(&self.escape_logger, &e);
                 Err(TcpConnectError::PeerTlsHandshakeFailed(e))
@@ -72,6 +87,7 @@ impl ProxySocks5sEscaper {
                     tls_name,
                     tls_peer: &peer,
                     tls_application: TlsApplication::HttpProxy,
+                    tls_handshake_duration: instant_now.elapsed(),
                 }
                 .log(&self.escape_logger, &e);
                 Err(TcpConnectError::PeerTlsHandshakeTimeout)
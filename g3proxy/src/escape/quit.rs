@@ -0,0 +1,35 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::LazyLock;
+
+use tokio::sync::watch;
+
+static NEGOTIATION_QUIT_SENDER: LazyLock<watch::Sender<bool>> =
+    LazyLock::new(|| watch::Sender::new(false));
+
+/// Cancel all in-flight upstream negotiations.
+///
+/// This should only be called after the graceful wait time of the daemon quit process.
+pub(crate) fn force_quit_negotiation() {
+    NEGOTIATION_QUIT_SENDER.send_replace(true);
+}
+
+/// Wait until the in-flight upstream negotiations should be canceled.
+pub(super) async fn wait_negotiation_quit() {
+    let mut receiver = NEGOTIATION_QUIT_SENDER.subscribe();
+    let _ = receiver.wait_for(|quit| *quit).await;
+}
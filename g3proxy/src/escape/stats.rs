@@ -14,14 +14,20 @@
  * limitations under the License.
  */
 
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
+use ahash::AHashMap;
 use arc_swap::ArcSwapOption;
 
 use g3_types::metrics::{NodeName, StaticMetricsTags};
+use g3_types::net::{ConnectError, UpstreamAddr, WeightedUpstreamAddr};
 use g3_types::stats::{StatId, TcpIoSnapshot, TcpIoStats, UdpIoSnapshot, UdpIoStats};
 
+use crate::module::tcp_connect::TcpConnectError;
+
 pub(crate) trait EscaperInternalStats {
     fn add_http_forward_request_attempted(&self);
     fn add_https_forward_request_attempted(&self);
@@ -48,6 +54,10 @@ pub(crate) trait EscaperStats: EscaperInternalStats {
         None
     }
 
+    fn connect_error_snapshot(&self) -> Option<EscaperConnectErrorSnapshot> {
+        None
+    }
+
     fn tcp_io_snapshot(&self) -> Option<TcpIoSnapshot> {
         None
     }
@@ -59,6 +69,42 @@ pub(crate) trait EscaperStats: EscaperInternalStats {
     fn forbidden_snapshot(&self) -> Option<EscaperForbiddenSnapshot> {
         None
     }
+
+    fn circuit_breaker_snapshot(&self) -> Option<EscaperCircuitBreakerSnapshot> {
+        None
+    }
+
+    fn peer_health_snapshot(&self) -> Option<EscaperPeerHealthSnapshot> {
+        None
+    }
+
+    fn peer_feed_snapshot(&self) -> Option<EscaperPeerFeedSnapshot> {
+        None
+    }
+
+    fn peer_expire_snapshot(&self) -> Option<EscaperPeerExpireSnapshot> {
+        None
+    }
+
+    fn negotiation_queued(&self) -> Option<u64> {
+        None
+    }
+
+    fn upstream_rtt_snapshot(&self) -> Option<Vec<(UpstreamAddr, u64)>> {
+        None
+    }
+
+    fn upstream_tunnel_snapshot(&self) -> Option<Vec<(UpstreamAddr, u64)>> {
+        None
+    }
+
+    fn upstream_select_snapshot(&self) -> Option<Vec<(UpstreamAddr, f64, u64)>> {
+        None
+    }
+
+    fn upstream_agent_snapshot(&self) -> Option<Vec<(UpstreamAddr, &'static str, Option<String>)>> {
+        None
+    }
 }
 
 pub(crate) type ArcEscaperInternalStats = Arc<dyn EscaperInternalStats + Send + Sync>;
@@ -67,11 +113,17 @@ pub(crate) type ArcEscaperStats = Arc<dyn EscaperStats + Send + Sync>;
 #[derive(Default)]
 pub(crate) struct EscaperForbiddenSnapshot {
     pub(crate) ip_blocked: u64,
+    pub(crate) asn_blocked: u64,
+    pub(crate) ip_locate_failed_permitted: u64,
+    pub(crate) ip_locate_failed_forbidden: u64,
 }
 
 #[derive(Default)]
 pub(crate) struct EscaperForbiddenStats {
     ip_blocked: AtomicU64,
+    asn_blocked: AtomicU64,
+    ip_locate_failed_permitted: AtomicU64,
+    ip_locate_failed_forbidden: AtomicU64,
 }
 
 impl EscaperForbiddenStats {
@@ -79,13 +131,402 @@ impl EscaperForbiddenStats {
         self.ip_blocked.fetch_add(1, Ordering::Relaxed);
     }
 
+    pub(crate) fn add_asn_blocked(&self) {
+        self.asn_blocked.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_ip_locate_failed(&self, forbid: bool) {
+        if forbid {
+            self.ip_locate_failed_forbidden
+                .fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.ip_locate_failed_permitted
+                .fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
     pub(crate) fn snapshot(&self) -> EscaperForbiddenSnapshot {
         EscaperForbiddenSnapshot {
             ip_blocked: self.ip_blocked.load(Ordering::Relaxed),
+            asn_blocked: self.asn_blocked.load(Ordering::Relaxed),
+            ip_locate_failed_permitted: self.ip_locate_failed_permitted.load(Ordering::Relaxed),
+            ip_locate_failed_forbidden: self.ip_locate_failed_forbidden.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct EscaperPeerHealthSnapshot {
+    pub(crate) healthy: u64,
+    pub(crate) unhealthy: u64,
+}
+
+#[derive(Default)]
+pub(crate) struct EscaperPeerExpireSnapshot {
+    pub(crate) alive: u64,
+    pub(crate) expired: u64,
+}
+
+#[derive(Default)]
+pub(crate) struct EscaperPeerHealthStats {
+    healthy: AtomicU64,
+    unhealthy: AtomicU64,
+}
+
+impl EscaperPeerHealthStats {
+    pub(crate) fn set(&self, healthy: u64, unhealthy: u64) {
+        self.healthy.store(healthy, Ordering::Relaxed);
+        self.unhealthy.store(unhealthy, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> Option<EscaperPeerHealthSnapshot> {
+        let healthy = self.healthy.load(Ordering::Relaxed);
+        let unhealthy = self.unhealthy.load(Ordering::Relaxed);
+        if healthy == 0 && unhealthy == 0 {
+            // health check is not enabled or not yet run
+            None
+        } else {
+            Some(EscaperPeerHealthSnapshot { healthy, unhealthy })
         }
     }
 }
 
+#[derive(Default)]
+pub(crate) struct EscaperNegotiationQueueStats {
+    enabled: AtomicBool,
+    queued: AtomicU64,
+}
+
+impl EscaperNegotiationQueueStats {
+    pub(crate) fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub(crate) fn enter(&self) -> EscaperNegotiationQueueGuard<'_> {
+        self.queued.fetch_add(1, Ordering::Relaxed);
+        EscaperNegotiationQueueGuard(self)
+    }
+
+    pub(crate) fn queued(&self) -> Option<u64> {
+        if self.enabled.load(Ordering::Relaxed) {
+            Some(self.queued.load(Ordering::Relaxed))
+        } else {
+            None
+        }
+    }
+}
+
+pub(crate) struct EscaperNegotiationQueueGuard<'a>(&'a EscaperNegotiationQueueStats);
+
+impl Drop for EscaperNegotiationQueueGuard<'_> {
+    fn drop(&mut self) {
+        self.0.queued.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// The state of the circuit breaker, 0 for closed, 1 for open and 2 for half-open
+#[derive(Default)]
+pub(crate) struct EscaperCircuitBreakerStats {
+    enabled: AtomicBool,
+    state: AtomicU8,
+    tripped: AtomicU64,
+}
+
+impl EscaperCircuitBreakerStats {
+    pub(crate) fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            self.state.store(0, Ordering::Relaxed);
+        }
+    }
+
+    pub(super) fn set_state(&self, state: u8) {
+        self.state.store(state, Ordering::Relaxed);
+    }
+
+    pub(super) fn add_trip(&self) {
+        self.tripped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> Option<EscaperCircuitBreakerSnapshot> {
+        if self.enabled.load(Ordering::Relaxed) {
+            Some(EscaperCircuitBreakerSnapshot {
+                state: self.state.load(Ordering::Relaxed),
+                tripped: self.tripped.load(Ordering::Relaxed),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct EscaperCircuitBreakerSnapshot {
+    pub(crate) state: u8,
+    pub(crate) tripped: u64,
+}
+
+/// The refresh result of the external peer feed
+#[derive(Default)]
+pub(crate) struct EscaperPeerFeedStats {
+    enabled: AtomicBool,
+    refresh_success: AtomicU64,
+    refresh_failure: AtomicU64,
+    peer_count: AtomicU64,
+}
+
+impl EscaperPeerFeedStats {
+    pub(crate) fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            self.peer_count.store(0, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn add_refresh_success(&self, peer_count: usize) {
+        self.refresh_success.fetch_add(1, Ordering::Relaxed);
+        self.peer_count.store(peer_count as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_refresh_failure(&self) {
+        self.refresh_failure.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> Option<EscaperPeerFeedSnapshot> {
+        if self.enabled.load(Ordering::Relaxed) {
+            Some(EscaperPeerFeedSnapshot {
+                refresh_success: self.refresh_success.load(Ordering::Relaxed),
+                refresh_failure: self.refresh_failure.load(Ordering::Relaxed),
+                peer_count: self.peer_count.load(Ordering::Relaxed),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct EscaperPeerFeedSnapshot {
+    pub(crate) refresh_success: u64,
+    pub(crate) refresh_failure: u64,
+    pub(crate) peer_count: u64,
+}
+
+/// The EWMA of the CONNECT negotiation round trip time to each next proxy, in microseconds
+#[derive(Default)]
+pub(crate) struct EscaperUpstreamRttStats {
+    created: Instant,
+    inner: RwLock<AHashMap<UpstreamAddr, Arc<UpstreamRttValue>>>,
+}
+
+#[derive(Default)]
+struct UpstreamRttValue {
+    ewma: AtomicU64,
+    /// the time of the last sample, in milliseconds since the creation of the stats
+    updated: AtomicU64,
+}
+
+impl Default for EscaperUpstreamRttStats {
+    fn default() -> Self {
+        EscaperUpstreamRttStats {
+            created: Instant::now(),
+            inner: RwLock::new(AHashMap::new()),
+        }
+    }
+}
+
+impl EscaperUpstreamRttStats {
+    fn elapsed_millis(&self) -> u64 {
+        u64::try_from(self.created.elapsed().as_millis()).unwrap_or(u64::MAX)
+    }
+
+    pub(crate) fn add_sample(&self, upstream: &UpstreamAddr, rtt: Duration) {
+        let sample = u64::try_from(rtt.as_micros()).unwrap_or(u64::MAX).max(1);
+        let value = self.inner.read().unwrap().get(upstream).cloned();
+        let value = match value {
+            Some(v) => v,
+            None => {
+                let mut map = self.inner.write().unwrap();
+                map.entry(upstream.clone()).or_default().clone()
+            }
+        };
+        // use the same smoothing factor 1/8 as tcp srtt
+        let _ = value
+            .ewma
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |old| {
+                if old == 0 {
+                    Some(sample)
+                } else {
+                    Some(old - old / 8 + sample / 8)
+                }
+            });
+        value
+            .updated
+            .store(self.elapsed_millis(), Ordering::Relaxed);
+    }
+
+    /// Get the current EWMA value, 0 will be returned if no sample has been added
+    /// within `max_age`, so the upstream can be probed again
+    pub(crate) fn get_recent(&self, upstream: &UpstreamAddr, max_age: Duration) -> u64 {
+        let map = self.inner.read().unwrap();
+        let Some(value) = map.get(upstream) else {
+            return 0;
+        };
+        let age = self
+            .elapsed_millis()
+            .saturating_sub(value.updated.load(Ordering::Relaxed));
+        if u128::from(age) > max_age.as_millis() {
+            return 0;
+        }
+        value.ewma.load(Ordering::Relaxed)
+    }
+
+    /// Drop the values for the upstreams that are no longer in use
+    pub(crate) fn retain<'a, I>(&self, upstreams: I)
+    where
+        I: Iterator<Item = &'a UpstreamAddr>,
+    {
+        let upstreams: Vec<&UpstreamAddr> = upstreams.collect();
+        self.inner
+            .write()
+            .unwrap()
+            .retain(|k, _| upstreams.contains(&k));
+    }
+
+    pub(crate) fn snapshot(&self) -> Vec<(UpstreamAddr, u64)> {
+        self.inner
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(k, v)| (k.clone(), v.ewma.load(Ordering::Relaxed)))
+            .collect()
+    }
+}
+
+/// The count of alive tunnels to each next proxy
+#[derive(Default)]
+pub(crate) struct EscaperUpstreamTunnelStats {
+    inner: RwLock<AHashMap<UpstreamAddr, Arc<AtomicU64>>>,
+}
+
+impl EscaperUpstreamTunnelStats {
+    /// Get the alive counter, which should be increased and decreased by the caller
+    pub(crate) fn get_alive_counter(&self, upstream: &UpstreamAddr) -> Arc<AtomicU64> {
+        if let Some(v) = self.inner.read().unwrap().get(upstream) {
+            return v.clone();
+        }
+        let mut map = self.inner.write().unwrap();
+        map.entry(upstream.clone()).or_default().clone()
+    }
+
+    /// Drop the values for the upstreams that are no longer in use
+    pub(crate) fn retain<'a, I>(&self, upstreams: I)
+    where
+        I: Iterator<Item = &'a UpstreamAddr>,
+    {
+        let upstreams: Vec<&UpstreamAddr> = upstreams.collect();
+        self.inner
+            .write()
+            .unwrap()
+            .retain(|k, _| upstreams.contains(&k));
+    }
+
+    pub(crate) fn snapshot(&self) -> Vec<(UpstreamAddr, u64)> {
+        self.inner
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(k, v)| (k.clone(), v.load(Ordering::Relaxed)))
+            .collect()
+    }
+}
+
+/// The count of times each next proxy has been selected, with its current weight
+#[derive(Default)]
+pub(crate) struct EscaperUpstreamSelectStats {
+    inner: RwLock<AHashMap<UpstreamAddr, (f64, AtomicU64)>>,
+}
+
+impl EscaperUpstreamSelectStats {
+    pub(crate) fn add_selected(&self, next_proxy: &WeightedUpstreamAddr) {
+        let weight = next_proxy.weight();
+        if let Some((old_weight, count)) = self.inner.read().unwrap().get(next_proxy.inner()) {
+            if *old_weight == weight {
+                count.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+        }
+        let mut map = self.inner.write().unwrap();
+        let (old_weight, count) = map
+            .entry(next_proxy.inner().clone())
+            .or_insert_with(|| (weight, AtomicU64::new(0)));
+        *old_weight = weight;
+        count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Drop the values for the upstreams that are no longer in use
+    pub(crate) fn retain<'a, I>(&self, upstreams: I)
+    where
+        I: Iterator<Item = &'a UpstreamAddr>,
+    {
+        let upstreams: Vec<&UpstreamAddr> = upstreams.collect();
+        self.inner
+            .write()
+            .unwrap()
+            .retain(|k, _| upstreams.contains(&k));
+    }
+
+    pub(crate) fn snapshot(&self) -> Vec<(UpstreamAddr, f64, u64)> {
+        self.inner
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(k, (weight, count))| (k.clone(), *weight, count.load(Ordering::Relaxed)))
+            .collect()
+    }
+}
+
+/// The product name and version of each next proxy, as reported in the CONNECT response
+#[derive(Default)]
+pub(crate) struct EscaperUpstreamAgentStats {
+    inner: RwLock<AHashMap<UpstreamAddr, (&'static str, Option<String>)>>,
+}
+
+impl EscaperUpstreamAgentStats {
+    pub(crate) fn set(&self, upstream: &UpstreamAddr, name: &'static str, version: Option<String>) {
+        if let Some((old_name, old_version)) = self.inner.read().unwrap().get(upstream) {
+            if *old_name == name && *old_version == version {
+                return;
+            }
+        }
+        self.inner
+            .write()
+            .unwrap()
+            .insert(upstream.clone(), (name, version));
+    }
+
+    /// Drop the values for the upstreams that are no longer in use
+    pub(crate) fn retain<'a, I>(&self, upstreams: I)
+    where
+        I: Iterator<Item = &'a UpstreamAddr>,
+    {
+        let upstreams: Vec<&UpstreamAddr> = upstreams.collect();
+        self.inner
+            .write()
+            .unwrap()
+            .retain(|k, _| upstreams.contains(&k));
+    }
+
+    pub(crate) fn snapshot(&self) -> Vec<(UpstreamAddr, &'static str, Option<String>)> {
+        self.inner
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(k, (name, version))| (k.clone(), *name, version.clone()))
+            .collect()
+    }
+}
+
 #[derive(Default)]
 pub(crate) struct EscaperInterfaceStats {
     tcp_connect_attempted: AtomicU64,
@@ -174,6 +615,15 @@ pub(crate) struct EscaperTcpConnectSnapshot {
     pub(crate) success: u64,
     pub(crate) error: u64,
     pub(crate) timeout: u64,
+    pub(crate) mptcp_establish: u64,
+    pub(crate) mptcp_fallback: u64,
+    pub(crate) happy_ipv4_establish: u64,
+    pub(crate) happy_ipv6_establish: u64,
+    pub(crate) preferred_addr_hit: u64,
+    pub(crate) preferred_addr_miss: u64,
+    pub(crate) negotiation_retry: u64,
+    pub(crate) unexpected_rsp_body: u64,
+    pub(crate) local_port_exhausted: u64,
 }
 
 #[derive(Default)]
@@ -183,6 +633,15 @@ pub(super) struct EscaperTcpConnectStats {
     success: AtomicU64,
     error: AtomicU64,
     timeout: AtomicU64,
+    mptcp_established: AtomicU64,
+    mptcp_fallback: AtomicU64,
+    happy_ipv4_established: AtomicU64,
+    happy_ipv6_established: AtomicU64,
+    preferred_addr_hit: AtomicU64,
+    preferred_addr_miss: AtomicU64,
+    negotiation_retry: AtomicU64,
+    unexpected_rsp_body: AtomicU64,
+    local_port_exhausted: AtomicU64,
 }
 
 impl EscaperTcpConnectStats {
@@ -206,6 +665,45 @@ impl EscaperTcpConnectStats {
         self.error.fetch_add(1, Ordering::Relaxed);
     }
 
+    #[cfg(target_os = "linux")]
+    pub(super) fn add_mptcp_established(&self) {
+        self.mptcp_established.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(super) fn add_mptcp_fallback(&self) {
+        self.mptcp_fallback.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// count the address family of the connection which won the happy eyeballs race
+    pub(super) fn add_happy_established(&self, peer: SocketAddr) {
+        match peer {
+            SocketAddr::V4(_) => self.happy_ipv4_established.fetch_add(1, Ordering::Relaxed),
+            SocketAddr::V6(_) => self.happy_ipv6_established.fetch_add(1, Ordering::Relaxed),
+        };
+    }
+
+    /// count whether the preferred address is the one established
+    pub(super) fn add_preferred_addr_result(&self, hit: bool) {
+        if hit {
+            self.preferred_addr_hit.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.preferred_addr_miss.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(super) fn add_negotiation_retry(&self) {
+        self.negotiation_retry.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn add_unexpected_rsp_body(&self) {
+        self.unexpected_rsp_body.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn add_local_port_exhausted(&self) {
+        self.local_port_exhausted.fetch_add(1, Ordering::Relaxed);
+    }
+
     fn snapshot(&self) -> EscaperTcpConnectSnapshot {
         EscaperTcpConnectSnapshot {
             attempt: self.attempted.load(Ordering::Relaxed),
@@ -213,6 +711,15 @@ impl EscaperTcpConnectStats {
             success: self.success.load(Ordering::Relaxed),
             error: self.error.load(Ordering::Relaxed),
             timeout: self.timeout.load(Ordering::Relaxed),
+            mptcp_establish: self.mptcp_established.load(Ordering::Relaxed),
+            mptcp_fallback: self.mptcp_fallback.load(Ordering::Relaxed),
+            happy_ipv4_establish: self.happy_ipv4_established.load(Ordering::Relaxed),
+            happy_ipv6_establish: self.happy_ipv6_established.load(Ordering::Relaxed),
+            preferred_addr_hit: self.preferred_addr_hit.load(Ordering::Relaxed),
+            preferred_addr_miss: self.preferred_addr_miss.load(Ordering::Relaxed),
+            negotiation_retry: self.negotiation_retry.load(Ordering::Relaxed),
+            unexpected_rsp_body: self.unexpected_rsp_body.load(Ordering::Relaxed),
+            local_port_exhausted: self.local_port_exhausted.load(Ordering::Relaxed),
         }
     }
 }
@@ -237,6 +744,59 @@ impl EscaperTcpStats {
     }
 }
 
+#[derive(Default)]
+pub(crate) struct EscaperConnectErrorSnapshot {
+    pub(crate) resolve_failed: u64,
+    pub(crate) connect_refused: u64,
+    pub(crate) connect_timeout: u64,
+    pub(crate) negotiation_timeout: u64,
+    pub(crate) tls_handshake_failed: u64,
+    pub(crate) tls_handshake_timeout: u64,
+}
+
+/// breakdown of the errors happened when setting up the remote connection,
+/// errors not in these categories are not counted in
+#[derive(Default)]
+pub(crate) struct EscaperConnectErrorStats {
+    resolve_failed: AtomicU64,
+    connect_refused: AtomicU64,
+    connect_timeout: AtomicU64,
+    negotiation_timeout: AtomicU64,
+    tls_handshake_failed: AtomicU64,
+    tls_handshake_timeout: AtomicU64,
+}
+
+impl EscaperConnectErrorStats {
+    pub(crate) fn add_error(&self, e: &TcpConnectError) {
+        let counter = match e {
+            TcpConnectError::ResolveFailed(_) => &self.resolve_failed,
+            TcpConnectError::ConnectFailed(ConnectError::ConnectionRefused) => {
+                &self.connect_refused
+            }
+            TcpConnectError::ConnectFailed(ConnectError::TimedOut)
+            | TcpConnectError::TimeoutByRule => &self.connect_timeout,
+            TcpConnectError::NegotiationPeerTimeout => &self.negotiation_timeout,
+            TcpConnectError::PeerTlsHandshakeFailed(_)
+            | TcpConnectError::UpstreamTlsHandshakeFailed(_) => &self.tls_handshake_failed,
+            TcpConnectError::PeerTlsHandshakeTimeout
+            | TcpConnectError::UpstreamTlsHandshakeTimeout => &self.tls_handshake_timeout,
+            _ => return,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> EscaperConnectErrorSnapshot {
+        EscaperConnectErrorSnapshot {
+            resolve_failed: self.resolve_failed.load(Ordering::Relaxed),
+            connect_refused: self.connect_refused.load(Ordering::Relaxed),
+            connect_timeout: self.connect_timeout.load(Ordering::Relaxed),
+            negotiation_timeout: self.negotiation_timeout.load(Ordering::Relaxed),
+            tls_handshake_failed: self.tls_handshake_failed.load(Ordering::Relaxed),
+            tls_handshake_timeout: self.tls_handshake_timeout.load(Ordering::Relaxed),
+        }
+    }
+}
+
 #[derive(Default)]
 pub(crate) struct EscaperUdpStats {
     pub(crate) io: UdpIoStats,
@@ -247,6 +807,8 @@ pub(crate) struct EscaperTlsSnapshot {
     pub(crate) handshake_success: u64,
     pub(crate) handshake_error: u64,
     pub(crate) handshake_timeout: u64,
+    pub(crate) early_data_accepted: u64,
+    pub(crate) early_data_rejected: u64,
 }
 
 #[derive(Default)]
@@ -254,6 +816,8 @@ pub(crate) struct EscaperTlsStats {
     handshake_success: AtomicU64,
     handshake_error: AtomicU64,
     handshake_timeout: AtomicU64,
+    early_data_accepted: AtomicU64,
+    early_data_rejected: AtomicU64,
 }
 
 impl EscaperTlsStats {
@@ -269,11 +833,21 @@ impl EscaperTlsStats {
         self.handshake_timeout.fetch_add(1, Ordering::Relaxed);
     }
 
+    pub(super) fn add_early_data_accepted(&self) {
+        self.early_data_accepted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn add_early_data_rejected(&self) {
+        self.early_data_rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
     pub(super) fn snapshot(&self) -> EscaperTlsSnapshot {
         EscaperTlsSnapshot {
             handshake_success: self.handshake_success.load(Ordering::Relaxed),
             handshake_error: self.handshake_error.load(Ordering::Relaxed),
             handshake_timeout: self.handshake_timeout.load(Ordering::Relaxed),
+            early_data_accepted: self.early_data_accepted.load(Ordering::Relaxed),
+            early_data_rejected: self.early_data_rejected.load(Ordering::Relaxed),
         }
     }
 }
@@ -327,3 +901,31 @@ impl RouteEscaperStats {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upstream_rtt() {
+        let stats = EscaperUpstreamRttStats::default();
+        let a = UpstreamAddr::from_ip_and_port("192.168.1.1".parse().unwrap(), 3128);
+        let b = UpstreamAddr::from_ip_and_port("192.168.1.2".parse().unwrap(), 3128);
+        let max_age = Duration::from_secs(60);
+        assert_eq!(stats.get_recent(&a, max_age), 0);
+
+        stats.add_sample(&a, Duration::from_millis(8));
+        assert_eq!(stats.get_recent(&a, max_age), 8000);
+        stats.add_sample(&a, Duration::from_millis(16));
+        assert_eq!(stats.get_recent(&a, max_age), 9000);
+        assert_eq!(stats.get_recent(&b, max_age), 0);
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(stats.get_recent(&a, Duration::from_millis(5)), 0);
+        assert_eq!(stats.snapshot(), vec![(a.clone(), 9000)]);
+
+        stats.add_sample(&b, Duration::from_millis(1));
+        stats.retain([b.clone()].iter());
+        assert_eq!(stats.snapshot(), vec![(b, 1000)]);
+    }
+}
@@ -0,0 +1,374 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use ahash::AHashMap;
+use anyhow::{anyhow, Context};
+use serde_json::Value;
+use yaml_rust::Yaml;
+
+use g3_types::net::{Host, OpensslCertificatePair};
+
+use crate::serve::ServerTaskNotes;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+enum TlsClientIdentityPickPolicy {
+    #[default]
+    RoundRobin,
+    UserHash,
+}
+
+impl FromStr for TlsClientIdentityPickPolicy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "round_robin" | "roundrobin" | "rr" => Ok(TlsClientIdentityPickPolicy::RoundRobin),
+            "user_hash" | "userhash" => Ok(TlsClientIdentityPickPolicy::UserHash),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The set of tls client identities that can be used to connect to a peer
+#[derive(Default)]
+pub(crate) struct TlsClientIdentities {
+    identities: Vec<(Arc<str>, OpensslCertificatePair)>,
+    /// if not set, no identity will be picked, and the tls client config will be used
+    pick_policy: Option<TlsClientIdentityPickPolicy>,
+    /// the name of the identity pinned to each tls server name
+    hosts: AHashMap<Host, Arc<str>>,
+    /// the name of the identity to use if the tls server name is not pinned
+    default: Option<Arc<str>>,
+    rr_index: AtomicUsize,
+}
+
+impl Clone for TlsClientIdentities {
+    fn clone(&self) -> Self {
+        TlsClientIdentities {
+            identities: self.identities.clone(),
+            pick_policy: self.pick_policy,
+            hosts: self.hosts.clone(),
+            default: self.default.clone(),
+            rr_index: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl PartialEq for TlsClientIdentities {
+    fn eq(&self, other: &Self) -> bool {
+        self.identities == other.identities
+            && self.pick_policy == other.pick_policy
+            && self.hosts == other.hosts
+            && self.default == other.default
+    }
+}
+
+impl TlsClientIdentities {
+    /// Create a set that will pick one identity for each task by the pick policy,
+    /// which defaults to round robin
+    pub(crate) fn with_pick() -> Self {
+        TlsClientIdentities {
+            pick_policy: Some(TlsClientIdentityPickPolicy::default()),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn set_identities_by_json(&mut self, v: &Value) -> anyhow::Result<()> {
+        let Value::Object(map) = v else {
+            return Err(anyhow!(
+                "json value type for tls client identities should be 'map'"
+            ));
+        };
+
+        let mut identities = Vec::with_capacity(map.len());
+        for (name, v) in map {
+            let pair = g3_json::value::as_openssl_certificate_pair(v)
+                .context(format!("invalid tls client identity value for {name}"))?;
+            identities.push((Arc::from(name.as_str()), pair));
+        }
+        self.identities = identities;
+        Ok(())
+    }
+
+    pub(crate) fn set_pick_policy_by_json(&mut self, v: &Value) -> anyhow::Result<()> {
+        let s = g3_json::value::as_string(v)?;
+        self.set_pick_policy(&s)
+    }
+
+    pub(crate) fn set_identities_by_yaml(
+        &mut self,
+        v: &Yaml,
+        lookup_dir: Option<&Path>,
+    ) -> anyhow::Result<()> {
+        let Yaml::Hash(map) = v else {
+            return Err(anyhow!(
+                "yaml value type for tls client identities should be 'map'"
+            ));
+        };
+
+        let mut identities = Vec::with_capacity(map.len());
+        g3_yaml::foreach_kv(map, |name, v| {
+            let pair = g3_yaml::value::as_openssl_certificate_pair(v, lookup_dir)
+                .context(format!("invalid tls client identity value for {name}"))?;
+            identities.push((Arc::from(name), pair));
+            Ok(())
+        })?;
+        self.identities = identities;
+        Ok(())
+    }
+
+    pub(crate) fn set_pick_policy_by_yaml(&mut self, v: &Yaml) -> anyhow::Result<()> {
+        let s = g3_yaml::value::as_string(v)?;
+        self.set_pick_policy(&s)
+    }
+
+    /// Pin the identities to some tls server names
+    pub(crate) fn set_hosts_by_yaml(&mut self, v: &Yaml) -> anyhow::Result<()> {
+        let Yaml::Hash(map) = v else {
+            return Err(anyhow!(
+                "yaml value type for tls client identity hosts should be 'map'"
+            ));
+        };
+
+        let mut hosts = AHashMap::with_capacity(map.len());
+        g3_yaml::foreach_kv(map, |host, v| {
+            let host = Host::from_str(host).context(format!("invalid host {host}"))?;
+            let name = g3_yaml::value::as_string(v)
+                .context(format!("invalid identity name value for host {host}"))?;
+            hosts.insert(host, Arc::from(name));
+            Ok(())
+        })?;
+        self.hosts = hosts;
+        Ok(())
+    }
+
+    pub(crate) fn set_default_by_yaml(&mut self, v: &Yaml) -> anyhow::Result<()> {
+        let name = g3_yaml::value::as_string(v)?;
+        self.default = Some(Arc::from(name));
+        Ok(())
+    }
+
+    pub(crate) fn check(&self) -> anyhow::Result<()> {
+        for (host, name) in &self.hosts {
+            if !self.identities.iter().any(|(n, _)| n == name) {
+                return Err(anyhow!(
+                    "no tls client identity {name} found for host {host}"
+                ));
+            }
+        }
+        if let Some(name) = &self.default {
+            if !self.identities.iter().any(|(n, _)| n == name) {
+                return Err(anyhow!("no default tls client identity {name} found"));
+            }
+        }
+        Ok(())
+    }
+
+    fn set_pick_policy(&mut self, s: &str) -> anyhow::Result<()> {
+        let policy = TlsClientIdentityPickPolicy::from_str(s)
+            .map_err(|_| anyhow!("invalid tls client identity pick policy {s}"))?;
+        self.pick_policy = Some(policy);
+        Ok(())
+    }
+
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.identities.is_empty()
+    }
+
+    /// Get the identity by name
+    pub(crate) fn get(&self, name: &str) -> Option<&(Arc<str>, OpensslCertificatePair)> {
+        self.identities.iter().find(|(n, _)| **n == *name)
+    }
+
+    /// Select the identity pinned to the tls server name, or the default identity,
+    /// or pick one by the pick policy. None will be returned if no one is selected,
+    /// and then the certificate in the tls client config should be used.
+    pub(crate) fn select(
+        &self,
+        tls_name: &Host,
+        task_notes: &ServerTaskNotes,
+    ) -> Option<&(Arc<str>, OpensslCertificatePair)> {
+        if let Some(name) = self.hosts.get(tls_name) {
+            return self.get(name);
+        }
+        if let Some(name) = &self.default {
+            return self.get(name);
+        }
+        let pick_policy = self.pick_policy?;
+        match self.identities.len() {
+            0 => None,
+            1 => self.identities.first(),
+            n => {
+                let i = match pick_policy {
+                    TlsClientIdentityPickPolicy::RoundRobin => {
+                        self.rr_index.fetch_add(1, Ordering::Relaxed) % n
+                    }
+                    TlsClientIdentityPickPolicy::UserHash => {
+                        let mut hasher = DefaultHasher::new();
+                        match task_notes.raw_user_name() {
+                            Some(user) => user.hash(&mut hasher),
+                            None => task_notes.client_ip().hash(&mut hasher),
+                        }
+                        (hasher.finish() % n as u64) as usize
+                    }
+                };
+                self.identities.get(i)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+    use std::time::Duration;
+
+    use g3_daemon::server::ClientConnectionInfo;
+
+    fn new_identities(names: &[&str]) -> TlsClientIdentities {
+        TlsClientIdentities {
+            identities: names
+                .iter()
+                .map(|name| (Arc::from(*name), OpensslCertificatePair::default()))
+                .collect(),
+            ..TlsClientIdentities::with_pick()
+        }
+    }
+
+    fn new_task_notes(ip: Ipv4Addr) -> ServerTaskNotes {
+        let client = SocketAddr::new(IpAddr::V4(ip), 12345);
+        let server = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 443);
+        ServerTaskNotes::new(
+            ClientConnectionInfo::new(client, server),
+            None,
+            Duration::ZERO,
+        )
+    }
+
+    fn selected(
+        identities: &TlsClientIdentities,
+        tls_name: &Host,
+        task_notes: &ServerTaskNotes,
+    ) -> Option<String> {
+        identities
+            .select(tls_name, task_notes)
+            .map(|(name, _)| name.to_string())
+    }
+
+    #[test]
+    fn select_round_robin() {
+        let identities = new_identities(&["a", "b", "c"]);
+        let task_notes = new_task_notes(Ipv4Addr::new(192, 0, 2, 1));
+        let host = Host::from_str("peer.example.net").unwrap();
+
+        let picked: Vec<_> = (0..4)
+            .map(|_| selected(&identities, &host, &task_notes).unwrap())
+            .collect();
+        assert_eq!(picked, ["a", "b", "c", "a"]);
+
+        let empty = TlsClientIdentities::default();
+        assert!(empty.select(&host, &task_notes).is_none());
+    }
+
+    #[test]
+    fn select_user_hash() {
+        let mut identities = new_identities(&["a", "b", "c"]);
+        identities.set_pick_policy("user_hash").unwrap();
+        let host = Host::from_str("peer.example.net").unwrap();
+
+        let task_notes = new_task_notes(Ipv4Addr::new(192, 0, 2, 1));
+        let first = selected(&identities, &host, &task_notes).unwrap();
+        for _ in 0..4 {
+            assert_eq!(selected(&identities, &host, &task_notes).unwrap(), first);
+        }
+    }
+
+    #[test]
+    fn select_pinned_host() {
+        let mut identities = new_identities(&["a", "b", "c"]);
+        let yaml = yaml_rust::YamlLoader::load_from_str("pinned.example.net: c").unwrap();
+        identities.set_hosts_by_yaml(&yaml[0]).unwrap();
+        identities.check().unwrap();
+        let task_notes = new_task_notes(Ipv4Addr::new(192, 0, 2, 1));
+
+        let pinned = Host::from_str("pinned.example.net").unwrap();
+        for _ in 0..4 {
+            assert_eq!(selected(&identities, &pinned, &task_notes).unwrap(), "c");
+        }
+        let other = Host::from_str("other.example.net").unwrap();
+        assert_eq!(selected(&identities, &other, &task_notes).unwrap(), "a");
+        assert_eq!(selected(&identities, &other, &task_notes).unwrap(), "b");
+    }
+
+    #[test]
+    fn select_default() {
+        let mut identities = new_identities(&["a", "b", "c"]);
+        identities.pick_policy = None;
+        let task_notes = new_task_notes(Ipv4Addr::new(192, 0, 2, 1));
+        let host = Host::from_str("peer.example.net").unwrap();
+
+        // fall back to the tls client config if no default and no pick policy
+        assert!(identities.select(&host, &task_notes).is_none());
+
+        let yaml = yaml_rust::YamlLoader::load_from_str("b").unwrap();
+        identities.set_default_by_yaml(&yaml[0]).unwrap();
+        identities.check().unwrap();
+        for _ in 0..4 {
+            assert_eq!(selected(&identities, &host, &task_notes).unwrap(), "b");
+        }
+
+        // the pinned one takes precedence
+        let yaml = yaml_rust::YamlLoader::load_from_str("pinned.example.net: c").unwrap();
+        identities.set_hosts_by_yaml(&yaml[0]).unwrap();
+        let pinned = Host::from_str("pinned.example.net").unwrap();
+        assert_eq!(selected(&identities, &pinned, &task_notes).unwrap(), "c");
+        assert_eq!(selected(&identities, &host, &task_notes).unwrap(), "b");
+
+        let yaml = yaml_rust::YamlLoader::load_from_str("d").unwrap();
+        identities.set_default_by_yaml(&yaml[0]).unwrap();
+        assert!(identities.check().is_err());
+    }
+
+    #[test]
+    fn get_by_name() {
+        let identities = new_identities(&["a", "b"]);
+        assert_eq!(identities.get("b").unwrap().0.as_ref(), "b");
+        assert!(identities.get("c").is_none());
+    }
+
+    #[test]
+    fn parse_err() {
+        let mut identities = new_identities(&["a"]);
+        let yaml = yaml_rust::YamlLoader::load_from_str("pinned.example.net: b").unwrap();
+        identities.set_hosts_by_yaml(&yaml[0]).unwrap();
+        assert!(identities.check().is_err());
+
+        let yaml = yaml_rust::YamlLoader::load_from_str("random").unwrap();
+        assert!(identities.set_pick_policy_by_yaml(&yaml[0]).is_err());
+
+        let yaml = yaml_rust::YamlLoader::load_from_str("[a, b]").unwrap();
+        assert!(identities.set_identities_by_yaml(&yaml[0], None).is_err());
+    }
+}
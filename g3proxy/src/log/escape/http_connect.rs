@@ -0,0 +1,60 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use slog::{slog_info, Logger};
+use uuid::Uuid;
+
+use g3_slog_types::{LtDateTime, LtDuration, LtIpAddr, LtUpstreamAddr, LtUuid};
+use g3_types::net::UpstreamAddr;
+
+use crate::module::tcp_connect::{TcpConnectError, TcpConnectTaskNotes};
+
+pub(crate) struct EscapeLogForHttpConnect<'a> {
+    pub(crate) upstream: &'a UpstreamAddr,
+    pub(crate) tcp_notes: &'a TcpConnectTaskNotes,
+    pub(crate) task_id: &'a Uuid,
+}
+
+impl EscapeLogForHttpConnect<'_> {
+    pub(crate) fn log(&self, logger: &Logger, e: &TcpConnectError) {
+        slog_info!(logger, "{}", e;
+            "escape_type" => "HttpConnect",
+            "task_id" => LtUuid(self.task_id),
+            "upstream" => LtUpstreamAddr(self.upstream),
+            "next_proxy" => self.tcp_notes.next_proxy_addr().map(LtUpstreamAddr),
+            "next_proxy_weight" => self.tcp_notes.next_proxy_weight(),
+            "next_bind_ip" => self.tcp_notes.bind.ip().map(LtIpAddr),
+            "next_bound_addr" => self.tcp_notes.local,
+            "next_peer_addr" => self.tcp_notes.next,
+            "next_expire" => self.tcp_notes.expire.as_ref().map(LtDateTime),
+            "socket_cookie" => self.tcp_notes.socket_cookie,
+            "negotiation_rtt" => self.tcp_notes.negotiation_rtt.map(LtDuration),
+            "reason" => e.brief(),
+        )
+    }
+
+    pub(crate) fn log_discarded_body(&self, logger: &Logger, rsp_code: u16) {
+        slog_info!(logger, "discarded unexpected body in {} response", rsp_code;
+            "escape_type" => "HttpConnect",
+            "task_id" => LtUuid(self.task_id),
+            "upstream" => LtUpstreamAddr(self.upstream),
+            "next_proxy" => self.tcp_notes.next_proxy_addr().map(LtUpstreamAddr),
+            "next_bound_addr" => self.tcp_notes.local,
+            "next_peer_addr" => self.tcp_notes.next,
+            "reason" => "DiscardedUnexpectedBody",
+        )
+    }
+}
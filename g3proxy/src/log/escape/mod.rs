@@ -18,6 +18,7 @@ use slog::{slog_o, Logger};
 
 use g3_types::metrics::NodeName;
 
+pub(crate) mod http_connect;
 pub(crate) mod tcp_connect;
 pub(crate) mod tls_handshake;
 pub(crate) mod udp_sendto;
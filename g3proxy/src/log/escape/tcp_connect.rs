@@ -34,10 +34,14 @@ impl EscapeLogForTcpConnect<'_> {
             "escape_type" => "TcpConnect",
             "task_id" => LtUuid(self.task_id),
             "upstream" => LtUpstreamAddr(self.upstream),
+            "next_proxy" => self.tcp_notes.next_proxy_addr().map(LtUpstreamAddr),
+            "next_proxy_weight" => self.tcp_notes.next_proxy_weight(),
             "next_bind_ip" => self.tcp_notes.bind.ip().map(LtIpAddr),
             "next_bound_addr" => self.tcp_notes.local,
             "next_peer_addr" => self.tcp_notes.next,
+            "next_isp_domain" => self.tcp_notes.next_isp_domain.as_deref(),
             "next_expire" => self.tcp_notes.expire.as_ref().map(LtDateTime),
+            "socket_cookie" => self.tcp_notes.socket_cookie,
             "tcp_connect_tries" => self.tcp_notes.tries,
             "tcp_connect_spend" => LtDuration(self.tcp_notes.duration),
             "reason" => e.brief(),
@@ -14,10 +14,13 @@
  * limitations under the License.
  */
 
+use std::time::Duration;
+
+use openssl::ssl::SslRef;
 use slog::{slog_info, Logger};
 use uuid::Uuid;
 
-use g3_slog_types::{LtDateTime, LtHost, LtIpAddr, LtUpstreamAddr, LtUuid};
+use g3_slog_types::{LtDateTime, LtDuration, LtHost, LtIpAddr, LtUpstreamAddr, LtUuid};
 use g3_types::net::{Host, UpstreamAddr};
 
 use crate::module::tcp_connect::TcpConnectTaskNotes;
@@ -29,6 +32,7 @@ pub(crate) struct EscapeLogForTlsHandshake<'a> {
     pub(crate) tls_name: &'a Host,
     pub(crate) tls_peer: &'a UpstreamAddr,
     pub(crate) tls_application: TlsApplication,
+    pub(crate) tls_handshake_duration: Duration,
 }
 
 pub(crate) enum TlsApplication {
@@ -53,13 +57,40 @@ impl EscapeLogForTlsHandshake<'_> {
             "escape_type" => "TlsHandshake",
             "task_id" => LtUuid(self.task_id),
             "upstream" => LtUpstreamAddr(self.upstream),
+            "next_proxy" => self.tcp_notes.next_proxy_addr().map(LtUpstreamAddr),
+            "next_proxy_weight" => self.tcp_notes.next_proxy_weight(),
+            "next_bind_ip" => self.tcp_notes.bind.ip().map(LtIpAddr),
+            "next_bound_addr" => self.tcp_notes.local,
+            "next_peer_addr" => self.tcp_notes.next,
+            "next_expire" => self.tcp_notes.expire.as_ref().map(LtDateTime),
+            "socket_cookie" => self.tcp_notes.socket_cookie,
+            "tls_name" => LtHost(self.tls_name),
+            "tls_peer" => LtUpstreamAddr(self.tls_peer),
+            "tls_application" => self.tls_application.as_str(),
+            "tls_handshake_duration" => LtDuration(self.tls_handshake_duration),
+            "tls_client_identity" => self.tcp_notes.tls_client_identity.as_deref(),
+        )
+    }
+
+    pub(crate) fn log_success(&self, logger: &Logger, ssl: &SslRef) {
+        slog_info!(logger, "ok";
+            "escape_type" => "TlsHandshake",
+            "task_id" => LtUuid(self.task_id),
+            "upstream" => LtUpstreamAddr(self.upstream),
+            "next_proxy" => self.tcp_notes.next_proxy_addr().map(LtUpstreamAddr),
+            "next_proxy_weight" => self.tcp_notes.next_proxy_weight(),
             "next_bind_ip" => self.tcp_notes.bind.ip().map(LtIpAddr),
             "next_bound_addr" => self.tcp_notes.local,
             "next_peer_addr" => self.tcp_notes.next,
             "next_expire" => self.tcp_notes.expire.as_ref().map(LtDateTime),
+            "socket_cookie" => self.tcp_notes.socket_cookie,
             "tls_name" => LtHost(self.tls_name),
             "tls_peer" => LtUpstreamAddr(self.tls_peer),
             "tls_application" => self.tls_application.as_str(),
+            "tls_handshake_duration" => LtDuration(self.tls_handshake_duration),
+            "tls_client_identity" => self.tcp_notes.tls_client_identity.as_deref(),
+            "tls_version" => ssl.version_str(),
+            "tls_cipher" => ssl.current_cipher().map(|c| c.name()),
         )
     }
 }
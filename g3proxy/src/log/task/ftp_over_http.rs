@@ -53,6 +53,8 @@ impl TaskLogForFtpOverHttp<'_> {
             "user" => self.task_notes.raw_user_name(),
             "server_addr" => self.task_notes.server_addr(),
             "client_addr" => self.task_notes.client_addr(),
+            "tls_client_ja3" => self.task_notes.tls_client_ja3(),
+            "tls_client_ja4" => self.task_notes.tls_client_ja4(),
             "upstream" => LtUpstreamAddr(self.ftp_notes.upstream()),
             "method" => LtHttpMethod(&self.ftp_notes.method),
             "uri" => LtHttpUri::new(&self.ftp_notes.uri, self.ftp_notes.uri_log_max_chars),
@@ -77,6 +79,8 @@ impl TaskLogForFtpOverHttp<'_> {
             "user" => self.task_notes.raw_user_name(),
             "server_addr" => self.task_notes.server_addr(),
             "client_addr" => self.task_notes.client_addr(),
+            "tls_client_ja3" => self.task_notes.tls_client_ja3(),
+            "tls_client_ja4" => self.task_notes.tls_client_ja4(),
             "upstream" => LtUpstreamAddr(self.ftp_notes.upstream()),
             "escaper" => self.ftp_notes.control_tcp_notes.escaper.as_str(),
             "next_bind_ip" => self.ftp_notes.control_tcp_notes.bind.ip().map(LtIpAddr),
@@ -112,6 +116,8 @@ impl TaskLogForFtpOverHttp<'_> {
             "user" => self.task_notes.raw_user_name(),
             "server_addr" => self.task_notes.server_addr(),
             "client_addr" => self.task_notes.client_addr(),
+            "tls_client_ja3" => self.task_notes.tls_client_ja3(),
+            "tls_client_ja4" => self.task_notes.tls_client_ja4(),
             "upstream" => LtUpstreamAddr(self.ftp_notes.upstream()),
             "escaper" => self.ftp_notes.control_tcp_notes.escaper.as_str(),
             "next_bind_ip" => self.ftp_notes.control_tcp_notes.bind.ip().map(LtIpAddr),
@@ -156,6 +162,8 @@ impl TaskLogForFtpOverHttp<'_> {
             "user" => self.task_notes.raw_user_name(),
             "server_addr" => self.task_notes.server_addr(),
             "client_addr" => self.task_notes.client_addr(),
+            "tls_client_ja3" => self.task_notes.tls_client_ja3(),
+            "tls_client_ja4" => self.task_notes.tls_client_ja4(),
             "upstream" => LtUpstreamAddr(self.ftp_notes.upstream()),
             "escaper" => self.ftp_notes.control_tcp_notes.escaper.as_str(),
             "next_bind_ip" => self.ftp_notes.control_tcp_notes.bind.ip().map(LtIpAddr),
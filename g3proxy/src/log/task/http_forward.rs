@@ -55,6 +55,8 @@ impl TaskLogForHttpForward<'_> {
             "user" => self.task_notes.raw_user_name(),
             "server_addr" => self.task_notes.server_addr(),
             "client_addr" => self.task_notes.client_addr(),
+            "tls_client_ja3" => self.task_notes.tls_client_ja3(),
+            "tls_client_ja4" => self.task_notes.tls_client_ja4(),
             "upstream" => LtUpstreamAddr(self.upstream),
             "pipeline_wait" => LtDuration(self.http_notes.pipeline_wait),
             "method" => LtHttpMethod(&self.http_notes.method),
@@ -80,6 +82,8 @@ impl TaskLogForHttpForward<'_> {
             "user" => self.task_notes.raw_user_name(),
             "server_addr" => self.task_notes.server_addr(),
             "client_addr" => self.task_notes.client_addr(),
+            "tls_client_ja3" => self.task_notes.tls_client_ja3(),
+            "tls_client_ja4" => self.task_notes.tls_client_ja4(),
             "upstream" => LtUpstreamAddr(self.upstream),
             "escaper" => self.tcp_notes.escaper.as_str(),
             "next_bind_ip" => self.tcp_notes.bind.ip().map(LtIpAddr),
@@ -114,6 +118,8 @@ impl TaskLogForHttpForward<'_> {
             "user" => self.task_notes.raw_user_name(),
             "server_addr" => self.task_notes.server_addr(),
             "client_addr" => self.task_notes.client_addr(),
+            "tls_client_ja3" => self.task_notes.tls_client_ja3(),
+            "tls_client_ja4" => self.task_notes.tls_client_ja4(),
             "upstream" => LtUpstreamAddr(self.upstream),
             "escaper" => self.tcp_notes.escaper.as_str(),
             "next_bind_ip" => self.tcp_notes.bind.ip().map(LtIpAddr),
@@ -159,6 +165,8 @@ impl TaskLogForHttpForward<'_> {
             "user" => self.task_notes.raw_user_name(),
             "server_addr" => self.task_notes.server_addr(),
             "client_addr" => self.task_notes.client_addr(),
+            "tls_client_ja3" => self.task_notes.tls_client_ja3(),
+            "tls_client_ja4" => self.task_notes.tls_client_ja4(),
             "upstream" => LtUpstreamAddr(self.upstream),
             "escaper" => self.tcp_notes.escaper.as_str(),
             "next_bind_ip" => self.tcp_notes.bind.ip().map(LtIpAddr),
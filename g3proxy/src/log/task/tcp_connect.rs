@@ -50,6 +50,8 @@ impl TaskLogForTcpConnect<'_> {
             "user" => self.task_notes.raw_user_name(),
             "server_addr" => self.task_notes.server_addr(),
             "client_addr" => self.task_notes.client_addr(),
+            "tls_client_ja3" => self.task_notes.tls_client_ja3(),
+            "tls_client_ja4" => self.task_notes.tls_client_ja4(),
             "upstream" => LtUpstreamAddr(self.upstream),
             "wait_time" => LtDuration(self.task_notes.wait_time),
         )
@@ -71,8 +73,12 @@ impl TaskLogForTcpConnect<'_> {
             "user" => self.task_notes.raw_user_name(),
             "server_addr" => self.task_notes.server_addr(),
             "client_addr" => self.task_notes.client_addr(),
+            "tls_client_ja3" => self.task_notes.tls_client_ja3(),
+            "tls_client_ja4" => self.task_notes.tls_client_ja4(),
             "upstream" => LtUpstreamAddr(self.upstream),
             "escaper" => self.tcp_notes.escaper.as_str(),
+            "next_proxy" => self.tcp_notes.next_proxy_addr().map(LtUpstreamAddr),
+            "next_proxy_weight" => self.tcp_notes.next_proxy_weight(),
             "next_bind_ip" => self.tcp_notes.bind.ip().map(LtIpAddr),
             "next_bound_addr" => self.tcp_notes.local,
             "next_peer_addr" => self.tcp_notes.next,
@@ -100,8 +106,12 @@ impl TaskLogForTcpConnect<'_> {
             "user" => self.task_notes.raw_user_name(),
             "server_addr" => self.task_notes.server_addr(),
             "client_addr" => self.task_notes.client_addr(),
+            "tls_client_ja3" => self.task_notes.tls_client_ja3(),
+            "tls_client_ja4" => self.task_notes.tls_client_ja4(),
             "upstream" => LtUpstreamAddr(self.upstream),
             "escaper" => self.tcp_notes.escaper.as_str(),
+            "next_proxy" => self.tcp_notes.next_proxy_addr().map(LtUpstreamAddr),
+            "next_proxy_weight" => self.tcp_notes.next_proxy_weight(),
             "next_bind_ip" => self.tcp_notes.bind.ip().map(LtIpAddr),
             "next_bound_addr" => self.tcp_notes.local,
             "next_peer_addr" => self.tcp_notes.next,
@@ -134,8 +144,12 @@ impl TaskLogForTcpConnect<'_> {
             "user" => self.task_notes.raw_user_name(),
             "server_addr" => self.task_notes.server_addr(),
             "client_addr" => self.task_notes.client_addr(),
+            "tls_client_ja3" => self.task_notes.tls_client_ja3(),
+            "tls_client_ja4" => self.task_notes.tls_client_ja4(),
             "upstream" => LtUpstreamAddr(self.upstream),
             "escaper" => self.tcp_notes.escaper.as_str(),
+            "next_proxy" => self.tcp_notes.next_proxy_addr().map(LtUpstreamAddr),
+            "next_proxy_weight" => self.tcp_notes.next_proxy_weight(),
             "next_bind_ip" => self.tcp_notes.bind.ip().map(LtIpAddr),
             "next_bound_addr" => self.tcp_notes.local,
             "next_peer_addr" => self.tcp_notes.next,
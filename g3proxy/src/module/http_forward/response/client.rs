@@ -292,12 +292,26 @@ impl HttpProxyClientResponse {
             | TcpConnectError::NegotiationRejected(_) => {
                 HttpProxyClientResponse::from_standard(StatusCode::BAD_GATEWAY, version, true)
             }
-            TcpConnectError::NegotiationPeerTimeout => {
+            TcpConnectError::NegotiationPeerTimeout | TcpConnectError::NegotiationQueueTimeout => {
                 HttpProxyClientResponse::from_standard(StatusCode::GATEWAY_TIMEOUT, version, close)
             }
-            TcpConnectError::NegotiationProtocolErr => {
+            TcpConnectError::NegotiationProtocolErr
+            | TcpConnectError::NegotiationUnexpectedBody => {
                 HttpProxyClientResponse::from_standard(StatusCode::BAD_GATEWAY, version, true)
             }
+            TcpConnectError::PeerTunnelLimitReached => HttpProxyClientResponse::from_standard(
+                StatusCode::SERVICE_UNAVAILABLE,
+                version,
+                close,
+            ),
+            TcpConnectError::TaskDeadlineExceeded => {
+                HttpProxyClientResponse::from_standard(StatusCode::GATEWAY_TIMEOUT, version, close)
+            }
+            TcpConnectError::CanceledAsServerQuit => HttpProxyClientResponse::from_standard(
+                StatusCode::SERVICE_UNAVAILABLE,
+                version,
+                true,
+            ),
             TcpConnectError::InternalServerError(_)
             | TcpConnectError::InternalTlsClientError(_) => HttpProxyClientResponse::from_standard(
                 StatusCode::INTERNAL_SERVER_ERROR,
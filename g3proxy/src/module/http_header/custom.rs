@@ -17,6 +17,7 @@
 use std::cell::RefCell;
 use std::io::Write;
 use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
 
 use base64::prelude::*;
 use chrono::{DateTime, Utc};
@@ -96,6 +97,30 @@ pub(crate) fn set_remote_connection_info(
     })
 }
 
+/// Get the local and remote address set by the remote g3proxy in the connect response.
+/// The first header will be used, as it should be set by the farthest proxy.
+pub(crate) fn parse_remote_connection_info(
+    headers: &HttpHeaderMap,
+) -> (Option<SocketAddr>, Option<SocketAddr>) {
+    let Some(value) = headers.get(HeaderName::from_static(REMOTE_CONNECTION_INFO)) else {
+        return (None, None);
+    };
+
+    let mut local = None;
+    let mut remote = None;
+    for part in value.to_str().split(';').skip(1) {
+        let Some((k, v)) = part.trim().split_once('=') else {
+            continue;
+        };
+        match k {
+            "local" => local = SocketAddr::from_str(v).ok(),
+            "remote" => remote = SocketAddr::from_str(v).ok(),
+            _ => {}
+        }
+    }
+    (local, remote)
+}
+
 fn set_value_for_dynamic_egress_info(
     v: &mut Vec<u8>,
     server_id: &HttpServerId,
@@ -167,3 +192,31 @@ pub(crate) fn set_outgoing_ip(headers: &mut HttpHeaderMap, addr: SocketAddr) {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_remote_connection() {
+        let mut headers = HttpHeaderMap::default();
+        assert_eq!(parse_remote_connection_info(&headers), (None, None));
+
+        let server_id = HttpServerId::from_str("exit-1").unwrap();
+        let local = SocketAddr::from_str("192.168.1.2:34567").unwrap();
+        let remote = SocketAddr::from_str("[2001:db8::1]:443").unwrap();
+        set_remote_connection_info(
+            &mut headers,
+            &server_id,
+            None,
+            Some(local),
+            Some(remote),
+            &None,
+        );
+        set_remote_connection_info(&mut headers, &server_id, None, None, None, &None);
+        assert_eq!(
+            parse_remote_connection_info(&headers),
+            (Some(local), Some(remote))
+        );
+    }
+}
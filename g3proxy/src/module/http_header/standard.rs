@@ -15,6 +15,25 @@
  */
 
 use base64::prelude::*;
+use http::{header, HeaderName};
+
+use g3_types::net::HttpHeaderMap;
+
+/// The proxy products that will be reported as is, all others will be bucketed as 'other'
+const KNOWN_PROXY_AGENTS: &[&str] = &[
+    "apache",
+    "ats",
+    "caddy",
+    "envoy",
+    "g3proxy",
+    "haproxy",
+    "mitmproxy",
+    "nginx",
+    "privoxy",
+    "squid",
+    "tinyproxy",
+    "varnish",
+];
 
 pub(crate) fn proxy_authorization_basic_pass(userid: &str) -> String {
     format!(
@@ -22,3 +41,100 @@ pub(crate) fn proxy_authorization_basic_pass(userid: &str) -> String {
         BASE64_STANDARD.encode(format!("{userid}:{}", crate::build::PKG_NAME))
     )
 }
+
+/// Get the product name and major.minor version of the next proxy from the CONNECT response.
+///
+/// The Proxy-Agent header is preferred, and the Server header will be used if not present.
+/// Only known products will be returned by name to keep the cardinality low.
+pub(crate) fn proxy_agent(headers: &HttpHeaderMap) -> (&'static str, Option<String>) {
+    let Some(value) = headers
+        .get(HeaderName::from_static("proxy-agent"))
+        .or_else(|| headers.get(header::SERVER))
+    else {
+        return ("none", None);
+    };
+
+    let product = value.to_str().split_whitespace().next().unwrap_or_default();
+    let (name, version) = product.split_once('/').unwrap_or((product, ""));
+    let Some(name) = KNOWN_PROXY_AGENTS
+        .iter()
+        .find(|known| known.eq_ignore_ascii_case(name))
+        .copied()
+    else {
+        return ("other", None);
+    };
+
+    let mut parts = Vec::with_capacity(2);
+    for part in version.split('.').take(2) {
+        let len = part.bytes().take_while(|c| c.is_ascii_digit()).count();
+        if len == 0 {
+            break;
+        }
+        parts.push(&part[..len]);
+    }
+    if parts.is_empty() {
+        (name, None)
+    } else {
+        (name, Some(parts.join(".")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use g3_types::net::HttpHeaderValue;
+
+    fn agent_of(name: &'static str, value: &'static str) -> (&'static str, Option<String>) {
+        let mut headers = HttpHeaderMap::default();
+        headers.insert(
+            HeaderName::from_static(name),
+            HttpHeaderValue::from_static(value),
+        );
+        proxy_agent(&headers)
+    }
+
+    #[test]
+    fn proxy_agent_none() {
+        let headers = HttpHeaderMap::default();
+        assert_eq!(proxy_agent(&headers), ("none", None));
+    }
+
+    #[test]
+    fn proxy_agent_known() {
+        assert_eq!(
+            agent_of("proxy-agent", "squid/6.10"),
+            ("squid", Some("6.10".to_string()))
+        );
+        assert_eq!(
+            agent_of("proxy-agent", "Squid/5.9.1 (Debian)"),
+            ("squid", Some("5.9".to_string()))
+        );
+        assert_eq!(
+            agent_of("server", "nginx/1.25.3"),
+            ("nginx", Some("1.25".to_string()))
+        );
+        assert_eq!(
+            agent_of("proxy-agent", "g3proxy/1.11.3-rc1"),
+            ("g3proxy", Some("1.11".to_string()))
+        );
+        assert_eq!(agent_of("proxy-agent", "envoy/v1.30"), ("envoy", None));
+        assert_eq!(agent_of("server", "HAProxy"), ("haproxy", None));
+    }
+
+    #[test]
+    fn proxy_agent_other() {
+        assert_eq!(agent_of("proxy-agent", "my-proxy/1.0"), ("other", None));
+        assert_eq!(agent_of("server", ""), ("other", None));
+    }
+
+    #[test]
+    fn proxy_agent_prefer_proxy_agent() {
+        let mut headers = HttpHeaderMap::default();
+        headers.insert(header::SERVER, HttpHeaderValue::from_static("nginx/1.25.3"));
+        headers.insert(
+            HeaderName::from_static("proxy-agent"),
+            HttpHeaderValue::from_static("squid/6.10"),
+        );
+        assert_eq!(proxy_agent(&headers), ("squid", Some("6.10".to_string())));
+    }
+}
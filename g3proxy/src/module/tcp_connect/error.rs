@@ -58,8 +58,18 @@ pub(crate) enum TcpConnectError {
     NegotiationRejected(String),
     #[error("negotiation timeout")]
     NegotiationPeerTimeout,
+    #[error("negotiation queue timeout")]
+    NegotiationQueueTimeout,
     #[error("negotiation protocol error")]
     NegotiationProtocolErr,
+    #[error("negotiation response has unexpected body")]
+    NegotiationUnexpectedBody,
+    #[error("peer tunnel limit reached")]
+    PeerTunnelLimitReached,
+    #[error("task deadline exceeded")]
+    TaskDeadlineExceeded,
+    #[error("canceled as server quit")]
+    CanceledAsServerQuit,
     #[error("internal server error: {0}")]
     InternalServerError(&'static str),
     #[error("internal tls client error: {0:?}")]
@@ -92,7 +102,12 @@ impl TcpConnectError {
             TcpConnectError::NegotiationWriteFailed(_) => "NegotiationWriteFailed",
             TcpConnectError::NegotiationRejected(_) => "NegotiationRejected",
             TcpConnectError::NegotiationPeerTimeout => "NegotiationPeerTimeout",
+            TcpConnectError::NegotiationQueueTimeout => "NegotiationQueueTimeout",
             TcpConnectError::NegotiationProtocolErr => "NegotiationProtocolErr",
+            TcpConnectError::NegotiationUnexpectedBody => "NegotiationUnexpectedBody",
+            TcpConnectError::PeerTunnelLimitReached => "PeerTunnelLimitReached",
+            TcpConnectError::TaskDeadlineExceeded => "TaskDeadlineExceeded",
+            TcpConnectError::CanceledAsServerQuit => "CanceledAsServerQuit",
             TcpConnectError::InternalServerError(_) => "InternalServerError",
             TcpConnectError::InternalTlsClientError(_) => "InternalTlsClientError",
             TcpConnectError::PeerTlsHandshakeTimeout => "PeerTlsHandshakeTimeout",
@@ -132,9 +147,20 @@ impl From<TcpConnectError> for ServerTaskError {
             TcpConnectError::NegotiationPeerTimeout => {
                 ServerTaskError::UpstreamAppTimeout("negotiation peer timeout")
             }
+            TcpConnectError::NegotiationQueueTimeout => {
+                ServerTaskError::UpstreamAppTimeout("negotiation queue timeout")
+            }
             TcpConnectError::NegotiationProtocolErr => {
                 ServerTaskError::InvalidUpstreamProtocol("protocol negotiation with remote failed")
             }
+            TcpConnectError::NegotiationUnexpectedBody => {
+                ServerTaskError::InvalidUpstreamProtocol("unexpected body in negotiation response")
+            }
+            TcpConnectError::PeerTunnelLimitReached => ServerTaskError::UpstreamNotAvailable,
+            TcpConnectError::TaskDeadlineExceeded => {
+                ServerTaskError::UpstreamAppTimeout("task deadline exceeded")
+            }
+            TcpConnectError::CanceledAsServerQuit => ServerTaskError::CanceledAsServerQuit,
             TcpConnectError::InternalServerError(s) => ServerTaskError::InternalServerError(s),
             TcpConnectError::InternalTlsClientError(e) => {
                 ServerTaskError::InternalTlsClientError(e)
@@ -196,12 +222,18 @@ impl From<&TcpConnectError> for Socks5Reply {
             TcpConnectError::EscaperNotUsable(_)
             | TcpConnectError::SetupSocketFailed(_)
             | TcpConnectError::ProxyProtocolEncodeError(_)
-            | TcpConnectError::NegotiationProtocolErr => Socks5Reply::GeneralServerFailure,
+            | TcpConnectError::NegotiationProtocolErr
+            | TcpConnectError::NegotiationUnexpectedBody => Socks5Reply::GeneralServerFailure,
             TcpConnectError::ProxyProtocolWriteFailed(_)
             | TcpConnectError::NegotiationReadFailed(_)
             | TcpConnectError::NegotiationWriteFailed(_) => Socks5Reply::GeneralServerFailure,
             TcpConnectError::NegotiationRejected(_) => Socks5Reply::ConnectionRefused,
-            TcpConnectError::NegotiationPeerTimeout => Socks5Reply::ConnectionTimedOut,
+            TcpConnectError::NegotiationPeerTimeout | TcpConnectError::NegotiationQueueTimeout => {
+                Socks5Reply::ConnectionTimedOut
+            }
+            TcpConnectError::PeerTunnelLimitReached => Socks5Reply::GeneralServerFailure,
+            TcpConnectError::TaskDeadlineExceeded => Socks5Reply::ConnectionTimedOut,
+            TcpConnectError::CanceledAsServerQuit => Socks5Reply::GeneralServerFailure,
             TcpConnectError::InternalServerError(_)
             | TcpConnectError::InternalTlsClientError(_) => Socks5Reply::GeneralServerFailure,
             TcpConnectError::PeerTlsHandshakeTimeout
@@ -21,6 +21,11 @@ use g3_io_ext::{LimitedReaderStats, LimitedWriterStats};
 
 use crate::auth::UserUpstreamTrafficStats;
 
+/// Remote side io stats for tcp connect tasks.
+///
+/// Every read or write is forwarded to the escaper, task and user stats at once,
+/// so the metrics emitter will see the traffic of long-lived tunnels while they
+/// are still alive, not just when they are closed.
 #[derive(Clone)]
 pub(crate) struct TcpConnectRemoteWrapperStats<T> {
     escaper: Arc<T>,
@@ -65,3 +70,42 @@ impl<T: TcpConnectionTaskRemoteStats> LimitedWriterStats for TcpConnectRemoteWra
             .for_each(|stats| stats.add_write_bytes(size));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[derive(Default)]
+    struct MockRemoteStats {
+        read: AtomicU64,
+        write: AtomicU64,
+    }
+
+    impl TcpConnectionTaskRemoteStats for MockRemoteStats {
+        fn add_read_bytes(&self, size: u64) {
+            self.read.fetch_add(size, Ordering::Relaxed);
+        }
+
+        fn add_write_bytes(&self, size: u64) {
+            self.write.fetch_add(size, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn live_update() {
+        let escaper = Arc::new(MockRemoteStats::default());
+        let task = Arc::new(MockRemoteStats::default());
+        let wrapper = TcpConnectRemoteWrapperStats::new(&escaper, task.clone());
+
+        LimitedReaderStats::add_read_bytes(&wrapper, 10);
+        assert_eq!(escaper.read.load(Ordering::Relaxed), 10);
+        assert_eq!(task.read.load(Ordering::Relaxed), 10);
+
+        LimitedReaderStats::add_read_bytes(&wrapper, 5);
+        LimitedWriterStats::add_write_bytes(&wrapper, 7);
+        assert_eq!(escaper.read.load(Ordering::Relaxed), 15);
+        assert_eq!(escaper.write.load(Ordering::Relaxed), 7);
+        assert_eq!(task.write.load(Ordering::Relaxed), 7);
+    }
+}
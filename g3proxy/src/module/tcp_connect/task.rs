@@ -14,7 +14,9 @@
  * limitations under the License.
  */
 
+use std::io;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
 
 use chrono::{DateTime, Utc};
@@ -22,7 +24,7 @@ use openssl::ssl::Ssl;
 
 use g3_socket::BindAddr;
 use g3_types::metrics::NodeName;
-use g3_types::net::{EgressInfo, Host, OpensslClientConfig, UpstreamAddr};
+use g3_types::net::{EgressInfo, Host, OpensslClientConfig, UpstreamAddr, WeightedUpstreamAddr};
 
 use super::TcpConnectError;
 
@@ -46,6 +48,10 @@ impl TlsConnectTaskConf<'_> {
     pub(crate) fn handshake_timeout(&self) -> Duration {
         self.tls_config.handshake_timeout
     }
+
+    pub(crate) fn handshake_error(&self, e: io::Error) -> anyhow::Error {
+        self.tls_config.handshake_error(e)
+    }
 }
 
 /// This contains the final chained info about the client request
@@ -65,6 +71,8 @@ impl TcpConnectChainedNotes {
 #[derive(Debug, Default, Clone)]
 pub(crate) struct TcpConnectTaskNotes {
     pub(crate) escaper: NodeName,
+    /// the next proxy selected by the escaper, with its weight
+    pub(crate) next_proxy: Option<WeightedUpstreamAddr>,
     pub(crate) bind: BindAddr,
     pub(crate) next: Option<SocketAddr>,
     pub(crate) tries: usize,
@@ -73,11 +81,28 @@ pub(crate) struct TcpConnectTaskNotes {
     pub(crate) egress: Option<EgressInfo>,
     pub(crate) chained: TcpConnectChainedNotes,
     pub(crate) duration: Duration,
+    /// the round trip time of the CONNECT negotiation with the next proxy
+    pub(crate) negotiation_rtt: Option<Duration>,
+    /// the socket cookie of the connection to the next proxy, only available on linux
+    pub(crate) socket_cookie: Option<u64>,
+    /// the tls client identity used to connect to the next proxy
+    pub(crate) tls_client_identity: Option<Arc<str>>,
+    /// the isp domain of the next peer address, if fetched from the ip locate service
+    pub(crate) next_isp_domain: Option<Arc<str>>,
 }
 
 impl TcpConnectTaskNotes {
+    pub(crate) fn next_proxy_addr(&self) -> Option<&UpstreamAddr> {
+        self.next_proxy.as_ref().map(|p| p.inner())
+    }
+
+    pub(crate) fn next_proxy_weight(&self) -> Option<f64> {
+        self.next_proxy.as_ref().map(|p| p.weight())
+    }
+
     pub(crate) fn reset(&mut self) {
         self.escaper.clear();
+        self.next_proxy = None;
         self.bind = BindAddr::None;
         self.next = None;
         self.tries = 0;
@@ -86,5 +111,9 @@ impl TcpConnectTaskNotes {
         self.egress = None;
         self.chained.reset();
         self.duration = Duration::ZERO;
+        self.negotiation_rtt = None;
+        self.socket_cookie = None;
+        self.tls_client_identity = None;
+        self.next_isp_domain = None;
     }
 }
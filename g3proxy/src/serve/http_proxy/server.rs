@@ -28,7 +28,7 @@ use slog::Logger;
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpStream;
 use tokio::sync::{broadcast, mpsc};
-use tokio_rustls::{server::TlsStream, TlsAcceptor};
+use tokio_rustls::server::TlsStream;
 
 use g3_daemon::listen::{AcceptQuicServer, AcceptTcpServer, ListenStats, ListenTcpRuntime};
 use g3_daemon::server::{BaseServer, ClientConnectionInfo, ServerReloadCommand};
@@ -52,7 +52,8 @@ use crate::config::server::http_proxy::HttpProxyServerConfig;
 use crate::config::server::{AnyServerConfig, ServerConfig};
 use crate::escape::ArcEscaper;
 use crate::serve::{
-    ArcServer, ArcServerStats, Server, ServerInternal, ServerQuitPolicy, ServerStats, WrapArcServer,
+    ArcServer, ArcServerStats, RustlsAcceptor, Server, ServerInternal, ServerQuitPolicy,
+    ServerStats, WrapArcServer,
 };
 
 pub(crate) struct HttpProxyServer {
@@ -60,7 +61,7 @@ pub(crate) struct HttpProxyServer {
     server_stats: Arc<HttpProxyServerStats>,
     listen_stats: Arc<ListenStats>,
     tls_rolling_ticketer: Option<Arc<RollingTicketer<OpensslTicketKey>>>,
-    tls_acceptor: Option<TlsAcceptor>,
+    tls_acceptor: Option<RustlsAcceptor>,
     tls_accept_timeout: Duration,
     tls_client_config: Arc<OpensslClientConfig>,
     ingress_net_filter: Option<AclNetworkRule>,
@@ -94,7 +95,7 @@ impl HttpProxyServer {
                 )
                 .context("failed to build tls server config")?;
             tls_accept_timeout = tls_server_config.accept_timeout;
-            Some(TlsAcceptor::from(tls_server_config.driver))
+            Some(RustlsAcceptor::from(tls_server_config))
         } else {
             None
         };
@@ -118,6 +119,8 @@ impl HttpProxyServer {
 
         // always update extra metrics tags
         server_stats.set_extra_tags(config.extra_metrics_tags.clone());
+        server_stats.set_dead_mans_switch(config.dead_mans_switch);
+        server_stats.set_user_metrics_top_k(config.user_metrics_top_k);
 
         let escaper = Arc::new(crate::escape::get_or_insert_default(config.escaper()));
         let user_group = config.get_user_group();
@@ -334,6 +337,7 @@ impl ServerInternal for HttpProxyServer {
 
     fn _start_runtime(&self, server: &ArcServer) -> anyhow::Result<()> {
         let Some(listen_config) = &self.config.listen else {
+            self.listen_stats.set_no_listen();
             return Ok(());
         };
         let runtime =
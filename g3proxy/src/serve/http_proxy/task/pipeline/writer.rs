@@ -21,6 +21,7 @@ use std::time::Duration;
 use ahash::AHashMap;
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::sync::mpsc;
+use tokio::time::Instant;
 
 use g3_io_ext::{ArcLimitedWriterStats, LimitedWriter};
 use g3_types::auth::UserAuthError;
@@ -38,6 +39,23 @@ use crate::escape::EgressPathSelection;
 use crate::module::http_forward::{BoxHttpForwardContext, HttpProxyClientResponse};
 use crate::serve::{ServerStats, ServerTaskNotes};
 
+/// Get the connect deadline of the task, the smaller one of the config value and the header value
+/// will be used.
+///
+/// No deadline will be set if it's too far away to be represented.
+fn connect_deadline(
+    time_accepted: Instant,
+    config: Option<Duration>,
+    header_value: Option<&str>,
+) -> Option<Instant> {
+    let mut budget = config;
+    if let Some(ms) = header_value.and_then(|v| u64::from_str(v).ok()) {
+        let d = Duration::from_millis(ms);
+        budget = Some(budget.map(|b| b.min(d)).unwrap_or(d));
+    }
+    budget.and_then(|d| time_accepted.checked_add(d))
+}
+
 struct UserData {
     req_stats: Arc<UserRequestStats>,
     site_req_stats: Option<Arc<UserRequestStats>>,
@@ -259,18 +277,37 @@ where
         None
     }
 
+    fn get_connect_deadline(&self, req: &mut HttpProxyRequest<CDR>) -> Option<Instant> {
+        // check and remove the custom header, the value should be in milliseconds
+        let header_value = self
+            .ctx
+            .server_config
+            .connect_deadline_header
+            .as_ref()
+            .and_then(|header| req.inner.end_to_end_headers.remove(header));
+        connect_deadline(
+            req.time_accepted,
+            self.ctx.server_config.connect_deadline,
+            header_value.as_ref().map(|v| v.to_str()),
+        )
+    }
+
     async fn run(
         &mut self,
         mut req: HttpProxyRequest<CDR>,
         user_ctx: Option<UserContext>,
     ) -> LoopAction {
         let path_selection = self.get_egress_path_selection(&mut req.inner.end_to_end_headers);
-        let task_notes = ServerTaskNotes::with_path_selection(
+        let connect_deadline = self.get_connect_deadline(&mut req);
+        let mut task_notes = ServerTaskNotes::with_path_selection(
             self.ctx.cc_info.clone(),
             user_ctx,
             req.time_accepted.elapsed(),
             path_selection,
         );
+        if let Some(deadline) = connect_deadline {
+            task_notes.set_connect_deadline(deadline);
+        }
 
         let mut audit_ctx = self.audit_ctx.clone();
         let remote_protocol = match req.client_protocol {
@@ -558,3 +595,35 @@ where
         self.task_queue.close(); // may be deleted as the writer will dropped later
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deadline() {
+        let now = Instant::now();
+        assert!(connect_deadline(now, None, None).is_none());
+        assert!(connect_deadline(now, None, Some("abc")).is_none());
+
+        let d = Duration::from_secs(10);
+        assert_eq!(connect_deadline(now, Some(d), None), Some(now + d));
+        assert_eq!(
+            connect_deadline(now, Some(d), Some("1000")),
+            Some(now + Duration::from_secs(1))
+        );
+        assert_eq!(connect_deadline(now, Some(d), Some("60000")), Some(now + d));
+        assert_eq!(
+            connect_deadline(now, None, Some("1000")),
+            Some(now + Duration::from_secs(1))
+        );
+    }
+
+    #[test]
+    fn deadline_overflow() {
+        let now = Instant::now();
+        let max = u64::MAX.to_string();
+        assert!(connect_deadline(now, None, Some(&max)).is_none());
+        assert!(connect_deadline(now, Some(Duration::MAX), None).is_none());
+    }
+}
@@ -106,6 +106,8 @@ impl HttpRProxyServer {
 
         // always update extra metrics tags
         server_stats.set_extra_tags(config.extra_metrics_tags.clone());
+        server_stats.set_dead_mans_switch(config.dead_mans_switch);
+        server_stats.set_user_metrics_top_k(config.user_metrics_top_k);
 
         let escaper = Arc::new(crate::escape::get_or_insert_default(config.escaper()));
         let user_group = config.get_user_group();
@@ -297,6 +299,7 @@ impl ServerInternal for HttpRProxyServer {
 
     fn _start_runtime(&self, server: &ArcServer) -> anyhow::Result<()> {
         let Some(listen_config) = &self.config.listen else {
+            self.listen_stats.set_no_listen();
             return Ok(());
         };
         let runtime =
@@ -360,9 +363,12 @@ impl AcceptTcpServer for HttpRProxyServer {
                         .or(self.global_tls_server.as_ref())
                     {
                         Some(tls_config) => {
+                            let driver = Arc::clone(
+                                tls_config.select_driver(start.client_hello().server_name()),
+                            );
                             match tokio::time::timeout(
                                 tls_config.accept_timeout,
-                                start.into_stream(Arc::clone(&tls_config.driver)),
+                                start.into_stream(driver),
                             )
                             .await
                             {
@@ -38,6 +38,9 @@ pub(crate) use registry::{foreach_online as foreach_server, get_names, get_or_in
 mod idle_check;
 pub(crate) use idle_check::ServerIdleChecker;
 
+mod tls_acceptor;
+use tls_acceptor::RustlsAcceptor;
+
 mod dummy_close;
 mod intelli_proxy;
 mod native_tls_port;
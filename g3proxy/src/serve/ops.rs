@@ -357,6 +357,7 @@ where
 
     // enable force_quit and wait more time
     force_quit_offline_servers();
+    crate::escape::force_quit_negotiation();
 
     debug!("will wait {quit_timeout:?} for all tasks to force quit");
     if tokio::time::timeout(quit_timeout, &mut loop_wait)
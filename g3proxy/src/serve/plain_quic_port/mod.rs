@@ -20,7 +20,7 @@ use std::time::Duration;
 use anyhow::{anyhow, Context};
 use arc_swap::ArcSwap;
 use async_trait::async_trait;
-use quinn::Connection;
+use quinn::{Connection, TransportConfig};
 use tokio::net::TcpStream;
 use tokio::sync::{broadcast, watch};
 use tokio_rustls::server::TlsStream;
@@ -32,12 +32,28 @@ use g3_daemon::server::{BaseServer, ClientConnectionInfo, ServerReloadCommand};
 use g3_openssl::SslStream;
 use g3_types::acl::AclNetworkRule;
 use g3_types::metrics::NodeName;
-use g3_types::net::{OpensslTicketKey, RollingTicketer, UdpListenConfig};
+use g3_types::net::{OpensslTicketKey, QuinnRetryPolicy, RollingTicketer, UdpListenConfig};
 
 use crate::config::server::plain_quic_port::{PlainQuicPortConfig, PlainQuicPortUpdateFlags};
 use crate::config::server::{AnyServerConfig, ServerConfig};
 use crate::serve::{ArcServer, Server, ServerInternal, ServerQuitPolicy, WrapArcServer};
 
+fn build_quinn_config(
+    config: &PlainQuicPortConfig,
+    crypto: Arc<dyn quinn::crypto::ServerConfig>,
+) -> quinn::ServerConfig {
+    let mut transport = TransportConfig::default();
+    transport.enable_segmentation_offload(config.enable_gso);
+    if let Some(size) = config.gso_segment_size {
+        // the gso segment size is the same as the current mtu
+        transport.initial_mtu(size);
+    }
+
+    let mut quinn_config = quinn::ServerConfig::with_crypto(crypto);
+    quinn_config.transport_config(Arc::new(transport));
+    quinn_config
+}
+
 #[derive(Clone)]
 struct PlainQuicPortAuxConfig {
     ingress_net_filter: Option<Arc<AclNetworkRule>>,
@@ -45,6 +61,7 @@ struct PlainQuicPortAuxConfig {
     quinn_config: Option<quinn::ServerConfig>,
     accept_timeout: Duration,
     offline_rebind_port: Option<u16>,
+    retry_policy: QuinnRetryPolicy,
 }
 
 impl ListenQuicConf for PlainQuicPortAuxConfig {
@@ -72,6 +89,11 @@ impl ListenQuicConf for PlainQuicPortAuxConfig {
     fn accept_timeout(&self) -> Duration {
         self.accept_timeout
     }
+
+    #[inline]
+    fn retry_policy(&self) -> QuinnRetryPolicy {
+        self.retry_policy
+    }
 }
 
 pub(crate) struct PlainQuicPort {
@@ -115,6 +137,7 @@ impl PlainQuicPort {
             quinn_config: None,
             accept_timeout: quic_server.accept_timeout,
             offline_rebind_port: config.offline_rebind_port,
+            retry_policy: config.retry_policy,
         };
         let (cfg_sender, _cfg_receiver) = watch::channel(aux_config);
 
@@ -122,7 +145,7 @@ impl PlainQuicPort {
             name: config.name().clone(),
             config: ArcSwap::new(config),
             tls_rolling_ticketer,
-            quinn_config: quinn::ServerConfig::with_crypto(quic_server.driver),
+            quinn_config: build_quinn_config(&config, quic_server.driver),
             listen_stats,
             reload_sender,
             cfg_sender,
@@ -196,7 +219,7 @@ impl ServerInternal for PlainQuicPort {
 
             let quinn_config = if flags.contains(PlainQuicPortUpdateFlags::QUINN) {
                 let quic_config = config.tls_server.build_quic()?;
-                Some(quinn::ServerConfig::with_crypto(quic_config.driver))
+                Some(build_quinn_config(&config, quic_config.driver))
             } else {
                 None
             };
@@ -215,6 +238,7 @@ impl ServerInternal for PlainQuicPort {
                 quinn_config,
                 accept_timeout: config.tls_server.accept_timeout(),
                 offline_rebind_port: config.offline_rebind_port,
+                retry_policy: config.retry_policy,
             };
             self.cfg_sender.send_replace(aux_config);
             self.config.store(config);
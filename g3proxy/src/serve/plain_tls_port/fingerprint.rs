@@ -0,0 +1,231 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fmt::Write;
+use std::io;
+use std::sync::Arc;
+
+use openssl::hash::MessageDigest;
+use tokio::io::Interest;
+use tokio::net::TcpStream;
+
+use g3_dpi::parser::tls::{ClientHello, HandshakeCoalescer, Record, RecordParseError};
+
+const PEEK_BUF_SIZE: usize = 16384;
+
+#[derive(Default)]
+pub(super) struct ClientHelloFingerprint {
+    pub(super) ja3: Option<Arc<str>>,
+    pub(super) ja4: Option<Arc<str>>,
+}
+
+/// Peek the TLS ClientHello message from the tcp stream, and get the JA3 / JA4 fingerprint of it.
+///
+/// The data in the tcp stream will not be consumed, so it can be used by the tls acceptor later.
+/// The ClientHello message should be contained in the first 16KB data.
+pub(super) async fn peek_client_hello(
+    stream: &TcpStream,
+    ja3: bool,
+    ja4: bool,
+) -> Option<ClientHelloFingerprint> {
+    let mut buf = vec![0u8; PEEK_BUF_SIZE];
+    let mut last_len = 0;
+    loop {
+        // wait for new data if nothing more can be peeked, the readiness will be cleared on
+        // WouldBlock, so we will be waked up only when more data arrives
+        let len = stream
+            .async_io(Interest::READABLE, || {
+                let len = g3_socket::tcp::try_peek(stream, &mut buf)?;
+                if len != 0 && len == last_len {
+                    Err(io::Error::from(io::ErrorKind::WouldBlock))
+                } else {
+                    Ok(len)
+                }
+            })
+            .await
+            .ok()?;
+        if len == 0 {
+            return None;
+        }
+        last_len = len;
+
+        let r = with_client_hello(&buf[..len], |ch| {
+            let mut fingerprint = ClientHelloFingerprint::default();
+            if ja3 {
+                fingerprint.ja3 = ch.ja3_string().ok().and_then(|s| ja3_hash(&s));
+            }
+            if ja4 {
+                fingerprint.ja4 = ja4_fingerprint(ch);
+            }
+            fingerprint
+        });
+        match r {
+            Ok(Some(fingerprint)) => return Some(fingerprint),
+            Ok(None) => {}
+            Err(_) => return None,
+        }
+        if len >= buf.len() {
+            return None;
+        }
+    }
+}
+
+/// Parse the ClientHello message from the TLS records in `data`, which may be fragmented.
+///
+/// `Ok(None)` will be returned if more data is needed.
+fn with_client_hello<T, F>(data: &[u8], f: F) -> Result<Option<T>, ()>
+where
+    F: FnOnce(&ClientHello<'_>) -> T,
+{
+    let mut handshake_coalescer = HandshakeCoalescer::new(PEEK_BUF_SIZE as u32);
+    let mut record_offset = 0;
+    loop {
+        let mut record = match Record::parse(&data[record_offset..]) {
+            Ok(r) => r,
+            Err(RecordParseError::NeedMoreData(_)) => return Ok(None),
+            Err(_) => return Err(()),
+        };
+        record_offset += record.encoded_len();
+
+        // The Client Hello Message MUST be the first Handshake message
+        match record.consume_handshake(&mut handshake_coalescer) {
+            Ok(Some(handshake_msg)) => {
+                let ch = handshake_msg.parse_client_hello().map_err(|_| ())?;
+                return Ok(Some(f(&ch)));
+            }
+            Ok(None) => match handshake_coalescer.parse_client_hello() {
+                Ok(Some(ch)) => return Ok(Some(f(&ch))),
+                Ok(None) => {
+                    if !record.consume_done() {
+                        return Err(());
+                    }
+                }
+                Err(_) => return Err(()),
+            },
+            Err(_) => return Err(()),
+        }
+    }
+}
+
+fn push_hex(s: &mut String, data: &[u8]) {
+    for b in data {
+        let _ = write!(s, "{b:02x}");
+    }
+}
+
+fn ja3_hash(s: &str) -> Option<Arc<str>> {
+    let digest = openssl::hash::hash(MessageDigest::md5(), s.as_bytes()).ok()?;
+    let mut hex = String::with_capacity(digest.len() * 2);
+    push_hex(&mut hex, &digest);
+    Some(Arc::from(hex))
+}
+
+fn ja4_fingerprint(ch: &ClientHello<'_>) -> Option<Arc<str>> {
+    fn push_truncated_hash(s: &mut String, part: &str) {
+        if part.is_empty() {
+            s.push_str("000000000000");
+        } else {
+            let digest = openssl::sha::sha256(part.as_bytes());
+            push_hex(s, &digest[..6]);
+        }
+    }
+
+    let parts = ch.ja4_raw_parts(false).ok()?;
+    let mut s = String::with_capacity(36);
+    s.push_str(&parts.prefix);
+    s.push('_');
+    push_truncated_hash(&mut s, &parts.ciphers);
+    s.push('_');
+    push_truncated_hash(&mut s, &parts.extensions);
+    Some(Arc::from(s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the same ClientHello message as in the g3-dpi ja3 test
+    const CLIENT_HELLO: &[u8] = &[
+        0x01, 0x00, 0x00, 0x49, 0x03, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x06, 0x0a, 0x0a, 0x13, 0x01,
+        0xc0, 0x2f, 0x01, 0x00, 0x00, 0x1a, 0x0a, 0x0a, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x08, 0x00,
+        0x06, 0x1a, 0x1a, 0x00, 0x1d, 0x00, 0x17, 0x00, 0x0b, 0x00, 0x02, 0x01, 0x00, 0x00, 0x17,
+        0x00, 0x00,
+    ];
+    const JA3_STRING: &str = "771,4865-49199,10-11-23,29-23,0";
+
+    fn push_record(buf: &mut Vec<u8>, fragment: &[u8]) {
+        buf.extend_from_slice(&[0x16, 0x03, 0x01]);
+        buf.extend_from_slice(&(fragment.len() as u16).to_be_bytes());
+        buf.extend_from_slice(fragment);
+    }
+
+    fn parse_ja3_string(data: &[u8]) -> Result<Option<String>, ()> {
+        with_client_hello(data, |ch| ch.ja3_string().unwrap())
+    }
+
+    #[test]
+    fn parse_ja3_string_single_record() {
+        let mut data = Vec::new();
+        push_record(&mut data, CLIENT_HELLO);
+        assert_eq!(parse_ja3_string(&data).unwrap().unwrap(), JA3_STRING);
+
+        // incomplete record
+        assert_eq!(parse_ja3_string(&data[..data.len() - 1]).unwrap(), None);
+    }
+
+    #[test]
+    fn parse_ja3_string_fragmented_records() {
+        let mut data = Vec::new();
+        push_record(&mut data, &CLIENT_HELLO[..3]);
+        push_record(&mut data, &CLIENT_HELLO[3..30]);
+        let first_len = data.len();
+        push_record(&mut data, &CLIENT_HELLO[30..]);
+
+        assert_eq!(parse_ja3_string(&data).unwrap().unwrap(), JA3_STRING);
+        assert_eq!(parse_ja3_string(&data[..first_len]).unwrap(), None);
+        assert_eq!(parse_ja3_string(&data[..first_len + 10]).unwrap(), None);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        let mut data = Vec::new();
+        // application data record
+        data.extend_from_slice(&[0x17, 0x03, 0x03, 0x00, 0x01, 0x00]);
+        assert!(parse_ja3_string(&data).is_err());
+    }
+
+    #[test]
+    fn fingerprint_hash() {
+        assert_eq!(
+            ja3_hash("771,4865-49199,10-11-23,29-23,0").unwrap().len(),
+            32
+        );
+
+        let mut data = Vec::new();
+        push_record(&mut data, CLIENT_HELLO);
+        let ja4 = with_client_hello(&data, ja4_fingerprint)
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        let parts = ja4.split('_').collect::<Vec<_>>();
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0], "t12i020300");
+        assert_eq!(parts[1].len(), 12);
+        assert_eq!(parts[2].len(), 12);
+    }
+}
@@ -26,7 +26,7 @@ use log::debug;
 use quinn::Connection;
 use tokio::net::TcpStream;
 use tokio::sync::broadcast;
-use tokio_rustls::{server::TlsStream, TlsAcceptor};
+use tokio_rustls::server::TlsStream;
 
 use g3_daemon::listen::{AcceptQuicServer, AcceptTcpServer, ListenStats, ListenTcpRuntime};
 use g3_daemon::server::{BaseServer, ClientConnectionInfo, ServerReloadCommand};
@@ -40,13 +40,17 @@ use g3_types::net::{
 
 use crate::config::server::plain_tls_port::PlainTlsPortConfig;
 use crate::config::server::{AnyServerConfig, ServerConfig};
-use crate::serve::{ArcServer, Server, ServerInternal, ServerQuitPolicy, WrapArcServer};
+use crate::serve::{
+    ArcServer, RustlsAcceptor, Server, ServerInternal, ServerQuitPolicy, WrapArcServer,
+};
+
+mod fingerprint;
 
 pub(crate) struct PlainTlsPort {
     config: PlainTlsPortConfig,
     listen_stats: Arc<ListenStats>,
     tls_rolling_ticketer: Option<Arc<RollingTicketer<OpensslTicketKey>>>,
-    tls_acceptor: TlsAcceptor,
+    tls_acceptor: RustlsAcceptor,
     tls_accept_timeout: Duration,
     ingress_net_filter: Option<AclNetworkRule>,
     reload_sender: broadcast::Sender<ServerReloadCommand>,
@@ -84,8 +88,8 @@ impl PlainTlsPort {
             config,
             listen_stats,
             tls_rolling_ticketer,
-            tls_acceptor: TlsAcceptor::from(tls_server_config.driver),
             tls_accept_timeout: tls_server_config.accept_timeout,
+            tls_acceptor: RustlsAcceptor::from(tls_server_config),
             ingress_net_filter,
             reload_sender,
             next_server: ArcSwap::new(next_server),
@@ -186,8 +190,26 @@ impl PlainTlsPort {
             None => {}
         }
 
-        match tokio::time::timeout(self.tls_accept_timeout, self.tls_acceptor.accept(stream)).await
-        {
+        let tls_accept = async {
+            if self.config.tls_client_ja3 || self.config.tls_client_ja4 {
+                if let Some(fingerprint) = fingerprint::peek_client_hello(
+                    &stream,
+                    self.config.tls_client_ja3,
+                    self.config.tls_client_ja4,
+                )
+                .await
+                {
+                    if let Some(ja3) = fingerprint.ja3 {
+                        cc_info.set_tls_client_ja3(ja3);
+                    }
+                    if let Some(ja4) = fingerprint.ja4 {
+                        cc_info.set_tls_client_ja4(ja4);
+                    }
+                }
+            }
+            self.tls_acceptor.accept(stream).await
+        };
+        match tokio::time::timeout(self.tls_accept_timeout, tls_accept).await {
             Ok(Ok(tls_stream)) => {
                 if tls_stream.get_ref().1.session_reused() {
                     // Quick ACK is needed with session resumption
@@ -85,6 +85,8 @@ impl SocksProxyServer {
         let task_logger = config.get_task_logger();
 
         server_stats.set_extra_tags(config.extra_metrics_tags.clone());
+        server_stats.set_dead_mans_switch(config.dead_mans_switch);
+        server_stats.set_user_metrics_top_k(config.user_metrics_top_k);
 
         let escaper = Arc::new(crate::escape::get_or_insert_default(config.escaper()));
         let user_group = config.get_user_group();
@@ -232,6 +234,7 @@ impl ServerInternal for SocksProxyServer {
 
     fn _start_runtime(&self, server: &ArcServer) -> anyhow::Result<()> {
         let Some(listen_config) = &self.config.listen else {
+            self.listen_stats.set_no_listen();
             return Ok(());
         };
         let runtime =
@@ -15,7 +15,7 @@
  */
 
 use std::net::SocketAddr;
-use std::sync::atomic::{AtomicIsize, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicIsize, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use arc_swap::ArcSwapOption;
@@ -32,6 +32,8 @@ pub(crate) struct SocksProxyServerStats {
     id: StatId,
 
     extra_metrics_tags: Arc<ArcSwapOption<StaticMetricsTags>>,
+    dead_mans_switch: AtomicBool,
+    user_metrics_top_k: AtomicUsize,
 
     online: AtomicIsize,
     conn_total: AtomicU64,
@@ -52,6 +54,8 @@ impl SocksProxyServerStats {
             name: name.clone(),
             id: StatId::new(),
             extra_metrics_tags: Arc::new(ArcSwapOption::new(None)),
+            dead_mans_switch: AtomicBool::new(false),
+            user_metrics_top_k: AtomicUsize::new(0),
             online: AtomicIsize::new(0),
             conn_total: AtomicU64::new(0),
             forbidden: Default::default(),
@@ -75,6 +79,14 @@ impl SocksProxyServerStats {
         self.extra_metrics_tags.store(tags);
     }
 
+    pub(crate) fn set_dead_mans_switch(&self, enable: bool) {
+        self.dead_mans_switch.store(enable, Ordering::Relaxed);
+    }
+
+    pub(crate) fn set_user_metrics_top_k(&self, top_k: usize) {
+        self.user_metrics_top_k.store(top_k, Ordering::Relaxed);
+    }
+
     pub(crate) fn add_conn(&self, _addr: SocketAddr) {
         self.conn_total.fetch_add(1, Ordering::Relaxed);
     }
@@ -101,6 +113,16 @@ impl ServerStats for SocksProxyServerStats {
         &self.extra_metrics_tags
     }
 
+    #[inline]
+    fn dead_mans_switch(&self) -> bool {
+        self.dead_mans_switch.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    fn user_metrics_top_k(&self) -> usize {
+        self.user_metrics_top_k.load(Ordering::Relaxed)
+    }
+
     fn is_online(&self) -> bool {
         self.online.load(Ordering::Relaxed) > 0
     }
@@ -51,6 +51,16 @@ pub(crate) trait ServerStats {
     fn untrusted_snapshot(&self) -> Option<UntrustedTaskStatsSnapshot> {
         None
     }
+
+    /// max number of users to emit per server user metrics for, 0 means disabled
+    fn user_metrics_top_k(&self) -> usize {
+        0
+    }
+
+    /// whether to stop emitting the alive gauge if the server is detected to be unhealthy
+    fn dead_mans_switch(&self) -> bool {
+        false
+    }
 }
 
 pub(crate) type ArcServerStats = Arc<dyn ServerStats + Send + Sync>;
@@ -27,6 +27,7 @@ use g3_types::limit::GaugeSemaphorePermit;
 
 use crate::auth::UserContext;
 use crate::escape::EgressPathSelection;
+use crate::module::tcp_connect::TcpConnectError;
 
 #[derive(Clone, Copy)]
 pub(crate) enum ServerTaskStage {
@@ -68,6 +69,7 @@ pub(crate) struct ServerTaskNotes {
     pub(crate) wait_time: Duration,
     pub(crate) ready_time: Duration,
     pub(crate) egress_path_selection: Option<EgressPathSelection>,
+    connect_deadline: Option<Instant>,
     /// the following fields should not be cloned
     pub(crate) user_req_alive_permit: Option<GaugeSemaphorePermit>,
 }
@@ -99,6 +101,7 @@ impl ServerTaskNotes {
             wait_time,
             ready_time: Duration::default(),
             egress_path_selection,
+            connect_deadline: None,
             user_req_alive_permit: None,
         }
     }
@@ -118,6 +121,16 @@ impl ServerTaskNotes {
         self.cc_info.server_addr()
     }
 
+    #[inline]
+    pub(crate) fn tls_client_ja3(&self) -> Option<&str> {
+        self.cc_info.tls_client_ja3()
+    }
+
+    #[inline]
+    pub(crate) fn tls_client_ja4(&self) -> Option<&str> {
+        self.cc_info.tls_client_ja4()
+    }
+
     #[inline]
     pub(crate) fn worker_id(&self) -> Option<usize> {
         self.cc_info.worker_id()
@@ -154,6 +167,32 @@ impl ServerTaskNotes {
         self.create_ins.elapsed()
     }
 
+    /// Set the deadline before which the remote connection should be set up
+    pub(crate) fn set_connect_deadline(&mut self, deadline: Instant) {
+        self.connect_deadline = Some(match self.connect_deadline {
+            Some(old) => old.min(deadline),
+            None => deadline,
+        });
+    }
+
+    /// Get the deadline for the escaper negotiation, which is the earlier one of the task
+    /// connect deadline and the escaper's own timeout.
+    /// The bool value will be true if it is limited by the task connect deadline.
+    pub(crate) fn escaper_connect_deadline(
+        &self,
+        timeout: Duration,
+    ) -> Result<(Instant, bool), TcpConnectError> {
+        let now = Instant::now();
+        let deadline = now + timeout;
+        match self.connect_deadline {
+            Some(task_deadline) if task_deadline <= now => {
+                Err(TcpConnectError::TaskDeadlineExceeded)
+            }
+            Some(task_deadline) if task_deadline < deadline => Ok((task_deadline, true)),
+            _ => Ok((deadline, false)),
+        }
+    }
+
     pub(crate) fn mark_relaying(&mut self) {
         self.stage = ServerTaskStage::Relaying;
         self.ready_time = self.create_ins.elapsed();
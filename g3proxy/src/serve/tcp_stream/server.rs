@@ -98,6 +98,7 @@ impl TcpStreamServer {
         let task_logger = config.get_task_logger();
 
         server_stats.set_extra_tags(config.extra_metrics_tags.clone());
+        server_stats.set_dead_mans_switch(config.dead_mans_switch);
 
         let escaper = Arc::new(crate::escape::get_or_insert_default(config.escaper()));
         let audit_handle = config.get_audit_handle()?;
@@ -264,6 +265,7 @@ impl ServerInternal for TcpStreamServer {
 
     fn _start_runtime(&self, server: &ArcServer) -> anyhow::Result<()> {
         let Some(listen_config) = &self.config.listen else {
+            self.listen_stats.set_no_listen();
             return Ok(());
         };
         let runtime =
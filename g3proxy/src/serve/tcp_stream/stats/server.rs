@@ -15,7 +15,7 @@
  */
 
 use std::net::SocketAddr;
-use std::sync::atomic::{AtomicI32, AtomicIsize, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicIsize, AtomicU64, Ordering};
 use std::sync::Arc;
 
 use arc_swap::ArcSwapOption;
@@ -30,6 +30,7 @@ pub(crate) struct TcpStreamServerStats {
     id: StatId,
 
     extra_metrics_tags: Arc<ArcSwapOption<StaticMetricsTags>>,
+    dead_mans_switch: AtomicBool,
 
     online: AtomicIsize,
     conn_total: AtomicU64,
@@ -47,6 +48,7 @@ impl TcpStreamServerStats {
             name: name.clone(),
             id: StatId::new(),
             extra_metrics_tags: Arc::new(ArcSwapOption::new(None)),
+            dead_mans_switch: AtomicBool::new(false),
             online: AtomicIsize::new(0),
             conn_total: AtomicU64::new(0),
             task_total: AtomicU64::new(0),
@@ -68,6 +70,10 @@ impl TcpStreamServerStats {
         self.extra_metrics_tags.store(tags);
     }
 
+    pub(crate) fn set_dead_mans_switch(&self, enable: bool) {
+        self.dead_mans_switch.store(enable, Ordering::Relaxed);
+    }
+
     pub(crate) fn add_conn(&self, _addr: SocketAddr) {
         self.conn_total.fetch_add(1, Ordering::Relaxed);
     }
@@ -116,6 +122,11 @@ impl ServerStats for TcpStreamServerStats {
         &self.extra_metrics_tags
     }
 
+    #[inline]
+    fn dead_mans_switch(&self) -> bool {
+        self.dead_mans_switch.load(Ordering::Relaxed)
+    }
+
     fn is_online(&self) -> bool {
         self.online.load(Ordering::Relaxed) > 0
     }
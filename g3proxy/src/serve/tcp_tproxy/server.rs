@@ -75,6 +75,7 @@ impl TcpTProxyServer {
         let task_logger = config.get_task_logger();
 
         server_stats.set_extra_tags(config.extra_metrics_tags.clone());
+        server_stats.set_dead_mans_switch(config.dead_mans_switch);
 
         let escaper = Arc::new(crate::escape::get_or_insert_default(config.escaper()));
         let audit_handle = config.get_audit_handle()?;
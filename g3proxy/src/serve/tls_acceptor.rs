@@ -0,0 +1,58 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io;
+use std::sync::Arc;
+
+use rustls::server::Acceptor;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::{LazyConfigAcceptor, TlsAcceptor};
+
+use g3_types::net::RustlsServerConfig;
+
+/// A rustls acceptor which selects the server config by the server name in client hello,
+/// so the per server name protocol version policies can be applied by rustls itself.
+#[derive(Clone)]
+pub(crate) struct RustlsAcceptor {
+    config: RustlsServerConfig,
+    default: TlsAcceptor,
+}
+
+impl From<RustlsServerConfig> for RustlsAcceptor {
+    fn from(config: RustlsServerConfig) -> Self {
+        let default = TlsAcceptor::from(config.driver.clone());
+        RustlsAcceptor { config, default }
+    }
+}
+
+impl RustlsAcceptor {
+    pub(crate) async fn accept<IO>(&self, stream: IO) -> io::Result<TlsStream<IO>>
+    where
+        IO: AsyncRead + AsyncWrite + Unpin,
+    {
+        if !self.config.has_sni_drivers() {
+            return self.default.accept(stream).await;
+        }
+
+        let start = LazyConfigAcceptor::new(Acceptor::default(), stream).await?;
+        let driver = Arc::clone(
+            self.config
+                .select_driver(start.client_hello().server_name()),
+        );
+        start.into_stream(driver).await
+    }
+}
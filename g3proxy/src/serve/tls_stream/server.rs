@@ -27,7 +27,7 @@ use quinn::Connection;
 use slog::Logger;
 use tokio::net::TcpStream;
 use tokio::sync::broadcast;
-use tokio_rustls::{server::TlsStream, TlsAcceptor};
+use tokio_rustls::server::TlsStream;
 
 use g3_daemon::listen::{AcceptQuicServer, AcceptTcpServer, ListenStats, ListenTcpRuntime};
 use g3_daemon::server::{BaseServer, ClientConnectionInfo, ServerExt, ServerReloadCommand};
@@ -48,7 +48,8 @@ use crate::config::server::{AnyServerConfig, ServerConfig};
 use crate::escape::ArcEscaper;
 use crate::serve::tcp_stream::TcpStreamServerStats;
 use crate::serve::{
-    ArcServer, ArcServerStats, Server, ServerInternal, ServerQuitPolicy, ServerStats, WrapArcServer,
+    ArcServer, ArcServerStats, RustlsAcceptor, Server, ServerInternal, ServerQuitPolicy,
+    ServerStats, WrapArcServer,
 };
 
 pub(crate) struct TlsStreamServer {
@@ -57,7 +58,7 @@ pub(crate) struct TlsStreamServer {
     listen_stats: Arc<ListenStats>,
     upstream: SelectiveVec<WeightedUpstreamAddr>,
     tls_rolling_ticketer: Option<Arc<RollingTicketer<OpensslTicketKey>>>,
-    tls_acceptor: TlsAcceptor,
+    tls_acceptor: RustlsAcceptor,
     tls_accept_timeout: Duration,
     tls_client_config: Option<Arc<OpensslClientConfig>>,
     ingress_net_filter: Option<AclNetworkRule>,
@@ -111,6 +112,7 @@ impl TlsStreamServer {
         let task_logger = config.get_task_logger();
 
         server_stats.set_extra_tags(config.extra_metrics_tags.clone());
+        server_stats.set_dead_mans_switch(config.dead_mans_switch);
 
         let escaper = Arc::new(crate::escape::get_or_insert_default(config.escaper()));
         let audit_handle = config.get_audit_handle()?;
@@ -121,8 +123,8 @@ impl TlsStreamServer {
             listen_stats,
             upstream,
             tls_rolling_ticketer,
-            tls_acceptor: TlsAcceptor::from(tls_server_config.driver),
             tls_accept_timeout: tls_server_config.accept_timeout,
+            tls_acceptor: RustlsAcceptor::from(tls_server_config),
             tls_client_config,
             ingress_net_filter,
             reload_sender,
@@ -276,6 +278,7 @@ impl ServerInternal for TlsStreamServer {
 
     fn _start_runtime(&self, server: &ArcServer) -> anyhow::Result<()> {
         let Some(listen_config) = &self.config.listen else {
+            self.listen_stats.set_no_listen();
             return Ok(());
         };
         let runtime =
@@ -25,26 +25,34 @@ async fn do_reload() {
     let _guard = RELOAD_MUTEX.lock().await;
     info!("reloading config");
 
+    let mut result = Ok(());
     if let Err(e) = crate::config::reload().await {
         warn!("error reloading config: {e:?}");
         warn!("reload aborted");
+        result = Err(e);
     }
 
     if let Err(e) = crate::resolve::spawn_all().await {
         error!("failed to reload all resolvers: {e:?}");
+        result = Err(e);
     }
     if let Err(e) = crate::escape::load_all().await {
         error!("failed to reload all escapers: {e:?}");
+        result = Err(e);
     }
     if let Err(e) = crate::auth::load_all().await {
         error!("failed to reload all user groups: {e:?}");
+        result = Err(e);
     }
     if let Err(e) = crate::audit::load_all().await {
         error!("failed to reload all auditors: {e:?}");
+        result = Err(e);
     }
     if let Err(e) = crate::serve::spawn_all().await {
         error!("failed to reload all servers: {e:?}");
+        result = Err(e);
     }
+    g3_daemon::config::metrics::add_reload_result(&result);
 
     info!("reload finished");
 }
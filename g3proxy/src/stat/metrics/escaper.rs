@@ -15,20 +15,24 @@
  */
 
 use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
 
-use ahash::AHashMap;
+use ahash::{AHashMap, AHashSet};
 
 use g3_daemon::metrics::{
     TAG_KEY_STAT_ID, TAG_KEY_TRANSPORT, TRANSPORT_TYPE_TCP, TRANSPORT_TYPE_UDP,
 };
 use g3_statsd_client::{StatsdClient, StatsdTagGroup};
 use g3_types::metrics::NodeName;
+use g3_types::net::UpstreamAddr;
 use g3_types::stats::{StatId, TcpIoSnapshot, UdpIoSnapshot};
 
 use super::TAG_KEY_ESCAPER;
 use crate::escape::{
-    ArcEscaperStats, EscaperForbiddenSnapshot, EscaperTcpConnectSnapshot, EscaperTlsSnapshot,
-    RouteEscaperSnapshot, RouteEscaperStats,
+    ArcEscaperStats, EscaperCircuitBreakerSnapshot, EscaperConnectErrorSnapshot,
+    EscaperForbiddenSnapshot, EscaperPeerExpireSnapshot, EscaperPeerFeedSnapshot,
+    EscaperPeerHealthSnapshot, EscaperTcpConnectSnapshot, EscaperTlsSnapshot, RouteEscaperSnapshot,
+    RouteEscaperStats,
 };
 
 const METRIC_NAME_ESCAPER_TASK_TOTAL: &str = "escaper.task.total";
@@ -39,14 +43,68 @@ const METRIC_NAME_ESCAPER_TCP_CONNECT_ESTABLISH: &str = "escaper.tcp.connect.est
 const METRIC_NAME_ESCAPER_TCP_CONNECT_SUCCESS: &str = "escaper.tcp.connect.success";
 const METRIC_NAME_ESCAPER_TCP_CONNECT_ERROR: &str = "escaper.tcp.connect.error";
 const METRIC_NAME_ESCAPER_TCP_CONNECT_TIMEOUT: &str = "escaper.tcp.connect.timeout";
+const METRIC_NAME_ESCAPER_TCP_CONNECT_MPTCP_ESTABLISH: &str = "escaper.tcp.connect.mptcp_establish";
+const METRIC_NAME_ESCAPER_TCP_CONNECT_MPTCP_FALLBACK: &str = "escaper.tcp.connect.mptcp_fallback";
+const METRIC_NAME_ESCAPER_TCP_CONNECT_HAPPY_IPV4_ESTABLISH: &str =
+    "escaper.tcp.connect.happy_ipv4_establish";
+const METRIC_NAME_ESCAPER_TCP_CONNECT_HAPPY_IPV6_ESTABLISH: &str =
+    "escaper.tcp.connect.happy_ipv6_establish";
+const METRIC_NAME_ESCAPER_TCP_CONNECT_PREFERRED_ADDR_HIT: &str =
+    "escaper.tcp.connect.preferred_addr_hit";
+const METRIC_NAME_ESCAPER_TCP_CONNECT_PREFERRED_ADDR_MISS: &str =
+    "escaper.tcp.connect.preferred_addr_miss";
+const METRIC_NAME_ESCAPER_TCP_CONNECT_NEGOTIATION_RETRY: &str =
+    "escaper.tcp.connect.negotiation_retry";
+const METRIC_NAME_ESCAPER_TCP_CONNECT_UNEXPECTED_RSP_BODY: &str =
+    "escaper.tcp.connect.unexpected_rsp_body";
+const METRIC_NAME_ESCAPER_TCP_CONNECT_LOCAL_PORT_EXHAUSTED: &str =
+    "escaper.tcp.connect.local_port_exhausted";
 const METRIC_NAME_ESCAPER_TLS_HANDSHAKE_SUCCESS: &str = "escaper.tls.handshake.success";
 const METRIC_NAME_ESCAPER_TLS_HANDSHAKE_ERROR: &str = "escaper.tls.handshake.error";
 const METRIC_NAME_ESCAPER_TLS_HANDSHAKE_TIMEOUT: &str = "escaper.tls.handshake.timeout";
+const METRIC_NAME_ESCAPER_TLS_EARLY_DATA_ACCEPTED: &str = "escaper.tls.early_data.accepted";
+const METRIC_NAME_ESCAPER_TLS_EARLY_DATA_REJECTED: &str = "escaper.tls.early_data.rejected";
+const METRIC_NAME_ESCAPER_CONNECT_ERROR_RESOLVE_FAILED: &str =
+    "escaper.connect_error.resolve_failed";
+const METRIC_NAME_ESCAPER_CONNECT_ERROR_CONNECT_REFUSED: &str =
+    "escaper.connect_error.connect_refused";
+const METRIC_NAME_ESCAPER_CONNECT_ERROR_CONNECT_TIMEOUT: &str =
+    "escaper.connect_error.connect_timeout";
+const METRIC_NAME_ESCAPER_CONNECT_ERROR_NEGOTIATION_TIMEOUT: &str =
+    "escaper.connect_error.negotiation_timeout";
+const METRIC_NAME_ESCAPER_CONNECT_ERROR_TLS_HANDSHAKE_FAILED: &str =
+    "escaper.connect_error.tls_handshake_failed";
+const METRIC_NAME_ESCAPER_CONNECT_ERROR_TLS_HANDSHAKE_TIMEOUT: &str =
+    "escaper.connect_error.tls_handshake_timeout";
 const METRIC_NAME_ESCAPER_IO_IN_BYTES: &str = "escaper.traffic.in.bytes";
 const METRIC_NAME_ESCAPER_IO_IN_PACKETS: &str = "escaper.traffic.in.packets";
 const METRIC_NAME_ESCAPER_IO_OUT_BYTES: &str = "escaper.traffic.out.bytes";
 const METRIC_NAME_ESCAPER_IO_OUT_PACKETS: &str = "escaper.traffic.out.packets";
 const METRIC_NAME_ESCAPER_FORBIDDEN_IP_BLOCKED: &str = "escaper.forbidden.ip_blocked";
+const METRIC_NAME_ESCAPER_FORBIDDEN_ASN_BLOCKED: &str = "escaper.forbidden.asn_blocked";
+const METRIC_NAME_ESCAPER_FORBIDDEN_IP_LOCATE_FAILED_PERMITTED: &str =
+    "escaper.forbidden.ip_locate_failed_permitted";
+const METRIC_NAME_ESCAPER_FORBIDDEN_IP_LOCATE_FAILED_FORBIDDEN: &str =
+    "escaper.forbidden.ip_locate_failed_forbidden";
+const METRIC_NAME_ESCAPER_PEER_HEALTHY: &str = "escaper.peer.healthy";
+const METRIC_NAME_ESCAPER_PEER_UNHEALTHY: &str = "escaper.peer.unhealthy";
+const METRIC_NAME_ESCAPER_PEER_ALIVE: &str = "escaper.peer.alive";
+const METRIC_NAME_ESCAPER_PEER_EXPIRED: &str = "escaper.peer.expired";
+const METRIC_NAME_ESCAPER_PEER_FEED_REFRESH_SUCCESS: &str = "escaper.peer_feed.refresh.success";
+const METRIC_NAME_ESCAPER_PEER_FEED_REFRESH_FAILURE: &str = "escaper.peer_feed.refresh.failure";
+const METRIC_NAME_ESCAPER_PEER_FEED_PEER_COUNT: &str = "escaper.peer_feed.peer.count";
+const METRIC_NAME_ESCAPER_NEGOTIATION_QUEUED: &str = "escaper.negotiation.queued";
+const METRIC_NAME_ESCAPER_UPSTREAM_RTT: &str = "escaper.upstream.rtt";
+const METRIC_NAME_ESCAPER_UPSTREAM_AGENT: &str = "escaper.upstream.agent";
+const METRIC_NAME_ESCAPER_UPSTREAM_TUNNEL_ALIVE: &str = "escaper.upstream.tunnel.alive";
+const METRIC_NAME_ESCAPER_UPSTREAM_SELECTED: &str = "escaper.upstream.selected";
+const METRIC_NAME_ESCAPER_CIRCUIT_BREAKER_STATE: &str = "escaper.circuit_breaker.state";
+const METRIC_NAME_ESCAPER_CIRCUIT_BREAKER_TRIPPED: &str = "escaper.circuit_breaker.tripped";
+
+const TAG_KEY_UPSTREAM: &str = "upstream";
+const TAG_KEY_AGENT: &str = "agent";
+const TAG_KEY_AGENT_VERSION: &str = "agent_version";
+const TAG_KEY_WEIGHT: &str = "weight";
 
 const METRIC_NAME_ROUTE_REQUEST_PASSED: &str = "route.request.passed";
 const METRIC_NAME_ROUTE_REQUEST_FAILED: &str = "route.request.failed";
@@ -58,6 +116,13 @@ static ESCAPER_STATS_MAP: LazyLock<Mutex<AHashMap<StatId, EscaperStatsValue>>> =
     LazyLock::new(|| Mutex::new(AHashMap::new()));
 static ROUTE_STATS_MAP: LazyLock<Mutex<AHashMap<StatId, RouterStatsValue>>> =
     LazyLock::new(|| Mutex::new(AHashMap::new()));
+static UNREACHABLE_ESCAPERS: LazyLock<Mutex<AHashSet<NodeName>>> =
+    LazyLock::new(|| Mutex::new(AHashSet::new()));
+
+/// the escaper will be treated as unreachable if there are connection attempts but no connection
+/// established for this long. The state is held through cycles without any connection attempt, so
+/// it won't flap, but it will be cleared if there is no more attempt for this long
+const UNREACHABLE_DURATION: Duration = Duration::from_secs(10);
 
 trait EscaperMetricExt {
     fn add_escaper_tags(&mut self, escaper: &NodeName, stat_id: StatId);
@@ -79,9 +144,38 @@ struct EscaperSnapshot {
     conn_establish: u64,
     tcp_connect: EscaperTcpConnectSnapshot,
     tls: EscaperTlsSnapshot,
+    connect_error: EscaperConnectErrorSnapshot,
     tcp: TcpIoSnapshot,
     udp: UdpIoSnapshot,
     forbidden: EscaperForbiddenSnapshot,
+    circuit_breaker_tripped: u64,
+    peer_feed: EscaperPeerFeedSnapshot,
+    upstream_selected: AHashMap<UpstreamAddr, u64>,
+    failing_since: Option<Instant>,
+    last_failed_attempt: Option<Instant>,
+}
+
+impl EscaperSnapshot {
+    fn update_reachability(&mut self, attempt_diff: u64, establish_diff: u64, now: Instant) {
+        if establish_diff > 0 {
+            self.failing_since = None;
+            self.last_failed_attempt = None;
+        } else if attempt_diff > 0 {
+            self.failing_since.get_or_insert(now);
+            self.last_failed_attempt = Some(now);
+        } else if let Some(last) = self.last_failed_attempt {
+            if now.saturating_duration_since(last) >= UNREACHABLE_DURATION {
+                self.failing_since = None;
+                self.last_failed_attempt = None;
+            }
+        }
+    }
+
+    fn is_unreachable(&self, now: Instant) -> bool {
+        self.failing_since
+            .map(|since| now.saturating_duration_since(since) >= UNREACHABLE_DURATION)
+            .unwrap_or(false)
+    }
 }
 
 pub(in crate::stat) fn sync_stats() {
@@ -109,14 +203,24 @@ pub(in crate::stat) fn sync_stats() {
     drop(route_stats_map);
 }
 
+pub(super) fn is_unreachable(escaper: &NodeName) -> bool {
+    let unreachable_escapers = UNREACHABLE_ESCAPERS.lock().unwrap();
+    unreachable_escapers.contains(escaper)
+}
+
 pub(in crate::stat) fn emit_stats(client: &mut StatsdClient) {
+    let mut unreachable_escapers = AHashSet::new();
     let mut escaper_stats_map = ESCAPER_STATS_MAP.lock().unwrap();
     escaper_stats_map.retain(|_, (stats, snap)| {
         emit_escaper_stats(client, stats, snap);
+        if snap.is_unreachable(Instant::now()) {
+            unreachable_escapers.insert(stats.name().clone());
+        }
         // use Arc instead of Weak here, as we should emit the final metrics before drop it
         Arc::strong_count(stats) > 1
     });
     drop(escaper_stats_map);
+    *UNREACHABLE_ESCAPERS.lock().unwrap() = unreachable_escapers;
 
     let mut route_stats_map = ROUTE_STATS_MAP.lock().unwrap();
     route_stats_map.retain(|_, (stats, snap)| {
@@ -145,19 +249,25 @@ fn emit_escaper_stats(
     snap.task_total = new_value;
 
     let new_value = stats.connection_attempted();
-    let diff_value = new_value.wrapping_sub(snap.conn_attempt);
+    let attempt_diff = new_value.wrapping_sub(snap.conn_attempt);
     client
-        .count_with_tags(METRIC_NAME_ESCAPER_CONN_ATTEMPT, diff_value, &common_tags)
+        .count_with_tags(METRIC_NAME_ESCAPER_CONN_ATTEMPT, attempt_diff, &common_tags)
         .send();
     snap.conn_attempt = new_value;
 
     let new_value = stats.connection_established();
-    let diff_value = new_value.wrapping_sub(snap.conn_establish);
+    let establish_diff = new_value.wrapping_sub(snap.conn_establish);
     client
-        .count_with_tags(METRIC_NAME_ESCAPER_CONN_ESTABLISH, diff_value, &common_tags)
+        .count_with_tags(
+            METRIC_NAME_ESCAPER_CONN_ESTABLISH,
+            establish_diff,
+            &common_tags,
+        )
         .send();
     snap.conn_establish = new_value;
 
+    snap.update_reachability(attempt_diff, establish_diff, Instant::now());
+
     if let Some(connect_stats) = stats.tcp_connect_snapshot() {
         emit_tcp_connect_stats(client, connect_stats, &mut snap.tcp_connect, &common_tags);
     }
@@ -166,10 +276,100 @@ fn emit_escaper_stats(
         emit_tls_stats(client, tls_stats, &mut snap.tls, &common_tags);
     }
 
+    if let Some(connect_error_stats) = stats.connect_error_snapshot() {
+        emit_connect_error_stats(
+            client,
+            connect_error_stats,
+            &mut snap.connect_error,
+            &common_tags,
+        );
+    }
+
     if let Some(forbidden_stats) = stats.forbidden_snapshot() {
         emit_forbidden_stats(client, forbidden_stats, &mut snap.forbidden, &common_tags);
     }
 
+    if let Some(peer_health) = stats.peer_health_snapshot() {
+        emit_peer_health_stats(client, peer_health, &common_tags);
+    }
+
+    if let Some(peer_expire) = stats.peer_expire_snapshot() {
+        emit_peer_expire_stats(client, peer_expire, &common_tags);
+    }
+
+    if let Some(peer_feed) = stats.peer_feed_snapshot() {
+        emit_peer_feed_stats(client, peer_feed, &mut snap.peer_feed, &common_tags);
+    }
+
+    if let Some(queued) = stats.negotiation_queued() {
+        client
+            .gauge_with_tags(METRIC_NAME_ESCAPER_NEGOTIATION_QUEUED, queued, &common_tags)
+            .send();
+    }
+
+    if let Some(breaker_stats) = stats.circuit_breaker_snapshot() {
+        emit_circuit_breaker_stats(
+            client,
+            breaker_stats,
+            &mut snap.circuit_breaker_tripped,
+            &common_tags,
+        );
+    }
+
+    if let Some(rtt_list) = stats.upstream_rtt_snapshot() {
+        for (upstream, rtt) in rtt_list {
+            client
+                .gauge_with_tags(METRIC_NAME_ESCAPER_UPSTREAM_RTT, rtt, &common_tags)
+                .with_tag(TAG_KEY_UPSTREAM, upstream.to_string())
+                .send();
+        }
+    }
+
+    if let Some(tunnel_list) = stats.upstream_tunnel_snapshot() {
+        for (upstream, alive) in tunnel_list {
+            client
+                .gauge_with_tags(
+                    METRIC_NAME_ESCAPER_UPSTREAM_TUNNEL_ALIVE,
+                    alive,
+                    &common_tags,
+                )
+                .with_tag(TAG_KEY_UPSTREAM, upstream.to_string())
+                .send();
+        }
+    }
+
+    if let Some(select_list) = stats.upstream_select_snapshot() {
+        let mut new_selected = AHashMap::with_capacity(select_list.len());
+        for (upstream, weight, selected) in select_list {
+            let old = snap.upstream_selected.get(&upstream).copied().unwrap_or(0);
+            client
+                .count_with_tags(
+                    METRIC_NAME_ESCAPER_UPSTREAM_SELECTED,
+                    selected.wrapping_sub(old),
+                    &common_tags,
+                )
+                .with_tag(TAG_KEY_UPSTREAM, upstream.to_string())
+                .with_tag(TAG_KEY_WEIGHT, weight.to_string())
+                .send();
+            new_selected.insert(upstream, selected);
+        }
+        snap.upstream_selected = new_selected;
+    }
+
+    if let Some(agent_list) = stats.upstream_agent_snapshot() {
+        for (upstream, agent, version) in agent_list {
+            client
+                .gauge_with_tags(METRIC_NAME_ESCAPER_UPSTREAM_AGENT, 1, &common_tags)
+                .with_tag(TAG_KEY_UPSTREAM, upstream.to_string())
+                .with_tag(TAG_KEY_AGENT, agent)
+                .with_tag(
+                    TAG_KEY_AGENT_VERSION,
+                    version.as_deref().unwrap_or("unknown"),
+                )
+                .send();
+        }
+    }
+
     if let Some(tcp_io_stats) = stats.tcp_io_snapshot() {
         emit_tcp_io_to_statsd(client, tcp_io_stats, &mut snap.tcp, &common_tags);
     }
@@ -203,6 +403,42 @@ fn emit_tcp_connect_stats(
     emit_optional_field!(success, METRIC_NAME_ESCAPER_TCP_CONNECT_SUCCESS);
     emit_optional_field!(error, METRIC_NAME_ESCAPER_TCP_CONNECT_ERROR);
     emit_optional_field!(timeout, METRIC_NAME_ESCAPER_TCP_CONNECT_TIMEOUT);
+    emit_optional_field!(
+        mptcp_establish,
+        METRIC_NAME_ESCAPER_TCP_CONNECT_MPTCP_ESTABLISH
+    );
+    emit_optional_field!(
+        mptcp_fallback,
+        METRIC_NAME_ESCAPER_TCP_CONNECT_MPTCP_FALLBACK
+    );
+    emit_optional_field!(
+        happy_ipv4_establish,
+        METRIC_NAME_ESCAPER_TCP_CONNECT_HAPPY_IPV4_ESTABLISH
+    );
+    emit_optional_field!(
+        happy_ipv6_establish,
+        METRIC_NAME_ESCAPER_TCP_CONNECT_HAPPY_IPV6_ESTABLISH
+    );
+    emit_optional_field!(
+        preferred_addr_hit,
+        METRIC_NAME_ESCAPER_TCP_CONNECT_PREFERRED_ADDR_HIT
+    );
+    emit_optional_field!(
+        preferred_addr_miss,
+        METRIC_NAME_ESCAPER_TCP_CONNECT_PREFERRED_ADDR_MISS
+    );
+    emit_optional_field!(
+        negotiation_retry,
+        METRIC_NAME_ESCAPER_TCP_CONNECT_NEGOTIATION_RETRY
+    );
+    emit_optional_field!(
+        unexpected_rsp_body,
+        METRIC_NAME_ESCAPER_TCP_CONNECT_UNEXPECTED_RSP_BODY
+    );
+    emit_optional_field!(
+        local_port_exhausted,
+        METRIC_NAME_ESCAPER_TCP_CONNECT_LOCAL_PORT_EXHAUSTED
+    );
 }
 
 fn emit_tls_stats(
@@ -227,6 +463,59 @@ fn emit_tls_stats(
     emit_optional_field!(handshake_success, METRIC_NAME_ESCAPER_TLS_HANDSHAKE_SUCCESS);
     emit_optional_field!(handshake_error, METRIC_NAME_ESCAPER_TLS_HANDSHAKE_ERROR);
     emit_optional_field!(handshake_timeout, METRIC_NAME_ESCAPER_TLS_HANDSHAKE_TIMEOUT);
+    emit_optional_field!(
+        early_data_accepted,
+        METRIC_NAME_ESCAPER_TLS_EARLY_DATA_ACCEPTED
+    );
+    emit_optional_field!(
+        early_data_rejected,
+        METRIC_NAME_ESCAPER_TLS_EARLY_DATA_REJECTED
+    );
+}
+
+fn emit_connect_error_stats(
+    client: &mut StatsdClient,
+    stats: EscaperConnectErrorSnapshot,
+    snap: &mut EscaperConnectErrorSnapshot,
+    common_tags: &StatsdTagGroup,
+) {
+    macro_rules! emit_optional_field {
+        ($field:ident, $name:expr) => {
+            let new_value = stats.$field;
+            if new_value != 0 || snap.$field != 0 {
+                let diff_value = new_value.wrapping_sub(snap.$field);
+                client
+                    .count_with_tags($name, diff_value, common_tags)
+                    .send();
+                snap.$field = new_value;
+            }
+        };
+    }
+
+    emit_optional_field!(
+        resolve_failed,
+        METRIC_NAME_ESCAPER_CONNECT_ERROR_RESOLVE_FAILED
+    );
+    emit_optional_field!(
+        connect_refused,
+        METRIC_NAME_ESCAPER_CONNECT_ERROR_CONNECT_REFUSED
+    );
+    emit_optional_field!(
+        connect_timeout,
+        METRIC_NAME_ESCAPER_CONNECT_ERROR_CONNECT_TIMEOUT
+    );
+    emit_optional_field!(
+        negotiation_timeout,
+        METRIC_NAME_ESCAPER_CONNECT_ERROR_NEGOTIATION_TIMEOUT
+    );
+    emit_optional_field!(
+        tls_handshake_failed,
+        METRIC_NAME_ESCAPER_CONNECT_ERROR_TLS_HANDSHAKE_FAILED
+    );
+    emit_optional_field!(
+        tls_handshake_timeout,
+        METRIC_NAME_ESCAPER_CONNECT_ERROR_TLS_HANDSHAKE_TIMEOUT
+    );
 }
 
 fn emit_forbidden_stats(
@@ -247,6 +536,135 @@ fn emit_forbidden_stats(
             .send();
         snap.ip_blocked = new_value;
     }
+
+    let new_value = stats.asn_blocked;
+    if new_value != 0 || snap.asn_blocked != 0 {
+        let diff_value = new_value.wrapping_sub(snap.asn_blocked);
+        client
+            .count_with_tags(
+                METRIC_NAME_ESCAPER_FORBIDDEN_ASN_BLOCKED,
+                diff_value,
+                common_tags,
+            )
+            .send();
+        snap.asn_blocked = new_value;
+    }
+
+    let new_value = stats.ip_locate_failed_permitted;
+    if new_value != 0 || snap.ip_locate_failed_permitted != 0 {
+        let diff_value = new_value.wrapping_sub(snap.ip_locate_failed_permitted);
+        client
+            .count_with_tags(
+                METRIC_NAME_ESCAPER_FORBIDDEN_IP_LOCATE_FAILED_PERMITTED,
+                diff_value,
+                common_tags,
+            )
+            .send();
+        snap.ip_locate_failed_permitted = new_value;
+    }
+
+    let new_value = stats.ip_locate_failed_forbidden;
+    if new_value != 0 || snap.ip_locate_failed_forbidden != 0 {
+        let diff_value = new_value.wrapping_sub(snap.ip_locate_failed_forbidden);
+        client
+            .count_with_tags(
+                METRIC_NAME_ESCAPER_FORBIDDEN_IP_LOCATE_FAILED_FORBIDDEN,
+                diff_value,
+                common_tags,
+            )
+            .send();
+        snap.ip_locate_failed_forbidden = new_value;
+    }
+}
+
+fn emit_circuit_breaker_stats(
+    client: &mut StatsdClient,
+    stats: EscaperCircuitBreakerSnapshot,
+    snap_tripped: &mut u64,
+    common_tags: &StatsdTagGroup,
+) {
+    client
+        .gauge_with_tags(
+            METRIC_NAME_ESCAPER_CIRCUIT_BREAKER_STATE,
+            stats.state,
+            common_tags,
+        )
+        .send();
+
+    let diff_value = stats.tripped.wrapping_sub(*snap_tripped);
+    client
+        .count_with_tags(
+            METRIC_NAME_ESCAPER_CIRCUIT_BREAKER_TRIPPED,
+            diff_value,
+            common_tags,
+        )
+        .send();
+    *snap_tripped = stats.tripped;
+}
+
+fn emit_peer_health_stats(
+    client: &mut StatsdClient,
+    stats: EscaperPeerHealthSnapshot,
+    common_tags: &StatsdTagGroup,
+) {
+    client
+        .gauge_with_tags(METRIC_NAME_ESCAPER_PEER_HEALTHY, stats.healthy, common_tags)
+        .send();
+    client
+        .gauge_with_tags(
+            METRIC_NAME_ESCAPER_PEER_UNHEALTHY,
+            stats.unhealthy,
+            common_tags,
+        )
+        .send();
+}
+
+fn emit_peer_feed_stats(
+    client: &mut StatsdClient,
+    stats: EscaperPeerFeedSnapshot,
+    snap: &mut EscaperPeerFeedSnapshot,
+    common_tags: &StatsdTagGroup,
+) {
+    client
+        .gauge_with_tags(
+            METRIC_NAME_ESCAPER_PEER_FEED_PEER_COUNT,
+            stats.peer_count,
+            common_tags,
+        )
+        .send();
+
+    let diff_value = stats.refresh_success.wrapping_sub(snap.refresh_success);
+    client
+        .count_with_tags(
+            METRIC_NAME_ESCAPER_PEER_FEED_REFRESH_SUCCESS,
+            diff_value,
+            common_tags,
+        )
+        .send();
+    snap.refresh_success = stats.refresh_success;
+
+    let diff_value = stats.refresh_failure.wrapping_sub(snap.refresh_failure);
+    client
+        .count_with_tags(
+            METRIC_NAME_ESCAPER_PEER_FEED_REFRESH_FAILURE,
+            diff_value,
+            common_tags,
+        )
+        .send();
+    snap.refresh_failure = stats.refresh_failure;
+}
+
+fn emit_peer_expire_stats(
+    client: &mut StatsdClient,
+    stats: EscaperPeerExpireSnapshot,
+    common_tags: &StatsdTagGroup,
+) {
+    client
+        .gauge_with_tags(METRIC_NAME_ESCAPER_PEER_ALIVE, stats.alive, common_tags)
+        .send();
+    client
+        .gauge_with_tags(METRIC_NAME_ESCAPER_PEER_EXPIRED, stats.expired, common_tags)
+        .send();
 }
 
 fn emit_tcp_io_to_statsd(
@@ -329,3 +747,65 @@ fn emit_route_stats(
         snap.request_failed = new_value;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reachability() {
+        let mut snap = EscaperSnapshot::default();
+        let start = Instant::now();
+
+        snap.update_reachability(1, 0, start);
+        assert!(!snap.is_unreachable(start));
+
+        // short idle cycles should neither clear nor restart the failing state
+        let t = start + Duration::from_secs(5);
+        snap.update_reachability(0, 0, t);
+        assert!(!snap.is_unreachable(t));
+        let t = start + Duration::from_secs(8);
+        snap.update_reachability(1, 0, t);
+        assert!(!snap.is_unreachable(t));
+        let t = start + UNREACHABLE_DURATION;
+        snap.update_reachability(0, 0, t);
+        assert!(snap.is_unreachable(t));
+        let t = t + Duration::from_secs(1);
+        snap.update_reachability(1, 0, t);
+        assert!(snap.is_unreachable(t));
+
+        snap.update_reachability(1, 1, t);
+        assert!(!snap.is_unreachable(t + UNREACHABLE_DURATION));
+    }
+
+    #[test]
+    fn reachability_decay() {
+        let mut snap = EscaperSnapshot::default();
+        let start = Instant::now();
+
+        // a single failed attempt followed by no traffic
+        snap.update_reachability(1, 0, start);
+        let t = start + Duration::from_secs(5);
+        snap.update_reachability(0, 0, t);
+        assert!(!snap.is_unreachable(t));
+        let t = start + UNREACHABLE_DURATION;
+        snap.update_reachability(0, 0, t);
+        assert!(!snap.is_unreachable(t));
+        let t = t + Duration::from_secs(60);
+        snap.update_reachability(0, 0, t);
+        assert!(!snap.is_unreachable(t));
+
+        // keep failing then go idle
+        let start = t;
+        snap.update_reachability(1, 0, start);
+        let t = start + UNREACHABLE_DURATION;
+        snap.update_reachability(1, 0, t);
+        assert!(snap.is_unreachable(t));
+        let t = t + Duration::from_secs(5);
+        snap.update_reachability(0, 0, t);
+        assert!(snap.is_unreachable(t));
+        let t = start + UNREACHABLE_DURATION * 2;
+        snap.update_reachability(0, 0, t);
+        assert!(!snap.is_unreachable(t));
+    }
+}
@@ -0,0 +1,53 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use g3_statsd_client::StatsdClient;
+
+const METRIC_NAME_DAEMON_IP_LOCATION_STATIC_TABLE_ENTRIES: &str =
+    "daemon.ip_location.static_table.entries";
+const METRIC_NAME_DAEMON_IP_LOCATION_CACHE_NEGATIVE_HIT: &str =
+    "daemon.ip_location.cache.negative_hit";
+const METRIC_NAME_DAEMON_IP_LOCATION_QUERY_IN_FLIGHT: &str = "daemon.ip_location.query.in_flight";
+const METRIC_NAME_DAEMON_IP_LOCATION_QUERY_QUEUED: &str = "daemon.ip_location.query.queued";
+
+static NEGATIVE_CACHE_HITS_SNAPSHOT: AtomicU64 = AtomicU64::new(0);
+
+pub(in crate::stat) fn emit_stats(client: &mut StatsdClient) {
+    let entries = g3_ip_locate::static_table_entry_count();
+    client
+        .gauge(METRIC_NAME_DAEMON_IP_LOCATION_STATIC_TABLE_ENTRIES, entries)
+        .send();
+
+    let negative_hits = g3_ip_locate::negative_cache_hits();
+    let last_negative_hits = NEGATIVE_CACHE_HITS_SNAPSHOT.swap(negative_hits, Ordering::Relaxed);
+    client
+        .count(
+            METRIC_NAME_DAEMON_IP_LOCATION_CACHE_NEGATIVE_HIT,
+            negative_hits.wrapping_sub(last_negative_hits),
+        )
+        .send();
+
+    let in_flight = g3_ip_locate::query_in_flight_count();
+    client
+        .gauge(METRIC_NAME_DAEMON_IP_LOCATION_QUERY_IN_FLIGHT, in_flight)
+        .send();
+    let queued = g3_ip_locate::query_queued_count();
+    client
+        .gauge(METRIC_NAME_DAEMON_IP_LOCATION_QUERY_QUEUED, queued)
+        .send();
+}
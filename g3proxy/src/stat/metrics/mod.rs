@@ -15,8 +15,11 @@
  */
 
 pub(super) mod escaper;
+pub(super) mod ip_location;
 pub(super) mod resolver;
 pub(super) mod server;
+pub(super) mod tls_cert;
+pub(super) mod tls_ticket;
 
 pub(super) mod user;
 use user::{RequestStatsNamesRef, TrafficStatsNamesRef, UserMetricExt};
@@ -15,21 +15,26 @@
  */
 
 use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Instant;
 
-use ahash::AHashMap;
+use ahash::{AHashMap, AHashSet};
 
 use g3_daemon::listen::{ListenSnapshot, ListenStats};
 use g3_daemon::metrics::{
     ServerMetricExt, TAG_KEY_TRANSPORT, TRANSPORT_TYPE_TCP, TRANSPORT_TYPE_UDP,
 };
 use g3_statsd_client::{StatsdClient, StatsdTagGroup};
+use g3_types::metrics::NodeName;
 use g3_types::stats::{StatId, TcpIoSnapshot, UdpIoSnapshot};
 
+use super::user::{TAG_KEY_USER, TAG_KEY_USER_GROUP};
 use crate::serve::{ArcServerStats, ServerForbiddenSnapshot};
 use crate::stat::types::UntrustedTaskStatsSnapshot;
 
 const METRIC_NAME_SERVER_CONN_TOTAL: &str = "server.connection.total";
+const METRIC_NAME_SERVER_CONN_RATE: &str = "server.connection.rate";
 const METRIC_NAME_SERVER_TASK_TOTAL: &str = "server.task.total";
+const METRIC_NAME_SERVER_TASK_RATE: &str = "server.task.rate";
 const METRIC_NAME_SERVER_TASK_ALIVE: &str = "server.task.alive";
 const METRIC_NAME_SERVER_FORBIDDEN_AUTH_FAILED: &str = "server.forbidden.auth_failed";
 const METRIC_NAME_SERVER_FORBIDDEN_DEST_DENIED: &str = "server.forbidden.dest_denied";
@@ -38,9 +43,22 @@ const METRIC_NAME_SERVER_IO_IN_BYTES: &str = "server.traffic.in.bytes";
 const METRIC_NAME_SERVER_IO_IN_PACKETS: &str = "server.traffic.in.packets";
 const METRIC_NAME_SERVER_IO_OUT_BYTES: &str = "server.traffic.out.bytes";
 const METRIC_NAME_SERVER_IO_OUT_PACKETS: &str = "server.traffic.out.packets";
+const METRIC_NAME_SERVER_IO_IN_BYTES_RATE: &str = "server.traffic.in.bytes_rate";
+const METRIC_NAME_SERVER_IO_OUT_BYTES_RATE: &str = "server.traffic.out.bytes_rate";
 const METRIC_NAME_SERVER_UNTRUSTED_TASK_TOTAL: &str = "server.task.untrusted_total";
 const METRIC_NAME_SERVER_UNTRUSTED_TASK_ALIVE: &str = "server.task.untrusted_alive";
 const METRIC_NAME_SERVER_IO_UNTRUSTED_IN_BYTES: &str = "server.traffic.untrusted_in.bytes";
+const METRIC_NAME_SERVER_USER_TASK_TOTAL: &str = "server.user.task.total";
+const METRIC_NAME_SERVER_USER_IO_IN_BYTES: &str = "server.user.traffic.in.bytes";
+const METRIC_NAME_SERVER_USER_IO_OUT_BYTES: &str = "server.user.traffic.out.bytes";
+const METRIC_NAME_DAEMON_STATS_SERVER_ENTRIES: &str = "daemon.stats.server.entries";
+const METRIC_NAME_DAEMON_STATS_SERVER_GC: &str = "daemon.stats.server.gc";
+const METRIC_NAME_DAEMON_STATS_LISTEN_ENTRIES: &str = "daemon.stats.listen.entries";
+const METRIC_NAME_DAEMON_STATS_LISTEN_GC: &str = "daemon.stats.listen.gc";
+
+// use a dedicated tag key so the rolled up bucket will never collide with a real user name
+const TAG_KEY_USER_ROLLUP: &str = "user_rollup";
+const TAG_VALUE_USER_ROLLUP_OTHERS: &str = "others";
 
 type ServerStatsValue = (ArcServerStats, ServerSnapshot);
 type ListenStatsValue = (Arc<ListenStats>, ListenSnapshot);
@@ -58,6 +76,89 @@ struct ServerSnapshot {
     tcp: TcpIoSnapshot,
     udp: UdpIoSnapshot,
     untrusted: UntrustedTaskStatsSnapshot,
+    last_emit: Option<Instant>,
+    escaper: NodeName,
+}
+
+#[derive(Default)]
+pub(super) struct ServerUserUsage {
+    pub(super) task_total: u64,
+    pub(super) in_bytes: u64,
+    pub(super) out_bytes: u64,
+}
+
+impl ServerUserUsage {
+    fn volume(&self) -> u64 {
+        self.in_bytes.wrapping_add(self.out_bytes)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.task_total == 0 && self.in_bytes == 0 && self.out_bytes == 0
+    }
+
+    fn merge(&mut self, other: &ServerUserUsage) {
+        self.task_total = self.task_total.wrapping_add(other.task_total);
+        self.in_bytes = self.in_bytes.wrapping_add(other.in_bytes);
+        self.out_bytes = self.out_bytes.wrapping_add(other.out_bytes);
+    }
+}
+
+type ServerUserUsageMap = AHashMap<(NodeName, String), ServerUserUsage>;
+
+/// Collect the per cycle usage of users on servers with top-k user metrics enabled
+pub(super) struct ServerUserUsageCollector {
+    servers: AHashSet<NodeName>,
+    usage: AHashMap<NodeName, ServerUserUsageMap>,
+}
+
+impl ServerUserUsageCollector {
+    pub(super) fn new() -> Self {
+        let mut servers = AHashSet::new();
+        let server_stats_map = SERVER_STATS_MAP.lock().unwrap();
+        for (stats, _) in server_stats_map.values() {
+            if stats.user_metrics_top_k() > 0 {
+                servers.insert(stats.name().clone());
+            }
+        }
+        ServerUserUsageCollector {
+            servers,
+            usage: AHashMap::new(),
+        }
+    }
+
+    pub(super) fn is_enabled(&self, server: &NodeName) -> bool {
+        self.servers.contains(server)
+    }
+
+    pub(super) fn user_mut(
+        &mut self,
+        server: &NodeName,
+        user_group: &NodeName,
+        user: &str,
+    ) -> &mut ServerUserUsage {
+        self.usage
+            .entry(server.clone())
+            .or_default()
+            .entry((user_group.clone(), user.to_string()))
+            .or_default()
+    }
+
+    pub(super) fn emit(mut self, client: &mut StatsdClient) {
+        if self.usage.is_empty() {
+            return;
+        }
+
+        let server_stats_map = SERVER_STATS_MAP.lock().unwrap();
+        for (stats, _) in server_stats_map.values() {
+            let top_k = stats.user_metrics_top_k();
+            if top_k == 0 {
+                continue;
+            }
+            if let Some(user_map) = self.usage.remove(stats.name()) {
+                emit_server_user_stats(client, stats, top_k, user_map);
+            }
+        }
+    }
 }
 
 pub(in crate::stat) fn sync_stats() {
@@ -65,9 +166,13 @@ pub(in crate::stat) fn sync_stats() {
     crate::serve::foreach_server(|_, server| {
         if let Some(stats) = server.get_server_stats() {
             let stat_id = stats.stat_id();
-            server_stats_map
+            let (_, snap) = server_stats_map
                 .entry(stat_id)
                 .or_insert_with(|| (stats, ServerSnapshot::default()));
+            if snap.escaper != *server.escaper() {
+                // the escaper may be changed at runtime
+                snap.escaper = server.escaper().clone();
+            }
         }
     });
     drop(server_stats_map);
@@ -83,35 +188,79 @@ pub(in crate::stat) fn sync_stats() {
     drop(listen_stats_map);
 }
 
-pub(in crate::stat) fn emit_stats(client: &mut StatsdClient) {
+pub(in crate::stat) fn emit_stats(client: &mut StatsdClient, emit_rate: bool) {
     let mut server_stats_map = SERVER_STATS_MAP.lock().unwrap();
+    let mut server_gc_count = 0usize;
     server_stats_map.retain(|_, (stats, snap)| {
-        emit_server_stats(client, stats, snap);
+        emit_server_stats(client, stats, snap, emit_rate);
         // use Arc instead of Weak here, as we should emit the final metrics before drop it
-        Arc::strong_count(stats) > 1
+        if Arc::strong_count(stats) > 1 {
+            true
+        } else {
+            server_gc_count += 1;
+            false
+        }
     });
+    let server_entries = server_stats_map.len();
     drop(server_stats_map);
 
     let mut listen_stats_map = LISTEN_STATS_MAP.lock().unwrap();
+    let mut listen_gc_count = 0usize;
     listen_stats_map.retain(|_, (stats, snap)| {
         g3_daemon::metrics::emit_listen_stats(client, stats, snap);
         // use Arc instead of Weak here, as we should emit the final metrics before drop it
-        Arc::strong_count(stats) > 1
+        if Arc::strong_count(stats) > 1 {
+            true
+        } else {
+            listen_gc_count += 1;
+            false
+        }
     });
+    let listen_entries = listen_stats_map.len();
+    drop(listen_stats_map);
+
+    client
+        .gauge(METRIC_NAME_DAEMON_STATS_SERVER_ENTRIES, server_entries)
+        .send();
+    client
+        .count(METRIC_NAME_DAEMON_STATS_SERVER_GC, server_gc_count)
+        .send();
+    client
+        .gauge(METRIC_NAME_DAEMON_STATS_LISTEN_ENTRIES, listen_entries)
+        .send();
+    client
+        .count(METRIC_NAME_DAEMON_STATS_LISTEN_GC, listen_gc_count)
+        .send();
 }
 
-fn emit_server_stats(client: &mut StatsdClient, stats: &ArcServerStats, snap: &mut ServerSnapshot) {
+fn emit_server_stats(
+    client: &mut StatsdClient,
+    stats: &ArcServerStats,
+    snap: &mut ServerSnapshot,
+    emit_rate: bool,
+) {
     let mut common_tags = StatsdTagGroup::default();
     common_tags.add_server_tags(stats.name(), stats.is_online(), stats.stat_id());
     if let Some(tags) = stats.load_extra_tags() {
         common_tags.add_static_tags(&tags);
     }
 
+    let rate_secs = g3_daemon::stat::emit::rate_elapsed_secs(emit_rate, &mut snap.last_emit);
+
     let new_value = stats.get_conn_total();
     let diff_value = new_value.wrapping_sub(snap.conn_total);
     client
         .count_with_tags(METRIC_NAME_SERVER_CONN_TOTAL, diff_value, &common_tags)
         .send();
+    if let Some(secs) = rate_secs {
+        client
+            .gauge_float_with_tags(
+                METRIC_NAME_SERVER_CONN_RATE,
+                diff_value as f64 / secs,
+                &common_tags,
+            )
+            .send();
+    }
     snap.conn_total = new_value;
 
     let new_value = stats.get_task_total();
@@ -119,15 +268,27 @@ fn emit_server_stats(client: &mut StatsdClient, stats: &ArcServerStats, snap: &m
     client
         .count_with_tags(METRIC_NAME_SERVER_TASK_TOTAL, diff_value, &common_tags)
         .send();
+    if let Some(secs) = rate_secs {
+        client
+            .gauge_float_with_tags(
+                METRIC_NAME_SERVER_TASK_RATE,
+                diff_value as f64 / secs,
+                &common_tags,
+            )
+            .send();
+    }
     snap.task_total = new_value;
 
-    client
-        .gauge_with_tags(
-            METRIC_NAME_SERVER_TASK_ALIVE,
-            stats.get_alive_count(),
-            &common_tags,
-        )
-        .send();
+    // act as a dead man's switch, so absence of data alerts can be set on this metric
+    if !(stats.dead_mans_switch() && super::escaper::is_unreachable(&snap.escaper)) {
+        client
+            .gauge_with_tags(
+                METRIC_NAME_SERVER_TASK_ALIVE,
+                stats.get_alive_count(),
+                &common_tags,
+            )
+            .send();
+    }
 
     emit_forbidden_stats(
         client,
@@ -137,11 +298,11 @@ fn emit_server_stats(client: &mut StatsdClient, stats: &ArcServerStats, snap: &m
     );
 
     if let Some(tcp_io_stats) = stats.tcp_io_snapshot() {
-        emit_tcp_io_to_statsd(client, tcp_io_stats, &mut snap.tcp, &common_tags);
+        emit_tcp_io_to_statsd(client, tcp_io_stats, &mut snap.tcp, &common_tags, rate_secs);
     }
 
     if let Some(udp_io_stats) = stats.udp_io_snapshot() {
-        emit_udp_io_to_statsd(client, udp_io_stats, &mut snap.udp, &common_tags);
+        emit_udp_io_to_statsd(client, udp_io_stats, &mut snap.udp, &common_tags, rate_secs);
     }
 
     if let Some(untrusted_stats) = stats.untrusted_snapshot() {
@@ -178,6 +339,7 @@ fn emit_tcp_io_to_statsd(
     stats: TcpIoSnapshot,
     snap: &mut TcpIoSnapshot,
     common_tags: &StatsdTagGroup,
+    rate_secs: Option<f64>,
 ) {
     if stats.in_bytes == 0 && snap.in_bytes == 0 {
         return;
@@ -193,10 +355,33 @@ fn emit_tcp_io_to_statsd(
                 .send();
             snap.$field = new_value;
         };
+        ($field:ident, $name:expr, $rate_name:expr) => {
+            let new_value = stats.$field;
+            let diff_value = new_value.wrapping_sub(snap.$field);
+            client
+                .count_with_tags($name, diff_value, common_tags)
+                .with_tag(TAG_KEY_TRANSPORT, TRANSPORT_TYPE_TCP)
+                .send();
+            if let Some(secs) = rate_secs {
+                client
+                    .gauge_float_with_tags($rate_name, diff_value as f64 / secs, common_tags)
+                    .with_tag(TAG_KEY_TRANSPORT, TRANSPORT_TYPE_TCP)
+                    .send();
+            }
+            snap.$field = new_value;
+        };
     }
 
-    emit_field!(in_bytes, METRIC_NAME_SERVER_IO_IN_BYTES);
-    emit_field!(out_bytes, METRIC_NAME_SERVER_IO_OUT_BYTES);
+    emit_field!(
+        in_bytes,
+        METRIC_NAME_SERVER_IO_IN_BYTES,
+        METRIC_NAME_SERVER_IO_IN_BYTES_RATE
+    );
+    emit_field!(
+        out_bytes,
+        METRIC_NAME_SERVER_IO_OUT_BYTES,
+        METRIC_NAME_SERVER_IO_OUT_BYTES_RATE
+    );
 }
 
 fn emit_udp_io_to_statsd(
@@ -204,6 +389,7 @@ fn emit_udp_io_to_statsd(
     stats: UdpIoSnapshot,
     snap: &mut UdpIoSnapshot,
     common_tags: &StatsdTagGroup,
+    rate_secs: Option<f64>,
 ) {
     if stats.in_packets == 0 && snap.in_packets == 0 {
         return;
@@ -219,12 +405,35 @@ fn emit_udp_io_to_statsd(
                 .send();
             snap.$field = new_value;
         };
+        ($field:ident, $name:expr, $rate_name:expr) => {
+            let new_value = stats.$field;
+            let diff_value = new_value.wrapping_sub(snap.$field);
+            client
+                .count_with_tags($name, diff_value, common_tags)
+                .with_tag(TAG_KEY_TRANSPORT, TRANSPORT_TYPE_UDP)
+                .send();
+            if let Some(secs) = rate_secs {
+                client
+                    .gauge_float_with_tags($rate_name, diff_value as f64 / secs, common_tags)
+                    .with_tag(TAG_KEY_TRANSPORT, TRANSPORT_TYPE_UDP)
+                    .send();
+            }
+            snap.$field = new_value;
+        };
     }
 
     emit_field!(in_packets, METRIC_NAME_SERVER_IO_IN_PACKETS);
-    emit_field!(in_bytes, METRIC_NAME_SERVER_IO_IN_BYTES);
+    emit_field!(
+        in_bytes,
+        METRIC_NAME_SERVER_IO_IN_BYTES,
+        METRIC_NAME_SERVER_IO_IN_BYTES_RATE
+    );
     emit_field!(out_packets, METRIC_NAME_SERVER_IO_OUT_PACKETS);
-    emit_field!(out_bytes, METRIC_NAME_SERVER_IO_OUT_BYTES);
+    emit_field!(
+        out_bytes,
+        METRIC_NAME_SERVER_IO_OUT_BYTES,
+        METRIC_NAME_SERVER_IO_OUT_BYTES_RATE
+    );
 }
 
 fn emit_untrusted_stats(
@@ -267,3 +476,58 @@ fn emit_untrusted_stats(
         .send();
     snap.in_bytes = new_value;
 }
+
+fn emit_server_user_stats(
+    client: &mut StatsdClient,
+    stats: &ArcServerStats,
+    top_k: usize,
+    user_map: ServerUserUsageMap,
+) {
+    let mut users: Vec<_> = user_map
+        .into_iter()
+        .filter(|(_, usage)| !usage.is_empty())
+        .collect();
+    if users.is_empty() {
+        return;
+    }
+    users.sort_unstable_by(|(_, a), (_, b)| {
+        b.volume()
+            .cmp(&a.volume())
+            .then(b.task_total.cmp(&a.task_total))
+    });
+
+    let mut common_tags = StatsdTagGroup::default();
+    common_tags.add_server_tags(stats.name(), stats.is_online(), stats.stat_id());
+    if let Some(tags) = stats.load_extra_tags() {
+        common_tags.add_static_tags(&tags);
+    }
+
+    let mut emit_usage = |usage: &ServerUserUsage, tags: &StatsdTagGroup| {
+        client
+            .count_with_tags(METRIC_NAME_SERVER_USER_TASK_TOTAL, usage.task_total, tags)
+            .send();
+        client
+            .count_with_tags(METRIC_NAME_SERVER_USER_IO_IN_BYTES, usage.in_bytes, tags)
+            .send();
+        client
+            .count_with_tags(METRIC_NAME_SERVER_USER_IO_OUT_BYTES, usage.out_bytes, tags)
+            .send();
+    };
+
+    let mut other = ServerUserUsage::default();
+    for (i, ((user_group, user), usage)) in users.iter().enumerate() {
+        if i < top_k {
+            let mut tags = common_tags.clone();
+            tags.add_tag(TAG_KEY_USER_GROUP, user_group);
+            tags.add_tag(TAG_KEY_USER, user);
+            emit_usage(usage, &tags);
+        } else {
+            other.merge(usage);
+        }
+    }
+    if !other.is_empty() {
+        let mut tags = common_tags;
+        tags.add_tag(TAG_KEY_USER_ROLLUP, TAG_VALUE_USER_ROLLUP_OTHERS);
+        emit_usage(&other, &tags);
+    }
+}
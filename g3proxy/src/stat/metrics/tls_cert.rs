@@ -0,0 +1,186 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::{HashMap, HashSet};
+use std::mem;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+
+use g3_daemon::metrics::TAG_KEY_SERVER;
+use g3_statsd_client::{StatsdClient, StatsdTagGroup};
+use g3_types::metrics::NodeName;
+
+use crate::config::server::AnyServerConfig;
+use crate::serve::{ArcServer, Server, ServerInternal};
+
+const METRIC_NAME_SERVER_TLS_CERT_EXPIRE_IN: &str = "server.tls.cert.expire_in";
+
+const TAG_KEY_CERT: &str = "cert";
+
+const CERT_TAG_FINGERPRINT_LENGTH: usize = 16;
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+struct CertExpiry {
+    cert: String,
+    not_after: i64,
+}
+
+struct ServerCerts {
+    server: Weak<dyn Server + Send + Sync>,
+    certs: Vec<CertExpiry>,
+}
+
+impl ServerCerts {
+    fn new(server: &ArcServer) -> Self {
+        ServerCerts {
+            server: Arc::downgrade(server),
+            certs: load_certs(&server._clone_config()),
+        }
+    }
+}
+
+fn load_certs(config: &AnyServerConfig) -> Vec<CertExpiry> {
+    let mut certs = Vec::new();
+    let mut fingerprints = HashSet::new();
+    for builder in config.rustls_server_configs() {
+        for pair in builder.cert_pairs() {
+            let Some(fingerprint) = pair.fingerprint() else {
+                continue;
+            };
+            let Ok(not_after) = pair.not_after_timestamp() else {
+                continue;
+            };
+            let cert = fingerprint[..CERT_TAG_FINGERPRINT_LENGTH].to_string();
+            if fingerprints.insert(cert.clone()) {
+                certs.push(CertExpiry { cert, not_after });
+            }
+        }
+    }
+    certs
+}
+
+struct CertExpiryState {
+    refresh_at: Option<Instant>,
+    servers: HashMap<NodeName, ServerCerts>,
+}
+
+impl CertExpiryState {
+    fn refresh(&mut self) {
+        let mut servers: Vec<(NodeName, ArcServer)> = Vec::new();
+        crate::serve::foreach_server(|name, server| {
+            servers.push((name.clone(), server.clone()));
+        });
+
+        // the config will only be loaded again if the server is reloaded
+        let mut old_servers = mem::take(&mut self.servers);
+        for (name, server) in servers {
+            let certs = match old_servers.remove(&name) {
+                Some(certs) if certs.server.ptr_eq(&Arc::downgrade(&server)) => certs,
+                _ => ServerCerts::new(&server),
+            };
+            self.servers.insert(name, certs);
+        }
+    }
+}
+
+static CERT_EXPIRY_STATE: Mutex<Option<CertExpiryState>> = Mutex::new(None);
+
+pub(in crate::stat) fn emit_stats(client: &mut StatsdClient) {
+    let mut guard = CERT_EXPIRY_STATE.lock().unwrap();
+    let state = guard.get_or_insert_with(|| CertExpiryState {
+        refresh_at: None,
+        servers: HashMap::new(),
+    });
+
+    let now = Instant::now();
+    if state.refresh_at.map(|t| t <= now).unwrap_or(true) {
+        state.refresh();
+        state.refresh_at = Some(now + REFRESH_INTERVAL);
+    }
+
+    let timestamp = Utc::now().timestamp();
+    for (server, certs) in &state.servers {
+        emit_server_certs(client, server, &certs.certs, timestamp);
+    }
+}
+
+fn emit_server_certs(
+    client: &mut StatsdClient,
+    server: &NodeName,
+    certs: &[CertExpiry],
+    timestamp: i64,
+) {
+    for cert in certs {
+        let mut tags = StatsdTagGroup::default();
+        tags.add_tag(TAG_KEY_SERVER, server);
+        tags.add_tag(TAG_KEY_CERT, &cert.cert);
+        client
+            .gauge_with_tags(
+                METRIC_NAME_SERVER_TLS_CERT_EXPIRE_IN,
+                cert.not_after - timestamp,
+                &tags,
+            )
+            .send();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{SocketAddr, UdpSocket};
+    use std::str::FromStr;
+
+    use g3_statsd_client::{StatsdBackend, StatsdClientConfig};
+
+    #[test]
+    fn emit_cert_expiry() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+        let addr: SocketAddr = socket.local_addr().unwrap();
+
+        let mut config = StatsdClientConfig::default();
+        config.set_backend(StatsdBackend::Udp(addr, None));
+        let mut client = config.build().unwrap();
+
+        let server = NodeName::from_str("test").unwrap();
+        let certs = vec![
+            CertExpiry {
+                cert: "0123456789abcdef".to_string(),
+                not_after: 1000,
+            },
+            CertExpiry {
+                cert: "fedcba9876543210".to_string(),
+                not_after: 100,
+            },
+        ];
+        emit_server_certs(&mut client, &server, &certs, 400);
+        client.flush_sink();
+
+        let mut buf = [0u8; 1024];
+        let len = socket.recv(&mut buf).unwrap();
+        let msg = std::str::from_utf8(&buf[..len]).unwrap();
+        assert_eq!(
+            msg,
+            "server.tls.cert.expire_in:600|g|#server:test,cert:0123456789abcdef\n\
+             server.tls.cert.expire_in:-300|g|#server:test,cert:fedcba9876543210"
+        );
+    }
+}
@@ -0,0 +1,34 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use g3_statsd_client::StatsdClient;
+
+const METRIC_NAME_DAEMON_TLS_TICKET_KEY_ROTATION: &str = "daemon.tls_ticket.key_rotation";
+
+static KEY_ROTATIONS_SNAPSHOT: AtomicU64 = AtomicU64::new(0);
+
+pub(in crate::stat) fn emit_stats(client: &mut StatsdClient) {
+    let rotations = g3_types::net::rustls_ticket_key_rotations();
+    let last_rotations = KEY_ROTATIONS_SNAPSHOT.swap(rotations, Ordering::Relaxed);
+    client
+        .count(
+            METRIC_NAME_DAEMON_TLS_TICKET_KEY_ROTATION,
+            rotations.wrapping_sub(last_rotations),
+        )
+        .send();
+}
@@ -26,6 +26,7 @@ use g3_statsd_client::{StatsdClient, StatsdTagGroup};
 use g3_types::metrics::NodeName;
 use g3_types::stats::{StatId, TcpIoSnapshot, UdpIoSnapshot};
 
+use super::server::ServerUserUsageCollector;
 use super::TAG_KEY_ESCAPER;
 use super::{MetricUserConnectionType, MetricUserRequestType};
 use crate::auth::{
@@ -38,8 +39,8 @@ use crate::stat::types::{
     TrafficStats, UpstreamTrafficSnapshot, UpstreamTrafficStats,
 };
 
-const TAG_KEY_USER_GROUP: &str = "user_group";
-const TAG_KEY_USER: &str = "user";
+pub(super) const TAG_KEY_USER_GROUP: &str = "user_group";
+pub(super) const TAG_KEY_USER: &str = "user";
 const TAG_KEY_USER_TYPE: &str = "user_type";
 
 const METRIC_NAME_FORBIDDEN_AUTH_FAILED: &str = "user.forbidden.auth_failed";
@@ -226,6 +227,8 @@ pub(in crate::stat) fn sync_stats() {
 }
 
 pub(in crate::stat) fn emit_stats(client: &mut StatsdClient) {
+    let mut user_usage = ServerUserUsageCollector::new();
+
     let mut fbd_stats_map = USER_FORBIDDEN_STATS_MAP.lock().unwrap();
     fbd_stats_map.retain(|_, (stats, snap)| {
         emit_user_forbidden_stats(client, stats, snap);
@@ -236,7 +239,12 @@ pub(in crate::stat) fn emit_stats(client: &mut StatsdClient) {
 
     let mut req_stats_map = USER_REQUEST_STATS_MAP.lock().unwrap();
     req_stats_map.retain(|_, (stats, snap)| {
+        let old_total = snap.req_total.total();
         emit_user_request_stats(client, stats, snap, &REQUEST_STATS_NAMES);
+        if user_usage.is_enabled(stats.server()) {
+            let usage = user_usage.user_mut(stats.server(), stats.user_group(), stats.user());
+            usage.task_total += snap.req_total.total().wrapping_sub(old_total);
+        }
         // use Arc instead of Weak here, as we should emit the final metrics before drop it
         Arc::strong_count(stats) > 1
     });
@@ -244,7 +252,14 @@ pub(in crate::stat) fn emit_stats(client: &mut StatsdClient) {
 
     let mut io_stats_map = USER_TRAFFIC_STATS_MAP.lock().unwrap();
     io_stats_map.retain(|_, (stats, snap)| {
+        let old_in_bytes = snap.io.in_bytes_total();
+        let old_out_bytes = snap.io.out_bytes_total();
         emit_user_traffic_stats(client, stats, snap, &TRAFFIC_STATS_NAMES);
+        if user_usage.is_enabled(stats.server()) {
+            let usage = user_usage.user_mut(stats.server(), stats.user_group(), stats.user());
+            usage.in_bytes += snap.io.in_bytes_total().wrapping_sub(old_in_bytes);
+            usage.out_bytes += snap.io.out_bytes_total().wrapping_sub(old_out_bytes);
+        }
         // use Arc instead of Weak here, as we should emit the final metrics before drop it
         Arc::strong_count(stats) > 1
     });
@@ -257,6 +272,8 @@ pub(in crate::stat) fn emit_stats(client: &mut StatsdClient) {
         Arc::strong_count(stats) > 1
     });
     drop(upstream_io_stats_map);
+
+    user_usage.emit(client);
 }
 
 fn emit_user_forbidden_stats(
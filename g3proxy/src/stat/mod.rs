@@ -43,6 +43,7 @@ fn spawn_main_thread(config: &StatsdClientConfig) -> anyhow::Result<JoinHandle<(
     let mut client = build_statsd_client(config)?;
 
     let emit_duration = config.emit_duration;
+    let emit_rate = config.emit_rate;
     let handle = std::thread::Builder::new()
         .name("stat-main".to_string())
         .spawn(move || loop {
@@ -54,12 +55,16 @@ fn spawn_main_thread(config: &StatsdClientConfig) -> anyhow::Result<JoinHandle<(
             metrics::user::sync_stats();
             g3_daemon::log::metrics::sync_stats();
 
-            metrics::server::emit_stats(&mut client);
+            metrics::server::emit_stats(&mut client, emit_rate);
             metrics::escaper::emit_stats(&mut client);
             metrics::resolver::emit_stats(&mut client);
             metrics::user::emit_stats(&mut client);
+            metrics::ip_location::emit_stats(&mut client);
+            metrics::tls_ticket::emit_stats(&mut client);
+            metrics::tls_cert::emit_stats(&mut client);
             g3_daemon::runtime::metrics::emit_stats(&mut client);
             g3_daemon::log::metrics::emit_stats(&mut client);
+            g3_daemon::config::metrics::emit_stats(&mut client);
 
             client.flush_sink();
 
@@ -38,6 +38,18 @@ pub(crate) struct RequestSnapshot {
     pub(crate) socks_udp_associate: u64,
 }
 
+impl RequestSnapshot {
+    pub(crate) fn total(&self) -> u64 {
+        self.http_forward
+            .wrapping_add(self.https_forward)
+            .wrapping_add(self.http_connect)
+            .wrapping_add(self.ftp_over_http)
+            .wrapping_add(self.socks_tcp_connect)
+            .wrapping_add(self.socks_udp_connect)
+            .wrapping_add(self.socks_udp_associate)
+    }
+}
+
 impl RequestStats {
     pub(crate) fn add_http_forward(&self, is_https: bool) {
         if is_https {
@@ -38,6 +38,30 @@ pub(crate) struct TrafficSnapshot {
     pub(crate) socks_udp_associate: UdpIoSnapshot,
 }
 
+impl TrafficSnapshot {
+    pub(crate) fn in_bytes_total(&self) -> u64 {
+        self.http_forward
+            .in_bytes
+            .wrapping_add(self.https_forward.in_bytes)
+            .wrapping_add(self.http_connect.in_bytes)
+            .wrapping_add(self.ftp_over_http.in_bytes)
+            .wrapping_add(self.socks_tcp_connect.in_bytes)
+            .wrapping_add(self.socks_udp_connect.in_bytes)
+            .wrapping_add(self.socks_udp_associate.in_bytes)
+    }
+
+    pub(crate) fn out_bytes_total(&self) -> u64 {
+        self.http_forward
+            .out_bytes
+            .wrapping_add(self.https_forward.out_bytes)
+            .wrapping_add(self.http_connect.out_bytes)
+            .wrapping_add(self.ftp_over_http.out_bytes)
+            .wrapping_add(self.socks_tcp_connect.out_bytes)
+            .wrapping_add(self.socks_udp_connect.out_bytes)
+            .wrapping_add(self.socks_udp_associate.out_bytes)
+    }
+}
+
 #[derive(Default)]
 pub(crate) struct UpstreamTrafficStats {
     pub(crate) tcp: TcpIoStats,
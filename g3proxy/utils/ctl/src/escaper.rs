@@ -36,6 +36,9 @@ const SUBCOMMAND_PUBLISH: &str = "publish";
 const SUBCOMMAND_PUBLISH_ARG_FILE: &str = "file";
 const SUBCOMMAND_PUBLISH_ARG_DATA: &str = "data";
 
+const SUBCOMMAND_PROBE: &str = "probe";
+const SUBCOMMAND_PROBE_ARG_TARGET: &str = "target";
+
 pub fn command() -> Command {
     Command::new(COMMAND)
         .arg(Arg::new(COMMAND_ARG_NAME).required(true).num_args(1))
@@ -61,6 +64,15 @@ pub fn command() -> Command {
                         .conflicts_with(SUBCOMMAND_PUBLISH_ARG_FILE),
                 ),
         )
+        .subcommand(
+            Command::new(SUBCOMMAND_PROBE).arg(
+                Arg::new(SUBCOMMAND_PROBE_ARG_TARGET)
+                    .help("Canary target address, in host:port format")
+                    .value_name("TARGET")
+                    .num_args(1)
+                    .required(true),
+            ),
+        )
 }
 
 async fn publish(client: &escaper_control::Client, args: &ArgMatches) -> CommandResult<()> {
@@ -89,6 +101,15 @@ async fn publish(client: &escaper_control::Client, args: &ArgMatches) -> Command
     parse_operation_result(rsp.get()?.get_result()?)
 }
 
+async fn probe(client: &escaper_control::Client, args: &ArgMatches) -> CommandResult<()> {
+    let target = args.get_one::<String>(SUBCOMMAND_PROBE_ARG_TARGET).unwrap();
+
+    let mut req = client.probe_request();
+    req.get().set_target(target.as_str());
+    let rsp = req.send().promise.await?;
+    parse_operation_result(rsp.get()?.get_result()?)
+}
+
 pub async fn run(client: &proc_control::Client, args: &ArgMatches) -> CommandResult<()> {
     let name = args.get_one::<String>(COMMAND_ARG_NAME).unwrap();
 
@@ -99,6 +120,11 @@ pub async fn run(client: &proc_control::Client, args: &ArgMatches) -> CommandRes
                 .and_then(|escaper| async move { publish(&escaper, args).await })
                 .await
         }
+        SUBCOMMAND_PROBE => {
+            super::proc::get_escaper(client, name)
+                .and_then(|escaper| async move { probe(&escaper, args).await })
+                .await
+        }
         _ => unreachable!(),
     }
 }
@@ -14,10 +14,11 @@
  * limitations under the License.
  */
 
+use anyhow::anyhow;
 use clap::{Arg, ArgMatches, Command};
 use futures_util::future::TryFutureExt;
 
-use g3_ctl::CommandResult;
+use g3_ctl::{CommandError, CommandResult};
 
 use g3proxy_proto::proc_capnp::proc_control;
 use g3proxy_proto::server_capnp::server_control;
@@ -27,12 +28,14 @@ pub const COMMAND: &str = "server";
 const COMMAND_ARG_NAME: &str = "name";
 
 const SUBCOMMAND_STATUS: &str = "status";
+const SUBCOMMAND_READY: &str = "ready";
 
 pub fn command() -> Command {
     Command::new(COMMAND)
         .arg(Arg::new(COMMAND_ARG_NAME).required(true).num_args(1))
         .subcommand_required(true)
         .subcommand(Command::new(SUBCOMMAND_STATUS))
+        .subcommand(Command::new(SUBCOMMAND_READY))
 }
 
 async fn status(client: &server_control::Client) -> CommandResult<()> {
@@ -43,9 +46,21 @@ async fn status(client: &server_control::Client) -> CommandResult<()> {
     println!("alive tasks: {}", stats.get_alive_task_count());
     println!("total conn: {}", stats.get_total_conn_count());
     println!("total task: {}", stats.get_total_task_count());
+    println!("ready: {}", stats.get_ready());
     Ok(())
 }
 
+async fn ready(client: &server_control::Client) -> CommandResult<()> {
+    let req = client.ready_request();
+    let rsp = req.send().promise.await?;
+    if rsp.get()?.get_ready() {
+        println!("ready");
+        Ok(())
+    } else {
+        Err(CommandError::Cli(anyhow!("not ready")))
+    }
+}
+
 pub async fn run(client: &proc_control::Client, args: &ArgMatches) -> CommandResult<()> {
     let name = args.get_one::<String>(COMMAND_ARG_NAME).unwrap();
 
@@ -56,6 +71,11 @@ pub async fn run(client: &proc_control::Client, args: &ArgMatches) -> CommandRes
                 .and_then(|server| async move { status(&server).await })
                 .await
         }
+        SUBCOMMAND_READY => {
+            super::proc::get_server(client, name)
+                .and_then(|server| async move { ready(&server).await })
+                .await
+        }
         _ => unreachable!(),
     }
 }
@@ -23,7 +23,7 @@ use yaml_rust::{yaml, Yaml};
 use g3_tls_ticket::TlsTicketConfig;
 use g3_types::acl::AclNetworkRuleBuilder;
 use g3_types::metrics::NodeName;
-use g3_types::net::{RustlsServerConfigBuilder, UdpListenConfig};
+use g3_types::net::{QuinnRetryPolicy, RustlsServerConfigBuilder, UdpListenConfig};
 use g3_yaml::YamlDocPosition;
 
 use super::ServerConfig;
@@ -50,6 +50,7 @@ pub(crate) struct PlainQuicPortConfig {
     pub(crate) ingress_net_filter: Option<AclNetworkRuleBuilder>,
     pub(crate) server: NodeName,
     pub(crate) offline_rebind_port: Option<u16>,
+    pub(crate) retry_policy: QuinnRetryPolicy,
 }
 
 impl PlainQuicPortConfig {
@@ -64,6 +65,7 @@ impl PlainQuicPortConfig {
             ingress_net_filter: None,
             server: NodeName::default(),
             offline_rebind_port: None,
+            retry_policy: QuinnRetryPolicy::default(),
         }
     }
 
@@ -100,6 +102,11 @@ impl PlainQuicPortConfig {
                 self.offline_rebind_port = Some(port);
                 Ok(())
             }
+            "retry_policy" | "quic_retry_policy" => {
+                self.retry_policy = g3_yaml::value::as_quinn_retry_policy(v)
+                    .context(format!("invalid quinn retry policy value for key {k}"))?;
+                Ok(())
+            }
             "quic_server" => {
                 let lookup_dir = g3_daemon::config::get_lookup_dir(self.position.as_ref())?;
                 self.tls_server =
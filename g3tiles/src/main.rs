@@ -72,6 +72,9 @@ fn main() -> anyhow::Result<()> {
 
     let ret = tokio_run(&proc_args);
 
+    // make sure the pending event logs be sent out before exit
+    let _ = g3_daemon::log::wait_flush();
+
     if let Some(handlers) = stat_join {
         g3tiles::stat::stop_working_threads();
         for handle in handlers {
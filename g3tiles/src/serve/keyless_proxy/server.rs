@@ -210,6 +210,7 @@ impl ServerInternal for KeylessProxyServer {
 
     fn _start_runtime(&self, _server: &ArcServer) -> anyhow::Result<()> {
         self.server_stats.set_online();
+        self.listen_stats.set_no_listen();
         Ok(())
     }
 
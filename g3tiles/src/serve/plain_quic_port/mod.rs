@@ -30,7 +30,7 @@ use g3_daemon::listen::{
 use g3_daemon::server::{BaseServer, ClientConnectionInfo, ServerReloadCommand};
 use g3_types::acl::AclNetworkRule;
 use g3_types::metrics::NodeName;
-use g3_types::net::{OpensslTicketKey, RollingTicketer, UdpListenConfig};
+use g3_types::net::{OpensslTicketKey, QuinnRetryPolicy, RollingTicketer, UdpListenConfig};
 
 use crate::config::server::plain_quic_port::{PlainQuicPortConfig, PlainQuicPortUpdateFlags};
 use crate::config::server::{AnyServerConfig, ServerConfig};
@@ -43,6 +43,7 @@ struct PlainQuicPortAuxConfig {
     quinn_config: Option<quinn::ServerConfig>,
     accept_timeout: Duration,
     offline_rebind_port: Option<u16>,
+    retry_policy: QuinnRetryPolicy,
 }
 
 impl ListenQuicConf for PlainQuicPortAuxConfig {
@@ -70,6 +71,11 @@ impl ListenQuicConf for PlainQuicPortAuxConfig {
     fn accept_timeout(&self) -> Duration {
         self.accept_timeout
     }
+
+    #[inline]
+    fn retry_policy(&self) -> QuinnRetryPolicy {
+        self.retry_policy
+    }
 }
 
 pub(crate) struct PlainQuicPort {
@@ -113,6 +119,7 @@ impl PlainQuicPort {
             quinn_config: None,
             accept_timeout: quic_server.accept_timeout,
             offline_rebind_port: config.offline_rebind_port,
+            retry_policy: config.retry_policy,
         };
         let (cfg_sender, _cfg_receiver) = watch::channel(aux_config);
 
@@ -213,6 +220,7 @@ impl ServerInternal for PlainQuicPort {
                 quinn_config,
                 accept_timeout: config.tls_server.accept_timeout(),
                 offline_rebind_port: config.offline_rebind_port,
+                retry_policy: config.retry_policy,
             };
             self.cfg_sender.send_replace(aux_config);
             self.config.store(config);
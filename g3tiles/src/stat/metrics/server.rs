@@ -15,6 +15,7 @@
  */
 
 use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Instant;
 
 use ahash::AHashMap;
 
@@ -28,12 +29,16 @@ use g3_types::stats::{StatId, TcpIoSnapshot, UdpIoSnapshot};
 use crate::serve::ArcServerStats;
 
 const METRIC_NAME_SERVER_CONN_TOTAL: &str = "server.connection.total";
+const METRIC_NAME_SERVER_CONN_RATE: &str = "server.connection.rate";
 const METRIC_NAME_SERVER_TASK_TOTAL: &str = "server.task.total";
+const METRIC_NAME_SERVER_TASK_RATE: &str = "server.task.rate";
 const METRIC_NAME_SERVER_TASK_ALIVE: &str = "server.task.alive";
 const METRIC_NAME_SERVER_IO_IN_BYTES: &str = "server.traffic.in.bytes";
 const METRIC_NAME_SERVER_IO_IN_PACKETS: &str = "server.traffic.in.packets";
 const METRIC_NAME_SERVER_IO_OUT_BYTES: &str = "server.traffic.out.bytes";
 const METRIC_NAME_SERVER_IO_OUT_PACKETS: &str = "server.traffic.out.packets";
+const METRIC_NAME_SERVER_IO_IN_BYTES_RATE: &str = "server.traffic.in.bytes_rate";
+const METRIC_NAME_SERVER_IO_OUT_BYTES_RATE: &str = "server.traffic.out.bytes_rate";
 
 type ServerStatsValue = (ArcServerStats, ServerSnapshot);
 type ListenStatsValue = (Arc<ListenStats>, ListenSnapshot);
@@ -49,6 +54,7 @@ struct ServerSnapshot {
     task_total: u64,
     tcp: TcpIoSnapshot,
     udp: UdpIoSnapshot,
+    last_emit: Option<Instant>,
 }
 
 pub(in crate::stat) fn sync_stats() {
@@ -74,10 +80,10 @@ pub(in crate::stat) fn sync_stats() {
     drop(listen_stats_map);
 }
 
-pub(in crate::stat) fn emit_stats(client: &mut StatsdClient) {
+pub(in crate::stat) fn emit_stats(client: &mut StatsdClient, emit_rate: bool) {
     let mut server_stats_map = SERVER_STATS_MAP.lock().unwrap();
     server_stats_map.retain(|_, (stats, snap)| {
-        emit_server_stats(client, stats, snap);
+        emit_server_stats(client, stats, snap, emit_rate);
         // use Arc instead of Weak here, as we should emit the final metrics before drop it
         Arc::strong_count(stats) > 1
     });
@@ -91,18 +97,34 @@ pub(in crate::stat) fn emit_stats(client: &mut StatsdClient) {
     });
 }
 
-fn emit_server_stats(client: &mut StatsdClient, stats: &ArcServerStats, snap: &mut ServerSnapshot) {
+fn emit_server_stats(
+    client: &mut StatsdClient,
+    stats: &ArcServerStats,
+    snap: &mut ServerSnapshot,
+    emit_rate: bool,
+) {
     let mut common_tags = StatsdTagGroup::default();
     common_tags.add_server_tags(stats.name(), stats.is_online(), stats.stat_id());
     if let Some(tags) = stats.load_extra_tags() {
         common_tags.add_static_tags(&tags);
     }
 
+    let rate_secs = g3_daemon::stat::emit::rate_elapsed_secs(emit_rate, &mut snap.last_emit);
+
     let new_value = stats.conn_total();
     let diff_value = new_value.wrapping_sub(snap.conn_total);
     client
         .count_with_tags(METRIC_NAME_SERVER_CONN_TOTAL, diff_value, &common_tags)
         .send();
+    if let Some(secs) = rate_secs {
+        client
+            .gauge_float_with_tags(
+                METRIC_NAME_SERVER_CONN_RATE,
+                diff_value as f64 / secs,
+                &common_tags,
+            )
+            .send();
+    }
     snap.conn_total = new_value;
 
     let new_value = stats.task_total();
@@ -110,6 +132,15 @@ fn emit_server_stats(client: &mut StatsdClient, stats: &ArcServerStats, snap: &m
     client
         .count_with_tags(METRIC_NAME_SERVER_TASK_TOTAL, diff_value, &common_tags)
         .send();
+    if let Some(secs) = rate_secs {
+        client
+            .gauge_float_with_tags(
+                METRIC_NAME_SERVER_TASK_RATE,
+                diff_value as f64 / secs,
+                &common_tags,
+            )
+            .send();
+    }
     snap.task_total = new_value;
 
     client
@@ -121,11 +152,11 @@ fn emit_server_stats(client: &mut StatsdClient, stats: &ArcServerStats, snap: &m
         .send();
 
     if let Some(tcp_io_stats) = stats.tcp_io_snapshot() {
-        emit_tcp_io_to_statsd(client, tcp_io_stats, &mut snap.tcp, &common_tags);
+        emit_tcp_io_to_statsd(client, tcp_io_stats, &mut snap.tcp, &common_tags, rate_secs);
     }
 
     if let Some(udp_io_stats) = stats.udp_io_snapshot() {
-        emit_udp_io_to_statsd(client, udp_io_stats, &mut snap.udp, &common_tags);
+        emit_udp_io_to_statsd(client, udp_io_stats, &mut snap.udp, &common_tags, rate_secs);
     }
 }
 
@@ -134,6 +165,7 @@ fn emit_tcp_io_to_statsd(
     stats: TcpIoSnapshot,
     snap: &mut TcpIoSnapshot,
     common_tags: &StatsdTagGroup,
+    rate_secs: Option<f64>,
 ) {
     if stats.in_bytes == 0 && snap.in_bytes == 0 {
         return;
@@ -149,10 +181,33 @@ fn emit_tcp_io_to_statsd(
                 .send();
             snap.$field = new_value;
         };
+        ($field:ident, $name:expr, $rate_name:expr) => {
+            let new_value = stats.$field;
+            let diff_value = new_value.wrapping_sub(snap.$field);
+            client
+                .count_with_tags($name, diff_value, common_tags)
+                .with_tag(TAG_KEY_TRANSPORT, TRANSPORT_TYPE_TCP)
+                .send();
+            if let Some(secs) = rate_secs {
+                client
+                    .gauge_float_with_tags($rate_name, diff_value as f64 / secs, common_tags)
+                    .with_tag(TAG_KEY_TRANSPORT, TRANSPORT_TYPE_TCP)
+                    .send();
+            }
+            snap.$field = new_value;
+        };
     }
 
-    emit_field!(in_bytes, METRIC_NAME_SERVER_IO_IN_BYTES);
-    emit_field!(out_bytes, METRIC_NAME_SERVER_IO_OUT_BYTES);
+    emit_field!(
+        in_bytes,
+        METRIC_NAME_SERVER_IO_IN_BYTES,
+        METRIC_NAME_SERVER_IO_IN_BYTES_RATE
+    );
+    emit_field!(
+        out_bytes,
+        METRIC_NAME_SERVER_IO_OUT_BYTES,
+        METRIC_NAME_SERVER_IO_OUT_BYTES_RATE
+    );
 }
 
 fn emit_udp_io_to_statsd(
@@ -160,6 +215,7 @@ fn emit_udp_io_to_statsd(
     stats: UdpIoSnapshot,
     snap: &mut UdpIoSnapshot,
     common_tags: &StatsdTagGroup,
+    rate_secs: Option<f64>,
 ) {
     if stats.in_packets == 0 && snap.in_packets == 0 {
         return;
@@ -175,10 +231,33 @@ fn emit_udp_io_to_statsd(
                 .send();
             snap.$field = new_value;
         };
+        ($field:ident, $name:expr, $rate_name:expr) => {
+            let new_value = stats.$field;
+            let diff_value = new_value.wrapping_sub(snap.$field);
+            client
+                .count_with_tags($name, diff_value, common_tags)
+                .with_tag(TAG_KEY_TRANSPORT, TRANSPORT_TYPE_UDP)
+                .send();
+            if let Some(secs) = rate_secs {
+                client
+                    .gauge_float_with_tags($rate_name, diff_value as f64 / secs, common_tags)
+                    .with_tag(TAG_KEY_TRANSPORT, TRANSPORT_TYPE_UDP)
+                    .send();
+            }
+            snap.$field = new_value;
+        };
     }
 
     emit_field!(in_packets, METRIC_NAME_SERVER_IO_IN_PACKETS);
-    emit_field!(in_bytes, METRIC_NAME_SERVER_IO_IN_BYTES);
+    emit_field!(
+        in_bytes,
+        METRIC_NAME_SERVER_IO_IN_BYTES,
+        METRIC_NAME_SERVER_IO_IN_BYTES_RATE
+    );
     emit_field!(out_packets, METRIC_NAME_SERVER_IO_OUT_PACKETS);
-    emit_field!(out_bytes, METRIC_NAME_SERVER_IO_OUT_BYTES);
+    emit_field!(
+        out_bytes,
+        METRIC_NAME_SERVER_IO_OUT_BYTES,
+        METRIC_NAME_SERVER_IO_OUT_BYTES_RATE
+    );
 }
@@ -0,0 +1,90 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use g3_statsd_client::StatsdClient;
+
+const METRIC_NAME_DAEMON_CONFIG_RELOAD_TOTAL: &str = "daemon.config.reload.total";
+const METRIC_NAME_DAEMON_CONFIG_RELOAD_SUCCESS: &str = "daemon.config.reload.success";
+const METRIC_NAME_DAEMON_CONFIG_RELOAD_FAILED: &str = "daemon.config.reload.failed";
+const METRIC_NAME_DAEMON_CONFIG_RELOAD_LAST_SUCCESS: &str = "daemon.config.reload.last_success";
+
+static RELOAD_TOTAL: AtomicU64 = AtomicU64::new(0);
+static RELOAD_SUCCESS: AtomicU64 = AtomicU64::new(0);
+static RELOAD_FAILED: AtomicU64 = AtomicU64::new(0);
+static RELOAD_LAST_SUCCESS: AtomicU64 = AtomicU64::new(0);
+
+static RELOAD_SNAPSHOT: Mutex<ReloadSnapshot> = Mutex::new(ReloadSnapshot {
+    total: 0,
+    success: 0,
+    failed: 0,
+});
+
+struct ReloadSnapshot {
+    total: u64,
+    success: u64,
+    failed: u64,
+}
+
+/// Record the result of a config reload, either for the whole daemon or for a single entity
+pub fn add_reload_result<T>(r: &anyhow::Result<T>) {
+    RELOAD_TOTAL.fetch_add(1, Ordering::Relaxed);
+    if r.is_ok() {
+        RELOAD_SUCCESS.fetch_add(1, Ordering::Relaxed);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        RELOAD_LAST_SUCCESS.store(now, Ordering::Relaxed);
+    } else {
+        RELOAD_FAILED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub fn emit_stats(client: &mut StatsdClient) {
+    let mut snap = RELOAD_SNAPSHOT.lock().unwrap();
+
+    let new_value = RELOAD_TOTAL.load(Ordering::Relaxed);
+    let diff_value = new_value.wrapping_sub(snap.total);
+    client
+        .count(METRIC_NAME_DAEMON_CONFIG_RELOAD_TOTAL, diff_value)
+        .send();
+    snap.total = new_value;
+
+    let new_value = RELOAD_SUCCESS.load(Ordering::Relaxed);
+    let diff_value = new_value.wrapping_sub(snap.success);
+    client
+        .count(METRIC_NAME_DAEMON_CONFIG_RELOAD_SUCCESS, diff_value)
+        .send();
+    snap.success = new_value;
+
+    let new_value = RELOAD_FAILED.load(Ordering::Relaxed);
+    let diff_value = new_value.wrapping_sub(snap.failed);
+    client
+        .count(METRIC_NAME_DAEMON_CONFIG_RELOAD_FAILED, diff_value)
+        .send();
+    snap.failed = new_value;
+
+    let last_success = RELOAD_LAST_SUCCESS.load(Ordering::Relaxed);
+    if last_success > 0 {
+        client
+            .gauge(METRIC_NAME_DAEMON_CONFIG_RELOAD_LAST_SUCCESS, last_success)
+            .send();
+    }
+}
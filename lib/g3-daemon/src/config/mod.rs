@@ -19,3 +19,5 @@ pub use lookup::get_lookup_dir;
 
 mod topology;
 pub use topology::TopoMap;
+
+pub mod metrics;
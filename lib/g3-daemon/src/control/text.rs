@@ -123,6 +123,7 @@ where
     }
 
     async fn handle(&mut self, command: &str) -> (String, CtlProtoType) {
+        let raw_command = command;
         let command = command.to_lowercase();
         let mut iter = command.split_whitespace();
         let cmd = iter.next();
@@ -138,6 +139,10 @@ where
             }
             Some("set") => self.set(iter),
             Some("pid") => Ok(std::process::id().to_string()),
+            #[cfg(feature = "event-log")]
+            Some("log_level") => self.log_level(iter),
+            #[cfg(feature = "event-log")]
+            Some("dump_log") => self.dump_log(raw_command.split_whitespace().nth(1)),
             Some(k) => Err(anyhow!("unknown command {k}")),
             None => Ok(String::new()),
         };
@@ -147,6 +152,32 @@ where
         }
     }
 
+    #[cfg(feature = "event-log")]
+    fn log_level(&self, mut iter: SplitWhitespace) -> anyhow::Result<String> {
+        let Some(log_type) = iter.next() else {
+            return Err(anyhow!("no log type set"));
+        };
+        if let Some(level) = iter.next() {
+            let level = level
+                .parse::<slog::Level>()
+                .map_err(|_| anyhow!("invalid log level {level}"))?;
+            crate::log::set_log_level(log_type, level)?;
+        }
+        let level = crate::log::get_log_level(log_type)
+            .ok_or_else(|| anyhow!("no logger found for log type {log_type}"))?;
+        Ok(format!("{log_type} = {}", level.as_str()))
+    }
+
+    #[cfg(feature = "event-log")]
+    fn dump_log(&self, logger_name: Option<&str>) -> anyhow::Result<String> {
+        if let Some(name) = logger_name {
+            crate::log::dump_memory_logs(name)
+                .ok_or_else(|| anyhow!("no memory log found for logger {name}"))
+        } else {
+            Ok(crate::log::memory_logger_names().join("\n"))
+        }
+    }
+
     fn set(&mut self, mut iter: SplitWhitespace) -> anyhow::Result<String> {
         if let Some(key) = iter.next() {
             if let Some(value) = iter.next() {
@@ -20,6 +20,9 @@ pub use stats::{ListenSnapshot, ListenStats};
 mod tcp;
 pub use tcp::{AcceptTcpServer, ListenTcpRuntime, ReloadTcpServer};
 
+#[cfg(feature = "quic")]
+mod quic_socket;
+
 #[cfg_attr(feature = "quic", path = "quic.rs")]
 #[cfg_attr(not(feature = "quic"), path = "no_quic.rs")]
 mod quic;
@@ -17,7 +17,7 @@
 use std::io;
 use std::net::{SocketAddr, UdpSocket};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use log::{info, warn};
@@ -28,8 +28,9 @@ use tokio::sync::{broadcast, watch};
 use g3_socket::util::native_socket_addr;
 use g3_socket::RawSocket;
 use g3_types::acl::{AclAction, AclNetworkRule};
-use g3_types::net::UdpListenConfig;
+use g3_types::net::{QuinnRetryPolicy, UdpListenConfig};
 
+use super::quic_socket::OffloadStatsUdpSocket;
 use crate::listen::ListenStats;
 use crate::server::{BaseServer, ClientConnectionInfo, ServerReloadCommand};
 
@@ -52,6 +53,43 @@ pub trait ListenQuicConf {
     fn ingress_network_acl(&self) -> Option<&AclNetworkRule>;
 
     fn accept_timeout(&self) -> Duration;
+
+    fn retry_policy(&self) -> QuinnRetryPolicy;
+}
+
+/// Track the new connection rate of a single listen instance
+#[derive(Default)]
+struct NewConnectionRate {
+    window_start: Option<Instant>,
+    current: u32,
+    previous: u32,
+}
+
+impl NewConnectionRate {
+    /// Record a new connection and return the rate of the recent full second
+    fn record(&mut self) -> u32 {
+        self.record_at(Instant::now())
+    }
+
+    fn record_at(&mut self, now: Instant) -> u32 {
+        match self.window_start {
+            Some(start) => {
+                let elapsed = now.duration_since(start);
+                if elapsed >= Duration::from_secs(2) {
+                    self.window_start = Some(now);
+                    self.previous = 0;
+                    self.current = 0;
+                } else if elapsed >= Duration::from_secs(1) {
+                    self.window_start = Some(start + Duration::from_secs(1));
+                    self.previous = self.current;
+                    self.current = 0;
+                }
+            }
+            None => self.window_start = Some(now),
+        }
+        self.current = self.current.saturating_add(1);
+        self.previous.max(self.current)
+    }
 }
 
 #[derive(Clone)]
@@ -128,6 +166,7 @@ where
         use broadcast::error::RecvError;
 
         let mut aux_config = quic_cfg_receiver.borrow().clone();
+        let mut new_connection_rate = NewConnectionRate::default();
 
         loop {
             tokio::select! {
@@ -185,16 +224,20 @@ where
                     let Some(incoming) = result else {
                         continue;
                     };
-                    self.listen_stats.add_accepted();
-                    self.run_task(incoming, listen_addr, &aux_config);
+                    self.run_task(incoming, listen_addr, &aux_config, &mut new_connection_rate);
                 }
             }
         }
         self.post_stop();
     }
 
-    fn run_task<C>(&self, incoming: Incoming, listen_addr: SocketAddr, aux_config: &C)
-    where
+    fn run_task<C>(
+        &self,
+        incoming: Incoming,
+        listen_addr: SocketAddr,
+        aux_config: &C,
+        new_connection_rate: &mut NewConnectionRate,
+    ) where
         C: ListenQuicConf + Send + Clone + 'static,
     {
         let peer_addr = incoming.remote_address();
@@ -209,6 +252,19 @@ where
             }
         }
 
+        // only the connections permitted by the ingress network acl will be counted
+        let rate = new_connection_rate.record();
+        if aux_config.retry_policy().need_retry(rate)
+            && !incoming.remote_address_validated()
+            && incoming.may_retry()
+        {
+            if incoming.retry().is_ok() {
+                self.listen_stats.add_quic_retry();
+            }
+            return;
+        }
+        self.listen_stats.add_accepted();
+
         let local_addr = incoming
             .local_ip()
             .map(|ip| SocketAddr::new(ip, listen_addr.port()))
@@ -290,6 +346,24 @@ where
         }
     }
 
+    /// Quinn will always try to enable GRO when wrapping the socket,
+    /// so this should be called after the wrap and before the endpoint starts to receive
+    fn update_udp_gro(&self, raw_socket: &RawSocket) {
+        #[cfg(target_os = "linux")]
+        if let Some(enable) = self.listen_config.udp_gro() {
+            if let Err(e) = raw_socket.set_udp_gro(enable) {
+                warn!(
+                    "SRT[{}_v{}#{}] update socket udp gro failed: {e}",
+                    self.server.name(),
+                    self.server_version,
+                    self.instance_id,
+                );
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        let _ = raw_socket;
+    }
+
     fn update_socket_opts(&self, raw_socket: &RawSocket) {
         if let Err(e) = raw_socket.set_udp_misc_opts(self.listen_config.socket_misc_opts()) {
             warn!(
@@ -307,13 +381,20 @@ where
                 self.instance_id,
             );
         }
+        self.update_udp_gro(raw_socket);
     }
 
     fn rebind_socket(&self, listener: &Endpoint) -> io::Result<(RawSocket, SocketAddr)> {
         match g3_socket::udp::new_std_bind_listen(&self.listen_config) {
             Ok(socket) => {
                 let raw_socket = RawSocket::from(&socket);
-                match listener.rebind(socket) {
+                let r = OffloadStatsUdpSocket::wrap(socket, self.listen_stats.clone()).and_then(
+                    |socket| {
+                        self.update_udp_gro(&raw_socket);
+                        listener.rebind_abstract(socket)
+                    },
+                );
+                match r {
                     Ok(_) => Ok((raw_socket, listener.local_addr().unwrap())),
                     Err(e) => {
                         warn!(
@@ -347,7 +428,9 @@ where
                 &self.listen_config,
                 SocketAddr::new(listen_addr.ip(), port),
             ) {
-                Ok(socket) => match listener.rebind(socket) {
+                Ok(socket) => match OffloadStatsUdpSocket::wrap(socket, self.listen_stats.clone())
+                    .and_then(|socket| listener.rebind_abstract(socket))
+                {
                     Ok(_) => {
                         info!(
                             "SRT[{}_v{}#{}] re-bound to: {rebind_addr}",
@@ -416,12 +499,17 @@ where
         handle.spawn(async move {
             let raw_socket = RawSocket::from(&socket);
             // make sure the listen socket associated with the correct reactor
-            match Endpoint::new(
-                Default::default(),
-                Some(config),
-                socket,
-                Arc::new(quinn::TokioRuntime),
-            ) {
+            let r =
+                OffloadStatsUdpSocket::wrap(socket, self.listen_stats.clone()).and_then(|socket| {
+                    self.update_udp_gro(&raw_socket);
+                    Endpoint::new_with_abstract_socket(
+                        Default::default(),
+                        Some(config),
+                        socket,
+                        Arc::new(quinn::TokioRuntime),
+                    )
+                });
+            match r {
                 Ok(endpoint) => {
                     self.pre_start();
                     self.run(
@@ -462,6 +550,7 @@ where
                 instance_count = worker_count;
             }
         }
+        self.listen_stats.set_expected_runtime_count(instance_count);
 
         for i in 0..instance_count {
             let mut runtime = self.clone();
@@ -481,3 +570,26 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_connection_rate() {
+        let start = Instant::now();
+        let mut rate = NewConnectionRate::default();
+        assert_eq!(rate.record_at(start), 1);
+        assert_eq!(rate.record_at(start + Duration::from_millis(500)), 2);
+        assert_eq!(rate.record_at(start + Duration::from_millis(900)), 3);
+
+        // the previous full second is kept
+        assert_eq!(rate.record_at(start + Duration::from_millis(1100)), 3);
+        assert_eq!(rate.record_at(start + Duration::from_millis(1500)), 3);
+        assert_eq!(rate.record_at(start + Duration::from_millis(1600)), 3);
+        assert_eq!(rate.record_at(start + Duration::from_millis(1700)), 4);
+
+        // reset after an idle second
+        assert_eq!(rate.record_at(start + Duration::from_millis(4000)), 1);
+    }
+}
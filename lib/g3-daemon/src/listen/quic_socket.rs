@@ -0,0 +1,127 @@
+/*
+ * Copyright 2023 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fmt;
+use std::io::{self, IoSliceMut};
+use std::net::{SocketAddr, UdpSocket};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+
+use quinn::udp::{RecvMeta, Transmit};
+use quinn::{AsyncUdpSocket, Runtime, UdpPoller};
+
+use crate::listen::ListenStats;
+
+/// The count of the extra datagrams carried by a single buffer of `len` bytes
+fn coalesced_count(len: usize, segment_size: usize) -> u64 {
+    if segment_size == 0 {
+        return 0;
+    }
+    len.div_ceil(segment_size).saturating_sub(1) as u64
+}
+
+/// Wrap the quinn listen socket to count the UDP datagrams coalesced by GSO and GRO
+pub(super) struct OffloadStatsUdpSocket {
+    inner: Arc<dyn AsyncUdpSocket>,
+    listen_stats: Arc<ListenStats>,
+}
+
+impl OffloadStatsUdpSocket {
+    /// Wrap the std socket by the quinn tokio runtime, this should be called in the tokio runtime.
+    /// Quinn will try to enable GRO on the socket here.
+    pub(super) fn wrap(
+        socket: UdpSocket,
+        listen_stats: Arc<ListenStats>,
+    ) -> io::Result<Arc<dyn AsyncUdpSocket>> {
+        let inner = quinn::TokioRuntime.wrap_udp_socket(socket)?;
+        Ok(Arc::new(OffloadStatsUdpSocket {
+            inner,
+            listen_stats,
+        }))
+    }
+}
+
+impl fmt::Debug for OffloadStatsUdpSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl AsyncUdpSocket for OffloadStatsUdpSocket {
+    fn create_io_poller(self: Arc<Self>) -> Pin<Box<dyn UdpPoller>> {
+        self.inner.clone().create_io_poller()
+    }
+
+    fn try_send(&self, transmit: &Transmit) -> io::Result<()> {
+        self.inner.try_send(transmit)?;
+        if let Some(segment_size) = transmit.segment_size {
+            let count = coalesced_count(transmit.contents.len(), segment_size);
+            if count > 0 {
+                self.listen_stats.add_udp_gso_coalesced(count);
+            }
+        }
+        Ok(())
+    }
+
+    fn poll_recv(
+        &self,
+        cx: &mut Context,
+        bufs: &mut [IoSliceMut<'_>],
+        meta: &mut [RecvMeta],
+    ) -> Poll<io::Result<usize>> {
+        let nr = ready!(self.inner.poll_recv(cx, bufs, meta))?;
+        let count: u64 = meta
+            .iter()
+            .take(nr)
+            .map(|m| coalesced_count(m.len, m.stride))
+            .sum();
+        if count > 0 {
+            self.listen_stats.add_udp_gro_coalesced(count);
+        }
+        Poll::Ready(Ok(nr))
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    fn max_transmit_segments(&self) -> usize {
+        self.inner.max_transmit_segments()
+    }
+
+    fn max_receive_segments(&self) -> usize {
+        self.inner.max_receive_segments()
+    }
+
+    fn may_fragment(&self) -> bool {
+        self.inner.may_fragment()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coalesced() {
+        assert_eq!(coalesced_count(1200, 1200), 0);
+        assert_eq!(coalesced_count(1000, 1200), 0);
+        assert_eq!(coalesced_count(2400, 1200), 1);
+        assert_eq!(coalesced_count(3000, 1200), 2);
+        assert_eq!(coalesced_count(1200, 0), 0);
+    }
+}
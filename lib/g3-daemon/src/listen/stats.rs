@@ -23,6 +23,9 @@ use g3_types::stats::StatId;
 #[derive(Default)]
 pub struct ListenSnapshot {
     pub accepted: u64,
+    pub udp_gso_coalesced: u64,
+    pub udp_gro_coalesced: u64,
+    pub quic_retry: u64,
     pub dropped: u64,
     pub timeout: u64,
     pub failed: u64,
@@ -34,7 +37,11 @@ pub struct ListenStats {
     id: StatId,
 
     runtime_count: AtomicIsize,
+    expected_runtime_count: AtomicIsize,
     accepted: AtomicU64,
+    udp_gso_coalesced: AtomicU64,
+    udp_gro_coalesced: AtomicU64,
+    quic_retry: AtomicU64,
     dropped: AtomicU64,
     timeout: AtomicU64,
     failed: AtomicU64,
@@ -46,7 +53,11 @@ impl ListenStats {
             name: name.clone(),
             id: StatId::new(),
             runtime_count: AtomicIsize::new(0),
+            expected_runtime_count: AtomicIsize::new(-1),
             accepted: AtomicU64::new(0),
+            udp_gso_coalesced: AtomicU64::new(0),
+            udp_gro_coalesced: AtomicU64::new(0),
+            quic_retry: AtomicU64::new(0),
             dropped: AtomicU64::new(0),
             timeout: AtomicU64::new(0),
             failed: AtomicU64::new(0),
@@ -77,6 +88,22 @@ impl ListenStats {
         self.get_running_runtime_count() > 0
     }
 
+    /// Set the count of runtime instances that should be running once the listen is started
+    pub fn set_expected_runtime_count(&self, count: usize) {
+        let count = isize::try_from(count).unwrap_or(isize::MAX);
+        self.expected_runtime_count.store(count, Ordering::Relaxed);
+    }
+    /// Mark the server as started without any listen runtime,
+    /// it will be ready as it only accepts connections from other servers
+    pub fn set_no_listen(&self) {
+        self.expected_runtime_count.store(0, Ordering::Relaxed);
+    }
+    /// Whether all the listen runtime instances are ready to accept new connections
+    pub fn is_ready(&self) -> bool {
+        let expected = self.expected_runtime_count.load(Ordering::Relaxed);
+        expected >= 0 && self.get_running_runtime_count() >= expected
+    }
+
     pub fn add_accepted(&self) {
         self.accepted.fetch_add(1, Ordering::Relaxed);
     }
@@ -84,6 +111,29 @@ impl ListenStats {
         self.accepted.load(Ordering::Relaxed)
     }
 
+    /// Add the count of UDP datagrams that have been sent without a separate syscall by GSO
+    pub fn add_udp_gso_coalesced(&self, count: u64) {
+        self.udp_gso_coalesced.fetch_add(count, Ordering::Relaxed);
+    }
+    pub fn udp_gso_coalesced(&self) -> u64 {
+        self.udp_gso_coalesced.load(Ordering::Relaxed)
+    }
+
+    /// Add the count of UDP datagrams that have been received without a separate syscall by GRO
+    pub fn add_udp_gro_coalesced(&self, count: u64) {
+        self.udp_gro_coalesced.fetch_add(count, Ordering::Relaxed);
+    }
+    pub fn udp_gro_coalesced(&self) -> u64 {
+        self.udp_gro_coalesced.load(Ordering::Relaxed)
+    }
+
+    pub fn add_quic_retry(&self) {
+        self.quic_retry.fetch_add(1, Ordering::Relaxed);
+    }
+    pub fn quic_retry(&self) -> u64 {
+        self.quic_retry.load(Ordering::Relaxed)
+    }
+
     pub fn add_dropped(&self) {
         self.dropped.fetch_add(1, Ordering::Relaxed);
     }
@@ -122,3 +172,34 @@ impl ListenStats {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn ready_with_listen() {
+        let stats = ListenStats::new(&NodeName::from_str("test").unwrap());
+        assert!(!stats.is_ready());
+
+        stats.set_expected_runtime_count(2);
+        assert!(!stats.is_ready());
+        stats.add_running_runtime();
+        assert!(!stats.is_ready());
+        stats.add_running_runtime();
+        assert!(stats.is_ready());
+
+        stats.del_running_runtime();
+        assert!(!stats.is_ready());
+    }
+
+    #[test]
+    fn ready_without_listen() {
+        let stats = ListenStats::new(&NodeName::from_str("test").unwrap());
+        assert!(!stats.is_ready());
+
+        stats.set_no_listen();
+        assert!(stats.is_ready());
+    }
+}
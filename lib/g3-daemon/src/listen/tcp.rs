@@ -246,6 +246,7 @@ where
                 instance_count = worker_count;
             }
         }
+        self.listen_stats.set_expected_runtime_count(instance_count);
 
         for i in 0..instance_count {
             let mut runtime = self.clone();
@@ -14,22 +14,27 @@
  * limitations under the License.
  */
 
+use std::panic::RefUnwindSafe;
 use std::path::Path;
 use std::sync::Arc;
 
 use anyhow::{anyhow, Context};
-use slog::{slog_o, Logger, OwnedKV, SendSyncRefUnwindSafeKV};
+use slog::{slog_o, Drain, Logger, Never, OwnedKV, SendSyncRefUnwindSafeKV};
 use yaml_rust::Yaml;
 
 use g3_fluentd::FluentdClientConfig;
 #[cfg(target_os = "linux")]
 use g3_journal::JournalConfig;
+use g3_otlp::OtlpExporterConfig;
 use g3_syslog::SyslogBuilder;
 use g3_types::log::AsyncLogConfig;
 
-use super::{LoggerStats, ReportLogIoError};
+use super::{
+    LogLevelFilter, LogRedactDrain, LogRedactor, LoggerStats, MemoryDrain, ReportLogIoError,
+};
 
 const DEFAULT_CHANNEL_SIZE: usize = 4096;
+const DEFAULT_MEMORY_LOG_SIZE: usize = 1024;
 const IO_ERROR_SAMPLING_OFFSET_MAX: usize = 16;
 const IO_ERROR_SAMPLING_OFFSET_DEFAULT: usize = 10;
 
@@ -40,7 +45,9 @@ pub enum LogConfigDriver {
     Journal(JournalConfig),
     Syslog(SyslogBuilder),
     Fluentd(Arc<FluentdClientConfig>),
+    Otlp(Arc<OtlpExporterConfig>),
     Stdout,
+    Memory(usize),
 }
 
 #[derive(Clone)]
@@ -49,6 +56,8 @@ pub struct LogConfig {
     pub(crate) async_channel_size: usize,
     pub(crate) async_thread_number: usize,
     pub(crate) io_err_sampling_mask: usize,
+    pub(crate) memory_tail: usize,
+    pub(crate) redactor: Arc<LogRedactor>,
     pub(crate) program_name: &'static str,
 }
 
@@ -59,6 +68,8 @@ impl LogConfig {
             async_channel_size: DEFAULT_CHANNEL_SIZE,
             async_thread_number: 1,
             io_err_sampling_mask: (1 << IO_ERROR_SAMPLING_OFFSET_DEFAULT) - 1,
+            memory_tail: 0,
+            redactor: Arc::new(LogRedactor::default()),
             program_name,
         }
     }
@@ -71,6 +82,7 @@ impl LogConfig {
             "syslog" => Ok(LogConfig::new_syslog(program_name)),
             "fluentd" => Ok(LogConfig::new_fluentd(program_name)),
             "stdout" => Ok(LogConfig::new_stdout(program_name)),
+            "memory" => Ok(LogConfig::new_memory(program_name)),
             _ => Err(anyhow!("invalid default log config")),
         }
     }
@@ -105,6 +117,13 @@ impl LogConfig {
         Self::with_driver(LogConfigDriver::Stdout, program_name)
     }
 
+    pub fn new_memory(program_name: &'static str) -> Self {
+        Self::with_driver(
+            LogConfigDriver::Memory(DEFAULT_MEMORY_LOG_SIZE),
+            program_name,
+        )
+    }
+
     pub fn parse_yaml(
         v: &Yaml,
         conf_dir: &Path,
@@ -118,6 +137,7 @@ impl LogConfig {
                 "syslog" => Ok(LogConfig::new_syslog(program_name)),
                 "fluentd" => Ok(LogConfig::new_fluentd(program_name)),
                 "stdout" => Ok(LogConfig::new_stdout(program_name)),
+                "memory" => Ok(LogConfig::new_memory(program_name)),
                 _ => Err(anyhow!("invalid log config")),
             },
             Yaml::Hash(map) => {
@@ -130,7 +150,7 @@ impl LogConfig {
                         Ok(())
                     }
                     "syslog" => {
-                        let builder = SyslogBuilder::parse_yaml(v, program_name)
+                        let builder = SyslogBuilder::parse_yaml(v, Some(conf_dir), program_name)
                             .context("invalid syslog config")?;
                         config.driver = LogConfigDriver::Syslog(builder);
                         Ok(())
@@ -141,6 +161,32 @@ impl LogConfig {
                         config.driver = LogConfigDriver::Fluentd(Arc::new(client));
                         Ok(())
                     }
+                    "otlp" => {
+                        let exporter =
+                            OtlpExporterConfig::parse_yaml(v).context("invalid otlp config")?;
+                        config.driver = LogConfigDriver::Otlp(Arc::new(exporter));
+                        Ok(())
+                    }
+                    "memory" => {
+                        let size = g3_yaml::value::as_nonzero_usize(v)
+                            .context(format!("invalid nonzero usize value for key {k}"))?;
+                        config.driver = LogConfigDriver::Memory(size.get());
+                        Ok(())
+                    }
+                    "redact" => Arc::make_mut(&mut config.redactor)
+                        .parse_enable_yaml(v)
+                        .context(format!("invalid bool value for key {k}")),
+                    "redact_keys" => Arc::make_mut(&mut config.redactor)
+                        .parse_keys_yaml(v)
+                        .context(format!("invalid redact keys value for key {k}")),
+                    "redact_patterns" => Arc::make_mut(&mut config.redactor)
+                        .parse_patterns_yaml(v)
+                        .context(format!("invalid redact patterns value for key {k}")),
+                    "memory_tail" => {
+                        config.memory_tail = g3_yaml::value::as_usize(v)
+                            .context(format!("invalid usize value for key {k}"))?;
+                        Ok(())
+                    }
                     "async_channel_size" | "channel_size" => {
                         let channel_size = g3_yaml::value::as_usize(v)
                             .context(format!("invalid usize value for key {k}"))?;
@@ -173,8 +219,13 @@ impl LogConfig {
         }
     }
 
-    pub fn parse_syslog_yaml(v: &Yaml, program_name: &'static str) -> anyhow::Result<LogConfig> {
-        let driver = SyslogBuilder::parse_yaml(v, program_name).context("invalid syslog config")?;
+    pub fn parse_syslog_yaml(
+        v: &Yaml,
+        conf_dir: &Path,
+        program_name: &'static str,
+    ) -> anyhow::Result<LogConfig> {
+        let driver = SyslogBuilder::parse_yaml(v, Some(conf_dir), program_name)
+            .context("invalid syslog config")?;
         Ok(LogConfig::with_driver(
             LogConfigDriver::Syslog(driver),
             program_name,
@@ -194,6 +245,14 @@ impl LogConfig {
         ))
     }
 
+    pub fn parse_otlp_yaml(v: &Yaml, program_name: &'static str) -> anyhow::Result<LogConfig> {
+        let driver = OtlpExporterConfig::parse_yaml(v).context("invalid otlp config")?;
+        Ok(LogConfig::with_driver(
+            LogConfigDriver::Otlp(Arc::new(driver)),
+            program_name,
+        ))
+    }
+
     pub fn build_shared_logger(
         self,
         logger_name: String,
@@ -223,10 +282,16 @@ impl LogConfig {
             thread_name: logger_name.clone(),
         };
 
+        let output = LoggerOutput {
+            logger_name: &logger_name,
+            log_type,
+            memory_tail: self.memory_tail,
+            redactor: self.redactor.clone(),
+        };
         match self.driver {
             LogConfigDriver::Discard => {
                 let drain = slog::Discard {};
-                Logger::root(drain, common_values)
+                output.new_root_logger(drain, common_values)
             }
             #[cfg(target_os = "linux")]
             LogConfigDriver::Journal(journal_conf) => {
@@ -234,14 +299,14 @@ impl LogConfig {
                 let logger_stats = LoggerStats::new(&logger_name, drain.get_stats());
                 super::registry::add(logger_name.clone(), Arc::new(logger_stats));
                 let drain = ReportLogIoError::new(drain, &logger_name, self.io_err_sampling_mask);
-                Logger::root(drain, common_values)
+                output.new_root_logger(drain, common_values)
             }
             LogConfigDriver::Syslog(builder) => {
                 let drain = builder.start_async(&async_conf);
                 let logger_stats = LoggerStats::new(&logger_name, drain.get_stats());
                 super::registry::add(logger_name.clone(), Arc::new(logger_stats));
                 let drain = ReportLogIoError::new(drain, &logger_name, self.io_err_sampling_mask);
-                Logger::root(drain, common_values)
+                output.new_root_logger(drain, common_values)
             }
             LogConfigDriver::Fluentd(fluentd_conf) => {
                 let drain = g3_fluentd::new_async_logger(
@@ -252,19 +317,67 @@ impl LogConfig {
                 let logger_stats = LoggerStats::new(&logger_name, drain.get_stats());
                 super::registry::add(logger_name.clone(), Arc::new(logger_stats));
                 let drain = ReportLogIoError::new(drain, &logger_name, self.io_err_sampling_mask);
-                Logger::root(drain, common_values)
+                output.new_root_logger(drain, common_values)
+            }
+            LogConfigDriver::Otlp(otlp_conf) => {
+                let drain =
+                    g3_otlp::new_async_logger(&async_conf, &otlp_conf, self.program_name, log_type);
+                let logger_stats = LoggerStats::new(&logger_name, drain.get_stats());
+                super::registry::add(logger_name.clone(), Arc::new(logger_stats));
+                let drain = ReportLogIoError::new(drain, &logger_name, self.io_err_sampling_mask);
+                output.new_root_logger(drain, common_values)
+            }
+            LogConfigDriver::Memory(size) => {
+                let drain = MemoryDrain::new(&logger_name, size);
+                output.new_filtered_logger(drain, common_values)
             }
             LogConfigDriver::Stdout => {
                 let drain = g3_stdlog::new_async_logger(&async_conf, false, true);
                 let logger_stats = LoggerStats::new(&logger_name, drain.get_stats());
                 super::registry::add(logger_name.clone(), Arc::new(logger_stats));
                 let drain = slog::IgnoreResult::new(drain);
-                Logger::root(drain, common_values)
+                output.new_root_logger(drain, common_values)
             }
         }
     }
 }
 
+struct LoggerOutput<'a> {
+    logger_name: &'a str,
+    log_type: &'a str,
+    memory_tail: usize,
+    redactor: Arc<LogRedactor>,
+}
+
+impl LoggerOutput<'_> {
+    fn new_root_logger<D, T>(&self, drain: D, common_values: OwnedKV<T>) -> Logger
+    where
+        D: Drain<Ok = (), Err = Never> + Send + Sync + RefUnwindSafe + 'static,
+        T: SendSyncRefUnwindSafeKV + 'static,
+    {
+        if self.memory_tail > 0 {
+            let memory_drain = MemoryDrain::new(self.logger_name, self.memory_tail);
+            let drain = slog::IgnoreResult::new(slog::Duplicate::new(drain, memory_drain));
+            self.new_filtered_logger(drain, common_values)
+        } else {
+            self.new_filtered_logger(drain, common_values)
+        }
+    }
+
+    fn new_filtered_logger<D, T>(&self, drain: D, common_values: OwnedKV<T>) -> Logger
+    where
+        D: Drain<Ok = (), Err = Never> + Send + Sync + RefUnwindSafe + 'static,
+        T: SendSyncRefUnwindSafeKV + 'static,
+    {
+        if self.redactor.is_empty() {
+            Logger::root(LogLevelFilter::new(drain, self.log_type), common_values)
+        } else {
+            let drain = LogRedactDrain::new(drain, self.redactor.clone());
+            Logger::root(LogLevelFilter::new(drain, self.log_type), common_values)
+        }
+    }
+}
+
 pub struct LogConfigContainer {
     inner: Option<LogConfig>,
 }
@@ -0,0 +1,146 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+
+use ahash::AHashMap;
+use anyhow::anyhow;
+use slog::{Drain, Level, Never, OwnedKVList, Record};
+
+static LOG_LEVEL_REGISTRY: LazyLock<Mutex<AHashMap<String, Arc<AtomicUsize>>>> =
+    LazyLock::new(|| Mutex::new(AHashMap::new()));
+
+/// the max level that the log macros are compiled with, see the slog features we enabled
+const fn static_max_level() -> Level {
+    if cfg!(debug_assertions) {
+        Level::Trace
+    } else {
+        Level::Info
+    }
+}
+
+fn get_level_handle(log_type: &str) -> Arc<AtomicUsize> {
+    // log type is case-insensitive
+    let log_type = log_type.to_ascii_lowercase();
+    let mut ht = LOG_LEVEL_REGISTRY.lock().unwrap();
+    ht.entry(log_type)
+        .or_insert_with(|| Arc::new(AtomicUsize::new(static_max_level().as_usize())))
+        .clone()
+}
+
+fn find_level_handle(log_type: &str) -> Option<Arc<AtomicUsize>> {
+    let log_type = log_type.to_ascii_lowercase();
+    let ht = LOG_LEVEL_REGISTRY.lock().unwrap();
+    ht.get(&log_type).cloned()
+}
+
+/// Set the min level of the records to keep for all loggers of this log type
+pub fn set_log_level(log_type: &str, level: Level) -> anyhow::Result<()> {
+    let max_level = static_max_level();
+    if !level.is_at_least(max_level) {
+        return Err(anyhow!(
+            "log level {} is not available in this build, the max level is {}",
+            level.as_str(),
+            max_level.as_str()
+        ));
+    }
+    let handle = find_level_handle(log_type)
+        .ok_or_else(|| anyhow!("no logger found for log type {log_type}"))?;
+    handle.store(level.as_usize(), Ordering::Relaxed);
+    Ok(())
+}
+
+/// Get the min level of the records to keep for all loggers of this log type
+pub fn get_log_level(log_type: &str) -> Option<Level> {
+    let v = find_level_handle(log_type)?.load(Ordering::Relaxed);
+    Some(Level::from_usize(v).unwrap_or(static_max_level()))
+}
+
+/// drop the records below the runtime adjustable level of the log type
+pub(super) struct LogLevelFilter<D> {
+    inner: D,
+    level: Arc<AtomicUsize>,
+}
+
+impl<D> LogLevelFilter<D> {
+    pub(super) fn new(drain: D, log_type: &str) -> Self {
+        LogLevelFilter {
+            inner: drain,
+            level: get_level_handle(log_type),
+        }
+    }
+
+    #[inline]
+    fn accept(&self, level: Level) -> bool {
+        level.as_usize() <= self.level.load(Ordering::Relaxed)
+    }
+}
+
+impl<D: Drain<Ok = (), Err = Never>> Drain for LogLevelFilter<D> {
+    type Ok = ();
+    type Err = Never;
+
+    fn log(&self, record: &Record, logger_values: &OwnedKVList) -> Result<(), Never> {
+        if self.accept(record.level()) {
+            self.inner.log(record, logger_values)
+        } else {
+            Ok(())
+        }
+    }
+
+    #[inline]
+    fn is_enabled(&self, level: Level) -> bool {
+        self.accept(level) && self.inner.is_enabled(level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_log_type() {
+        assert!(get_log_level("level-test-unknown").is_none());
+        assert!(set_log_level("level-test-unknown", Level::Error).is_err());
+        assert!(get_log_level("level-test-unknown").is_none());
+    }
+
+    #[test]
+    fn set_level() {
+        let filter = LogLevelFilter::new(slog::Discard, "Level-Test-Set");
+        assert_eq!(get_log_level("level-test-set"), Some(static_max_level()));
+        assert!(filter.accept(Level::Info));
+
+        set_log_level("LEVEL-TEST-SET", Level::Warning).unwrap();
+        assert_eq!(get_log_level("level-test-set"), Some(Level::Warning));
+        assert!(filter.accept(Level::Error));
+        assert!(filter.accept(Level::Warning));
+        assert!(!filter.accept(Level::Info));
+    }
+
+    #[test]
+    fn static_max() {
+        let _filter = LogLevelFilter::new(slog::Discard, "level-test-max");
+        set_log_level("level-test-max", Level::Info).unwrap();
+        if cfg!(debug_assertions) {
+            assert!(set_log_level("level-test-max", Level::Trace).is_ok());
+        } else {
+            assert!(set_log_level("level-test-max", Level::Debug).is_err());
+            assert_eq!(get_log_level("level-test-max"), Some(Level::Info));
+        }
+    }
+}
@@ -0,0 +1,223 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::VecDeque;
+use std::fmt::Arguments;
+use std::sync::{Arc, LazyLock, Mutex, Weak};
+
+use ahash::AHashMap;
+use chrono::{SecondsFormat, Utc};
+use serde_json::{Map, Value};
+use slog::{Drain, Key, Never, OwnedKVList, Record, Serializer, KV};
+
+// only weak references are kept here, so the buffer will be gone with the logger
+static MEMORY_LOG_REGISTRY: LazyLock<Mutex<AHashMap<String, Weak<MemoryLogBuffer>>>> =
+    LazyLock::new(|| Mutex::new(AHashMap::new()));
+
+struct MemoryLogBuffer {
+    capacity: usize,
+    records: Mutex<VecDeque<Arc<str>>>,
+}
+
+impl MemoryLogBuffer {
+    fn push(&self, record: Arc<str>) {
+        let mut records = self.records.lock().unwrap();
+        if records.len() >= self.capacity {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+}
+
+/// keep the recent log records in a bounded ring buffer, which can be dumped on demand
+pub(super) struct MemoryDrain {
+    buffer: Arc<MemoryLogBuffer>,
+}
+
+impl MemoryDrain {
+    pub(super) fn new(logger_name: &str, capacity: usize) -> Self {
+        let buffer = Arc::new(MemoryLogBuffer {
+            capacity,
+            records: Mutex::new(VecDeque::with_capacity(capacity)),
+        });
+        let mut ht = MEMORY_LOG_REGISTRY.lock().unwrap();
+        ht.retain(|_, v| v.strong_count() > 0);
+        ht.insert(logger_name.to_string(), Arc::downgrade(&buffer));
+        MemoryDrain { buffer }
+    }
+}
+
+impl Drain for MemoryDrain {
+    type Ok = ();
+    type Err = Never;
+
+    fn log(&self, record: &Record, logger_values: &OwnedKVList) -> Result<(), Never> {
+        let mut map = Map::new();
+        map.insert(
+            "time".to_string(),
+            Value::String(Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true)),
+        );
+        map.insert(
+            "level".to_string(),
+            Value::String(record.level().as_str().to_string()),
+        );
+
+        let mut serializer = JsonSerializer(&mut map);
+        let _ = logger_values.serialize(record, &mut serializer);
+        let _ = record.kv().serialize(record, &mut serializer);
+
+        map.insert("msg".to_string(), Value::String(record.msg().to_string()));
+        // serialize before taking the lock, the dump will only need to concat the records
+        let record = Value::Object(map).to_string();
+        self.buffer.push(Arc::from(record));
+        Ok(())
+    }
+}
+
+struct JsonSerializer<'a>(&'a mut Map<String, Value>);
+
+impl JsonSerializer<'_> {
+    fn insert(&mut self, key: Key, value: Value) -> slog::Result {
+        self.0.insert(key.to_string(), value);
+        Ok(())
+    }
+}
+
+impl Serializer for JsonSerializer<'_> {
+    fn emit_usize(&mut self, key: Key, val: usize) -> slog::Result {
+        self.insert(key, Value::from(val))
+    }
+
+    fn emit_isize(&mut self, key: Key, val: isize) -> slog::Result {
+        self.insert(key, Value::from(val))
+    }
+
+    fn emit_bool(&mut self, key: Key, val: bool) -> slog::Result {
+        self.insert(key, Value::Bool(val))
+    }
+
+    fn emit_u8(&mut self, key: Key, val: u8) -> slog::Result {
+        self.insert(key, Value::from(val))
+    }
+
+    fn emit_i8(&mut self, key: Key, val: i8) -> slog::Result {
+        self.insert(key, Value::from(val))
+    }
+
+    fn emit_u16(&mut self, key: Key, val: u16) -> slog::Result {
+        self.insert(key, Value::from(val))
+    }
+
+    fn emit_i16(&mut self, key: Key, val: i16) -> slog::Result {
+        self.insert(key, Value::from(val))
+    }
+
+    fn emit_u32(&mut self, key: Key, val: u32) -> slog::Result {
+        self.insert(key, Value::from(val))
+    }
+
+    fn emit_i32(&mut self, key: Key, val: i32) -> slog::Result {
+        self.insert(key, Value::from(val))
+    }
+
+    fn emit_f32(&mut self, key: Key, val: f32) -> slog::Result {
+        self.insert(key, Value::from(val))
+    }
+
+    fn emit_u64(&mut self, key: Key, val: u64) -> slog::Result {
+        self.insert(key, Value::from(val))
+    }
+
+    fn emit_i64(&mut self, key: Key, val: i64) -> slog::Result {
+        self.insert(key, Value::from(val))
+    }
+
+    fn emit_f64(&mut self, key: Key, val: f64) -> slog::Result {
+        self.insert(key, Value::from(val))
+    }
+
+    fn emit_none(&mut self, key: Key) -> slog::Result {
+        self.insert(key, Value::Null)
+    }
+
+    fn emit_str(&mut self, key: Key, val: &str) -> slog::Result {
+        self.insert(key, Value::String(val.to_string()))
+    }
+
+    fn emit_arguments(&mut self, key: Key, val: &Arguments) -> slog::Result {
+        self.insert(key, Value::String(val.to_string()))
+    }
+}
+
+/// Dump the log records kept in memory for the logger as a JSON array
+pub fn dump_memory_logs(logger_name: &str) -> Option<String> {
+    let buffer = {
+        let ht = MEMORY_LOG_REGISTRY.lock().unwrap();
+        ht.get(logger_name).and_then(|v| v.upgrade())
+    }?;
+    let records: Vec<Arc<str>> = {
+        let records = buffer.records.lock().unwrap();
+        records.iter().cloned().collect()
+    };
+
+    let total_len = records.iter().map(|r| r.len() + 1).sum::<usize>() + 1;
+    let mut s = String::with_capacity(total_len);
+    s.push('[');
+    for (i, r) in records.iter().enumerate() {
+        if i > 0 {
+            s.push(',');
+        }
+        s.push_str(r);
+    }
+    s.push(']');
+    Some(s)
+}
+
+/// Get the names of all loggers that have log records kept in memory
+pub fn memory_logger_names() -> Vec<String> {
+    let mut ht = MEMORY_LOG_REGISTRY.lock().unwrap();
+    ht.retain(|_, v| v.strong_count() > 0);
+    let mut names: Vec<String> = ht.keys().cloned().collect();
+    names.sort_unstable();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slog::{slog_info, slog_o, Logger};
+
+    #[test]
+    fn dump_and_drop() {
+        let logger = Logger::root(MemoryDrain::new("memory-test", 2), slog_o!("a" => 1));
+        slog_info!(logger, "msg1"; "b" => "x");
+        slog_info!(logger, "msg2");
+        slog_info!(logger, "msg3");
+
+        let s = dump_memory_logs("memory-test").unwrap();
+        let v: Value = serde_json::from_str(&s).unwrap();
+        let records = v.as_array().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["msg"], "msg2");
+        assert_eq!(records[0]["a"], 1);
+        assert_eq!(records[1]["msg"], "msg3");
+        assert!(memory_logger_names().contains(&"memory-test".to_string()));
+
+        drop(logger);
+        assert!(dump_memory_logs("memory-test").is_none());
+        assert!(!memory_logger_names().contains(&"memory-test".to_string()));
+    }
+}
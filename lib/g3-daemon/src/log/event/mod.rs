@@ -23,6 +23,19 @@ pub(crate) use stats::LoggerStats;
 pub mod metrics;
 
 mod registry;
+pub use registry::{set_flush_timeout, wait_flush};
+
+mod level;
+use level::LogLevelFilter;
+pub use level::{get_log_level, set_log_level};
+
+mod redact;
+use redact::LogRedactDrain;
+pub use redact::LogRedactor;
+
+mod memory;
+use memory::MemoryDrain;
+pub use memory::{dump_memory_logs, memory_logger_names};
 
 mod config;
 pub use config::{LogConfig, LogConfigContainer, LogConfigDriver};
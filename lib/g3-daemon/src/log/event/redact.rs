@@ -0,0 +1,506 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::cell::RefCell;
+use std::fmt::{Arguments, Write};
+use std::sync::Arc;
+
+use ahash::AHashSet;
+use anyhow::{anyhow, Context};
+use regex::{Regex, RegexSet};
+use slog::{
+    slog_o, Drain, Key, Level, Never, OwnedKVList, Record, RecordStatic, SerdeValue, Serializer, KV,
+};
+use yaml_rust::Yaml;
+
+const REDACTED_VALUE: &str = "***";
+
+const DEFAULT_REDACT_KEYS: &[&str] = &[
+    "authorization",
+    "proxy_authorization",
+    "cookie",
+    "set_cookie",
+    "password",
+    "passwd",
+    "token",
+];
+const DEFAULT_REDACT_PATTERNS: &[&str] = &[
+    // the credential in basic auth headers
+    r"(?i)\bbasic\s+[a-z0-9+/]+=*",
+];
+
+thread_local! {
+    static TL_BUF: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+#[derive(Clone)]
+pub struct LogRedactor {
+    keys: AHashSet<String>,
+    patterns: Vec<Regex>,
+    pattern_set: Option<RegexSet>,
+}
+
+impl Default for LogRedactor {
+    /// The sensitive values will be masked by default
+    fn default() -> Self {
+        LogRedactor::with_default_rules()
+    }
+}
+
+impl LogRedactor {
+    fn empty() -> Self {
+        LogRedactor {
+            keys: AHashSet::new(),
+            patterns: Vec::new(),
+            pattern_set: None,
+        }
+    }
+
+    fn with_default_rules() -> Self {
+        let mut redactor = LogRedactor {
+            keys: DEFAULT_REDACT_KEYS.iter().map(|s| s.to_string()).collect(),
+            patterns: DEFAULT_REDACT_PATTERNS
+                .iter()
+                .map(|s| Regex::new(s).unwrap())
+                .collect(),
+            pattern_set: None,
+        };
+        redactor.build_pattern_set().unwrap();
+        redactor
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.keys.is_empty() && self.patterns.is_empty()
+    }
+
+    fn build_pattern_set(&mut self) -> anyhow::Result<()> {
+        if self.patterns.is_empty() {
+            self.pattern_set = None;
+        } else {
+            let set = RegexSet::new(self.patterns.iter().map(|r| r.as_str()))
+                .map_err(|e| anyhow!("failed to build regex set: {e}"))?;
+            self.pattern_set = Some(set);
+        }
+        Ok(())
+    }
+
+    pub(super) fn parse_enable_yaml(&mut self, v: &Yaml) -> anyhow::Result<()> {
+        if g3_yaml::value::as_bool(v)? {
+            // keep the rules that have already been set
+            let default = LogRedactor::with_default_rules();
+            if self.keys.is_empty() {
+                self.keys = default.keys;
+            }
+            if self.patterns.is_empty() {
+                self.patterns = default.patterns;
+                self.pattern_set = default.pattern_set;
+            }
+        } else {
+            *self = LogRedactor::empty();
+        }
+        Ok(())
+    }
+
+    pub(super) fn parse_keys_yaml(&mut self, v: &Yaml) -> anyhow::Result<()> {
+        if let Yaml::Array(seq) = v {
+            self.keys.clear();
+            for (i, v) in seq.iter().enumerate() {
+                let key =
+                    g3_yaml::value::as_string(v).context(format!("invalid string value #{i}"))?;
+                self.keys.insert(key);
+            }
+            Ok(())
+        } else {
+            Err(anyhow!("the yaml value type should be 'seq'"))
+        }
+    }
+
+    pub(super) fn parse_patterns_yaml(&mut self, v: &Yaml) -> anyhow::Result<()> {
+        if let Yaml::Array(seq) = v {
+            self.patterns.clear();
+            for (i, v) in seq.iter().enumerate() {
+                let s =
+                    g3_yaml::value::as_string(v).context(format!("invalid string value #{i}"))?;
+                let regex = Regex::new(&s).map_err(|e| anyhow!("invalid regex value #{i}: {e}"))?;
+                self.patterns.push(regex);
+            }
+            self.build_pattern_set()
+        } else {
+            Err(anyhow!("the yaml value type should be 'seq'"))
+        }
+    }
+
+    #[inline]
+    fn is_redact_key(&self, key: &str) -> bool {
+        self.keys.contains(key)
+    }
+
+    fn redact_str(&self, value: &str) -> Option<String> {
+        // match all patterns in a single pass, and only allocate if there is a match
+        let set = self.pattern_set.as_ref()?;
+        let matches = set.matches(value);
+        if !matches.matched_any() {
+            return None;
+        }
+        let mut redacted = value.to_string();
+        for i in matches.iter() {
+            let regex = &self.patterns[i];
+            if let std::borrow::Cow::Owned(s) = regex.replace_all(&redacted, REDACTED_VALUE) {
+                redacted = s;
+            }
+        }
+        Some(redacted)
+    }
+
+    fn redact_json(&self, value: &mut serde_json::Value) -> bool {
+        match value {
+            serde_json::Value::String(s) => {
+                if let Some(redacted) = self.redact_str(s) {
+                    *s = redacted;
+                    true
+                } else {
+                    false
+                }
+            }
+            serde_json::Value::Array(seq) => {
+                let mut changed = false;
+                for v in seq {
+                    changed |= self.redact_json(v);
+                }
+                changed
+            }
+            serde_json::Value::Object(map) => {
+                let mut changed = false;
+                for (k, v) in map.iter_mut() {
+                    if self.is_redact_key(k) {
+                        *v = serde_json::Value::String(REDACTED_VALUE.to_string());
+                        changed = true;
+                    } else {
+                        changed |= self.redact_json(v);
+                    }
+                }
+                changed
+            }
+            _ => false,
+        }
+    }
+}
+
+/// mask the sensitive key-values of the record before sending to the inner drain
+pub(super) struct LogRedactDrain<D> {
+    inner: D,
+    redactor: Arc<LogRedactor>,
+    empty_values: OwnedKVList,
+}
+
+impl<D> LogRedactDrain<D> {
+    pub(super) fn new(drain: D, redactor: Arc<LogRedactor>) -> Self {
+        LogRedactDrain {
+            inner: drain,
+            redactor,
+            empty_values: OwnedKVList::from(slog_o!()),
+        }
+    }
+}
+
+impl<D: Drain<Ok = (), Err = Never>> Drain for LogRedactDrain<D> {
+    type Ok = ();
+    type Err = Never;
+
+    fn log(&self, record: &Record, logger_values: &OwnedKVList) -> Result<(), Never> {
+        // the logger values are moved into the record kv, so they will also be redacted
+        let redact_kv = RedactKV {
+            logger_values,
+            record_kv: record.kv(),
+            redactor: &self.redactor,
+        };
+        let record_static = RecordStatic {
+            location: record.location(),
+            tag: record.tag(),
+            level: record.level(),
+        };
+        let record = Record::new(&record_static, record.msg(), slog::BorrowedKV(&redact_kv));
+        self.inner.log(&record, &self.empty_values)
+    }
+
+    #[inline]
+    fn is_enabled(&self, level: Level) -> bool {
+        self.inner.is_enabled(level)
+    }
+}
+
+struct RedactKV<'a> {
+    logger_values: &'a OwnedKVList,
+    record_kv: slog::BorrowedKV<'a>,
+    redactor: &'a LogRedactor,
+}
+
+impl KV for RedactKV<'_> {
+    fn serialize(&self, record: &Record, serializer: &mut dyn Serializer) -> slog::Result {
+        let mut redact_serializer = RedactSerializer {
+            inner: serializer,
+            redactor: self.redactor,
+        };
+        // keep the same order as the drains serialize them
+        self.logger_values
+            .serialize(record, &mut redact_serializer)?;
+        self.record_kv.serialize(record, &mut redact_serializer)
+    }
+}
+
+struct RedactSerializer<'a> {
+    inner: &'a mut dyn Serializer,
+    redactor: &'a LogRedactor,
+}
+
+impl RedactSerializer<'_> {
+    #[inline]
+    fn is_redact_key(&self, key: &Key) -> bool {
+        self.redactor.is_redact_key(key)
+    }
+}
+
+macro_rules! impl_redact_emit {
+    ($f:ident, $t:ty) => {
+        fn $f(&mut self, key: Key, val: $t) -> slog::Result {
+            if self.is_redact_key(&key) {
+                self.inner.emit_str(key, REDACTED_VALUE)
+            } else {
+                self.inner.$f(key, val)
+            }
+        }
+    };
+}
+
+impl Serializer for RedactSerializer<'_> {
+    impl_redact_emit!(emit_usize, usize);
+    impl_redact_emit!(emit_isize, isize);
+    impl_redact_emit!(emit_bool, bool);
+    impl_redact_emit!(emit_char, char);
+    impl_redact_emit!(emit_u8, u8);
+    impl_redact_emit!(emit_i8, i8);
+    impl_redact_emit!(emit_u16, u16);
+    impl_redact_emit!(emit_i16, i16);
+    impl_redact_emit!(emit_u32, u32);
+    impl_redact_emit!(emit_i32, i32);
+    impl_redact_emit!(emit_f32, f32);
+    impl_redact_emit!(emit_u64, u64);
+    impl_redact_emit!(emit_i64, i64);
+    impl_redact_emit!(emit_f64, f64);
+
+    fn emit_none(&mut self, key: Key) -> slog::Result {
+        self.inner.emit_none(key)
+    }
+
+    fn emit_unit(&mut self, key: Key) -> slog::Result {
+        self.inner.emit_unit(key)
+    }
+
+    fn emit_str(&mut self, key: Key, val: &str) -> slog::Result {
+        if self.is_redact_key(&key) {
+            self.inner.emit_str(key, REDACTED_VALUE)
+        } else if let Some(redacted) = self.redactor.redact_str(val) {
+            self.inner.emit_str(key, &redacted)
+        } else {
+            self.inner.emit_str(key, val)
+        }
+    }
+
+    fn emit_arguments(&mut self, key: Key, val: &Arguments) -> slog::Result {
+        if self.is_redact_key(&key) {
+            return self.inner.emit_str(key, REDACTED_VALUE);
+        }
+        if self.redactor.pattern_set.is_none() {
+            return self.inner.emit_arguments(key, val);
+        }
+        if let Some(s) = val.as_str() {
+            self.emit_str(key, s)
+        } else {
+            TL_BUF.with_borrow_mut(|buf| {
+                buf.clear();
+                let _ = buf.write_fmt(*val);
+                match self.redactor.redact_str(buf) {
+                    Some(redacted) => self.inner.emit_str(key, &redacted),
+                    None => self.inner.emit_str(key, buf),
+                }
+            })
+        }
+    }
+
+    fn emit_serde(&mut self, key: Key, val: &dyn SerdeValue) -> slog::Result {
+        if self.is_redact_key(&key) {
+            return self.inner.emit_str(key, REDACTED_VALUE);
+        }
+        // nested values need to be converted to check the inner fields
+        let Ok(mut value) = serde_json::to_value(val.as_serde()) else {
+            return self.inner.emit_serde(key, val);
+        };
+        if self.redactor.redact_json(&mut value) {
+            self.inner.emit_serde(key, &RedactedSerdeValue(value))
+        } else {
+            self.inner.emit_serde(key, val)
+        }
+    }
+}
+
+struct RedactedSerdeValue(serde_json::Value);
+
+impl slog::Value for RedactedSerdeValue {
+    fn serialize(
+        &self,
+        _record: &Record,
+        key: Key,
+        serializer: &mut dyn Serializer,
+    ) -> slog::Result {
+        serializer.emit_serde(key, self)
+    }
+}
+
+impl SerdeValue for RedactedSerdeValue {
+    fn serialize_fallback(&self, key: Key, serializer: &mut dyn Serializer) -> slog::Result {
+        serializer.emit_str(key, &self.0.to_string())
+    }
+
+    fn as_serde(&self) -> &dyn erased_serde::Serialize {
+        &self.0
+    }
+
+    fn to_sendable(&self) -> Box<dyn SerdeValue + Send + 'static> {
+        Box::new(RedactedSerdeValue(self.0.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    use serde_json::json;
+    use slog::{slog_info, Logger};
+
+    #[derive(Clone, Default)]
+    struct CollectDrain(Arc<Mutex<Vec<(String, String)>>>);
+
+    struct CollectSerializer<'a>(&'a mut Vec<(String, String)>);
+
+    impl Serializer for CollectSerializer<'_> {
+        fn emit_arguments(&mut self, key: Key, val: &Arguments) -> slog::Result {
+            self.0.push((key.to_string(), val.to_string()));
+            Ok(())
+        }
+
+        fn emit_serde(&mut self, key: Key, val: &dyn SerdeValue) -> slog::Result {
+            let v = serde_json::to_value(val.as_serde()).unwrap();
+            self.0.push((key.to_string(), v.to_string()));
+            Ok(())
+        }
+    }
+
+    impl Drain for CollectDrain {
+        type Ok = ();
+        type Err = Never;
+
+        fn log(&self, record: &Record, values: &OwnedKVList) -> Result<(), Never> {
+            let mut kvs = self.0.lock().unwrap();
+            let mut serializer = CollectSerializer(&mut kvs);
+            values.serialize(record, &mut serializer).unwrap();
+            record.kv().serialize(record, &mut serializer).unwrap();
+            Ok(())
+        }
+    }
+
+    fn take_kvs(drain: &CollectDrain) -> Vec<(String, String)> {
+        std::mem::take(&mut *drain.0.lock().unwrap())
+    }
+
+    #[test]
+    fn enabled_by_default() {
+        assert!(!LogRedactor::default().is_empty());
+        assert!(LogRedactor::empty().is_empty());
+
+        let mut redactor = LogRedactor::default();
+        let yaml = yaml_rust::YamlLoader::load_from_str("false").unwrap();
+        redactor.parse_enable_yaml(&yaml[0]).unwrap();
+        assert!(redactor.is_empty());
+    }
+
+    #[test]
+    fn redact_record_and_logger_values() {
+        let collect = CollectDrain::default();
+        let redactor = Arc::new(LogRedactor::with_default_rules());
+        let drain = LogRedactDrain::new(collect.clone(), redactor);
+        let logger = Logger::root(drain, slog_o!("token" => "secret", "server" => "s1"));
+
+        slog_info!(logger, "test"; "password" => 1234, "auth" => "Basic dGVzdDp0ZXN0", "user" => "u1");
+        let kvs = take_kvs(&collect);
+        let get = |k: &str| {
+            kvs.iter()
+                .find(|(key, _)| key == k)
+                .map(|(_, v)| v.as_str())
+        };
+        assert_eq!(get("token"), Some("***"));
+        assert_eq!(get("server"), Some("s1"));
+        assert_eq!(get("password"), Some("***"));
+        assert_eq!(get("auth"), Some("***"));
+        assert_eq!(get("user"), Some("u1"));
+
+        let child = logger.new(slog_o!("cookie" => "c=1"));
+        slog_info!(child, "test"; "x" => %"1 basic YWJj");
+        let kvs = take_kvs(&collect);
+        let get = |k: &str| {
+            kvs.iter()
+                .find(|(key, _)| key == k)
+                .map(|(_, v)| v.as_str())
+        };
+        assert_eq!(get("cookie"), Some("***"));
+        assert_eq!(get("token"), Some("***"));
+        assert_eq!(get("x"), Some("1 ***"));
+    }
+
+    #[test]
+    fn redact_nested_values() {
+        let collect = CollectDrain::default();
+        let redactor = Arc::new(LogRedactor::with_default_rules());
+        let drain = LogRedactDrain::new(collect.clone(), redactor);
+        let logger = Logger::root(drain, slog_o!());
+
+        let nested = RedactedSerdeValue(json!({
+            "user": "u1",
+            "password": "p1",
+            "headers": ["Authorization: Basic dGVzdDp0ZXN0"],
+        }));
+        slog_info!(logger, "test"; "nested" => nested);
+        let kvs = take_kvs(&collect);
+        let value: serde_json::Value = serde_json::from_str(&kvs[0].1).unwrap();
+        assert_eq!(value["user"], "u1");
+        assert_eq!(value["password"], "***");
+        assert_eq!(value["headers"][0], "Authorization: ***");
+    }
+
+    #[test]
+    fn redact_patterns() {
+        let mut redactor = LogRedactor::empty();
+        let yaml = yaml_rust::YamlLoader::load_from_str("[\"a+\", \"b+\"]").unwrap();
+        redactor.parse_patterns_yaml(&yaml[0]).unwrap();
+        assert_eq!(redactor.redact_str("xaabbx").unwrap(), "x******x");
+        assert!(redactor.redact_str("xyz").is_none());
+
+        let yaml = yaml_rust::YamlLoader::load_from_str("[]").unwrap();
+        redactor.parse_patterns_yaml(&yaml[0]).unwrap();
+        assert!(redactor.redact_str("xaabbx").is_none());
+        assert!(redactor.is_empty());
+    }
+}
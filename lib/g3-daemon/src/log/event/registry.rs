@@ -16,9 +16,17 @@
 
 use std::collections::HashMap;
 use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use log::warn;
 
 use super::LoggerStats;
 
+const FLUSH_CHECK_INTERVAL: Duration = Duration::from_millis(10);
+const DEFAULT_FLUSH_TIMEOUT: Duration = Duration::from_secs(4);
+
+static FLUSH_TIMEOUT: Mutex<Duration> = Mutex::new(DEFAULT_FLUSH_TIMEOUT);
+
 static RUNTIME_LOGGER_REGISTRY: LazyLock<Mutex<HashMap<String, Arc<LoggerStats>>>> =
     LazyLock::new(|| Mutex::new(HashMap::new()));
 
@@ -36,3 +44,70 @@ where
         f(name, server)
     }
 }
+
+/// Set the max time to wait in [wait_flush]
+pub fn set_flush_timeout(timeout: Duration) {
+    *FLUSH_TIMEOUT.lock().unwrap() = timeout;
+}
+
+/// Block until all the registered async loggers have handled their pending records,
+/// or until the flush timeout reached. The number of records still pending will be returned.
+pub fn wait_flush() -> u64 {
+    let timeout = *FLUSH_TIMEOUT.lock().unwrap();
+    wait_flush_with_timeout(timeout)
+}
+
+fn wait_flush_with_timeout(timeout: Duration) -> u64 {
+    let all_loggers: Vec<Arc<LoggerStats>> = {
+        let ht = RUNTIME_LOGGER_REGISTRY.lock().unwrap();
+        ht.values().cloned().collect()
+    };
+
+    let start = Instant::now();
+    loop {
+        let pending: u64 = all_loggers.iter().map(|s| s.pending()).sum();
+        if pending == 0 {
+            return 0;
+        }
+        if start.elapsed() >= timeout {
+            for stats in &all_loggers {
+                let pending = stats.pending();
+                if pending > 0 {
+                    warn!(
+                        "logger {}: {pending} records still pending after waiting for {timeout:?}",
+                        stats.name()
+                    );
+                }
+            }
+            return pending;
+        }
+        std::thread::sleep(FLUSH_CHECK_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use g3_types::log::LogStats;
+
+    #[test]
+    fn pending_to_zero() {
+        let inner = Arc::new(LogStats::default());
+        add(
+            "registry-test".to_string(),
+            Arc::new(LoggerStats::new("registry-test", inner.clone())),
+        );
+
+        for _ in 0..5 {
+            inner.io.add_total();
+        }
+        inner.io.add_passed();
+        inner.drop.add_channel_overflow();
+        assert_eq!(wait_flush_with_timeout(Duration::ZERO), 3);
+
+        inner.io.add_passed();
+        inner.drop.add_format_failed();
+        inner.drop.add_peer_unreachable();
+        assert_eq!(wait_flush_with_timeout(Duration::from_millis(100)), 0);
+    }
+}
@@ -45,4 +45,15 @@ impl LoggerStats {
     pub(crate) fn inner(&self) -> &Arc<LogStats> {
         &self.inner
     }
+
+    /// the number of records that have been accepted but not yet sent out or dropped
+    pub(crate) fn pending(&self) -> u64 {
+        let snap = self.inner.snapshot();
+        let handled = snap.io.passed
+            + snap.drop.format_failed
+            + snap.drop.channel_closed
+            + snap.drop.channel_overflow
+            + snap.drop.peer_unreachable;
+        snap.io.total.saturating_sub(handled)
+    }
 }
@@ -22,7 +22,11 @@ use super::ServerMetricExt;
 use crate::listen::{ListenSnapshot, ListenStats};
 
 const METRIC_NAME_LISTEN_INSTANCE_COUNT: &str = "listen.instance.count";
+const METRIC_NAME_LISTEN_READY: &str = "listen.ready";
 const METRIC_NAME_LISTEN_ACCEPTED: &str = "listen.accepted";
+const METRIC_NAME_LISTEN_UDP_GSO_COALESCED: &str = "listen.udp.gso_coalesced";
+const METRIC_NAME_LISTEN_UDP_GRO_COALESCED: &str = "listen.udp.gro_coalesced";
+const METRIC_NAME_LISTEN_QUIC_RETRY: &str = "listen.quic.retry";
 const METRIC_NAME_LISTEN_DROPPED: &str = "listen.dropped";
 const METRIC_NAME_LISTEN_TIMEOUT: &str = "listen.timeout";
 const METRIC_NAME_LISTEN_FAILED: &str = "listen.failed";
@@ -42,6 +46,13 @@ pub fn emit_listen_stats(
             &common_tags,
         )
         .send();
+    client
+        .gauge_with_tags(
+            METRIC_NAME_LISTEN_READY,
+            u8::from(stats.is_ready()),
+            &common_tags,
+        )
+        .send();
 
     macro_rules! emit_field {
         ($field:ident, $name:expr) => {
@@ -57,6 +68,9 @@ pub fn emit_listen_stats(
     }
 
     emit_field!(accepted, METRIC_NAME_LISTEN_ACCEPTED);
+    emit_field!(udp_gso_coalesced, METRIC_NAME_LISTEN_UDP_GSO_COALESCED);
+    emit_field!(udp_gro_coalesced, METRIC_NAME_LISTEN_UDP_GRO_COALESCED);
+    emit_field!(quic_retry, METRIC_NAME_LISTEN_QUIC_RETRY);
     emit_field!(dropped, METRIC_NAME_LISTEN_DROPPED);
     emit_field!(timeout, METRIC_NAME_LISTEN_TIMEOUT);
     emit_field!(failed, METRIC_NAME_LISTEN_FAILED);
@@ -16,6 +16,7 @@
 
 use std::io;
 use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
 
 use g3_io_ext::haproxy::ProxyAddr;
 use g3_socket::RawSocket;
@@ -30,6 +31,8 @@ pub struct ClientConnectionInfo {
     #[allow(unused)]
     sock_local_addr: SocketAddr,
     tcp_raw_socket: Option<RawSocket>,
+    tls_client_ja3: Option<Arc<str>>,
+    tls_client_ja4: Option<Arc<str>>,
 }
 
 impl ClientConnectionInfo {
@@ -41,6 +44,8 @@ impl ClientConnectionInfo {
             sock_peer_addr: peer_addr,
             sock_local_addr: local_addr,
             tcp_raw_socket: None,
+            tls_client_ja3: None,
+            tls_client_ja4: None,
         }
     }
 
@@ -55,6 +60,16 @@ impl ClientConnectionInfo {
         self.server_addr = addr.dst_addr;
     }
 
+    #[inline]
+    pub fn set_tls_client_ja3(&mut self, ja3: Arc<str>) {
+        self.tls_client_ja3 = Some(ja3);
+    }
+
+    #[inline]
+    pub fn set_tls_client_ja4(&mut self, ja4: Arc<str>) {
+        self.tls_client_ja4 = Some(ja4);
+    }
+
     #[inline]
     pub fn set_worker_id(&mut self, worker_id: Option<usize>) {
         self.worker_id = worker_id;
@@ -100,6 +115,18 @@ impl ClientConnectionInfo {
         self.sock_local_addr
     }
 
+    /// The JA3 hash of the TLS ClientHello message, if captured by the listening server
+    #[inline]
+    pub fn tls_client_ja3(&self) -> Option<&str> {
+        self.tls_client_ja3.as_deref()
+    }
+
+    /// The JA4 fingerprint of the TLS ClientHello message, if captured by the listening server
+    #[inline]
+    pub fn tls_client_ja4(&self) -> Option<&str> {
+        self.tls_client_ja4.as_deref()
+    }
+
     pub fn tcp_sock_set_raw_opts(
         &self,
         opts: &TcpMiscSockOpts,
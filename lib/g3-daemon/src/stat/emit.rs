@@ -27,3 +27,36 @@ pub fn wait_duration(emit_duration: Duration, instant_start: Instant) {
         std::thread::sleep(emit_duration);
     }
 }
+
+/// Get the elapsed seconds since the last emit, which should be used to calculate the per-second
+/// rate of count values. `None` will be returned if rate emitting is disabled, or at the first emit
+/// as there is no valid elapsed time.
+pub fn rate_elapsed_secs(emit_rate: bool, last_emit: &mut Option<Instant>) -> Option<f64> {
+    let now = Instant::now();
+    let last = last_emit.replace(now);
+    if !emit_rate {
+        return None;
+    }
+    last.map(|last| now.duration_since(last).as_secs_f64())
+        .filter(|secs| *secs > 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_elapsed() {
+        let mut last_emit = None;
+        assert!(rate_elapsed_secs(true, &mut last_emit).is_none());
+        assert!(last_emit.is_some());
+
+        last_emit = Some(Instant::now() - Duration::from_secs(2));
+        let secs = rate_elapsed_secs(true, &mut last_emit).unwrap();
+        assert!(secs >= 2.0);
+
+        last_emit = Some(Instant::now() - Duration::from_secs(2));
+        assert!(rate_elapsed_secs(false, &mut last_emit).is_none());
+        assert!(last_emit.unwrap().elapsed() < Duration::from_secs(1));
+    }
+}
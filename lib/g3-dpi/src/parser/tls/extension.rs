@@ -23,6 +23,7 @@ pub enum ExtensionType {
     MaxFragmentLength = 1,                    // rfc6066
     StatusRequest = 5,                        // rfc6066
     SupportedGroups = 10,                     // rfc8422, rfc7919
+    EcPointFormats = 11,                      // rfc8422
     SignatureAlgorithms = 13,                 // rfc8446
     UseSrtp = 14,                             // rfc5764
     Heartbeat = 15,                           // rfc6520
@@ -111,4 +112,19 @@ impl ExtensionList {
 
         Ok(None)
     }
+
+    /// Get all the extension types in order from the raw extensions buf
+    pub(crate) fn ext_types(full_data: &[u8]) -> Result<Vec<u16>, ExtensionParseError> {
+        let mut offset = 0usize;
+        let mut types = Vec::new();
+
+        while offset < full_data.len() {
+            let left = &full_data[offset..];
+            let ext = Extension::parse(left)?;
+            types.push(ext.ext_type);
+            offset += Extension::HEADER_LEN + ext.ext_len as usize;
+        }
+
+        Ok(types)
+    }
 }
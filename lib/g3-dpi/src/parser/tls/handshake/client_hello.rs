@@ -176,6 +176,206 @@ impl<'a> ClientHello<'a> {
 
         ExtensionList::get_ext(data, ext_type)
     }
+
+    /// Get the JA3 fingerprint string, GREASE values are ignored.
+    ///
+    /// The format is `SSLVersion,Ciphers,Extensions,EllipticCurves,EllipticCurvePointFormats`.
+    pub fn ja3_string(&self) -> Result<String, ExtensionParseError> {
+        use std::fmt::Write;
+
+        fn push_u16_list<I: Iterator<Item = u16>>(s: &mut String, iter: I) {
+            let mut first = true;
+            for v in iter.filter(|v| !is_grease(*v)) {
+                if first {
+                    first = false;
+                } else {
+                    s.push('-');
+                }
+                let _ = write!(s, "{v}");
+            }
+        }
+
+        let mut s = String::with_capacity(256);
+        let version = u16::from_be_bytes([self.legacy_version.major, self.legacy_version.minor]);
+        let _ = write!(s, "{version},");
+
+        let ciphers = self
+            .cipher_suites
+            .chunks_exact(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]));
+        push_u16_list(&mut s, ciphers);
+        s.push(',');
+
+        if let Some(data) = self.extensions {
+            let types = ExtensionList::ext_types(data)?;
+            push_u16_list(&mut s, types.into_iter());
+        }
+        s.push(',');
+
+        if let Some(data) = self.get_ext(ExtensionType::SupportedGroups)? {
+            if data.len() < 2 {
+                return Err(ExtensionParseError::InvalidLength);
+            }
+            let list_len = u16::from_be_bytes([data[0], data[1]]) as usize;
+            let Some(list) = data.get(2..2 + list_len) else {
+                return Err(ExtensionParseError::InvalidLength);
+            };
+            let groups = list
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]));
+            push_u16_list(&mut s, groups);
+        }
+        s.push(',');
+
+        if let Some(data) = self.get_ext(ExtensionType::EcPointFormats)? {
+            let Some((list_len, left)) = data.split_first() else {
+                return Err(ExtensionParseError::InvalidLength);
+            };
+            let Some(list) = left.get(..*list_len as usize) else {
+                return Err(ExtensionParseError::InvalidLength);
+            };
+            push_u16_list(&mut s, list.iter().map(|v| *v as u16));
+        }
+
+        Ok(s)
+    }
+}
+
+/// The raw parts of the JA4 fingerprint, GREASE values are ignored.
+///
+/// The JA4 fingerprint is `prefix_hash(ciphers)_hash(extensions)`, where the hash is the
+/// first 12 hex characters of the SHA256 digest, or `000000000000` if the part is empty.
+pub struct Ja4RawParts {
+    /// The JA4_a part, like `t13d1516h2`
+    pub prefix: String,
+    /// The sorted cipher suites in 4 hex digits, separated by comma
+    pub ciphers: String,
+    /// The sorted extension types in 4 hex digits, SNI and ALPN excluded, separated by comma,
+    /// followed by `_` and the signature algorithms in the original order if present
+    pub extensions: String,
+}
+
+impl ClientHello<'_> {
+    /// Get the raw parts of the JA4 fingerprint.
+    ///
+    /// Set `quic` if the ClientHello message is received over QUIC.
+    pub fn ja4_raw_parts(&self, quic: bool) -> Result<Ja4RawParts, ExtensionParseError> {
+        use std::fmt::Write;
+
+        fn u16_list(data: &[u8]) -> impl Iterator<Item = u16> + '_ {
+            data.chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .filter(|v| !is_grease(*v))
+        }
+
+        fn join_hex<I: Iterator<Item = u16>>(s: &mut String, iter: I) {
+            for (i, v) in iter.enumerate() {
+                if i > 0 {
+                    s.push(',');
+                }
+                let _ = write!(s, "{v:04x}");
+            }
+        }
+
+        let mut ciphers = u16_list(self.cipher_suites).collect::<Vec<_>>();
+        let mut ext_types = match self.extensions {
+            Some(data) => ExtensionList::ext_types(data)?,
+            None => Vec::new(),
+        };
+        ext_types.retain(|v| !is_grease(*v));
+        let has_sni = ext_types.contains(&(ExtensionType::ServerName as u16));
+
+        let mut version =
+            u16::from_be_bytes([self.legacy_version.major, self.legacy_version.minor]);
+        if let Some(data) = self.get_ext(ExtensionType::SupportedVersions)? {
+            let Some((list_len, left)) = data.split_first() else {
+                return Err(ExtensionParseError::InvalidLength);
+            };
+            let Some(list) = left.get(..*list_len as usize) else {
+                return Err(ExtensionParseError::InvalidLength);
+            };
+            if let Some(v) = u16_list(list).max() {
+                version = v;
+            }
+        }
+        let version = match version {
+            0x0304 => "13",
+            0x0303 => "12",
+            0x0302 => "11",
+            0x0301 => "10",
+            0x0300 => "s3",
+            0x0200 => "s2",
+            _ => "00",
+        };
+
+        let mut alpn = ['0', '0'];
+        if let Some(data) = self.get_ext(ExtensionType::ApplicationLayerProtocolNegotiation)? {
+            if data.len() < 3 {
+                return Err(ExtensionParseError::InvalidLength);
+            }
+            let name_len = data[2] as usize;
+            let Some(name) = data.get(3..3 + name_len) else {
+                return Err(ExtensionParseError::InvalidLength);
+            };
+            if let (Some(first), Some(last)) = (name.first(), name.last()) {
+                if first.is_ascii_alphanumeric() && last.is_ascii_alphanumeric() {
+                    alpn = [*first as char, *last as char];
+                } else {
+                    let hex = |v: u8| char::from_digit(v as u32, 16).unwrap();
+                    alpn = [hex(first >> 4), hex(last & 0x0f)];
+                }
+            }
+        }
+
+        let mut prefix = String::with_capacity(10);
+        prefix.push(if quic { 'q' } else { 't' });
+        prefix.push_str(version);
+        prefix.push(if has_sni { 'd' } else { 'i' });
+        let _ = write!(
+            prefix,
+            "{:02}{:02}{}{}",
+            ciphers.len().min(99),
+            ext_types.len().min(99),
+            alpn[0],
+            alpn[1]
+        );
+
+        ciphers.sort_unstable();
+        let mut cipher_s = String::with_capacity(ciphers.len() * 5);
+        join_hex(&mut cipher_s, ciphers.into_iter());
+
+        ext_types.retain(|v| {
+            *v != ExtensionType::ServerName as u16
+                && *v != ExtensionType::ApplicationLayerProtocolNegotiation as u16
+        });
+        ext_types.sort_unstable();
+        let mut ext_s = String::with_capacity(ext_types.len() * 5 + 64);
+        join_hex(&mut ext_s, ext_types.into_iter());
+        if let Some(data) = self.get_ext(ExtensionType::SignatureAlgorithms)? {
+            if data.len() < 2 {
+                return Err(ExtensionParseError::InvalidLength);
+            }
+            let list_len = u16::from_be_bytes([data[0], data[1]]) as usize;
+            let Some(list) = data.get(2..2 + list_len) else {
+                return Err(ExtensionParseError::InvalidLength);
+            };
+            if !list.is_empty() {
+                ext_s.push('_');
+                join_hex(&mut ext_s, u16_list(list));
+            }
+        }
+
+        Ok(Ja4RawParts {
+            prefix,
+            ciphers: cipher_s,
+            extensions: ext_s,
+        })
+    }
+}
+
+/// GREASE values are defined in rfc8701
+fn is_grease(v: u16) -> bool {
+    (v & 0x0f0f) == 0x0a0a && (v >> 8) == (v & 0xff)
 }
 
 #[cfg(test)]
@@ -183,6 +383,88 @@ mod tests {
     use super::*;
     use crate::parser::tls::HandshakeMessage;
 
+    #[test]
+    fn ja3() {
+        let data: &[u8] = &[
+            0x01, // Handshake Type - ClientHello
+            0x00, 0x00, 0x49, // Message Length, 73
+            0x03, 0x03, // TLS 1.2
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, // Random data, 32 bytes
+            0x00, // Session ID Length
+            0x00, 0x06, // Cipher Suites Length
+            0x0a, 0x0a, 0x13, 0x01, 0xc0, 0x2f, // Cipher Suites, with GREASE
+            0x01, // Compression Methods Length
+            0x00, // Compression Methods
+            0x00, 0x1a, // Extensions Length, 26
+            0x0a, 0x0a, // Extension Type - GREASE
+            0x00, 0x00, // Extension Length, 0
+            0x00, 0x0a, // Extension Type - Supported Groups
+            0x00, 0x08, // Extension Length, 8
+            0x00, 0x06, // Supported Groups List Length, 6
+            0x1a, 0x1a, 0x00, 0x1d, 0x00, 0x17, // Supported Groups, with GREASE
+            0x00, 0x0b, // Extension Type - EC Point Formats
+            0x00, 0x02, // Extension Length, 2
+            0x01, // EC Point Formats Length, 1
+            0x00, // EC Point Formats
+            0x00, 0x17, // Extension Type - Extended Master Secret
+            0x00, 0x00, // Extension Length, 0
+        ];
+
+        let handshake_msg = HandshakeMessage::try_parse_fragment(data).unwrap();
+        let ch = handshake_msg.parse_client_hello().unwrap();
+        assert_eq!(ch.ja3_string().unwrap(), "771,4865-49199,10-11-23,29-23,0");
+
+        let ja4 = ch.ja4_raw_parts(false).unwrap();
+        assert_eq!(ja4.prefix, "t12i020300");
+        assert_eq!(ja4.ciphers, "1301,c02f");
+        assert_eq!(ja4.extensions, "000a,000b,0017");
+    }
+
+    #[test]
+    fn ja4() {
+        let data: &[u8] = &[
+            0x01, // Handshake Type - ClientHello
+            0x00, 0x00, 0x57, // Message Length, 87
+            0x03, 0x03, // TLS 1.2
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, // Random data, 32 bytes
+            0x00, // Session ID Length
+            0x00, 0x06, // Cipher Suites Length
+            0xc0, 0x2f, 0x13, 0x01, 0x2a, 0x2a, // Cipher Suites, with GREASE
+            0x01, // Compression Methods Length
+            0x00, // Compression Methods
+            0x00, 0x28, // Extensions Length, 40
+            0x00, 0x10, // Extension Type - ALPN
+            0x00, 0x05, // Extension Length, 5
+            0x00, 0x03, // ALPN Extension Length, 3
+            0x02, b'h', b'2', // ALPN Protocol
+            0x00, 0x00, // Extension Type - Server Name
+            0x00, 0x08, // Extension Length, 8
+            0x00, 0x06, // Server Name List Length, 6
+            0x00, // Server Name Type - Domain
+            0x00, 0x03, // Server Name Length, 3
+            b'a', b'.', b'b', // Server Name
+            0x00, 0x2b, // Extension Type - Supported Versions
+            0x00, 0x05, // Extension Length, 5
+            0x04, // Supported Versions Length, 4
+            0x03, 0x04, 0x03, 0x03, // Supported Versions
+            0x00, 0x0d, // Extension Type - Signature Algorithms
+            0x00, 0x06, // Extension Length, 6
+            0x00, 0x04, // Signature Algorithms Length, 4
+            0x08, 0x04, 0x04, 0x03, // Signature Algorithms
+        ];
+
+        let handshake_msg = HandshakeMessage::try_parse_fragment(data).unwrap();
+        let ch = handshake_msg.parse_client_hello().unwrap();
+        let ja4 = ch.ja4_raw_parts(true).unwrap();
+        assert_eq!(ja4.prefix, "q13d0204h2");
+        assert_eq!(ja4.ciphers, "1301,c02f");
+        assert_eq!(ja4.extensions, "000d,002b_0804,0403");
+    }
+
     #[test]
     fn invalid_ext_len() {
         let data: &[u8] = &[
@@ -17,7 +17,7 @@
 use thiserror::Error;
 
 mod client_hello;
-pub use client_hello::{ClientHello, ClientHelloParseError};
+pub use client_hello::{ClientHello, ClientHelloParseError, Ja4RawParts};
 
 #[allow(dead_code)]
 #[repr(u8)]
@@ -34,7 +34,9 @@ mod handshake;
 #[cfg(feature = "quic")]
 pub(crate) use handshake::HandshakeHeader;
 pub(crate) use handshake::HandshakeType;
-pub use handshake::{ClientHello, ClientHelloParseError, HandshakeCoalescer, HandshakeMessage};
+pub use handshake::{
+    ClientHello, ClientHelloParseError, HandshakeCoalescer, HandshakeMessage, Ja4RawParts,
+};
 
 mod extension;
 pub use extension::{ExtensionList, ExtensionParseError, ExtensionType};
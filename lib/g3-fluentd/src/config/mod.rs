@@ -51,6 +51,7 @@ pub struct FluentdClientConfig {
     pub(super) write_timeout: Duration,
     pub(super) flush_interval: Duration,
     pub(super) retry_queue_len: usize,
+    pub(super) gzip_compress: bool,
 }
 
 impl Default for FluentdClientConfig {
@@ -80,6 +81,7 @@ impl FluentdClientConfig {
             write_timeout: Duration::from_secs(1),
             flush_interval: Duration::from_millis(100),
             retry_queue_len: 10,
+            gzip_compress: false,
         }
     }
 
@@ -143,6 +145,10 @@ impl FluentdClientConfig {
         self.retry_queue_len = len;
     }
 
+    pub fn set_gzip_compress(&mut self, enable: bool) {
+        self.gzip_compress = enable;
+    }
+
     pub(super) async fn new_connection(&self) -> anyhow::Result<FluentdConnection> {
         let socket = g3_socket::tcp::new_socket_to(
             self.server_addr.ip(),
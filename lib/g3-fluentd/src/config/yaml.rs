@@ -107,6 +107,11 @@ impl FluentdClientConfig {
                         config.set_flush_interval(interval);
                         Ok(())
                     }
+                    "gzip_compress" => {
+                        let enable = g3_yaml::value::as_bool(v)?;
+                        config.set_gzip_compress(enable);
+                        Ok(())
+                    }
                     _ => Err(anyhow!("invalid key {k}")),
                 })?;
 
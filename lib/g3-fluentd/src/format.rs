@@ -44,11 +44,23 @@ impl From<slog::Error> for EncodeError {
 
 pub struct FluentdFormatter {
     tag_name: String,
+    entry_only: bool,
 }
 
 impl FluentdFormatter {
     pub(super) fn new(tag_name: String) -> Self {
-        FluentdFormatter { tag_name }
+        FluentdFormatter {
+            tag_name,
+            entry_only: false,
+        }
+    }
+
+    /// only encode the `[time, record]` entry, which is used in (Compressed)PackedForward mode
+    pub(super) fn new_entry_only() -> Self {
+        FluentdFormatter {
+            tag_name: String::new(),
+            entry_only: true,
+        }
     }
 
     fn rmp_encode(
@@ -59,10 +71,16 @@ impl FluentdFormatter {
         let datetime_now = Utc::now();
         let mut buf = Vec::<u8>::with_capacity(1024);
 
-        rmp::encode::write_array_len(&mut buf, 3)?;
+        if self.entry_only {
+            rmp::encode::write_array_len(&mut buf, 2)?;
+        } else {
+            rmp::encode::write_array_len(&mut buf, 3)?;
+        }
         {
             // #1
-            rmp::encode::write_str(&mut buf, &self.tag_name)?;
+            if !self.entry_only {
+                rmp::encode::write_str(&mut buf, &self.tag_name)?;
+            }
 
             // #2
             rmp::encode::write_ext_meta(&mut buf, 8, 0)?;
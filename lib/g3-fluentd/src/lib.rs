@@ -15,10 +15,13 @@
  */
 
 use std::collections::VecDeque;
+use std::io::Write;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use anyhow::anyhow;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use flume::Receiver;
 use log::warn;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
@@ -38,6 +41,8 @@ mod macros;
 mod format;
 pub use format::FluentdFormatter;
 
+const PACKED_ENTRIES_MAX_SIZE: usize = 64 * 1024;
+
 pub fn new_async_logger(
     async_conf: &AsyncLogConfig,
     fluent_conf: &Arc<FluentdClientConfig>,
@@ -50,6 +55,7 @@ pub fn new_async_logger(
     for i in 0..async_conf.thread_number {
         let io_thread = AsyncIoThread {
             config: Arc::clone(fluent_conf),
+            tag_name: tag_name.clone(),
             receiver: receiver.clone(),
             stats: Arc::clone(&stats),
             retry_queue: VecDeque::with_capacity(fluent_conf.retry_queue_len),
@@ -66,7 +72,12 @@ pub fn new_async_logger(
             });
     }
 
-    AsyncLogger::new(sender, FluentdFormatter::new(tag_name), stats)
+    let formatter = if fluent_conf.gzip_compress {
+        FluentdFormatter::new_entry_only()
+    } else {
+        FluentdFormatter::new(tag_name)
+    };
+    AsyncLogger::new(sender, formatter, stats)
 }
 
 enum FluentdConnection {
@@ -76,9 +87,11 @@ enum FluentdConnection {
 
 struct AsyncIoThread {
     config: Arc<FluentdClientConfig>,
+    tag_name: String,
     receiver: Receiver<Vec<u8>>,
     stats: Arc<LogStats>,
-    retry_queue: VecDeque<Vec<u8>>,
+    /// the data to retry, with the number of log records in it
+    retry_queue: VecDeque<(Vec<u8>, usize)>,
 }
 
 impl AsyncIoThread {
@@ -90,10 +103,18 @@ impl AsyncIoThread {
                 Ok(Ok(connection)) => {
                     let r = match connection {
                         FluentdConnection::Tcp(tcp_stream) => {
-                            self.run_with_connection(tcp_stream).await
+                            if self.config.gzip_compress {
+                                self.run_with_connection_gzip(tcp_stream).await
+                            } else {
+                                self.run_with_connection(tcp_stream).await
+                            }
                         }
                         FluentdConnection::Tls(tls_stream) => {
-                            self.run_with_connection(tls_stream).await
+                            if self.config.gzip_compress {
+                                self.run_with_connection_gzip(tls_stream).await
+                            } else {
+                                self.run_with_connection(tls_stream).await
+                            }
                         }
                     };
                     match r {
@@ -117,6 +138,16 @@ impl AsyncIoThread {
                 }
             }
         }
+        // the channel is closed, no chance to send the left ones
+        self.drop_retry_queue();
+    }
+
+    fn drop_retry_queue(&mut self) {
+        for (_, count) in self.retry_queue.drain(..) {
+            for _ in 0..count {
+                self.stats.drop.add_peer_unreachable();
+            }
+        }
     }
 
     async fn run_without_connection(&mut self) -> anyhow::Result<()> {
@@ -124,8 +155,8 @@ impl AsyncIoThread {
         let drop_count_i = drop_count.clone();
         match tokio::time::timeout(self.config.connect_delay, async {
             while let Ok(data) = self.receiver.recv_async().await {
-                if self.push_to_retry(data).is_some() {
-                    drop_count_i.fetch_add(1, Ordering::Relaxed);
+                if let Some((_, count)) = self.push_to_retry(data, 1) {
+                    drop_count_i.fetch_add(count, Ordering::Relaxed);
                 }
             }
         })
@@ -147,21 +178,28 @@ impl AsyncIoThread {
         let mut flush_interval = tokio::time::interval(self.config.flush_interval);
         // skip flush_interval.tick().await;
 
-        while let Some(data) = self.retry_queue.pop_front() {
+        while let Some((data, count)) = self.retry_queue.pop_front() {
             match tokio::time::timeout(
                 self.config.write_timeout,
                 connection.write_all(data.as_slice()),
             )
             .await
             {
-                Ok(Ok(_)) => {}
+                Ok(Ok(_)) => {
+                    for _ in 0..count {
+                        self.stats.io.add_passed();
+                    }
+                    self.stats.io.add_size(data.len());
+                }
                 Ok(Err(e)) => {
-                    self.retry_queue.push_front(data);
+                    self.retry_queue.push_front((data, count));
                     return Err(anyhow!("write event failed: {e:?}"));
                 }
                 Err(_) => {
                     // drop directly on write timeout
-                    self.stats.drop.add_peer_unreachable();
+                    for _ in 0..count {
+                        self.stats.drop.add_peer_unreachable();
+                    }
                 }
             }
         }
@@ -177,7 +215,7 @@ impl AsyncIoThread {
                                     self.stats.io.add_size(data.len());
                                 }
                                 Ok(Err(e)) => {
-                                    self.push_to_retry(data);
+                                    self.push_to_retry(data, 1);
                                     return Err(anyhow!("write event failed: {e:?}"));
                                 }
                                 Err(_) => {
@@ -203,13 +241,293 @@ impl AsyncIoThread {
         }
     }
 
-    fn push_to_retry(&mut self, data: Vec<u8>) -> Option<Vec<u8>> {
-        self.retry_queue.push_back(data);
+    /// send the log entries in CompressedPackedForward mode.
+    /// the data in retry queue are concatenated entries, which may contain more than one entry
+    async fn run_with_connection_gzip<T>(&mut self, mut connection: T) -> anyhow::Result<()>
+    where
+        T: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut read_buf = [0u8; 8];
+        let mut flush_interval = tokio::time::interval(self.config.flush_interval);
+        // skip flush_interval.tick().await;
+
+        let mut packed = PackedEntries::default();
+        while let Some((data, count)) = self.retry_queue.pop_front() {
+            packed.push_many(&data, count);
+        }
+
+        loop {
+            tokio::select! {
+                r = self.receiver.recv_async() => {
+                    match r {
+                        Ok(data) => {
+                            packed.push(&data);
+                            if packed.entries.len() >= PACKED_ENTRIES_MAX_SIZE {
+                                self.send_packed(&mut connection, &mut packed).await?;
+                            }
+                        }
+                        Err(_) => {
+                            self.send_packed(&mut connection, &mut packed).await?;
+                            connection.flush().await.map_err(|e| anyhow!("flush data failed: {e:?}"))?;
+                            return Ok(());
+                        }
+                    }
+                }
+                r = connection.read(&mut read_buf) => {
+                    if !packed.entries.is_empty() {
+                        self.push_to_retry(packed.entries, packed.count);
+                    }
+                    return match r {
+                        Ok(0) => Err(anyhow!("connection closed by server")),
+                        Ok(_) => Err(anyhow!("unexpected data received, will close this connection")),
+                        Err(e) => Err(anyhow!("connection closed: {e:?}")),
+                    };
+                }
+                _ = flush_interval.tick() => {
+                    self.send_packed(&mut connection, &mut packed).await?;
+                    connection.flush().await.map_err(|e| anyhow!("flush data failed: {e:?}"))?;
+                }
+            }
+        }
+    }
+
+    async fn send_packed<T>(
+        &mut self,
+        connection: &mut T,
+        packed: &mut PackedEntries,
+    ) -> anyhow::Result<()>
+    where
+        T: AsyncWrite + Unpin,
+    {
+        if packed.entries.is_empty() {
+            return Ok(());
+        }
+
+        let data = match packed.encode_gzip(&self.tag_name) {
+            Ok(data) => data,
+            Err(e) => {
+                for _ in 0..packed.count {
+                    self.stats.drop.add_format_failed();
+                }
+                packed.clear();
+                return Err(anyhow!("gzip compress failed: {e:?}"));
+            }
+        };
+
+        match tokio::time::timeout(self.config.write_timeout, connection.write_all(&data)).await {
+            Ok(Ok(_)) => {
+                for _ in 0..packed.count {
+                    self.stats.io.add_passed();
+                }
+                self.stats.io.add_size(packed.entries.len());
+                packed.clear();
+                Ok(())
+            }
+            Ok(Err(e)) => {
+                let entries = std::mem::take(&mut packed.entries);
+                let count = packed.count;
+                packed.clear();
+                self.push_to_retry(entries, count);
+                Err(anyhow!("write event failed: {e:?}"))
+            }
+            Err(_) => {
+                // drop directly on write timeout
+                for _ in 0..packed.count {
+                    self.stats.drop.add_peer_unreachable();
+                }
+                packed.clear();
+                Ok(())
+            }
+        }
+    }
+
+    fn push_to_retry(&mut self, data: Vec<u8>, count: usize) -> Option<(Vec<u8>, usize)> {
+        self.retry_queue.push_back((data, count));
         if self.retry_queue.len() > self.config.retry_queue_len {
-            self.stats.drop.add_peer_unreachable();
-            self.retry_queue.pop_front()
+            let dropped = self.retry_queue.pop_front()?;
+            for _ in 0..dropped.1 {
+                self.stats.drop.add_peer_unreachable();
+            }
+            Some(dropped)
         } else {
             None
         }
     }
 }
+
+#[derive(Default)]
+struct PackedEntries {
+    entries: Vec<u8>,
+    count: usize,
+}
+
+impl PackedEntries {
+    fn push(&mut self, data: &[u8]) {
+        self.push_many(data, 1);
+    }
+
+    fn push_many(&mut self, data: &[u8], count: usize) {
+        self.entries.extend_from_slice(data);
+        self.count += count;
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.count = 0;
+    }
+
+    /// encode as `[tag, entries, {"compressed": "gzip"}]`
+    fn encode_gzip(&self, tag_name: &str) -> std::io::Result<Vec<u8>> {
+        let mut encoder = GzEncoder::new(
+            Vec::with_capacity(self.entries.len() / 2),
+            Compression::default(),
+        );
+        encoder.write_all(&self.entries)?;
+        let compressed = encoder.finish()?;
+
+        let mut buf = Vec::with_capacity(compressed.len() + tag_name.len() + 32);
+        rmp::encode::write_array_len(&mut buf, 3)?;
+        rmp::encode::write_str(&mut buf, tag_name)?;
+        rmp::encode::write_bin(&mut buf, &compressed)?;
+        rmp::encode::write_map_len(&mut buf, 1)?;
+        rmp::encode::write_str(&mut buf, "compressed")?;
+        rmp::encode::write_str(&mut buf, "gzip")?;
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    fn decode_packed(buf: &mut &[u8]) -> (String, Vec<u8>) {
+        assert_eq!(rmp::decode::read_array_len(buf).unwrap(), 3);
+        let tag_len = rmp::decode::read_str_len(buf).unwrap() as usize;
+        let (tag, left) = (*buf).split_at(tag_len);
+        let tag = std::str::from_utf8(tag).unwrap().to_string();
+        *buf = left;
+        let bin_len = rmp::decode::read_bin_len(buf).unwrap() as usize;
+        let (compressed, left) = (*buf).split_at(bin_len);
+        *buf = left;
+        assert_eq!(rmp::decode::read_map_len(buf).unwrap(), 1);
+        let mut str_buf = [0u8; 16];
+        assert_eq!(
+            rmp::decode::read_str(buf, &mut str_buf).unwrap(),
+            "compressed"
+        );
+        assert_eq!(rmp::decode::read_str(buf, &mut str_buf).unwrap(), "gzip");
+
+        let mut entries = Vec::new();
+        GzDecoder::new(compressed)
+            .read_to_end(&mut entries)
+            .unwrap();
+        (tag, entries)
+    }
+
+    #[test]
+    fn packed_entries() {
+        let mut packed = PackedEntries::default();
+        packed.push(b"entry1");
+        packed.push(b"entry2");
+        assert_eq!(packed.count, 2);
+
+        let data = packed.encode_gzip("test").unwrap();
+        let mut buf = data.as_slice();
+        let (tag, entries) = decode_packed(&mut buf);
+        assert!(buf.is_empty());
+        assert_eq!(tag, "test");
+        assert_eq!(entries, b"entry1entry2");
+
+        packed.clear();
+        assert_eq!(packed.count, 0);
+        assert!(packed.entries.is_empty());
+    }
+
+    #[tokio::test]
+    async fn retry_queue_count() {
+        let (sender, receiver) = flume::bounded(16);
+        let stats = Arc::new(LogStats::default());
+        let mut config = FluentdClientConfig::default();
+        config.set_retry_queue_len(2);
+        let mut io_thread = AsyncIoThread {
+            config: Arc::new(config),
+            tag_name: "test".to_string(),
+            receiver,
+            stats: stats.clone(),
+            retry_queue: VecDeque::new(),
+        };
+
+        // packed blobs from previous failed gzip writes
+        assert!(io_thread.push_to_retry(b"e0e1e2".to_vec(), 3).is_none());
+        assert!(io_thread.push_to_retry(b"e3e4".to_vec(), 2).is_none());
+        let dropped = io_thread.push_to_retry(b"e5".to_vec(), 1).unwrap();
+        assert_eq!(dropped.1, 3);
+        assert_eq!(stats.snapshot().drop.peer_unreachable, 3);
+
+        sender.send(b"e6".to_vec()).unwrap();
+        drop(sender);
+
+        let (client, mut server) = tokio::io::duplex(4096);
+        io_thread.run_with_connection_gzip(client).await.unwrap();
+        drop(io_thread);
+
+        let mut data = Vec::new();
+        server.read_to_end(&mut data).await.unwrap();
+        let mut buf = data.as_slice();
+        let mut all_entries = Vec::new();
+        while !buf.is_empty() {
+            let (_, entries) = decode_packed(&mut buf);
+            all_entries.extend_from_slice(&entries);
+        }
+        assert_eq!(all_entries, b"e3e4e5e6");
+
+        // every record should be counted exactly once
+        let snap = stats.snapshot();
+        assert_eq!(snap.io.passed, 4);
+        assert_eq!(snap.io.passed + snap.drop.peer_unreachable, 7);
+    }
+
+    #[tokio::test]
+    async fn gzip_batching() {
+        let (sender, receiver) = flume::bounded(16);
+        let stats = Arc::new(LogStats::default());
+        let mut io_thread = AsyncIoThread {
+            config: Arc::new(FluentdClientConfig::default()),
+            tag_name: "test".to_string(),
+            receiver,
+            stats: stats.clone(),
+            retry_queue: VecDeque::new(),
+        };
+
+        for i in 0..3 {
+            sender.send(format!("entry{i}").into_bytes()).unwrap();
+        }
+        drop(sender);
+
+        let (client, mut server) = tokio::io::duplex(4096);
+        io_thread.run_with_connection_gzip(client).await.unwrap();
+        drop(io_thread);
+
+        let mut data = Vec::new();
+        server.read_to_end(&mut data).await.unwrap();
+        let mut buf = data.as_slice();
+        let mut batches = 0;
+        let mut all_entries = Vec::new();
+        while !buf.is_empty() {
+            let (tag, entries) = decode_packed(&mut buf);
+            assert_eq!(tag, "test");
+            all_entries.extend_from_slice(&entries);
+            batches += 1;
+        }
+        assert_eq!(all_entries, b"entry0entry1entry2");
+        // the first flush tick fires immediately, which may split at most one entry out
+        assert!(batches <= 2);
+
+        let snap = stats.snapshot();
+        assert_eq!(snap.io.passed, 3);
+        assert_eq!(snap.io.size, all_entries.len() as u64);
+    }
+}
@@ -121,4 +121,18 @@ impl IpLocation {
     pub fn isp_domain(&self) -> Option<&str> {
         self.isp_domain.as_deref()
     }
+
+    /// Check if all the info except the network address are the same
+    pub fn has_same_info(&self, other: &IpLocation) -> bool {
+        self.country == other.country
+            && self.continent == other.continent
+            && self.as_number == other.as_number
+            && self.isp_name == other.isp_name
+            && self.isp_domain == other.isp_domain
+    }
+
+    /// Replace the network address, and keep all the other info
+    pub fn with_network(self, net: IpNetwork) -> IpLocation {
+        IpLocation { net, ..self }
+    }
 }
@@ -33,6 +33,7 @@ pub struct HttpConnectResponse {
     chunked_transfer: bool,
     has_transfer_encoding: bool,
     has_content_length: bool,
+    unexpected_body: bool,
 }
 
 impl HttpConnectResponse {
@@ -45,6 +46,7 @@ impl HttpConnectResponse {
             chunked_transfer: false,
             has_transfer_encoding: false,
             has_content_length: false,
+            unexpected_body: false,
         }
     }
 
@@ -196,13 +198,21 @@ impl HttpConnectResponse {
         }
     }
 
+    /// Check if there is body in the 2xx response, which is not allowed for CONNECT.
+    /// The body will always be discarded in `recv`.
+    #[inline]
+    pub fn has_unexpected_body(&self) -> bool {
+        self.unexpected_body
+    }
+
     pub async fn recv<R>(r: &mut R, max_header_size: usize) -> Result<Self, HttpConnectError>
     where
         R: AsyncBufRead + Unpin,
     {
-        let rsp = HttpConnectResponse::parse(r, max_header_size).await?;
+        let mut rsp = HttpConnectResponse::parse(r, max_header_size).await?;
 
         if let Some(body_type) = rsp.body_type() {
+            rsp.unexpected_body = (200..300).contains(&rsp.code);
             // the body should be simple in non-2xx case, use a default 2048 for its max line size
             let mut body_reader = HttpBodyReader::new(r, body_type, 2048);
             let mut sink = tokio::io::sink();
@@ -216,3 +226,53 @@ impl HttpConnectResponse {
         Ok(rsp)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, BufReader};
+
+    #[tokio::test]
+    async fn recv_ok() {
+        let content = b"HTTP/1.1 200 Connection established\r\n\r\nxyz";
+        let stream = tokio_test::io::Builder::new().read(content).build();
+        let mut buf_stream = BufReader::new(stream);
+        let rsp = HttpConnectResponse::recv(&mut buf_stream, 4096)
+            .await
+            .unwrap();
+        assert_eq!(rsp.code, 200);
+        assert!(!rsp.has_unexpected_body());
+
+        let mut left = Vec::new();
+        buf_stream.read_to_end(&mut left).await.unwrap();
+        assert_eq!(left.as_slice(), b"xyz");
+    }
+
+    #[tokio::test]
+    async fn recv_ok_with_body() {
+        let content = b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nbodyxyz";
+        let stream = tokio_test::io::Builder::new().read(content).build();
+        let mut buf_stream = BufReader::new(stream);
+        let rsp = HttpConnectResponse::recv(&mut buf_stream, 4096)
+            .await
+            .unwrap();
+        assert_eq!(rsp.code, 200);
+        assert!(rsp.has_unexpected_body());
+
+        let mut left = Vec::new();
+        buf_stream.read_to_end(&mut left).await.unwrap();
+        assert_eq!(left.as_slice(), b"xyz");
+    }
+
+    #[tokio::test]
+    async fn recv_rejected_with_body() {
+        let content = b"HTTP/1.1 403 Forbidden\r\nContent-Length: 4\r\n\r\nbody";
+        let stream = tokio_test::io::Builder::new().read(content).build();
+        let mut buf_stream = BufReader::new(stream);
+        let r = HttpConnectResponse::recv(&mut buf_stream, 4096).await;
+        assert!(matches!(
+            r,
+            Err(HttpConnectError::UnexpectedStatusCode(403, _))
+        ));
+    }
+}
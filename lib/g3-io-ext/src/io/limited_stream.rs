@@ -98,6 +98,10 @@ impl<S> LimitedStream<S> {
             .reset_local_limit(shift_millis, write_max_bytes);
     }
 
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
     pub fn into_inner(self) -> S {
         self.inner
     }
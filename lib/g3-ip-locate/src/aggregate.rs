@@ -0,0 +1,159 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::BTreeMap;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use ip_network::{IpNetwork, Ipv4Network, Ipv6Network};
+
+use g3_geoip_types::IpLocation;
+
+/// Merge sibling networks with the same location info into their parent network.
+///
+/// The result is exact, which means that the longest match of any ip address will return
+/// the same location info as the input. A merge is skipped if the parent network is already
+/// present with different info.
+pub fn aggregate_ip_locations(locations: Vec<IpLocation>) -> Vec<IpLocation> {
+    let mut v4_levels: Vec<BTreeMap<u128, IpLocation>> =
+        (0..=32).map(|_| BTreeMap::new()).collect();
+    let mut v6_levels: Vec<BTreeMap<u128, IpLocation>> =
+        (0..=128).map(|_| BTreeMap::new()).collect();
+
+    for location in locations {
+        match location.network_addr() {
+            IpNetwork::V4(net) => {
+                let addr = u32::from(net.network_address()) as u128;
+                v4_levels[net.netmask() as usize].insert(addr, location);
+            }
+            IpNetwork::V6(net) => {
+                let addr = u128::from(net.network_address());
+                v6_levels[net.netmask() as usize].insert(addr, location);
+            }
+        }
+    }
+
+    merge_levels(&mut v4_levels, 32, |addr, len| {
+        Ipv4Network::new(Ipv4Addr::from(addr as u32), len)
+            .ok()
+            .map(IpNetwork::V4)
+    });
+    merge_levels(&mut v6_levels, 128, |addr, len| {
+        Ipv6Network::new(Ipv6Addr::from(addr), len)
+            .ok()
+            .map(IpNetwork::V6)
+    });
+
+    v4_levels
+        .into_iter()
+        .chain(v6_levels)
+        .flat_map(|level| level.into_values())
+        .collect()
+}
+
+fn merge_levels<F>(levels: &mut [BTreeMap<u128, IpLocation>], bits: u8, build_net: F)
+where
+    F: Fn(u128, u8) -> Option<IpNetwork>,
+{
+    for len in (1..=bits).rev() {
+        let sibling_bit = 1u128 << (bits - len);
+        let level = std::mem::take(&mut levels[len as usize]);
+        let mut kept = BTreeMap::new();
+
+        let mut iter = level.into_iter().peekable();
+        while let Some((addr, location)) = iter.next() {
+            if addr & sibling_bit == 0 && !levels[len as usize - 1].contains_key(&addr) {
+                if let Some((next_addr, next_location)) = iter.peek() {
+                    if *next_addr == addr | sibling_bit && location.has_same_info(next_location) {
+                        if let Some(parent) = build_net(addr, len - 1) {
+                            iter.next();
+                            levels[len as usize - 1].insert(addr, location.with_network(parent));
+                            continue;
+                        }
+                    }
+                }
+            }
+            kept.insert(addr, location);
+        }
+
+        levels[len as usize] = kept;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use g3_geoip_types::{IpLocationBuilder, IsoCountryCode};
+    use std::str::FromStr;
+
+    fn build(net: &str, country: IsoCountryCode) -> IpLocation {
+        let mut builder = IpLocationBuilder::default();
+        builder.set_network(IpNetwork::from_str(net).unwrap());
+        builder.set_country(country);
+        builder.build().unwrap()
+    }
+
+    fn networks(locations: &[IpLocation]) -> Vec<String> {
+        let mut nets: Vec<String> = locations
+            .iter()
+            .map(|l| l.network_addr().to_string())
+            .collect();
+        nets.sort();
+        nets
+    }
+
+    #[test]
+    fn merge_siblings() {
+        let locations = vec![
+            build("10.0.0.0/24", IsoCountryCode::CN),
+            build("10.0.1.0/24", IsoCountryCode::CN),
+            build("10.0.2.0/24", IsoCountryCode::CN),
+            build("10.0.3.0/24", IsoCountryCode::CN),
+            build("10.0.4.0/24", IsoCountryCode::CN),
+            build("10.0.6.0/24", IsoCountryCode::CN),
+            build("2001:db8::/33", IsoCountryCode::US),
+            build("2001:db8:8000::/33", IsoCountryCode::US),
+        ];
+        let aggregated = aggregate_ip_locations(locations);
+        assert_eq!(
+            networks(&aggregated),
+            vec!["10.0.0.0/22", "10.0.4.0/24", "10.0.6.0/24", "2001:db8::/32"]
+        );
+    }
+
+    #[test]
+    fn keep_different_info() {
+        let locations = vec![
+            build("10.0.0.0/24", IsoCountryCode::CN),
+            build("10.0.1.0/24", IsoCountryCode::US),
+        ];
+        let aggregated = aggregate_ip_locations(locations);
+        assert_eq!(networks(&aggregated), vec!["10.0.0.0/24", "10.0.1.0/24"]);
+    }
+
+    #[test]
+    fn keep_existing_parent() {
+        let locations = vec![
+            build("10.0.0.0/23", IsoCountryCode::US),
+            build("10.0.0.0/24", IsoCountryCode::CN),
+            build("10.0.1.0/24", IsoCountryCode::CN),
+        ];
+        let aggregated = aggregate_ip_locations(locations);
+        assert_eq!(
+            networks(&aggregated),
+            vec!["10.0.0.0/23", "10.0.0.0/24", "10.0.1.0/24"]
+        );
+    }
+}
@@ -19,18 +19,27 @@ use std::future::Future;
 use std::io;
 use std::net::IpAddr;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use ahash::AHashMap;
 use ip_network_table::IpNetworkTable;
 use tokio::sync::mpsc;
-use tokio::time::Instant;
+use tokio::time::{Instant, Interval, MissedTickBehavior};
 
 use g3_geoip_types::IpLocation;
 
 use super::{CacheQueryRequest, IpLocateServiceConfig, IpLocationCacheResponse};
 
+static NEGATIVE_CACHE_HITS: AtomicU64 = AtomicU64::new(0);
+
+/// Get the total count of requests that have been served by the negative cache
+pub fn negative_cache_hits() -> u64 {
+    NEGATIVE_CACHE_HITS.load(Ordering::Relaxed)
+}
+
 struct CacheValue {
     valid_before: Instant,
     location: Arc<IpLocation>,
@@ -39,6 +48,8 @@ struct CacheValue {
 pub(crate) struct IpLocationCacheRuntime {
     request_batch_handle_count: usize,
     cache: IpNetworkTable<CacheValue>,
+    negative_cache: AHashMap<IpAddr, Instant>,
+    negative_gc_interval: Interval,
     doing: AHashMap<IpAddr, Vec<CacheQueryRequest>>,
     req_receiver: mpsc::UnboundedReceiver<CacheQueryRequest>,
     rsp_receiver: mpsc::UnboundedReceiver<(Option<IpAddr>, IpLocationCacheResponse)>,
@@ -52,9 +63,16 @@ impl IpLocationCacheRuntime {
         rsp_receiver: mpsc::UnboundedReceiver<(Option<IpAddr>, IpLocationCacheResponse)>,
         query_sender: mpsc::UnboundedSender<IpAddr>,
     ) -> Self {
+        // expired negative entries will be removed in the next tick
+        let gc_period = Duration::from_secs(config.negative_expire_ttl.max(1) as u64);
+        let mut negative_gc_interval = tokio::time::interval(gc_period);
+        negative_gc_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
         IpLocationCacheRuntime {
             request_batch_handle_count: config.cache_request_batch_count,
             cache: IpNetworkTable::new(),
+            negative_cache: AHashMap::new(),
+            negative_gc_interval,
             doing: AHashMap::new(),
             req_receiver,
             rsp_receiver,
@@ -68,6 +86,7 @@ impl IpLocationCacheRuntime {
             let location = Arc::new(location);
 
             if let Some(ip) = ip {
+                self.negative_cache.remove(&ip);
                 if let Some(vec) = self.doing.remove(&ip) {
                     for req in vec.into_iter() {
                         let _ = req.notifier.send(location.clone());
@@ -84,17 +103,28 @@ impl IpLocationCacheRuntime {
                 },
             );
         } else if let Some(ip) = ip {
-            // if no new value found, just use the old expired value
+            if rsp.negative && rsp.expire_at > Instant::now() {
+                self.negative_cache.insert(ip, rsp.expire_at);
+            }
+
+            let Some(vec) = self.doing.remove(&ip) else {
+                return;
+            };
+            // if no new value found, just use the old expired value,
+            // or the pending requests will be dropped
             if let Some((_net, v)) = self.cache.longest_match(ip) {
-                if let Some(vec) = self.doing.remove(&ip) {
-                    for req in vec.into_iter() {
-                        let _ = req.notifier.send(v.location.clone());
-                    }
+                for req in vec.into_iter() {
+                    let _ = req.notifier.send(v.location.clone());
                 }
             }
         }
     }
 
+    fn gc_negative_cache(&mut self) {
+        let now = Instant::now();
+        self.negative_cache.retain(|_, expire_at| *expire_at > now);
+    }
+
     fn send_req(&mut self, ip: IpAddr) {
         if self.query_sender.send(ip).is_err() {
             // the query runtime should not close before the cache runtime
@@ -103,11 +133,26 @@ impl IpLocationCacheRuntime {
     }
 
     fn handle_req(&mut self, req: CacheQueryRequest) {
+        let mut stale_location = None;
         if let Some((_net, v)) = self.cache.longest_match(req.ip) {
             if v.valid_before >= Instant::now() {
                 let _ = req.notifier.send(v.location.clone());
                 return;
             }
+            stale_location = Some(v.location.clone());
+        }
+
+        if let hash_map::Entry::Occupied(o) = self.negative_cache.entry(req.ip) {
+            if *o.get() > Instant::now() {
+                NEGATIVE_CACHE_HITS.fetch_add(1, Ordering::Relaxed);
+                // prefer the old expired value, the same as when the query failed,
+                // or drop the request, so the caller will get no location
+                if let Some(location) = stale_location {
+                    let _ = req.notifier.send(location);
+                }
+                return;
+            }
+            o.remove();
         }
 
         match self.doing.entry(req.ip) {
@@ -133,6 +178,11 @@ impl IpLocationCacheRuntime {
                 }
             }
 
+            // evict expired negative entries
+            while self.negative_gc_interval.poll_tick(cx).is_ready() {
+                self.gc_negative_cache();
+            }
+
             // handle req
             for _ in 1..self.request_batch_handle_count {
                 match self.req_receiver.poll_recv(cx) {
@@ -152,3 +202,96 @@ impl Future for IpLocationCacheRuntime {
         (*self).poll_loop(cx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use tokio::sync::oneshot;
+
+    use g3_geoip_types::IpLocationBuilder;
+    use ip_network::IpNetwork;
+
+    fn new_runtime() -> (
+        IpLocationCacheRuntime,
+        mpsc::UnboundedReceiver<IpAddr>,
+        mpsc::UnboundedSender<CacheQueryRequest>,
+        mpsc::UnboundedSender<(Option<IpAddr>, IpLocationCacheResponse)>,
+    ) {
+        let (req_sender, req_receiver) = mpsc::unbounded_channel();
+        let (rsp_sender, rsp_receiver) = mpsc::unbounded_channel();
+        let (query_sender, query_receiver) = mpsc::unbounded_channel();
+        let runtime = IpLocationCacheRuntime::new(
+            &IpLocateServiceConfig::default(),
+            req_receiver,
+            rsp_receiver,
+            query_sender,
+        );
+        (runtime, query_receiver, req_sender, rsp_sender)
+    }
+
+    fn location(net: &str, asn: u32) -> IpLocation {
+        let mut builder = IpLocationBuilder::default();
+        builder.set_network(IpNetwork::from_str(net).unwrap());
+        builder.set_as_number(asn);
+        builder.build().unwrap()
+    }
+
+    fn request(runtime: &mut IpLocationCacheRuntime, ip: IpAddr) -> Option<u32> {
+        let (notifier, mut receiver) = oneshot::channel();
+        runtime.handle_req(CacheQueryRequest { ip, notifier });
+        receiver.try_recv().ok().and_then(|l| l.network_asn())
+    }
+
+    #[test]
+    fn negative_cache() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let _guard = rt.enter();
+        let (mut runtime, mut query_receiver, _req_sender, _rsp_sender) = new_runtime();
+
+        let ip = IpAddr::from_str("192.0.2.1").unwrap();
+        assert_eq!(request(&mut runtime, ip), None);
+        assert_eq!(query_receiver.try_recv().unwrap(), ip);
+
+        runtime.handle_rsp(Some(ip), IpLocationCacheResponse::negative(60));
+        assert!(runtime.doing.is_empty());
+        // served by the negative cache without a new query
+        assert_eq!(request(&mut runtime, ip), None);
+        assert!(query_receiver.try_recv().is_err());
+
+        // a positive response will remove the negative entry
+        runtime.handle_rsp(
+            Some(ip),
+            IpLocationCacheResponse::new(location("192.0.2.0/24", 64496), 60),
+        );
+        assert!(runtime.negative_cache.is_empty());
+        assert_eq!(request(&mut runtime, ip), Some(64496));
+    }
+
+    #[test]
+    fn negative_cache_prefer_stale() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let _guard = rt.enter();
+        let (mut runtime, mut query_receiver, _req_sender, _rsp_sender) = new_runtime();
+
+        let ip = IpAddr::from_str("192.0.2.1").unwrap();
+        // an already expired positive entry
+        runtime.handle_rsp(
+            None,
+            IpLocationCacheResponse::new(location("192.0.2.0/24", 64496), 0),
+        );
+        runtime.negative_cache.insert(
+            ip,
+            Instant::now().checked_add(Duration::from_secs(60)).unwrap(),
+        );
+
+        assert_eq!(request(&mut runtime, ip), Some(64496));
+        assert!(query_receiver.try_recv().is_err());
+    }
+}
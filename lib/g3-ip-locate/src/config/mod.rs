@@ -14,15 +14,21 @@
  * limitations under the License.
  */
 
+use std::io;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::num::NonZeroUsize;
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use anyhow::anyhow;
 use tokio::net::UdpSocket;
+#[cfg(unix)]
+use tokio::net::UnixDatagram;
 
 use g3_types::net::SocketBufferConfig;
 
-use super::{IpLocationQueryRuntime, IpLocationServiceHandle};
+use super::{IpLocationQueryRuntime, IpLocationServiceHandle, QuerySocket};
 
 #[cfg(feature = "yaml")]
 mod yaml;
@@ -32,10 +38,15 @@ pub struct IpLocateServiceConfig {
     pub(crate) cache_request_batch_count: usize,
     pub(crate) cache_request_timeout: Duration,
     pub(crate) query_peer_addr: SocketAddr,
+    #[cfg(unix)]
+    pub(crate) query_peer_path: Option<PathBuf>,
     pub(crate) query_socket_buffer: SocketBufferConfig,
     pub(crate) query_wait_timeout: Duration,
+    pub(crate) query_max_in_flight: NonZeroUsize,
+    pub(crate) query_max_queued: usize,
     pub(crate) default_expire_ttl: u32,
     pub(crate) maximum_expire_ttl: u32,
+    pub(crate) negative_expire_ttl: u32,
 }
 
 impl Default for IpLocateServiceConfig {
@@ -44,10 +55,15 @@ impl Default for IpLocateServiceConfig {
             cache_request_batch_count: 10,
             cache_request_timeout: Duration::from_secs(2),
             query_peer_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 2888),
+            #[cfg(unix)]
+            query_peer_path: None,
             query_socket_buffer: SocketBufferConfig::default(),
             query_wait_timeout: Duration::from_secs(1),
+            query_max_in_flight: NonZeroUsize::new(1024).unwrap(),
+            query_max_queued: 4096,
             default_expire_ttl: 10,
             maximum_expire_ttl: 300,
+            negative_expire_ttl: 5,
         }
     }
 }
@@ -63,6 +79,15 @@ impl IpLocateServiceConfig {
 
     pub fn set_query_peer_addr(&mut self, addr: SocketAddr) {
         self.query_peer_addr = addr;
+        #[cfg(unix)]
+        {
+            self.query_peer_path = None;
+        }
+    }
+
+    #[cfg(unix)]
+    pub fn set_query_peer_path(&mut self, path: PathBuf) {
+        self.query_peer_path = Some(path);
     }
 
     pub fn set_query_socket_buffer(&mut self, config: SocketBufferConfig) {
@@ -73,6 +98,14 @@ impl IpLocateServiceConfig {
         self.query_wait_timeout = time;
     }
 
+    pub fn set_query_max_in_flight(&mut self, max: NonZeroUsize) {
+        self.query_max_in_flight = max;
+    }
+
+    pub fn set_query_max_queued(&mut self, max: usize) {
+        self.query_max_queued = max;
+    }
+
     pub fn set_default_expire_ttl(&mut self, ttl: u32) {
         self.default_expire_ttl = ttl;
     }
@@ -81,9 +114,32 @@ impl IpLocateServiceConfig {
         self.maximum_expire_ttl = ttl;
     }
 
+    pub fn set_negative_expire_ttl(&mut self, ttl: u32) {
+        self.negative_expire_ttl = ttl;
+    }
+
+    pub fn check(&self) -> anyhow::Result<()> {
+        if self.negative_expire_ttl > self.default_expire_ttl {
+            return Err(anyhow!(
+                "negative expire ttl {} should not be larger than the default expire ttl {}",
+                self.negative_expire_ttl,
+                self.default_expire_ttl
+            ));
+        }
+        Ok(())
+    }
+
     pub fn spawn_ip_locate_agent(&self) -> anyhow::Result<IpLocationServiceHandle> {
         use anyhow::Context;
 
+        #[cfg(unix)]
+        if let Some(path) = &self.query_peer_path {
+            let (socket, socket_file) = self.new_unix_socket(path)?;
+            return self.spawn_with_socket(move || {
+                UnixDatagram::from_std(socket).map(|s| QuerySocket::Unix(s, socket_file))
+            });
+        }
+
         let socket = g3_socket::udp::new_std_socket_to(
             self.query_peer_addr,
             &Default::default(),
@@ -98,16 +154,50 @@ impl IpLocateServiceConfig {
             )
         })?;
 
+        self.spawn_with_socket(move || UdpSocket::from_std(socket).map(QuerySocket::Udp))
+    }
+
+    #[cfg(unix)]
+    fn new_unix_socket(
+        &self,
+        path: &Path,
+    ) -> anyhow::Result<(std::os::unix::net::UnixDatagram, Option<LocalSocketFile>)> {
+        use anyhow::Context;
+
+        // the socket should be bound, or the peer won't be able to send back responses
+        let (socket, socket_file) =
+            bind_local_unix_socket().context("failed to bind local unix socket")?;
+        g3_socket::RawSocket::from(&socket)
+            .set_buf_opts(self.query_socket_buffer)
+            .context("failed to set socket buffer")?;
+        socket.connect(path).map_err(|e| {
+            anyhow!(
+                "failed to connect to peer unix socket {}: {e:?}",
+                path.display()
+            )
+        })?;
+        socket
+            .set_nonblocking(true)
+            .context("failed to set unix socket to non-blocking mode")?;
+        Ok((socket, socket_file))
+    }
+
+    fn spawn_with_socket<F>(&self, build_socket: F) -> anyhow::Result<IpLocationServiceHandle>
+    where
+        F: FnOnce() -> io::Result<QuerySocket> + Send + 'static,
+    {
+        use anyhow::Context;
+
         let (cache_runtime, cache_handle, query_handle) = super::crate_ip_location_cache(self);
         if let Some(rt) = crate::get_ip_locate_rt_handle() {
             let config = self.clone();
             rt.spawn(async move {
-                let socket = UdpSocket::from_std(socket).expect("failed to setup udp socket");
+                let socket = build_socket().expect("failed to setup query socket");
                 IpLocationQueryRuntime::new(&config, socket, query_handle).await
             });
             rt.spawn(cache_runtime);
         } else {
-            let socket = UdpSocket::from_std(socket).context("failed to setup udp socket")?;
+            let socket = build_socket().context("failed to setup query socket")?;
             let query_runtime = IpLocationQueryRuntime::new(self, socket, query_handle);
             tokio::spawn(query_runtime);
             tokio::spawn(cache_runtime);
@@ -119,3 +209,109 @@ impl IpLocateServiceConfig {
         ))
     }
 }
+
+#[cfg(unix)]
+fn local_unix_socket_name() -> String {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static SOCKET_ID: AtomicUsize = AtomicUsize::new(0);
+
+    let id = SOCKET_ID.fetch_add(1, Ordering::Relaxed);
+    format!("g3-ip-locate-{}-{id}", std::process::id())
+}
+
+/// The socket file of the bound local unix socket, which will be removed on drop
+#[cfg(unix)]
+pub(crate) struct LocalSocketFile(PathBuf);
+
+#[cfg(unix)]
+impl Drop for LocalSocketFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn bind_local_unix_socket(
+) -> io::Result<(std::os::unix::net::UnixDatagram, Option<LocalSocketFile>)> {
+    use std::os::linux::net::SocketAddrExt;
+    use std::os::unix::net::{SocketAddr, UnixDatagram};
+
+    let addr = SocketAddr::from_abstract_name(local_unix_socket_name())?;
+    let socket = UnixDatagram::bind_addr(&addr)?;
+    Ok((socket, None))
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn bind_local_unix_socket(
+) -> io::Result<(std::os::unix::net::UnixDatagram, Option<LocalSocketFile>)> {
+    let path = std::env::temp_dir().join(format!("{}.sock", local_unix_socket_name()));
+    let _ = std::fs::remove_file(&path);
+    let socket = std::os::unix::net::UnixDatagram::bind(&path)?;
+    Ok((socket, Some(LocalSocketFile(path))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use std::os::unix::net::UnixDatagram;
+
+    #[test]
+    fn check_negative_expire_ttl() {
+        let mut config = IpLocateServiceConfig::default();
+        assert!(config.check().is_ok());
+
+        config.set_negative_expire_ttl(config.default_expire_ttl);
+        assert!(config.check().is_ok());
+
+        config.set_negative_expire_ttl(config.default_expire_ttl + 1);
+        assert!(config.check().is_err());
+
+        config.set_negative_expire_ttl(0);
+        assert!(config.check().is_ok());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn local_socket_file_removed() {
+        let path = std::env::temp_dir().join(format!("{}.test", local_unix_socket_name()));
+        let socket = UnixDatagram::bind(&path).unwrap();
+        let socket_file = LocalSocketFile(path.clone());
+        assert!(path.exists());
+        drop(socket);
+        drop(socket_file);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn unix_socket_round_trip() {
+        let peer_path = std::env::temp_dir().join(format!("{}.peer", local_unix_socket_name()));
+        let _peer_file = LocalSocketFile(peer_path.clone());
+        let peer = UnixDatagram::bind(&peer_path).unwrap();
+
+        let mut config = IpLocateServiceConfig::default();
+        config.set_query_peer_path(peer_path);
+        let (local, local_file) = config
+            .new_unix_socket(config.query_peer_path.as_ref().unwrap())
+            .unwrap();
+        #[cfg(target_os = "linux")]
+        assert!(local_file.is_none());
+        #[cfg(not(target_os = "linux"))]
+        let local_path = local_file.as_ref().map(|f| f.0.clone()).unwrap();
+
+        local.send(b"req").unwrap();
+        let mut buf = [0u8; 16];
+        let (len, addr) = peer.recv_from(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"req");
+        peer.send_to_addr(b"rsp", &addr).unwrap();
+        let len = local.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"rsp");
+
+        drop(local);
+        drop(local_file);
+        #[cfg(not(target_os = "linux"))]
+        assert!(!local_path.exists());
+    }
+}
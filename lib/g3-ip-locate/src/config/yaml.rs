@@ -21,6 +21,15 @@ use crate::IpLocateServiceConfig;
 
 impl IpLocateServiceConfig {
     fn set_query_peer_addr_by_yaml(&mut self, value: &Yaml) -> anyhow::Result<()> {
+        #[cfg(unix)]
+        if let Yaml::String(s) = value {
+            if s.starts_with('/') {
+                let path = g3_yaml::value::as_absolute_path(value)?;
+                self.set_query_peer_path(path);
+                return Ok(());
+            }
+        }
+
         let addr = g3_yaml::value::as_env_sockaddr(value)?;
         self.set_query_peer_addr(addr);
         Ok(())
@@ -44,9 +53,9 @@ impl IpLocateServiceConfig {
                         Ok(())
                     }
                     "query_peer_addr" => {
-                        config
-                            .set_query_peer_addr_by_yaml(v)
-                            .context(format!("invalid sockaddr str value for key {k}"))?;
+                        config.set_query_peer_addr_by_yaml(v).context(format!(
+                            "invalid sockaddr str or unix socket path value for key {k}"
+                        ))?;
                         Ok(())
                     }
                     "query_socket_buffer" => {
@@ -61,6 +70,16 @@ impl IpLocateServiceConfig {
                         config.set_query_wait_timeout(time);
                         Ok(())
                     }
+                    "query_max_in_flight" => {
+                        let max = g3_yaml::value::as_nonzero_usize(v)?;
+                        config.set_query_max_in_flight(max);
+                        Ok(())
+                    }
+                    "query_max_queued" => {
+                        let max = g3_yaml::value::as_usize(v)?;
+                        config.set_query_max_queued(max);
+                        Ok(())
+                    }
                     "default_expire_ttl" => {
                         let ttl = g3_yaml::value::as_u32(v)?;
                         config.set_default_expire_ttl(ttl);
@@ -71,16 +90,22 @@ impl IpLocateServiceConfig {
                         config.set_maximum_expire_ttl(ttl);
                         Ok(())
                     }
+                    "negative_expire_ttl" => {
+                        let ttl = g3_yaml::value::as_u32(v)?;
+                        config.set_negative_expire_ttl(ttl);
+                        Ok(())
+                    }
                     _ => Err(anyhow!("invalid key {k}")),
                 })?;
 
+                config.check()?;
                 Ok(config)
             }
             Yaml::String(_) => {
                 let mut config = IpLocateServiceConfig::default();
                 config
                     .set_query_peer_addr_by_yaml(value)
-                    .context("invalid sockaddr str value")?;
+                    .context("invalid sockaddr str or unix socket path value")?;
                 Ok(config)
             }
             _ => Err(anyhow!(
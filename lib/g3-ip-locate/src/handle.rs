@@ -42,6 +42,9 @@ impl IpLocationServiceHandle {
     }
 
     pub async fn fetch(&self, ip: IpAddr) -> Option<Arc<IpLocation>> {
+        if let Some(location) = crate::static_table::fetch_static(ip) {
+            return Some(location);
+        }
         self.cache_handle.fetch(ip, self.request_timeout).await
     }
 }
@@ -119,6 +122,15 @@ impl IpLocationQueryHandle {
         let _ = self.rsp_sender.send((ip, data));
     }
 
+    /// Get the count of queries that have been sent but not responded yet
+    pub(super) fn in_flight_count(&self) -> usize {
+        self.doing_cache.len()
+    }
+
+    pub(super) fn is_querying(&self, ip: &IpAddr) -> bool {
+        self.doing_cache.contains_key(ip)
+    }
+
     pub(super) fn should_send_raw_query(&mut self, ip: IpAddr, query_wait: Duration) -> bool {
         match self.doing_cache.entry(ip) {
             hash_map::Entry::Occupied(_) => false,
@@ -31,10 +31,12 @@ pub use handle::IpLocationServiceHandle;
 use handle::{IpLocationCacheHandle, IpLocationQueryHandle};
 
 mod cache;
+pub use cache::negative_cache_hits;
 use cache::IpLocationCacheRuntime;
 
 mod query;
-use query::IpLocationQueryRuntime;
+pub use query::{query_in_flight_count, query_queued_count};
+use query::{IpLocationQueryRuntime, QuerySocket};
 
 mod protocol;
 pub use protocol::{request_key, request_key_id, response_key, response_key_id};
@@ -48,6 +50,12 @@ pub use response::Response;
 mod runtime;
 pub use runtime::*;
 
+mod static_table;
+pub use static_table::{load_static_table, static_table_entry_count};
+
+mod aggregate;
+pub use aggregate::aggregate_ip_locations;
+
 struct CacheQueryRequest {
     ip: IpAddr,
     notifier: oneshot::Sender<Arc<IpLocation>>,
@@ -56,6 +64,8 @@ struct CacheQueryRequest {
 struct IpLocationCacheResponse {
     value: Option<IpLocation>,
     expire_at: Instant,
+    /// the peer has told us that there is no location for the ip
+    negative: bool,
 }
 
 impl IpLocationCacheResponse {
@@ -67,6 +77,7 @@ impl IpLocationCacheResponse {
         IpLocationCacheResponse {
             value: Some(location),
             expire_at,
+            negative: false,
         }
     }
 
@@ -78,8 +89,15 @@ impl IpLocationCacheResponse {
         IpLocationCacheResponse {
             value: None,
             expire_at,
+            negative: false,
         }
     }
+
+    fn negative(ttl: u32) -> Self {
+        let mut rsp = IpLocationCacheResponse::empty(ttl);
+        rsp.negative = true;
+        rsp
+    }
 }
 
 fn crate_ip_location_cache(
@@ -19,32 +19,82 @@ use std::future::Future;
 use std::io;
 use std::net::IpAddr;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::task::{Context, Poll};
 use std::time::Duration;
 
+use ahash::AHashSet;
 use anyhow::anyhow;
 use log::warn;
 use tokio::io::ReadBuf;
 use tokio::net::UdpSocket;
+#[cfg(unix)]
+use tokio::net::UnixDatagram;
 
+#[cfg(unix)]
+use super::config::LocalSocketFile;
 use super::{
     IpLocateServiceConfig, IpLocationCacheResponse, IpLocationQueryHandle, Request, Response,
 };
 
+static QUERY_IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+static QUERY_QUEUED: AtomicUsize = AtomicUsize::new(0);
+
+/// Get the count of in-flight queries of all ip locate services
+pub fn query_in_flight_count() -> usize {
+    QUERY_IN_FLIGHT.load(Ordering::Relaxed)
+}
+
+/// Get the count of queued queries of all ip locate services
+pub fn query_queued_count() -> usize {
+    QUERY_QUEUED.load(Ordering::Relaxed)
+}
+
+pub(crate) enum QuerySocket {
+    Udp(UdpSocket),
+    #[cfg(unix)]
+    Unix(UnixDatagram, Option<LocalSocketFile>),
+}
+
+impl QuerySocket {
+    fn poll_recv(&self, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        match self {
+            QuerySocket::Udp(s) => s.poll_recv(cx, buf),
+            #[cfg(unix)]
+            QuerySocket::Unix(s, _) => s.poll_recv(cx, buf),
+        }
+    }
+
+    fn poll_send(&self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        match self {
+            QuerySocket::Udp(s) => s.poll_send(cx, buf),
+            #[cfg(unix)]
+            QuerySocket::Unix(s, _) => s.poll_send(cx, buf),
+        }
+    }
+}
+
 pub(crate) struct IpLocationQueryRuntime {
-    socket: UdpSocket,
+    socket: QuerySocket,
     query_handle: IpLocationQueryHandle,
     read_buffer: Box<[u8]>,
     write_queue: VecDeque<(IpAddr, Vec<u8>)>,
+    pending_queue: VecDeque<IpAddr>,
+    pending_set: AHashSet<IpAddr>,
+    max_in_flight: usize,
+    max_queued: usize,
+    reported_in_flight: usize,
+    reported_queued: usize,
     default_expire_ttl: u32,
     maximum_expire_ttl: u32,
+    negative_expire_ttl: u32,
     query_wait: Duration,
 }
 
 impl IpLocationQueryRuntime {
     pub(crate) fn new(
         config: &IpLocateServiceConfig,
-        socket: UdpSocket,
+        socket: QuerySocket,
         query_handle: IpLocationQueryHandle,
     ) -> Self {
         IpLocationQueryRuntime {
@@ -52,8 +102,15 @@ impl IpLocationQueryRuntime {
             query_handle,
             read_buffer: vec![0u8; 16384].into_boxed_slice(),
             write_queue: VecDeque::new(),
+            pending_queue: VecDeque::new(),
+            pending_set: AHashSet::new(),
+            max_in_flight: config.query_max_in_flight.get(),
+            max_queued: config.query_max_queued,
+            reported_in_flight: 0,
+            reported_queued: 0,
             default_expire_ttl: config.default_expire_ttl,
             maximum_expire_ttl: config.maximum_expire_ttl,
+            negative_expire_ttl: config.negative_expire_ttl,
             query_wait: config.query_wait_timeout,
         }
     }
@@ -63,12 +120,17 @@ impl IpLocationQueryRuntime {
         self.query_handle.send_rsp_data(Some(ip), result, expired);
     }
 
+    fn send_negative_result(&mut self, ip: IpAddr, ttl: u32) {
+        let result = IpLocationCacheResponse::negative(ttl);
+        self.query_handle.send_rsp_data(Some(ip), result, false);
+    }
+
     fn send_expire_ttl(&mut self, ttl: u32) {
         let result = IpLocationCacheResponse::empty(ttl);
         self.query_handle.send_rsp_data(None, result, false);
     }
 
-    fn handle_req(&mut self, ip: IpAddr) {
+    fn send_query(&mut self, ip: IpAddr) {
         if self.query_handle.should_send_raw_query(ip, self.query_wait) {
             match Request::encode_new(ip) {
                 Ok(buf) => self.write_queue.push_back((ip, buf)),
@@ -77,6 +139,48 @@ impl IpLocationQueryRuntime {
         }
     }
 
+    fn handle_req(&mut self, ip: IpAddr) {
+        if self.query_handle.is_querying(&ip) || self.pending_set.contains(&ip) {
+            return;
+        }
+
+        if self.query_handle.in_flight_count() < self.max_in_flight {
+            self.send_query(ip);
+        } else if self.pending_queue.len() < self.max_queued {
+            self.pending_queue.push_back(ip);
+            self.pending_set.insert(ip);
+        } else {
+            // fail fast if there are too many queries
+            self.send_empty_result(ip, self.default_expire_ttl, false);
+        }
+    }
+
+    fn send_pending_queries(&mut self) {
+        while self.query_handle.in_flight_count() < self.max_in_flight {
+            let Some(ip) = self.pending_queue.pop_front() else {
+                break;
+            };
+            self.pending_set.remove(&ip);
+            self.send_query(ip);
+        }
+    }
+
+    fn update_stats(&mut self) {
+        let in_flight = self.query_handle.in_flight_count();
+        if in_flight != self.reported_in_flight {
+            QUERY_IN_FLIGHT.fetch_add(in_flight, Ordering::Relaxed);
+            QUERY_IN_FLIGHT.fetch_sub(self.reported_in_flight, Ordering::Relaxed);
+            self.reported_in_flight = in_flight;
+        }
+
+        let queued = self.pending_queue.len();
+        if queued != self.reported_queued {
+            QUERY_QUEUED.fetch_add(queued, Ordering::Relaxed);
+            QUERY_QUEUED.fetch_sub(self.reported_queued, Ordering::Relaxed);
+            self.reported_queued = queued;
+        }
+    }
+
     fn handle_rsp(&mut self, len: usize) {
         let mut buf = &self.read_buffer[..len];
         match rmpv::decode::read_value_ref(&mut buf)
@@ -84,8 +188,8 @@ impl IpLocationQueryRuntime {
             .and_then(|v| Response::parse(v))
             .map(|r| r.into_parts())
         {
-            Ok((ip, location, ttl)) => {
-                let ttl = ttl
+            Ok((ip, location, rsp_ttl)) => {
+                let ttl = rsp_ttl
                     .unwrap_or(self.default_expire_ttl)
                     .min(self.maximum_expire_ttl);
 
@@ -93,7 +197,10 @@ impl IpLocationQueryRuntime {
                     let result = IpLocationCacheResponse::new(location, ttl);
                     self.query_handle.send_rsp_data(ip, result, false);
                 } else if let Some(ip) = ip {
-                    self.send_empty_result(ip, ttl, false);
+                    let ttl = rsp_ttl
+                        .unwrap_or(self.negative_expire_ttl)
+                        .min(self.negative_expire_ttl);
+                    self.send_negative_result(ip, ttl);
                 } else {
                     self.send_expire_ttl(ttl);
                 }
@@ -123,6 +230,20 @@ impl IpLocationQueryRuntime {
                 }
             }
 
+            // handle timeout
+            loop {
+                match self.query_handle.poll_query_expired(cx) {
+                    Poll::Pending => break,
+                    Poll::Ready(None) => break,
+                    Poll::Ready(Some(ip)) => {
+                        self.send_empty_result(ip, self.default_expire_ttl, true)
+                    }
+                }
+            }
+
+            // move queued req to write queue if allowed
+            self.send_pending_queries();
+
             // send req from write queue
             while let Some((ip, buf)) = self.write_queue.pop_front() {
                 match self.socket.poll_send(cx, &buf) {
@@ -137,20 +258,12 @@ impl IpLocationQueryRuntime {
                 }
             }
 
-            // handle timeout
-            loop {
-                match self.query_handle.poll_query_expired(cx) {
-                    Poll::Pending => break,
-                    Poll::Ready(None) => break,
-                    Poll::Ready(Some(ip)) => {
-                        self.send_empty_result(ip, self.default_expire_ttl, true)
-                    }
-                }
-            }
-
             // handle req
             match self.query_handle.poll_recv_req(cx) {
-                Poll::Pending => return Poll::Pending,
+                Poll::Pending => {
+                    self.update_stats();
+                    return Poll::Pending;
+                }
                 Poll::Ready(None) => return Poll::Ready(Ok(())),
                 Poll::Ready(Some(req)) => self.handle_req(req),
             }
@@ -158,6 +271,13 @@ impl IpLocationQueryRuntime {
     }
 }
 
+impl Drop for IpLocationQueryRuntime {
+    fn drop(&mut self) {
+        QUERY_IN_FLIGHT.fetch_sub(self.reported_in_flight, Ordering::Relaxed);
+        QUERY_QUEUED.fetch_sub(self.reported_queued, Ordering::Relaxed);
+    }
+}
+
 impl Future for IpLocationQueryRuntime {
     type Output = io::Result<()>;
 
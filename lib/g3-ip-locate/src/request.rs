@@ -83,11 +83,10 @@ impl Request {
     }
 
     pub fn encode_new(ip: IpAddr) -> anyhow::Result<Vec<u8>> {
-        let ip = ip.to_string();
-        let value = ValueRef::String(ip.as_str().into());
+        let value = g3_msgpack::value::encode_ipaddr(ip);
 
         let mut buf = Vec::with_capacity(320);
-        rmpv::encode::write_value_ref(&mut buf, &value)?;
+        rmpv::encode::write_value(&mut buf, &value)?;
         Ok(buf)
     }
 }
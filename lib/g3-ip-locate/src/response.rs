@@ -151,16 +151,13 @@ impl Response {
     }
 
     pub fn encode_new(ip: IpAddr, location: IpLocation, ttl: u32) -> anyhow::Result<Vec<u8>> {
-        let ip = ip.to_string();
-        let network = location.network_addr().to_string();
+        let ip = g3_msgpack::value::encode_ipaddr(ip);
+        let network = g3_msgpack::value::encode_ip_network(location.network_addr());
         let mut map = vec![
-            (
-                ValueRef::Integer(response_key_id::IP.into()),
-                ValueRef::String(ip.as_str().into()),
-            ),
+            (ValueRef::Integer(response_key_id::IP.into()), ip.as_ref()),
             (
                 ValueRef::Integer(response_key_id::NETWORK.into()),
-                ValueRef::String(network.as_str().into()),
+                network.as_ref(),
             ),
             (
                 ValueRef::Integer(response_key_id::TTL.into()),
@@ -0,0 +1,62 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::IpAddr;
+use std::sync::{Arc, LazyLock};
+
+use arc_swap::ArcSwap;
+use ip_network_table::IpNetworkTable;
+
+use g3_geoip_types::IpLocation;
+
+static STATIC_TABLE: LazyLock<ArcSwap<StaticIpLocationTable>> =
+    LazyLock::new(|| ArcSwap::from_pointee(StaticIpLocationTable::default()));
+
+#[derive(Default)]
+struct StaticIpLocationTable {
+    table: IpNetworkTable<Arc<IpLocation>>,
+    count: usize,
+}
+
+/// Replace the static ip location table, which will be checked before the ip locate service.
+///
+/// The new table is built completely before the swap, so a lookup will see either the old
+/// or the new table. Returns the count of entries in the new table.
+pub fn load_static_table(locations: Vec<IpLocation>) -> usize {
+    let mut table = IpNetworkTable::new();
+    for location in locations {
+        table.insert(location.network_addr(), Arc::new(location));
+    }
+    let (ipv4_count, ipv6_count) = table.len();
+    let count = ipv4_count + ipv6_count;
+    STATIC_TABLE.store(Arc::new(StaticIpLocationTable { table, count }));
+    count
+}
+
+pub fn static_table_entry_count() -> usize {
+    STATIC_TABLE.load().count
+}
+
+pub(crate) fn fetch_static(ip: IpAddr) -> Option<Arc<IpLocation>> {
+    let table = STATIC_TABLE.load();
+    if table.count == 0 {
+        return None;
+    }
+    table
+        .table
+        .longest_match(ip)
+        .map(|(_, location)| Arc::clone(location))
+}
@@ -0,0 +1,100 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use humanize_rs::ParseError;
+use rmpv::ValueRef;
+
+/// The max duration that is allowed to be transferred, which is also the max value of u32 ttl
+const MAX_DURATION: Duration = Duration::from_secs(u32::MAX as u64);
+
+fn check_duration(d: Duration) -> anyhow::Result<Duration> {
+    if d > MAX_DURATION {
+        Err(anyhow!("too large duration value {d:?}"))
+    } else {
+        Ok(d)
+    }
+}
+
+fn parse_duration_str(s: &str) -> anyhow::Result<Duration> {
+    match humanize_rs::duration::parse(s) {
+        Ok(d) => check_duration(d),
+        Err(ParseError::MissingUnit) => {
+            let secs = u64::from_str(s).map_err(|e| anyhow!("invalid duration string: {e}"))?;
+            check_duration(Duration::from_secs(secs))
+        }
+        Err(e) => Err(anyhow!("invalid humanize duration string: {e}")),
+    }
+}
+
+pub fn as_duration(v: &ValueRef) -> anyhow::Result<Duration> {
+    match v {
+        ValueRef::String(s) => match s.as_str() {
+            Some(s) => parse_duration_str(s),
+            None => Err(anyhow!("invalid utf-8 string")),
+        },
+        ValueRef::Binary(b) => {
+            let s = std::str::from_utf8(b).map_err(|e| anyhow!("invalid utf-8 string: {e}"))?;
+            parse_duration_str(s)
+        }
+        ValueRef::Integer(i) => match i.as_u64() {
+            Some(secs) => check_duration(Duration::from_secs(secs)),
+            None => Err(anyhow!("invalid unsigned integer value")),
+        },
+        _ => Err(anyhow!(
+            "msgpack value type for humanize duration should be 'integer' / 'string' / 'binary'"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmpv::Utf8StringRef;
+
+    #[test]
+    fn t_duration() {
+        let v = ValueRef::String(Utf8StringRef::from("1h2m"));
+        assert_eq!(as_duration(&v).unwrap(), Duration::from_secs(3600 + 120));
+
+        let v = ValueRef::String(Utf8StringRef::from("1000"));
+        assert_eq!(as_duration(&v).unwrap(), Duration::from_secs(1000));
+
+        let v = ValueRef::Binary(b"10s");
+        assert_eq!(as_duration(&v).unwrap(), Duration::from_secs(10));
+
+        let v = ValueRef::from(300u32);
+        assert_eq!(as_duration(&v).unwrap(), Duration::from_secs(300));
+
+        let v = ValueRef::from(-1i32);
+        assert!(as_duration(&v).is_err());
+
+        let v = ValueRef::String(Utf8StringRef::from("-1000"));
+        assert!(as_duration(&v).is_err());
+
+        let v = ValueRef::from(u64::MAX);
+        assert!(as_duration(&v).is_err());
+
+        let v = ValueRef::String(Utf8StringRef::from("1000000000h"));
+        assert!(as_duration(&v).is_err());
+
+        let v = ValueRef::F64(1.0);
+        assert!(as_duration(&v).is_err());
+    }
+}
@@ -14,5 +14,6 @@
  * limitations under the License.
  */
 
+pub mod humanize;
 pub mod key;
 pub mod value;
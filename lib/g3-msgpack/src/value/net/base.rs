@@ -14,14 +14,29 @@
  * limitations under the License.
  */
 
-use std::net::IpAddr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::str::FromStr;
 
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 #[cfg(feature = "geoip")]
 use ip_network::IpNetwork;
-use rmpv::ValueRef;
+use rmpv::{Value, ValueRef};
 
+use g3_types::net::{UpstreamAddr, WeightedUpstreamAddr};
+
+fn ipaddr_from_octets(b: &[u8]) -> Option<IpAddr> {
+    match b.len() {
+        4 => Some(IpAddr::V4(Ipv4Addr::new(b[0], b[1], b[2], b[3]))),
+        16 => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(b);
+            Some(IpAddr::V6(Ipv6Addr::from(octets)))
+        }
+        _ => None,
+    }
+}
+
+/// Parse a string or a 4 (for v4) or 16 (for v6) bytes binary as `IpAddr`
 pub fn as_ipaddr(value: &ValueRef) -> anyhow::Result<IpAddr> {
     match value {
         ValueRef::String(s) => {
@@ -31,15 +46,34 @@ pub fn as_ipaddr(value: &ValueRef) -> anyhow::Result<IpAddr> {
             let ip = IpAddr::from_str(s).map_err(|e| anyhow!("invalid ip address: {e}"))?;
             Ok(ip)
         }
+        ValueRef::Binary(b) => ipaddr_from_octets(b)
+            .ok_or_else(|| anyhow!("invalid {} bytes value for ip address", b.len())),
         _ => Err(anyhow!(
-            "msgpack value type for 'IpAddr' should be 'string'"
+            "msgpack value type for 'IpAddr' should be 'string' or 'binary'"
         )),
     }
 }
 
+/// Parse a string or a binary as `IpNetwork`.
+///
+/// The binary can be the ip address octets for a single host network,
+/// or the network address octets followed by the 1 byte prefix length.
 #[cfg(feature = "geoip")]
 pub fn as_ip_network(value: &ValueRef) -> anyhow::Result<IpNetwork> {
-    if let ValueRef::String(s) = value {
+    if let ValueRef::Binary(b) = value {
+        let (ip, prefix) = match b.len() {
+            4 | 16 => {
+                let ip = ipaddr_from_octets(b).unwrap();
+                (ip, if ip.is_ipv4() { 32 } else { 128 })
+            }
+            5 | 17 => {
+                let (prefix, octets) = b.split_last().unwrap();
+                (ipaddr_from_octets(octets).unwrap(), *prefix)
+            }
+            len => return Err(anyhow!("invalid {len} bytes value for ip network")),
+        };
+        IpNetwork::new(ip, prefix).map_err(|e| anyhow!("invalid ip network: {e}"))
+    } else if let ValueRef::String(s) = value {
         let s = s
             .as_str()
             .ok_or(anyhow!("invalid utf-8 ip network string value"))?;
@@ -58,7 +92,299 @@ pub fn as_ip_network(value: &ValueRef) -> anyhow::Result<IpNetwork> {
         Ok(net)
     } else {
         Err(anyhow!(
-            "yaml value type for 'IpNetwork' should be 'string'"
+            "msgpack value type for 'IpNetwork' should be 'string' or 'binary'"
         ))
     }
 }
+
+/// Parse a tagged map like `{"type": "v4", "addr": "127.0.0.1"}` as `IpAddr`.
+///
+/// The address can be a string, or a 4 (for v4) or 16 (for v6) bytes binary,
+/// and should match the declared address family.
+pub fn as_tagged_ipaddr(value: &ValueRef) -> anyhow::Result<IpAddr> {
+    let map = match value {
+        ValueRef::Map(map) => map,
+        _ => {
+            return Err(anyhow!(
+                "msgpack value type for 'tagged IpAddr' should be 'map'"
+            ))
+        }
+    };
+
+    let mut family = None;
+    let mut addr = None;
+    for (k, v) in map {
+        let key = crate::value::as_string(k).context("all keys should be string")?;
+        match crate::key::normalize(key.as_str()).as_str() {
+            "type" | "family" => {
+                let s = crate::value::as_string(v)
+                    .context(format!("invalid string value for key {key}"))?;
+                let is_ipv4 = match s.to_lowercase().as_str() {
+                    "v4" | "ipv4" => true,
+                    "v6" | "ipv6" => false,
+                    _ => return Err(anyhow!("invalid address family {s} for key {key}")),
+                };
+                family = Some(is_ipv4);
+            }
+            "addr" | "address" => addr = Some(v),
+            _ => return Err(anyhow!("invalid key {key}")),
+        }
+    }
+
+    let is_ipv4 = family.ok_or_else(|| anyhow!("no address family set"))?;
+    let addr = addr.ok_or_else(|| anyhow!("no address set"))?;
+    let ip = match addr {
+        ValueRef::Binary(b) => match (is_ipv4, b.len()) {
+            (true, 4) | (false, 16) => ipaddr_from_octets(b).unwrap(),
+            (true, len) => return Err(anyhow!("invalid {len} bytes value for ipv4 address")),
+            (false, len) => return Err(anyhow!("invalid {len} bytes value for ipv6 address")),
+        },
+        _ => {
+            let ip = as_ipaddr(addr).context("invalid address value")?;
+            if ip.is_ipv4() != is_ipv4 {
+                return Err(anyhow!("the address {ip} doesn't match the address family"));
+            }
+            ip
+        }
+    };
+    Ok(ip)
+}
+
+pub fn encode_ipaddr(ip: IpAddr) -> Value {
+    Value::String(ip.to_string().into())
+}
+
+/// Encode the ip address as the compact 4 or 16 bytes binary form
+pub fn encode_ipaddr_bin(ip: IpAddr) -> Value {
+    match ip {
+        IpAddr::V4(ip4) => Value::Binary(ip4.octets().to_vec()),
+        IpAddr::V6(ip6) => Value::Binary(ip6.octets().to_vec()),
+    }
+}
+
+#[cfg(feature = "geoip")]
+pub fn encode_ip_network(net: IpNetwork) -> Value {
+    // use the plain ip address form for single host networks
+    let s = match net {
+        IpNetwork::V4(n) if n.netmask() == 32 => n.network_address().to_string(),
+        IpNetwork::V6(n) if n.netmask() == 128 => n.network_address().to_string(),
+        _ => net.to_string(),
+    };
+    Value::String(s.into())
+}
+
+/// Encode the ip network as the compact binary form, which is the same as
+/// [encode_ipaddr_bin] for single host networks, or with the prefix length appended
+#[cfg(feature = "geoip")]
+pub fn encode_ip_network_bin(net: IpNetwork) -> Value {
+    let (mut buf, prefix, host_prefix) = match net {
+        IpNetwork::V4(n) => (n.network_address().octets().to_vec(), n.netmask(), 32),
+        IpNetwork::V6(n) => (n.network_address().octets().to_vec(), n.netmask(), 128),
+    };
+    if prefix != host_prefix {
+        buf.push(prefix);
+    }
+    Value::Binary(buf)
+}
+
+pub fn as_upstream_addr(value: &ValueRef, default_port: u16) -> anyhow::Result<UpstreamAddr> {
+    let s = crate::value::as_string(value).context("invalid upstream addr string value")?;
+    let mut addr = UpstreamAddr::from_str(&s).context("invalid upstream addr string")?;
+    if addr.port() == 0 {
+        if default_port == 0 {
+            return Err(anyhow!("port is required"));
+        }
+        addr.set_port(default_port);
+    }
+    Ok(addr)
+}
+
+pub fn as_weighted_upstream_addr(
+    value: &ValueRef,
+    default_port: u16,
+) -> anyhow::Result<WeightedUpstreamAddr> {
+    match value {
+        ValueRef::Map(map) => {
+            let mut addr = None;
+            let mut weight = WeightedUpstreamAddr::DEFAULT_WEIGHT;
+
+            for (k, v) in map {
+                let key = crate::value::as_string(k).context("all keys should be string")?;
+                match crate::key::normalize(key.as_str()).as_str() {
+                    "addr" | "address" => {
+                        let a = as_upstream_addr(v, default_port)
+                            .context(format!("invalid upstream addr value for key {key}"))?;
+                        addr = Some(a);
+                    }
+                    "weight" => {
+                        weight = crate::value::as_f64(v)
+                            .context(format!("invalid f64 value for key {key}"))?;
+                    }
+                    _ => {} // ignore all other keys
+                }
+            }
+
+            match addr {
+                Some(addr) => Ok(WeightedUpstreamAddr::with_weight(addr, weight)),
+                None => Err(anyhow!("no valid upstream addr set")),
+            }
+        }
+        _ => {
+            let addr = as_upstream_addr(value, default_port)?;
+            Ok(WeightedUpstreamAddr::new(addr))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmpv::Utf8StringRef;
+
+    #[test]
+    fn t_ipaddr() {
+        for s in ["127.0.0.1", "::1", "2001:db8::1"] {
+            let ip = IpAddr::from_str(s).unwrap();
+            let v = encode_ipaddr(ip);
+            assert_eq!(v.as_str(), Some(s));
+            assert_eq!(as_ipaddr(&v.as_ref()).unwrap(), ip);
+        }
+    }
+
+    #[cfg(feature = "geoip")]
+    #[test]
+    fn t_ip_network() {
+        for s in ["192.168.0.0/16", "2001:db8::/32"] {
+            let net = IpNetwork::from_str(s).unwrap();
+            let v = encode_ip_network(net);
+            assert_eq!(v.as_str(), Some(s));
+            assert_eq!(as_ip_network(&v.as_ref()).unwrap(), net);
+        }
+
+        for s in ["192.168.1.1", "2001:db8::1"] {
+            let ip = IpAddr::from_str(s).unwrap();
+            let prefix = if ip.is_ipv4() { 32 } else { 128 };
+            let net = IpNetwork::new(ip, prefix).unwrap();
+            let v = encode_ip_network(net);
+            assert_eq!(v.as_str(), Some(s));
+            assert_eq!(as_ip_network(&v.as_ref()).unwrap(), net);
+        }
+    }
+
+    #[test]
+    fn t_tagged_ipaddr() {
+        fn tagged<'a>(family: &'a str, addr: ValueRef<'a>) -> ValueRef<'a> {
+            ValueRef::Map(vec![
+                (
+                    ValueRef::String(Utf8StringRef::from("type")),
+                    ValueRef::String(Utf8StringRef::from(family)),
+                ),
+                (ValueRef::String(Utf8StringRef::from("addr")), addr),
+            ])
+        }
+        let v6_octets = Ipv6Addr::LOCALHOST.octets();
+
+        let v = tagged("v4", ValueRef::String(Utf8StringRef::from("127.0.0.1")));
+        assert_eq!(
+            as_tagged_ipaddr(&v).unwrap(),
+            IpAddr::V4(Ipv4Addr::LOCALHOST)
+        );
+        let v = tagged("v4", ValueRef::Binary(&[127, 0, 0, 1]));
+        assert_eq!(
+            as_tagged_ipaddr(&v).unwrap(),
+            IpAddr::V4(Ipv4Addr::LOCALHOST)
+        );
+        let v = tagged("v6", ValueRef::String(Utf8StringRef::from("::1")));
+        assert_eq!(
+            as_tagged_ipaddr(&v).unwrap(),
+            IpAddr::V6(Ipv6Addr::LOCALHOST)
+        );
+        let v = tagged("v6", ValueRef::Binary(&v6_octets));
+        assert_eq!(
+            as_tagged_ipaddr(&v).unwrap(),
+            IpAddr::V6(Ipv6Addr::LOCALHOST)
+        );
+
+        let v = tagged("v4", ValueRef::Binary(&v6_octets));
+        assert!(as_tagged_ipaddr(&v).is_err());
+        let v = tagged("v6", ValueRef::String(Utf8StringRef::from("127.0.0.1")));
+        assert!(as_tagged_ipaddr(&v).is_err());
+        let v = tagged("v5", ValueRef::String(Utf8StringRef::from("127.0.0.1")));
+        assert!(as_tagged_ipaddr(&v).is_err());
+    }
+
+    #[test]
+    fn t_ipaddr_bin() {
+        for s in ["127.0.0.1", "::1", "2001:db8::1"] {
+            let ip = IpAddr::from_str(s).unwrap();
+            let v = encode_ipaddr_bin(ip);
+            let len = if ip.is_ipv4() { 4 } else { 16 };
+            assert_eq!(v.as_slice().map(|b| b.len()), Some(len));
+            assert_eq!(as_ipaddr(&v.as_ref()).unwrap(), ip);
+        }
+
+        let v = Value::Binary(vec![127, 0, 0]);
+        assert!(as_ipaddr(&v.as_ref()).is_err());
+    }
+
+    #[cfg(feature = "geoip")]
+    #[test]
+    fn t_ip_network_bin() {
+        for (s, len) in [("192.168.0.0/16", 5), ("2001:db8::/32", 17)] {
+            let net = IpNetwork::from_str(s).unwrap();
+            let v = encode_ip_network_bin(net);
+            assert_eq!(v.as_slice().map(|b| b.len()), Some(len));
+            assert_eq!(as_ip_network(&v.as_ref()).unwrap(), net);
+        }
+
+        for (s, len) in [("192.168.1.1", 4), ("2001:db8::1", 16)] {
+            let ip = IpAddr::from_str(s).unwrap();
+            let prefix = if ip.is_ipv4() { 32 } else { 128 };
+            let net = IpNetwork::new(ip, prefix).unwrap();
+            let v = encode_ip_network_bin(net);
+            assert_eq!(v.as_slice().map(|b| b.len()), Some(len));
+            assert_eq!(as_ip_network(&v.as_ref()).unwrap(), net);
+        }
+
+        // invalid prefix length
+        let v = Value::Binary(vec![192, 168, 0, 0, 33]);
+        assert!(as_ip_network(&v.as_ref()).is_err());
+        let v = Value::Binary(vec![192, 168, 0]);
+        assert!(as_ip_network(&v.as_ref()).is_err());
+    }
+
+    #[test]
+    fn t_upstream_addr() {
+        let v = ValueRef::String(Utf8StringRef::from("127.0.0.1:8080"));
+        let addr = as_upstream_addr(&v, 0).unwrap();
+        assert_eq!(addr.port(), 8080);
+
+        let v = ValueRef::String(Utf8StringRef::from("proxy.example.net"));
+        let addr = as_upstream_addr(&v, 3128).unwrap();
+        assert_eq!(addr.port(), 3128);
+        assert!(as_upstream_addr(&v, 0).is_err());
+    }
+
+    #[test]
+    fn t_weighted_upstream_addr() {
+        let v = ValueRef::Map(vec![
+            (
+                ValueRef::String(Utf8StringRef::from("addr")),
+                ValueRef::String(Utf8StringRef::from("127.0.0.1:8080")),
+            ),
+            (
+                ValueRef::String(Utf8StringRef::from("weight")),
+                ValueRef::F64(2.0),
+            ),
+        ]);
+        let node = as_weighted_upstream_addr(&v, 3128).unwrap();
+        assert_eq!(node.inner().port(), 8080);
+        assert_eq!(node.weight(), 2.0);
+
+        let v = ValueRef::Map(vec![(
+            ValueRef::String(Utf8StringRef::from("weight")),
+            ValueRef::F64(2.0),
+        )]);
+        assert!(as_weighted_upstream_addr(&v, 3128).is_err());
+    }
+}
@@ -16,7 +16,10 @@
 
 mod base;
 
-pub use base::as_ipaddr;
+pub use base::{
+    as_ipaddr, as_tagged_ipaddr, as_upstream_addr, as_weighted_upstream_addr, encode_ipaddr,
+    encode_ipaddr_bin,
+};
 
 #[cfg(feature = "geoip")]
-pub use base::as_ip_network;
+pub use base::{as_ip_network, encode_ip_network, encode_ip_network_bin};
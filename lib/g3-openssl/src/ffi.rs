@@ -31,6 +31,10 @@ pub const ASYNC_STATUS_ERR: c_int = 1;
 pub const ASYNC_STATUS_OK: c_int = 2;
 pub const ASYNC_STATUS_EAGAIN: c_int = 3;
 
+pub const SSL_EARLY_DATA_NOT_SENT: c_int = 0;
+pub const SSL_EARLY_DATA_REJECTED: c_int = 1;
+pub const SSL_EARLY_DATA_ACCEPTED: c_int = 2;
+
 #[allow(non_camel_case_types)]
 pub enum ASYNC_JOB {}
 
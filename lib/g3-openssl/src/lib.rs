@@ -22,4 +22,7 @@ pub mod async_job;
 mod ssl;
 #[cfg(feature = "async-job")]
 pub use ssl::SslAsyncModeExt;
-pub use ssl::{SslAcceptor, SslConnector, SslLazyAcceptor, SslStream};
+pub use ssl::{
+    set_handshake_rejection, SslAcceptor, SslConnector, SslHandshakeRejected, SslLazyAcceptor,
+    SslRejectionReason, SslStream,
+};
@@ -22,7 +22,7 @@ use openssl::error::ErrorStack;
 use openssl::ssl::{self, ErrorCode, Ssl};
 use tokio::io::{AsyncRead, AsyncWrite};
 
-use super::{AsyncEnginePoller, SslIoWrapper, SslStream};
+use super::{rejection, AsyncEnginePoller, SslIoWrapper, SslStream};
 
 pub struct SslConnector<S> {
     inner: ssl::SslStream<SslIoWrapper<S>>,
@@ -30,7 +30,8 @@ pub struct SslConnector<S> {
 }
 
 impl<S: AsyncRead + AsyncWrite + Unpin> SslConnector<S> {
-    pub fn new(ssl: Ssl, stream: S) -> Result<Self, ErrorStack> {
+    pub fn new(mut ssl: Ssl, stream: S) -> Result<Self, ErrorStack> {
+        rejection::init_rejection(&mut ssl);
         let wrapper = SslIoWrapper::new(stream);
         let async_engine = AsyncEnginePoller::new(&ssl)?;
 
@@ -52,6 +53,42 @@ impl<S: AsyncRead + AsyncWrite + Unpin> SslConnector<S> {
         loop {
             match self.inner.connect() {
                 Ok(_) => return Poll::Ready(Ok(())),
+                Err(e) => match e.code() {
+                    ErrorCode::WANT_READ | ErrorCode::WANT_WRITE => return Poll::Pending,
+                    ErrorCode::WANT_ASYNC => {
+                        if let Some(async_engine) = &mut self.async_engine {
+                            ready!(async_engine.poll_ready(self.inner.ssl(), cx))?
+                        } else {
+                            return Poll::Ready(Err(io::Error::other(
+                                "async engine poller is not set",
+                            )));
+                        }
+                    }
+                    ErrorCode::WANT_ASYNC_JOB => {
+                        cx.waker().wake_by_ref();
+                        return Poll::Pending;
+                    }
+                    _ => return Poll::Ready(Err(rejection::connect_error(self.inner.ssl(), e))),
+                },
+            }
+        }
+    }
+
+    #[cfg(not(feature = "boringssl"))]
+    pub fn poll_write_early_data(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.inner.get_mut().set_cx(cx);
+        #[cfg(ossl300)]
+        if let Some(async_engine) = &self.async_engine {
+            async_engine.set_cx(cx);
+        }
+
+        loop {
+            match self.inner.write_early_data(buf) {
+                Ok(n) => return Poll::Ready(Ok(n)),
                 Err(e) => match e.code() {
                     ErrorCode::WANT_READ | ErrorCode::WANT_WRITE => return Poll::Pending,
                     ErrorCode::WANT_ASYNC => {
@@ -68,15 +105,52 @@ impl<S: AsyncRead + AsyncWrite + Unpin> SslConnector<S> {
                         return Poll::Pending;
                     }
                     _ => {
-                        return Poll::Ready(Err(e
-                            .into_io_error()
-                            .unwrap_or_else(|e| io::Error::other(format!("ssl connect: {e}")))))
+                        return Poll::Ready(Err(e.into_io_error().unwrap_or_else(|e| {
+                            io::Error::other(format!("ssl write early data: {e}"))
+                        })))
                     }
                 },
             }
         }
     }
 
+    /// Check if the session set for resumption allows sending of TLS 1.3 early data.
+    #[cfg(not(feature = "boringssl"))]
+    pub fn early_data_allowed(&self) -> bool {
+        self.inner
+            .ssl()
+            .session()
+            .map(|s| s.max_early_data() > 0)
+            .unwrap_or(false)
+    }
+
+    #[cfg(feature = "boringssl")]
+    pub fn early_data_allowed(&self) -> bool {
+        false
+    }
+
+    /// Write all data as TLS 1.3 early data. This should be called before `connect`,
+    /// and use `SslStream::early_data_accepted` after connected to check if it's accepted.
+    #[cfg(not(feature = "boringssl"))]
+    pub async fn write_all_early_data(&mut self, mut buf: &[u8]) -> io::Result<()> {
+        while !buf.is_empty() {
+            let n = future::poll_fn(|cx| self.poll_write_early_data(cx, buf)).await?;
+            if n == 0 {
+                return Err(io::Error::from(io::ErrorKind::WriteZero));
+            }
+            buf = &buf[n..];
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "boringssl")]
+    pub async fn write_all_early_data(&mut self, _buf: &[u8]) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "tls early data is not supported",
+        ))
+    }
+
     pub async fn connect(mut self) -> io::Result<SslStream<S>> {
         future::poll_fn(|cx| self.poll_connect(cx)).await?;
         Ok(SslStream::new(self.inner, None))
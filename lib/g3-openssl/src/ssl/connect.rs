@@ -23,14 +23,15 @@ use openssl::error::ErrorStack;
 use openssl::ssl::{self, ErrorCode, Ssl};
 use tokio::io::{AsyncRead, AsyncWrite};
 
-use super::{SslIoWrapper, SslStream};
+use super::{rejection, SslIoWrapper, SslStream};
 
 pub struct SslConnector<S> {
     inner: ssl::SslStream<SslIoWrapper<S>>,
 }
 
 impl<S: AsyncRead + AsyncWrite + Unpin> SslConnector<S> {
-    pub fn new(ssl: Ssl, stream: S) -> Result<Self, ErrorStack> {
+    pub fn new(mut ssl: Ssl, stream: S) -> Result<Self, ErrorStack> {
+        rejection::init_rejection(&mut ssl);
         ssl::SslStream::new(ssl, SslIoWrapper::new(stream)).map(|inner| SslConnector { inner })
     }
 }
@@ -43,15 +44,245 @@ impl<S: AsyncRead + AsyncWrite + Unpin> SslConnector<S> {
             Ok(_) => Poll::Ready(Ok(())),
             Err(e) => match e.code() {
                 ErrorCode::WANT_READ | ErrorCode::WANT_WRITE => Poll::Pending,
-                _ => Poll::Ready(Err(e
-                    .into_io_error()
-                    .unwrap_or_else(|e| io::Error::other(format!("ssl connect: {e}"))))),
+                _ => Poll::Ready(Err(rejection::connect_error(self.inner.ssl(), e))),
             },
         }
     }
 
+    #[cfg(not(feature = "boringssl"))]
+    pub fn poll_write_early_data(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.inner.get_mut().set_cx(cx);
+
+        match self.inner.write_early_data(buf) {
+            Ok(n) => Poll::Ready(Ok(n)),
+            Err(e) => match e.code() {
+                ErrorCode::WANT_READ | ErrorCode::WANT_WRITE => Poll::Pending,
+                _ => Poll::Ready(Err(e.into_io_error().unwrap_or_else(|e| {
+                    io::Error::other(format!("ssl write early data: {e}"))
+                }))),
+            },
+        }
+    }
+
+    /// Check if the session set for resumption allows sending of TLS 1.3 early data.
+    #[cfg(not(feature = "boringssl"))]
+    pub fn early_data_allowed(&self) -> bool {
+        self.inner
+            .ssl()
+            .session()
+            .map(|s| s.max_early_data() > 0)
+            .unwrap_or(false)
+    }
+
+    #[cfg(feature = "boringssl")]
+    pub fn early_data_allowed(&self) -> bool {
+        false
+    }
+
+    /// Write all data as TLS 1.3 early data. This should be called before `connect`,
+    /// and use `SslStream::early_data_accepted` after connected to check if it's accepted.
+    #[cfg(not(feature = "boringssl"))]
+    pub async fn write_all_early_data(&mut self, mut buf: &[u8]) -> io::Result<()> {
+        while !buf.is_empty() {
+            let n = future::poll_fn(|cx| self.poll_write_early_data(cx, buf)).await?;
+            if n == 0 {
+                return Err(io::Error::from(io::ErrorKind::WriteZero));
+            }
+            buf = &buf[n..];
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "boringssl")]
+    pub async fn write_all_early_data(&mut self, _buf: &[u8]) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "tls early data is not supported",
+        ))
+    }
+
     pub async fn connect(mut self) -> io::Result<SslStream<S>> {
         future::poll_fn(|cx| self.poll_connect(cx)).await?;
         Ok(SslStream::new(self.inner))
     }
 }
+
+#[cfg(all(test, not(feature = "boringssl")))]
+mod tests {
+    use super::*;
+    use crate::SslAcceptor;
+    use openssl::asn1::Asn1Time;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::nid::Nid;
+    use openssl::pkey::{PKey, Private};
+    use openssl::ssl::{
+        SslContext, SslContextBuilder, SslMethod, SslSession, SslSessionCacheMode, SslVerifyMode,
+        SslVersion,
+    };
+    use openssl::x509::{X509NameBuilder, X509StoreContext, X509};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn self_signed() -> (X509, PKey<Private>) {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_nid(Nid::COMMONNAME, "localhost")
+            .unwrap();
+        let name = name.build();
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        (builder.build(), key)
+    }
+
+    fn server_context() -> SslContext {
+        let (cert, key) = self_signed();
+        let mut builder = SslContextBuilder::new(SslMethod::tls_server()).unwrap();
+        builder.set_certificate(&cert).unwrap();
+        builder.set_private_key(&key).unwrap();
+        builder
+            .set_min_proto_version(Some(SslVersion::TLS1_3))
+            .unwrap();
+        builder.set_max_early_data(16384).unwrap();
+        builder.build()
+    }
+
+    fn client_context(session: Arc<Mutex<Option<SslSession>>>) -> SslContext {
+        let mut builder = SslContextBuilder::new(SslMethod::tls_client()).unwrap();
+        builder.set_verify(SslVerifyMode::NONE);
+        builder
+            .set_min_proto_version(Some(SslVersion::TLS1_3))
+            .unwrap();
+        builder.set_session_cache_mode(SslSessionCacheMode::CLIENT);
+        builder.set_new_session_callback(move |_, s| {
+            *session.lock().unwrap() = Some(s);
+        });
+        builder.build()
+    }
+
+    /// Run a handshake, and the server will reply a single byte after the handshake,
+    /// so that the client can receive the TLS 1.3 session tickets.
+    async fn run(
+        server_ctx: &SslContext,
+        client_ctx: &SslContext,
+        session: Option<&SslSession>,
+        early_data: Option<&[u8]>,
+    ) -> (bool, Option<bool>) {
+        let (client_io, server_io) = tokio::io::duplex(65536);
+
+        let server_ssl = Ssl::new(server_ctx).unwrap();
+        let server = tokio::spawn(async move {
+            let acceptor = SslAcceptor::new(server_ssl, server_io, Duration::from_secs(5)).unwrap();
+            let mut stream = acceptor.accept().await.unwrap();
+            stream.write_all(b"x").await.unwrap();
+            stream.flush().await.unwrap();
+            let mut buf = [0u8; 1];
+            let _ = stream.read(&mut buf).await;
+        });
+
+        let mut client_ssl = Ssl::new(client_ctx).unwrap();
+        if let Some(session) = session {
+            unsafe { client_ssl.set_session(session).unwrap() };
+        }
+        let mut connector = SslConnector::new(client_ssl, client_io).unwrap();
+        let allowed = connector.early_data_allowed();
+        if let Some(data) = early_data {
+            connector.write_all_early_data(data).await.unwrap();
+        }
+        let mut stream = connector.connect().await.unwrap();
+        let mut buf = [0u8; 1];
+        stream.read_exact(&mut buf).await.unwrap();
+        let accepted = stream.early_data_accepted();
+        drop(stream);
+        server.await.unwrap();
+        (allowed, accepted)
+    }
+
+    #[tokio::test]
+    async fn early_data_status() {
+        let server_ctx = server_context();
+        let session = Arc::new(Mutex::new(None));
+        let client_ctx = client_context(session.clone());
+
+        // no session to resume, so no early data can be sent
+        let (allowed, accepted) = run(&server_ctx, &client_ctx, None, None).await;
+        assert!(!allowed);
+        assert_eq!(accepted, None);
+
+        let saved = session.lock().unwrap().take().unwrap();
+        // the resumed session allows early data, but we send none of it
+        let (allowed, accepted) = run(&server_ctx, &client_ctx, Some(&saved), None).await;
+        assert!(allowed);
+        assert_eq!(accepted, None);
+
+        let saved = session.lock().unwrap().take().unwrap();
+        // the server side doesn't read early data, so it will be rejected
+        let (allowed, accepted) =
+            run(&server_ctx, &client_ctx, Some(&saved), Some(b"CONNECT")).await;
+        assert!(allowed);
+        assert_eq!(accepted, Some(false));
+    }
+
+    async fn run_rejected(server_ctx: &SslContext, record: bool) -> io::Error {
+        let (client_io, server_io) = tokio::io::duplex(65536);
+
+        let server_ssl = Ssl::new(server_ctx).unwrap();
+        let server = tokio::spawn(async move {
+            let acceptor = SslAcceptor::new(server_ssl, server_io, Duration::from_secs(5)).unwrap();
+            let _ = acceptor.accept().await;
+        });
+
+        let mut builder = SslContextBuilder::new(SslMethod::tls_client()).unwrap();
+        builder.set_verify_callback(SslVerifyMode::PEER, move |_, ctx| {
+            if record {
+                let ssl_idx = X509StoreContext::ssl_idx().unwrap();
+                let ssl = ctx.ex_data(ssl_idx).unwrap();
+                crate::set_handshake_rejection(ssl, Arc::new(io::Error::other("not pinned")));
+            }
+            false
+        });
+        let client_ctx = builder.build();
+
+        let client_ssl = Ssl::new(&client_ctx).unwrap();
+        let connector = SslConnector::new(client_ssl, client_io).unwrap();
+        let Err(e) = connector.connect().await else {
+            panic!("the handshake should fail");
+        };
+        server.await.unwrap();
+        e
+    }
+
+    #[tokio::test]
+    async fn handshake_rejection() {
+        let server_ctx = server_context();
+
+        let e = run_rejected(&server_ctx, true).await;
+        let rejected = e
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<crate::SslHandshakeRejected>())
+            .unwrap();
+        assert_eq!(rejected.reason().to_string(), "not pinned");
+
+        let e = run_rejected(&server_ctx, false).await;
+        assert!(e
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<crate::SslHandshakeRejected>())
+            .is_none());
+    }
+}
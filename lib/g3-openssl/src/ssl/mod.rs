@@ -27,6 +27,9 @@ pub use async_mode::SslAsyncModeExt;
 mod stream;
 pub use stream::SslStream;
 
+mod rejection;
+pub use rejection::{set_handshake_rejection, SslHandshakeRejected, SslRejectionReason};
+
 #[cfg_attr(not(feature = "async-job"), path = "accept.rs")]
 #[cfg_attr(feature = "async-job", path = "async_accept.rs")]
 mod accept;
@@ -0,0 +1,77 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::error::Error as StdError;
+use std::io;
+use std::sync::{Arc, LazyLock, OnceLock};
+
+use openssl::ex_data::Index;
+use openssl::ssl::{self, Ssl, SslRef};
+use thiserror::Error;
+
+pub type SslRejectionReason = Arc<dyn StdError + Send + Sync>;
+
+type RejectionSlot = OnceLock<SslRejectionReason>;
+
+static REJECTION_INDEX: LazyLock<Option<Index<Ssl, RejectionSlot>>> =
+    LazyLock::new(|| Ssl::new_ex_index().ok());
+
+/// Prepare the rejection slot, which should be done before the handshake
+pub(super) fn init_rejection(ssl: &mut SslRef) {
+    if let Some(index) = *REJECTION_INDEX {
+        ssl.set_ex_data(index, RejectionSlot::new());
+    }
+}
+
+fn get_rejection(ssl: &SslRef) -> Option<SslRejectionReason> {
+    let index = (*REJECTION_INDEX)?;
+    ssl.ex_data(index).and_then(|slot| slot.get().cloned())
+}
+
+/// Record the reason why the handshake is rejected by the application callbacks.
+///
+/// The reason will be returned as `SslHandshakeRejected`, which is the inner error of the
+/// handshake io error. Only the first recorded reason will be kept.
+pub fn set_handshake_rejection(ssl: &SslRef, reason: SslRejectionReason) {
+    if let Some(index) = *REJECTION_INDEX {
+        if let Some(slot) = ssl.ex_data(index) {
+            let _ = slot.set(reason);
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("ssl connect: {reason}: {source}")]
+pub struct SslHandshakeRejected {
+    reason: SslRejectionReason,
+    source: ssl::Error,
+}
+
+impl SslHandshakeRejected {
+    pub fn reason(&self) -> &(dyn StdError + Send + Sync + 'static) {
+        self.reason.as_ref()
+    }
+}
+
+/// Convert the fatal handshake error, using the recorded rejection reason if any
+pub(super) fn connect_error(ssl: &SslRef, e: ssl::Error) -> io::Error {
+    match get_rejection(ssl) {
+        Some(reason) => io::Error::other(SslHandshakeRejected { reason, source: e }),
+        None => e
+            .into_io_error()
+            .unwrap_or_else(|e| io::Error::other(format!("ssl connect: {e}"))),
+    }
+}
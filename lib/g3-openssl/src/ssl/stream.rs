@@ -20,12 +20,16 @@ use std::pin::Pin;
 use std::task::ready;
 use std::task::{Context, Poll};
 
+#[cfg(not(feature = "boringssl"))]
+use openssl::foreign_types::ForeignTypeRef;
 use openssl::ssl::{self, ErrorCode, SslRef};
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 
 #[cfg(feature = "async-job")]
 use super::AsyncEnginePoller;
 use super::SslIoWrapper;
+#[cfg(not(feature = "boringssl"))]
+use crate::ffi;
 
 pub struct SslStream<S> {
     inner: ssl::SslStream<SslIoWrapper<S>>,
@@ -60,6 +64,30 @@ impl<S> SslStream<S> {
         self.inner.ssl_mut()
     }
 
+    /// Check if the TLS 1.3 early data sent by us has been accepted by the peer.
+    /// Return None if no early data has been sent.
+    #[cfg(not(feature = "boringssl"))]
+    pub fn early_data_accepted(&self) -> Option<bool> {
+        let status = unsafe { openssl_sys::SSL_get_early_data_status(self.ssl().as_ptr()) };
+        match status {
+            ffi::SSL_EARLY_DATA_ACCEPTED => Some(true),
+            ffi::SSL_EARLY_DATA_REJECTED => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Check if the TLS 1.3 early data sent by us has been accepted by the peer.
+    /// Return None if no early data has been sent.
+    #[cfg(feature = "boringssl")]
+    pub fn early_data_accepted(&self) -> Option<bool> {
+        None
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &S {
+        self.inner.get_ref().get_ref()
+    }
+
     #[inline]
     pub fn get_mut(&mut self) -> &mut S {
         self.inner.get_mut().get_mut()
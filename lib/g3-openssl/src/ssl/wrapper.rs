@@ -35,6 +35,11 @@ impl<S> SslIoWrapper<S> {
         self.waker = Some(cx.waker().clone());
     }
 
+    #[inline]
+    pub(crate) fn get_ref(&self) -> &S {
+        &self.io
+    }
+
     #[inline]
     pub(crate) fn get_mut(&mut self) -> &mut S {
         &mut self.io
@@ -0,0 +1,150 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use tokio::net::TcpStream;
+
+use g3_socket::BindAddr;
+use g3_types::net::TcpKeepAliveConfig;
+
+#[cfg(feature = "yaml")]
+mod yaml;
+
+const OTLP_HTTP_DEFAULT_PORT: u16 = 4318;
+const OTLP_HTTP_DEFAULT_LOGS_PATH: &str = "/v1/logs";
+
+#[derive(Clone)]
+pub struct OtlpExporterConfig {
+    server_addr: SocketAddr,
+    bind: BindAddr,
+    tcp_keepalive: TcpKeepAliveConfig,
+    pub(super) http_host: String,
+    pub(super) http_path: String,
+    pub(super) http_headers: Vec<(String, String)>,
+    pub(super) service_name: String,
+    pub(super) hostname: String,
+    pub(super) connect_timeout: Duration,
+    pub(super) write_timeout: Duration,
+    pub(super) flush_interval: Duration,
+    pub(super) max_batch_size: usize,
+    pub(super) rsp_header_max_size: usize,
+}
+
+impl Default for OtlpExporterConfig {
+    fn default() -> Self {
+        OtlpExporterConfig::new(SocketAddr::new(
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+            OTLP_HTTP_DEFAULT_PORT,
+        ))
+    }
+}
+
+impl OtlpExporterConfig {
+    pub fn new(server: SocketAddr) -> Self {
+        let hostname = g3_compat::hostname().to_string_lossy().to_string();
+        OtlpExporterConfig {
+            server_addr: server,
+            bind: BindAddr::None,
+            tcp_keepalive: TcpKeepAliveConfig::default_enabled(),
+            http_host: server.to_string(),
+            http_path: OTLP_HTTP_DEFAULT_LOGS_PATH.to_string(),
+            http_headers: Vec::new(),
+            service_name: String::new(),
+            hostname,
+            connect_timeout: Duration::from_secs(10),
+            write_timeout: Duration::from_secs(10),
+            flush_interval: Duration::from_secs(1),
+            max_batch_size: 512,
+            rsp_header_max_size: 4096,
+        }
+    }
+
+    pub fn set_server_addr(&mut self, addr: SocketAddr) {
+        self.server_addr = addr;
+        self.http_host = addr.to_string();
+    }
+
+    pub fn set_bind_ip(&mut self, ip: IpAddr) {
+        self.bind = BindAddr::Ip(ip);
+    }
+
+    pub fn set_tcp_keepalive(&mut self, keepalive: TcpKeepAliveConfig) {
+        self.tcp_keepalive = keepalive;
+    }
+
+    pub fn set_http_host(&mut self, host: String) {
+        self.http_host = host;
+    }
+
+    pub fn set_http_path(&mut self, path: String) -> anyhow::Result<()> {
+        if !path.starts_with('/') {
+            return Err(anyhow!("the http path should be started with '/'"));
+        }
+        self.http_path = path;
+        Ok(())
+    }
+
+    pub fn add_http_header(&mut self, name: String, value: String) -> anyhow::Result<()> {
+        http::HeaderName::try_from(name.as_str())
+            .map_err(|e| anyhow!("invalid http header name {name}: {e}"))?;
+        http::HeaderValue::try_from(value.as_str())
+            .map_err(|e| anyhow!("invalid http header value for {name}: {e}"))?;
+        self.http_headers.push((name, value));
+        Ok(())
+    }
+
+    pub fn set_service_name(&mut self, name: String) {
+        self.service_name = name;
+    }
+
+    pub fn set_hostname(&mut self, hostname: String) {
+        self.hostname = hostname;
+    }
+
+    pub fn set_connect_timeout(&mut self, timeout: Duration) {
+        self.connect_timeout = timeout;
+    }
+
+    pub fn set_write_timeout(&mut self, timeout: Duration) {
+        self.write_timeout = timeout;
+    }
+
+    pub fn set_flush_interval(&mut self, interval: Duration) {
+        self.flush_interval = interval;
+    }
+
+    pub fn set_max_batch_size(&mut self, size: usize) {
+        self.max_batch_size = size.max(1);
+    }
+
+    pub(super) async fn new_connection(&self) -> anyhow::Result<TcpStream> {
+        let socket = g3_socket::tcp::new_socket_to(
+            self.server_addr.ip(),
+            &self.bind,
+            &self.tcp_keepalive,
+            &Default::default(),
+            false,
+        )
+        .map_err(|e| anyhow!("failed to setup socket: {e:?}"))?;
+        socket
+            .connect(self.server_addr)
+            .await
+            .map_err(|e| anyhow!("failed to tcp connect to peer {}: {e:?}", self.server_addr))
+    }
+}
@@ -0,0 +1,124 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use anyhow::{anyhow, Context};
+use yaml_rust::Yaml;
+
+use super::OtlpExporterConfig;
+
+impl OtlpExporterConfig {
+    pub fn parse_yaml(value: &Yaml) -> anyhow::Result<Self> {
+        match value {
+            Yaml::Hash(map) => {
+                let mut config = OtlpExporterConfig::default();
+                let mut http_host = None;
+
+                g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                    "address" | "addr" => {
+                        let addr = g3_yaml::value::as_env_sockaddr(v)?;
+                        config.set_server_addr(addr);
+                        Ok(())
+                    }
+                    "bind_ip" | "bind" => {
+                        let ip = g3_yaml::value::as_ipaddr(v)?;
+                        config.set_bind_ip(ip);
+                        Ok(())
+                    }
+                    "tcp_keepalive" => {
+                        let keepalive = g3_yaml::value::as_tcp_keepalive_config(v)
+                            .context(format!("invalid tcp keepalive config value for key {k}"))?;
+                        config.set_tcp_keepalive(keepalive);
+                        Ok(())
+                    }
+                    "http_host" | "host" => {
+                        let host = g3_yaml::value::as_string(v)?;
+                        http_host = Some(host);
+                        Ok(())
+                    }
+                    "http_path" | "path" => {
+                        let path = g3_yaml::value::as_string(v)?;
+                        config
+                            .set_http_path(path)
+                            .context(format!("invalid http path value for key {k}"))
+                    }
+                    "http_headers" | "headers" => {
+                        let Yaml::Hash(map) = v else {
+                            return Err(anyhow!("invalid map value for key {k}"));
+                        };
+                        g3_yaml::foreach_kv(map, |name, value| {
+                            let value = g3_yaml::value::as_string(value)
+                                .context(format!("invalid string value for header {name}"))?;
+                            config.add_http_header(name.to_string(), value)
+                        })
+                        .context(format!("invalid http headers value for key {k}"))
+                    }
+                    "service_name" => {
+                        let name = g3_yaml::value::as_string(v)?;
+                        config.set_service_name(name);
+                        Ok(())
+                    }
+                    "hostname" => {
+                        let hostname = g3_yaml::value::as_string(v)?;
+                        config.set_hostname(hostname);
+                        Ok(())
+                    }
+                    "connect_timeout" => {
+                        let timeout = g3_yaml::humanize::as_duration(v)
+                            .context(format!("invalid humanize duration value for key {k}"))?;
+                        config.set_connect_timeout(timeout);
+                        Ok(())
+                    }
+                    "write_timeout" => {
+                        let timeout = g3_yaml::humanize::as_duration(v)
+                            .context(format!("invalid humanize duration value for key {k}"))?;
+                        config.set_write_timeout(timeout);
+                        Ok(())
+                    }
+                    "flush_interval" => {
+                        let interval = g3_yaml::humanize::as_duration(v)
+                            .context(format!("invalid humanize duration value for key {k}"))?;
+                        config.set_flush_interval(interval);
+                        Ok(())
+                    }
+                    "max_batch_size" => {
+                        let size = g3_yaml::value::as_usize(v)
+                            .context(format!("invalid usize value for key {k}"))?;
+                        config.set_max_batch_size(size);
+                        Ok(())
+                    }
+                    _ => Err(anyhow!("invalid key {k}")),
+                })?;
+
+                if let Some(host) = http_host {
+                    config.set_http_host(host);
+                }
+                Ok(config)
+            }
+            Yaml::String(_) => {
+                let addr = g3_yaml::value::as_env_sockaddr(value)?;
+                let config = OtlpExporterConfig::new(addr);
+                Ok(config)
+            }
+            Yaml::Null => {
+                let config = OtlpExporterConfig::default();
+                Ok(config)
+            }
+            _ => Err(anyhow!(
+                "yaml value type for 'OtlpExporterConfig' should be 'map'"
+            )),
+        }
+    }
+}
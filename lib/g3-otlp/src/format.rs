@@ -0,0 +1,263 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fmt::{Arguments, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+use slog::{Error, Level, OwnedKVList, Record, Serializer, KV};
+
+use g3_types::log::AsyncLogFormatter;
+
+const TASK_ID_KEY: &str = "task_id";
+
+pub enum OtlpAttrValue {
+    String(String),
+    Int(i64),
+    Double(f64),
+    Bool(bool),
+}
+
+impl OtlpAttrValue {
+    fn to_json(&self) -> Value {
+        match self {
+            // int64 value should be encoded as decimal string in OTLP/JSON
+            OtlpAttrValue::String(s) => json!({"stringValue": s}),
+            OtlpAttrValue::Int(i) => json!({"intValue": i.to_string()}),
+            OtlpAttrValue::Double(f) => json!({"doubleValue": f}),
+            OtlpAttrValue::Bool(b) => json!({"boolValue": b}),
+        }
+    }
+}
+
+pub struct OtlpLogRecord {
+    time_unix_nano: u128,
+    level: Level,
+    body: String,
+    trace_id: Option<String>,
+    attributes: Vec<(String, OtlpAttrValue)>,
+}
+
+fn severity_number(level: Level) -> u8 {
+    match level {
+        Level::Critical => 21,
+        Level::Error => 17,
+        Level::Warning => 13,
+        Level::Info => 9,
+        Level::Debug => 5,
+        Level::Trace => 1,
+    }
+}
+
+fn severity_text(level: Level) -> &'static str {
+    match level {
+        Level::Critical => "FATAL",
+        Level::Error => "ERROR",
+        Level::Warning => "WARN",
+        Level::Info => "INFO",
+        Level::Debug => "DEBUG",
+        Level::Trace => "TRACE",
+    }
+}
+
+impl OtlpLogRecord {
+    pub(super) fn to_json(&self) -> Value {
+        let attributes: Vec<Value> = self
+            .attributes
+            .iter()
+            .map(|(k, v)| json!({"key": k, "value": v.to_json()}))
+            .collect();
+        let time = self.time_unix_nano.to_string();
+        let mut map = json!({
+            "timeUnixNano": time,
+            "observedTimeUnixNano": time,
+            "severityNumber": severity_number(self.level),
+            "severityText": severity_text(self.level),
+            "body": {"stringValue": self.body},
+            "attributes": attributes,
+        });
+        if let Some(trace_id) = &self.trace_id {
+            map["traceId"] = Value::String(trace_id.clone());
+        }
+        map
+    }
+}
+
+pub struct OtlpFormatter {}
+
+impl OtlpFormatter {
+    pub(super) fn new() -> Self {
+        OtlpFormatter {}
+    }
+}
+
+impl AsyncLogFormatter<OtlpLogRecord> for OtlpFormatter {
+    fn format_slog(
+        &self,
+        record: &Record,
+        logger_values: &OwnedKVList,
+    ) -> Result<OtlpLogRecord, Error> {
+        let time_unix_nano = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+
+        let mut attributes = Vec::new();
+        let mut kv_formatter = FormatterKv(&mut attributes);
+
+        logger_values.serialize(record, &mut kv_formatter)?;
+        record.kv().serialize(record, &mut kv_formatter)?;
+
+        // the task id is a simple formatted uuid, which is also a valid trace id,
+        // so we can correlate the proxy hops with the downstream service traces
+        let trace_id = attributes.iter().find_map(|(k, v)| match v {
+            OtlpAttrValue::String(s) if k == TASK_ID_KEY && is_valid_trace_id(s) => Some(s.clone()),
+            _ => None,
+        });
+
+        Ok(OtlpLogRecord {
+            time_unix_nano,
+            level: record.level(),
+            body: record.msg().to_string(),
+            trace_id,
+            attributes,
+        })
+    }
+}
+
+fn is_valid_trace_id(s: &str) -> bool {
+    s.len() == 32 && s.bytes().all(|b| b.is_ascii_hexdigit()) && s.bytes().any(|b| b != b'0')
+}
+
+struct FormatterKv<'a>(&'a mut Vec<(String, OtlpAttrValue)>);
+
+impl FormatterKv<'_> {
+    fn emit_value(&mut self, key: slog::Key, value: OtlpAttrValue) -> slog::Result {
+        self.0.push((key.to_string(), value));
+        Ok(())
+    }
+}
+
+impl Serializer for FormatterKv<'_> {
+    impl_integer_by_i64_saturating! {
+        /// Emit `usize`
+        usize => emit_usize
+    }
+    impl_integer_by_i64_saturating! {
+        /// Emit `isize`
+        isize => emit_isize
+    }
+    impl_integer_by_i64! {
+        /// Emit `u8`
+        u8 => emit_u8
+    }
+    impl_integer_by_i64! {
+        /// Emit `i8`
+        i8 => emit_i8
+    }
+    impl_integer_by_i64! {
+        /// Emit `u16`
+        u16 => emit_u16
+    }
+    impl_integer_by_i64! {
+        /// Emit `i16`
+        i16 => emit_i16
+    }
+    impl_integer_by_i64! {
+        /// Emit `u32`
+        u32 => emit_u32
+    }
+    impl_integer_by_i64! {
+        /// Emit `i32`
+        i32 => emit_i32
+    }
+    impl_integer_by_i64_saturating! {
+        /// Emit `u64`
+        u64 => emit_u64
+    }
+
+    fn emit_i64(&mut self, key: slog::Key, value: i64) -> slog::Result {
+        self.emit_value(key, OtlpAttrValue::Int(value))
+    }
+
+    fn emit_f32(&mut self, key: slog::Key, value: f32) -> slog::Result {
+        self.emit_value(key, OtlpAttrValue::Double(value as f64))
+    }
+
+    fn emit_f64(&mut self, key: slog::Key, value: f64) -> slog::Result {
+        self.emit_value(key, OtlpAttrValue::Double(value))
+    }
+
+    fn emit_bool(&mut self, key: slog::Key, value: bool) -> slog::Result {
+        self.emit_value(key, OtlpAttrValue::Bool(value))
+    }
+
+    fn emit_char(&mut self, key: slog::Key, value: char) -> slog::Result {
+        self.emit_value(key, OtlpAttrValue::String(value.to_string()))
+    }
+
+    fn emit_none(&mut self, _key: slog::Key) -> slog::Result {
+        Ok(())
+    }
+
+    fn emit_str(&mut self, key: slog::Key, value: &str) -> slog::Result {
+        self.emit_value(key, OtlpAttrValue::String(value.to_string()))
+    }
+
+    fn emit_arguments(&mut self, key: slog::Key, value: &Arguments) -> slog::Result {
+        if let Some(s) = value.as_str() {
+            self.emit_str(key, s)
+        } else {
+            let mut s = String::new();
+            s.write_fmt(*value).map_err(|_| Error::Other)?;
+            self.emit_value(key, OtlpAttrValue::String(s))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_u64() {
+        let mut vars = Vec::new();
+        let mut kv_formatter = FormatterKv(&mut vars);
+
+        kv_formatter.emit_u64("a-key", 8u64).unwrap();
+        assert_eq!(vars.len(), 1);
+        assert_eq!(vars[0].1.to_json(), json!({"intValue": "8"}));
+    }
+
+    #[test]
+    fn format_argument() {
+        let mut vars = Vec::new();
+        let mut kv_formatter = FormatterKv(&mut vars);
+
+        let v = "value";
+        kv_formatter
+            .emit_arguments("a-key", &format_args!("a-{v}"))
+            .unwrap();
+        assert_eq!(vars[0].1.to_json(), json!({"stringValue": "a-value"}));
+    }
+
+    #[test]
+    fn trace_id() {
+        assert!(is_valid_trace_id("4bf92f3577b34da6a3ce929d0e0e4736"));
+        assert!(!is_valid_trace_id("00000000000000000000000000000000"));
+        assert!(!is_valid_trace_id("4bf92f35-77b3-4da6-a3ce-929d0e0e4736"));
+    }
+}
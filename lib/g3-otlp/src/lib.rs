@@ -0,0 +1,310 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use flume::Receiver;
+use http::Method;
+use log::warn;
+use serde_json::json;
+use tokio::io::{AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+use g3_http::client::HttpForwardRemoteResponse;
+use g3_http::HttpBodyReader;
+use g3_types::log::{AsyncLogConfig, AsyncLogger, LogStats};
+
+mod config;
+pub use config::OtlpExporterConfig;
+
+#[macro_use]
+mod macros;
+
+mod format;
+pub use format::{OtlpAttrValue, OtlpFormatter, OtlpLogRecord};
+
+pub fn new_async_logger(
+    async_conf: &AsyncLogConfig,
+    otlp_conf: &Arc<OtlpExporterConfig>,
+    program_name: &'static str,
+    log_type: &'static str,
+) -> AsyncLogger<OtlpLogRecord, OtlpFormatter> {
+    let (sender, receiver) = flume::bounded::<OtlpLogRecord>(async_conf.channel_capacity);
+
+    let stats = Arc::new(LogStats::default());
+
+    for i in 0..async_conf.thread_number {
+        let io_thread = AsyncIoThread {
+            config: Arc::clone(otlp_conf),
+            program_name,
+            log_type,
+            receiver: receiver.clone(),
+            stats: Arc::clone(&stats),
+            connection: None,
+        };
+
+        let _detached_thread = std::thread::Builder::new()
+            .name(format!("{}#{i}", async_conf.thread_name))
+            .spawn(move || {
+                let rt = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap();
+                rt.block_on(io_thread.run_to_end());
+            });
+    }
+
+    AsyncLogger::new(sender, OtlpFormatter::new(), stats)
+}
+
+struct AsyncIoThread {
+    config: Arc<OtlpExporterConfig>,
+    program_name: &'static str,
+    log_type: &'static str,
+    receiver: Receiver<OtlpLogRecord>,
+    stats: Arc<LogStats>,
+    connection: Option<BufReader<TcpStream>>,
+}
+
+impl AsyncIoThread {
+    async fn run_to_end(mut self) {
+        let mut batch = Vec::with_capacity(self.config.max_batch_size);
+        loop {
+            let Ok(record) = self.receiver.recv_async().await else {
+                break;
+            };
+            batch.push(record);
+
+            let mut channel_closed = false;
+            let flush_timeout = tokio::time::sleep(self.config.flush_interval);
+            tokio::pin!(flush_timeout);
+            while batch.len() < self.config.max_batch_size {
+                tokio::select! {
+                    r = self.receiver.recv_async() => {
+                        match r {
+                            Ok(record) => batch.push(record),
+                            Err(_) => {
+                                channel_closed = true;
+                                break;
+                            }
+                        }
+                    }
+                    _ = &mut flush_timeout => break,
+                }
+            }
+
+            let count = batch.len();
+            let body = self.encode_batch(&batch);
+            batch.clear();
+            match self.export(&body).await {
+                Ok(_) => {
+                    for _ in 0..count {
+                        self.stats.io.add_passed();
+                    }
+                    self.stats.io.add_size(body.len());
+                }
+                Err(e) => {
+                    warn!("failed to export {count} logs to otlp collector: {e:?}");
+                    self.connection = None;
+                    for _ in 0..count {
+                        self.stats.drop.add_peer_unreachable();
+                    }
+                }
+            }
+
+            if channel_closed {
+                break;
+            }
+        }
+    }
+
+    fn encode_batch(&self, batch: &[OtlpLogRecord]) -> Vec<u8> {
+        let records: Vec<_> = batch.iter().map(|r| r.to_json()).collect();
+        let service_name = if self.config.service_name.is_empty() {
+            self.program_name
+        } else {
+            self.config.service_name.as_str()
+        };
+        let request = json!({
+            "resourceLogs": [{
+                "resource": {
+                    "attributes": [
+                        {"key": "service.name", "value": {"stringValue": service_name}},
+                        {"key": "host.name", "value": {"stringValue": self.config.hostname}},
+                    ],
+                },
+                "scopeLogs": [{
+                    "scope": {"name": self.log_type},
+                    "logRecords": records,
+                }],
+            }],
+        });
+        serde_json::to_vec(&request).unwrap_or_default()
+    }
+
+    async fn new_connection(&self) -> anyhow::Result<BufReader<TcpStream>> {
+        let stream =
+            tokio::time::timeout(self.config.connect_timeout, self.config.new_connection())
+                .await
+                .map_err(|_| anyhow!("timed out to connect to otlp collector"))??;
+        Ok(BufReader::new(stream))
+    }
+
+    async fn export(&mut self, body: &[u8]) -> anyhow::Result<()> {
+        let mut header = Vec::with_capacity(256);
+        header.extend_from_slice(
+            format!(
+                "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n",
+                self.config.http_path,
+                self.config.http_host,
+                body.len()
+            )
+            .as_bytes(),
+        );
+        for (name, value) in &self.config.http_headers {
+            header.extend_from_slice(format!("{name}: {value}\r\n").as_bytes());
+        }
+        header.extend_from_slice(b"\r\n");
+
+        let (connection, (code, keep_alive)) = match self.connection.take() {
+            Some(mut connection) => match self.send_request(&mut connection, &header, body).await {
+                Ok(r) => (connection, r),
+                Err(e) => {
+                    // the keep-alive connection may have been closed by the collector,
+                    // so retry once with a new connection
+                    warn!("failed to export logs on the idle otlp connection, will retry: {e:?}");
+                    let mut connection = self.new_connection().await?;
+                    let r = self.send_request(&mut connection, &header, body).await?;
+                    (connection, r)
+                }
+            },
+            None => {
+                let mut connection = self.new_connection().await?;
+                let r = self.send_request(&mut connection, &header, body).await?;
+                (connection, r)
+            }
+        };
+        if !(200..300).contains(&code) {
+            return Err(anyhow!("unexpected response code {code}"));
+        }
+
+        if keep_alive {
+            self.connection = Some(connection);
+        }
+        Ok(())
+    }
+
+    /// Send the request and read the response, the response code and keep-alive flag will be returned
+    async fn send_request(
+        &self,
+        connection: &mut BufReader<TcpStream>,
+        header: &[u8],
+        body: &[u8],
+    ) -> anyhow::Result<(u16, bool)> {
+        tokio::time::timeout(self.config.write_timeout, async {
+            let stream = connection.get_mut();
+            stream.write_all(header).await?;
+            stream.write_all(body).await?;
+            stream.flush().await?;
+
+            let rsp = HttpForwardRemoteResponse::parse(
+                &mut *connection,
+                &Method::POST,
+                true,
+                self.config.rsp_header_max_size,
+            )
+            .await
+            .map_err(|e| anyhow!("failed to parse response: {e}"))?;
+            if let Some(body_type) = rsp.body_type(&Method::POST) {
+                let mut body_reader = HttpBodyReader::new(
+                    &mut *connection,
+                    body_type,
+                    self.config.rsp_header_max_size,
+                );
+                tokio::io::copy(&mut body_reader, &mut tokio::io::sink()).await?;
+            }
+            Ok::<(u16, bool), anyhow::Error>((rsp.code, rsp.keep_alive()))
+        })
+        .await
+        .map_err(|_| anyhow!("timed out to export logs"))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+    use tokio::net::TcpListener;
+
+    async fn serve_one(stream: &mut BufReader<TcpStream>) {
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            stream.read_line(&mut line).await.unwrap();
+            if line == "\r\n" {
+                break;
+            }
+            if let Some(v) = line.strip_prefix("Content-Length: ") {
+                content_length = v.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0u8; content_length];
+        stream.read_exact(&mut body).await.unwrap();
+        stream
+            .get_mut()
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn retry_on_stale_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            // close the first connection after one request
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(stream);
+            serve_one(&mut stream).await;
+            drop(stream);
+
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(stream);
+            serve_one(&mut stream).await;
+        });
+
+        let (_sender, receiver) = flume::bounded(1);
+        let mut io_thread = AsyncIoThread {
+            config: Arc::new(OtlpExporterConfig::new(addr)),
+            program_name: "test",
+            log_type: "test",
+            receiver,
+            stats: Arc::new(LogStats::default()),
+            connection: None,
+        };
+
+        io_thread.export(b"{}").await.unwrap();
+        assert!(io_thread.connection.is_some());
+        // wait for the server side close
+        let mut buf = [0u8; 1];
+        let stream = io_thread.connection.as_mut().unwrap().get_mut();
+        assert_eq!(stream.peek(&mut buf).await.unwrap(), 0);
+
+        io_thread.export(b"{}").await.unwrap();
+        server.await.unwrap();
+    }
+}
@@ -0,0 +1,39 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#[macro_export]
+macro_rules! impl_integer_by_i64 {
+    ($(#[$m:meta])* $t:ty => $f:ident) => {
+        $(#[$m])*
+        fn $f(&mut self, key : slog::Key, val : $t)
+            -> slog::Result {
+                self.emit_value(key, OtlpAttrValue::Int(i64::from(val)))
+            }
+    };
+}
+
+#[macro_export]
+macro_rules! impl_integer_by_i64_saturating {
+    ($(#[$m:meta])* $t:ty => $f:ident) => {
+        $(#[$m])*
+        fn $f(&mut self, key : slog::Key, val : $t)
+            -> slog::Result {
+                // values out of the i64 range will be saturated
+                let v = i64::try_from(val).unwrap_or(if val > 0 { i64::MAX } else { i64::MIN });
+                self.emit_value(key, OtlpAttrValue::Int(v))
+            }
+    };
+}
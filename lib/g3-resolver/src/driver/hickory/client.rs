@@ -24,15 +24,19 @@ use async_recursion::async_recursion;
 use hickory_client::client::{Client, ClientHandle};
 use hickory_proto::rr::{DNSClass, Name, RData, RecordType};
 use hickory_proto::BufDnsStreamHandle;
+use log::warn;
 use rustls::ClientConfig;
 use rustls_pki_types::ServerName;
 use tokio::sync::mpsc;
+use tokio::time::Instant;
 
 use g3_socket::{BindAddr, TcpConnectInfo, UdpConnectInfo};
 use g3_types::net::{DnsEncryptionConfig, DnsEncryptionProtocol, TcpMiscSockOpts, UdpMiscSockOpts};
 
 use crate::{ResolveDriverError, ResolveError, ResolvedRecord};
 
+const RECONNECT_MIN_INTERVAL: Duration = Duration::from_secs(5);
+
 #[derive(Clone)]
 pub(super) struct DnsRequest {
     domain: Arc<str>,
@@ -70,21 +74,63 @@ impl HickoryClientState {
     }
 }
 
-#[derive(Clone)]
 pub(super) struct HickoryClient {
     config: Arc<HickoryClientConfig>,
     state: Arc<HickoryClientState>,
-    client: Client,
+    client: Option<Client>,
+    reconnecting: bool,
+    last_connect: Instant,
 }
 
 impl HickoryClient {
-    pub(super) async fn new(config: HickoryClientConfig) -> anyhow::Result<Self> {
-        let client = config.build_async_client().await?;
-        Ok(HickoryClient {
+    /// Create the client and contact the server.
+    ///
+    /// The encrypted endpoint will be validated by a probe query. If the server is not usable,
+    /// a warning log will be emitted, and the connection will be retried later.
+    pub(super) async fn new(config: HickoryClientConfig) -> Self {
+        let state = Arc::new(HickoryClientState::default());
+        let client = match config.build_async_client().await {
+            Ok(client) => {
+                if config.encryption.is_some() {
+                    if let Err(e) = config.probe(client.clone()).await {
+                        warn!(
+                            "dns server {} is not usable at startup: {e:?}",
+                            config.target
+                        );
+                        state.add_failed();
+                    }
+                }
+                Some(client)
+            }
+            Err(e) => {
+                warn!(
+                    "failed to connect to dns server {} at startup: {e:?}",
+                    config.target
+                );
+                None
+            }
+        };
+        HickoryClient {
             config: Arc::new(config),
-            state: Arc::new(HickoryClientState::default()),
+            state,
             client,
-        })
+            reconnecting: false,
+            last_connect: Instant::now(),
+        }
+    }
+
+    fn reconnect(&mut self, client_sender: &mpsc::Sender<anyhow::Result<Client>>) {
+        if self.reconnecting {
+            return;
+        }
+        self.reconnecting = true;
+        self.last_connect = Instant::now();
+        let client_sender = client_sender.clone();
+        let client_config = self.config.clone();
+        tokio::spawn(async move {
+            let r = client_config.build_async_client().await;
+            let _ = client_sender.send(r).await;
+        });
     }
 
     pub(super) async fn run(
@@ -101,32 +147,42 @@ impl HickoryClient {
                     let Ok((req, rsp_sender)) = r else {
                         break;
                     };
+                    let Some(async_client) = self.client.clone() else {
+                        if self.last_connect.elapsed() >= RECONNECT_MIN_INTERVAL {
+                            self.reconnect(&client_sender);
+                        }
+                        let r = ResolvedRecord::failed(
+                            req.domain,
+                            self.config.negative_ttl,
+                            ResolveDriverError::Internal(format!(
+                                "no connection to server {}",
+                                self.config.target
+                            ))
+                            .into(),
+                        );
+                        let _ = rsp_sender.try_send(r);
+                        continue;
+                    };
                     let client_job = HickoryClientJob {
                         config: self.config.clone(),
                         state: self.state.clone(),
                         try_failed: self.config.each_tries,
                         try_truncated: self.config.retry_tcp(),
                     };
-                    let async_client = self.client.clone();
                     tokio::spawn(async move {
                         let r = client_job.run(async_client, req).await;
                         let _ = rsp_sender.send(r).await;
                     });
                 }
                 _ = check_interval.tick() => {
-                    if self.state.clear_failed() > 0 {
-                        let client_sender = client_sender.clone();
-                        let client_config = self.config.clone();
-                        tokio::spawn(async move {
-                            if let Ok(client) = client_config.build_async_client().await {
-                                let _ = client_sender.try_send(client);
-                            }
-                        });
+                    if self.state.clear_failed() > 0 || self.client.is_none() {
+                        self.reconnect(&client_sender);
                     }
                 }
                 r = client_receiver.recv() => {
-                    if let Some(client) = r {
-                        self.client = client;
+                    self.reconnecting = false;
+                    if let Some(Ok(client)) = r {
+                        self.client = Some(client);
                     }
                 }
             }
@@ -245,6 +301,20 @@ impl HickoryClientConfig {
         self.encryption.is_none()
     }
 
+    async fn probe(&self, mut client: Client) -> anyhow::Result<()> {
+        let rsp = tokio::time::timeout(
+            self.request_timeout,
+            client.query(Name::root(), DNSClass::IN, RecordType::NS),
+        )
+        .await
+        .map_err(|_| anyhow!("probe query timed out"))?
+        .map_err(|e| anyhow!("probe query failed: {e}"))?;
+        match ResolveError::from_response_code(rsp.response_code()) {
+            Some(e) => Err(anyhow!("probe query failed: {e}")),
+            None => Ok(()),
+        }
+    }
+
     async fn build_async_client(&self) -> anyhow::Result<Client> {
         if let Some(ec) = &self.encryption {
             let tls_client = ec.tls_client().driver.as_ref().clone();
@@ -77,6 +77,10 @@ impl HickoryDriverConfig {
         if self.positive_max_ttl < self.positive_min_ttl {
             self.positive_max_ttl = self.positive_min_ttl;
         }
+        if let Some(ec) = &self.encryption {
+            ec.build_tls_client_config()
+                .context(format!("invalid tls client config for {}", ec.summary()))?;
+        }
 
         Ok(())
     }
@@ -160,7 +164,7 @@ impl HickoryDriverConfig {
             let (req_sender, req_receiver) = flume::unbounded();
             driver.push_client(req_sender);
             tokio::spawn(async move {
-                let client = HickoryClient::new(client_config).await.unwrap(); // TODO
+                let client = HickoryClient::new(client_config).await;
                 client.run(req_receiver).await;
             });
         }
@@ -79,6 +79,39 @@ impl BindAddr {
         }
     }
 
+    pub(crate) fn bind_for_connect_with_port(
+        &self,
+        socket: &Socket,
+        peer_family: AddressFamily,
+        port: u16,
+    ) -> io::Result<()> {
+        let bind_ip = match self {
+            BindAddr::None => match peer_family {
+                AddressFamily::Ipv4 => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                AddressFamily::Ipv6 => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            },
+            BindAddr::Ip(ip) => {
+                if AddressFamily::from(ip) != peer_family {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "bind_ip should be of the same family with peer ip",
+                    ));
+                }
+                *ip
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            BindAddr::Interface(name) => {
+                socket.bind_device(Some(name.as_bytes()))?;
+                match peer_family {
+                    AddressFamily::Ipv4 => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                    AddressFamily::Ipv6 => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+                }
+            }
+        };
+        let bind_addr = SockAddr::from(SocketAddr::new(bind_ip, port));
+        socket.bind(&bind_addr)
+    }
+
     pub(crate) fn bind_for_relay(&self, socket: &Socket, family: AddressFamily) -> io::Result<()> {
         let bind_ip = match self {
             BindAddr::None => match family {
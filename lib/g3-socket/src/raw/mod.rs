@@ -67,6 +67,23 @@ impl RawSocket {
             socket.set_ttl(ttl)?;
         }
         if let Some(tos) = misc_opts.type_of_service {
+            #[cfg(any(
+                target_os = "linux",
+                target_os = "android",
+                target_os = "freebsd",
+                target_os = "macos"
+            ))]
+            if socket.local_addr()?.is_ipv6() {
+                socket.set_tclass_v6(tos as u32)?;
+            } else {
+                socket.set_tos(tos as u32)?;
+            }
+            #[cfg(not(any(
+                target_os = "linux",
+                target_os = "android",
+                target_os = "freebsd",
+                target_os = "macos"
+            )))]
             socket.set_tos(tos as u32)?;
         }
         #[cfg(target_os = "linux")]
@@ -96,4 +113,10 @@ impl RawSocket {
         }
         Ok(())
     }
+
+    #[cfg(target_os = "linux")]
+    pub fn set_udp_gro(&self, enable: bool) -> io::Result<()> {
+        let socket = self.get_inner()?;
+        crate::sockopt::set_udp_gro(socket, enable)
+    }
 }
@@ -31,6 +31,21 @@ where
     Ok(())
 }
 
+#[cfg(target_os = "linux")]
+unsafe fn getsockopt<T>(fd: c_int, level: c_int, name: c_int) -> io::Result<T>
+where
+    T: Copy + Default,
+{
+    let mut value = T::default();
+    let payload = &mut value as *mut T as *mut c_void;
+    let mut len = size_of::<T>() as socklen_t;
+    let ret = libc::getsockopt(fd, level, name, payload, &mut len);
+    if ret == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(value)
+}
+
 pub(crate) fn set_bind_address_no_port<T: AsRawFd>(fd: &T, enable: bool) -> io::Result<()> {
     unsafe {
         setsockopt(
@@ -42,3 +57,89 @@ pub(crate) fn set_bind_address_no_port<T: AsRawFd>(fd: &T, enable: bool) -> io::
         Ok(())
     }
 }
+
+/// Return `None` if the kernel is too old to tell whether MPTCP is in use
+#[cfg(target_os = "linux")]
+pub(crate) fn is_mptcp<T: AsRawFd>(fd: &T) -> io::Result<Option<bool>> {
+    const IPPROTO_MPTCP: c_int = 262;
+    // TCP_IS_MPTCP is available since linux 5.16
+    const TCP_IS_MPTCP: c_int = 43;
+
+    unsafe {
+        let protocol = getsockopt::<c_int>(fd.as_raw_fd(), libc::SOL_SOCKET, libc::SO_PROTOCOL)?;
+        if protocol != IPPROTO_MPTCP {
+            // the socket is created as plain TCP
+            return Ok(Some(false));
+        }
+        match getsockopt::<c_int>(fd.as_raw_fd(), libc::IPPROTO_TCP, TCP_IS_MPTCP) {
+            Ok(v) => Ok(Some(v != 0)),
+            Err(e) if e.raw_os_error() == Some(libc::ENOPROTOOPT) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn set_udp_gro<T: AsRawFd>(fd: &T, enable: bool) -> io::Result<()> {
+    // UDP_GRO is available since linux 5.0
+    unsafe {
+        setsockopt(
+            fd.as_raw_fd(),
+            libc::SOL_UDP,
+            libc::UDP_GRO,
+            enable as c_int,
+        )
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn socket_cookie<T: AsRawFd>(fd: &T) -> io::Result<u64> {
+    // SO_COOKIE is available since linux 4.12
+    unsafe { getsockopt::<u64>(fd.as_raw_fd(), libc::SOL_SOCKET, libc::SO_COOKIE) }
+}
+
+#[cfg(target_os = "linux")]
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct In6FlowLabelReq {
+    flr_dst: libc::in6_addr,
+    flr_label: u32,
+    flr_action: u8,
+    flr_share: u8,
+    flr_flags: u16,
+    flr_expires: u16,
+    flr_linger: u16,
+    __flr_pad: u32,
+}
+
+#[cfg(target_os = "linux")]
+/// the `flowinfo` should be in network byte order
+pub(crate) fn set_ipv6_flow_label<T: AsRawFd>(fd: &T, flowinfo: u32) -> io::Result<()> {
+    const IPV6_FLOWLABEL_MGR: c_int = 32;
+    const IPV6_FLOWINFO_SEND: c_int = 33;
+    const IPV6_FL_A_GET: u8 = 0;
+    const IPV6_FL_F_CREATE: u16 = 1;
+    const IPV6_FL_S_ANY: u8 = 255;
+
+    let req = In6FlowLabelReq {
+        // use the unspecified address so the label can be shared by different destinations
+        flr_dst: libc::in6_addr { s6_addr: [0; 16] },
+        flr_label: flowinfo,
+        flr_action: IPV6_FL_A_GET,
+        flr_share: IPV6_FL_S_ANY,
+        flr_flags: IPV6_FL_F_CREATE,
+        flr_expires: 0,
+        flr_linger: 0,
+        __flr_pad: 0,
+    };
+    unsafe {
+        setsockopt(fd.as_raw_fd(), libc::IPPROTO_IPV6, IPV6_FLOWLABEL_MGR, req)?;
+        setsockopt(
+            fd.as_raw_fd(),
+            libc::IPPROTO_IPV6,
+            IPV6_FLOWINFO_SEND,
+            1 as c_int,
+        )?;
+    }
+    Ok(())
+}
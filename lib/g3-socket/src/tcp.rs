@@ -15,12 +15,15 @@
  */
 
 use std::io;
+use std::mem::MaybeUninit;
 use std::net::IpAddr;
 
+#[cfg(target_os = "linux")]
+use socket2::Protocol;
 use socket2::{Domain, SockAddr, Socket, TcpKeepalive, Type};
 use tokio::net::{TcpListener, TcpSocket};
 
-use g3_types::net::{TcpKeepAliveConfig, TcpListenConfig, TcpMiscSockOpts};
+use g3_types::net::{PortRange, TcpKeepAliveConfig, TcpListenConfig, TcpMiscSockOpts};
 
 use super::util::AddressFamily;
 use super::{BindAddr, RawSocket};
@@ -56,6 +59,112 @@ pub fn new_std_socket_to(
     let peer_family = AddressFamily::from(&peer_ip);
     let socket = new_tcp_socket(peer_family)?;
     bind.bind_for_connect(&socket, peer_family)?;
+    setup_connect_socket(socket, keepalive, misc_opts, default_set_nodelay)
+}
+
+/// Create a socket with the local port selected within the specified range.
+/// An error of kind `AddrNotAvailable` will be returned if all ports in the range are in use.
+pub fn new_std_socket_in_range_to(
+    peer_ip: IpAddr,
+    bind: &BindAddr,
+    port: PortRange,
+    keepalive: &TcpKeepAliveConfig,
+    misc_opts: &TcpMiscSockOpts,
+    default_set_nodelay: bool,
+) -> io::Result<std::net::TcpStream> {
+    let peer_family = AddressFamily::from(&peer_ip);
+    let socket = new_tcp_socket(peer_family)?;
+    bind_in_range_for_connect(&socket, peer_family, bind, port)?;
+    setup_connect_socket(socket, keepalive, misc_opts, default_set_nodelay)
+}
+
+/// Create a socket with Multipath TCP requested, fallback to plain TCP if MPTCP is not available.
+/// Use `is_mptcp_in_use` after connected to check if MPTCP is really used.
+#[cfg(target_os = "linux")]
+pub fn new_std_mptcp_socket_to(
+    peer_ip: IpAddr,
+    bind: &BindAddr,
+    keepalive: &TcpKeepAliveConfig,
+    misc_opts: &TcpMiscSockOpts,
+    default_set_nodelay: bool,
+) -> io::Result<std::net::TcpStream> {
+    let peer_family = AddressFamily::from(&peer_ip);
+    let socket = new_mptcp_socket(peer_family)?;
+    bind.bind_for_connect(&socket, peer_family)?;
+    setup_connect_socket(socket, keepalive, misc_opts, default_set_nodelay)
+}
+
+/// Like `new_std_mptcp_socket_to`, but with the local port selected within the specified range.
+#[cfg(target_os = "linux")]
+pub fn new_std_mptcp_socket_in_range_to(
+    peer_ip: IpAddr,
+    bind: &BindAddr,
+    port: PortRange,
+    keepalive: &TcpKeepAliveConfig,
+    misc_opts: &TcpMiscSockOpts,
+    default_set_nodelay: bool,
+) -> io::Result<std::net::TcpStream> {
+    let peer_family = AddressFamily::from(&peer_ip);
+    let socket = new_mptcp_socket(peer_family)?;
+    bind_in_range_for_connect(&socket, peer_family, bind, port)?;
+    setup_connect_socket(socket, keepalive, misc_opts, default_set_nodelay)
+}
+
+#[cfg(target_os = "linux")]
+fn new_mptcp_socket(family: AddressFamily) -> io::Result<Socket> {
+    match Socket::new(
+        Domain::from(family),
+        Type::STREAM.nonblocking(),
+        Some(Protocol::MPTCP),
+    ) {
+        Ok(socket) => Ok(socket),
+        Err(_) => new_tcp_socket(family),
+    }
+}
+
+fn bind_in_range_for_connect(
+    socket: &Socket,
+    peer_family: AddressFamily,
+    bind: &BindAddr,
+    port: PortRange,
+) -> io::Result<()> {
+    let port_start = port.start();
+    let port_end = port.end();
+
+    debug_assert!(port_start < port_end);
+
+    macro_rules! try_bind {
+        ($port:expr) => {
+            match bind.bind_for_connect_with_port(socket, peer_family, $port) {
+                Ok(_) => return Ok(()),
+                Err(e) if e.kind() == io::ErrorKind::AddrInUse => {}
+                Err(e) => return Err(e),
+            }
+        };
+    }
+
+    // like what's has been done in new_std_in_range_bind_lazy_connect for udp
+    let tries = port.count().min(10);
+    for _i in 0..tries {
+        try_bind!(fastrand::u16(port_start..=port_end));
+    }
+
+    for port in port_start..=port_end {
+        try_bind!(port);
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::AddrNotAvailable,
+        "no port can be selected within specified range",
+    ))
+}
+
+fn setup_connect_socket(
+    socket: Socket,
+    keepalive: &TcpKeepAliveConfig,
+    misc_opts: &TcpMiscSockOpts,
+    default_set_nodelay: bool,
+) -> io::Result<std::net::TcpStream> {
     #[cfg(windows)]
     if keepalive.is_enabled() {
         // set keepalive_idle
@@ -122,11 +231,181 @@ pub fn new_socket_to(
     Ok(TcpSocket::from_std_stream(socket))
 }
 
+pub fn new_socket_in_range_to(
+    peer_ip: IpAddr,
+    bind: &BindAddr,
+    port: PortRange,
+    keepalive: &TcpKeepAliveConfig,
+    misc_opts: &TcpMiscSockOpts,
+    default_set_nodelay: bool,
+) -> io::Result<TcpSocket> {
+    let socket = new_std_socket_in_range_to(
+        peer_ip,
+        bind,
+        port,
+        keepalive,
+        misc_opts,
+        default_set_nodelay,
+    )?;
+    Ok(TcpSocket::from_std_stream(socket))
+}
+
+#[cfg(target_os = "linux")]
+pub fn new_mptcp_socket_to(
+    peer_ip: IpAddr,
+    bind: &BindAddr,
+    keepalive: &TcpKeepAliveConfig,
+    misc_opts: &TcpMiscSockOpts,
+    default_set_nodelay: bool,
+) -> io::Result<TcpSocket> {
+    let socket = new_std_mptcp_socket_to(peer_ip, bind, keepalive, misc_opts, default_set_nodelay)?;
+    Ok(TcpSocket::from_std_stream(socket))
+}
+
+#[cfg(target_os = "linux")]
+pub fn new_mptcp_socket_in_range_to(
+    peer_ip: IpAddr,
+    bind: &BindAddr,
+    port: PortRange,
+    keepalive: &TcpKeepAliveConfig,
+    misc_opts: &TcpMiscSockOpts,
+    default_set_nodelay: bool,
+) -> io::Result<TcpSocket> {
+    let socket = new_std_mptcp_socket_in_range_to(
+        peer_ip,
+        bind,
+        port,
+        keepalive,
+        misc_opts,
+        default_set_nodelay,
+    )?;
+    Ok(TcpSocket::from_std_stream(socket))
+}
+
+/// Check if Multipath TCP is in use for the connected socket.
+/// Return `Some(false)` if MPTCP is fallen back to plain TCP, and `None` if the kernel (< 5.16)
+/// doesn't support the check.
+#[cfg(target_os = "linux")]
+pub fn is_mptcp_in_use<T: std::os::unix::io::AsRawFd>(stream: &T) -> Option<bool> {
+    crate::sockopt::is_mptcp(stream).ok().flatten()
+}
+
+/// Get the socket cookie (SO_COOKIE), which can be used to identify the socket in eBPF programs.
+/// Return None if the kernel doesn't support it.
+#[cfg(target_os = "linux")]
+pub fn socket_cookie<T: std::os::unix::io::AsRawFd>(stream: &T) -> Option<u64> {
+    crate::sockopt::socket_cookie(stream).ok()
+}
+
+/// Get the socket cookie (SO_COOKIE), which is only available on Linux.
+#[cfg(not(target_os = "linux"))]
+pub fn socket_cookie<T>(_stream: &T) -> Option<u64> {
+    None
+}
+
+const IPV6_FLOW_LABEL_MASK: u32 = 0x000f_ffff;
+
+/// Get the `sin6_flowinfo` value, which is in network byte order, for the IPv6 flow label
+pub fn ipv6_flowinfo(label: u32) -> u32 {
+    (label & IPV6_FLOW_LABEL_MASK).to_be()
+}
+
+/// Set the IPv6 flow label for the socket before connect.
+/// The peer address to connect to will be returned, with the same flow label set for IPv6.
+#[cfg(target_os = "linux")]
+pub fn set_ipv6_flow_label<T: std::os::unix::io::AsRawFd>(
+    socket: &T,
+    peer: std::net::SocketAddr,
+    label: u32,
+) -> io::Result<std::net::SocketAddr> {
+    let std::net::SocketAddr::V6(mut peer_v6) = peer else {
+        return Ok(peer);
+    };
+    let flowinfo = ipv6_flowinfo(label);
+    crate::sockopt::set_ipv6_flow_label(socket, flowinfo)?;
+    peer_v6.set_flowinfo(flowinfo);
+    Ok(std::net::SocketAddr::V6(peer_v6))
+}
+
+/// Check if the connected socket is still usable, without consuming any pending data.
+/// Return false if the peer has closed the connection or the socket is in error state.
+#[cfg(unix)]
+pub fn is_connection_alive<T: std::os::fd::AsFd>(stream: &T) -> bool {
+    peek_connection_alive(socket2::SockRef::from(stream))
+}
+
+/// Check if the connected socket is still usable, without consuming any pending data.
+/// Return false if the peer has closed the connection or the socket is in error state.
+#[cfg(windows)]
+pub fn is_connection_alive<T: std::os::windows::io::AsSocket>(stream: &T) -> bool {
+    peek_connection_alive(socket2::SockRef::from(stream))
+}
+
+/// Peek the pending data without consuming it, the socket should be in nonblocking mode.
+/// An error of kind `WouldBlock` will be returned if there is no pending data.
+#[cfg(unix)]
+pub fn try_peek<T: std::os::fd::AsFd>(stream: &T, buf: &mut [u8]) -> io::Result<usize> {
+    peek_nonblocking(socket2::SockRef::from(stream), buf)
+}
+
+/// Peek the pending data without consuming it, the socket should be in nonblocking mode.
+/// An error of kind `WouldBlock` will be returned if there is no pending data.
+#[cfg(windows)]
+pub fn try_peek<T: std::os::windows::io::AsSocket>(
+    stream: &T,
+    buf: &mut [u8],
+) -> io::Result<usize> {
+    peek_nonblocking(socket2::SockRef::from(stream), buf)
+}
+
+fn peek_nonblocking(sock: socket2::SockRef<'_>, buf: &mut [u8]) -> io::Result<usize> {
+    // SAFETY: the initialized buf is a valid MaybeUninit buf, and the data will only be written
+    let buf = unsafe { &mut *(buf as *mut [u8] as *mut [MaybeUninit<u8>]) };
+    sock.peek(buf)
+}
+
+fn peek_connection_alive(sock: socket2::SockRef<'_>) -> bool {
+    // the socket should be in nonblocking mode
+    let mut buf = [MaybeUninit::<u8>::uninit(); 1];
+    match sock.peek(&mut buf) {
+        Ok(0) => false,
+        Ok(_) => true,
+        Err(e) => e.kind() == io::ErrorKind::WouldBlock,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::net::{Ipv4Addr, SocketAddr};
 
+    #[test]
+    fn flowinfo() {
+        assert_eq!(ipv6_flowinfo(0x12345), 0x12345u32.to_be());
+        assert_eq!(u32::from_be(ipv6_flowinfo(0x12345)), 0x12345);
+        // the traffic class bits should be cleared
+        assert_eq!(u32::from_be(ipv6_flowinfo(0xfff1_2345)), 0x12345);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn flow_label_v4_peer() {
+        let socket = TcpSocket::new_v4().unwrap();
+        let peer = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 80);
+        assert_eq!(set_ipv6_flow_label(&socket, peer, 0x12345).unwrap(), peer);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn cookie() {
+        let s1 = TcpSocket::new_v4().unwrap();
+        let s2 = TcpSocket::new_v4().unwrap();
+        let c1 = socket_cookie(&s1).unwrap();
+        let c2 = socket_cookie(&s2).unwrap();
+        assert_ne!(c1, c2);
+        assert_eq!(socket_cookie(&s1), Some(c1));
+    }
+
     #[tokio::test]
     async fn listen_connect() {
         let listen_config =
@@ -152,4 +431,35 @@ mod tests {
         let accepted_addr = accept_task.await.unwrap();
         assert_eq!(connect_addr, accepted_addr);
     }
+
+    #[tokio::test]
+    async fn connect_in_range() {
+        let listen_config =
+            TcpListenConfig::new(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0));
+        let listen_socket = new_listen_to(&listen_config).unwrap();
+        let listen_addr = listen_socket.local_addr().unwrap();
+
+        let accept_task = tokio::spawn(async move {
+            let (_stream, accepted_addr) = listen_socket.accept().await.unwrap();
+            accepted_addr
+        });
+
+        let port_start = 61000;
+        let port_end = 65000;
+        let connect_sock = new_socket_in_range_to(
+            listen_addr.ip(),
+            &BindAddr::None,
+            PortRange::new(port_start, port_end),
+            &TcpKeepAliveConfig::default(),
+            &TcpMiscSockOpts::default(),
+            true,
+        )
+        .unwrap();
+        let connected_stream = connect_sock.connect(listen_addr).await.unwrap();
+        let connect_addr = connected_stream.local_addr().unwrap();
+        assert!(connect_addr.port() >= port_start);
+        assert!(connect_addr.port() <= port_end);
+        let accepted_addr = accept_task.await.unwrap();
+        assert_eq!(connect_addr, accepted_addr);
+    }
 }
@@ -37,6 +37,20 @@ pub fn new_std_socket_to(
     Ok(UdpSocket::from(socket))
 }
 
+pub fn new_std_socket_with_port_to(
+    peer_addr: SocketAddr,
+    bind: &BindAddr,
+    port: u16,
+    buf_conf: SocketBufferConfig,
+    misc_opts: UdpMiscSockOpts,
+) -> io::Result<UdpSocket> {
+    let peer_family = AddressFamily::from(&peer_addr);
+    let socket = new_udp_socket(peer_family, buf_conf)?;
+    bind.bind_for_connect_with_port(&socket, peer_family, port)?;
+    RawSocket::from(&socket).set_udp_misc_opts(misc_opts)?;
+    Ok(UdpSocket::from(socket))
+}
+
 pub fn new_std_bind_lazy_connect(
     bind_ip: Option<IpAddr>,
     buf_conf: SocketBufferConfig,
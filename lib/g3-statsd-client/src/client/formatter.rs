@@ -173,7 +173,7 @@ impl<'a> MetricFormatter<'a> {
                 self.msg_len += 2 + self.local_tags.len() // |#<tags>
             }
         }
-        if let Err(e) = self.client.sink.emit(self.msg_len, |buf| {
+        let format = |buf: &mut Vec<u8>| {
             if !self.client.prefix.is_empty() {
                 buf.extend_from_slice(self.client.prefix.as_bytes());
                 buf.push(b'.');
@@ -212,7 +212,15 @@ impl<'a> MetricFormatter<'a> {
                 }
                 buf.extend_from_slice(self.local_tags.as_bytes());
             }
-        }) {
+        };
+
+        let mut emit_error = self.client.sink.emit(self.msg_len, &format).err();
+        for sink in self.client.extra_sinks.iter_mut() {
+            if let Err(e) = sink.emit(self.msg_len, &format) {
+                emit_error = Some(e);
+            }
+        }
+        if let Some(e) = emit_error {
             self.client.handle_emit_error(e);
         }
     }
@@ -28,6 +28,7 @@ mod formatter;
 pub struct StatsdClient {
     prefix: NodeName,
     sink: StatsdMetricsSink,
+    extra_sinks: Vec<StatsdMetricsSink>,
     tags: StatsdTagGroup,
 
     create_instant: Instant,
@@ -39,12 +40,17 @@ impl StatsdClient {
         StatsdClient {
             prefix,
             sink,
+            extra_sinks: Vec::new(),
             tags: Default::default(),
             create_instant: Instant::now(),
             last_error_report: 0,
         }
     }
 
+    pub(crate) fn add_extra_sink(&mut self, sink: StatsdMetricsSink) {
+        self.extra_sinks.push(sink);
+    }
+
     pub fn with_tag<T: AsRef<str>>(mut self, key: &str, value: T) -> Self {
         self.tags.add_tag(key, value);
         self
@@ -56,7 +62,13 @@ impl StatsdClient {
     }
 
     pub fn flush_sink(&mut self) {
-        if let Err(e) = self.sink.flush() {
+        let mut flush_error = self.sink.flush().err();
+        for sink in self.extra_sinks.iter_mut() {
+            if let Err(e) = sink.flush() {
+                flush_error = Some(e);
+            }
+        }
+        if let Some(e) = flush_error {
             self.handle_emit_error(e);
         }
     }
@@ -188,4 +200,22 @@ mod tests {
             b"test.count:20|c|#c1:v1,c2:v2test.count:30|c|#c1:v1"
         );
     }
+
+    #[test]
+    fn count_extra_sink() {
+        let buf = Rc::new(Mutex::new(Vec::default()));
+        let sink = StatsdMetricsSink::buf_with_capacity(buf.clone(), 32);
+        let extra_buf = Rc::new(Mutex::new(Vec::default()));
+        let extra_sink = StatsdMetricsSink::buf_with_capacity(extra_buf.clone(), 32);
+        let prefix = unsafe { NodeName::new_unchecked("test") };
+        let mut client = StatsdClient::new(prefix, sink);
+        client.add_extra_sink(extra_sink);
+        client.count("count", 20).send();
+        client.flush_sink();
+
+        let buf = buf.lock().unwrap();
+        assert_eq!(buf.as_slice(), b"test.count:20|c");
+        let extra_buf = extra_buf.lock().unwrap();
+        assert_eq!(extra_buf.as_slice(), b"test.count:20|c");
+    }
 }
@@ -38,6 +38,30 @@ pub enum StatsdBackend {
     Unix(PathBuf),
 }
 
+impl StatsdBackend {
+    fn build_sink(&self) -> io::Result<StatsdMetricsSink> {
+        match self {
+            StatsdBackend::Udp(addr, bind) => {
+                let bind_ip = bind.unwrap_or_else(|| match addr {
+                    SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                    SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+                });
+                let socket = UdpSocket::bind(SocketAddr::new(bind_ip, 0))?;
+                Ok(StatsdMetricsSink::udp_with_capacity(*addr, socket, 1024))
+            }
+            #[cfg(unix)]
+            StatsdBackend::Unix(path) => {
+                let socket = UnixDatagram::unbound()?;
+                Ok(StatsdMetricsSink::unix_with_capacity(
+                    path.clone(),
+                    socket,
+                    4096,
+                ))
+            }
+        }
+    }
+}
+
 impl Default for StatsdBackend {
     fn default() -> Self {
         let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), UDP_DEFAULT_PORT);
@@ -48,8 +72,10 @@ impl Default for StatsdBackend {
 #[derive(Debug, Clone)]
 pub struct StatsdClientConfig {
     backend: StatsdBackend,
+    extra_backends: Vec<StatsdBackend>,
     prefix: NodeName,
     pub emit_duration: Duration,
+    pub emit_rate: bool,
 }
 
 impl Default for StatsdClientConfig {
@@ -62,8 +88,10 @@ impl StatsdClientConfig {
     pub fn with_prefix(prefix: NodeName) -> Self {
         StatsdClientConfig {
             backend: StatsdBackend::default(),
+            extra_backends: Vec::new(),
             prefix,
             emit_duration: Duration::from_millis(200),
+            emit_rate: false,
         }
     }
 
@@ -71,27 +99,21 @@ impl StatsdClientConfig {
         self.backend = target;
     }
 
+    pub fn add_extra_backend(&mut self, target: StatsdBackend) {
+        self.extra_backends.push(target);
+    }
+
     pub fn set_prefix(&mut self, prefix: NodeName) {
         self.prefix = prefix;
     }
 
     pub fn build(&self) -> io::Result<StatsdClient> {
-        let sink = match &self.backend {
-            StatsdBackend::Udp(addr, bind) => {
-                let bind_ip = bind.unwrap_or_else(|| match addr {
-                    SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
-                    SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
-                });
-                let socket = UdpSocket::bind(SocketAddr::new(bind_ip, 0))?;
-                StatsdMetricsSink::udp_with_capacity(*addr, socket, 1024)
-            }
-            #[cfg(unix)]
-            StatsdBackend::Unix(path) => {
-                let socket = UnixDatagram::unbound()?;
-                StatsdMetricsSink::unix_with_capacity(path.clone(), socket, 4096)
-            }
-        };
-
-        Ok(StatsdClient::new(self.prefix.clone(), sink))
+        let sink = self.backend.build_sink()?;
+        let mut client = StatsdClient::new(self.prefix.clone(), sink);
+        for backend in &self.extra_backends {
+            let sink = backend.build_sink()?;
+            client.add_extra_sink(sink);
+        }
+        Ok(client)
     }
 }
@@ -96,6 +96,35 @@ impl StatsdBackend {
     }
 }
 
+impl StatsdBackend {
+    fn parse_target_yaml(v: &Yaml) -> anyhow::Result<Option<Self>> {
+        if let Yaml::Hash(map) = v {
+            let mut target = None;
+            g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                "udp" => {
+                    let backend = StatsdBackend::parse_udp_yaml(v)
+                        .context(format!("invalid value for key {k}"))?;
+                    target = Some(backend);
+                    Ok(())
+                }
+                #[cfg(unix)]
+                "unix" => {
+                    let backend = StatsdBackend::parse_unix_yaml(v)
+                        .context(format!("invalid value for key {k}"))?;
+                    target = Some(backend);
+                    Ok(())
+                }
+                _ => Err(anyhow!("invalid key {k}")),
+            })?;
+            Ok(target)
+        } else {
+            Err(anyhow!(
+                "yaml value type for 'statsd target' should be 'map'"
+            ))
+        }
+    }
+}
+
 impl StatsdClientConfig {
     pub fn parse_yaml(v: &Yaml, prefix: NodeName) -> anyhow::Result<Self> {
         if let Yaml::Hash(map) = v {
@@ -116,26 +145,25 @@ impl StatsdClientConfig {
                     Ok(())
                 }
                 "target" | "backend" => {
-                    if let Yaml::Hash(map) = v {
-                        g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
-                            "udp" => {
-                                let target = StatsdBackend::parse_udp_yaml(v)
-                                    .context(format!("invalid value for key {k}"))?;
-                                config.set_backend(target);
-                                Ok(())
-                            }
-                            #[cfg(unix)]
-                            "unix" => {
-                                let target = StatsdBackend::parse_unix_yaml(v)
-                                    .context(format!("invalid value for key {k}"))?;
-                                config.set_backend(target);
-                                Ok(())
-                            }
-                            _ => Err(anyhow!("invalid key {k}")),
-                        })
-                        .context(format!("invalid value for key {k}"))
+                    // keep the default backend if no target set
+                    if let Some(target) = StatsdBackend::parse_target_yaml(v)
+                        .context(format!("invalid value for key {k}"))?
+                    {
+                        config.set_backend(target);
+                    }
+                    Ok(())
+                }
+                "extra_targets" | "extra_backends" => {
+                    if let Yaml::Array(seq) = v {
+                        for (i, v) in seq.iter().enumerate() {
+                            let target = StatsdBackend::parse_target_yaml(v)
+                                .context(format!("invalid value for {k}#{i}"))?
+                                .ok_or_else(|| anyhow!("no valid target set for {k}#{i}"))?;
+                            config.add_extra_backend(target);
+                        }
+                        Ok(())
                     } else {
-                        Err(anyhow!("yaml value type for key {k} should be 'map'"))
+                        Err(anyhow!("yaml value type for key {k} should be 'seq'"))
                     }
                 }
                 "prefix" => {
@@ -149,6 +177,10 @@ impl StatsdClientConfig {
                         .context(format!("invalid humanize duration value for key {k}"))?;
                     Ok(())
                 }
+                "emit_rate" => {
+                    config.emit_rate = g3_yaml::value::as_bool(v)?;
+                    Ok(())
+                }
                 _ => Err(anyhow!("invalid key {k}")),
             })?;
 
@@ -160,3 +192,45 @@ impl StatsdClientConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    fn parse(s: &str) -> anyhow::Result<StatsdClientConfig> {
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        StatsdClientConfig::parse_yaml(&docs[0], NodeName::from_str("test").unwrap())
+    }
+
+    #[test]
+    fn empty_target() {
+        let config = parse("target: {}").unwrap();
+        let StatsdBackend::Udp(addr, None) = config.backend else {
+            panic!("unexpected backend");
+        };
+        assert!(addr.ip().is_loopback());
+        assert!(config.extra_backends.is_empty());
+
+        assert!(parse("extra_targets: [{}]").is_err());
+    }
+
+    #[test]
+    fn extra_targets() {
+        let config = parse(
+            r#"
+            target:
+              udp: 127.0.0.1:8125
+            extra_targets:
+              - udp: 127.0.0.1:8126
+              - udp: 127.0.0.1:8127
+            "#,
+        )
+        .unwrap();
+        let StatsdBackend::Udp(addr, _) = config.backend else {
+            panic!("unexpected backend");
+        };
+        assert_eq!(addr.port(), 8125);
+        assert_eq!(config.extra_backends.len(), 2);
+    }
+}
@@ -15,7 +15,7 @@
  */
 
 use std::cell::RefCell;
-use std::io::{self, Write};
+use std::io;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -28,6 +28,9 @@ use g3_types::log::{AsyncLogConfig, LogStats};
 use super::{BoxSyslogFormatter, SyslogBackendBuilder, SyslogHeader};
 use crate::backend::SyslogBackend;
 
+const RECONNECT_INTERVAL_MIN: Duration = Duration::from_secs(4);
+const RECONNECT_INTERVAL_MAX: Duration = Duration::from_secs(64);
+
 thread_local! {
     static TL_BUF: RefCell<Vec<u8>> = RefCell::new(Vec::with_capacity(1024))
 }
@@ -119,6 +122,7 @@ impl AsyncIoThread {
     fn run_to_end(self) {
         let mut backend_container: Option<SyslogBackend> = self.build_backend();
         let mut failed_instant = Instant::now();
+        let mut reconnect_interval = RECONNECT_INTERVAL_MIN;
         while let Ok(s) = self.receiver.recv() {
             if let Some(mut backend) = backend_container.take() {
                 if self.send_data(s, &mut backend).is_err() {
@@ -132,14 +136,17 @@ impl AsyncIoThread {
                     backend_container = Some(backend);
                 }
             } else {
-                if failed_instant.elapsed() > Duration::from_secs(4) {
-                    // hard coded 4s for a minimal reconnect interval
+                if failed_instant.elapsed() > reconnect_interval {
                     if let Some(mut backend) = self.build_backend() {
                         if self.send_data(s, &mut backend).is_ok() {
                             backend_container = Some(backend);
+                            reconnect_interval = RECONNECT_INTERVAL_MIN;
                             continue;
                         }
                     }
+                    // exponential backoff if the reconnect failed
+                    failed_instant = Instant::now();
+                    reconnect_interval = (reconnect_interval * 2).min(RECONNECT_INTERVAL_MAX);
                 }
                 self.stats.drop.add_peer_unreachable();
             }
@@ -158,8 +165,7 @@ impl AsyncIoThread {
 
     fn send_data(&self, data: String, backend: &mut SyslogBackend) -> io::Result<()> {
         let size = data.len();
-        backend.write_all(data.as_bytes())?;
-        backend.flush()?;
+        backend.send_msg(data.as_bytes())?;
         self.stats.io.add_passed();
         self.stats.io.add_size(size);
         Ok(())
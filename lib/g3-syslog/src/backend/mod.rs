@@ -14,16 +14,22 @@
  * limitations under the License.
  */
 
+use std::fmt;
 use std::io;
-use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::net::{IpAddr, SocketAddr, TcpStream, UdpSocket};
 #[cfg(unix)]
 use std::os::unix::net::UnixDatagram;
 #[cfg(unix)]
 use std::path::PathBuf;
 
+use openssl::ssl::SslStream;
+
+use g3_types::net::{Host, OpensslClientConfig};
+
 #[cfg(feature = "yaml")]
 mod yaml;
 
+mod tcp;
 mod udp;
 #[cfg(unix)]
 mod unix_datagram;
@@ -32,25 +38,66 @@ pub(super) enum SyslogBackend {
     Udp(UdpSocket),
     #[cfg(unix)]
     Unix(UnixDatagram),
+    Tcp(TcpStream),
+    Tls(SslStream<TcpStream>),
 }
 
 impl SyslogBackend {
     pub(super) fn need_reconnect(&self) -> bool {
-        false
+        matches!(self, SyslogBackend::Tcp(_) | SyslogBackend::Tls(_))
     }
-}
 
-impl io::Write for SyslogBackend {
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    /// send a single syslog message, octet counting framing (rfc6587) will be used for stream transports
+    pub(super) fn send_msg(&mut self, msg: &[u8]) -> io::Result<()> {
+        use io::Write;
+
         match self {
-            SyslogBackend::Udp(s) => s.send(buf),
+            SyslogBackend::Udp(s) => s.send(msg).map(|_| ()),
             #[cfg(unix)]
-            SyslogBackend::Unix(s) => s.send(buf),
+            SyslogBackend::Unix(s) => s.send(msg).map(|_| ()),
+            SyslogBackend::Tcp(s) => {
+                write_octet_counting(s, msg)?;
+                s.flush()
+            }
+            SyslogBackend::Tls(s) => {
+                write_octet_counting(s, msg)?;
+                s.flush()
+            }
         }
     }
+}
+
+fn write_octet_counting<W: io::Write>(w: &mut W, msg: &[u8]) -> io::Result<()> {
+    let mut len_buf = itoa::Buffer::new();
+    let len = len_buf.format(msg.len());
+    let mut buf = Vec::with_capacity(len.len() + 1 + msg.len());
+    buf.extend_from_slice(len.as_bytes());
+    buf.push(b' ');
+    buf.extend_from_slice(msg);
+    w.write_all(&buf)
+}
+
+#[derive(Clone)]
+pub struct SyslogTlsConfig {
+    client: OpensslClientConfig,
+    tls_name: Option<Host>,
+}
+
+impl SyslogTlsConfig {
+    pub fn new(client: OpensslClientConfig, tls_name: Option<Host>) -> Self {
+        SyslogTlsConfig { client, tls_name }
+    }
 
-    fn flush(&mut self) -> io::Result<()> {
-        Ok(())
+    fn tls_name(&self, server: SocketAddr) -> Host {
+        self.tls_name.clone().unwrap_or(Host::Ip(server.ip()))
+    }
+}
+
+impl fmt::Debug for SyslogTlsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyslogTlsConfig")
+            .field("tls_name", &self.tls_name)
+            .finish()
     }
 }
 
@@ -61,6 +108,10 @@ pub enum SyslogBackendBuilder {
     Unix(Option<PathBuf>),
     /// udp socket with optional bind ip and remote address
     Udp(Option<IpAddr>, SocketAddr),
+    /// tcp socket with optional bind ip and remote address
+    Tcp(Option<IpAddr>, SocketAddr),
+    /// tls over tcp socket with optional bind ip, remote address and tls config
+    Tls(Option<IpAddr>, SocketAddr, SyslogTlsConfig),
 }
 
 #[cfg(unix)]
@@ -95,6 +146,14 @@ impl SyslogBackendBuilder {
                 let socket = udp::udp(*bind_ip, *server)?;
                 Ok(SyslogBackend::Udp(socket))
             }
+            SyslogBackendBuilder::Tcp(bind_ip, server) => {
+                let stream = tcp::tcp(*bind_ip, *server)?;
+                Ok(SyslogBackend::Tcp(stream))
+            }
+            SyslogBackendBuilder::Tls(bind_ip, server, tls_config) => {
+                let stream = tcp::tls(*bind_ip, *server, tls_config)?;
+                Ok(SyslogBackend::Tls(stream))
+            }
         }
     }
 }
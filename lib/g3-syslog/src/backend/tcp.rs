@@ -0,0 +1,63 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io;
+use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::time::Duration;
+
+use openssl::ssl::SslStream;
+use socket2::{Domain, SockAddr, Socket, Type};
+
+use super::SyslogTlsConfig;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub(crate) fn tcp(bind_ip: Option<IpAddr>, server: SocketAddr) -> io::Result<TcpStream> {
+    let socket = Socket::new(Domain::for_address(server), Type::STREAM, None)?;
+    if let Some(ip) = bind_ip {
+        let bind_addr = SockAddr::from(SocketAddr::new(ip, 0));
+        socket.bind(&bind_addr)?;
+    }
+    socket.connect_timeout(&SockAddr::from(server), CONNECT_TIMEOUT)?;
+    socket.set_nodelay(true)?;
+    socket.set_keepalive(true)?;
+    socket.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    Ok(TcpStream::from(socket))
+}
+
+pub(crate) fn tls(
+    bind_ip: Option<IpAddr>,
+    server: SocketAddr,
+    tls_config: &SyslogTlsConfig,
+) -> io::Result<SslStream<TcpStream>> {
+    let stream = tcp(bind_ip, server)?;
+
+    let ssl = tls_config
+        .client
+        .build_ssl(&tls_config.tls_name(server), server.port())
+        .map_err(|e| io::Error::other(format!("failed to prepare ssl: {e}")))?;
+
+    let handshake_timeout = tls_config.client.handshake_timeout;
+    stream.set_read_timeout(Some(handshake_timeout))?;
+    stream.set_write_timeout(Some(handshake_timeout))?;
+    let stream = ssl
+        .connect(stream)
+        .map_err(|e| io::Error::other(format!("tls handshake failed: {e}")))?;
+    stream.get_ref().set_read_timeout(None)?;
+    stream.get_ref().set_write_timeout(Some(WRITE_TIMEOUT))?;
+    Ok(stream)
+}
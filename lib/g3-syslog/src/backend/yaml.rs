@@ -15,6 +15,7 @@
  */
 
 use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
 #[cfg(unix)]
 use std::path::PathBuf;
 use std::str::FromStr;
@@ -22,7 +23,9 @@ use std::str::FromStr;
 use anyhow::{anyhow, Context};
 use yaml_rust::Yaml;
 
-use super::SyslogBackendBuilder;
+use g3_types::net::{Host, OpensslClientConfigBuilder};
+
+use super::{SyslogBackendBuilder, SyslogTlsConfig};
 
 impl SyslogBackendBuilder {
     pub(crate) fn parse_udp_yaml(value: &Yaml) -> anyhow::Result<Self> {
@@ -63,6 +66,113 @@ impl SyslogBackendBuilder {
         }
     }
 
+    pub(crate) fn parse_tcp_yaml(value: &Yaml) -> anyhow::Result<Self> {
+        match value {
+            Yaml::Hash(map) => {
+                let mut addr: Option<SocketAddr> = None;
+                let mut bind: Option<IpAddr> = None;
+
+                g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                    "address" | "addr" => {
+                        addr = Some(g3_yaml::value::as_env_sockaddr(v).context(format!(
+                            "invalid syslog tcp peer socket address value for key {k}"
+                        ))?);
+                        Ok(())
+                    }
+                    "bind_ip" | "bind" => {
+                        bind = Some(
+                            g3_yaml::value::as_ipaddr(v)
+                                .context(format!("invalid value for key {k}"))?,
+                        );
+                        Ok(())
+                    }
+                    _ => Err(anyhow!("invalid key {k}")),
+                })?;
+
+                if let Some(addr) = addr.take() {
+                    Ok(SyslogBackendBuilder::Tcp(bind, addr))
+                } else {
+                    Err(anyhow!("no target address has been set"))
+                }
+            }
+            Yaml::String(s) => {
+                let addr =
+                    SocketAddr::from_str(s).map_err(|e| anyhow!("invalid SocketAddr: {e}"))?;
+                Ok(SyslogBackendBuilder::Tcp(None, addr))
+            }
+            _ => Err(anyhow!("invalid yaml value for tcp syslog backend")),
+        }
+    }
+
+    pub(crate) fn parse_tls_yaml(value: &Yaml, lookup_dir: Option<&Path>) -> anyhow::Result<Self> {
+        match value {
+            Yaml::Hash(map) => {
+                let mut addr: Option<SocketAddr> = None;
+                let mut bind: Option<IpAddr> = None;
+                let mut tls_client = OpensslClientConfigBuilder::with_cache_for_one_site();
+                let mut tls_name: Option<Host> = None;
+
+                g3_yaml::foreach_kv(map, |k, v| match g3_yaml::key::normalize(k).as_str() {
+                    "address" | "addr" => {
+                        addr = Some(g3_yaml::value::as_env_sockaddr(v).context(format!(
+                            "invalid syslog tls peer socket address value for key {k}"
+                        ))?);
+                        Ok(())
+                    }
+                    "bind_ip" | "bind" => {
+                        bind = Some(
+                            g3_yaml::value::as_ipaddr(v)
+                                .context(format!("invalid value for key {k}"))?,
+                        );
+                        Ok(())
+                    }
+                    "tls_client" | "tls" => {
+                        tls_client = g3_yaml::value::as_to_one_openssl_tls_client_config_builder(
+                            v, lookup_dir,
+                        )
+                        .context(format!(
+                            "invalid openssl tls client config value for key {k}"
+                        ))?;
+                        Ok(())
+                    }
+                    "tls_name" => {
+                        tls_name = Some(
+                            g3_yaml::value::as_host(v)
+                                .context(format!("invalid tls server name value for key {k}"))?,
+                        );
+                        Ok(())
+                    }
+                    _ => Err(anyhow!("invalid key {k}")),
+                })?;
+
+                let Some(addr) = addr.take() else {
+                    return Err(anyhow!("no target address has been set"));
+                };
+                let tls_client = tls_client
+                    .build()
+                    .context("failed to build tls client config")?;
+                Ok(SyslogBackendBuilder::Tls(
+                    bind,
+                    addr,
+                    SyslogTlsConfig::new(tls_client, tls_name),
+                ))
+            }
+            Yaml::String(s) => {
+                let addr =
+                    SocketAddr::from_str(s).map_err(|e| anyhow!("invalid SocketAddr: {e}"))?;
+                let tls_client = OpensslClientConfigBuilder::with_cache_for_one_site()
+                    .build()
+                    .context("failed to build default tls client config")?;
+                Ok(SyslogBackendBuilder::Tls(
+                    None,
+                    addr,
+                    SyslogTlsConfig::new(tls_client, None),
+                ))
+            }
+            _ => Err(anyhow!("invalid yaml value for tls syslog backend")),
+        }
+    }
+
     #[cfg(unix)]
     pub(crate) fn parse_unix_yaml(value: &Yaml) -> anyhow::Result<Self> {
         match value {
@@ -54,8 +54,8 @@ pub enum SyslogFormatterKind {
     Rfc3164,
     /// rfc3164 cee formatter with event flag
     Rfc3164Cee(String),
-    /// rfc5424 formatter with enterprise id and optional message id
-    Rfc5424(i32, Option<String>),
+    /// rfc5424 formatter with enterprise id, optional message id and optional sd name
+    Rfc5424(i32, Option<String>, Option<String>),
     /// rfc5424 cee formatter with optional message id and event flag
     Rfc5424Cee(Option<String>, String),
 }
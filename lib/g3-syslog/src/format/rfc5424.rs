@@ -31,17 +31,25 @@ thread_local! {
     static TL_VBUF: RefCell<Vec<u8>> = RefCell::new(Vec::with_capacity(128));
 }
 
+const DEFAULT_SD_NAME: &str = "g3proxy";
+
 pub(crate) struct FormatterRfc5424 {
     enterprise_id: i32,
     message_id: Option<String>,
+    sd_name: String,
     append_report_ts: bool,
 }
 
 impl FormatterRfc5424 {
-    pub(crate) fn new(enterprise_id: i32, message_id: Option<String>) -> Self {
+    pub(crate) fn new(
+        enterprise_id: i32,
+        message_id: Option<String>,
+        sd_name: Option<String>,
+    ) -> Self {
         FormatterRfc5424 {
             enterprise_id,
             message_id,
+            sd_name: sd_name.unwrap_or_else(|| DEFAULT_SD_NAME.to_string()),
             append_report_ts: false,
         }
     }
@@ -68,7 +76,14 @@ impl SyslogFormatter for FormatterRfc5424 {
         } else {
             None
         };
-        format_content_as_sd(w, self.enterprise_id, record, logger_values, report_ts)
+        format_content_as_sd(
+            w,
+            &self.sd_name,
+            self.enterprise_id,
+            record,
+            logger_values,
+            report_ts,
+        )
     }
 }
 
@@ -119,12 +134,15 @@ pub(super) fn format_rfc5424_header(
 
 fn format_content_as_sd(
     w: &mut Vec<u8>,
+    sd_name: &str,
     enterprise_id: i32,
     record: &Record,
     logger_values: &OwnedKVList,
     report_ts: Option<i64>,
 ) -> Result<(), slog::Error> {
-    w.extend_from_slice(b"[g3proxy@");
+    w.push(b'[');
+    w.extend_from_slice(sd_name.as_bytes());
+    w.push(b'@');
     let mut buffer = itoa::Buffer::new();
     let eid_s = buffer.format(enterprise_id);
     w.extend_from_slice(eid_s.as_bytes());
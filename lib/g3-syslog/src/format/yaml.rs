@@ -23,6 +23,7 @@ impl SyslogFormatterKind {
     pub(crate) fn parse_rfc5424_yaml(value: &Yaml) -> anyhow::Result<Self> {
         let mut enterprise_id = 0i32;
         let mut message_id: Option<String> = None;
+        let mut sd_name: Option<String> = None;
 
         match value {
             Yaml::Hash(map) => {
@@ -39,20 +40,57 @@ impl SyslogFormatterKind {
                         );
                         Ok(())
                     }
+                    "sd_name" => {
+                        let name = g3_yaml::value::as_string(v)
+                            .context(format!("invalid value for key {k}"))?;
+                        check_sd_name(&name).context(format!("invalid value for key {k}"))?;
+                        sd_name = Some(name);
+                        Ok(())
+                    }
                     _ => Err(anyhow!("invalid key {k}")),
                 })?;
-                Ok(SyslogFormatterKind::Rfc5424(enterprise_id, message_id))
+                Ok(SyslogFormatterKind::Rfc5424(
+                    enterprise_id,
+                    message_id,
+                    sd_name,
+                ))
             }
             Yaml::Integer(i) => {
                 enterprise_id =
                     i32::try_from(*i).map_err(|e| anyhow!("invalid enterprise_id: {e}"))?;
-                Ok(SyslogFormatterKind::Rfc5424(enterprise_id, message_id))
+                Ok(SyslogFormatterKind::Rfc5424(
+                    enterprise_id,
+                    message_id,
+                    None,
+                ))
             }
             Yaml::String(s) => {
                 message_id = Some(s.to_string());
-                Ok(SyslogFormatterKind::Rfc5424(enterprise_id, message_id))
+                Ok(SyslogFormatterKind::Rfc5424(
+                    enterprise_id,
+                    message_id,
+                    None,
+                ))
             }
             _ => Err(anyhow!("invalid yaml value for rfc5424 syslog format")),
         }
     }
 }
+
+/// SD-NAME = 1*32PRINTUSASCII, except '=', SP, ']', %d34 (")
+/// and '@' is also not allowed as we will append the enterprise id
+fn check_sd_name(name: &str) -> anyhow::Result<()> {
+    if name.is_empty() {
+        return Err(anyhow!("empty sd name"));
+    }
+    if name.len() > 32 {
+        return Err(anyhow!("too long sd name, the max length is 32"));
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| !c.is_ascii_graphic() || matches!(c, '=' | ']' | '"' | '@'))
+    {
+        return Err(anyhow!("invalid char {c:?} found in sd name"));
+    }
+    Ok(())
+}
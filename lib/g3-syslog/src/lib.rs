@@ -30,7 +30,7 @@ pub use types::{Facility, Severity};
 
 use async_streamer::AsyncSyslogStreamer;
 
-pub use backend::SyslogBackendBuilder;
+pub use backend::{SyslogBackendBuilder, SyslogTlsConfig};
 
 use format::BoxSyslogFormatter;
 pub use format::SyslogFormatterKind;
@@ -82,7 +82,7 @@ impl SyslogBuilder {
             SyslogFormatterKind::Rfc3164 | SyslogFormatterKind::Rfc3164Cee(_) => {
                 SyslogFormatterKind::Rfc3164Cee(event_flag)
             }
-            SyslogFormatterKind::Rfc5424(_, mid) | SyslogFormatterKind::Rfc5424Cee(mid, _) => {
+            SyslogFormatterKind::Rfc5424(_, mid, _) | SyslogFormatterKind::Rfc5424Cee(mid, _) => {
                 SyslogFormatterKind::Rfc5424Cee(mid.clone(), event_flag)
             }
         };
@@ -119,8 +119,8 @@ impl SyslogBuilder {
                 let formatter = format::FormatterRfc3164Cee::new(event_flag);
                 Box::new(formatter) as BoxSyslogFormatter
             }
-            SyslogFormatterKind::Rfc5424(eid, mid) => {
-                let formatter = format::FormatterRfc5424::new(eid, mid);
+            SyslogFormatterKind::Rfc5424(eid, mid, sd_name) => {
+                let formatter = format::FormatterRfc5424::new(eid, mid, sd_name);
                 Box::new(formatter) as BoxSyslogFormatter
             }
             SyslogFormatterKind::Rfc5424Cee(mid, event_flag) => {
@@ -14,13 +14,19 @@
  * limitations under the License.
  */
 
+use std::path::Path;
+
 use anyhow::{anyhow, Context};
 use yaml_rust::Yaml;
 
 use super::{Facility, SyslogBackendBuilder, SyslogBuilder, SyslogFormatterKind};
 
 impl SyslogBuilder {
-    pub fn parse_yaml(value: &Yaml, ident: &'static str) -> anyhow::Result<Self> {
+    pub fn parse_yaml(
+        value: &Yaml,
+        lookup_dir: Option<&Path>,
+        ident: &'static str,
+    ) -> anyhow::Result<Self> {
         match value {
             Yaml::Hash(map) => {
                 let mut builder = SyslogBuilder::with_ident(ident);
@@ -41,6 +47,18 @@ impl SyslogBuilder {
                         builder.set_backend(backend);
                         Ok(())
                     }
+                    "target_tcp" | "backend_tcp" => {
+                        let backend = SyslogBackendBuilder::parse_tcp_yaml(v)
+                            .context(format!("invalid value for key {k}"))?;
+                        builder.set_backend(backend);
+                        Ok(())
+                    }
+                    "target_tls" | "backend_tls" => {
+                        let backend = SyslogBackendBuilder::parse_tls_yaml(v, lookup_dir)
+                            .context(format!("invalid value for key {k}"))?;
+                        builder.set_backend(backend);
+                        Ok(())
+                    }
                     "target" | "backend" => {
                         if let Yaml::Hash(map) = v {
                             g3_yaml::foreach_kv(map, |k, v| {
@@ -51,6 +69,19 @@ impl SyslogBuilder {
                                         builder.set_backend(backend);
                                         Ok(())
                                     }
+                                    "tcp" => {
+                                        let backend = SyslogBackendBuilder::parse_tcp_yaml(v)
+                                            .context(format!("invalid value for key {k}"))?;
+                                        builder.set_backend(backend);
+                                        Ok(())
+                                    }
+                                    "tls" => {
+                                        let backend =
+                                            SyslogBackendBuilder::parse_tls_yaml(v, lookup_dir)
+                                                .context(format!("invalid value for key {k}"))?;
+                                        builder.set_backend(backend);
+                                        Ok(())
+                                    }
                                     #[cfg(unix)]
                                     "unix" => {
                                         let backend = SyslogBackendBuilder::parse_unix_yaml(v)
@@ -161,6 +161,10 @@ macro_rules! panic_on_empty {
 }
 
 impl<T: SelectiveItem> SelectiveVec<T> {
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.inner.iter()
+    }
+
     pub fn pick_random(&self) -> &T {
         match self.inner.len() {
             0 => panic_on_empty!(),
@@ -16,7 +16,7 @@
 
 use anyhow::anyhow;
 use openssl::pkey::{PKey, Private};
-use openssl::ssl::SslContextBuilder;
+use openssl::ssl::{SslContextBuilder, SslRef};
 use openssl::x509::X509;
 
 use super::OpensslSessionIdContext;
@@ -87,6 +87,21 @@ impl OpensslCertificatePair {
         self.add_to_ssl_context(ssl_builder)
     }
 
+    pub fn add_to_client_ssl(&self, ssl: &mut SslRef) -> anyhow::Result<()> {
+        let leaf_cert = X509::from_der(self.leaf_cert.as_slice()).unwrap();
+        ssl.set_certificate(&leaf_cert)
+            .map_err(|e| anyhow!("failed to set certificate: {e}"))?;
+        for (i, cert) in self.chain_certs.iter().enumerate() {
+            let chain_cert = X509::from_der(cert.as_slice()).unwrap();
+            ssl.add_chain_cert(chain_cert)
+                .map_err(|e| anyhow!("failed to add chain certificate #{i}: {e}"))?;
+        }
+        let key = PKey::private_key_from_der(self.key.as_slice()).unwrap();
+        ssl.set_private_key(&key)
+            .map_err(|e| anyhow!("failed to set private key: {e}"))?;
+        Ok(())
+    }
+
     pub fn add_to_server_ssl_context(
         &self,
         ssl_builder: &mut SslContextBuilder,
@@ -52,7 +52,7 @@ impl ContextPair {
                 .map_err(|e| anyhow!("failed to set sni hostname: {e}"))?;
         }
         if let Some(cache) = &self.session_cache {
-            cache.find_and_set_cache(&mut ssl, upstream.host(), upstream.port())?;
+            cache.find_and_set_cache(&mut ssl, upstream.host(), upstream.port(), None)?;
         }
         if let Some(v) = alpn_ext {
             ssl.set_alpn_protos(v.wired_list_sequence())
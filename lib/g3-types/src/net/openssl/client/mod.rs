@@ -14,9 +14,12 @@
  * limitations under the License.
  */
 
+use std::io;
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::anyhow;
+use g3_openssl::SslHandshakeRejected;
 use log::warn;
 #[cfg(any(feature = "boringssl", feature = "tongsuo"))]
 use openssl::ssl::CertCompressionAlgorithm;
@@ -27,6 +30,7 @@ use openssl::ssl::{
 use openssl::ssl::{SslCtValidationMode, StatusType};
 use openssl::x509::store::X509StoreBuilder;
 use openssl::x509::X509;
+use thiserror::Error;
 
 use super::{OpensslCertificatePair, OpensslProtocol};
 use crate::net::tls::AlpnProtocol;
@@ -41,6 +45,21 @@ pub use intercept::{OpensslInterceptionClientConfig, OpensslInterceptionClientCo
 mod session;
 use session::{OpensslClientSessionCache, OpensslSessionCacheConfig};
 
+#[cfg(not(feature = "boringssl"))]
+mod ocsp;
+
+mod spki;
+pub use spki::SPKI_PIN_LENGTH;
+
+/// The handshake rejections recorded by the verify callbacks
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum OpensslHandshakeRejection {
+    #[error("OCSP must-staple check failed")]
+    OcspMustStaple,
+    #[error("SPKI pin mismatch")]
+    SpkiPinMismatch,
+}
+
 const MINIMAL_HANDSHAKE_TIMEOUT: Duration = Duration::from_millis(100);
 const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
 
@@ -53,7 +72,33 @@ pub struct OpensslClientConfig {
 }
 
 impl OpensslClientConfig {
+    /// Convert the handshake error, and the rejections recorded by our own checks, like the
+    /// OCSP must-staple check and the SPKI pin check, will be reported distinctly.
+    pub fn handshake_error(&self, e: io::Error) -> anyhow::Error {
+        let rejection = e
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<SslHandshakeRejected>())
+            .and_then(|e| e.reason().downcast_ref::<OpensslHandshakeRejection>())
+            .copied();
+        match rejection {
+            Some(r) => anyhow::Error::new(e).context(r),
+            None => anyhow::Error::new(e),
+        }
+    }
+
     pub fn build_ssl(&self, tls_name: &Host, port: u16) -> anyhow::Result<Ssl> {
+        self.build_ssl_with_identity(tls_name, port, None)
+    }
+
+    /// Build the Ssl with the client identity, which will override the client certificate set
+    /// in the context. The session cache is also keyed by the identity name, so sessions created
+    /// with other client certificates will not be resumed.
+    pub fn build_ssl_with_identity(
+        &self,
+        tls_name: &Host,
+        port: u16,
+        identity: Option<(&str, &OpensslCertificatePair)>,
+    ) -> anyhow::Result<Ssl> {
         let mut ssl =
             Ssl::new(&self.ssl_context).map_err(|e| anyhow!("failed to get new Ssl state: {e}"))?;
         let verify_param = ssl.param_mut();
@@ -73,8 +118,11 @@ impl OpensslClientConfig {
                     .map_err(|e| anyhow!("failed to set cert verify ip: {e}"))?;
             }
         }
+        if let Some((_, cert_pair)) = identity {
+            cert_pair.add_to_client_ssl(&mut ssl)?;
+        }
         if let Some(cache) = &self.session_cache {
-            cache.find_and_set_cache(&mut ssl, tls_name, port)?;
+            cache.find_and_set_cache(&mut ssl, tls_name, port, identity.map(|(name, _)| name))?;
         }
         Ok(ssl)
     }
@@ -96,7 +144,7 @@ impl OpensslClientConfig {
                 .map_err(|e| anyhow!("failed to set sni hostname: {e}"))?;
         }
         if let Some(cache) = &self.session_cache {
-            cache.find_and_set_cache(&mut ssl, upstream.host(), upstream.port())?;
+            cache.find_and_set_cache(&mut ssl, upstream.host(), upstream.port(), None)?;
         }
         if let Some(v) = alpn_ext {
             ssl.set_alpn_protos(v.wired_list_sequence())
@@ -122,6 +170,8 @@ pub struct OpensslClientConfigBuilder {
     session_cache: OpensslSessionCacheConfig,
     supported_groups: String,
     use_ocsp_stapling: bool,
+    require_ocsp_must_staple: bool,
+    spki_pins: Vec<[u8; SPKI_PIN_LENGTH]>,
     enable_sct: bool,
     #[cfg(feature = "boringssl")]
     enable_grease: bool,
@@ -147,6 +197,8 @@ impl Default for OpensslClientConfigBuilder {
             session_cache: OpensslSessionCacheConfig::default(),
             supported_groups: String::default(),
             use_ocsp_stapling: false,
+            require_ocsp_must_staple: false,
+            spki_pins: Vec::new(),
             enable_sct: false,
             #[cfg(feature = "boringssl")]
             enable_grease: false,
@@ -280,6 +332,20 @@ impl OpensslClientConfigBuilder {
         self.use_ocsp_stapling = enable;
     }
 
+    /// Reject the server certificate with the OCSP must-staple extension,
+    /// if no valid stapled OCSP response is provided. This will also enable OCSP stapling.
+    #[inline]
+    pub fn set_require_ocsp_must_staple(&mut self, enable: bool) {
+        self.require_ocsp_must_staple = enable;
+    }
+
+    /// Set the SHA-256 hashes of the SPKI of the pinned peer certificates.
+    /// Multiple pins can be set for key rotation.
+    #[inline]
+    pub fn set_spki_pins(&mut self, pins: Vec<[u8; SPKI_PIN_LENGTH]>) {
+        self.spki_pins = pins;
+    }
+
     #[inline]
     pub fn set_enable_sct(&mut self, enable: bool) {
         self.enable_sct = enable;
@@ -313,6 +379,10 @@ impl OpensslClientConfigBuilder {
     fn set_verify(&self, builder: &mut SslConnectorBuilder) {
         if self.insecure {
             warn!("Tls Insecure Mode: Tls Peer (server) cert vertification is no longer enforced for this Context!");
+        }
+        if !self.spki_pins.is_empty() {
+            spki::set_verify(builder, Arc::from(self.spki_pins.as_slice()), self.insecure);
+        } else if self.insecure {
             builder.set_verify(SslVerifyMode::NONE);
         } else {
             builder.set_verify(SslVerifyMode::PEER);
@@ -485,14 +555,24 @@ impl OpensslClientConfigBuilder {
                 .map_err(|e| anyhow!("failed to set supported elliptic curve groups: {e}"))?;
         }
 
-        if self.use_ocsp_stapling {
+        if self.use_ocsp_stapling || self.require_ocsp_must_staple {
             #[cfg(not(feature = "boringssl"))]
             ctx_builder
                 .set_status_type(StatusType::OCSP)
                 .map_err(|e| anyhow!("failed to enable OCSP status request: {e}"))?;
             #[cfg(feature = "boringssl")]
             ctx_builder.enable_ocsp_stapling();
-            // TODO check OCSP response
+        }
+
+        if self.require_ocsp_must_staple {
+            #[cfg(not(feature = "boringssl"))]
+            ctx_builder
+                .set_status_callback(ocsp::check_must_staple)
+                .map_err(|e| anyhow!("failed to set OCSP status callback: {e}"))?;
+            #[cfg(feature = "boringssl")]
+            return Err(anyhow!(
+                "OCSP must-staple check is not supported for BoringSSL variants"
+            ));
         }
 
         if self.enable_sct {
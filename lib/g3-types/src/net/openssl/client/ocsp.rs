@@ -0,0 +1,248 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::slice;
+use std::sync::{Arc, LazyLock};
+
+use log::debug;
+use openssl::asn1::Asn1Object;
+use openssl::error::ErrorStack;
+use openssl::foreign_types::ForeignTypeRef;
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::ocsp::{OcspCertId, OcspCertStatus, OcspFlag, OcspResponse, OcspResponseStatus};
+use openssl::ssl::SslRef;
+use openssl::x509::store::X509StoreBuilder;
+use openssl::x509::X509Ref;
+use openssl_sys as ffi;
+
+use super::OpensslHandshakeRejection;
+
+/// OID of id-pe-tlsfeature
+const OID_TLSFEATURE: &str = "1.3.6.1.5.5.7.1.24";
+/// The status_request TLS extension type
+const TLS_FEATURE_STATUS_REQUEST: u8 = 5;
+
+/// Max allowed clock skew when checking the validity of the OCSP response, in seconds
+const OCSP_VALIDITY_NSEC: u32 = 300;
+
+static NID_TLSFEATURE: LazyLock<Option<Nid>> = LazyLock::new(|| {
+    Asn1Object::from_str(OID_TLSFEATURE)
+        .ok()
+        .map(|obj| obj.nid())
+        .filter(|nid| *nid != Nid::UNDEF)
+});
+
+fn read_tlv(data: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    let (&t, left) = data.split_first()?;
+    if t != tag {
+        return None;
+    }
+    let (&l, left) = left.split_first()?;
+    let (len, left) = if l & 0x80 == 0 {
+        (l as usize, left)
+    } else {
+        let n = (l & 0x7F) as usize;
+        if n == 0 || n > 2 || left.len() < n {
+            return None;
+        }
+        let len = left[..n]
+            .iter()
+            .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+        (len, &left[n..])
+    };
+    if left.len() < len {
+        return None;
+    }
+    Some(left.split_at(len))
+}
+
+/// Get the DER encoded value of the TLS Feature extension
+fn tls_feature_ext_value(cert: &X509Ref) -> Option<&[u8]> {
+    let nid = (*NID_TLSFEATURE)?;
+    // SAFETY: the certificate pointer is valid during the borrow of `cert`, the extension
+    // and its data are owned by the certificate and not freed before it. The returned slice
+    // is built from the pointer and length of the ASN1_STRING, and it is bound to the
+    // lifetime of `cert`.
+    unsafe {
+        let loc = ffi::X509_get_ext_by_NID(cert.as_ptr(), nid.as_raw(), -1);
+        if loc < 0 {
+            return None;
+        }
+        let ext = ffi::X509_get_ext(cert.as_ptr(), loc);
+        if ext.is_null() {
+            return None;
+        }
+        let data = ffi::X509_EXTENSION_get_data(ext);
+        if data.is_null() {
+            return None;
+        }
+        let ptr = ffi::ASN1_STRING_get0_data(data as *const ffi::ASN1_STRING);
+        let len = ffi::ASN1_STRING_length(data as *const ffi::ASN1_STRING);
+        if ptr.is_null() || len <= 0 {
+            return None;
+        }
+        Some(slice::from_raw_parts(ptr, len as usize))
+    }
+}
+
+/// Check if the certificate has the TLS Feature extension with status_request,
+/// which is also known as OCSP must-staple.
+pub(super) fn cert_has_must_staple(cert: &X509Ref) -> bool {
+    let Some(value) = tls_feature_ext_value(cert) else {
+        return false;
+    };
+    let Some((mut features, _)) = read_tlv(value, 0x30) else {
+        return false;
+    };
+    while let Some((feature, left)) = read_tlv(features, 0x02) {
+        if feature == [TLS_FEATURE_STATUS_REQUEST] {
+            return true;
+        }
+        features = left;
+    }
+    false
+}
+
+/// The status callback for clients, which rejects must-staple certificates without a valid
+/// stapled OCSP response. The rejection will be recorded so it can be reported distinctly.
+pub(super) fn check_must_staple(ssl: &mut SslRef) -> Result<bool, ErrorStack> {
+    let valid = verify_must_staple(ssl)?;
+    if !valid {
+        g3_openssl::set_handshake_rejection(
+            ssl,
+            Arc::new(OpensslHandshakeRejection::OcspMustStaple),
+        );
+    }
+    Ok(valid)
+}
+
+fn verify_must_staple(ssl: &SslRef) -> Result<bool, ErrorStack> {
+    let Some(cert) = ssl.peer_certificate() else {
+        return Ok(true);
+    };
+    if !cert_has_must_staple(&cert) {
+        return Ok(true);
+    }
+
+    let Some(response) = ssl.ocsp_status() else {
+        debug!("no OCSP response stapled for must-staple certificate");
+        return Ok(false);
+    };
+    let response = OcspResponse::from_der(response)?;
+    if response.status() != OcspResponseStatus::SUCCESSFUL {
+        debug!("unsuccessful OCSP response stapled for must-staple certificate");
+        return Ok(false);
+    }
+    let basic = response.basic()?;
+
+    let Some(chain) = ssl.verified_chain() else {
+        debug!("no verified chain found for must-staple certificate");
+        return Ok(false);
+    };
+    let Some(issuer) = chain.get(1) else {
+        debug!("no issuer found for must-staple certificate");
+        return Ok(false);
+    };
+    let mut store_builder = X509StoreBuilder::new()?;
+    for cert in chain.iter().skip(1) {
+        store_builder.add_cert(cert.to_owned())?;
+    }
+    let store = store_builder.build();
+    if let Err(e) = basic.verify(chain, &store, OcspFlag::empty()) {
+        debug!("invalid OCSP response stapled for must-staple certificate: {e}");
+        return Ok(false);
+    }
+
+    let cert_id = OcspCertId::from_cert(MessageDigest::sha1(), &cert, issuer)?;
+    let Some(status) = basic.find_status(&cert_id) else {
+        debug!("no matched status in the stapled OCSP response");
+        return Ok(false);
+    };
+    if status.status != OcspCertStatus::GOOD {
+        debug!("the must-staple certificate is not in good OCSP status");
+        return Ok(false);
+    }
+    if let Err(e) = status.check_validity(OCSP_VALIDITY_NSEC, None) {
+        debug!("the stapled OCSP response is out of date: {e}");
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::asn1::{Asn1Object, Asn1OctetString, Asn1Time};
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::nid::Nid;
+    use openssl::pkey::PKey;
+    use openssl::x509::{X509Extension, X509NameBuilder, X509};
+
+    fn build_cert(tls_feature: Option<&[u8]>) -> X509 {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_nid(Nid::COMMONNAME, "example.net")
+            .unwrap();
+        let name = name.build();
+
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        if let Some(value) = tls_feature {
+            let oid = Asn1Object::from_str("1.3.6.1.5.5.7.1.24").unwrap();
+            let value = Asn1OctetString::new_from_bytes(value).unwrap();
+            let ext = X509Extension::new_from_der(&oid, false, &value).unwrap();
+            builder.append_extension(ext).unwrap();
+        }
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        builder.build()
+    }
+
+    #[test]
+    fn must_staple() {
+        // SEQUENCE { INTEGER 5 }
+        let cert = build_cert(Some(&[0x30, 0x03, 0x02, 0x01, 0x05]));
+        assert!(cert_has_must_staple(&cert));
+
+        // SEQUENCE { INTEGER 17, INTEGER 5 }
+        let cert = build_cert(Some(&[0x30, 0x06, 0x02, 0x01, 0x11, 0x02, 0x01, 0x05]));
+        assert!(cert_has_must_staple(&cert));
+
+        // status_request_v2 only
+        let cert = build_cert(Some(&[0x30, 0x03, 0x02, 0x01, 0x11]));
+        assert!(!cert_has_must_staple(&cert));
+
+        let cert = build_cert(None);
+        assert!(!cert_has_must_staple(&cert));
+    }
+
+    #[test]
+    fn tls_feature_nid() {
+        let nid = NID_TLSFEATURE.unwrap();
+        assert_eq!(nid.short_name().unwrap(), "tlsfeature");
+    }
+}
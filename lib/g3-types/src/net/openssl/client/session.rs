@@ -28,6 +28,8 @@ use crate::net::Host;
 const SESSION_CACHE_DEFAULT_SITES_COUNT: NonZeroUsize = unsafe { NonZeroUsize::new_unchecked(128) };
 const SESSION_CACHE_DEFAULT_EACH_CAPACITY: NonZeroUsize =
     unsafe { NonZeroUsize::new_unchecked(16) };
+const SESSION_CACHE_ONE_SITE_IDENTITIES_COUNT: NonZeroUsize =
+    unsafe { NonZeroUsize::new_unchecked(16) };
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum OpensslSessionCacheMethod {
@@ -166,18 +168,38 @@ impl ToManyCaches {
     }
 }
 
-enum SessionCaches {
-    One(Mutex<ToOneCaches>),
-    Many(Mutex<ToManyCaches>),
+/// The sessions are keyed by the tls client identity too, as a session created with one client
+/// certificate should not be resumed when another one is used.
+struct SessionCaches {
+    for_one_site: bool,
+    caches: Mutex<ToManyCaches>,
 }
 
 impl SessionCaches {
     fn for_many(sites_count: NonZeroUsize, each_capacity: usize) -> Self {
-        SessionCaches::Many(Mutex::new(ToManyCaches::new(sites_count, each_capacity)))
+        SessionCaches {
+            for_one_site: false,
+            caches: Mutex::new(ToManyCaches::new(sites_count, each_capacity)),
+        }
     }
 
     fn for_one(capacity: usize) -> Self {
-        SessionCaches::One(Mutex::new(ToOneCaches::new(capacity)))
+        SessionCaches {
+            for_one_site: true,
+            caches: Mutex::new(ToManyCaches::new(
+                SESSION_CACHE_ONE_SITE_IDENTITIES_COUNT,
+                capacity,
+            )),
+        }
+    }
+
+    fn key(&self, tls_name: &Host, port: u16, identity: Option<&str>) -> String {
+        match (self.for_one_site, identity) {
+            (true, Some(identity)) => identity.to_string(),
+            (true, None) => String::new(),
+            (false, Some(identity)) => format!("[{tls_name}]:{port}#{identity}"),
+            (false, None) => format!("[{tls_name}]:{port}"),
+        }
     }
 }
 
@@ -204,16 +226,13 @@ impl OpensslClientSessionCache {
         let session_cache = *self;
         ctx_builder.set_new_session_callback(move |ssl, session| {
             if let Some(caches) = ssl.ssl_context().ex_data(session_cache.session_cache_index) {
-                match caches {
-                    SessionCaches::One(m) => m.lock().unwrap().push(session),
-                    SessionCaches::Many(m) => {
-                        if let Some(key) = ssl.ex_data(session_cache.session_key_index) {
-                            m.lock()
-                                .unwrap()
-                                .get_or_insert_mut(key.clone())
-                                .push(session);
-                        }
-                    }
+                if let Some(key) = ssl.ex_data(session_cache.session_key_index) {
+                    caches
+                        .caches
+                        .lock()
+                        .unwrap()
+                        .get_or_insert_mut(key.clone())
+                        .push(session);
                 }
             }
         });
@@ -226,20 +245,17 @@ impl OpensslClientSessionCache {
         ssl: &mut Ssl,
         tls_name: &Host,
         port: u16,
+        identity: Option<&str>,
     ) -> anyhow::Result<()> {
         if let Some(caches) = ssl.ssl_context().ex_data(self.session_cache_index) {
-            let session = match caches {
-                SessionCaches::One(m) => {
-                    let mut o = m.lock().unwrap();
-                    o.pop()
-                }
-                SessionCaches::Many(m) => {
-                    let key = format!("[{tls_name}]:{port}");
-                    let session = m.lock().unwrap().peek_mut(&key).and_then(|m| m.pop());
-                    ssl.set_ex_data(self.session_key_index, key);
-                    session
-                }
-            };
+            let key = caches.key(tls_name, port, identity);
+            let session = caches
+                .caches
+                .lock()
+                .unwrap()
+                .peek_mut(&key)
+                .and_then(|m| m.pop());
+            ssl.set_ex_data(self.session_key_index, key);
 
             if let Some(s) = session {
                 unsafe {
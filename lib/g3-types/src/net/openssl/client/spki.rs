@@ -0,0 +1,228 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+
+use log::debug;
+use openssl::ssl::{SslConnectorBuilder, SslVerifyMode};
+use openssl::x509::{X509Ref, X509StoreContext, X509StoreContextRef, X509VerifyResult};
+
+use super::OpensslHandshakeRejection;
+
+pub const SPKI_PIN_LENGTH: usize = 32;
+
+fn spki_pin(cert: &X509Ref) -> Option<[u8; SPKI_PIN_LENGTH]> {
+    let spki = cert
+        .public_key()
+        .and_then(|key| key.public_key_to_der())
+        .ok()?;
+    Some(openssl::sha::sha256(&spki))
+}
+
+fn spki_pin_matched(pins: &[[u8; SPKI_PIN_LENGTH]], cert: &X509Ref) -> bool {
+    spki_pin(cert)
+        .map(|hash| pins.contains(&hash))
+        .unwrap_or(false)
+}
+
+fn chain_pin_matched(pins: &[[u8; SPKI_PIN_LENGTH]], ctx: &X509StoreContextRef) -> bool {
+    if let Some(chain) = ctx.chain() {
+        chain.iter().any(|cert| spki_pin_matched(pins, cert))
+    } else if let Some(cert) = ctx.current_cert() {
+        spki_pin_matched(pins, cert)
+    } else {
+        false
+    }
+}
+
+/// Log the SPKI pins of the peer certificates, none of which matched the configured pins
+fn log_pin_mismatch(ctx: &X509StoreContextRef) {
+    let certs: Vec<&X509Ref> = match ctx.chain() {
+        Some(chain) => chain.iter().collect(),
+        None => ctx.current_cert().into_iter().collect(),
+    };
+    let peer_pins = certs
+        .into_iter()
+        .filter_map(spki_pin)
+        .map(|pin| format!("sha256//{}", openssl::base64::encode_block(&pin)))
+        .collect::<Vec<_>>();
+    debug!(
+        "SPKI pin mismatch, no configured pin matched the peer chain: [{}]",
+        peer_pins.join(", ")
+    );
+}
+
+fn record_pin_mismatch(ctx: &X509StoreContextRef) {
+    let Ok(ssl_idx) = X509StoreContext::ssl_idx() else {
+        return;
+    };
+    if let Some(ssl) = ctx.ex_data(ssl_idx) {
+        g3_openssl::set_handshake_rejection(
+            ssl,
+            Arc::new(OpensslHandshakeRejection::SpkiPinMismatch),
+        );
+    }
+}
+
+/// Set the verify callback which will check the SPKI SHA-256 hash of the peer certificates
+/// against the pin set. The check passes if any certificate in the chain matches any pin.
+///
+/// The verify mode will always be PEER, so the pin check will be enforced even in insecure mode,
+/// in which case all other verify errors will be ignored.
+pub(super) fn set_verify(
+    builder: &mut SslConnectorBuilder,
+    pins: Arc<[[u8; SPKI_PIN_LENGTH]]>,
+    insecure: bool,
+) {
+    builder.set_verify_callback(SslVerifyMode::PEER, move |preverify_ok, ctx| {
+        let ok = preverify_ok || insecure;
+        if !ok || ctx.error_depth() != 0 {
+            return ok;
+        }
+        if chain_pin_matched(&pins, ctx) {
+            true
+        } else {
+            log_pin_mismatch(ctx);
+            record_pin_mismatch(ctx);
+            ctx.set_error(X509VerifyResult::APPLICATION_VERIFICATION);
+            false
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::Host;
+    use crate::net::OpensslClientConfigBuilder;
+    use openssl::asn1::Asn1Time;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::nid::Nid;
+    use openssl::pkey::{PKey, Private};
+    use openssl::ssl::{SslAcceptor, SslMethod};
+    use openssl::x509::extension::SubjectAlternativeName;
+    use openssl::x509::{X509NameBuilder, X509};
+    use std::net::{TcpListener, TcpStream};
+    use std::str::FromStr;
+
+    fn build_cert() -> (X509, PKey<Private>) {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_nid(Nid::COMMONNAME, "example.net")
+            .unwrap();
+        let name = name.build();
+
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        let san = SubjectAlternativeName::new()
+            .dns("example.net")
+            .build(&builder.x509v3_context(None, None))
+            .unwrap();
+        builder.append_extension(san).unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        (builder.build(), key)
+    }
+
+    fn pin_of(cert: &X509) -> [u8; SPKI_PIN_LENGTH] {
+        let spki = cert.public_key().unwrap().public_key_to_der().unwrap();
+        openssl::sha::sha256(&spki)
+    }
+
+    fn handshake(
+        cert: &X509,
+        key: &PKey<Private>,
+        builder: OpensslClientConfigBuilder,
+    ) -> Result<(), String> {
+        let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls_server()).unwrap();
+        acceptor.set_certificate(cert).unwrap();
+        acceptor.set_private_key(key).unwrap();
+        let acceptor = acceptor.build();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (server_s, _) = listener.accept().unwrap();
+            let _ = acceptor.accept(server_s);
+        });
+        let client_s = TcpStream::connect(addr).unwrap();
+
+        let config = builder.build().unwrap();
+        let ssl = config
+            .build_ssl(&Host::from_str("example.net").unwrap(), 443)
+            .unwrap();
+        let r = ssl.connect(client_s).map(|_| ()).map_err(|e| e.to_string());
+        server.join().unwrap();
+        r
+    }
+
+    #[test]
+    fn pin_match() {
+        let (cert, key) = build_cert();
+        let pin = pin_of(&cert);
+        assert!(spki_pin_matched(&[[0u8; SPKI_PIN_LENGTH], pin], &cert));
+        assert!(!spki_pin_matched(&[[0u8; SPKI_PIN_LENGTH]], &cert));
+
+        let mut builder = OpensslClientConfigBuilder::default();
+        builder.set_no_default_ca_certificates();
+        builder.set_ca_certificates(vec![cert.clone()]).unwrap();
+        builder.set_spki_pins(vec![[0u8; SPKI_PIN_LENGTH], pin]);
+        handshake(&cert, &key, builder).unwrap();
+
+        // the pin check should be enforced in insecure mode
+        let mut builder = OpensslClientConfigBuilder::default();
+        builder.set_no_default_ca_certificates();
+        builder.set_insecure(true);
+        builder.set_spki_pins(vec![pin]);
+        handshake(&cert, &key, builder).unwrap();
+    }
+
+    #[test]
+    fn pin_mismatch() {
+        let (cert, key) = build_cert();
+        let (other_cert, _) = build_cert();
+
+        let mut builder = OpensslClientConfigBuilder::default();
+        builder.set_no_default_ca_certificates();
+        builder.set_ca_certificates(vec![cert.clone()]).unwrap();
+        builder.set_spki_pins(vec![pin_of(&other_cert)]);
+        let e = handshake(&cert, &key, builder).unwrap_err();
+        assert!(e.contains("application verification failure"));
+
+        let mut builder = OpensslClientConfigBuilder::default();
+        builder.set_no_default_ca_certificates();
+        builder.set_insecure(true);
+        builder.set_spki_pins(vec![pin_of(&other_cert)]);
+        assert!(handshake(&cert, &key, builder).is_err());
+
+        // verify errors should still be reported if not insecure
+        let mut builder = OpensslClientConfigBuilder::default();
+        builder.set_no_default_ca_certificates();
+        builder.set_spki_pins(vec![pin_of(&cert)]);
+        assert!(handshake(&cert, &key, builder).is_err());
+    }
+}
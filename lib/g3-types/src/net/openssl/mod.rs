@@ -16,8 +16,8 @@
 
 mod client;
 pub use client::{
-    OpensslClientConfig, OpensslClientConfigBuilder, OpensslInterceptionClientConfig,
-    OpensslInterceptionClientConfigBuilder,
+    OpensslClientConfig, OpensslClientConfigBuilder, OpensslHandshakeRejection,
+    OpensslInterceptionClientConfig, OpensslInterceptionClientConfigBuilder, SPKI_PIN_LENGTH,
 };
 
 mod server;
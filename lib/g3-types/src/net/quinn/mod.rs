@@ -14,5 +14,8 @@
  * limitations under the License.
  */
 
+mod retry;
 mod transport;
+
+pub use retry::QuinnRetryPolicy;
 pub use transport::QuinnTransportConfigBuilder;
@@ -0,0 +1,56 @@
+/*
+ * Copyright 2024 ByteDance and/or its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::num::NonZeroU32;
+
+/// The policy to decide when to send QUIC Retry packets for address validation
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QuinnRetryPolicy {
+    /// Always validate the client address before accepting new connections
+    Always,
+    /// Never send Retry packets
+    #[default]
+    Never,
+    /// Send Retry packets only if the new connection rate (per second) is over the threshold
+    UnderLoad(NonZeroU32),
+}
+
+impl QuinnRetryPolicy {
+    /// Check whether to send Retry packets with the recent new connection rate (per second)
+    pub fn need_retry(&self, new_connection_rate: u32) -> bool {
+        match self {
+            QuinnRetryPolicy::Always => true,
+            QuinnRetryPolicy::Never => false,
+            QuinnRetryPolicy::UnderLoad(threshold) => new_connection_rate > threshold.get(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn need_retry() {
+        assert!(QuinnRetryPolicy::Always.need_retry(0));
+        assert!(!QuinnRetryPolicy::Never.need_retry(u32::MAX));
+
+        let policy = QuinnRetryPolicy::UnderLoad(NonZeroU32::new(100).unwrap());
+        assert!(!policy.need_retry(0));
+        assert!(!policy.need_retry(100));
+        assert!(policy.need_retry(101));
+    }
+}
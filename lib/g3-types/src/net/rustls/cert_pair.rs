@@ -15,6 +15,7 @@
  */
 
 use anyhow::anyhow;
+use rustls_pki_types::pem::PemObject;
 use rustls_pki_types::{CertificateDer, PrivateKeyDer};
 
 #[derive(Default)]
@@ -62,6 +63,82 @@ impl Clone for RustlsCertificatePair {
 }
 
 impl RustlsCertificatePair {
+    /// Load the certificate pair from in-memory PEM data.
+    ///
+    /// The private key will be checked to match the end-entity certificate if openssl is enabled.
+    pub fn from_pem(cert_pem: &[u8], key_pem: &[u8]) -> anyhow::Result<Self> {
+        let certs = CertificateDer::pem_slice_iter(cert_pem)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| anyhow!("invalid certificate pem data: {e:?}"))?;
+        let key = PrivateKeyDer::from_pem_slice(key_pem)
+            .map_err(|e| anyhow!("invalid private key pem data: {e:?}"))?;
+
+        let mut builder = RustlsCertificatePairBuilder::default();
+        builder.set_certs(certs);
+        builder.set_key(key);
+        let pair = builder.build()?;
+        #[cfg(feature = "openssl")]
+        pair.check_key_match()?;
+        Ok(pair)
+    }
+
+    #[cfg(feature = "openssl")]
+    fn end_entity_x509(&self) -> anyhow::Result<openssl::x509::X509> {
+        let Some(cert) = self.certs.first() else {
+            return Err(anyhow!("no certificate set"));
+        };
+        openssl::x509::X509::from_der(cert.as_ref())
+            .map_err(|e| anyhow!("invalid end-entity certificate: {e}"))
+    }
+
+    /// Check if the private key matches the end-entity certificate.
+    #[cfg(feature = "openssl")]
+    pub fn check_key_match(&self) -> anyhow::Result<()> {
+        use openssl::pkey::PKey;
+
+        let cert = self.end_entity_x509()?;
+        let cert_key = cert
+            .public_key()
+            .map_err(|e| anyhow!("failed to get public key of the certificate: {e}"))?;
+        let key = PKey::private_key_from_der(self.key.secret_der())
+            .map_err(|e| anyhow!("invalid private key: {e}"))?;
+        if cert_key.public_eq(&key) {
+            Ok(())
+        } else {
+            Err(anyhow!("the private key does not match the certificate"))
+        }
+    }
+
+    /// Get the unix timestamp of the notAfter time of the end-entity certificate.
+    #[cfg(feature = "openssl")]
+    pub fn not_after_timestamp(&self) -> anyhow::Result<i64> {
+        use openssl::asn1::Asn1Time;
+
+        let cert = self.end_entity_x509()?;
+        let epoch = Asn1Time::from_unix(0).map_err(|e| anyhow!("failed to get epoch time: {e}"))?;
+        let diff = epoch
+            .diff(cert.not_after())
+            .map_err(|e| anyhow!("invalid notAfter time: {e}"))?;
+        Ok(diff.days as i64 * 86400 + diff.secs as i64)
+    }
+
+    /// Get the hex encoded SHA-256 fingerprint of the end-entity certificate.
+    #[cfg(feature = "openssl")]
+    pub fn fingerprint(&self) -> Option<String> {
+        self.certs.first().map(super::ext::cert_fingerprint)
+    }
+
+    /// Get the common name in the subject of the end-entity certificate.
+    #[cfg(feature = "openssl")]
+    pub fn subject_common_name(&self) -> Option<String> {
+        let cert = self.end_entity_x509().ok()?;
+        let entry = cert
+            .subject_name()
+            .entries_by_nid(openssl::nid::Nid::COMMONNAME)
+            .next()?;
+        entry.data().as_utf8().ok().map(|s| s.to_string())
+    }
+
     pub fn certs_owned(&self) -> Vec<CertificateDer<'static>> {
         self.certs.clone()
     }
@@ -78,3 +155,66 @@ impl RustlsCertificatePair {
         (self.certs, self.key)
     }
 }
+
+#[cfg(all(test, feature = "openssl"))]
+mod tests {
+    use super::*;
+    use openssl::asn1::Asn1Time;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::nid::Nid;
+    use openssl::pkey::{PKey, Private};
+    use openssl::x509::{X509NameBuilder, X509};
+
+    fn generate_key() -> PKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap()
+    }
+
+    fn generate_cert_pem(key: &PKey<Private>) -> Vec<u8> {
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_nid(Nid::COMMONNAME, "example.net")
+            .unwrap();
+        let name = name.build();
+
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        builder.sign(key, MessageDigest::sha256()).unwrap();
+        builder.build().to_pem().unwrap()
+    }
+
+    #[test]
+    fn from_pem() {
+        let key = generate_key();
+        let cert_pem = generate_cert_pem(&key);
+        let key_pem = key.private_key_to_pem_pkcs8().unwrap();
+
+        let pair = RustlsCertificatePair::from_pem(&cert_pem, &key_pem).unwrap();
+        assert_eq!(pair.certs_owned().len(), 1);
+        assert_eq!(pair.subject_common_name().as_deref(), Some("example.net"));
+        let fingerprint = pair.fingerprint().unwrap();
+        assert_eq!(fingerprint.len(), 64);
+        assert!(fingerprint.bytes().all(|b| b.is_ascii_hexdigit()));
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let expire_in = pair.not_after_timestamp().unwrap() - now;
+        assert!(expire_in > 86400 - 60 && expire_in <= 86400);
+
+        let other_key_pem = generate_key().private_key_to_pem_pkcs8().unwrap();
+        assert!(RustlsCertificatePair::from_pem(&cert_pem, &other_key_pem).is_err());
+
+        assert!(RustlsCertificatePair::from_pem(b"", &key_pem).is_err());
+        assert!(RustlsCertificatePair::from_pem(&cert_pem, b"").is_err());
+    }
+}
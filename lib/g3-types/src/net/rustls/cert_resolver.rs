@@ -20,8 +20,10 @@ use anyhow::anyhow;
 use rustls::crypto::CryptoProvider;
 use rustls::server::{ClientHello, ResolvesServerCert};
 use rustls::sign::CertifiedKey;
+use rustls::SupportedProtocolVersion;
 
 use super::RustlsCertificatePair;
+use crate::net::TlsVersion;
 
 #[derive(Debug, Default)]
 pub struct MultipleCertResolver {
@@ -60,3 +62,106 @@ impl ResolvesServerCert for MultipleCertResolver {
         None
     }
 }
+
+/// The allowed TLS protocol version range for a server name
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RustlsSniVersionPolicy {
+    min_version: Option<TlsVersion>,
+    max_version: Option<TlsVersion>,
+}
+
+impl RustlsSniVersionPolicy {
+    pub fn set_min_version(&mut self, version: TlsVersion) {
+        self.min_version = Some(version);
+    }
+
+    pub fn set_max_version(&mut self, version: TlsVersion) {
+        self.max_version = Some(version);
+    }
+
+    pub fn check(&self) -> anyhow::Result<()> {
+        if let (Some(min), Some(max)) = (self.min_version, self.max_version) {
+            if version_rank(min) > version_rank(max) {
+                return Err(anyhow!(
+                    "min version {min} is larger than max version {max}"
+                ));
+            }
+        }
+        if self.protocol_versions().is_empty() {
+            return Err(anyhow!("no TLS 1.2 or TLS 1.3 version is allowed"));
+        }
+        Ok(())
+    }
+
+    /// Get the allowed protocol versions that are supported by rustls
+    pub fn protocol_versions(&self) -> Vec<&'static SupportedProtocolVersion> {
+        let mut versions = Vec::with_capacity(2);
+        if self.allow(TlsVersion::TLS1_3) {
+            versions.push(&rustls::version::TLS13);
+        }
+        if self.allow(TlsVersion::TLS1_2) {
+            versions.push(&rustls::version::TLS12);
+        }
+        versions
+    }
+
+    fn allow(&self, version: TlsVersion) -> bool {
+        let rank = version_rank(version);
+        if let Some(min) = self.min_version {
+            if rank < version_rank(min) {
+                return false;
+            }
+        }
+        if let Some(max) = self.max_version {
+            if rank > version_rank(max) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn version_rank(version: TlsVersion) -> u8 {
+    match version {
+        TlsVersion::TLS1_0 => 0,
+        TlsVersion::TLS1_1 => 1,
+        TlsVersion::TLS1_2 => 2,
+        TlsVersion::TLS1_3 => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sni_version_policy() {
+        let policy = RustlsSniVersionPolicy::default();
+        assert!(policy.check().is_ok());
+        assert_eq!(policy.protocol_versions().len(), 2);
+
+        let mut policy = RustlsSniVersionPolicy::default();
+        policy.set_min_version(TlsVersion::TLS1_3);
+        assert!(policy.check().is_ok());
+        let versions = policy.protocol_versions();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].version, rustls::ProtocolVersion::TLSv1_3);
+
+        let mut policy = RustlsSniVersionPolicy::default();
+        policy.set_min_version(TlsVersion::TLS1_0);
+        policy.set_max_version(TlsVersion::TLS1_2);
+        assert!(policy.check().is_ok());
+        let versions = policy.protocol_versions();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].version, rustls::ProtocolVersion::TLSv1_2);
+
+        let mut policy = RustlsSniVersionPolicy::default();
+        policy.set_max_version(TlsVersion::TLS1_1);
+        assert!(policy.check().is_err());
+
+        let mut policy = RustlsSniVersionPolicy::default();
+        policy.set_min_version(TlsVersion::TLS1_3);
+        policy.set_max_version(TlsVersion::TLS1_2);
+        assert!(policy.check().is_err());
+    }
+}
@@ -14,23 +14,99 @@
  * limitations under the License.
  */
 
+#[cfg(feature = "openssl")]
+use std::collections::VecDeque;
+#[cfg(feature = "openssl")]
+use std::fmt;
+#[cfg(feature = "openssl")]
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+#[cfg(feature = "openssl")]
+use std::sync::Mutex;
+#[cfg(feature = "openssl")]
+use std::time::{Duration, Instant};
 
+#[cfg(feature = "openssl")]
+use anyhow::{anyhow, Context};
+#[cfg(feature = "openssl")]
+use log::warn;
 use rustls::server::{NoServerSessionStorage, ProducesTickets};
 use rustls::{ClientConnection, HandshakeKind, ServerConfig, ServerConnection};
+#[cfg(feature = "openssl")]
+use rustls_pki_types::CertificateDer;
 
 use super::{RustlsNoSessionTicketer, RustlsServerSessionCache};
+use crate::net::AlpnProtocol;
+#[cfg(feature = "openssl")]
+use crate::net::{OpensslTicketKey, RollingTicketKey, RollingTicketer, TicketKeyName};
+
+#[cfg(feature = "openssl")]
+static TICKET_KEY_ROTATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Get the total count of ticket key rotations of all rotating ticketers.
+#[cfg(feature = "openssl")]
+pub fn rustls_ticket_key_rotations() -> u64 {
+    TICKET_KEY_ROTATIONS.load(Ordering::Relaxed)
+}
 
 pub trait RustlsConnectionExt {}
 
 pub trait RustlsServerConnectionExt {
     fn session_reused(&self) -> bool;
+
+    /// Get the hex encoded SHA-256 fingerprint of the client end-entity certificate.
+    ///
+    /// None will be returned if no client certificate is presented.
+    #[cfg(feature = "openssl")]
+    fn peer_cert_fingerprint(&self) -> Option<String>;
+
+    /// Get the subject name of the client end-entity certificate, in `SN=value, ...` format.
+    ///
+    /// None will be returned if no client certificate is presented or it can not be parsed.
+    #[cfg(feature = "openssl")]
+    fn peer_cert_subject(&self) -> Option<String>;
 }
 
 impl RustlsServerConnectionExt for ServerConnection {
     fn session_reused(&self) -> bool {
         matches!(self.handshake_kind(), Some(HandshakeKind::Resumed))
     }
+
+    #[cfg(feature = "openssl")]
+    fn peer_cert_fingerprint(&self) -> Option<String> {
+        let cert = self.peer_certificates()?.first()?;
+        Some(cert_fingerprint(cert))
+    }
+
+    #[cfg(feature = "openssl")]
+    fn peer_cert_subject(&self) -> Option<String> {
+        let cert = self.peer_certificates()?.first()?;
+        cert_subject(cert)
+    }
+}
+
+#[cfg(feature = "openssl")]
+pub(super) fn cert_fingerprint(cert: &CertificateDer<'_>) -> String {
+    use std::fmt::Write;
+
+    let digest = openssl::sha::sha256(cert.as_ref());
+    let mut s = String::with_capacity(digest.len() * 2);
+    for b in digest {
+        let _ = write!(s, "{b:02x}");
+    }
+    s
+}
+
+#[cfg(feature = "openssl")]
+fn cert_subject(cert: &CertificateDer<'_>) -> Option<String> {
+    let cert = openssl::x509::X509::from_der(cert.as_ref()).ok()?;
+    let mut entries = Vec::new();
+    for entry in cert.subject_name().entries() {
+        let name = entry.object().nid().short_name().ok()?;
+        let value = entry.data().as_utf8().ok()?;
+        entries.push(format!("{name}={value}"));
+    }
+    Some(entries.join(", "))
 }
 
 pub trait RustlsClientConnectionExt {
@@ -50,6 +126,21 @@ pub trait RustlsServerConfigExt {
         enable: bool,
         ticketer: Option<Arc<T>>,
     ) -> anyhow::Result<()>;
+
+    /// Disable TLS 1.3 0-RTT early data, the same as setting `max_early_data_size` to 0.
+    ///
+    /// Session resumption is not affected, the TLS 1.3 tickets will still be sent as set by
+    /// `send_tls13_tickets`, but they won't allow the client to send early data.
+    /// Call this after `set_session_ticketer`, which may reset `send_tls13_tickets` to 0 if
+    /// ticketing is disabled, and in that case only stateful resumption is available.
+    fn disable_early_data(&mut self);
+
+    /// Set the ALPN protocols supported by the server, in preference order.
+    ///
+    /// If the client offers ALPN but there is no overlap with the list, the handshake will fail
+    /// with a `no_application_protocol` alert, clients that offer no ALPN are not affected.
+    /// The list should not be empty, leave `alpn_protocols` unset to disable ALPN negotiation.
+    fn set_alpn_protocols(&mut self, protocols: &[AlpnProtocol]) -> anyhow::Result<()>;
 }
 
 impl RustlsServerConfigExt for ServerConfig {
@@ -78,6 +169,130 @@ impl RustlsServerConfigExt for ServerConfig {
         }
         Ok(())
     }
+
+    fn disable_early_data(&mut self) {
+        self.max_early_data_size = 0;
+    }
+
+    fn set_alpn_protocols(&mut self, protocols: &[AlpnProtocol]) -> anyhow::Result<()> {
+        if protocols.is_empty() {
+            return Err(anyhow::anyhow!(
+                "the alpn protocol list should not be empty"
+            ));
+        }
+        self.alpn_protocols = protocols
+            .iter()
+            .map(|p| p.to_identification_sequence())
+            .collect();
+        Ok(())
+    }
+}
+
+#[cfg(feature = "openssl")]
+struct RotationState {
+    next_rotate_at: Instant,
+    retired_keys: VecDeque<(TicketKeyName, Instant)>,
+}
+
+/// A session ticketer which rotates its ticket key at a fixed interval.
+///
+/// The retired keys can still be used to decrypt tickets within the grace time.
+/// The rotation is checked when the ticketer is used, so no background task is needed.
+#[cfg(feature = "openssl")]
+pub struct RustlsRotatingTicketer {
+    inner: RollingTicketer<OpensslTicketKey>,
+    interval: Duration,
+    grace: Duration,
+    state: Mutex<RotationState>,
+}
+
+#[cfg(feature = "openssl")]
+impl RustlsRotatingTicketer {
+    pub fn new(interval: Duration, grace: Duration) -> anyhow::Result<Self> {
+        if interval.is_zero() {
+            return Err(anyhow!("the rotation interval should not be zero"));
+        }
+        let lifetime = u32::try_from(grace.as_secs()).unwrap_or(u32::MAX);
+        if lifetime == 0 {
+            return Err(anyhow!("the grace time should be at least 1s"));
+        }
+
+        let initial_key = OpensslTicketKey::new_random(lifetime)
+            .context("failed to create initial random key")?;
+        Ok(RustlsRotatingTicketer {
+            inner: RollingTicketer::new(initial_key),
+            interval,
+            grace,
+            state: Mutex::new(RotationState {
+                next_rotate_at: Instant::now() + interval,
+                retired_keys: VecDeque::with_capacity(4),
+            }),
+        })
+    }
+
+    fn check_rotate(&self) {
+        // skip if some other thread is doing the rotation
+        let Ok(mut state) = self.state.try_lock() else {
+            return;
+        };
+
+        let now = Instant::now();
+        if state.next_rotate_at <= now {
+            let old_key = self.inner.encrypt_key();
+            match OpensslTicketKey::new_random(old_key.lifetime()) {
+                Ok(key) => {
+                    let key = Arc::new(key);
+                    self.inner.set_encrypt_key(key.clone());
+                    self.inner.add_decrypt_key(key);
+                    state
+                        .retired_keys
+                        .push_back((old_key.name(), now + self.grace));
+                    TICKET_KEY_ROTATIONS.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(e) => warn!("failed to create new ticket key: {e}"),
+            }
+            state.next_rotate_at = now + self.interval;
+        }
+
+        while let Some((name, expire_at)) = state.retired_keys.front() {
+            if *expire_at > now {
+                break;
+            }
+            self.inner.del_decrypt_key(*name);
+            state.retired_keys.pop_front();
+        }
+    }
+}
+
+#[cfg(feature = "openssl")]
+impl ProducesTickets for RustlsRotatingTicketer {
+    fn enabled(&self) -> bool {
+        true
+    }
+
+    fn lifetime(&self) -> u32 {
+        self.inner.lifetime()
+    }
+
+    fn encrypt(&self, plain: &[u8]) -> Option<Vec<u8>> {
+        self.check_rotate();
+        self.inner.encrypt(plain)
+    }
+
+    fn decrypt(&self, cipher: &[u8]) -> Option<Vec<u8>> {
+        self.check_rotate();
+        self.inner.decrypt(cipher)
+    }
+}
+
+#[cfg(feature = "openssl")]
+impl fmt::Debug for RustlsRotatingTicketer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RustlsRotatingTicketer")
+            .field("interval", &self.interval)
+            .field("grace", &self.grace)
+            .finish()
+    }
 }
 
 #[cfg(feature = "rustls-ring")]
@@ -94,3 +309,324 @@ fn set_default_session_ticketer(config: &mut ServerConfig) -> anyhow::Result<()>
     config.send_tls13_tickets = 0;
     Ok(())
 }
+
+#[cfg(all(test, feature = "openssl", feature = "rustls-ring"))]
+mod tests {
+    use super::*;
+    use crate::net::TICKET_KEY_NAME_LENGTH;
+    use openssl::asn1::Asn1Time;
+    use openssl::bn::BigNum;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::nid::Nid;
+    use openssl::pkey::PKey;
+    use openssl::x509::{X509NameBuilder, X509};
+    use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+    use rustls::server::danger::{ClientCertVerified, ClientCertVerifier};
+    use rustls::{ClientConfig, DigitallySignedStruct, DistinguishedName, SignatureScheme};
+    use rustls_pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer, ServerName, UnixTime};
+
+    #[derive(Debug)]
+    struct AcceptAnyCert;
+
+    impl ServerCertVerifier for AcceptAnyCert {
+        fn verify_server_cert(
+            &self,
+            _end_entity: &CertificateDer<'_>,
+            _intermediates: &[CertificateDer<'_>],
+            _server_name: &ServerName<'_>,
+            _ocsp_response: &[u8],
+            _now: UnixTime,
+        ) -> Result<ServerCertVerified, rustls::Error> {
+            Ok(ServerCertVerified::assertion())
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            _message: &[u8],
+            _cert: &CertificateDer<'_>,
+            _dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            Ok(HandshakeSignatureValid::assertion())
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            _message: &[u8],
+            _cert: &CertificateDer<'_>,
+            _dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            Ok(HandshakeSignatureValid::assertion())
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            vec![SignatureScheme::ECDSA_NISTP256_SHA256]
+        }
+    }
+
+    impl ClientCertVerifier for AcceptAnyCert {
+        fn client_auth_mandatory(&self) -> bool {
+            false
+        }
+
+        fn root_hint_subjects(&self) -> &[DistinguishedName] {
+            &[]
+        }
+
+        fn verify_client_cert(
+            &self,
+            _end_entity: &CertificateDer<'_>,
+            _intermediates: &[CertificateDer<'_>],
+            _now: UnixTime,
+        ) -> Result<ClientCertVerified, rustls::Error> {
+            Ok(ClientCertVerified::assertion())
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            _message: &[u8],
+            _cert: &CertificateDer<'_>,
+            _dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            Ok(HandshakeSignatureValid::assertion())
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            _message: &[u8],
+            _cert: &CertificateDer<'_>,
+            _dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            Ok(HandshakeSignatureValid::assertion())
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            vec![SignatureScheme::ECDSA_NISTP256_SHA256]
+        }
+    }
+
+    fn generate_cert(cn: &str) -> (CertificateDer<'static>, PrivateKeyDer<'static>) {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let pkey = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_nid(Nid::ORGANIZATIONNAME, "g3")
+            .unwrap();
+        name.append_entry_by_nid(Nid::COMMONNAME, cn).unwrap();
+        let name = name.build();
+
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        let serial = BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap();
+        builder.set_serial_number(&serial).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&pkey).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        builder.sign(&pkey, MessageDigest::sha256()).unwrap();
+        let cert = builder.build();
+
+        (
+            CertificateDer::from(cert.to_der().unwrap()),
+            PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(
+                pkey.private_key_to_pkcs8().unwrap(),
+            )),
+        )
+    }
+
+    fn handshake(
+        client_cert: Option<(CertificateDer<'static>, PrivateKeyDer<'static>)>,
+    ) -> ServerConnection {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+
+        let (server_cert, server_key) = generate_cert("server.example.net");
+        let server_config = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_client_cert_verifier(Arc::new(AcceptAnyCert))
+            .with_single_cert(vec![server_cert], server_key)
+            .unwrap();
+
+        let client_builder = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(AcceptAnyCert));
+        let client_config = match client_cert {
+            Some((cert, key)) => client_builder
+                .with_client_auth_cert(vec![cert], key)
+                .unwrap(),
+            None => client_builder.with_no_client_auth(),
+        };
+
+        let server_name = ServerName::try_from("server.example.net").unwrap();
+        let mut client = ClientConnection::new(Arc::new(client_config), server_name).unwrap();
+        let mut server = ServerConnection::new(Arc::new(server_config)).unwrap();
+
+        let mut buf = Vec::new();
+        for _ in 0..8 {
+            if !client.is_handshaking() && !server.is_handshaking() {
+                break;
+            }
+
+            buf.clear();
+            while client.wants_write() {
+                client.write_tls(&mut buf).unwrap();
+            }
+            let mut rd = buf.as_slice();
+            while !rd.is_empty() {
+                server.read_tls(&mut rd).unwrap();
+            }
+            server.process_new_packets().unwrap();
+
+            buf.clear();
+            while server.wants_write() {
+                server.write_tls(&mut buf).unwrap();
+            }
+            let mut rd = buf.as_slice();
+            while !rd.is_empty() {
+                client.read_tls(&mut rd).unwrap();
+            }
+            client.process_new_packets().unwrap();
+        }
+        assert!(!server.is_handshaking());
+        server
+    }
+
+    #[test]
+    fn with_client_cert() {
+        let (cert, key) = generate_cert("client.example.net");
+        let expected_fingerprint = cert_fingerprint(&cert);
+        assert_eq!(expected_fingerprint.len(), 64);
+
+        let server = handshake(Some((cert, key)));
+        assert_eq!(server.peer_cert_fingerprint(), Some(expected_fingerprint));
+        assert_eq!(
+            server.peer_cert_subject().as_deref(),
+            Some("O=g3, CN=client.example.net")
+        );
+    }
+
+    #[test]
+    fn rotating_ticketer() {
+        let ticketer =
+            RustlsRotatingTicketer::new(Duration::from_millis(10), Duration::from_secs(1)).unwrap();
+        assert_eq!(ticketer.lifetime(), 1);
+
+        let msg = b"session state";
+        let ticket = ticketer.encrypt(msg).unwrap();
+        let rotations = rustls_ticket_key_rotations();
+
+        std::thread::sleep(Duration::from_millis(20));
+        let new_ticket = ticketer.encrypt(msg).unwrap();
+        assert!(rustls_ticket_key_rotations() > rotations);
+        assert_ne!(
+            ticket[..TICKET_KEY_NAME_LENGTH],
+            new_ticket[..TICKET_KEY_NAME_LENGTH]
+        );
+        // the retired key is still usable within the grace time
+        assert_eq!(ticketer.decrypt(&ticket).unwrap(), msg);
+        assert_eq!(ticketer.decrypt(&new_ticket).unwrap(), msg);
+
+        assert!(RustlsRotatingTicketer::new(Duration::ZERO, Duration::from_secs(1)).is_err());
+        assert!(RustlsRotatingTicketer::new(Duration::from_secs(1), Duration::ZERO).is_err());
+    }
+
+    #[test]
+    fn disable_early_data() {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let (cert, key) = generate_cert("server.example.net");
+        let mut config = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert], key)
+            .unwrap();
+        config.max_early_data_size = u32::MAX;
+
+        config
+            .set_session_ticketer::<RustlsNoSessionTicketer>(true, None)
+            .unwrap();
+        let tickets = config.send_tls13_tickets;
+        config.disable_early_data();
+        assert_eq!(config.max_early_data_size, 0);
+        assert_eq!(config.send_tls13_tickets, tickets);
+        assert!(config.ticketer.enabled());
+    }
+
+    #[test]
+    fn alpn_protocols() {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let (cert, key) = generate_cert("server.example.net");
+        let mut server_config = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert], key)
+            .unwrap();
+        assert!(server_config.set_alpn_protocols(&[]).is_err());
+        server_config
+            .set_alpn_protocols(&[AlpnProtocol::Http2, AlpnProtocol::Http11])
+            .unwrap();
+        assert_eq!(
+            server_config.alpn_protocols,
+            vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+        );
+
+        let server_config = Arc::new(server_config);
+
+        let mut client_config = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(AcceptAnyCert))
+            .with_no_client_auth();
+
+        // clients that offer no ALPN are not affected
+        let server_name = ServerName::try_from("server.example.net").unwrap();
+        let mut client =
+            ClientConnection::new(Arc::new(client_config.clone()), server_name).unwrap();
+        let mut server = ServerConnection::new(server_config.clone()).unwrap();
+
+        let mut buf = Vec::new();
+        while client.wants_write() {
+            client.write_tls(&mut buf).unwrap();
+        }
+        let mut rd = buf.as_slice();
+        while !rd.is_empty() {
+            server.read_tls(&mut rd).unwrap();
+        }
+        server.process_new_packets().unwrap();
+        assert!(server.alpn_protocol().is_none());
+
+        client_config.alpn_protocols = vec![b"smtp".to_vec()];
+        let server_name = ServerName::try_from("server.example.net").unwrap();
+        let mut client = ClientConnection::new(Arc::new(client_config), server_name).unwrap();
+        let mut server = ServerConnection::new(server_config).unwrap();
+
+        let mut buf = Vec::new();
+        while client.wants_write() {
+            client.write_tls(&mut buf).unwrap();
+        }
+        let mut rd = buf.as_slice();
+        while !rd.is_empty() {
+            server.read_tls(&mut rd).unwrap();
+        }
+        assert_eq!(
+            server.process_new_packets().unwrap_err(),
+            rustls::Error::NoApplicationProtocol
+        );
+    }
+
+    #[test]
+    fn without_client_cert() {
+        let server = handshake(None);
+        assert!(server.peer_cert_fingerprint().is_none());
+        assert!(server.peer_cert_subject().is_none());
+    }
+}
@@ -34,12 +34,14 @@ mod cert_pair;
 pub use cert_pair::{RustlsCertificatePair, RustlsCertificatePairBuilder};
 
 mod cert_resolver;
-pub use cert_resolver::MultipleCertResolver;
+pub use cert_resolver::{MultipleCertResolver, RustlsSniVersionPolicy};
 
 mod ca_certs;
 pub use ca_certs::load_native_certs_for_rustls;
 
 mod ext;
+#[cfg(feature = "openssl")]
+pub use ext::{rustls_ticket_key_rotations, RustlsRotatingTicketer};
 pub use ext::{
     RustlsClientConnectionExt, RustlsConnectionExt, RustlsServerConfigExt,
     RustlsServerConnectionExt,
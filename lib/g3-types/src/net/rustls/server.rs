@@ -14,6 +14,7 @@
  * limitations under the License.
  */
 
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -21,11 +22,14 @@ use anyhow::{anyhow, Context};
 #[cfg(feature = "quinn")]
 use quinn::crypto::rustls::QuicServerConfig;
 use rustls::server::{ProducesTickets, WebPkiClientVerifier};
-use rustls::{RootCertStore, ServerConfig};
+use rustls::{RootCertStore, ServerConfig, SupportedProtocolVersion};
 use rustls_pki_types::CertificateDer;
 
+#[cfg(feature = "openssl")]
+use super::RustlsRotatingTicketer;
 use super::{
     MultipleCertResolver, RustlsCertificatePair, RustlsNoSessionTicketer, RustlsServerConfigExt,
+    RustlsSniVersionPolicy,
 };
 use crate::net::tls::AlpnProtocol;
 #[cfg(feature = "openssl")]
@@ -35,6 +39,22 @@ use crate::net::{OpensslTicketKey, RollingTicketer};
 pub struct RustlsServerConfig {
     pub driver: Arc<ServerConfig>,
     pub accept_timeout: Duration,
+    sni_drivers: BTreeMap<String, Arc<ServerConfig>>,
+}
+
+impl RustlsServerConfig {
+    /// Whether there are drivers that should be selected by the client hello server name
+    #[inline]
+    pub fn has_sni_drivers(&self) -> bool {
+        !self.sni_drivers.is_empty()
+    }
+
+    /// Select the driver for the server name in the client hello message
+    pub fn select_driver(&self, server_name: Option<&str>) -> &Arc<ServerConfig> {
+        server_name
+            .and_then(|name| self.sni_drivers.get(&name.to_ascii_lowercase()))
+            .unwrap_or(&self.driver)
+    }
 }
 
 #[cfg(feature = "quinn")]
@@ -52,6 +72,9 @@ pub struct RustlsServerConfigBuilder {
     use_session_ticket: bool,
     no_session_cache: bool,
     accept_timeout: Duration,
+    sni_version_policies: BTreeMap<String, RustlsSniVersionPolicy>,
+    #[cfg(feature = "openssl")]
+    ticket_key_rotation: Option<(Duration, Duration)>,
 }
 
 impl RustlsServerConfigBuilder {
@@ -63,9 +86,20 @@ impl RustlsServerConfigBuilder {
             use_session_ticket: true,
             no_session_cache: false,
             accept_timeout: Duration::from_secs(10),
+            sni_version_policies: BTreeMap::new(),
+            #[cfg(feature = "openssl")]
+            ticket_key_rotation: None,
         }
     }
 
+    /// Create a builder with a certificate pair loaded from in-memory PEM data.
+    pub fn with_pem_cert_pair(cert_pem: &[u8], key_pem: &[u8]) -> anyhow::Result<Self> {
+        let pair = RustlsCertificatePair::from_pem(cert_pem, key_pem)?;
+        let mut builder = Self::empty();
+        builder.push_cert_pair(pair);
+        Ok(builder)
+    }
+
     pub fn check(&self) -> anyhow::Result<()> {
         if self.cert_pairs.is_empty() {
             return Err(anyhow!("no cert pair is set"));
@@ -98,6 +132,39 @@ impl RustlsServerConfigBuilder {
         self.cert_pairs.push(cert_pair);
     }
 
+    #[inline]
+    pub fn cert_pairs(&self) -> &[RustlsCertificatePair] {
+        &self.cert_pairs
+    }
+
+    pub fn set_sni_version_policy(&mut self, server_name: &str, policy: RustlsSniVersionPolicy) {
+        self.sni_version_policies
+            .insert(server_name.to_ascii_lowercase(), policy);
+    }
+
+    /// Rotate the ticket key of the default session ticketer at `interval`,
+    /// and keep the retired keys for decryption within `grace`.
+    #[cfg(feature = "openssl")]
+    pub fn set_ticket_key_rotation(
+        &mut self,
+        interval: Duration,
+        grace: Duration,
+    ) -> anyhow::Result<()> {
+        self.ticket_key_rotation = Some((interval, grace));
+        Ok(())
+    }
+
+    #[cfg(not(feature = "openssl"))]
+    pub fn set_ticket_key_rotation(
+        &mut self,
+        _interval: Duration,
+        _grace: Duration,
+    ) -> anyhow::Result<()> {
+        Err(anyhow!(
+            "ticket key rotation is not supported as openssl is not enabled"
+        ))
+    }
+
     #[inline]
     pub fn set_accept_timeout(&mut self, timeout: Duration) {
         self.accept_timeout = timeout;
@@ -110,13 +177,14 @@ impl RustlsServerConfigBuilder {
 
     fn build_server_config<T>(
         &self,
+        versions: &[&'static SupportedProtocolVersion],
         alpn_protocols: Option<Vec<AlpnProtocol>>,
         ticketer: Option<Arc<T>>,
     ) -> anyhow::Result<ServerConfig>
     where
         T: ProducesTickets + 'static,
     {
-        let config_builder = ServerConfig::builder();
+        let config_builder = ServerConfig::builder_with_protocol_versions(versions);
         let config_builder = if self.client_auth {
             let mut root_store = RootCertStore::empty();
             if let Some(certs) = &self.client_auth_certs {
@@ -161,14 +229,20 @@ impl RustlsServerConfigBuilder {
         };
 
         config.set_session_cache(self.no_session_cache);
+        #[cfg(feature = "openssl")]
+        match self.ticket_key_rotation {
+            Some((interval, grace)) if self.use_session_ticket && ticketer.is_none() => {
+                let ticketer = RustlsRotatingTicketer::new(interval, grace)
+                    .context("failed to create rotating session ticketer")?;
+                config.set_session_ticketer(true, Some(Arc::new(ticketer)))?;
+            }
+            _ => config.set_session_ticketer(self.use_session_ticket, ticketer)?,
+        }
+        #[cfg(not(feature = "openssl"))]
         config.set_session_ticketer(self.use_session_ticket, ticketer)?;
 
         if let Some(protocols) = alpn_protocols {
-            for proto in protocols {
-                config
-                    .alpn_protocols
-                    .push(proto.to_identification_sequence());
-            }
+            config.set_alpn_protocols(&protocols)?;
         }
 
         Ok(config)
@@ -182,10 +256,30 @@ impl RustlsServerConfigBuilder {
     where
         T: ProducesTickets + 'static,
     {
-        let config = self.build_server_config(alpn_protocols, ticketer)?;
+        let config = self.build_server_config(
+            rustls::DEFAULT_VERSIONS,
+            alpn_protocols.clone(),
+            ticketer.clone(),
+        )?;
+
+        let mut sni_drivers = BTreeMap::new();
+        for (name, policy) in &self.sni_version_policies {
+            let config = self
+                .build_server_config(
+                    &policy.protocol_versions(),
+                    alpn_protocols.clone(),
+                    ticketer.clone(),
+                )
+                .context(format!(
+                    "failed to build server config for server name {name}"
+                ))?;
+            sni_drivers.insert(name.to_string(), Arc::new(config));
+        }
+
         Ok(RustlsServerConfig {
             driver: Arc::new(config),
             accept_timeout: self.accept_timeout,
+            sni_drivers,
         })
     }
 
@@ -210,7 +304,8 @@ impl RustlsServerConfigBuilder {
     where
         T: ProducesTickets + 'static,
     {
-        let config = self.build_server_config(alpn_protocols, ticketer)?;
+        let config =
+            self.build_server_config(rustls::DEFAULT_VERSIONS, alpn_protocols, ticketer)?;
         let quic_config = QuicServerConfig::try_from(config)
             .map_err(|e| anyhow!("invalid quic tls config: {e}"))?;
         Ok(RustlsQuicServerConfig {
@@ -232,3 +327,167 @@ impl RustlsServerConfigBuilder {
         self.build_quic_with_alpn_protocols::<RustlsNoSessionTicketer>(None, None)
     }
 }
+
+#[cfg(all(test, feature = "openssl", feature = "rustls-ring"))]
+mod tests {
+    use super::*;
+    use crate::net::TlsVersion;
+    use openssl::asn1::Asn1Time;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::nid::Nid;
+    use openssl::pkey::PKey;
+    use openssl::x509::{X509NameBuilder, X509};
+    use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+    use rustls::{
+        AlertDescription, ClientConfig, ClientConnection, DigitallySignedStruct, ServerConnection,
+        SignatureScheme,
+    };
+    use rustls_pki_types::{ServerName, UnixTime};
+
+    #[derive(Debug)]
+    struct AcceptAnyCert;
+
+    impl ServerCertVerifier for AcceptAnyCert {
+        fn verify_server_cert(
+            &self,
+            _end_entity: &CertificateDer<'_>,
+            _intermediates: &[CertificateDer<'_>],
+            _server_name: &ServerName<'_>,
+            _ocsp_response: &[u8],
+            _now: UnixTime,
+        ) -> Result<ServerCertVerified, rustls::Error> {
+            Ok(ServerCertVerified::assertion())
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            _message: &[u8],
+            _cert: &CertificateDer<'_>,
+            _dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            Ok(HandshakeSignatureValid::assertion())
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            _message: &[u8],
+            _cert: &CertificateDer<'_>,
+            _dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            Ok(HandshakeSignatureValid::assertion())
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            vec![SignatureScheme::ECDSA_NISTP256_SHA256]
+        }
+    }
+
+    fn build_server_config() -> RustlsServerConfig {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_nid(Nid::COMMONNAME, "example.net")
+            .unwrap();
+        let name = name.build();
+
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        let cert_pem = builder.build().to_pem().unwrap();
+        let key_pem = key.private_key_to_pem_pkcs8().unwrap();
+
+        let mut builder =
+            RustlsServerConfigBuilder::with_pem_cert_pair(&cert_pem, &key_pem).unwrap();
+        let mut policy = RustlsSniVersionPolicy::default();
+        policy.set_min_version(TlsVersion::TLS1_3);
+        builder.set_sni_version_policy("TLS13.example.net", policy);
+        builder.build().unwrap()
+    }
+
+    fn handshake(
+        server_config: Arc<ServerConfig>,
+        server_name: &'static str,
+    ) -> Result<(), rustls::Error> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let client_config = ClientConfig::builder_with_provider(provider)
+            .with_protocol_versions(&[&rustls::version::TLS12])
+            .unwrap()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(AcceptAnyCert))
+            .with_no_client_auth();
+
+        let server_name = ServerName::try_from(server_name).unwrap();
+        let mut client = ClientConnection::new(Arc::new(client_config), server_name).unwrap();
+        let mut server = ServerConnection::new(server_config).unwrap();
+
+        let mut buf = Vec::new();
+        for _ in 0..8 {
+            if !client.is_handshaking() && !server.is_handshaking() {
+                return Ok(());
+            }
+
+            buf.clear();
+            while client.wants_write() {
+                client.write_tls(&mut buf).unwrap();
+            }
+            let mut rd = buf.as_slice();
+            while !rd.is_empty() {
+                server.read_tls(&mut rd).unwrap();
+            }
+            let server_r = server.process_new_packets();
+
+            buf.clear();
+            while server.wants_write() {
+                server.write_tls(&mut buf).unwrap();
+            }
+            let mut rd = buf.as_slice();
+            while !rd.is_empty() {
+                client.read_tls(&mut rd).unwrap();
+            }
+            client.process_new_packets()?;
+            server_r?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn select_sni_driver() {
+        let config = build_server_config();
+        assert!(config.has_sni_drivers());
+
+        let driver = config.select_driver(Some("tls13.example.net"));
+        assert!(!Arc::ptr_eq(driver, &config.driver));
+        let driver = config.select_driver(Some("TLS13.Example.Net"));
+        assert!(!Arc::ptr_eq(driver, &config.driver));
+        let driver = config.select_driver(Some("www.example.net"));
+        assert!(Arc::ptr_eq(driver, &config.driver));
+        let driver = config.select_driver(None);
+        assert!(Arc::ptr_eq(driver, &config.driver));
+    }
+
+    #[test]
+    fn sni_version_policy() {
+        let config = build_server_config();
+
+        let driver = config.select_driver(Some("www.example.net"));
+        handshake(driver.clone(), "www.example.net").unwrap();
+
+        let driver = config.select_driver(Some("tls13.example.net"));
+        let e = handshake(driver.clone(), "tls13.example.net").unwrap_err();
+        assert_eq!(
+            e,
+            rustls::Error::AlertReceived(AlertDescription::ProtocolVersion)
+        );
+    }
+}
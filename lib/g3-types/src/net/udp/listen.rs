@@ -27,6 +27,7 @@ pub struct UdpListenConfig {
     ipv6only: bool,
     buf_conf: SocketBufferConfig,
     misc_opts: UdpMiscSockOpts,
+    gro: Option<bool>,
     instance: usize,
     scale: usize,
 }
@@ -44,6 +45,7 @@ impl UdpListenConfig {
             ipv6only: false,
             buf_conf: SocketBufferConfig::default(),
             misc_opts: UdpMiscSockOpts::default(),
+            gro: None,
             instance: 1,
             scale: 0,
         }
@@ -72,6 +74,12 @@ impl UdpListenConfig {
         self.misc_opts
     }
 
+    /// Whether to enable UDP GRO on the socket, the OS / library default will be used if not set
+    #[inline]
+    pub fn udp_gro(&self) -> Option<bool> {
+        self.gro
+    }
+
     #[inline]
     pub fn is_ipv6only(&self) -> bool {
         self.ipv6only
@@ -97,6 +105,11 @@ impl UdpListenConfig {
         self.misc_opts = misc_opts;
     }
 
+    #[inline]
+    pub fn set_udp_gro(&mut self, enable: bool) {
+        self.gro = Some(enable);
+    }
+
     #[inline]
     pub fn set_port(&mut self, port: u16) {
         self.address.set_port(port);
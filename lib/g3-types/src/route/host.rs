@@ -18,7 +18,7 @@ use std::hash::Hash;
 use std::net::IpAddr;
 use std::sync::Arc;
 
-use ahash::AHashMap;
+use ahash::{AHashMap, AHashSet};
 use radix_trie::{Trie, TrieCommon};
 
 use crate::collection::NamedValue;
@@ -109,6 +109,33 @@ impl<T> HostMatch<T> {
 }
 
 impl<T> HostMatch<Arc<T>> {
+    /// Call `f` for each value, the shared values will only be visited once.
+    pub fn foreach_value<F>(&self, mut f: F)
+    where
+        F: FnMut(&Arc<T>),
+    {
+        let mut visited = AHashSet::new();
+        let mut visit = |v: &Arc<T>| {
+            let v_index = Arc::as_ptr(v) as usize;
+            if visited.insert(v_index) {
+                f(v);
+            }
+        };
+
+        if let Some(ht) = &self.exact_domain {
+            ht.values().for_each(&mut visit);
+        }
+        if let Some(ht) = &self.exact_ip {
+            ht.values().for_each(&mut visit);
+        }
+        if let Some(trie) = &self.child_domain {
+            trie.values().for_each(&mut visit);
+        }
+        if let Some(default) = &self.default {
+            visit(default);
+        }
+    }
+
     pub fn try_build_arc<R, E, F>(&self, try_build: F) -> Result<HostMatch<Arc<R>>, E>
     where
         F: Fn(&Arc<T>) -> Result<R, E>,
@@ -93,3 +93,51 @@ pub fn as_ip_location(value: &Yaml) -> anyhow::Result<IpLocation> {
         Err(anyhow!("yaml value type for 'ip location' should be 'map'"))
     }
 }
+
+pub fn as_ip_location_list(value: &Yaml) -> anyhow::Result<Vec<IpLocation>> {
+    if let Yaml::Array(seq) = value {
+        let mut locations = Vec::with_capacity(seq.len());
+        for (i, v) in seq.iter().enumerate() {
+            let location =
+                as_ip_location(v).context(format!("invalid ip location value for #{i}"))?;
+            locations.push(location);
+        }
+        Ok(locations)
+    } else {
+        Err(anyhow!(
+            "yaml value type for 'ip location list' should be 'seq'"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn ip_location_list() {
+        let s = r#"
+            - network: 192.168.0.0/16
+              country: CN
+            - net: 10.0.0.0/8
+              asn: 64512
+        "#;
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        let locations = as_ip_location_list(&docs[0]).unwrap();
+        assert_eq!(locations.len(), 2);
+        assert_eq!(locations[0].country(), Some(IsoCountryCode::CN));
+        assert_eq!(locations[1].network_asn(), Some(64512));
+
+        let s = r#"
+            - network: 192.168.0.0/16
+            - country: CN
+        "#;
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        assert!(as_ip_location_list(&docs[0]).is_err());
+
+        let s = "network: 192.168.0.0/16";
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        assert!(as_ip_location_list(&docs[0]).is_err());
+    }
+}
@@ -81,7 +81,7 @@ pub use self::openssl::{
 #[cfg(feature = "quinn")]
 mod quinn;
 #[cfg(feature = "quinn")]
-pub use quinn::as_quinn_transport_config;
+pub use quinn::{as_quinn_retry_policy, as_quinn_transport_config};
 
 #[cfg(all(unix, not(target_os = "openbsd"), feature = "sched"))]
 mod sched;
@@ -101,4 +101,4 @@ pub use dpi::*;
 #[cfg(feature = "geoip")]
 mod geoip;
 #[cfg(feature = "geoip")]
-pub use geoip::{as_continent_code, as_ip_location, as_iso_country_code};
+pub use geoip::{as_continent_code, as_ip_location, as_ip_location_list, as_iso_country_code};
@@ -261,7 +261,18 @@ pub fn as_tcp_misc_sock_opts(v: &Yaml) -> anyhow::Result<TcpMiscSockOpts> {
                 config.type_of_service = Some(tos);
                 Ok(())
             }
-            "netfilter_mark" | "mark" => {
+            "dscp" => {
+                let dscp =
+                    crate::value::as_u8(v).context(format!("invalid u8 value for key {k}"))?;
+                if dscp > 63 {
+                    return Err(anyhow!(
+                        "invalid dscp value {dscp}, should be in range 0-63"
+                    ));
+                }
+                config.type_of_service = Some(dscp << 2);
+                Ok(())
+            }
+            "netfilter_mark" | "mark" | "so_mark" => {
                 let mark =
                     crate::value::as_u32(v).context(format!("invalid u32 value for key {k}"))?;
                 config.netfilter_mark = Some(mark);
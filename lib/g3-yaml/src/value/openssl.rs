@@ -26,6 +26,7 @@ use yaml_rust::Yaml;
 use g3_types::net::{
     OpensslCertificatePair, OpensslClientConfigBuilder, OpensslInterceptionClientConfigBuilder,
     OpensslInterceptionServerConfigBuilder, OpensslProtocol, OpensslServerConfigBuilder,
+    SPKI_PIN_LENGTH,
 };
 
 #[cfg(feature = "tongsuo")]
@@ -353,6 +354,27 @@ fn set_openssl_tls_client_config_builder(
                 builder.set_use_ocsp_stapling(enable);
                 Ok(())
             }
+            "require_ocsp_must_staple" | "ocsp_must_staple" => {
+                let enable = crate::value::as_bool(v)?;
+                builder.set_require_ocsp_must_staple(enable);
+                Ok(())
+            }
+            "spki_pins" | "spki_pin" | "pinned_spki" => {
+                let mut pins = Vec::new();
+                if let Yaml::Array(seq) = v {
+                    for (i, v) in seq.iter().enumerate() {
+                        let pin = as_spki_pin(v)
+                            .context(format!("invalid spki pin value for {k}#{i}"))?;
+                        pins.push(pin);
+                    }
+                } else {
+                    let pin =
+                        as_spki_pin(v).context(format!("invalid spki pin value for key {k}"))?;
+                    pins.push(pin);
+                }
+                builder.set_spki_pins(pins);
+                Ok(())
+            }
             "enable_sct" => {
                 let enable = crate::value::as_bool(v)?;
                 builder.set_enable_sct(enable);
@@ -389,6 +411,19 @@ fn set_openssl_tls_client_config_builder(
     }
 }
 
+fn as_spki_pin(value: &Yaml) -> anyhow::Result<[u8; SPKI_PIN_LENGTH]> {
+    let s = crate::value::as_string(value)?;
+    let s = s.strip_prefix("sha256//").unwrap_or(&s);
+    let data =
+        openssl::base64::decode_block(s).map_err(|e| anyhow!("invalid base64 string: {e}"))?;
+    <[u8; SPKI_PIN_LENGTH]>::try_from(data.as_slice()).map_err(|_| {
+        anyhow!(
+            "invalid SHA-256 hash length {}, should be {SPKI_PIN_LENGTH}",
+            data.len()
+        )
+    })
+}
+
 pub fn as_to_one_openssl_tls_client_config_builder(
     value: &Yaml,
     lookup_dir: Option<&Path>,
@@ -624,3 +659,58 @@ pub fn as_tls_interception_server_config_builder(
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    fn parse_pin(s: &str) -> anyhow::Result<[u8; SPKI_PIN_LENGTH]> {
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        as_spki_pin(&docs[0])
+    }
+
+    #[test]
+    fn spki_pin() {
+        let mut expected = [0u8; SPKI_PIN_LENGTH];
+        for (i, v) in expected.iter_mut().enumerate() {
+            *v = i as u8;
+        }
+
+        let pin = parse_pin("AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=").unwrap();
+        assert_eq!(pin, expected);
+        let pin = parse_pin("sha256//AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=").unwrap();
+        assert_eq!(pin, expected);
+
+        // not base64 encoded
+        assert!(parse_pin("sha256//not-a-pin!").is_err());
+        // 16 bytes, too short
+        assert!(parse_pin("AAECAwQFBgcICQoLDA0ODw==").is_err());
+        // 33 bytes, too long
+        assert!(parse_pin("AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8g").is_err());
+        assert!(parse_pin("[]").is_err());
+    }
+
+    #[test]
+    fn spki_pins() {
+        let docs = YamlLoader::load_from_str(
+            r#"
+            spki_pins:
+              - AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=
+              - sha256//AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=
+            "#,
+        )
+        .unwrap();
+        assert!(as_to_one_openssl_tls_client_config_builder(&docs[0], None).is_ok());
+
+        let docs = YamlLoader::load_from_str(
+            r#"
+            spki_pins:
+              - AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=
+              - AAECAwQFBgcICQoLDA0ODw==
+            "#,
+        )
+        .unwrap();
+        assert!(as_to_one_openssl_tls_client_config_builder(&docs[0], None).is_err());
+    }
+}
@@ -17,7 +17,7 @@
 use anyhow::{anyhow, Context};
 use yaml_rust::Yaml;
 
-use g3_types::net::QuinnTransportConfigBuilder;
+use g3_types::net::{QuinnRetryPolicy, QuinnTransportConfigBuilder};
 
 pub fn as_quinn_transport_config(value: &Yaml) -> anyhow::Result<QuinnTransportConfigBuilder> {
     let Yaml::Hash(map) = value else {
@@ -63,3 +63,58 @@ pub fn as_quinn_transport_config(value: &Yaml) -> anyhow::Result<QuinnTransportC
     })?;
     Ok(config)
 }
+
+pub fn as_quinn_retry_policy(value: &Yaml) -> anyhow::Result<QuinnRetryPolicy> {
+    match value {
+        Yaml::Boolean(true) => Ok(QuinnRetryPolicy::Always),
+        Yaml::Boolean(false) => Ok(QuinnRetryPolicy::Never),
+        Yaml::String(s) => match crate::key::normalize(s).as_str() {
+            "always" => Ok(QuinnRetryPolicy::Always),
+            "never" => Ok(QuinnRetryPolicy::Never),
+            _ => Err(anyhow!("invalid quinn retry policy string value {s}")),
+        },
+        Yaml::Integer(_) => {
+            let rate = crate::value::as_nonzero_u32(value)
+                .context("invalid new connection rate threshold value")?;
+            Ok(QuinnRetryPolicy::UnderLoad(rate))
+        }
+        Yaml::Hash(map) => {
+            let v = crate::hash::get_required(map, "under_load")?;
+            let rate = crate::value::as_nonzero_u32(v)
+                .context("invalid nonzero u32 value for key under_load")?;
+            Ok(QuinnRetryPolicy::UnderLoad(rate))
+        }
+        _ => Err(anyhow!("invalid yaml value type for quinn retry policy")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::num::NonZeroU32;
+    use yaml_rust::YamlLoader;
+
+    fn parse(s: &str) -> anyhow::Result<QuinnRetryPolicy> {
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        as_quinn_retry_policy(&docs[0])
+    }
+
+    #[test]
+    fn retry_policy() {
+        assert_eq!(parse("true").unwrap(), QuinnRetryPolicy::Always);
+        assert_eq!(parse("false").unwrap(), QuinnRetryPolicy::Never);
+        assert_eq!(parse("always").unwrap(), QuinnRetryPolicy::Always);
+        assert_eq!(parse("Never").unwrap(), QuinnRetryPolicy::Never);
+
+        let under_load = QuinnRetryPolicy::UnderLoad(NonZeroU32::new(1000).unwrap());
+        assert_eq!(parse("1000").unwrap(), under_load);
+        assert_eq!(parse("under_load: 1000").unwrap(), under_load);
+
+        assert!(parse("sometimes").is_err());
+        assert!(parse("0").is_err());
+        assert!(parse("-1").is_err());
+        assert!(parse("under_load: 0").is_err());
+        assert!(parse("{}").is_err());
+        assert!(parse("[1000]").is_err());
+    }
+}
@@ -15,6 +15,7 @@
  */
 
 use std::path::Path;
+use std::time::Duration;
 
 use anyhow::{anyhow, Context};
 use rustls_pki_types::pem::PemObject;
@@ -23,7 +24,7 @@ use yaml_rust::Yaml;
 
 use g3_types::net::{
     RustlsCertificatePair, RustlsCertificatePairBuilder, RustlsClientConfigBuilder,
-    RustlsServerConfigBuilder,
+    RustlsServerConfigBuilder, RustlsSniVersionPolicy,
 };
 
 pub fn as_rustls_server_name(value: &Yaml) -> anyhow::Result<ServerName<'static>> {
@@ -226,6 +227,67 @@ pub fn as_rustls_client_config_builder(
     }
 }
 
+fn as_rustls_sni_version_policy(value: &Yaml) -> anyhow::Result<RustlsSniVersionPolicy> {
+    if let Yaml::Hash(map) = value {
+        let mut policy = RustlsSniVersionPolicy::default();
+
+        crate::foreach_kv(map, |k, v| match crate::key::normalize(k).as_str() {
+            "min_version" | "min_tls_version" => {
+                let version = crate::value::as_tls_version(v)
+                    .context(format!("invalid tls version value for key {k}"))?;
+                policy.set_min_version(version);
+                Ok(())
+            }
+            "max_version" | "max_tls_version" => {
+                let version = crate::value::as_tls_version(v)
+                    .context(format!("invalid tls version value for key {k}"))?;
+                policy.set_max_version(version);
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+
+        policy.check()?;
+        Ok(policy)
+    } else {
+        Err(anyhow!(
+            "yaml value type for 'rustls sni version policy' should be 'map'"
+        ))
+    }
+}
+
+fn as_rustls_ticket_key_rotation(value: &Yaml) -> anyhow::Result<(Duration, Duration)> {
+    if let Yaml::Hash(map) = value {
+        let mut interval = None;
+        let mut grace = None;
+
+        crate::foreach_kv(map, |k, v| match crate::key::normalize(k).as_str() {
+            "interval" | "rotate_interval" => {
+                let t = crate::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                interval = Some(t);
+                Ok(())
+            }
+            "grace" | "grace_time" => {
+                let t = crate::humanize::as_duration(v)
+                    .context(format!("invalid humanize duration value for key {k}"))?;
+                grace = Some(t);
+                Ok(())
+            }
+            _ => Err(anyhow!("invalid key {k}")),
+        })?;
+
+        let Some(interval) = interval else {
+            return Err(anyhow!("no rotate interval set"));
+        };
+        Ok((interval, grace.unwrap_or(interval)))
+    } else {
+        let interval = crate::humanize::as_duration(value)
+            .context("invalid humanize duration value for the rotate interval")?;
+        Ok((interval, interval))
+    }
+}
+
 pub fn as_rustls_server_config_builder(
     value: &Yaml,
     lookup_dir: Option<&Path>,
@@ -295,6 +357,24 @@ pub fn as_rustls_server_config_builder(
                 builder.set_accept_timeout(timeout);
                 Ok(())
             }
+            "sni_version_policy" | "sni_version_policies" => {
+                if let Yaml::Hash(map) = v {
+                    crate::foreach_kv(map, |name, v| {
+                        let policy = as_rustls_sni_version_policy(v).context(format!(
+                            "invalid rustls sni version policy value for {k}.{name}"
+                        ))?;
+                        builder.set_sni_version_policy(name, policy);
+                        Ok(())
+                    })
+                } else {
+                    Err(anyhow!("yaml value type for key {k} should be 'map'"))
+                }
+            }
+            "ticket_key_rotation" | "session_ticket_key_rotation" => {
+                let (interval, grace) = as_rustls_ticket_key_rotation(v)
+                    .context(format!("invalid ticket key rotation value for key {k}"))?;
+                builder.set_ticket_key_rotation(interval, grace)
+            }
             _ => Err(anyhow!("invalid key {k}")),
         })?;
 